@@ -27,8 +27,10 @@ use std::mem;
 use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::slice;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::OnceLock;
 use std::time::SystemTime;
 
 use bstr::ByteVec as _;
@@ -81,6 +83,8 @@ use jj_lib::op_store::RefTarget;
 use jj_lib::op_walk;
 use jj_lib::op_walk::OpsetEvaluationError;
 use jj_lib::operation::Operation;
+use jj_lib::opset::OpsetCommitResolver;
+use jj_lib::opset::OpsetParseContext;
 use jj_lib::ref_name::RefName;
 use jj_lib::ref_name::RefNameBuf;
 use jj_lib::ref_name::RemoteName;
@@ -92,6 +96,7 @@ use jj_lib::repo::MutableRepo;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo;
 use jj_lib::repo::RepoLoader;
+use jj_lib::repo::RepoLoaderError;
 use jj_lib::repo::StoreFactories;
 use jj_lib::repo::StoreLoadError;
 use jj_lib::repo::merge_factories_map;
@@ -110,6 +115,7 @@ use jj_lib::revset::RevsetFunction;
 use jj_lib::revset::RevsetIteratorExt as _;
 use jj_lib::revset::RevsetModifier;
 use jj_lib::revset::RevsetParseContext;
+use jj_lib::revset::RevsetTrustPolicy;
 use jj_lib::revset::RevsetWorkspaceContext;
 use jj_lib::revset::SymbolResolverExtension;
 use jj_lib::revset::UserRevsetExpression;
@@ -120,9 +126,11 @@ use jj_lib::store::Store;
 use jj_lib::str_util::StringExpression;
 use jj_lib::str_util::StringMatcher;
 use jj_lib::str_util::StringPattern;
+use jj_lib::time_util::DatePatternContext;
 use jj_lib::transaction::Transaction;
 use jj_lib::working_copy;
 use jj_lib::working_copy::CheckoutStats;
+use jj_lib::working_copy::NewFileSizeLimit;
 use jj_lib::working_copy::SnapshotOptions;
 use jj_lib::working_copy::SnapshotStats;
 use jj_lib::working_copy::UntrackedReason;
@@ -182,6 +190,7 @@ use crate::template_parser::TemplateDiagnostics;
 use crate::templater::TemplateRenderer;
 use crate::templater::WrapTemplateProperty;
 use crate::text_util;
+use crate::textconv::TextConverters;
 use crate::ui::ColorChoice;
 use crate::ui::Ui;
 
@@ -425,7 +434,9 @@ impl CommandHelper {
     #[instrument(skip(self, ui))]
     pub fn workspace_helper(&self, ui: &Ui) -> Result<WorkspaceCommandHelper, CommandError> {
         let (workspace_command, stats) = self.workspace_helper_with_stats(ui)?;
-        print_snapshot_stats(ui, &stats, workspace_command.env().path_converter())?;
+        if workspace_command.should_print_auto_snapshot_stats()? {
+            print_snapshot_stats(ui, &stats, workspace_command.env().path_converter())?;
+        }
         Ok(workspace_command)
     }
 
@@ -787,8 +798,10 @@ pub struct WorkspaceCommandEnvironment {
     default_ignored_remote: Option<&'static RemoteName>,
     path_converter: RepoPathUiConverter,
     workspace_name: WorkspaceNameBuf,
+    repo_path: PathBuf,
     immutable_heads_expression: Arc<UserRevsetExpression>,
     short_prefixes_expression: Option<Arc<UserRevsetExpression>>,
+    trusted_signature_expression: OnceLock<Arc<UserRevsetExpression>>,
     conflict_marker_style: ConflictMarkerStyle,
 }
 
@@ -811,8 +824,10 @@ impl WorkspaceCommandEnvironment {
             default_ignored_remote,
             path_converter,
             workspace_name: workspace.workspace_name().to_owned(),
+            repo_path: workspace.repo_path().to_owned(),
             immutable_heads_expression: RevsetExpression::root(),
             short_prefixes_expression: None,
+            trusted_signature_expression: OnceLock::new(),
             conflict_marker_style: settings.get("ui.conflict-marker-style")?,
         };
         env.immutable_heads_expression = env.load_immutable_heads_expression(ui)?;
@@ -833,24 +848,41 @@ impl WorkspaceCommandEnvironment {
             path_converter: &self.path_converter,
             workspace_name: &self.workspace_name,
         };
-        let now = if let Some(timestamp) = self.settings.commit_timestamp() {
-            chrono::Local
-                .timestamp_millis_opt(timestamp.timestamp.0)
-                .unwrap()
-        } else {
-            chrono::Local::now()
-        };
         RevsetParseContext {
             aliases_map: &self.revset_aliases_map,
             local_variables: HashMap::new(),
             user_email: self.settings.user_email(),
-            date_pattern_context: now.into(),
+            date_pattern_context: self.date_pattern_context(),
             default_ignored_remote: self.default_ignored_remote,
             extensions: self.command.revset_extensions(),
             workspace: Some(workspace_context),
+            trust_policy: Some(self),
         }
     }
 
+    /// Creates opset parse context. Revsets passed to `touching()` are
+    /// resolved by the given `commit_resolver`.
+    pub fn opset_parse_context<'a>(
+        &self,
+        commit_resolver: Option<&'a dyn OpsetCommitResolver>,
+    ) -> OpsetParseContext<'a> {
+        OpsetParseContext {
+            date_pattern_context: self.date_pattern_context(),
+            commit_resolver,
+        }
+    }
+
+    fn date_pattern_context(&self) -> DatePatternContext {
+        let now = if let Some(timestamp) = self.settings.commit_timestamp() {
+            chrono::Local
+                .timestamp_millis_opt(timestamp.timestamp.0)
+                .unwrap()
+        } else {
+            chrono::Local::now()
+        };
+        now.into()
+    }
+
     /// Creates fresh new context which manages cache of short commit/change ID
     /// prefixes. New context should be created per repo view (or operation.)
     pub fn new_id_prefix_context(&self) -> IdPrefixContext {
@@ -991,15 +1023,111 @@ impl WorkspaceCommandEnvironment {
             id_prefix_context,
             self.immutable_expression(),
             self.conflict_marker_style,
+            self.text_converters(),
             &self.command.data.commit_template_extensions,
         )
     }
 
+    /// Loads converters of file contents to text from the settings. Converted
+    /// contents are cached in the repo.
+    pub fn text_converters(&self) -> Result<TextConverters, ConfigGetError> {
+        let text_converters = TextConverters::from_settings(&self.settings)?;
+        Ok(text_converters.with_cache_dir(self.repo_path.join("textconv")))
+    }
+
     pub fn operation_template_extensions(&self) -> &[Arc<dyn OperationTemplateLanguageExtension>] {
         &self.command.data.operation_template_extensions
     }
 }
 
+impl RevsetTrustPolicy for WorkspaceCommandEnvironment {
+    fn trusted_signature(
+        &self,
+    ) -> Result<Arc<UserRevsetExpression>, Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(expression) = self.trusted_signature_expression.get() {
+            return Ok(expression.clone());
+        }
+        // The rules can't refer to the trusted_signature() being defined.
+        let context = RevsetParseContext {
+            trust_policy: None,
+            ..self.revset_parse_context()
+        };
+        let expression = revset_util::parse_trusted_signature_expression(&self.settings, &context)?;
+        Ok(self
+            .trusted_signature_expression
+            .get_or_init(|| expression)
+            .clone())
+    }
+}
+
+/// Resolves revsets passed to opset functions such as `touching()`.
+pub struct RevsetCommitResolver<'a> {
+    env: &'a WorkspaceCommandEnvironment,
+    repo: OnceCell<Arc<ReadonlyRepo>>,
+    lazy_load: Option<(&'a RepoLoader, &'a Operation)>,
+}
+
+impl<'a> RevsetCommitResolver<'a> {
+    /// Creates resolver that evaluates revsets in the given `repo`.
+    pub fn new(env: &'a WorkspaceCommandEnvironment, repo: Arc<ReadonlyRepo>) -> Self {
+        Self {
+            env,
+            repo: OnceCell::from(repo),
+            lazy_load: None,
+        }
+    }
+
+    /// Creates resolver that loads the repo at `op` when a revset is first
+    /// evaluated.
+    pub fn load_at(
+        env: &'a WorkspaceCommandEnvironment,
+        repo_loader: &'a RepoLoader,
+        op: &'a Operation,
+    ) -> Self {
+        Self {
+            env,
+            repo: OnceCell::new(),
+            lazy_load: Some((repo_loader, op)),
+        }
+    }
+
+    fn repo(&self) -> Result<&Arc<ReadonlyRepo>, RepoLoaderError> {
+        if let Some(repo) = self.repo.get() {
+            return Ok(repo);
+        }
+        let (repo_loader, op) = self.lazy_load.expect("repo or loader should be set");
+        let repo = repo_loader.load_at(op)?;
+        Ok(self.repo.get_or_init(|| repo))
+    }
+}
+
+impl OpsetCommitResolver for RevsetCommitResolver<'_> {
+    fn resolve_commits(
+        &self,
+        text: &str,
+    ) -> Result<Vec<CommitId>, Box<dyn std::error::Error + Send + Sync>> {
+        let repo = self.repo()?;
+        let mut diagnostics = RevsetDiagnostics::new();
+        let expression = revset::parse(&mut diagnostics, text, &self.env.revset_parse_context())?;
+        let id_prefix_context = self.env.new_id_prefix_context();
+        let evaluator = RevsetExpressionEvaluator::new(
+            repo.as_ref(),
+            self.env.command.revset_extensions().clone(),
+            &id_prefix_context,
+            expression,
+        );
+        let commit_ids = evaluator.evaluate_to_commit_ids()?.try_collect()?;
+        Ok(commit_ids)
+    }
+
+    fn repo(&self) -> &dyn Repo {
+        self.repo
+            .get()
+            .expect("repo should have been loaded")
+            .as_ref()
+    }
+}
+
 /// Provides utilities for writing a command that works on a [`Workspace`]
 /// (which most commands do).
 pub struct WorkspaceCommandHelper {
@@ -1123,7 +1251,9 @@ impl WorkspaceCommandHelper {
         let stats = self
             .maybe_snapshot_impl(ui)
             .map_err(|err| err.into_command_error())?;
-        print_snapshot_stats(ui, &stats, self.env().path_converter())?;
+        if self.should_print_auto_snapshot_stats()? {
+            print_snapshot_stats(ui, &stats, self.env().path_converter())?;
+        }
         Ok(())
     }
 
@@ -1377,20 +1507,66 @@ to the current parents may contain changes from multiple commits.
         start_tracking_matcher: &'a dyn Matcher,
     ) -> Result<SnapshotOptions<'a>, CommandError> {
         let base_ignores = self.base_ignores()?;
-        let HumanByteSize(mut max_new_file_size) = self
+        let HumanByteSize(max_new_file_size) = self
             .settings()
             .get_value_with("snapshot.max-new-file-size", TryInto::try_into)?;
-        if max_new_file_size == 0 {
-            max_new_file_size = u64::MAX;
-        }
+        let max_new_file_size_overrides = self.new_file_size_limits()?;
         Ok(SnapshotOptions {
             base_ignores,
             progress: None,
             start_tracking_matcher,
-            max_new_file_size,
+            max_new_file_size: unlimited_if_zero(max_new_file_size),
+            max_new_file_size_overrides,
         })
     }
 
+    /// Parses the `snapshot.file-size-limits` table.
+    fn new_file_size_limits(&self) -> Result<Vec<NewFileSizeLimit>, CommandError> {
+        let settings = self.settings();
+        settings
+            .table_keys("snapshot.file-size-limits")
+            // Sort keys so the first matching limit is deterministic.
+            .sorted()
+            .map(|name| {
+                let patterns: Vec<String> =
+                    settings.get(["snapshot", "file-size-limits", name, "patterns"])?;
+                let HumanByteSize(max_size) = settings.get_value_with(
+                    ["snapshot", "file-size-limits", name, "max-new-file-size"],
+                    TryInto::try_into,
+                )?;
+                let mut diagnostics = FilesetDiagnostics::new();
+                let expressions: Vec<_> = patterns
+                    .iter()
+                    .map(|pattern| {
+                        fileset::parse(
+                            &mut diagnostics,
+                            pattern,
+                            &RepoPathUiConverter::Fs {
+                                cwd: "".into(),
+                                base: "".into(),
+                            },
+                        )
+                    })
+                    .try_collect()?;
+                // Diagnostics are dropped since the limits are evaluated on
+                // every snapshot, and the patterns are usually fixed.
+                Ok(NewFileSizeLimit {
+                    matcher: FilesetExpression::union_all(expressions)
+                        .to_matcher()
+                        .into(),
+                    max_size: unlimited_if_zero(max_size),
+                })
+            })
+            .try_collect()
+    }
+
+    /// Returns true if the snapshot stats should be printed after the working
+    /// copy is snapshotted implicitly.
+    pub fn should_print_auto_snapshot_stats(&self) -> Result<bool, CommandError> {
+        let policy: LargeNewFilePolicy = self.settings().get("snapshot.large-new-file-policy")?;
+        Ok(policy == LargeNewFilePolicy::Warn)
+    }
+
     pub(crate) fn path_converter(&self) -> &RepoPathUiConverter {
         self.env.path_converter()
     }
@@ -1459,7 +1635,10 @@ to the current parents may contain changes from multiple commits.
         args: &DiffFormatArgs,
     ) -> Result<DiffRenderer<'_>, CommandError> {
         let formats = diff_util::diff_formats_for(self.settings(), args)?;
-        Ok(self.diff_renderer(formats))
+        let text_converters = self.text_converters()?;
+        Ok(self
+            .diff_renderer(formats)
+            .with_text_converters(text_converters))
     }
 
     /// Loads textual diff renderer from the settings and log-like command
@@ -1471,7 +1650,20 @@ to the current parents may contain changes from multiple commits.
         patch: bool,
     ) -> Result<Option<DiffRenderer<'_>>, CommandError> {
         let formats = diff_util::diff_formats_for_log(self.settings(), args, patch)?;
-        Ok((!formats.is_empty()).then(|| self.diff_renderer(formats)))
+        if formats.is_empty() {
+            return Ok(None);
+        }
+        let text_converters = self.text_converters()?;
+        Ok(Some(
+            self.diff_renderer(formats)
+                .with_text_converters(text_converters),
+        ))
+    }
+
+    /// Loads converters of file contents to text from the settings. Converted
+    /// contents are cached in the repo.
+    pub fn text_converters(&self) -> Result<TextConverters, ConfigGetError> {
+        self.env.text_converters()
     }
 
     /// Loads diff editor from the settings.
@@ -1549,7 +1741,27 @@ to the current parents may contain changes from multiple commits.
     }
 
     pub fn resolve_single_op(&self, op_str: &str) -> Result<Operation, OpsetEvaluationError> {
-        op_walk::resolve_op_with_repo(self.repo(), op_str)
+        let commit_resolver = RevsetCommitResolver::new(&self.env, self.repo().clone());
+        let context = self.env.opset_parse_context(Some(&commit_resolver));
+        op_walk::resolve_op_at_with_context(
+            self.repo().op_store(),
+            slice::from_ref(self.repo().operation()),
+            op_str,
+            &context,
+        )
+    }
+
+    /// Resolves opset expression to operations reachable from the current
+    /// operation. The order of the returned operations is unspecified.
+    pub fn resolve_ops(&self, op_str: &str) -> Result<Vec<Operation>, OpsetEvaluationError> {
+        let commit_resolver = RevsetCommitResolver::new(&self.env, self.repo().clone());
+        let context = self.env.opset_parse_context(Some(&commit_resolver));
+        op_walk::resolve_ops_at(
+            self.repo().op_store(),
+            slice::from_ref(self.repo().operation()),
+            op_str,
+            &context,
+        )
     }
 
     /// Resolve a revset to a single revision. Return an error if the revset is
@@ -2721,6 +2933,20 @@ pub fn print_conflicted_paths(
     Ok(())
 }
 
+/// What to do when new files are larger than `snapshot.max-new-file-size`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum LargeNewFilePolicy {
+    /// Leave the files untracked and warn about them on every snapshot.
+    Warn,
+    /// Leave the files untracked. They are only reported by `jj status`.
+    Ignore,
+}
+
+fn unlimited_if_zero(max_size: u64) -> u64 {
+    if max_size == 0 { u64::MAX } else { max_size }
+}
+
 /// Build human-readable messages explaining why the file was not tracked
 fn build_untracked_reason_message(reason: &UntrackedReason) -> Option<String> {
     match reason {
@@ -2771,14 +2997,19 @@ pub fn print_snapshot_stats(
 ) -> io::Result<()> {
     print_untracked_files(ui, &stats.untracked_paths, path_converter)?;
 
-    let large_files_sizes = stats
+    let (large_files, sizes): (Vec<_>, Vec<_>) = stats
         .untracked_paths
-        .values()
-        .filter_map(|reason| match reason {
-            UntrackedReason::FileTooLarge { size, .. } => Some(size),
+        .iter()
+        .filter_map(|(path, reason)| match reason {
+            UntrackedReason::FileTooLarge { size, .. } => Some((path, *size)),
             UntrackedReason::FileNotAutoTracked => None,
-        });
-    if let Some(size) = large_files_sizes.max() {
+        })
+        .unzip();
+    if let Some(size) = sizes.iter().max() {
+        let large_files_list = large_files
+            .iter()
+            .map(|path| path_converter.format_file_path(path))
+            .join(" ");
         writedoc!(
             ui.hint_default(),
             r"
@@ -2788,6 +3019,8 @@ pub fn print_snapshot_stats(
                 This will increase the maximum file size allowed for new files, in this repository only.
               - Run `jj --config snapshot.max-new-file-size={size} st`
                 This will increase the maximum file size allowed for new files, for this command only.
+              - Run `jj file track --force-large {large_files_list}`
+                This will track the files regardless of their size.
             "
         )?;
     }
@@ -39,8 +39,12 @@ use jj_lib::index::IndexError;
 use jj_lib::op_heads_store::OpHeadResolutionError;
 use jj_lib::op_heads_store::OpHeadsStoreError;
 use jj_lib::op_store::OpStoreError;
+use jj_lib::op_walk::OpRewriteError;
 use jj_lib::op_walk::OpsetEvaluationError;
 use jj_lib::op_walk::OpsetResolutionError;
+use jj_lib::opset::OpsetParseError;
+use jj_lib::opset::OpsetParseErrorKind;
+use jj_lib::patch::PatchApplyError;
 use jj_lib::repo::CheckOutCommitError;
 use jj_lib::repo::EditCommitError;
 use jj_lib::repo::RepoLoaderError;
@@ -52,6 +56,7 @@ use jj_lib::revset::RevsetEvaluationError;
 use jj_lib::revset::RevsetParseError;
 use jj_lib::revset::RevsetParseErrorKind;
 use jj_lib::revset::RevsetResolutionError;
+use jj_lib::signing::SignError;
 use jj_lib::str_util::StringPatternParseError;
 use jj_lib::trailer::TrailerParseError;
 use jj_lib::transaction::TransactionCommitError;
@@ -80,6 +85,7 @@ use crate::revset_util::TagNameParseError;
 use crate::revset_util::UserRevsetEvaluationError;
 use crate::template_parser::TemplateParseError;
 use crate::template_parser::TemplateParseErrorKind;
+use crate::textconv::TextConvError;
 use crate::ui::Ui;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -324,6 +330,10 @@ impl From<BackendError> for CommandError {
     fn from(err: BackendError) -> Self {
         match &err {
             BackendError::Unsupported(_) => user_error(err),
+            // Text converters are configured by the user.
+            BackendError::ReadFile { source, .. } if source.is::<TextConvError>() => {
+                user_error_with_message("Failed to convert file content to text", err)
+            }
             _ => internal_error_with_message("Unexpected error from backend", err),
         }
     }
@@ -391,6 +401,9 @@ impl From<OpsetEvaluationError> for CommandError {
             OpsetEvaluationError::OpHeadResolution(err) => err.into(),
             OpsetEvaluationError::OpHeadsStore(err) => err.into(),
             OpsetEvaluationError::OpStore(err) => err.into(),
+            OpsetEvaluationError::OpsetParse(err) => err.into(),
+            OpsetEvaluationError::Index(err) => err.into(),
+            OpsetEvaluationError::RevsetEvaluation(err) => err.into(),
         }
     }
 }
@@ -407,6 +420,15 @@ impl From<OpStoreError> for CommandError {
     }
 }
 
+impl From<OpRewriteError> for CommandError {
+    fn from(err: OpRewriteError) -> Self {
+        match err {
+            OpRewriteError::OpStore(err) => err.into(),
+            OpRewriteError::Sign(_) => user_error(err),
+        }
+    }
+}
+
 impl From<RepoLoaderError> for CommandError {
     fn from(err: RepoLoaderError) -> Self {
         internal_error_with_message("Failed to load the repo", err)
@@ -421,7 +443,18 @@ impl From<ResetError> for CommandError {
 
 impl From<TransactionCommitError> for CommandError {
     fn from(err: TransactionCommitError) -> Self {
-        internal_error(err)
+        match err {
+            TransactionCommitError::Sign(err) => {
+                user_error_with_message("Failed to sign operation", err)
+            }
+            _ => internal_error(err),
+        }
+    }
+}
+
+impl From<SignError> for CommandError {
+    fn from(err: SignError) -> Self {
+        user_error(err)
     }
 }
 
@@ -528,10 +561,12 @@ impl From<TrailerParseError> for CommandError {
 
 #[cfg(feature = "git")]
 mod git {
+    use jj_lib::git::GitBundleError;
     use jj_lib::git::GitDefaultRefspecError;
     use jj_lib::git::GitExportError;
     use jj_lib::git::GitFetchError;
     use jj_lib::git::GitImportError;
+    use jj_lib::git::GitOperationSyncError;
     use jj_lib::git::GitPushError;
     use jj_lib::git::GitRefExpansionError;
     use jj_lib::git::GitRemoteManagementError;
@@ -622,6 +657,55 @@ jj currently does not support partial clones. To use jj with this repository, tr
         }
     }
 
+    impl From<GitBundleError> for CommandError {
+        fn from(err: GitBundleError) -> Self {
+            match err {
+                GitBundleError::Subprocess(_)
+                | GitBundleError::UnexpectedBackend(_)
+                | GitBundleError::Io(_)
+                | GitBundleError::InvalidMetadata(..) => user_error(err),
+                GitBundleError::RemoteName(_) => user_error_with_hint(
+                    err,
+                    "Run `jj bundle import --remote` to give a different name.",
+                ),
+                GitBundleError::Backend(err) => err.into(),
+                GitBundleError::Import(err) => err.into(),
+                GitBundleError::Git(_) => internal_error(err),
+            }
+        }
+    }
+
+    impl From<GitOperationSyncError> for CommandError {
+        fn from(err: GitOperationSyncError) -> Self {
+            match err {
+                GitOperationSyncError::NoSuchRemote(_)
+                | GitOperationSyncError::Subprocess(_)
+                | GitOperationSyncError::UnexpectedBackend(_)
+                | GitOperationSyncError::UnrelatedHistory
+                | GitOperationSyncError::InvalidObject(..) => user_error(err),
+                GitOperationSyncError::RemoteName(_) => user_error_with_hint(
+                    err,
+                    "Run `jj git remote rename` to give a different name.",
+                ),
+                GitOperationSyncError::Backend(err) => err.into(),
+                GitOperationSyncError::OpStore(err) => err.into(),
+                GitOperationSyncError::Index(err) => err.into(),
+                GitOperationSyncError::Push(err) => err.into(),
+                GitOperationSyncError::RemoteAhead => user_error_with_hint(
+                    err,
+                    "Run `jj op pull` to integrate the remote operations first.",
+                ),
+                GitOperationSyncError::Rejected(ref reason) => {
+                    let hint = reason.as_ref().map(|reason| format!("Reason: {reason}"));
+                    let mut cmd_err = user_error(err);
+                    cmd_err.extend_hints(hint);
+                    cmd_err
+                }
+                GitOperationSyncError::Git(_) => internal_error(err),
+            }
+        }
+    }
+
     impl From<GitRemoteManagementError> for CommandError {
         fn from(err: GitRemoteManagementError) -> Self {
             user_error(err)
@@ -657,6 +741,16 @@ impl From<FilesetParseError> for CommandError {
     }
 }
 
+impl From<OpsetParseError> for CommandError {
+    fn from(err: OpsetParseError) -> Self {
+        let hint = opset_parse_error_hint(&err);
+        let mut cmd_err =
+            user_error_with_message(format!("Failed to parse opset: {}", err.kind()), err);
+        cmd_err.extend_hints(hint);
+        cmd_err
+    }
+}
+
 impl From<RecoverWorkspaceError> for CommandError {
     fn from(err: RecoverWorkspaceError) -> Self {
         match err {
@@ -763,6 +857,17 @@ impl From<FixError> for CommandError {
     }
 }
 
+impl From<PatchApplyError> for CommandError {
+    fn from(err: PatchApplyError) -> Self {
+        match err {
+            PatchApplyError::Backend(err) => err.into(),
+            PatchApplyError::BinaryPatch(_) | PatchApplyError::UnsupportedFile { .. } => {
+                user_error(err)
+            }
+        }
+    }
+}
+
 impl From<BisectionError> for CommandError {
     fn from(err: BisectionError) -> Self {
         match err {
@@ -855,6 +960,21 @@ fn fileset_parse_error_hint(err: &FilesetParseError) -> Option<String> {
     }
 }
 
+fn opset_parse_error_hint(err: &OpsetParseError) -> Option<String> {
+    match err.kind() {
+        OpsetParseErrorKind::SyntaxError => Some(String::from(
+            "See https://jj-vcs.github.io/jj/latest/operation-log/ for opsets syntax.",
+        )),
+        OpsetParseErrorKind::NoSuchFunction {
+            name: _,
+            candidates,
+        } => format_similarity_hint(candidates),
+        OpsetParseErrorKind::InvalidArguments { .. } | OpsetParseErrorKind::Expression(_) => {
+            find_source_parse_error_hint(&err)
+        }
+    }
+}
+
 fn opset_resolution_error_hint(err: &OpsetResolutionError) -> Option<String> {
     match err {
         OpsetResolutionError::MultipleOperations {
@@ -64,6 +64,17 @@ pub(crate) struct AbandonArgs {
     /// Do not modify the content of the children of the abandoned commits
     #[arg(long)]
     restore_descendants: bool,
+    /// Abandon revisions whose changes already landed in `trunk()`
+    ///
+    /// The specified revisions are compared with the `trunk()` history by
+    /// their changes, so revisions that were cherry-picked or squashed
+    /// upstream are abandoned. See the `landed()` revset function.
+    ///
+    /// If no revisions are specified, this defaults to the
+    /// `revsets.abandon-landed` setting, which includes mutable revisions
+    /// based on the last 1000 `trunk()` commits.
+    #[arg(long)]
+    landed: bool,
 }
 
 #[instrument(skip_all)]
@@ -77,8 +88,21 @@ pub(crate) fn cmd_abandon(
         let target_expr = if !args.revisions_pos.is_empty() || !args.revisions_opt.is_empty() {
             workspace_command
                 .parse_union_revsets(ui, &[&*args.revisions_pos, &*args.revisions_opt].concat())?
+        } else if args.landed {
+            let revset_string = workspace_command
+                .settings()
+                .get_string("revsets.abandon-landed")?;
+            workspace_command.parse_revset(ui, &RevisionArg::from(revset_string))?
         } else {
             workspace_command.parse_revset(ui, &RevisionArg::AT)?
+        };
+        let target_expr = if args.landed {
+            let upstream_expr =
+                workspace_command.parse_revset(ui, &RevisionArg::from("trunk()".to_owned()))?;
+            let expression = target_expr.expression().landed(upstream_expr.expression());
+            workspace_command.attach_revset_evaluator(expression)
+        } else {
+            target_expr
         }
         .resolve()?;
         let visible_expr = target_expr.intersection(&RevsetExpression::visible_heads().ancestors());
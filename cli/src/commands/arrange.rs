@@ -0,0 +1,461 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::slice;
+
+use clap_complete::ArgValueCompleter;
+use indoc::indoc;
+use itertools::Itertools as _;
+use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
+use jj_lib::commit::CommitIteratorExt as _;
+use jj_lib::repo::Repo as _;
+use jj_lib::rewrite;
+use jj_lib::rewrite::CommitRewriter;
+use jj_lib::rewrite::CommitWithSelection;
+use pollster::FutureExt as _;
+use tracing::instrument;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
+use crate::cli_util::short_commit_hash;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_hint;
+use crate::complete;
+use crate::description_util::combine_messages_for_editing;
+use crate::description_util::description_template;
+use crate::description_util::edit_description;
+use crate::description_util::try_combine_messages;
+use crate::ui::Ui;
+
+/// Reorder, squash, drop, and reword revisions by editing a todo list
+///
+/// Starts an editor with one line per revision, parents first. Each line
+/// starts with an action, followed by the commit ID of the revision. The rest
+/// of the line is ignored. The lines can be reordered, and the action of each
+/// line can be changed to one of the following:
+///
+/// * `pick`: Keep the revision.
+///
+/// * `reword`: Keep the revision, and edit its description.
+///
+/// * `squash`: Squash the revision into the revision on the previous line, and
+///   edit the combined description.
+///
+/// * `fixup`: Squash the revision into the revision on the previous line, and
+///   discard its description.
+///
+/// * `drop`: Abandon the revision, and discard its changes.
+///
+/// * `edit-parents`: Keep the revision, but rebase it onto the comma-separated
+///   list of parents given after the commit ID.
+///
+/// Each revision is rebased onto the revision on the previous line, or onto
+/// the parents of the first revision if it's the first line. Revisions which
+/// aren't a child of the previous revision are initially listed with
+/// `edit-parents`, so the unedited list doesn't change anything. Descendants
+/// of the arranged revisions are rebased onto the rewritten revisions.
+///
+/// All changes are made in a single operation, which can be undone by `jj
+/// undo`.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct ArrangeArgs {
+    /// The revisions to arrange
+    ///
+    /// If no revisions are specified, this defaults to the `revsets.arrange`
+    /// setting, or `reachable(@, mutable())` if it is not set.
+    #[arg(
+        value_name = "REVSETS",
+        add = ArgValueCompleter::new(complete::revset_expression_mutable),
+    )]
+    revisions_pos: Vec<RevisionArg>,
+    #[arg(
+        short = 'r',
+        hide = true,
+        value_name = "REVSETS",
+        add = ArgValueCompleter::new(complete::revset_expression_mutable),
+    )]
+    revisions_opt: Vec<RevisionArg>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TodoAction {
+    Pick,
+    Reword,
+    Squash,
+    Fixup,
+    Drop,
+    EditParents,
+}
+
+impl TodoAction {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "pick" => Some(Self::Pick),
+            "reword" => Some(Self::Reword),
+            "squash" => Some(Self::Squash),
+            "fixup" => Some(Self::Fixup),
+            "drop" => Some(Self::Drop),
+            "edit-parents" => Some(Self::EditParents),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TodoEntry {
+    action: TodoAction,
+    commit: Commit,
+    /// Parents given to `edit-parents`.
+    parent_ids: Vec<CommitId>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_arrange(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &ArrangeArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let target_expr = if args.revisions_pos.is_empty() && args.revisions_opt.is_empty() {
+        let revs = workspace_command.settings().get_string("revsets.arrange")?;
+        workspace_command.parse_revset(ui, &RevisionArg::from(revs))?
+    } else {
+        workspace_command
+            .parse_union_revsets(ui, &[&*args.revisions_pos, &*args.revisions_opt].concat())?
+    };
+    // Parents first
+    let commits: Vec<Commit> = target_expr
+        .evaluate_to_commits()?
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .rev()
+        .collect();
+    if commits.is_empty() {
+        writeln!(ui.status(), "No revisions to arrange.")?;
+        return Ok(());
+    }
+    workspace_command.check_rewritable(commits.iter().ids())?;
+
+    let text_editor = workspace_command.text_editor()?;
+    let todo_list = text_editor
+        .edit_str(format_todo_list(&commits), Some(".jjarrange"))
+        .map_err(|err| err.with_name("todo list"))?;
+    let entries = parse_todo_list(ui, &workspace_command, &todo_list, &commits)?;
+    if entries.is_empty() {
+        writeln!(ui.status(), "Nothing changed.")?;
+        return Ok(());
+    }
+    validate_todo_list(&workspace_command, &entries)?;
+
+    let base_parent_ids = commits[0].parent_ids();
+    let mut tx = workspace_command.start_transaction();
+    // Maps the arranged revisions to their rewritten commits
+    let mut new_ids: HashMap<CommitId, CommitId> = HashMap::new();
+    let mut previous: Option<Commit> = None;
+    let mut num_rewritten = 0;
+    let mut num_squashed = 0;
+    let mut num_abandoned = 0;
+    for entry in &entries {
+        let old_commit = &entry.commit;
+        match entry.action {
+            TodoAction::Pick | TodoAction::Reword | TodoAction::EditParents => {
+                let new_parent_ids = if entry.action == TodoAction::EditParents {
+                    entry
+                        .parent_ids
+                        .iter()
+                        .map(|id| new_ids.get(id).unwrap_or(id).clone())
+                        .collect_vec()
+                } else if let Some(previous) = &previous {
+                    vec![previous.id().clone()]
+                } else {
+                    base_parent_ids.to_vec()
+                };
+                let new_commit = if entry.action != TodoAction::Reword
+                    && new_parent_ids == old_commit.parent_ids()
+                {
+                    old_commit.clone()
+                } else {
+                    let mut commit_builder =
+                        CommitRewriter::new(tx.repo_mut(), old_commit.clone(), new_parent_ids)
+                            .rebase()
+                            .block_on()?
+                            .detach();
+                    if entry.action == TodoAction::Reword {
+                        let temp_commit = commit_builder.write_hidden()?;
+                        let template = description_template(ui, &tx, "", &temp_commit)?;
+                        let description = edit_description(&text_editor, &template)?;
+                        commit_builder.set_description(description);
+                    }
+                    num_rewritten += 1;
+                    commit_builder.write(tx.repo_mut())?
+                };
+                new_ids.insert(old_commit.id().clone(), new_commit.id().clone());
+                previous = Some(new_commit);
+            }
+            TodoAction::Squash | TodoAction::Fixup => {
+                let destination = previous.take().expect("validated by validate_todo_list()");
+                let source = CommitWithSelection {
+                    commit: old_commit.clone(),
+                    selected_tree: old_commit.tree()?,
+                    parent_tree: old_commit.parent_tree(tx.repo())?,
+                };
+                let squashed =
+                    rewrite::squash_commits(tx.repo_mut(), &[source], &destination, false)?
+                        .expect("fully selected source should be squashed");
+                let mut commit_builder = squashed.commit_builder.detach();
+                let description = if entry.action == TodoAction::Fixup {
+                    destination.description().to_owned()
+                } else if let Some(description) =
+                    try_combine_messages(slice::from_ref(old_commit), &destination)
+                {
+                    description
+                } else {
+                    let combined = combine_messages_for_editing(
+                        ui,
+                        &tx,
+                        slice::from_ref(old_commit),
+                        Some(&destination),
+                        &commit_builder,
+                    )?;
+                    commit_builder.set_description(combined);
+                    let temp_commit = commit_builder.write_hidden()?;
+                    let intro = "Enter a description for the combined commit.";
+                    let template = description_template(ui, &tx, intro, &temp_commit)?;
+                    edit_description(&text_editor, &template)?
+                };
+                commit_builder.set_description(description);
+                let new_commit = commit_builder.write(tx.repo_mut())?;
+                // Descendants of the squashed revision are rebased onto the
+                // combined revision.
+                tx.repo_mut()
+                    .set_rewritten_commit(old_commit.id().clone(), new_commit.id().clone());
+                for id in new_ids.values_mut() {
+                    if id == destination.id() {
+                        *id = new_commit.id().clone();
+                    }
+                }
+                new_ids.insert(old_commit.id().clone(), new_commit.id().clone());
+                num_squashed += 1;
+                previous = Some(new_commit);
+            }
+            TodoAction::Drop => {
+                tx.repo_mut().record_abandoned_commit(old_commit);
+                num_abandoned += 1;
+            }
+        }
+    }
+    let num_rebased = tx.repo_mut().rebase_descendants()?;
+    if !tx.repo().has_changes() {
+        writeln!(ui.status(), "Nothing changed.")?;
+        return Ok(());
+    }
+
+    if let Some(mut formatter) = ui.status_formatter() {
+        if num_rewritten > 0 {
+            writeln!(formatter, "Rewrote {num_rewritten} commits")?;
+        }
+        if num_squashed > 0 {
+            writeln!(formatter, "Squashed {num_squashed} commits")?;
+        }
+        if num_abandoned > 0 {
+            writeln!(formatter, "Abandoned {num_abandoned} commits")?;
+        }
+        if num_rebased > 0 {
+            writeln!(formatter, "Rebased {num_rebased} descendant commits")?;
+        }
+    }
+    tx.finish(ui, format!("arrange {} commits", commits.len()))?;
+    Ok(())
+}
+
+fn format_todo_list(commits: &[Commit]) -> String {
+    let mut todo_list = String::new();
+    for (i, commit) in commits.iter().enumerate() {
+        let previous_id = i.checked_sub(1).map(|j| commits[j].id());
+        let subject = commit.description().lines().next().unwrap_or_default();
+        if previous_id.is_none_or(|id| commit.parent_ids() == [id.clone()]) {
+            write!(todo_list, "pick {}", short_commit_hash(commit.id())).unwrap();
+        } else {
+            let parents = commit.parent_ids().iter().map(short_commit_hash).join(",");
+            write!(
+                todo_list,
+                "edit-parents {} {parents}",
+                short_commit_hash(commit.id())
+            )
+            .unwrap();
+        }
+        if subject.is_empty() {
+            todo_list.push('\n');
+        } else {
+            writeln!(todo_list, " {subject}").unwrap();
+        }
+    }
+    todo_list.push_str(indoc! {r#"
+
+        JJ: Reorder the lines above, and change the action at the start of each line:
+        JJ:   pick <commit>                     Keep the revision
+        JJ:   reword <commit>                   Keep the revision, and edit its description
+        JJ:   squash <commit>                   Squash into the previous revision
+        JJ:   fixup <commit>                    Squash into the previous revision, and discard
+        JJ:                                     the description
+        JJ:   drop <commit>                     Abandon the revision, and discard its changes
+        JJ:   edit-parents <commit> <parents>   Rebase onto the comma-separated parents
+        JJ:
+        JJ: Each revision is rebased onto the revision on the previous line.
+        JJ: Remove all lines to abort.
+        JJ: Lines starting with "JJ:" (like this one) will be removed.
+    "#});
+    todo_list
+}
+
+fn parse_todo_list(
+    ui: &Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    todo_list: &str,
+    commits: &[Commit],
+) -> Result<Vec<TodoEntry>, CommandError> {
+    let commit_ids: HashSet<&CommitId> = commits.iter().ids().collect();
+    let mut seen_ids = HashSet::new();
+    let mut entries = vec![];
+    for line in todo_list.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("JJ:") {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap();
+        let action = TodoAction::parse(name).ok_or_else(|| {
+            user_error_with_hint(
+                format!("Unknown action `{name}` in todo list line: {line}"),
+                "Valid actions are pick, reword, squash, fixup, drop, and edit-parents.",
+            )
+        })?;
+        let rev = words
+            .next()
+            .ok_or_else(|| user_error(format!("Missing revision in todo list line: {line}")))?;
+        let commit =
+            workspace_command.resolve_single_rev(ui, &RevisionArg::from(rev.to_owned()))?;
+        if !commit_ids.contains(commit.id()) {
+            return Err(user_error(format!(
+                "Revision {rev} is not one of the revisions being arranged"
+            )));
+        }
+        if !seen_ids.insert(commit.id().clone()) {
+            return Err(user_error(format!(
+                "Revision {rev} appears more than once in the todo list"
+            )));
+        }
+        let parent_ids = if action == TodoAction::EditParents {
+            let parents = words
+                .next()
+                .ok_or_else(|| user_error(format!("Missing parents in todo list line: {line}")))?;
+            parents
+                .split(',')
+                .map(|parent| {
+                    let parent = RevisionArg::from(parent.to_owned());
+                    Ok::<_, CommandError>(
+                        workspace_command
+                            .resolve_single_rev(ui, &parent)?
+                            .id()
+                            .clone(),
+                    )
+                })
+                .try_collect()?
+        } else {
+            vec![]
+        };
+        entries.push(TodoEntry {
+            action,
+            commit,
+            parent_ids,
+        });
+    }
+    if entries.is_empty() {
+        return Ok(entries);
+    }
+    if let Some(commit) = commits
+        .iter()
+        .find(|commit| !seen_ids.contains(commit.id()))
+    {
+        return Err(user_error_with_hint(
+            format!(
+                "Revision {} is missing from the todo list",
+                short_commit_hash(commit.id())
+            ),
+            "Use `drop` to abandon a revision.",
+        ));
+    }
+    Ok(entries)
+}
+
+fn validate_todo_list(
+    workspace_command: &WorkspaceCommandHelper,
+    entries: &[TodoEntry],
+) -> Result<(), CommandError> {
+    let repo = workspace_command.repo();
+    let index = repo.index();
+    let mut listed_ids = HashSet::new();
+    let mut dropped_ids = HashSet::new();
+    let mut has_previous = false;
+    for entry in entries {
+        let commit_hash = short_commit_hash(entry.commit.id());
+        match entry.action {
+            TodoAction::Squash | TodoAction::Fixup if !has_previous => {
+                return Err(user_error(format!(
+                    "Cannot squash revision {commit_hash} because there is no previous revision"
+                )));
+            }
+            TodoAction::Drop => {
+                dropped_ids.insert(entry.commit.id());
+            }
+            _ => has_previous = true,
+        }
+        for parent_id in &entry.parent_ids {
+            let parent_hash = short_commit_hash(parent_id);
+            if parent_id == entry.commit.id() {
+                return Err(user_error(format!(
+                    "Revision {commit_hash} cannot be rebased onto itself"
+                )));
+            } else if dropped_ids.contains(parent_id) {
+                return Err(user_error(format!(
+                    "Revision {commit_hash} cannot be rebased onto the dropped revision \
+                     {parent_hash}"
+                )));
+            } else if listed_ids.contains(parent_id) {
+                continue;
+            } else if entries.iter().any(|other| other.commit.id() == parent_id) {
+                return Err(user_error(format!(
+                    "Revision {commit_hash} must be listed after its parent {parent_hash}"
+                )));
+            }
+            for other in entries {
+                if index.is_ancestor(other.commit.id(), parent_id)? {
+                    return Err(user_error(format!(
+                        "Revision {commit_hash} cannot be rebased onto {parent_hash}, which is a \
+                         descendant of the arranged revision {}",
+                        short_commit_hash(other.commit.id())
+                    )));
+                }
+            }
+        }
+        listed_ids.insert(entry.commit.id());
+    }
+    Ok(())
+}
@@ -0,0 +1,86 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use clap_complete::ArgValueCompleter;
+use itertools::Itertools as _;
+use jj_lib::commit::Commit;
+use jj_lib::git;
+use jj_lib::repo::Repo as _;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Write revisions and their bookmarks to a bundle file
+///
+/// The bundle can be imported with `jj bundle import`, or fetched from with
+/// `git fetch`. Local bookmarks pointing to the revisions are included.
+///
+/// Parents of the revisions that aren't included have to exist in the repo
+/// the bundle is imported to.
+#[derive(clap::Args, Clone, Debug)]
+pub struct BundleCreateArgs {
+    /// The revisions to include in the bundle
+    #[arg(
+        long,
+        short,
+        required = true,
+        value_name = "REVSETS",
+        add = ArgValueCompleter::new(complete::revset_expression_all),
+    )]
+    revisions: Vec<RevisionArg>,
+    /// The bundle file to write
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    path: PathBuf,
+}
+
+pub fn cmd_bundle_create(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BundleCreateArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let repo = workspace_command.repo();
+    let commits: Vec<Commit> = workspace_command
+        .parse_union_revsets(ui, &args.revisions)?
+        .evaluate_to_commits()?
+        .try_collect()?;
+    if commits
+        .iter()
+        .all(|commit| commit.id() == repo.store().root_commit_id())
+    {
+        return Err(user_error("No revisions to bundle"));
+    }
+    let git_settings = workspace_command.settings().git_settings()?;
+    let stats = git::create_bundle(
+        repo,
+        &git_settings,
+        &commits,
+        &command.cwd().join(&args.path),
+    )?;
+    writeln!(
+        ui.status(),
+        "Wrote {} commits and {} bookmarks to {}",
+        stats.num_commits,
+        stats.bookmarks.len(),
+        args.path.display()
+    )?;
+    Ok(())
+}
@@ -0,0 +1,75 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use jj_lib::git;
+use jj_lib::ref_name::RemoteNameBuf;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::git_util::print_git_import_stats;
+use crate::ui::Ui;
+
+/// Import revisions and bookmarks from a bundle file
+///
+/// The bookmarks in the bundle are imported as remote bookmarks, in the same
+/// way as `jj git fetch` does. The remote doesn't have to be configured. The
+/// revisions in the bundle are made visible even if no bookmark points to
+/// them, and keep the change IDs they had in the repo the bundle was created
+/// from.
+#[derive(clap::Args, Clone, Debug)]
+pub struct BundleImportArgs {
+    /// The bundle file to import
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    path: PathBuf,
+    /// The remote to import the bookmarks of the bundle as
+    #[arg(long, default_value = "bundle")]
+    remote: RemoteNameBuf,
+}
+
+pub fn cmd_bundle_import(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BundleImportArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let git_settings = workspace_command.settings().git_settings()?;
+    let mut tx = workspace_command.start_transaction();
+    let stats = git::import_bundle(
+        tx.repo_mut(),
+        &git_settings,
+        &args.remote,
+        &command.cwd().join(&args.path),
+    )?;
+    print_git_import_stats(ui, tx.repo(), &stats.import_stats, true)?;
+    if stats.num_new_commits > 0 {
+        writeln!(
+            ui.status(),
+            "Imported {} commits from {}",
+            stats.num_new_commits,
+            args.path.display()
+        )?;
+    }
+    tx.finish(
+        ui,
+        format!(
+            "import bundle {} as remote {}",
+            args.path.display(),
+            args.remote.as_symbol()
+        ),
+    )?;
+    Ok(())
+}
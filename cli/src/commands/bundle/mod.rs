@@ -0,0 +1,48 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod create;
+mod import;
+
+use clap::Subcommand;
+
+use self::create::BundleCreateArgs;
+use self::create::cmd_bundle_create;
+use self::import::BundleImportArgs;
+use self::import::cmd_bundle_import;
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Transfer revisions and bookmarks through bundle files
+///
+/// Bundles are Git bundle files, which can be carried to repos without network
+/// access between them. Besides the commits and the bookmarks, bundles created
+/// by `jj bundle create` record the change IDs of the revisions.
+#[derive(Subcommand, Clone, Debug)]
+pub enum BundleCommand {
+    Create(BundleCreateArgs),
+    Import(BundleImportArgs),
+}
+
+pub fn cmd_bundle(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &BundleCommand,
+) -> Result<(), CommandError> {
+    match subcommand {
+        BundleCommand::Create(args) => cmd_bundle_create(ui, command, args),
+        BundleCommand::Import(args) => cmd_bundle_import(ui, command, args),
+    }
+}
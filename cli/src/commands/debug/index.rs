@@ -76,6 +76,43 @@ pub fn cmd_debug_index(
                 level.num_changed_paths
             )?;
             writeln!(ui.stdout(), "    Number of paths: {}", level.num_paths)?;
+            if let Some(num_bytes) = level.num_bloom_filter_bytes {
+                writeln!(ui.stdout(), "    Bloom filter size: {num_bytes} bytes")?;
+            } else {
+                writeln!(ui.stdout(), "    Bloom filter size: none")?;
+            }
+            writeln!(ui.stdout(), "    Name: {}", level.name)?;
+        }
+
+        writeln!(ui.stdout(), "=== Reachability bitmaps ===")?;
+        if let Some(bitmaps) = &stats.reachability_bitmaps {
+            writeln!(ui.stdout(), "Number of heads: {}", bitmaps.num_heads)?;
+            writeln!(
+                ui.stdout(),
+                "Number of reachable commits: {}",
+                bitmaps.num_reachable_commits
+            )?;
+            writeln!(ui.stdout(), "Name: {}", bitmaps.name)?;
+        } else {
+            writeln!(ui.stdout(), "Number of heads: 0")?;
+        }
+
+        writeln!(ui.stdout(), "=== Text index ===")?;
+        if let Some(range) = &stats.text_index_commits_range {
+            writeln!(ui.stdout(), "Indexed commits: {range:?}")?;
+        } else {
+            writeln!(ui.stdout(), "Indexed commits: none")?;
+        }
+        writeln!(ui.stdout(), "Stats per level:")?;
+        for (i, level) in stats.text_index_levels.iter().enumerate() {
+            writeln!(ui.stdout(), "  Level {i}:")?;
+            writeln!(ui.stdout(), "    Number of commits: {}", level.num_commits)?;
+            writeln!(ui.stdout(), "    Number of keys: {}", level.num_keys)?;
+            writeln!(
+                ui.stdout(),
+                "    Number of commit keys: {}",
+                level.num_commit_keys
+            )?;
             writeln!(ui.stdout(), "    Name: {}", level.name)?;
         }
     } else {
@@ -0,0 +1,74 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+
+use itertools::Itertools as _;
+use jj_lib::default_index::DefaultIndexStore;
+use jj_lib::repo::Repo as _;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::command_error::CommandError;
+use crate::command_error::internal_error;
+use crate::command_error::user_error;
+use crate::ui::Ui;
+
+/// Build reachability bitmaps of the selected heads
+///
+/// Ancestor queries such as `::x` and `x..y` are evaluated by using the
+/// bitmaps of the revisions, or of their nearest ancestors. Existing bitmaps
+/// are replaced.
+#[derive(clap::Args, Clone, Debug)]
+pub struct DebugIndexBitmapsArgs {
+    /// Revisions to build bitmaps for
+    #[arg(
+        long,
+        short,
+        default_value = "trunk() | bookmarks()",
+        value_name = "REVSETS"
+    )]
+    revisions: Vec<RevisionArg>,
+}
+
+pub fn cmd_debug_index_bitmaps(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &DebugIndexBitmapsArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let repo = workspace_command.repo();
+    let heads: Vec<_> = workspace_command
+        .parse_union_revsets(ui, &args.revisions)?
+        .evaluate_to_commit_ids()?
+        .try_collect()?;
+    let repo_loader = workspace_command.workspace().repo_loader();
+    let index_store = repo_loader.index_store();
+    let Some(default_index_store) = index_store.downcast_ref::<DefaultIndexStore>() else {
+        return Err(user_error(format!(
+            "Unsupported index type '{}'",
+            index_store.name()
+        )));
+    };
+    let index = default_index_store
+        .build_reachability_bitmaps_at_operation(repo.op_id(), repo.store(), &heads)
+        .map_err(internal_error)?;
+    let stats = index.stats().reachability_bitmaps.unwrap();
+    writeln!(
+        ui.status(),
+        "Built reachability bitmaps for {} revisions.",
+        stats.num_heads
+    )?;
+    Ok(())
+}
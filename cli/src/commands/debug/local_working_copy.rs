@@ -37,6 +37,20 @@ pub fn cmd_debug_local_working_copy(
     let wc = check_local_disk_wc(workspace_command.working_copy())?;
     writeln!(ui.stdout(), "Current operation: {:?}", wc.operation_id())?;
     writeln!(ui.stdout(), "Current tree: {:?}", wc.tree_id()?)?;
+    // The file states are stored inline unless the segmented format is used
+    let segments = wc.file_state_segments()?;
+    if !segments.is_empty() {
+        writeln!(ui.stdout(), "Tree state format: segmented")?;
+        for segment in &segments {
+            writeln!(
+                ui.stdout(),
+                "Segment {}: {} entries, {} removed paths",
+                segment.name,
+                segment.num_entries,
+                segment.num_removed_paths
+            )?;
+        }
+    }
     for (file, state) in wc.file_states()? {
         writeln!(
             ui.stdout(),
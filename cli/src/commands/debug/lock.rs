@@ -0,0 +1,86 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Debug;
+use std::io::Write as _;
+use std::time::SystemTime;
+
+use jj_lib::lock::lease;
+use jj_lib::network_op_heads_store::NetworkOpHeadsStore;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::ui::Ui;
+
+/// Show or break the lease lock of the operation heads store
+///
+/// This command only works with the network operation heads store. The repo is
+/// not loaded, so this command works while the lock is held.
+///
+/// Locks which are no longer renewed are broken automatically by the next
+/// command that waits for the lock.
+#[derive(clap::Args, Clone, Debug)]
+pub struct DebugLockArgs {
+    /// Remove the lock file even if the owner may still be renewing the lease
+    ///
+    /// Only use this if the owner process is known to have terminated.
+    #[arg(long = "break")]
+    break_lock: bool,
+}
+
+pub fn cmd_debug_lock(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &DebugLockArgs,
+) -> Result<(), CommandError> {
+    let workspace = command.load_workspace()?;
+    let op_heads_store = workspace.repo_loader().op_heads_store();
+    let Some(store) = op_heads_store.downcast_ref::<NetworkOpHeadsStore>() else {
+        return Err(user_error(
+            "This command requires the network operation heads store",
+        ));
+    };
+    let lock_path = store.lock_path();
+    let Some(info) = lease::inspect_lock(&lock_path).map_err(user_error)? else {
+        writeln!(ui.status(), "The lock is not held")?;
+        return Ok(());
+    };
+    // The modification time is set by the file server, whose clock may differ
+    // from the local clock.
+    let age = SystemTime::now()
+        .duration_since(info.renewed_at)
+        .unwrap_or_default();
+    writeln!(ui.stdout(), "Lock file: {}", lock_path.display())?;
+    match &info.owner {
+        Some(owner) => {
+            writeln!(ui.stdout(), "Owner host: {}", owner.hostname)?;
+            writeln!(ui.stdout(), "Owner pid: {}", owner.pid)?;
+        }
+        None => writeln!(ui.stdout(), "Owner: unknown")?,
+    }
+    writeln!(ui.stdout(), "Renewed: {age:.1?} ago")?;
+
+    if args.break_lock {
+        if lease::break_lock(&lock_path, &info).map_err(user_error)? {
+            writeln!(ui.status(), "Removed lock file")?;
+        } else {
+            writeln!(
+                ui.status(),
+                "The lock was released or taken over in the meantime"
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -15,9 +15,13 @@
 mod copy_detection;
 mod fileset;
 mod index;
+mod index_bitmaps;
 mod index_changed_paths;
+mod index_text;
+mod init_native;
 mod init_simple;
 mod local_working_copy;
+mod lock;
 mod object;
 mod reindex;
 mod revset;
@@ -37,12 +41,20 @@ use self::fileset::DebugFilesetArgs;
 use self::fileset::cmd_debug_fileset;
 use self::index::DebugIndexArgs;
 use self::index::cmd_debug_index;
+use self::index_bitmaps::DebugIndexBitmapsArgs;
+use self::index_bitmaps::cmd_debug_index_bitmaps;
 use self::index_changed_paths::DebugIndexChangedPathsArgs;
 use self::index_changed_paths::cmd_debug_index_changed_paths;
+use self::index_text::DebugIndexTextArgs;
+use self::index_text::cmd_debug_index_text;
+use self::init_native::DebugInitNativeArgs;
+use self::init_native::cmd_debug_init_native;
 use self::init_simple::DebugInitSimpleArgs;
 use self::init_simple::cmd_debug_init_simple;
 use self::local_working_copy::DebugLocalWorkingCopyArgs;
 use self::local_working_copy::cmd_debug_local_working_copy;
+use self::lock::DebugLockArgs;
+use self::lock::cmd_debug_lock;
 use self::object::DebugObjectArgs;
 use self::object::cmd_debug_object;
 use self::reindex::DebugReindexArgs;
@@ -71,9 +83,13 @@ pub enum DebugCommand {
     CopyDetection(CopyDetectionArgs),
     Fileset(DebugFilesetArgs),
     Index(DebugIndexArgs),
+    IndexBitmaps(DebugIndexBitmapsArgs),
     IndexChangedPaths(DebugIndexChangedPathsArgs),
+    IndexText(DebugIndexTextArgs),
+    InitNative(DebugInitNativeArgs),
     InitSimple(DebugInitSimpleArgs),
     LocalWorkingCopy(DebugLocalWorkingCopyArgs),
+    Lock(DebugLockArgs),
     #[command(subcommand)]
     Object(DebugObjectArgs),
     Reindex(DebugReindexArgs),
@@ -95,9 +111,13 @@ pub fn cmd_debug(
         DebugCommand::CopyDetection(args) => cmd_debug_copy_detection(ui, command, args),
         DebugCommand::Fileset(args) => cmd_debug_fileset(ui, command, args),
         DebugCommand::Index(args) => cmd_debug_index(ui, command, args),
+        DebugCommand::IndexBitmaps(args) => cmd_debug_index_bitmaps(ui, command, args),
         DebugCommand::IndexChangedPaths(args) => cmd_debug_index_changed_paths(ui, command, args),
+        DebugCommand::IndexText(args) => cmd_debug_index_text(ui, command, args),
+        DebugCommand::InitNative(args) => cmd_debug_init_native(ui, command, args),
         DebugCommand::InitSimple(args) => cmd_debug_init_simple(ui, command, args),
         DebugCommand::LocalWorkingCopy(args) => cmd_debug_local_working_copy(ui, command, args),
+        DebugCommand::Lock(args) => cmd_debug_lock(ui, command, args),
         DebugCommand::Object(args) => cmd_debug_object(ui, command, args),
         DebugCommand::Reindex(args) => cmd_debug_reindex(ui, command, args),
         DebugCommand::Revset(args) => cmd_debug_revset(ui, command, args),
@@ -13,21 +13,49 @@
 // limitations under the License.
 
 use std::fmt::Debug;
+use std::io::Write as _;
+
+use jj_lib::working_copy::SnapshotTimings;
 
 use crate::cli_util::CommandHelper;
+use crate::cli_util::print_snapshot_stats;
 use crate::command_error::CommandError;
 use crate::ui::Ui;
 
 /// Trigger a snapshot in the op log
 #[derive(clap::Args, Clone, Debug)]
-pub struct DebugSnapshotArgs {}
+pub struct DebugSnapshotArgs {
+    /// Print statistics about the snapshot
+    #[arg(long)]
+    stats: bool,
+}
 
 pub fn cmd_debug_snapshot(
     ui: &mut Ui,
     command: &CommandHelper,
-    _args: &DebugSnapshotArgs,
+    args: &DebugSnapshotArgs,
 ) -> Result<(), CommandError> {
     // workspace helper will snapshot as needed
-    command.workspace_helper(ui)?;
+    let (workspace_command, stats) = command.workspace_helper_with_stats(ui)?;
+    if workspace_command.should_print_auto_snapshot_stats()? {
+        print_snapshot_stats(ui, &stats, workspace_command.env().path_converter())?;
+    }
+    if args.stats {
+        let SnapshotTimings {
+            fsmonitor,
+            traverse_filesystem,
+            write_tree,
+        } = stats.timings;
+        let mut formatter = ui.stdout_formatter();
+        writeln!(formatter, "Threads: {}", stats.num_threads)?;
+        writeln!(
+            formatter,
+            "Untracked paths: {}",
+            stats.untracked_paths.len()
+        )?;
+        writeln!(formatter, "Query fsmonitor: {fsmonitor:?}")?;
+        writeln!(formatter, "Traverse filesystem: {traverse_filesystem:?}")?;
+        writeln!(formatter, "Write tree: {write_tree:?}")?;
+    }
     Ok(())
 }
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
+
 use clap_complete::ArgValueCandidates;
 use clap_complete::ArgValueCompleter;
 use jj_lib::annotate::FileAnnotation;
@@ -103,7 +105,13 @@ pub(crate) fn cmd_file_annotate(
     // Note that this is probably different from "--skip REVS", which won't
     // exclude the revisions, but will ignore diffs in those revisions as if
     // ancestor revisions had new content.
-    let mut annotator = FileAnnotator::from_commit(&starting_commit, &file_path)?;
+    let text_converters = workspace_command.text_converters()?;
+    let mut annotator = if text_converters.is_empty() {
+        FileAnnotator::from_commit(&starting_commit, &file_path)?
+    } else {
+        let converter = Arc::new(text_converters);
+        FileAnnotator::from_commit_with_converter(&starting_commit, &file_path, converter)?
+    };
     annotator.compute(repo.as_ref(), &RevsetExpression::all())?;
     let annotation = annotator.to_annotation();
 
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use std::io::Write as _;
+use std::num::NonZeroUsize;
 
 use clap_complete::ArgValueCandidates;
 use clap_complete::ArgValueCompleter;
@@ -45,6 +46,10 @@ use crate::ui::Ui;
 ///
 /// If the given path is a directory, files in the directory will be visited
 /// recursively.
+///
+/// Conflicted files are printed with conflict markers by default. Use `--side`
+/// or `--base` to print a single term of the conflict instead. Files without
+/// conflicts are printed as is.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct FileShowArgs {
     /// The revision to get the file contents from
@@ -72,6 +77,18 @@ pub(crate) struct FileShowArgs {
     #[arg(long, short = 'T', add = ArgValueCandidates::new(complete::template_aliases))]
     template: Option<String>,
 
+    /// Print the given side of conflicted files (starting from 1)
+    ///
+    /// Sides are numbered as in the conflict markers.
+    #[arg(long, value_name = "N", conflicts_with = "base")]
+    side: Option<NonZeroUsize>,
+
+    /// Print the given base of conflicted files (starting from 1)
+    ///
+    /// Bases are numbered as in the conflict markers.
+    #[arg(long, value_name = "N")]
+    base: Option<NonZeroUsize>,
+
     /// Paths to print
     #[arg(
         required = true,
@@ -104,6 +121,11 @@ pub(crate) fn cmd_file_show(
             .parse_template(ui, &language, &text)?
             .labeled(["file_show"])
     };
+    let conflict_term = match (args.side, args.base) {
+        (Some(n), _) => Some(ConflictTerm::Side(n.get())),
+        (_, Some(n)) => Some(ConflictTerm::Base(n.get())),
+        (None, None) => None,
+    };
 
     // Try fast path for single file entry
     if let Some(path) = get_single_path(&fileset_expression) {
@@ -118,7 +140,13 @@ pub(crate) fn cmd_file_show(
                 path: path.to_owned(),
                 value,
             };
-            write_tree_entries(ui, &workspace_command, &template, [Ok(entry)])?;
+            write_tree_entries(
+                ui,
+                &workspace_command,
+                &template,
+                conflict_term,
+                [Ok(entry)],
+            )?;
             return Ok(());
         }
     }
@@ -129,6 +157,7 @@ pub(crate) fn cmd_file_show(
         ui,
         &workspace_command,
         &template,
+        conflict_term,
         tree.entries_matching(matcher.as_ref())
             .map(|(path, value)| Ok((path, value?)))
             .map_ok(|(path, value)| TreeEntry { path, value }),
@@ -149,10 +178,18 @@ fn get_single_path(expression: &FilesetExpression) -> Option<&RepoPath> {
     }
 }
 
+/// Term of a conflicted file to print, numbered from 1.
+#[derive(Clone, Copy, Debug)]
+enum ConflictTerm {
+    Side(usize),
+    Base(usize),
+}
+
 fn write_tree_entries(
     ui: &Ui,
     workspace_command: &WorkspaceCommandHelper,
     template: &TemplateRenderer<TreeEntry>,
+    conflict_term: Option<ConflictTerm>,
     entries: impl IntoIterator<Item = BackendResult<TreeEntry>>,
 ) -> Result<(), CommandError> {
     let repo = workspace_command.repo();
@@ -174,6 +211,25 @@ fn write_tree_entries(
                 copy_async_to_sync(file.reader, ui.stdout_formatter().as_mut()).block_on()?;
             }
             MaterializedTreeValue::FileConflict(file) => {
+                if let Some(term) = conflict_term {
+                    let content = match term {
+                        ConflictTerm::Side(n) => file.contents.get_add(n - 1),
+                        ConflictTerm::Base(n) => file.contents.get_remove(n - 1),
+                    };
+                    let Some(content) = content else {
+                        let ui_path = workspace_command.format_file_path(&entry.path);
+                        return Err(user_error(match term {
+                            ConflictTerm::Side(n) => {
+                                format!("Conflict at '{ui_path}' has no side #{n}")
+                            }
+                            ConflictTerm::Base(n) => {
+                                format!("Conflict at '{ui_path}' has no base #{n}")
+                            }
+                        }));
+                    };
+                    ui.stdout_formatter().write_all(content)?;
+                    continue;
+                }
                 let options = ConflictMaterializeOptions {
                     marker_style: workspace_command.env().conflict_marker_style(),
                     marker_len: None,
@@ -38,11 +38,17 @@ use crate::ui::Ui;
 /// `snapshot.auto-track` (e.g. to `"none()"` or `"glob:**/*.rs"`). Files that
 /// don't match the pattern can be manually tracked using this command. The
 /// default pattern is `all()`.
+///
+/// New files larger than `snapshot.max-new-file-size` are left untracked
+/// unless `--force-large` is specified.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct FileTrackArgs {
     /// Paths to track
     #[arg(required = true, value_name = "FILESETS", value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+    /// Track the specified paths regardless of their size
+    #[arg(long)]
+    force_large: bool,
 }
 
 #[instrument(skip_all)]
@@ -51,11 +57,15 @@ pub(crate) fn cmd_file_track(
     command: &CommandHelper,
     args: &FileTrackArgs,
 ) -> Result<(), CommandError> {
-    let (mut workspace_command, auto_stats) = command.workspace_helper_with_stats(ui)?;
+    let (mut workspace_command, mut auto_stats) = command.workspace_helper_with_stats(ui)?;
     let matcher = workspace_command
         .parse_file_patterns(ui, &args.paths)?
         .to_matcher();
-    let options = workspace_command.snapshot_options_with_start_tracking_matcher(&matcher)?;
+    let mut options = workspace_command.snapshot_options_with_start_tracking_matcher(&matcher)?;
+    if args.force_large {
+        options.max_new_file_size = u64::MAX;
+        options.max_new_file_size_overrides.clear();
+    }
 
     let mut tx = workspace_command.start_transaction().into_inner();
     let (mut locked_ws, _wc_commit) = workspace_command.start_working_copy_mutation()?;
@@ -66,6 +76,14 @@ pub(crate) fn cmd_file_track(
     }
     let repo = tx.commit("track paths")?;
     locked_ws.finish(repo.op_id().clone())?;
+    if workspace_command.should_print_auto_snapshot_stats()? {
+        // Paths tracked by this command are no longer untracked
+        auto_stats.untracked_paths.retain(|path, _| {
+            !matcher.matches(path) || track_stats.untracked_paths.contains_key(path)
+        });
+    } else {
+        auto_stats.untracked_paths.clear();
+    }
     print_track_snapshot_stats(
         ui,
         auto_stats,
@@ -116,6 +134,8 @@ pub fn print_track_snapshot_stats(
                 This will increase the maximum file size allowed for new files, in this repository only.
               - Run `jj --config snapshot.max-new-file-size={size} file track {large_files_list}`
                 This will increase the maximum file size allowed for new files, for this command only.
+              - Run `jj file track --force-large {large_files_list}`
+                This will track the files regardless of their size.
             "
         )?;
     }
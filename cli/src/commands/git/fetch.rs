@@ -12,26 +12,44 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::collections::HashSet;
 
 use clap_complete::ArgValueCandidates;
 use itertools::Itertools as _;
+use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
 use jj_lib::config::ConfigGetResultExt as _;
 use jj_lib::git;
 use jj_lib::git::GitFetch;
+use jj_lib::git::GitImportStats;
 use jj_lib::git::IgnoredRefspec;
 use jj_lib::git::IgnoredRefspecs;
 use jj_lib::git::expand_default_fetch_refspecs;
 use jj_lib::git::expand_fetch_refspecs;
 use jj_lib::git::get_git_backend;
+use jj_lib::id_prefix::IdPrefixContext;
 use jj_lib::ref_name::RemoteName;
+use jj_lib::ref_name::RemoteRefSymbol;
 use jj_lib::repo::Repo as _;
+use jj_lib::revset::RevsetExpression;
+use jj_lib::revset::RevsetIteratorExt as _;
+use jj_lib::rewrite::EmptyBehavior;
+use jj_lib::rewrite::MoveCommitsLocation;
+use jj_lib::rewrite::MoveCommitsTarget;
+use jj_lib::rewrite::RebaseOptions;
+use jj_lib::rewrite::RebasedCommit;
+use jj_lib::rewrite::RewriteRefsOptions;
+use jj_lib::rewrite::move_commits;
 use jj_lib::str_util::StringMatcher;
 use jj_lib::str_util::StringPattern;
 
 use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
 use crate::cli_util::WorkspaceCommandHelper;
 use crate::cli_util::WorkspaceCommandTransaction;
+use crate::cli_util::print_updated_commits;
+use crate::cli_util::short_commit_hash;
 use crate::command_error::CommandError;
 use crate::command_error::config_error;
 use crate::command_error::user_error;
@@ -39,12 +57,16 @@ use crate::commands::git::get_single_remote;
 use crate::complete;
 use crate::git_util::print_git_import_stats;
 use crate::git_util::with_remote_git_callbacks;
+use crate::revset_util::RevsetExpressionEvaluator;
 use crate::ui::Ui;
 
 /// Fetch from a Git remote
 ///
 /// If a working-copy commit gets abandoned, it will be given a new, empty
 /// commit. This is true in general; it is not specific to this command.
+///
+/// If `git.fetch-auto-rebase` is enabled, mutable commits on top of fetched
+/// bookmarks are rebased as by `jj git sync`.
 #[derive(clap::Args, Clone, Debug)]
 pub struct GitFetchArgs {
     /// Fetch only some of the branches
@@ -95,6 +117,19 @@ pub fn cmd_git_fetch(
     ui: &mut Ui,
     command: &CommandHelper,
     args: &GitFetchArgs,
+) -> Result<(), CommandError> {
+    let auto_rebase = command.settings().get_bool("git.fetch-auto-rebase")?;
+    fetch_and_import(ui, command, args, auto_rebase)
+}
+
+/// Fetches from the remotes specified by `args` and imports the refs. If
+/// `auto_rebase` is true, mutable descendants of the moved remote bookmarks
+/// are rebased onto their new positions.
+pub(crate) fn fetch_and_import(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &GitFetchArgs,
+    auto_rebase: bool,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
     let remote_patterns = if args.all_remotes {
@@ -168,8 +203,21 @@ pub fn cmd_git_fetch(
     }
 
     let import_stats = git_fetch.import_refs()?;
+    if tx
+        .settings()
+        .get_bool("git.fetch-require-trusted-signature")?
+        && let Err(err) = check_fetched_commits_trusted(ui, command, &tx, &import_stats)
+    {
+        // The Git refs have already been updated by the fetch. Reset them so
+        // the rejected commits won't be imported by the next import.
+        git::reset_imported_git_refs(tx.repo().store(), &import_stats)?;
+        return Err(err);
+    }
     print_git_import_stats(ui, tx.repo(), &import_stats, true)?;
     warn_if_branches_not_found(ui, &tx, &args.branch, &remotes)?;
+    if auto_rebase {
+        rebase_onto_moved_bookmarks(ui, command, &mut tx, &import_stats)?;
+    }
     tx.finish(
         ui,
         format!(
@@ -214,6 +262,189 @@ fn parse_remote_pattern(remote: &str) -> Result<StringPattern, CommandError> {
     StringPattern::parse(remote).map_err(config_error)
 }
 
+/// Fails if any of the newly fetched commits isn't matched by the
+/// `trusted_signature()` revset.
+fn check_fetched_commits_trusted(
+    ui: &Ui,
+    command: &CommandHelper,
+    tx: &WorkspaceCommandTransaction,
+    import_stats: &GitImportStats,
+) -> Result<(), CommandError> {
+    let workspace_helper = tx.base_workspace_helper();
+    let trusted_revset_str = RevisionArg::from("trusted_signature()".to_owned());
+    let trusted_expression = workspace_helper
+        .parse_revset(ui, &trusted_revset_str)?
+        .expression()
+        .clone();
+    let new_heads = import_stats
+        .changed_remote_bookmarks
+        .iter()
+        .chain(&import_stats.changed_remote_tags)
+        .flat_map(|(_, (_, new_target))| new_target.added_ids())
+        .cloned()
+        .collect_vec();
+    let old_heads = tx.base_repo().view().heads().iter().cloned().collect_vec();
+    let untrusted_expression = RevsetExpression::commits(old_heads)
+        .range(&RevsetExpression::commits(new_heads))
+        .minus(&trusted_expression);
+    // The disambiguation data must not be cached against the transaction repo.
+    let id_prefix_context = IdPrefixContext::new(command.revset_extensions().clone());
+    let evaluator = RevsetExpressionEvaluator::new(
+        tx.repo(),
+        command.revset_extensions().clone(),
+        &id_prefix_context,
+        untrusted_expression,
+    );
+    if let Some(commit) = evaluator.evaluate_to_commits()?.next() {
+        let commit = commit?;
+        let mut error = user_error(format!(
+            "Won't import commit {} since it doesn't have a trusted signature",
+            short_commit_hash(commit.id())
+        ));
+        error.add_formatted_hint_with(|formatter| {
+            write!(formatter, "Rejected commit: ")?;
+            tx.write_commit_summary(formatter, &commit)?;
+            Ok(())
+        });
+        error.add_hint(
+            "Commits must be signed by a signer in `signing.trust-policy` when \
+             `git.fetch-require-trusted-signature` is set",
+        );
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Rebases mutable descendants of the old positions of the fetched remote
+/// bookmarks onto their new positions. Commits that become empty, because
+/// their changes landed upstream, are abandoned. Stacks are left in place if
+/// the bookmark was deleted, became conflicted, or moved backwards.
+fn rebase_onto_moved_bookmarks(
+    ui: &Ui,
+    command: &CommandHelper,
+    tx: &mut WorkspaceCommandTransaction,
+    import_stats: &GitImportStats,
+) -> Result<(), CommandError> {
+    // Commits that were fetched shouldn't be rewritten, but stacks that were
+    // pushed before are rebased along with the local ones.
+    let new_heads = import_stats
+        .changed_remote_bookmarks
+        .iter()
+        .flat_map(|(_, (_, new_target))| new_target.added_ids())
+        .cloned()
+        .collect_vec();
+    let upstream_expression = RevsetExpression::commits(new_heads).ancestors();
+    let immutable_expression = tx.base_workspace_helper().env().immutable_expression();
+    let options = RebaseOptions {
+        empty: EmptyBehavior::AbandonNewlyEmpty,
+        rewrite_refs: RewriteRefsOptions {
+            delete_abandoned_bookmarks: false,
+        },
+        simplify_ancestor_merge: false,
+    };
+    for (symbol, (old_remote_ref, new_target)) in &import_stats.changed_remote_bookmarks {
+        let old_target = &old_remote_ref.target;
+        if old_target.is_absent() || old_target == new_target {
+            continue;
+        }
+        // Local stacks start at the children of the old position.
+        let roots_expression = RevsetExpression::commits(old_target.added_ids().cloned().collect())
+            .children()
+            .minus(&upstream_expression)
+            .minus(&immutable_expression);
+        // The disambiguation data must not be cached against the transaction repo.
+        let id_prefix_context = IdPrefixContext::new(command.revset_extensions().clone());
+        let root_ids: Vec<CommitId> = RevsetExpressionEvaluator::new(
+            tx.repo(),
+            command.revset_extensions().clone(),
+            &id_prefix_context,
+            roots_expression,
+        )
+        .evaluate_to_commit_ids()?
+        .try_collect()?;
+        if root_ids.is_empty() {
+            continue;
+        }
+        let symbol = symbol.as_ref();
+        let new_id = match (old_target.as_normal(), new_target.as_normal()) {
+            _ if new_target.is_absent() => Err("was deleted"),
+            (Some(old_id), Some(new_id)) if tx.repo().index().is_ancestor(new_id, old_id)? => {
+                Err("moved backwards")
+            }
+            (Some(_), Some(new_id)) => Ok(new_id),
+            _ => Err("is conflicted"),
+        };
+        let new_id = match new_id {
+            Ok(new_id) => new_id,
+            Err(reason) => {
+                writeln!(
+                    ui.warning_default(),
+                    "Not rebasing descendants of {symbol} because the bookmark {reason}:"
+                )?;
+                let root_commits: Vec<Commit> = root_ids
+                    .iter()
+                    .map(|id| tx.repo().store().get_commit(id))
+                    .try_collect()?;
+                print_updated_commits(
+                    ui.stderr_formatter().as_mut(),
+                    &tx.base_workspace_helper().commit_summary_template(),
+                    &root_commits,
+                )?;
+                continue;
+            }
+        };
+        let loc = MoveCommitsLocation {
+            new_parent_ids: vec![new_id.clone()],
+            new_child_ids: vec![],
+            target: MoveCommitsTarget::Roots(root_ids),
+        };
+        let stats = move_commits(tx.repo_mut(), &loc, &options)?;
+        print_rebased_onto_bookmark(ui, tx, symbol, &stats.rebased_commits)?;
+    }
+    Ok(())
+}
+
+fn print_rebased_onto_bookmark(
+    ui: &Ui,
+    tx: &WorkspaceCommandTransaction,
+    symbol: RemoteRefSymbol<'_>,
+    rebased_commits: &HashMap<CommitId, RebasedCommit>,
+) -> Result<(), CommandError> {
+    let Some(mut formatter) = ui.status_formatter() else {
+        return Ok(());
+    };
+    let num_rebased = rebased_commits
+        .values()
+        .filter(|rebased| matches!(rebased, RebasedCommit::Rewritten(_)))
+        .count();
+    let abandoned_ids = rebased_commits
+        .iter()
+        .filter(|(_, rebased)| matches!(rebased, RebasedCommit::Abandoned { .. }))
+        .map(|(id, _)| id.clone())
+        .collect_vec();
+    let abandoned_commits: Vec<Commit> = RevsetExpression::commits(abandoned_ids)
+        .evaluate(tx.repo())?
+        .iter()
+        .commits(tx.repo().store())
+        .try_collect()?;
+    if num_rebased > 0 {
+        writeln!(formatter, "Rebased {num_rebased} commits onto {symbol}")?;
+    }
+    if !abandoned_commits.is_empty() {
+        writeln!(
+            formatter,
+            "Abandoned {} commits that became empty on {symbol}:",
+            abandoned_commits.len()
+        )?;
+        print_updated_commits(
+            formatter.as_mut(),
+            &tx.base_workspace_helper().commit_summary_template(),
+            &abandoned_commits,
+        )?;
+    }
+    Ok(())
+}
+
 fn warn_if_branches_not_found(
     ui: &mut Ui,
     tx: &WorkspaceCommandTransaction,
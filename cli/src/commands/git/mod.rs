@@ -21,6 +21,7 @@ mod init;
 mod push;
 mod remote;
 mod root;
+mod sync;
 
 use std::io::Write as _;
 use std::path::Path;
@@ -49,11 +50,14 @@ use self::init::GitInitArgs;
 use self::init::cmd_git_init;
 use self::push::GitPushArgs;
 use self::push::cmd_git_push;
+pub(crate) use self::push::get_default_push_remote;
 pub use self::push::is_push_operation;
 use self::remote::RemoteCommand;
 use self::remote::cmd_git_remote;
 use self::root::GitRootArgs;
 use self::root::cmd_git_root;
+use self::sync::GitSyncArgs;
+use self::sync::cmd_git_sync;
 use crate::cli_util::CommandHelper;
 use crate::cli_util::WorkspaceCommandHelper;
 use crate::command_error::CommandError;
@@ -82,6 +86,7 @@ pub enum GitCommand {
     #[command(subcommand)]
     Remote(RemoteCommand),
     Root(GitRootArgs),
+    Sync(GitSyncArgs),
 }
 
 pub fn cmd_git(
@@ -99,6 +104,7 @@ pub fn cmd_git(
         GitCommand::Push(args) => cmd_git_push(ui, command, args),
         GitCommand::Remote(args) => cmd_git_remote(ui, command, args),
         GitCommand::Root(args) => cmd_git_root(ui, command, args),
+        GitCommand::Sync(args) => cmd_git_sync(ui, command, args),
     }
 }
 
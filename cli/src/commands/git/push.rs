@@ -534,6 +534,17 @@ fn validate_commits_ready_to_push(
         .parse_revset(ui, &private_revset_str)?
         .evaluate()?
         .containing_fn();
+    let is_trusted = if settings.get_bool("git.push-require-trusted-signature")? {
+        let trusted_revset_str = RevisionArg::from("trusted_signature()".to_owned());
+        Some(
+            workspace_helper
+                .parse_revset(ui, &trusted_revset_str)?
+                .evaluate()?
+                .containing_fn(),
+        )
+    } else {
+        None
+    };
     let sign_settings = sign_behavior.map(|sign_behavior| {
         let mut sign_settings = settings.sign_settings();
         sign_settings.behavior = sign_behavior;
@@ -569,6 +580,18 @@ fn validate_commits_ready_to_push(
         if !args.allow_private && is_private {
             reasons.push("it is private");
         }
+        // Commits to be signed on push will carry the user's own signature, so
+        // they aren't checked against the trust policy.
+        let will_be_signed = sign_settings.as_ref().is_some_and(|sign_settings| {
+            !commit.is_signed() && sign_settings.should_sign(commit.store_commit())
+        });
+        let is_untrusted = match &is_trusted {
+            Some(is_trusted) => !will_be_signed && !is_trusted(commit.id())?,
+            None => false,
+        };
+        if is_untrusted {
+            reasons.push("it doesn't have a trusted signature");
+        }
         if !reasons.is_empty() {
             let mut error = user_error(format!(
                 "Won't push commit {} since {}",
@@ -585,12 +608,15 @@ fn validate_commits_ready_to_push(
                     "Configured git.private-commits: '{private_revset_str}'",
                 ));
             }
+            if is_untrusted {
+                error.add_hint(
+                    "Commits must be signed by a signer in `signing.trust-policy` when \
+                     `git.push-require-trusted-signature` is set",
+                );
+            }
             return Err(error);
         }
-        if let Some(sign_settings) = &sign_settings
-            && !commit.is_signed()
-            && sign_settings.should_sign(commit.store_commit())
-        {
+        if will_be_signed {
             commits_to_sign.push(commit);
         }
     }
@@ -713,7 +739,7 @@ fn print_commits_ready_to_push(
     Ok(())
 }
 
-fn get_default_push_remote(
+pub(crate) fn get_default_push_remote(
     ui: &Ui,
     workspace_command: &WorkspaceCommandHelper,
 ) -> Result<RemoteNameBuf, CommandError> {
@@ -0,0 +1,45 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use tracing::instrument;
+
+use super::fetch::GitFetchArgs;
+use super::fetch::fetch_and_import;
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Fetch from a Git remote and rebase local changes onto the fetched bookmarks
+///
+/// For each remote bookmark that moved, mutable descendants of its previous
+/// position are rebased onto the new position. Commits that become empty
+/// because their changes landed upstream are abandoned. Descendants of a
+/// bookmark that was deleted, became conflicted, or moved backwards are left
+/// in place.
+///
+/// This is the same as `jj git fetch` with `git.fetch-auto-rebase` enabled.
+#[derive(clap::Args, Clone, Debug)]
+pub struct GitSyncArgs {
+    #[command(flatten)]
+    fetch: GitFetchArgs,
+}
+
+#[instrument(skip_all)]
+pub fn cmd_git_sync(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &GitSyncArgs,
+) -> Result<(), CommandError> {
+    fetch_and_import(ui, command, &args.fetch, true)
+}
@@ -14,10 +14,13 @@
 
 mod abandon;
 mod absorb;
+mod arrange;
 #[cfg(feature = "bench")]
 mod bench;
 mod bisect;
 mod bookmark;
+#[cfg(feature = "git")]
+mod bundle;
 mod commit;
 mod config;
 mod debug;
@@ -41,6 +44,7 @@ mod new;
 mod next;
 mod operation;
 mod parallelize;
+mod patch;
 mod prev;
 mod rebase;
 mod redo;
@@ -93,6 +97,7 @@ const STYLES: Styles = Styles::styled()
 enum Command {
     Abandon(abandon::AbandonArgs),
     Absorb(absorb::AbsorbArgs),
+    Arrange(arrange::ArrangeArgs),
     #[cfg(feature = "bench")]
     #[command(subcommand)]
     Bench(bench::BenchCommand),
@@ -100,6 +105,9 @@ enum Command {
     Bisect(bisect::BisectCommand),
     #[command(subcommand)]
     Bookmark(bookmark::BookmarkCommand),
+    #[cfg(feature = "git")]
+    #[command(subcommand)]
+    Bundle(bundle::BundleCommand),
     Commit(commit::CommitArgs),
     #[command(subcommand)]
     Config(config::ConfigCommand),
@@ -131,6 +139,8 @@ enum Command {
     #[command(visible_alias = "op")]
     Operation(operation::OperationCommand),
     Parallelize(parallelize::ParallelizeArgs),
+    #[command(subcommand)]
+    Patch(patch::PatchCommand),
     Prev(prev::PrevArgs),
     Rebase(rebase::RebaseArgs),
     Redo(redo::RedoArgs),
@@ -170,10 +180,13 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
     match &subcommand {
         Command::Abandon(args) => abandon::cmd_abandon(ui, command_helper, args),
         Command::Absorb(args) => absorb::cmd_absorb(ui, command_helper, args),
+        Command::Arrange(args) => arrange::cmd_arrange(ui, command_helper, args),
         #[cfg(feature = "bench")]
         Command::Bench(args) => bench::cmd_bench(ui, command_helper, args),
         Command::Bisect(args) => bisect::cmd_bisect(ui, command_helper, args),
         Command::Bookmark(args) => bookmark::cmd_bookmark(ui, command_helper, args),
+        #[cfg(feature = "git")]
+        Command::Bundle(args) => bundle::cmd_bundle(ui, command_helper, args),
         Command::Commit(args) => commit::cmd_commit(ui, command_helper, args),
         Command::Config(args) => config::cmd_config(ui, command_helper, args),
         Command::Debug(args) => debug::cmd_debug(ui, command_helper, args),
@@ -197,6 +210,7 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Next(args) => next::cmd_next(ui, command_helper, args),
         Command::Operation(args) => operation::cmd_operation(ui, command_helper, args),
         Command::Parallelize(args) => parallelize::cmd_parallelize(ui, command_helper, args),
+        Command::Patch(args) => patch::cmd_patch(ui, command_helper, args),
         Command::Prev(args) => prev::cmd_prev(ui, command_helper, args),
         Command::Rebase(args) => rebase::cmd_rebase(ui, command_helper, args),
         Command::Redo(args) => redo::cmd_redo(ui, command_helper, args),
@@ -19,9 +19,11 @@ use std::slice;
 use clap_complete::ArgValueCandidates;
 use itertools::Itertools as _;
 use jj_lib::op_walk;
+use jj_lib::transaction;
 use pollster::FutureExt as _;
 
 use crate::cli_util::CommandHelper;
+use crate::cli_util::RevsetCommitResolver;
 use crate::cli_util::short_operation_hash;
 use crate::command_error::CommandError;
 use crate::command_error::cli_error;
@@ -38,13 +40,17 @@ use crate::ui::Ui;
 /// To discard recent operations, use `jj op restore <operation ID>` followed
 /// by `jj op abandon <operation ID>..@-`.
 ///
+/// Any opset expression can be used to select the operations. For example,
+/// `jj op abandon 'description(glob:"snapshot*") ~ @'` abandons snapshot
+/// operations other than the current one.
+///
 /// Previous versions of a change (or predecessors) are also discarded if they
 /// become unreachable from the operation history. The abandoned operations,
 /// commits, and other unreachable objects can later be garbage collected by
 /// using `jj util gc` command.
 #[derive(clap::Args, Clone, Debug)]
 pub struct OperationAbandonArgs {
-    /// The operation or operation range to abandon
+    /// The operations to abandon
     #[arg(add = ArgValueCandidates::new(complete::operations))]
     operation: String,
 }
@@ -66,35 +72,24 @@ pub fn cmd_op_abandon(
         return Err(cli_error("--at-op is not respected"));
     }
     let current_head_ops = op_walk::get_current_head_ops(op_store, op_heads_store.as_ref())?;
-    let resolve_op = |op_str| op_walk::resolve_op_at(op_store, &current_head_ops, op_str);
-    let (abandon_root_op, abandon_head_ops) =
-        if let Some((root_op_str, head_op_str)) = args.operation.split_once("..") {
-            let root_op = if root_op_str.is_empty() {
-                repo_loader.root_operation()
-            } else {
-                resolve_op(root_op_str)?
-            };
-            let head_ops = if head_op_str.is_empty() {
-                current_head_ops.clone()
-            } else {
-                vec![resolve_op(head_op_str)?]
-            };
-            (root_op, head_ops)
-        } else {
-            let op = resolve_op(&args.operation)?;
-            let parent_ops: Vec<_> = op.parents().try_collect()?;
-            let parent_op = match parent_ops.len() {
-                0 => return Err(user_error("Cannot abandon the root operation")),
-                1 => parent_ops.into_iter().next().unwrap(),
-                _ => return Err(user_error("Cannot abandon a merge operation")),
-            };
-            (parent_op, vec![op])
-        };
+    let workspace_env = command.workspace_environment(ui, &workspace)?;
+    let latest_head_op = current_head_ops
+        .iter()
+        .max_by_key(|op| op.metadata().time.end.timestamp)
+        .expect("there should be at least one head operation");
+    let commit_resolver =
+        RevsetCommitResolver::load_at(&workspace_env, repo_loader, latest_head_op);
+    let context = workspace_env.opset_parse_context(Some(&commit_resolver));
+    let abandon_ops =
+        op_walk::resolve_ops_at(op_store, &current_head_ops, &args.operation, &context)?;
 
-    if let Some(op) = abandon_head_ops
+    if abandon_ops
         .iter()
-        .find(|op| current_head_ops.contains(op))
+        .any(|op| op.id() == op_store.root_operation_id())
     {
+        return Err(user_error("Cannot abandon the root operation"));
+    }
+    if let Some(op) = abandon_ops.iter().find(|op| current_head_ops.contains(op)) {
         let mut err = user_error(format!(
             "Cannot abandon the current operation {}",
             short_operation_hash(op.id())
@@ -105,17 +100,23 @@ pub fn cmd_op_abandon(
         return Err(err);
     }
 
-    // Reparent descendants, count the number of abandoned operations.
-    let stats = op_walk::reparent_range(
-        op_store.as_ref(),
-        &abandon_head_ops,
-        &current_head_ops,
-        &abandon_root_op,
-    )?;
+    // Reparent descendants, count the number of abandoned operations. The
+    // rewritten operations are signed again since the old signatures don't
+    // cover the new parents.
+    let stats = {
+        let signer = repo_loader.store().signer();
+        let mut sign_with = transaction::operation_signing_fn(signer, command.settings())?;
+        op_walk::abandon_ops(
+            op_store.as_ref(),
+            &current_head_ops,
+            &abandon_ops,
+            sign_with.as_deref_mut(),
+        )?
+    };
     assert_eq!(
         current_head_ops.len(),
         stats.new_head_ids.len(),
-        "all current_head_ops should be reparented as they aren't included in abandon_head_ops"
+        "all current_head_ops should be reparented as they aren't included in abandon_ops"
     );
     let reparented_head_ops = || iter::zip(&current_head_ops, &stats.new_head_ids);
     if reparented_head_ops().all(|(old, new_id)| old.id() == new_id) {
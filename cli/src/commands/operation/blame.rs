@@ -0,0 +1,226 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::slice;
+
+use clap::ArgGroup;
+use clap_complete::ArgValueCandidates;
+use clap_complete::ArgValueCompleter;
+use itertools::Itertools as _;
+use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
+use jj_lib::object_id::HexPrefix;
+use jj_lib::object_id::PrefixResolution;
+use jj_lib::op_walk;
+use jj_lib::op_walk::OpValueChange;
+use jj_lib::operation::Operation;
+use jj_lib::ref_name::RefNameBuf;
+use jj_lib::repo::Repo as _;
+use jj_lib::view::View;
+
+use super::diff::write_ref_target_summary;
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::command_error::CommandError;
+use crate::command_error::internal_error;
+use crate::complete;
+use crate::formatter::Formatter;
+use crate::formatter::FormatterExt as _;
+use crate::revset_util;
+use crate::templater::TemplateRenderer;
+use crate::ui::Ui;
+
+/// Show operations that changed a bookmark, a tag, or a change
+///
+/// Walks the operation log from the current operation, and shows each
+/// operation that moved the given bookmark or tag, or that created, rewrote,
+/// abandoned, or restored the given change. The new and old targets are shown
+/// below each operation.
+///
+/// Operations that merge concurrent operations aren't shown unless the merge
+/// itself changed the target.
+#[derive(clap::Args, Clone, Debug)]
+#[command(group(ArgGroup::new("target").args(["bookmark", "tag", "revision"]).required(true)))]
+pub struct OperationBlameArgs {
+    /// Show operations that moved the given local bookmark
+    #[arg(
+        long,
+        short,
+        value_name = "BOOKMARK",
+        value_parser = revset_util::parse_bookmark_name,
+        add = ArgValueCandidates::new(complete::local_bookmarks),
+    )]
+    bookmark: Option<RefNameBuf>,
+    /// Show operations that moved the given tag
+    #[arg(
+        long,
+        short,
+        value_name = "TAG",
+        value_parser = revset_util::parse_tag_name,
+        add = ArgValueCandidates::new(complete::local_tags),
+    )]
+    tag: Option<RefNameBuf>,
+    /// Show operations that changed the visible commits of the given change
+    ///
+    /// All commits of the change are followed, including hidden ones. A change
+    /// that is no longer visible can be specified by the ID of one of its
+    /// hidden commits.
+    #[arg(long, short, value_name = "REVSET", add = ArgValueCompleter::new(complete::revset_expression_all))]
+    revision: Option<RevisionArg>,
+    /// Limit number of operations to show
+    #[arg(long, short = 'n')]
+    limit: Option<usize>,
+    /// Render each operation using the given template
+    ///
+    /// You can specify arbitrary template expressions using the
+    /// [built-in keywords]. See [`jj help -k templates`] for more
+    /// information.
+    ///
+    /// [built-in keywords]:
+    ///     https://jj-vcs.github.io/jj/latest/templates/#operation-keywords
+    ///
+    /// [`jj help -k templates`]:
+    ///     https://jj-vcs.github.io/jj/latest/templates/
+    #[arg(long, short = 'T', add = ArgValueCandidates::new(complete::template_aliases))]
+    template: Option<String>,
+}
+
+pub fn cmd_op_blame(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationBlameArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let workspace_env = workspace_command.env();
+    let repo = workspace_command.repo();
+    let settings = workspace_command.settings();
+
+    let template: TemplateRenderer<Operation> = {
+        let text = match &args.template {
+            Some(value) => value.to_owned(),
+            None => settings.get_string("templates.op_blame")?,
+        };
+        workspace_command
+            .parse_operation_template(ui, &text)?
+            .labeled(["op_blame", "operation"])
+    };
+    let id_prefix_context = workspace_env.new_id_prefix_context();
+    let commit_summary_template = {
+        let language = workspace_env.commit_template_language(repo.as_ref(), &id_prefix_context);
+        let text = settings.get_string("templates.commit_summary")?;
+        workspace_env
+            .parse_template(ui, &language, &text)?
+            .labeled(["op_blame", "commit"])
+    };
+
+    let head_ops = slice::from_ref(repo.operation());
+    let limit = args.limit.unwrap_or(usize::MAX);
+    ui.request_pager();
+    let mut formatter = ui.stdout_formatter();
+    let formatter = formatter.as_mut();
+    if args.bookmark.is_some() || args.tag.is_some() {
+        let changes = op_walk::walk_value_changes(head_ops, |view| {
+            let target = match (&args.bookmark, &args.tag) {
+                (Some(name), _) => view.get_local_bookmark(name),
+                (None, Some(name)) => view.get_local_tag(name),
+                (None, None) => unreachable!(),
+            };
+            Ok::<_, CommandError>(target.clone())
+        });
+        for change in changes.take(limit) {
+            let OpValueChange {
+                operation,
+                old_value,
+                new_value,
+            } = change?;
+            template.format(&operation, formatter)?;
+            write_ref_target_summary(
+                formatter,
+                repo.as_ref(),
+                &commit_summary_template,
+                &new_value,
+                true,
+                None,
+            )?;
+            write_ref_target_summary(
+                formatter,
+                repo.as_ref(),
+                &commit_summary_template,
+                &old_value,
+                false,
+                None,
+            )?;
+        }
+    } else if let Some(revision_arg) = &args.revision {
+        let commit = workspace_command.resolve_single_rev(ui, revision_arg)?;
+        // The index contains commits of all ancestor operations, so hidden
+        // commits of the change can be found there.
+        let all_heads = repo.index().all_heads_for_gc()?.collect_vec();
+        let change_id_index = repo.readonly_index().change_id_index(&mut all_heads.iter());
+        let candidate_ids =
+            match change_id_index.resolve_prefix(&HexPrefix::from_id(commit.change_id()))? {
+                PrefixResolution::SingleMatch(ids) => ids,
+                PrefixResolution::NoMatch | PrefixResolution::AmbiguousMatch => {
+                    return Err(internal_error(format!(
+                        "Change {} isn't indexed",
+                        commit.change_id().reverse_hex()
+                    )));
+                }
+            };
+        let visible_ids = |view: &View| -> Result<Vec<CommitId>, CommandError> {
+            Ok(op_walk::visible_commit_ids(
+                repo.as_ref(),
+                view,
+                &candidate_ids,
+            )?)
+        };
+        let changes = op_walk::walk_value_changes(head_ops, visible_ids);
+        for change in changes.take(limit) {
+            let OpValueChange {
+                operation,
+                old_value,
+                new_value,
+            } = change?;
+            template.format(&operation, formatter)?;
+            let added_ids = new_value.iter().filter(|id| !old_value.contains(id));
+            let removed_ids = old_value.iter().filter(|id| !new_value.contains(id));
+            for id in added_ids {
+                let commit = repo.store().get_commit(id)?;
+                write_commit_summary(formatter, &commit_summary_template, &commit, true)?;
+            }
+            for id in removed_ids {
+                let commit = repo.store().get_commit(id)?;
+                write_commit_summary(formatter, &commit_summary_template, &commit, false)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_commit_summary(
+    formatter: &mut dyn Formatter,
+    commit_summary_template: &TemplateRenderer<Commit>,
+    commit: &Commit,
+    added: bool,
+) -> Result<(), CommandError> {
+    if added {
+        write!(formatter.labeled("diff").labeled("added"), "+")?;
+    } else {
+        write!(formatter.labeled("diff").labeled("removed"), "-")?;
+    }
+    write!(formatter, " ")?;
+    commit_summary_template.format(commit, formatter)?;
+    writeln!(formatter)?;
+    Ok(())
+}
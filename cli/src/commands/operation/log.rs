@@ -12,13 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::slice;
 
 use clap_complete::ArgValueCandidates;
 use itertools::Itertools as _;
 use jj_lib::graph::GraphEdge;
+use jj_lib::graph::GraphNode;
 use jj_lib::graph::reverse_graph;
 use jj_lib::op_store::OpStoreError;
+use jj_lib::op_store::OperationId;
 use jj_lib::op_walk;
 use jj_lib::operation::Operation;
 use jj_lib::repo::RepoLoader;
@@ -26,6 +30,7 @@ use jj_lib::repo::RepoLoader;
 use super::diff::show_op_diff;
 use crate::cli_util::CommandHelper;
 use crate::cli_util::LogContentFormat;
+use crate::cli_util::RevsetCommitResolver;
 use crate::cli_util::WorkspaceCommandEnvironment;
 use crate::cli_util::format_template;
 use crate::command_error::CommandError;
@@ -47,6 +52,15 @@ use crate::ui::Ui;
 /// to inspect the current state without mutation.
 #[derive(clap::Args, Clone, Debug)]
 pub struct OperationLogArgs {
+    /// Show only operations matching the given opset expression
+    ///
+    /// For example, `description(glob:"rebase*")` or `touching(xyz)`. See
+    /// [opset expressions] for the syntax.
+    ///
+    /// [opset expressions]:
+    ///     https://jj-vcs.github.io/jj/latest/operation-log/#opset-expressions
+    #[arg(long, value_name = "OPSET")]
+    operations: Option<String>,
     /// Limit number of operations to show
     ///
     /// Applied after operations are reordered topologically, but before being
@@ -196,21 +210,43 @@ fn do_op_log(
         None
     };
 
+    let selected_op_ids: Option<HashSet<OperationId>> = match &args.operations {
+        Some(text) => {
+            // The repo is loaded only if the expression needs to resolve
+            // revsets.
+            let commit_resolver =
+                RevsetCommitResolver::load_at(workspace_env, repo_loader, current_op);
+            let context = workspace_env.opset_parse_context(Some(&commit_resolver));
+            let ops = op_walk::resolve_ops_at(
+                repo_loader.op_store(),
+                slice::from_ref(current_op),
+                text,
+                &context,
+            )?;
+            Some(ops.iter().map(|op| op.id().clone()).collect())
+        }
+        None => None,
+    };
+
     ui.request_pager();
     let mut formatter = ui.stdout_formatter();
     let formatter = formatter.as_mut();
-    let iter =
-        op_walk::walk_ancestors(slice::from_ref(current_op)).take(args.limit.unwrap_or(usize::MAX));
-
-    if !args.no_graph {
-        let mut raw_output = formatter.raw()?;
-        let mut graph = get_graphlog(graph_style, raw_output.as_mut());
-        let iter = iter.map(|op| -> Result<_, OpStoreError> {
+    let iter = op_walk::walk_ancestors(slice::from_ref(current_op));
+    let iter: Box<dyn Iterator<Item = _>> = if let Some(op_ids) = &selected_op_ids {
+        Box::new(filter_op_graph(iter, op_ids)?.into_iter().map(Ok))
+    } else {
+        Box::new(iter.map(|op| -> Result<_, OpStoreError> {
             let op = op?;
             let ids = op.parent_ids();
             let edges = ids.iter().cloned().map(GraphEdge::direct).collect();
             Ok((op, edges))
-        });
+        }))
+    };
+    let iter = iter.take(args.limit.unwrap_or(usize::MAX));
+
+    if !args.no_graph {
+        let mut raw_output = formatter.raw()?;
+        let mut graph = get_graphlog(graph_style, raw_output.as_mut());
         let iter_nodes: Box<dyn Iterator<Item = _>> = if args.reversed {
             Box::new(reverse_graph(iter, Operation::id)?.into_iter().map(Ok))
         } else {
@@ -244,8 +280,8 @@ fn do_op_log(
         } else {
             Box::new(iter)
         };
-        for op in iter {
-            let op = op?;
+        for node in iter {
+            let (op, _edges) = node?;
             with_content_format.write(formatter, |formatter| template.format(&op, formatter))?;
             if let Some(show) = &maybe_show_op_diff {
                 show(ui, formatter, &op, &with_content_format)?;
@@ -255,3 +291,53 @@ fn do_op_log(
 
     Ok(())
 }
+
+/// Filters operations yielded by `iter` to the `selected` ones. Edges of
+/// the selected operations point to their nearest selected ancestors.
+fn filter_op_graph(
+    iter: impl Iterator<Item = Result<Operation, OpStoreError>>,
+    selected: &HashSet<OperationId>,
+) -> Result<Vec<GraphNode<Operation, OperationId>>, OpStoreError> {
+    let all_ops: Vec<Operation> = iter.try_collect()?;
+    // Map each operation to its nearest selected ancestors (or itself),
+    // visiting parents before children.
+    let mut nearest_selected: HashMap<&OperationId, Vec<OperationId>> = HashMap::new();
+    for op in all_ops.iter().rev() {
+        let ids = if selected.contains(op.id()) {
+            vec![op.id().clone()]
+        } else {
+            op.parent_ids()
+                .iter()
+                .flat_map(|id| &nearest_selected[id])
+                .unique()
+                .cloned()
+                .collect()
+        };
+        nearest_selected.insert(op.id(), ids);
+    }
+    let all_edges = all_ops
+        .iter()
+        .filter(|op| selected.contains(op.id()))
+        .map(|op| {
+            op.parent_ids()
+                .iter()
+                .flat_map(|parent_id| match nearest_selected[parent_id].as_slice() {
+                    [] => vec![GraphEdge::missing(parent_id.clone())],
+                    ids => ids
+                        .iter()
+                        .map(|id| {
+                            if id == parent_id {
+                                GraphEdge::direct(id.clone())
+                            } else {
+                                GraphEdge::indirect(id.clone())
+                            }
+                        })
+                        .collect(),
+                })
+                .unique()
+                .collect_vec()
+        })
+        .collect_vec();
+    let selected_ops = all_ops.into_iter().filter(|op| selected.contains(op.id()));
+    Ok(selected_ops.zip(all_edges).collect())
+}
@@ -13,25 +13,43 @@
 // limitations under the License.
 
 mod abandon;
+mod blame;
 mod diff;
 mod log;
+#[cfg(feature = "git")]
+mod pull;
+#[cfg(feature = "git")]
+mod push;
 mod restore;
 pub mod revert;
 mod show;
+mod verify;
 
 use abandon::OperationAbandonArgs;
 use abandon::cmd_op_abandon;
+use blame::OperationBlameArgs;
+use blame::cmd_op_blame;
 use clap::Subcommand;
 use diff::OperationDiffArgs;
 use diff::cmd_op_diff;
 use log::OperationLogArgs;
 use log::cmd_op_log;
+#[cfg(feature = "git")]
+use pull::OperationPullArgs;
+#[cfg(feature = "git")]
+use pull::cmd_op_pull;
+#[cfg(feature = "git")]
+use push::OperationPushArgs;
+#[cfg(feature = "git")]
+use push::cmd_op_push;
 use restore::OperationRestoreArgs;
 use restore::cmd_op_restore;
 use revert::OperationRevertArgs;
 use revert::cmd_op_revert;
 use show::OperationShowArgs;
 use show::cmd_op_show;
+use verify::OperationVerifyArgs;
+use verify::cmd_op_verify;
 
 use crate::cli_util::CommandHelper;
 use crate::command_error::CommandError;
@@ -47,11 +65,17 @@ use crate::ui::Ui;
 #[derive(Subcommand, Clone, Debug)]
 pub enum OperationCommand {
     Abandon(OperationAbandonArgs),
+    Blame(OperationBlameArgs),
     Diff(OperationDiffArgs),
     Log(OperationLogArgs),
+    #[cfg(feature = "git")]
+    Pull(OperationPullArgs),
+    #[cfg(feature = "git")]
+    Push(OperationPushArgs),
     Restore(OperationRestoreArgs),
     Revert(OperationRevertArgs),
     Show(OperationShowArgs),
+    Verify(OperationVerifyArgs),
     // TODO: Delete in jj 0.39.0+
     #[command(hide = true)]
     Undo(OperationRevertArgs),
@@ -64,11 +88,17 @@ pub fn cmd_operation(
 ) -> Result<(), CommandError> {
     match subcommand {
         OperationCommand::Abandon(args) => cmd_op_abandon(ui, command, args),
+        OperationCommand::Blame(args) => cmd_op_blame(ui, command, args),
         OperationCommand::Diff(args) => cmd_op_diff(ui, command, args),
         OperationCommand::Log(args) => cmd_op_log(ui, command, args),
+        #[cfg(feature = "git")]
+        OperationCommand::Pull(args) => cmd_op_pull(ui, command, args),
+        #[cfg(feature = "git")]
+        OperationCommand::Push(args) => cmd_op_push(ui, command, args),
         OperationCommand::Restore(args) => cmd_op_restore(ui, command, args),
         OperationCommand::Revert(args) => cmd_op_revert(ui, command, args),
         OperationCommand::Show(args) => cmd_op_show(ui, command, args),
+        OperationCommand::Verify(args) => cmd_op_verify(ui, command, args),
         OperationCommand::Undo(args) => {
             let cmd = renamed_cmd("op undo", "op revert", cmd_op_revert);
             cmd(ui, command, args)
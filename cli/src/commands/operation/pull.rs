@@ -0,0 +1,126 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+
+use clap_complete::ArgValueCandidates;
+use itertools::Itertools as _;
+use jj_lib::dag_walk;
+use jj_lib::git;
+use jj_lib::operation::Operation;
+use jj_lib::ref_name::RemoteNameBuf;
+use jj_lib::repo::Repo as _;
+use pollster::FutureExt as _;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::command_error::cli_error;
+use crate::commands::git::get_default_push_remote;
+use crate::complete;
+use crate::git_util::with_remote_git_callbacks;
+use crate::ui::Ui;
+
+/// Pull operations from a Git remote
+///
+/// Fetches the operation log published by `jj op push` and adds the remote
+/// operations to the local operation log. If both sides have new operations,
+/// they are merged the same way as concurrent operations.
+///
+/// Operations recorded in a workspace of the same name on another machine will
+/// update the working-copy commit of the local workspace.
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationPullArgs {
+    /// The remote to pull from
+    ///
+    /// This defaults to the `git.push` setting. If that is not configured, and
+    /// if there are multiple remotes, the remote named "origin" will be used.
+    #[arg(long, add = ArgValueCandidates::new(complete::git_remotes))]
+    remote: Option<RemoteNameBuf>,
+}
+
+pub fn cmd_op_pull(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationPullArgs,
+) -> Result<(), CommandError> {
+    // The remote operations are merged with the current head.
+    if command.global_args().at_operation.is_some() {
+        return Err(cli_error("--at-op is not respected"));
+    }
+    let workspace_command = command.workspace_helper(ui)?;
+    let remote = match &args.remote {
+        Some(remote) => remote.clone(),
+        None => get_default_push_remote(ui, &workspace_command)?,
+    };
+    let repo = workspace_command.repo().clone();
+    let git_settings = workspace_command.settings().git_settings()?;
+    let stats = with_remote_git_callbacks(ui, |callbacks| {
+        git::fetch_operations(&repo, &git_settings, &remote, callbacks)
+    })?;
+    let Some(remote_head_id) = stats.remote_head_id else {
+        writeln!(
+            ui.status(),
+            "No operations to pull from {}",
+            remote.as_symbol()
+        )?;
+        return Ok(());
+    };
+
+    let repo_loader = repo.loader();
+    let remote_head = repo_loader.load_operation(&remote_head_id)?;
+    let ancestor_op = dag_walk::closest_common_node_ok(
+        [Ok(repo.operation().clone())],
+        [Ok(remote_head)],
+        |op: &Operation| op.id().clone(),
+        |op: &Operation| op.parents().collect_vec(),
+    )?
+    .expect("operations should share the root operation");
+    if *ancestor_op.id() == remote_head_id {
+        writeln!(ui.status(), "Nothing changed.")?;
+        return Ok(());
+    }
+    // Fast-forward if there are no local operations to merge. Otherwise, the
+    // divergent heads are merged when the repo is loaded.
+    let old_op_ids = if ancestor_op.id() == repo.op_id() {
+        vec![repo.op_id().clone()]
+    } else {
+        vec![]
+    };
+    repo_loader
+        .op_heads_store()
+        .update_op_heads(&old_op_ids, &remote_head_id)
+        .block_on()?;
+    writeln!(
+        ui.status(),
+        "Pulled {} operations from {}",
+        stats.num_imported,
+        remote.as_symbol()
+    )?;
+
+    let old_wc_commit = workspace_command
+        .get_wc_commit_id()
+        .map(|id| repo.store().get_commit(id))
+        .transpose()?;
+    let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    let new_wc_commit = workspace_command
+        .get_wc_commit_id()
+        .map(|id| workspace_command.repo().store().get_commit(id))
+        .transpose()?;
+    if let (Some(old_wc_commit), Some(new_wc_commit)) = (old_wc_commit, new_wc_commit)
+        && old_wc_commit.tree_id() != new_wc_commit.tree_id()
+    {
+        command.recover_stale_working_copy(ui)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+
+use clap_complete::ArgValueCandidates;
+use jj_lib::git;
+use jj_lib::ref_name::RemoteNameBuf;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::short_operation_hash;
+use crate::command_error::CommandError;
+use crate::commands::git::get_default_push_remote;
+use crate::complete;
+use crate::git_util::with_remote_git_callbacks;
+use crate::ui::Ui;
+
+/// Publish the operation log to a Git remote
+///
+/// Stores the operations up to the current operation under `refs/jj/ops/` on
+/// the remote, along with the commits they refer to. This includes hidden
+/// commits, so the undo history can be restored on another machine by using
+/// `jj op pull`.
+///
+/// The push is refused if the remote has operations that haven't been pulled
+/// yet.
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationPushArgs {
+    /// The remote to push to
+    ///
+    /// This defaults to the `git.push` setting. If that is not configured, and
+    /// if there are multiple remotes, the remote named "origin" will be used.
+    #[arg(long, add = ArgValueCandidates::new(complete::git_remotes))]
+    remote: Option<RemoteNameBuf>,
+}
+
+pub fn cmd_op_push(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationPushArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let remote = match &args.remote {
+        Some(remote) => remote.clone(),
+        None => get_default_push_remote(ui, &workspace_command)?,
+    };
+    let repo = workspace_command.repo();
+    let git_settings = workspace_command.settings().git_settings()?;
+    with_remote_git_callbacks(ui, |callbacks| {
+        git::push_operations(repo, &git_settings, &remote, callbacks)
+    })?;
+    writeln!(
+        ui.status(),
+        "Pushed operations up to {} to {}",
+        short_operation_hash(repo.op_id()),
+        remote.as_symbol()
+    )?;
+    Ok(())
+}
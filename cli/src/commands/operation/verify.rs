@@ -0,0 +1,119 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+
+use clap_complete::ArgValueCandidates;
+use jj_lib::op_walk;
+use jj_lib::signing::SigStatus;
+use jj_lib::signing::SignError;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::short_operation_hash;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Verify the cryptographic signatures of operations
+///
+/// Checks that the given operation and all of its ancestors are signed with a
+/// good signature. Operations can be signed by setting
+/// `signing.sign-operations = true`.
+///
+/// Since operations refer to their parents by content hash, a good signature
+/// on an operation also covers the ids of its ancestors. Use `--trusted` to
+/// stop at an operation that is known to be trustworthy, such as the last
+/// operation written before signing was enabled.
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationVerifyArgs {
+    /// The operation to verify along with its ancestors
+    #[arg(default_value = "@", add = ArgValueCandidates::new(complete::operations))]
+    operation: String,
+    /// Stop verification at this operation
+    ///
+    /// The operation and its ancestors are assumed to be trustworthy. Defaults
+    /// to the root operation.
+    #[arg(
+        long,
+        value_name = "OPERATION",
+        add = ArgValueCandidates::new(complete::operations)
+    )]
+    trusted: Option<String>,
+}
+
+pub fn cmd_op_verify(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationVerifyArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    let repo_loader = workspace_command.workspace().repo_loader();
+    let signer = repo_loader.store().signer();
+    let head_op = workspace_command.resolve_single_op(&args.operation)?;
+    let trusted_op = match &args.trusted {
+        Some(op_str) => workspace_command.resolve_single_op(op_str)?,
+        None => repo_loader.root_operation(),
+    };
+
+    let mut num_verified = 0;
+    let mut num_failed = 0;
+    for op in op_walk::walk_ancestors_range(&[head_op], &[trusted_op]) {
+        let op = op?;
+        let problem = match op.verification(signer) {
+            Ok(Some(verification)) => match verification.status {
+                SigStatus::Good => None,
+                SigStatus::Unknown => Some("has a signature that could not be verified"),
+                SigStatus::Bad => Some("has a bad signature"),
+            },
+            Ok(None) => Some("is not signed"),
+            Err(SignError::InvalidSignatureFormat) => Some("has an invalid signature"),
+            Err(err) => return Err(err.into()),
+        };
+        if let Some(problem) = problem {
+            writeln!(
+                ui.warning_default(),
+                "Operation {} {problem}: {}",
+                short_operation_hash(op.id()),
+                op.metadata().description.lines().next().unwrap_or_default()
+            )?;
+            num_failed += 1;
+        } else {
+            num_verified += 1;
+        }
+    }
+
+    if num_failed > 0 {
+        let mut err = user_error(format!(
+            "{num_failed} {operations} could not be verified",
+            operations = if num_failed == 1 {
+                "operation"
+            } else {
+                "operations"
+            }
+        ));
+        err.add_hint("Use `--trusted` to skip operations written before signing was enabled");
+        return Err(err);
+    }
+    writeln!(
+        ui.status(),
+        "Verified {num_verified} {operations}.",
+        operations = if num_verified == 1 {
+            "operation"
+        } else {
+            "operations"
+        }
+    )?;
+    Ok(())
+}
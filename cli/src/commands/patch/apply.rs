@@ -0,0 +1,128 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use clap_complete::ArgValueCompleter;
+use jj_lib::patch::apply_file_patches;
+use jj_lib::patch::parse_patches;
+use jj_lib::repo::Repo as _;
+use pollster::FutureExt as _;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::command_error::CommandError;
+use crate::command_error::user_error_with_message;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Apply patch files as new revisions
+///
+/// The patch files can be in the mbox format of `git format-patch`, or plain
+/// Git-style diffs. Each message is applied as a new revision on top of the
+/// previous one. The author and the description are taken from the message.
+/// The change ID is restored from the `Change-Id` trailer written by `jj patch
+/// export`, unless a visible revision already has that change ID.
+///
+/// If the file content the patch was made against is found by the `index`
+/// line, the patch is merged with the current content. Otherwise, hunks are
+/// located by their context lines. If the changes can't be merged or the
+/// context doesn't match the file content, the file is left in a conflicted
+/// state, which can be resolved later.
+#[derive(clap::Args, Clone, Debug)]
+pub struct PatchApplyArgs {
+    /// The patch files to apply
+    #[arg(required = true, value_hint = clap::ValueHint::FilePath)]
+    paths: Vec<PathBuf>,
+    /// The revision to apply the patches onto
+    #[arg(
+        long,
+        short,
+        default_value = "@",
+        value_name = "REVSET",
+        add = ArgValueCompleter::new(complete::revset_expression_all),
+    )]
+    destination: RevisionArg,
+}
+
+pub fn cmd_patch_apply(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &PatchApplyArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut parent = workspace_command.resolve_single_rev(ui, &args.destination)?;
+    let mut messages = vec![];
+    for path in &args.paths {
+        let data = fs::read(command.cwd().join(path)).map_err(|err| {
+            user_error_with_message(format!("Failed to read {}", path.display()), err)
+        })?;
+        let parsed = parse_patches(&data).map_err(|err| {
+            user_error_with_message(format!("Failed to parse {}", path.display()), err)
+        })?;
+        messages.extend(parsed);
+    }
+
+    let mut tx = workspace_command.start_transaction();
+    let mut new_commits = vec![];
+    for message in messages {
+        if message.files.is_empty() {
+            let subject = message.description.lines().next().unwrap_or_default();
+            writeln!(
+                ui.warning_default(),
+                "Skipping patch without changes: {subject}"
+            )?;
+            continue;
+        }
+        // Don't make the change divergent if it's already in the repo.
+        let change_id = match message.change_id {
+            Some(change_id) if tx.repo().resolve_change_id(&change_id)?.is_none() => {
+                Some(change_id)
+            }
+            _ => None,
+        };
+        let parent_tree = parent.tree()?;
+        let tree_id = apply_file_patches(&parent_tree, &message.files).block_on()?;
+        let mut commit_builder = tx
+            .repo_mut()
+            .new_commit(vec![parent.id().clone()], tree_id)
+            .set_description(message.description);
+        if let Some(author) = message.author {
+            commit_builder = commit_builder.set_author(author);
+        }
+        if let Some(change_id) = change_id {
+            commit_builder = commit_builder.set_change_id(change_id);
+        }
+        let new_commit = commit_builder.write()?;
+        new_commits.push(new_commit.clone());
+        parent = new_commit;
+    }
+    if new_commits.is_empty() {
+        writeln!(ui.status(), "Nothing changed.")?;
+        return Ok(());
+    }
+
+    if let Some(mut formatter) = ui.status_formatter() {
+        writeln!(formatter, "Applied {} patches:", new_commits.len())?;
+        for commit in &new_commits {
+            write!(formatter, "  ")?;
+            tx.write_commit_summary(formatter.as_mut(), commit)?;
+            writeln!(formatter)?;
+        }
+    }
+    tx.finish(ui, format!("apply {} patches", new_commits.len()))?;
+    Ok(())
+}
@@ -0,0 +1,246 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use clap_complete::ArgValueCompleter;
+use indexmap::IndexMap;
+use itertools::Itertools as _;
+use jj_lib::commit::Commit;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::patch::format_patch_header;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPathUiConverter;
+use pollster::FutureExt as _;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_message;
+use crate::complete;
+use crate::diff_util::DiffFormat;
+use crate::diff_util::DiffRenderer;
+use crate::diff_util::UnifiedDiffOptions;
+use crate::formatter::PlainTextFormatter;
+use crate::ui::Ui;
+
+/// Width of the diffstat, which is the same as `git format-patch`.
+const STAT_WIDTH: usize = 72;
+
+/// Export revisions as patch files
+///
+/// One file is written per revision, in the mbox format of `git
+/// format-patch`. The files are named after the first line of the
+/// descriptions, numbered in topological order.
+///
+/// Each patch includes the author, the description, and a `Change-Id` trailer
+/// from which `jj patch apply` restores the change ID.
+#[derive(clap::Args, Clone, Debug)]
+pub struct PatchExportArgs {
+    /// The revisions to export
+    #[arg(
+        long,
+        short,
+        required = true,
+        value_name = "REVSETS",
+        add = ArgValueCompleter::new(complete::revset_expression_all),
+    )]
+    revisions: Vec<RevisionArg>,
+    /// Directory to write the patch files to [default: current directory]
+    #[arg(
+        long,
+        short,
+        value_name = "DIR",
+        value_hint = clap::ValueHint::DirPath,
+        conflicts_with = "stdout",
+    )]
+    output_directory: Option<PathBuf>,
+    /// Write the patches to the standard output as a single mbox file
+    #[arg(long)]
+    stdout: bool,
+    /// Also write a cover letter, which lists the exported revisions
+    #[arg(long)]
+    cover_letter: bool,
+}
+
+pub fn cmd_patch_export(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &PatchExportArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let mut commits: Vec<Commit> = workspace_command
+        .parse_union_revsets(ui, &args.revisions)?
+        .evaluate_to_commits()?
+        .try_collect()?;
+    if commits.is_empty() {
+        writeln!(ui.status(), "No revisions to export.")?;
+        return Ok(());
+    }
+    // Parents first
+    commits.reverse();
+    if let Some(commit) = commits.iter().find(|commit| commit.parent_ids().len() > 1) {
+        return Err(user_error(format!(
+            "Cannot export merge commit {}",
+            commit.id().hex()
+        )));
+    }
+
+    // Paths in patches are relative to the workspace root.
+    let workspace_root = workspace_command.workspace_root().to_owned();
+    let path_converter = RepoPathUiConverter::Fs {
+        cwd: workspace_root.clone(),
+        base: workspace_root,
+    };
+    let repo = workspace_command.repo().as_ref();
+    let marker_style = workspace_command.env().conflict_marker_style();
+    let stat_renderer = DiffRenderer::new(
+        repo,
+        &path_converter,
+        marker_style,
+        vec![DiffFormat::Stat(Box::default())],
+    );
+    let diff_renderer = DiffRenderer::new(
+        repo,
+        &path_converter,
+        marker_style,
+        vec![DiffFormat::Git(Box::new(
+            UnifiedDiffOptions::from_settings(command.settings())?,
+        ))],
+    );
+
+    let numbered = commits.len() > 1 || args.cover_letter;
+    let total = commits.len();
+    let mut patches = vec![];
+    if args.cover_letter {
+        let content = format_cover_letter(&workspace_command, &commits, total);
+        patches.push(("0000-cover-letter.patch".to_owned(), content));
+    }
+    for (index, commit) in commits.iter().enumerate() {
+        let number = index + 1;
+        let subject_prefix = if numbered {
+            format!("PATCH {number}/{total}")
+        } else {
+            "PATCH".to_owned()
+        };
+        let mut content = format_patch_header(
+            commit.id(),
+            Some(commit.change_id()),
+            commit.author(),
+            &subject_prefix,
+            commit.description(),
+        )
+        .into_bytes();
+        let mut formatter = PlainTextFormatter::new(&mut content);
+        stat_renderer
+            .show_patch(ui, &mut formatter, commit, &EverythingMatcher, STAT_WIDTH)
+            .block_on()?;
+        writeln!(formatter)?;
+        diff_renderer
+            .show_patch(ui, &mut formatter, commit, &EverythingMatcher, STAT_WIDTH)
+            .block_on()?;
+        let file_name = format!(
+            "{number:04}-{}.patch",
+            sanitize_subject(commit.description())
+        );
+        patches.push((file_name, content));
+    }
+
+    if args.stdout {
+        let mut stdout = ui.stdout();
+        for (_, content) in &patches {
+            stdout.write_all(content)?;
+            writeln!(stdout)?;
+        }
+        return Ok(());
+    }
+    let output_dir = args.output_directory.clone().unwrap_or_default();
+    let output_dir_path = command.cwd().join(&output_dir);
+    fs::create_dir_all(&output_dir_path).map_err(|err| {
+        user_error_with_message(
+            format!("Failed to create directory {}", output_dir.display()),
+            err,
+        )
+    })?;
+    for (file_name, content) in &patches {
+        let path = output_dir_path.join(file_name);
+        fs::write(&path, content).map_err(|err| {
+            user_error_with_message(format!("Failed to write {}", path.display()), err)
+        })?;
+        writeln!(ui.stdout(), "{}", output_dir.join(file_name).display())?;
+    }
+    Ok(())
+}
+
+/// Formats a cover letter with placeholders for the subject and the
+/// description, followed by the subjects of the patches grouped by author.
+fn format_cover_letter(
+    workspace_command: &WorkspaceCommandHelper,
+    commits: &[Commit],
+    total: usize,
+) -> Vec<u8> {
+    let mut shortlog: IndexMap<&str, Vec<&str>> = IndexMap::new();
+    for commit in commits {
+        let subject = commit.description().lines().next().unwrap_or_default();
+        shortlog
+            .entry(&commit.author().name)
+            .or_default()
+            .push(subject);
+    }
+    let mut description = "*** SUBJECT HERE ***\n\n*** BLURB HERE ***\n".to_owned();
+    for (author, subjects) in shortlog {
+        description.push_str(&format!("\n{author} ({}):\n", subjects.len()));
+        for subject in subjects {
+            description.push_str(&format!("  {subject}\n"));
+        }
+    }
+    let repo = workspace_command.repo();
+    format_patch_header(
+        repo.store().root_commit_id(),
+        None,
+        &workspace_command.settings().signature(),
+        &format!("PATCH 0/{total}"),
+        &description,
+    )
+    .into_bytes()
+}
+
+/// Turns the first line of the description into a file name as `git
+/// format-patch` does.
+fn sanitize_subject(description: &str) -> String {
+    const MAX_LEN: usize = 52;
+    let subject = description.lines().next().unwrap_or_default();
+    let mut name = String::new();
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+        if name.len() >= MAX_LEN {
+            break;
+        }
+    }
+    let name = name.trim_start_matches('.').trim_end_matches(['-', '.']);
+    if name.is_empty() {
+        "patch".to_owned()
+    } else {
+        name.to_owned()
+    }
+}
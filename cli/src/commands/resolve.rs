@@ -12,19 +12,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::num::NonZeroUsize;
+
 use clap_complete::ArgValueCandidates;
 use clap_complete::ArgValueCompleter;
 use itertools::Itertools as _;
+use jj_lib::backend::MergedTreeId;
+use jj_lib::conflicts::try_materialize_file_conflict_value;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::merged_tree::MergedTreeBuilder;
 use jj_lib::object_id::ObjectId as _;
+use jj_lib::repo_path::RepoPath;
+use pollster::FutureExt as _;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
 use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
 use crate::cli_util::print_conflicted_paths;
 use crate::command_error::CommandError;
 use crate::command_error::cli_error;
+use crate::command_error::user_error;
 use crate::complete;
 use crate::formatter::FormatterExt as _;
+use crate::merge_tools::ConflictResolveError;
 use crate::ui::Ui;
 
 /// Resolve conflicted files with an external merge tool
@@ -38,6 +49,9 @@ use crate::ui::Ui;
 /// Note that conflicts can also be resolved without using this command. You may
 /// edit the conflict markers in the conflicted file directly with a text
 /// editor.
+///
+/// Conflicts with more sides can be resolved piecemeal by editing one side at
+/// a time with `--edit-side`. Once all sides agree, the conflict is resolved.
 //  TODOs:
 //   - `jj resolve --editor` to resolve a conflict in the default text editor. Should work for
 //     conflicts with 3+ adds. Useful to resolve conflicts in a commit other than the current one.
@@ -70,6 +84,14 @@ pub(crate) struct ResolveArgs {
         add = ArgValueCandidates::new(complete::merge_editors),
     )]
     tool: Option<String>,
+    /// Edit the given side of the conflicted file in a text editor (starting
+    /// from 1)
+    ///
+    /// The edited contents replace that side of the conflict, and the other
+    /// sides are left unchanged. Sides are numbered as in the conflict
+    /// markers. `jj file show --side` prints a side without editing it.
+    #[arg(long, value_name = "N", conflicts_with_all = ["list", "tool"])]
+    edit_side: Option<NonZeroUsize>,
     /// Only resolve conflicts in these paths. You can use the `--list` argument
     /// to find paths to use here.
     #[arg(
@@ -116,19 +138,39 @@ pub(crate) fn cmd_resolve(
         .map(|(path, _)| path.as_ref())
         .collect_vec();
     workspace_command.check_rewritable([commit.id()])?;
-    let merge_editor = workspace_command.merge_editor(ui, args.tool.as_deref())?;
+    let (new_tree_id, partial_resolution_error, tx_description) = if let Some(side) = args.edit_side
+    {
+        let [repo_path] = repo_paths.as_slice() else {
+            return Err(user_error(format!(
+                "--edit-side requires a single conflicted path, but {} paths have conflicts",
+                repo_paths.len()
+            )));
+        };
+        let Some(new_tree_id) =
+            edit_conflict_side(&workspace_command, &tree, repo_path, side.get())?
+        else {
+            writeln!(ui.status(), "Nothing changed.")?;
+            return Ok(());
+        };
+        let tx_description = format!(
+            "Edit side #{side} of conflict in commit {}",
+            commit.id().hex()
+        );
+        (new_tree_id, None, tx_description)
+    } else {
+        let merge_editor = workspace_command.merge_editor(ui, args.tool.as_deref())?;
+        let (new_tree_id, partial_resolution_error) =
+            merge_editor.edit_files(ui, &tree, &repo_paths)?;
+        let tx_description = format!("Resolve conflicts in commit {}", commit.id().hex());
+        (new_tree_id, partial_resolution_error, tx_description)
+    };
     let mut tx = workspace_command.start_transaction();
-    let (new_tree_id, partial_resolution_error) =
-        merge_editor.edit_files(ui, &tree, &repo_paths)?;
     let new_commit = tx
         .repo_mut()
         .rewrite_commit(&commit)
         .set_tree_id(new_tree_id)
         .write()?;
-    tx.finish(
-        ui,
-        format!("Resolve conflicts in commit {}", commit.id().hex()),
-    )?;
+    tx.finish(ui, tx_description)?;
 
     // Print conflicts that are still present after resolution if the workspace
     // working copy is not at the commit. Otherwise, the conflicting paths will
@@ -151,3 +193,57 @@ pub(crate) fn cmd_resolve(
     }
     Ok(())
 }
+
+/// Opens the given side of a file conflict in the text editor, and replaces it
+/// with the edited contents. Returns `None` if nothing changed.
+fn edit_conflict_side(
+    workspace_command: &WorkspaceCommandHelper,
+    tree: &MergedTree,
+    repo_path: &RepoPath,
+    side: usize,
+) -> Result<Option<MergedTreeId>, CommandError> {
+    let store = tree.store();
+    let conflict = tree.path_value(repo_path)?;
+    let file = try_materialize_file_conflict_value(store, repo_path, &conflict)
+        .block_on()?
+        .ok_or_else(|| ConflictResolveError::NotNormalFiles {
+            path: repo_path.to_owned(),
+            summary: conflict.describe(),
+        })?;
+    let ui_path = workspace_command.format_file_path(repo_path);
+    let (Some(file_id), Some(content)) =
+        (file.ids.get_add(side - 1), file.contents.get_add(side - 1))
+    else {
+        return Err(user_error(format!(
+            "Conflict at '{ui_path}' has no side #{side}"
+        )));
+    };
+    if file_id.is_none() {
+        return Err(user_error(format!(
+            "Cannot edit side #{side} of the conflict at '{ui_path}': the file is absent"
+        )));
+    }
+
+    // Keep the file name so that the editor can detect the file type
+    let suffix = repo_path
+        .split()
+        .map(|(_, name)| format!("-{}", name.as_internal_str()));
+    let text_editor = workspace_command.text_editor()?;
+    let edited = text_editor.edit_str(content, suffix.as_deref())?;
+    if edited.as_bytes() == content.as_slice() {
+        return Ok(None);
+    }
+
+    let new_file_id = store
+        .write_file(repo_path, &mut edited.as_bytes())
+        .block_on()?;
+    let mut new_file_ids = file.ids.clone();
+    *new_file_ids.get_add_mut(side - 1).unwrap() = Some(new_file_id);
+    let new_file_ids = file.unsimplified_ids.update_from_simplified(new_file_ids);
+    let mut tree_builder = MergedTreeBuilder::new(tree.id());
+    tree_builder.set_or_remove(
+        repo_path.to_owned(),
+        conflict.with_new_file_ids(&new_file_ids),
+    );
+    Ok(Some(tree_builder.write_tree(store)?))
+}
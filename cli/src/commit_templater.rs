@@ -34,6 +34,7 @@ use jj_lib::backend::ChangeId;
 use jj_lib::backend::CommitId;
 use jj_lib::backend::TreeValue;
 use jj_lib::commit::Commit;
+use jj_lib::config::ConfigGetError;
 use jj_lib::conflicts;
 use jj_lib::conflicts::ConflictMarkerStyle;
 use jj_lib::copies::CopiesTreeDiffEntry;
@@ -83,6 +84,7 @@ use pollster::FutureExt as _;
 use serde::Serialize as _;
 
 use crate::diff_util;
+use crate::diff_util::DiffHunkSummary;
 use crate::diff_util::DiffStats;
 use crate::formatter::Formatter;
 use crate::operation_templater;
@@ -116,6 +118,7 @@ use crate::templater::Template;
 use crate::templater::TemplateFormatter;
 use crate::templater::TemplatePropertyError;
 use crate::templater::TemplatePropertyExt as _;
+use crate::textconv::TextConverters;
 
 pub trait CommitTemplateLanguageExtension {
     fn build_fn_table<'repo>(&self) -> CommitTemplateBuildFnTable<'repo>;
@@ -137,6 +140,8 @@ pub struct CommitTemplateLanguage<'repo> {
     id_prefix_context: &'repo IdPrefixContext,
     immutable_expression: Arc<UserRevsetExpression>,
     conflict_marker_style: ConflictMarkerStyle,
+    // Config errors are reported only if the diff functions are used.
+    text_converters: Result<TextConverters, Arc<ConfigGetError>>,
     build_fn_table: CommitTemplateBuildFnTable<'repo>,
     keyword_cache: CommitKeywordCache<'repo>,
     cache_extensions: ExtensionsMap,
@@ -154,6 +159,7 @@ impl<'repo> CommitTemplateLanguage<'repo> {
         id_prefix_context: &'repo IdPrefixContext,
         immutable_expression: Arc<UserRevsetExpression>,
         conflict_marker_style: ConflictMarkerStyle,
+        text_converters: Result<TextConverters, ConfigGetError>,
         extensions: &[impl AsRef<dyn CommitTemplateLanguageExtension>],
     ) -> Self {
         let mut build_fn_table = CommitTemplateBuildFnTable::builtin();
@@ -174,11 +180,19 @@ impl<'repo> CommitTemplateLanguage<'repo> {
             id_prefix_context,
             immutable_expression,
             conflict_marker_style,
+            text_converters: text_converters.map_err(Arc::new),
             build_fn_table,
             keyword_cache: CommitKeywordCache::default(),
             cache_extensions,
         }
     }
+
+    fn text_converters(&self, span: pest::Span<'_>) -> TemplateParseResult<TextConverters> {
+        self.text_converters.clone().map_err(|err| {
+            let message = "Failed to load diff settings";
+            TemplateParseError::expression(message, span).with_source(err)
+        })
+    }
 }
 
 impl<'repo> TemplateLanguage<'repo> for CommitTemplateLanguage<'repo> {
@@ -329,6 +343,16 @@ impl<'repo> TemplateLanguage<'repo> for CommitTemplateLanguage<'repo> {
                 let build = template_parser::lookup_method(type_name, table, function)?;
                 build(self, diagnostics, build_ctx, property, function)
             }
+            CommitTemplatePropertyKind::TreeDiffHunk(property) => {
+                let table = &self.build_fn_table.tree_diff_hunk_methods;
+                let build = template_parser::lookup_method(type_name, table, function)?;
+                build(self, diagnostics, build_ctx, property, function)
+            }
+            CommitTemplatePropertyKind::TreeDiffHunkList(property) => {
+                let table = &self.build_fn_table.tree_diff_hunk_list_methods;
+                let build = template_parser::lookup_method(type_name, table, function)?;
+                build(self, diagnostics, build_ctx, property, function)
+            }
             CommitTemplatePropertyKind::TreeEntry(property) => {
                 let table = &self.build_fn_table.tree_entry_methods;
                 let build = template_parser::lookup_method(type_name, table, function)?;
@@ -428,6 +452,8 @@ pub enum CommitTemplatePropertyKind<'repo> {
     TreeDiff(BoxedTemplateProperty<'repo, TreeDiff>),
     TreeDiffEntry(BoxedTemplateProperty<'repo, TreeDiffEntry>),
     TreeDiffEntryList(BoxedTemplateProperty<'repo, Vec<TreeDiffEntry>>),
+    TreeDiffHunk(BoxedTemplateProperty<'repo, DiffHunkSummary>),
+    TreeDiffHunkList(BoxedTemplateProperty<'repo, Vec<DiffHunkSummary>>),
     TreeEntry(BoxedTemplateProperty<'repo, TreeEntry>),
     TreeEntryList(BoxedTemplateProperty<'repo, Vec<TreeEntry>>),
     DiffStats(BoxedTemplateProperty<'repo, DiffStatsFormatted<'repo>>),
@@ -460,6 +486,8 @@ template_builder::impl_property_wrappers!(<'repo> CommitTemplatePropertyKind<'re
     TreeDiff(TreeDiff),
     TreeDiffEntry(TreeDiffEntry),
     TreeDiffEntryList(Vec<TreeDiffEntry>),
+    TreeDiffHunk(DiffHunkSummary),
+    TreeDiffHunkList(Vec<DiffHunkSummary>),
     TreeEntry(TreeEntry),
     TreeEntryList(Vec<TreeEntry>),
     DiffStats(DiffStatsFormatted<'repo>),
@@ -502,6 +530,8 @@ impl<'repo> CoreTemplatePropertyVar<'repo> for CommitTemplatePropertyKind<'repo>
             Self::TreeDiff(_) => "TreeDiff",
             Self::TreeDiffEntry(_) => "TreeDiffEntry",
             Self::TreeDiffEntryList(_) => "List<TreeDiffEntry>",
+            Self::TreeDiffHunk(_) => "TreeDiffHunk",
+            Self::TreeDiffHunkList(_) => "List<TreeDiffHunk>",
             Self::TreeEntry(_) => "TreeEntry",
             Self::TreeEntryList(_) => "List<TreeEntry>",
             Self::DiffStats(_) => "DiffStats",
@@ -538,6 +568,8 @@ impl<'repo> CoreTemplatePropertyVar<'repo> for CommitTemplatePropertyKind<'repo>
             Self::TreeDiff(_) => None,
             Self::TreeDiffEntry(_) => None,
             Self::TreeDiffEntryList(property) => Some(property.map(|l| !l.is_empty()).into_dyn()),
+            Self::TreeDiffHunk(_) => None,
+            Self::TreeDiffHunkList(property) => Some(property.map(|l| !l.is_empty()).into_dyn()),
             Self::TreeEntry(_) => None,
             Self::TreeEntryList(property) => Some(property.map(|l| !l.is_empty()).into_dyn()),
             Self::DiffStats(_) => None,
@@ -599,6 +631,8 @@ impl<'repo> CoreTemplatePropertyVar<'repo> for CommitTemplatePropertyKind<'repo>
             Self::TreeDiff(_) => None,
             Self::TreeDiffEntry(_) => None,
             Self::TreeDiffEntryList(_) => None,
+            Self::TreeDiffHunk(_) => None,
+            Self::TreeDiffHunkList(_) => None,
             Self::TreeEntry(_) => None,
             Self::TreeEntryList(_) => None,
             Self::DiffStats(_) => None,
@@ -633,6 +667,8 @@ impl<'repo> CoreTemplatePropertyVar<'repo> for CommitTemplatePropertyKind<'repo>
             Self::TreeDiff(_) => None,
             Self::TreeDiffEntry(_) => None,
             Self::TreeDiffEntryList(_) => None,
+            Self::TreeDiffHunk(_) => None,
+            Self::TreeDiffHunkList(_) => None,
             Self::TreeEntry(_) => None,
             Self::TreeEntryList(_) => None,
             Self::DiffStats(property) => Some(property.into_template()),
@@ -700,6 +736,8 @@ impl<'repo> CoreTemplatePropertyVar<'repo> for CommitTemplatePropertyKind<'repo>
             (Self::TreeDiff(_), _) => None,
             (Self::TreeDiffEntry(_), _) => None,
             (Self::TreeDiffEntryList(_), _) => None,
+            (Self::TreeDiffHunk(_), _) => None,
+            (Self::TreeDiffHunkList(_), _) => None,
             (Self::TreeEntry(_), _) => None,
             (Self::TreeEntryList(_), _) => None,
             (Self::DiffStats(_), _) => None,
@@ -740,6 +778,8 @@ impl<'repo> CoreTemplatePropertyVar<'repo> for CommitTemplatePropertyKind<'repo>
             (Self::TreeDiff(_), _) => None,
             (Self::TreeDiffEntry(_), _) => None,
             (Self::TreeDiffEntryList(_), _) => None,
+            (Self::TreeDiffHunk(_), _) => None,
+            (Self::TreeDiffHunkList(_), _) => None,
             (Self::TreeEntry(_), _) => None,
             (Self::TreeEntryList(_), _) => None,
             (Self::DiffStats(_), _) => None,
@@ -775,6 +815,8 @@ pub struct CommitTemplateBuildFnTable<'repo> {
     pub tree_diff_methods: CommitTemplateBuildMethodFnMap<'repo, TreeDiff>,
     pub tree_diff_entry_methods: CommitTemplateBuildMethodFnMap<'repo, TreeDiffEntry>,
     pub tree_diff_entry_list_methods: CommitTemplateBuildMethodFnMap<'repo, Vec<TreeDiffEntry>>,
+    pub tree_diff_hunk_methods: CommitTemplateBuildMethodFnMap<'repo, DiffHunkSummary>,
+    pub tree_diff_hunk_list_methods: CommitTemplateBuildMethodFnMap<'repo, Vec<DiffHunkSummary>>,
     pub tree_entry_methods: CommitTemplateBuildMethodFnMap<'repo, TreeEntry>,
     pub tree_entry_list_methods: CommitTemplateBuildMethodFnMap<'repo, Vec<TreeEntry>>,
     pub diff_stats_methods: CommitTemplateBuildMethodFnMap<'repo, DiffStats>,
@@ -804,6 +846,8 @@ impl CommitTemplateBuildFnTable<'_> {
             tree_diff_methods: HashMap::new(),
             tree_diff_entry_methods: HashMap::new(),
             tree_diff_entry_list_methods: HashMap::new(),
+            tree_diff_hunk_methods: HashMap::new(),
+            tree_diff_hunk_list_methods: HashMap::new(),
             tree_entry_methods: HashMap::new(),
             tree_entry_list_methods: HashMap::new(),
             diff_stats_methods: HashMap::new(),
@@ -832,6 +876,8 @@ impl CommitTemplateBuildFnTable<'_> {
             tree_diff_methods,
             tree_diff_entry_methods,
             tree_diff_entry_list_methods,
+            tree_diff_hunk_methods,
+            tree_diff_hunk_list_methods,
             tree_entry_methods,
             tree_entry_list_methods,
             diff_stats_methods,
@@ -869,6 +915,11 @@ impl CommitTemplateBuildFnTable<'_> {
             &mut self.tree_diff_entry_list_methods,
             tree_diff_entry_list_methods,
         );
+        merge_fn_map(&mut self.tree_diff_hunk_methods, tree_diff_hunk_methods);
+        merge_fn_map(
+            &mut self.tree_diff_hunk_list_methods,
+            tree_diff_hunk_list_methods,
+        );
         merge_fn_map(&mut self.tree_entry_methods, tree_entry_methods);
         merge_fn_map(&mut self.tree_entry_list_methods, tree_entry_list_methods);
         merge_fn_map(&mut self.diff_stats_methods, diff_stats_methods);
@@ -900,6 +951,8 @@ impl CommitTemplateBuildFnTable<'_> {
             tree_diff_methods: builtin_tree_diff_methods(),
             tree_diff_entry_methods: builtin_tree_diff_entry_methods(),
             tree_diff_entry_list_methods: template_builder::builtin_unformattable_list_methods(),
+            tree_diff_hunk_methods: builtin_tree_diff_hunk_methods(),
+            tree_diff_hunk_list_methods: template_builder::builtin_unformattable_list_methods(),
             tree_entry_methods: builtin_tree_entry_methods(),
             tree_entry_list_methods: template_builder::builtin_unformattable_list_methods(),
             diff_stats_methods: builtin_diff_stats_methods(),
@@ -2187,6 +2240,48 @@ fn builtin_tree_diff_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, T
             Ok(out_property.into_dyn_wrapped())
         },
     );
+    map.insert(
+        "hunks",
+        |language, diagnostics, build_ctx, self_property, function| {
+            let ([], [context_node]) = function.expect_arguments()?;
+            let context_property = context_node
+                .map(|node| {
+                    template_builder::expect_usize_expression(
+                        language,
+                        diagnostics,
+                        build_ctx,
+                        node,
+                    )
+                })
+                .transpose()?;
+            let options = diff_util::UnifiedDiffOptions::from_settings(language.settings())
+                .map_err(|err| {
+                    let message = "Failed to load diff settings";
+                    TemplateParseError::expression(message, function.name_span).with_source(err)
+                })?;
+            let text_converters = language.text_converters(function.name_span)?;
+            let conflict_marker_style = language.conflict_marker_style;
+            let out_property =
+                (self_property, context_property).and_then(move |(diff, context)| {
+                    let mut options = options.clone();
+                    if let Some(context) = context {
+                        options.context = context;
+                    }
+                    let store = diff.from_tree.store();
+                    let tree_diff = diff.diff_stream();
+                    let hunks = diff_util::diff_hunk_summaries(
+                        store,
+                        &text_converters,
+                        tree_diff,
+                        &options,
+                        conflict_marker_style,
+                    )
+                    .block_on()?;
+                    Ok(hunks)
+                });
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
     map.insert(
         "color_words",
         |language, diagnostics, build_ctx, self_property, function| {
@@ -2207,6 +2302,7 @@ fn builtin_tree_diff_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, T
                     let message = "Failed to load diff settings";
                     TemplateParseError::expression(message, function.name_span).with_source(err)
                 })?;
+            let text_converters = language.text_converters(function.name_span)?;
             let conflict_marker_style = language.conflict_marker_style;
             let template = (self_property, context_property)
                 .map(move |(diff, context)| {
@@ -2214,10 +2310,12 @@ fn builtin_tree_diff_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, T
                     if let Some(context) = context {
                         options.context = context;
                     }
+                    let text_converters = text_converters.clone();
                     diff.into_formatted(move |formatter, store, tree_diff| {
                         diff_util::show_color_words_diff(
                             formatter,
                             store,
+                            &text_converters,
                             tree_diff,
                             path_converter,
                             &options,
@@ -2249,6 +2347,7 @@ fn builtin_tree_diff_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, T
                     let message = "Failed to load diff settings";
                     TemplateParseError::expression(message, function.name_span).with_source(err)
                 })?;
+            let text_converters = language.text_converters(function.name_span)?;
             let conflict_marker_style = language.conflict_marker_style;
             let template = (self_property, context_property)
                 .map(move |(diff, context)| {
@@ -2256,10 +2355,12 @@ fn builtin_tree_diff_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, T
                     if let Some(context) = context {
                         options.context = context;
                     }
+                    let text_converters = text_converters.clone();
                     diff.into_formatted(move |formatter, store, tree_diff| {
                         diff_util::show_git_diff(
                             formatter,
                             store,
+                            &text_converters,
                             tree_diff,
                             &options,
                             conflict_marker_style,
@@ -2288,13 +2389,20 @@ fn builtin_tree_diff_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, T
             let path_converter = language.path_converter;
             // No user configuration exists for diff stat.
             let options = diff_util::DiffStatOptions::default();
+            let text_converters = language.text_converters(function.name_span)?;
             let conflict_marker_style = language.conflict_marker_style;
             // TODO: cache and reuse stats within the current evaluation?
             let out_property = (self_property, width_property).and_then(move |(diff, width)| {
                 let store = diff.from_tree.store();
                 let tree_diff = diff.diff_stream();
-                let stats = DiffStats::calculate(store, tree_diff, &options, conflict_marker_style)
-                    .block_on()?;
+                let stats = DiffStats::calculate(
+                    store,
+                    &text_converters,
+                    tree_diff,
+                    &options,
+                    conflict_marker_style,
+                )
+                .block_on()?;
                 Ok(DiffStatsFormatted {
                     stats,
                     path_converter,
@@ -2401,6 +2509,66 @@ fn builtin_tree_diff_entry_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'r
     map
 }
 
+fn builtin_tree_diff_hunk_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, DiffHunkSummary>
+{
+    // Not using maplit::hashmap!{} or custom declarative macro here because
+    // code completion inside macro is quite restricted.
+    let mut map = CommitTemplateBuildMethodFnMap::<DiffHunkSummary>::new();
+    map.insert(
+        "path",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|hunk| hunk.path);
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map.insert(
+        "old_start",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.and_then(|hunk| Ok(i64::try_from(hunk.left_line_range.start + 1)?));
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map.insert(
+        "old_lines",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.and_then(|hunk| Ok(i64::try_from(hunk.left_line_range.len())?));
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map.insert(
+        "new_start",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.and_then(|hunk| Ok(i64::try_from(hunk.right_line_range.start + 1)?));
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map.insert(
+        "new_lines",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.and_then(|hunk| Ok(i64::try_from(hunk.right_line_range.len())?));
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map.insert(
+        "moved",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|hunk| hunk.moved);
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map
+}
+
 /// [`MergedTree`] entry.
 #[derive(Clone, Debug)]
 pub struct TreeEntry {
@@ -2774,6 +2942,7 @@ mod tests {
                     path_converter: &self.path_converter,
                     workspace_name: self.test_workspace.workspace.workspace_name(),
                 }),
+                trust_policy: None,
             };
             let mut language = CommitTemplateLanguage::new(
                 self.test_workspace.repo.as_ref(),
@@ -2783,6 +2952,7 @@ mod tests {
                 &self.id_prefix_context,
                 self.immutable_expression.clone(),
                 ConflictMarkerStyle::Diff,
+                Ok(TextConverters::default()),
                 &[] as &[Box<dyn CommitTemplateLanguageExtension>],
             );
             // Not using .extend() to infer lifetime of f
@@ -19,14 +19,17 @@ use std::iter;
 use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use bstr::BStr;
 use bstr::BString;
+use bstr::ByteSlice as _;
 use clap_complete::ArgValueCandidates;
 use futures::StreamExt as _;
 use futures::TryStreamExt as _;
 use futures::executor::block_on_stream;
 use futures::stream::BoxStream;
+use futures::try_join;
 use itertools::Itertools as _;
 use jj_lib::backend::BackendError;
 use jj_lib::backend::BackendResult;
@@ -50,11 +53,18 @@ use jj_lib::diff::DiffHunk;
 use jj_lib::diff::DiffHunkKind;
 use jj_lib::diff_presentation::DiffTokenType;
 use jj_lib::diff_presentation::FileContent;
+use jj_lib::diff_presentation::FunctionHeaders;
+use jj_lib::diff_presentation::FunctionMatcher;
 use jj_lib::diff_presentation::LineCompareMode;
 use jj_lib::diff_presentation::diff_by_line;
 use jj_lib::diff_presentation::file_content_for_diff;
+use jj_lib::diff_presentation::moved::FileMovedLines;
+use jj_lib::diff_presentation::moved::MovedLineDetector;
+use jj_lib::diff_presentation::moved::MovedLines;
 use jj_lib::diff_presentation::unified::DiffLineType;
+use jj_lib::diff_presentation::unified::GitDiffPart;
 use jj_lib::diff_presentation::unified::UnifiedDiffError;
+use jj_lib::diff_presentation::unified::UnifiedDiffHunk;
 use jj_lib::diff_presentation::unified::git_diff_part;
 use jj_lib::diff_presentation::unified::unified_diff_hunks;
 use jj_lib::diff_presentation::unzip_diff_hunks_to_lines;
@@ -63,6 +73,9 @@ use jj_lib::files::ConflictDiffHunk;
 use jj_lib::files::DiffLineHunkSide;
 use jj_lib::files::DiffLineIterator;
 use jj_lib::files::DiffLineNumber;
+use jj_lib::fileset;
+use jj_lib::fileset::FilesetDiagnostics;
+use jj_lib::fileset::FilesetExpression;
 use jj_lib::matchers::Matcher;
 use jj_lib::merge::Diff;
 use jj_lib::merge::Merge;
@@ -72,6 +85,7 @@ use jj_lib::merged_tree::MergedTree;
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::InvalidRepoPathError;
 use jj_lib::repo_path::RepoPath;
+use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::repo_path::RepoPathUiConverter;
 use jj_lib::rewrite::rebase_to_dest_parent;
 use jj_lib::settings::UserSettings;
@@ -79,6 +93,7 @@ use jj_lib::store::Store;
 use pollster::FutureExt as _;
 use thiserror::Error;
 use tracing::instrument;
+use unicode_width::UnicodeWidthChar as _;
 use unicode_width::UnicodeWidthStr as _;
 
 use crate::command_error::CommandError;
@@ -96,6 +111,7 @@ use crate::merge_tools::invoke_external_diff;
 use crate::merge_tools::new_utf8_temp_dir;
 use crate::templater::TemplateRenderer;
 use crate::text_util;
+use crate::textconv::TextConverters;
 use crate::ui::Ui;
 
 #[derive(clap::Args, Clone, Debug)]
@@ -142,6 +158,14 @@ pub struct DiffFormatArgs {
     /// Number of lines of context to show
     #[arg(long)]
     context: Option<usize>,
+    /// Show whole functions as context
+    ///
+    /// Functions are detected by the patterns in the `diff.functions` config.
+    #[arg(long)]
+    function_context: bool,
+    /// Highlight lines moved within or across files
+    #[arg(long)]
+    color_moved: bool,
 
     // Short flags are set by command to avoid future conflicts.
     /// Ignore whitespace when comparing lines.
@@ -161,6 +185,8 @@ pub enum DiffFormat {
     NameOnly,
     Git(Box<UnifiedDiffOptions>),
     ColorWords(Box<ColorWordsDiffOptions>),
+    SideBySide(Box<SideBySideDiffOptions>),
+    Json(Box<JsonDiffOptions>),
     Tool(Box<ExternalMergeTool>),
 }
 
@@ -172,6 +198,8 @@ enum BuiltinFormatKind {
     NameOnly,
     Git,
     ColorWords,
+    SideBySide,
+    Json,
 }
 
 impl BuiltinFormatKind {
@@ -185,6 +213,8 @@ impl BuiltinFormatKind {
         Self::NameOnly,
         Self::Git,
         Self::ColorWords,
+        Self::SideBySide,
+        Self::Json,
     ];
 
     fn from_name(name: &str) -> Result<Self, String> {
@@ -195,6 +225,8 @@ impl BuiltinFormatKind {
             "name-only" => Ok(Self::NameOnly),
             "git" => Ok(Self::Git),
             "color-words" => Ok(Self::ColorWords),
+            "side-by-side" => Ok(Self::SideBySide),
+            "json" => Ok(Self::Json),
             _ => Err(format!("Invalid builtin diff format: {name}")),
         }
     }
@@ -226,7 +258,7 @@ impl BuiltinFormatKind {
     fn is_short(self) -> bool {
         match self {
             Self::Summary | Self::Stat | Self::Types | Self::NameOnly => true,
-            Self::Git | Self::ColorWords => false,
+            Self::Git | Self::ColorWords | Self::SideBySide | Self::Json => false,
         }
     }
 
@@ -238,6 +270,8 @@ impl BuiltinFormatKind {
             Self::NameOnly => "name-only",
             Self::Git => "git",
             Self::ColorWords => "color-words",
+            Self::SideBySide => "side-by-side",
+            Self::Json => "json",
         }
     }
 
@@ -265,6 +299,16 @@ impl BuiltinFormatKind {
                 options.merge_args(args);
                 Ok(DiffFormat::ColorWords(Box::new(options)))
             }
+            Self::SideBySide => {
+                let mut options = SideBySideDiffOptions::from_settings(settings)?;
+                options.merge_args(args);
+                Ok(DiffFormat::SideBySide(Box::new(options)))
+            }
+            Self::Json => {
+                let mut options = JsonDiffOptions::from_settings(settings)?;
+                options.merge_args(args);
+                Ok(DiffFormat::Json(Box::new(options)))
+            }
         }
     }
 }
@@ -421,6 +465,7 @@ pub struct DiffRenderer<'a> {
     path_converter: &'a RepoPathUiConverter,
     conflict_marker_style: ConflictMarkerStyle,
     formats: Vec<DiffFormat>,
+    text_converters: TextConverters,
 }
 
 impl<'a> DiffRenderer<'a> {
@@ -435,9 +480,16 @@ impl<'a> DiffRenderer<'a> {
             path_converter,
             conflict_marker_style,
             formats,
+            text_converters: TextConverters::default(),
         }
     }
 
+    /// Converts file contents by the `text_converters` before diffing.
+    pub fn with_text_converters(mut self, text_converters: TextConverters) -> Self {
+        self.text_converters = text_converters;
+        self
+    }
+
     /// Generates diff between `trees`.
     pub async fn show_diff(
         &self,
@@ -464,6 +516,7 @@ impl<'a> DiffRenderer<'a> {
     ) -> Result<(), DiffRenderError> {
         let store = self.repo.store();
         let path_converter = self.path_converter;
+        let text_converters = &self.text_converters;
         for format in &self.formats {
             match format {
                 DiffFormat::Summary => {
@@ -474,9 +527,14 @@ impl<'a> DiffRenderer<'a> {
                 DiffFormat::Stat(options) => {
                     let tree_diff =
                         from_tree.diff_stream_with_copies(to_tree, matcher, copy_records);
-                    let stats =
-                        DiffStats::calculate(store, tree_diff, options, self.conflict_marker_style)
-                            .block_on()?;
+                    let stats = DiffStats::calculate(
+                        store,
+                        text_converters,
+                        tree_diff,
+                        options,
+                        self.conflict_marker_style,
+                    )
+                    .block_on()?;
                     show_diff_stats(formatter, &stats, path_converter, width)?;
                 }
                 DiffFormat::Types => {
@@ -495,6 +553,7 @@ impl<'a> DiffRenderer<'a> {
                     show_git_diff(
                         formatter,
                         store,
+                        text_converters,
                         tree_diff,
                         options,
                         self.conflict_marker_style,
@@ -507,10 +566,39 @@ impl<'a> DiffRenderer<'a> {
                     show_color_words_diff(
                         formatter,
                         store,
+                        text_converters,
+                        tree_diff,
+                        path_converter,
+                        options,
+                        self.conflict_marker_style,
+                    )
+                    .await?;
+                }
+                DiffFormat::SideBySide(options) => {
+                    let tree_diff =
+                        from_tree.diff_stream_with_copies(to_tree, matcher, copy_records);
+                    show_side_by_side_diff(
+                        formatter,
+                        store,
+                        text_converters,
                         tree_diff,
                         path_converter,
                         options,
                         self.conflict_marker_style,
+                        width,
+                    )
+                    .await?;
+                }
+                DiffFormat::Json(options) => {
+                    let tree_diff =
+                        from_tree.diff_stream_with_copies(to_tree, matcher, copy_records);
+                    show_json_diff(
+                        formatter,
+                        store,
+                        text_converters,
+                        tree_diff,
+                        options,
+                        self.conflict_marker_style,
                     )
                     .await?;
                 }
@@ -555,6 +643,7 @@ impl<'a> DiffRenderer<'a> {
         &self,
         formatter: &mut dyn Formatter,
         [from_description, to_description]: [&Merge<&str>; 2],
+        width: usize,
     ) -> Result<(), DiffRenderError> {
         if from_description == to_description {
             return Ok(());
@@ -589,9 +678,25 @@ impl<'a> DiffRenderer<'a> {
                         formatter,
                         [from_description, to_description],
                         options,
+                        None,
+                        None,
                         &materialize_options,
                     )?;
                 }
+                DiffFormat::SideBySide(options) => {
+                    writeln!(formatter.labeled("header"), "Modified commit description:")?;
+                    let [left, right] = [from_description, to_description]
+                        .map(|description| materialize_text(description, &materialize_options));
+                    show_side_by_side_diff_hunks(
+                        formatter,
+                        [left.as_ref(), right.as_ref()],
+                        options,
+                        width,
+                    )?;
+                }
+                DiffFormat::Json(_) => {
+                    // TODO: add description changes to the JSON document?
+                }
                 DiffFormat::Tool(_) => {
                     // TODO: materialize commit description as file?
                 }
@@ -628,7 +733,11 @@ impl<'a> DiffRenderer<'a> {
         let from_tree = rebase_to_dest_parent(self.repo, from_commits, to_commit)?;
         let to_tree = to_commit.tree_async().await?;
         let copy_records = CopyRecords::default(); // TODO
-        self.show_diff_commit_descriptions(*formatter, [&from_description, &to_description])?;
+        self.show_diff_commit_descriptions(
+            *formatter,
+            [&from_description, &to_description],
+            width,
+        )?;
         self.show_diff_trees(
             ui,
             *formatter,
@@ -710,12 +819,99 @@ impl LineDiffOptions {
     }
 }
 
+/// Patterns to detect functions in files, configured by the `diff.functions`
+/// table.
+#[derive(Clone, Debug, Default)]
+pub struct DiffFunctionRules {
+    /// Rules sorted by name. The first rule matching a file is used.
+    rules: Vec<DiffFunctionRule>,
+}
+
+#[derive(Clone, Debug)]
+struct DiffFunctionRule {
+    name: String,
+    file_matcher: Arc<dyn Matcher>,
+    function_matcher: FunctionMatcher,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawDiffFunctionRule {
+    patterns: Vec<String>,
+    regex: String,
+}
+
+impl DiffFunctionRules {
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        let rules = settings
+            .table_keys("diff.functions")
+            .sorted()
+            .map(|name| {
+                let to_config_err =
+                    |error: Box<dyn std::error::Error + Send + Sync>| ConfigGetError::Type {
+                        name: format!("diff.functions.{name}"),
+                        error,
+                        source_path: None,
+                    };
+                let rule: RawDiffFunctionRule = settings.get(["diff", "functions", name])?;
+                let path_converter = RepoPathUiConverter::Fs {
+                    cwd: "".into(),
+                    base: "".into(),
+                };
+                let expressions: Vec<_> = rule
+                    .patterns
+                    .iter()
+                    .map(|text| {
+                        fileset::parse(&mut FilesetDiagnostics::new(), text, &path_converter)
+                    })
+                    .try_collect()
+                    .map_err(|err| to_config_err(err.into()))?;
+                let function_matcher =
+                    FunctionMatcher::new(&rule.regex).map_err(|err| to_config_err(err.into()))?;
+                Ok(DiffFunctionRule {
+                    name: name.to_owned(),
+                    file_matcher: FilesetExpression::union_all(expressions)
+                        .to_matcher()
+                        .into(),
+                    function_matcher,
+                })
+            })
+            .try_collect()?;
+        Ok(Self { rules })
+    }
+
+    /// Returns the matcher of function headers for the file at `path`.
+    pub fn matcher_for(&self, path: &RepoPath) -> Option<&FunctionMatcher> {
+        self.rules
+            .iter()
+            .find(|rule| rule.file_matcher.matches(path))
+            .map(|rule| &rule.function_matcher)
+    }
+}
+
+impl PartialEq for DiffFunctionRules {
+    fn eq(&self, other: &Self) -> bool {
+        // File matchers are derived from the names in the same config.
+        self.rules.len() == other.rules.len()
+            && iter::zip(&self.rules, &other.rules)
+                .all(|(a, b)| a.name == b.name && a.function_matcher == b.function_matcher)
+    }
+}
+
+impl Eq for DiffFunctionRules {}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ColorWordsDiffOptions {
     /// How conflicts are processed and rendered.
     pub conflict: ConflictDiffMethod,
     /// Number of context lines to show.
     pub context: usize,
+    /// Whether to show whole functions as context.
+    pub function_context: bool,
+    /// Patterns to detect functions.
+    pub functions: DiffFunctionRules,
+    /// Whether to highlight moved lines.
+    pub color_moved: bool,
     /// How lines are tokenized and compared.
     pub line_diff: LineDiffOptions,
     /// Maximum number of removed/added word alternation to inline.
@@ -738,6 +934,9 @@ impl ColorWordsDiffOptions {
         Ok(Self {
             conflict: settings.get("diff.color-words.conflict")?,
             context: settings.get("diff.color-words.context")?,
+            function_context: settings.get("diff.color-words.function-context")?,
+            functions: DiffFunctionRules::from_settings(settings)?,
+            color_moved: settings.get("diff.color-words.color-moved")?,
             line_diff: LineDiffOptions::default(),
             max_inline_alternation,
         })
@@ -747,6 +946,12 @@ impl ColorWordsDiffOptions {
         if let Some(context) = args.context {
             self.context = context;
         }
+        if args.function_context {
+            self.function_context = true;
+        }
+        if args.color_moved {
+            self.color_moved = true;
+        }
         self.line_diff.merge_args(args);
     }
 }
@@ -755,13 +960,23 @@ fn show_color_words_diff_hunks<T: AsRef<[u8]>>(
     formatter: &mut dyn Formatter,
     [lefts, rights]: [&Merge<T>; 2],
     options: &ColorWordsDiffOptions,
+    function_matcher: Option<&FunctionMatcher>,
+    moved_lines: Option<&FileMovedLines>,
     materialize_options: &ConflictMaterializeOptions,
 ) -> io::Result<()> {
     let line_number = DiffLineNumber { left: 1, right: 1 };
     let labels = ["removed", "added"];
     if let (Some(left), Some(right)) = (lefts.as_resolved(), rights.as_resolved()) {
         let contents = [left, right].map(BStr::new);
-        show_color_words_resolved_hunks(formatter, contents, line_number, labels, options)?;
+        show_color_words_resolved_hunks(
+            formatter,
+            contents,
+            line_number,
+            labels,
+            options,
+            function_matcher,
+            moved_lines,
+        )?;
         return Ok(());
     }
     match options.conflict {
@@ -769,7 +984,15 @@ fn show_color_words_diff_hunks<T: AsRef<[u8]>>(
             let left = materialize_merge_result_to_bytes(lefts, materialize_options);
             let right = materialize_merge_result_to_bytes(rights, materialize_options);
             let contents = [&left, &right].map(BStr::new);
-            show_color_words_resolved_hunks(formatter, contents, line_number, labels, options)?;
+            show_color_words_resolved_hunks(
+                formatter,
+                contents,
+                line_number,
+                labels,
+                options,
+                function_matcher,
+                None,
+            )?;
         }
         ConflictDiffMethod::Pair => {
             let lefts = files::merge(lefts, &materialize_options.merge);
@@ -817,6 +1040,7 @@ fn show_color_words_conflict_hunks(
                     options,
                     num_after,
                     num_before,
+                    None,
                 )?;
                 contexts.clear();
                 emitted = true;
@@ -824,7 +1048,14 @@ fn show_color_words_conflict_hunks(
                     (hunk.lefts.as_resolved(), hunk.rights.as_resolved())
                 {
                     let contents = [left, right];
-                    show_color_words_diff_lines(formatter, contents, line_number, labels, options)?
+                    show_color_words_diff_lines(
+                        formatter,
+                        contents,
+                        line_number,
+                        labels,
+                        options,
+                        None,
+                    )?
                 } else {
                     show_color_words_unresolved_hunk(
                         formatter,
@@ -848,6 +1079,7 @@ fn show_color_words_conflict_hunks(
         options,
         num_after,
         num_before,
+        None,
     )
 }
 
@@ -898,8 +1130,15 @@ fn show_color_words_unresolved_hunk(
             false => [label2, label1],
         };
         // Individual hunk pair may be largely the same, so diff it again.
-        let new_line_number =
-            show_color_words_resolved_hunks(formatter, contents, line_number, labels, options)?;
+        let new_line_number = show_color_words_resolved_hunks(
+            formatter,
+            contents,
+            line_number,
+            labels,
+            options,
+            None,
+            None,
+        )?;
         // Take max to assign unique line numbers to trailing hunks. The line
         // numbers can't be real anyway because preceding conflict hunks might
         // have been resolved.
@@ -917,8 +1156,22 @@ fn show_color_words_resolved_hunks(
     mut line_number: DiffLineNumber,
     labels: [&str; 2],
     options: &ColorWordsDiffOptions,
+    function_matcher: Option<&FunctionMatcher>,
+    moved_lines: Option<&FileMovedLines>,
 ) -> io::Result<DiffLineNumber> {
     let line_diff = diff_by_line(contents, &options.line_diff.compare_mode);
+    let function_headers = function_matcher.map(|matcher| matcher.find_headers(contents[0]));
+    // Returns the numbers of context lines after and before the changes.
+    let context_sizes = |context: Option<[&BStr; 2]>| match (function_matcher, context) {
+        (Some(matcher), Some([_, right])) if options.function_context => {
+            let (num_leading, num_trailing) = matcher.context_lines(right);
+            (
+                max(options.context, num_leading),
+                max(options.context, num_trailing),
+            )
+        }
+        _ => (options.context, options.context),
+    };
     // Matching entries shouldn't appear consecutively in diff of two inputs.
     let mut context: Option<[&BStr; 2]> = None;
     let mut emitted = false;
@@ -930,8 +1183,8 @@ fn show_color_words_resolved_hunks(
                 context = Some(hunk_contents);
             }
             DiffHunkKind::Different => {
-                let num_after = if emitted { options.context } else { 0 };
-                let num_before = options.context;
+                let (num_after, num_before) = context_sizes(context);
+                let num_after = if emitted { num_after } else { 0 };
                 line_number = show_color_words_context_lines(
                     formatter,
                     context.as_slice(),
@@ -940,6 +1193,7 @@ fn show_color_words_resolved_hunks(
                     options,
                     num_after,
                     num_before,
+                    function_headers.as_ref(),
                 )?;
                 context = None;
                 emitted = true;
@@ -949,12 +1203,13 @@ fn show_color_words_resolved_hunks(
                     line_number,
                     labels,
                     options,
+                    moved_lines,
                 )?;
             }
         }
     }
 
-    let num_after = if emitted { options.context } else { 0 };
+    let num_after = if emitted { context_sizes(context).0 } else { 0 };
     let num_before = 0;
     show_color_words_context_lines(
         formatter,
@@ -964,10 +1219,15 @@ fn show_color_words_resolved_hunks(
         options,
         num_after,
         num_before,
+        None,
     )
 }
 
 /// Prints `num_after` lines, ellipsis, and `num_before` lines.
+///
+/// If `function_headers` is given, the ellipsis is annotated with the function
+/// enclosing the following lines.
+#[expect(clippy::too_many_arguments)]
 fn show_color_words_context_lines(
     formatter: &mut dyn Formatter,
     contexts: &[[&BStr; 2]],
@@ -976,8 +1236,8 @@ fn show_color_words_context_lines(
     options: &ColorWordsDiffOptions,
     num_after: usize,
     num_before: usize,
+    function_headers: Option<&FunctionHeaders>,
 ) -> io::Result<DiffLineNumber> {
-    const SKIPPED_CONTEXT_LINE: &str = "    ...\n";
     let extract = |side: usize| -> (Vec<&[u8]>, Vec<&[u8]>, u32) {
         let mut lines = contexts
             .iter()
@@ -1016,6 +1276,7 @@ fn show_color_words_context_lines(
                 line_number,
                 labels,
                 options,
+                None,
             )
         }
     };
@@ -1024,7 +1285,6 @@ fn show_color_words_context_lines(
     let (right_after, mut right_before, num_right_skipped) = extract(1);
     line_number = show(formatter, [&left_after, &right_after], line_number)?;
     if num_left_skipped > 0 || num_right_skipped > 0 {
-        write!(formatter, "{SKIPPED_CONTEXT_LINE}")?;
         line_number.left += num_left_skipped;
         line_number.right += num_right_skipped;
         if left_before.len() > num_before {
@@ -1035,6 +1295,13 @@ fn show_color_words_context_lines(
             right_before.pop();
             line_number.right += 1;
         }
+        write!(formatter, "    ...")?;
+        let line_index = usize::try_from(line_number.left - 1).unwrap();
+        if let Some(header) = function_headers.and_then(|headers| headers.enclosing(line_index)) {
+            write!(formatter, " ")?;
+            formatter.labeled("hunk_header").write_all(header)?;
+        }
+        writeln!(formatter)?;
     }
     left_before.reverse();
     right_before.reverse();
@@ -1048,6 +1315,7 @@ fn show_color_words_diff_lines(
     mut line_number: DiffLineNumber,
     labels: [&str; 2],
     options: &ColorWordsDiffOptions,
+    moved_lines: Option<&FileMovedLines>,
 ) -> io::Result<DiffLineNumber> {
     let word_diff_hunks = ContentDiff::by_word(contents).hunks().collect_vec();
     let can_inline = match options.max_inline_alternation {
@@ -1058,42 +1326,73 @@ fn show_color_words_diff_lines(
             groups.map(count_diff_alternation).max().unwrap_or(0) <= max_num
         }
     };
+    let is_removed_line_moved = |line_number: u32| {
+        let line_index = usize::try_from(line_number - 1).unwrap();
+        moved_lines.is_some_and(|moved| moved.is_removed_line_moved(line_index))
+    };
+    let is_added_line_moved = |line_number: u32| {
+        let line_index = usize::try_from(line_number - 1).unwrap();
+        moved_lines.is_some_and(|moved| moved.is_added_line_moved(line_index))
+    };
     if can_inline {
         let mut diff_line_iter =
             DiffLineIterator::with_line_number(word_diff_hunks.iter(), line_number);
         for diff_line in diff_line_iter.by_ref() {
-            show_color_words_line_number(
-                formatter,
-                [
-                    diff_line
-                        .has_left_content()
-                        .then_some(diff_line.line_number.left),
-                    diff_line
-                        .has_right_content()
-                        .then_some(diff_line.line_number.right),
-                ],
-                labels,
-            )?;
-            show_color_words_inline_hunks(formatter, &diff_line.hunks, labels)?;
+            let line_numbers = [
+                diff_line
+                    .has_left_content()
+                    .then_some(diff_line.line_number.left),
+                diff_line
+                    .has_right_content()
+                    .then_some(diff_line.line_number.right),
+            ];
+            let is_moved = match line_numbers {
+                [Some(left), None] => is_removed_line_moved(left),
+                [None, Some(right)] => is_added_line_moved(right),
+                _ => false,
+            };
+            with_moved_label(formatter, is_moved, |formatter| {
+                show_color_words_line_number(formatter, line_numbers, labels)?;
+                show_color_words_inline_hunks(formatter, &diff_line.hunks, labels)
+            })?;
         }
         line_number = diff_line_iter.next_line_number();
     } else {
         let [left_lines, right_lines] = unzip_diff_hunks_to_lines(&word_diff_hunks);
         let [left_label, right_label] = labels;
         for tokens in &left_lines {
-            show_color_words_line_number(formatter, [Some(line_number.left), None], labels)?;
-            show_color_words_single_sided_line(formatter, tokens, left_label)?;
+            let is_moved = is_removed_line_moved(line_number.left);
+            with_moved_label(formatter, is_moved, |formatter| {
+                show_color_words_line_number(formatter, [Some(line_number.left), None], labels)?;
+                show_color_words_single_sided_line(formatter, tokens, left_label)
+            })?;
             line_number.left += 1;
         }
         for tokens in &right_lines {
-            show_color_words_line_number(formatter, [None, Some(line_number.right)], labels)?;
-            show_color_words_single_sided_line(formatter, tokens, right_label)?;
+            let is_moved = is_added_line_moved(line_number.right);
+            with_moved_label(formatter, is_moved, |formatter| {
+                show_color_words_line_number(formatter, [None, Some(line_number.right)], labels)?;
+                show_color_words_single_sided_line(formatter, tokens, right_label)
+            })?;
             line_number.right += 1;
         }
     }
     Ok(line_number)
 }
 
+/// Calls `f` with the formatter labeled as "moved" if `is_moved`.
+fn with_moved_label<T>(
+    formatter: &mut dyn Formatter,
+    is_moved: bool,
+    f: impl FnOnce(&mut dyn Formatter) -> io::Result<T>,
+) -> io::Result<T> {
+    if is_moved {
+        f(*formatter.labeled("moved"))
+    } else {
+        f(formatter)
+    }
+}
+
 fn show_color_words_line_number(
     formatter: &mut dyn Formatter,
     [left_line_number, right_line_number]: [Option<u32>; 2],
@@ -1279,9 +1578,57 @@ fn basic_diff_file_type(value: &MaterializedTreeValue) -> &'static str {
     }
 }
 
+/// Returns a description of the file modified from `left_value` to
+/// `right_value`, such as "Modified regular file".
+fn modified_file_description(
+    left_value: &MaterializedTreeValue,
+    right_value: &MaterializedTreeValue,
+) -> String {
+    match (left_value, right_value) {
+        (MaterializedTreeValue::File(left), MaterializedTreeValue::File(right)) => {
+            if left.executable && right.executable {
+                "Modified executable file".to_string()
+            } else if left.executable {
+                "Executable file became non-executable at".to_string()
+            } else if right.executable {
+                "Non-executable file became executable at".to_string()
+            } else {
+                "Modified regular file".to_string()
+            }
+        }
+        (
+            MaterializedTreeValue::FileConflict(_) | MaterializedTreeValue::OtherConflict { .. },
+            MaterializedTreeValue::FileConflict(_) | MaterializedTreeValue::OtherConflict { .. },
+        ) => "Modified conflict in".to_string(),
+        (
+            MaterializedTreeValue::FileConflict(_) | MaterializedTreeValue::OtherConflict { .. },
+            _,
+        ) => "Resolved conflict in".to_string(),
+        (
+            _,
+            MaterializedTreeValue::FileConflict(_) | MaterializedTreeValue::OtherConflict { .. },
+        ) => "Created conflict in".to_string(),
+        (MaterializedTreeValue::Symlink { .. }, MaterializedTreeValue::Symlink { .. }) => {
+            "Symlink target changed at".to_string()
+        }
+        (_, _) => {
+            let left_type = basic_diff_file_type(left_value);
+            let right_type = basic_diff_file_type(right_value);
+            let (first, rest) = left_type.split_at(1);
+            format!(
+                "{}{} became {} at",
+                first.to_ascii_uppercase(),
+                rest,
+                right_type
+            )
+        }
+    }
+}
+
 pub async fn show_color_words_diff(
     formatter: &mut dyn Formatter,
     store: &Store,
+    text_converters: &TextConverters,
     tree_diff: BoxStream<'_, CopiesTreeDiffEntry>,
     path_converter: &RepoPathUiConverter,
     options: &ColorWordsDiffOptions,
@@ -1292,41 +1639,164 @@ pub async fn show_color_words_diff(
         marker_len: None,
         merge: store.merge_options().clone(),
     };
-    let empty_content = || Merge::resolved(BString::default());
-    let mut diff_stream = materialized_diff_stream(store, tree_diff);
-    while let Some(MaterializedTreeDiffEntry { path, values }) = diff_stream.next().await {
-        let left_path = path.source();
-        let right_path = path.target();
-        let left_ui_path = path_converter.format_file_path(left_path);
-        let right_ui_path = path_converter.format_file_path(right_path);
+    let mut diff_stream = materialized_text_diff_stream(store, tree_diff, text_converters);
+    let mut next_file = async || -> Result<_, DiffRenderError> {
+        let Some(MaterializedTreeDiffEntry { path, values }) = diff_stream.next().await else {
+            return Ok(None);
+        };
         let (left_value, right_value) = values?;
+        let file = ColorWordsFileDiff::load(&path, left_value, right_value)?;
+        Ok(Some((path, file)))
+    };
+    if options.color_moved {
+        // Moved lines can't be detected until all files are loaded.
+        let mut files = vec![];
+        while let Some(file) = next_file().await? {
+            files.push(file);
+        }
+        let mut detector = MovedLineDetector::new(options.line_diff.compare_mode);
+        for (_, file) in &files {
+            detector.add_file(file.text_contents());
+        }
+        let moved_lines = detector.detect();
+        for (index, (path, file)) in files.iter().enumerate() {
+            show_color_words_file_diff(
+                formatter,
+                path,
+                file,
+                path_converter,
+                options,
+                Some(moved_lines.file(index)),
+                &materialize_options,
+            )?;
+        }
+    } else {
+        while let Some((path, file)) = next_file().await? {
+            show_color_words_file_diff(
+                formatter,
+                &path,
+                &file,
+                path_converter,
+                options,
+                None,
+                &materialize_options,
+            )?;
+        }
+    }
+    Ok(())
+}
 
-        match (&left_value, &right_value) {
-            (MaterializedTreeValue::AccessDenied(source), _) => {
-                write!(
-                    formatter.labeled("access-denied"),
-                    "Access denied to {left_ui_path}:"
-                )?;
-                writeln!(formatter, " {source}")?;
-                continue;
-            }
-            (_, MaterializedTreeValue::AccessDenied(source)) => {
-                write!(
-                    formatter.labeled("access-denied"),
-                    "Access denied to {right_ui_path}:"
-                )?;
-                writeln!(formatter, " {source}")?;
-                continue;
-            }
-            _ => {}
+/// File contents loaded for the color-words diff.
+enum ColorWordsFileDiff {
+    AccessDenied {
+        path: RepoPathBuf,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    Added {
+        description: &'static str,
+        content: FileContent<Merge<BString>>,
+    },
+    Modified {
+        description: String,
+        left_content: FileContent<Merge<BString>>,
+        right_content: FileContent<Merge<BString>>,
+    },
+    Removed {
+        description: &'static str,
+        content: FileContent<Merge<BString>>,
+    },
+}
+
+impl ColorWordsFileDiff {
+    fn load(
+        path: &CopiesTreeDiffEntryPath,
+        left_value: MaterializedTreeValue,
+        right_value: MaterializedTreeValue,
+    ) -> BackendResult<Self> {
+        let left_path = path.source();
+        let right_path = path.target();
+        if let MaterializedTreeValue::AccessDenied(source) = left_value {
+            let path = left_path.to_owned();
+            return Ok(Self::AccessDenied { path, source });
+        }
+        if let MaterializedTreeValue::AccessDenied(source) = right_value {
+            let path = right_path.to_owned();
+            return Ok(Self::AccessDenied { path, source });
         }
         if left_value.is_absent() {
-            let description = basic_diff_file_type(&right_value);
+            Ok(Self::Added {
+                description: basic_diff_file_type(&right_value),
+                content: diff_content_as_merge(right_path, right_value)?,
+            })
+        } else if right_value.is_present() {
+            Ok(Self::Modified {
+                description: modified_file_description(&left_value, &right_value),
+                left_content: diff_content_as_merge(left_path, left_value)?,
+                right_content: diff_content_as_merge(right_path, right_value)?,
+            })
+        } else {
+            Ok(Self::Removed {
+                description: basic_diff_file_type(&left_value),
+                content: diff_content_as_merge(left_path, left_value)?,
+            })
+        }
+    }
+
+    /// Returns the `[left, right]` contents to detect moved lines from.
+    /// Conflicts and binary files are ignored.
+    fn text_contents(&self) -> [&BStr; 2] {
+        fn text(content: &FileContent<Merge<BString>>) -> Option<&BStr> {
+            let text = content.contents.as_resolved()?;
+            (!content.is_binary).then(|| text.as_bstr())
+        }
+        let empty = BStr::new("");
+        match self {
+            Self::AccessDenied { .. } => [empty, empty],
+            Self::Added { content, .. } => [empty, text(content).unwrap_or(empty)],
+            Self::Modified {
+                left_content,
+                right_content,
+                ..
+            } => match (text(left_content), text(right_content)) {
+                (Some(left), Some(right)) => [left, right],
+                _ => [empty, empty],
+            },
+            Self::Removed { content, .. } => [text(content).unwrap_or(empty), empty],
+        }
+    }
+}
+
+fn show_color_words_file_diff(
+    formatter: &mut dyn Formatter,
+    path: &CopiesTreeDiffEntryPath,
+    file: &ColorWordsFileDiff,
+    path_converter: &RepoPathUiConverter,
+    options: &ColorWordsDiffOptions,
+    moved_lines: Option<&FileMovedLines>,
+    materialize_options: &ConflictMaterializeOptions,
+) -> Result<(), DiffRenderError> {
+    let left_path = path.source();
+    let right_path = path.target();
+    let left_ui_path = path_converter.format_file_path(left_path);
+    let right_ui_path = path_converter.format_file_path(right_path);
+    let empty_content = || Merge::resolved(BString::default());
+    match file {
+        ColorWordsFileDiff::AccessDenied { path, source } => {
+            let ui_path = path_converter.format_file_path(path);
+            write!(
+                formatter.labeled("access-denied"),
+                "Access denied to {ui_path}:"
+            )?;
+            writeln!(formatter, " {source}")?;
+        }
+        ColorWordsFileDiff::Added {
+            description,
+            content: right_content,
+        } => {
             writeln!(
                 formatter.labeled("header"),
                 "Added {description} {right_ui_path}:"
             )?;
-            let right_content = diff_content_as_merge(right_path, right_value)?;
             if right_content.is_empty() {
                 writeln!(formatter.labeled("empty"), "    (empty)")?;
             } else if right_content.is_binary {
@@ -1336,55 +1806,17 @@ pub async fn show_color_words_diff(
                     formatter,
                     [&empty_content(), &right_content.contents],
                     options,
-                    &materialize_options,
+                    None,
+                    moved_lines,
+                    materialize_options,
                 )?;
             }
-        } else if right_value.is_present() {
-            let description = match (&left_value, &right_value) {
-                (MaterializedTreeValue::File(left), MaterializedTreeValue::File(right)) => {
-                    if left.executable && right.executable {
-                        "Modified executable file".to_string()
-                    } else if left.executable {
-                        "Executable file became non-executable at".to_string()
-                    } else if right.executable {
-                        "Non-executable file became executable at".to_string()
-                    } else {
-                        "Modified regular file".to_string()
-                    }
-                }
-                (
-                    MaterializedTreeValue::FileConflict(_)
-                    | MaterializedTreeValue::OtherConflict { .. },
-                    MaterializedTreeValue::FileConflict(_)
-                    | MaterializedTreeValue::OtherConflict { .. },
-                ) => "Modified conflict in".to_string(),
-                (
-                    MaterializedTreeValue::FileConflict(_)
-                    | MaterializedTreeValue::OtherConflict { .. },
-                    _,
-                ) => "Resolved conflict in".to_string(),
-                (
-                    _,
-                    MaterializedTreeValue::FileConflict(_)
-                    | MaterializedTreeValue::OtherConflict { .. },
-                ) => "Created conflict in".to_string(),
-                (MaterializedTreeValue::Symlink { .. }, MaterializedTreeValue::Symlink { .. }) => {
-                    "Symlink target changed at".to_string()
-                }
-                (_, _) => {
-                    let left_type = basic_diff_file_type(&left_value);
-                    let right_type = basic_diff_file_type(&right_value);
-                    let (first, rest) = left_type.split_at(1);
-                    format!(
-                        "{}{} became {} at",
-                        first.to_ascii_uppercase(),
-                        rest,
-                        right_type
-                    )
-                }
-            };
-            let left_content = diff_content_as_merge(left_path, left_value)?;
-            let right_content = diff_content_as_merge(right_path, right_value)?;
+        }
+        ColorWordsFileDiff::Modified {
+            description,
+            left_content,
+            right_content,
+        } => {
             if left_path == right_path {
                 writeln!(
                     formatter.labeled("header"),
@@ -1403,16 +1835,20 @@ pub async fn show_color_words_diff(
                     formatter,
                     [&left_content.contents, &right_content.contents],
                     options,
-                    &materialize_options,
+                    options.functions.matcher_for(right_path),
+                    moved_lines,
+                    materialize_options,
                 )?;
             }
-        } else {
-            let description = basic_diff_file_type(&left_value);
+        }
+        ColorWordsFileDiff::Removed {
+            description,
+            content: left_content,
+        } => {
             writeln!(
                 formatter.labeled("header"),
                 "Removed {description} {right_ui_path}:"
             )?;
-            let left_content = diff_content_as_merge(left_path, left_value)?;
             if left_content.is_empty() {
                 writeln!(formatter.labeled("empty"), "    (empty)")?;
             } else if left_content.is_binary {
@@ -1422,7 +1858,9 @@ pub async fn show_color_words_diff(
                     formatter,
                     [&left_content.contents, &empty_content()],
                     options,
-                    &materialize_options,
+                    None,
+                    moved_lines,
+                    materialize_options,
                 )?;
             }
         }
@@ -1516,6 +1954,12 @@ pub async fn show_file_by_file_diff(
 pub struct UnifiedDiffOptions {
     /// Number of context lines to show.
     pub context: usize,
+    /// Whether to show whole functions as context.
+    pub function_context: bool,
+    /// Patterns to detect functions.
+    pub functions: DiffFunctionRules,
+    /// Whether to highlight moved lines.
+    pub color_moved: bool,
     /// How lines are tokenized and compared.
     pub line_diff: LineDiffOptions,
 }
@@ -1524,6 +1968,9 @@ impl UnifiedDiffOptions {
     pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
         Ok(Self {
             context: settings.get("diff.git.context")?,
+            function_context: settings.get("diff.git.function-context")?,
+            functions: DiffFunctionRules::from_settings(settings)?,
+            color_moved: settings.get("diff.git.color-moved")?,
             line_diff: LineDiffOptions::default(),
         })
     }
@@ -1532,6 +1979,12 @@ impl UnifiedDiffOptions {
         if let Some(context) = args.context {
             self.context = context;
         }
+        if args.function_context {
+            self.function_context = true;
+        }
+        if args.color_moved {
+            self.color_moved = true;
+        }
         self.line_diff.merge_args(args);
     }
 }
@@ -1540,6 +1993,8 @@ fn show_unified_diff_hunks(
     formatter: &mut dyn Formatter,
     contents: [&BStr; 2],
     options: &UnifiedDiffOptions,
+    function_matcher: Option<&FunctionMatcher>,
+    moved_lines: Option<&FileMovedLines>,
 ) -> io::Result<()> {
     // "If the chunk size is 0, the first number is one lower than one would
     // expect." - https://www.artima.com/weblogs/viewpost.jsp?thread=164293
@@ -1556,32 +2011,86 @@ fn show_unified_diff_hunks(
         }
     }
 
-    for hunk in unified_diff_hunks(contents, options.context, options.line_diff.compare_mode) {
-        writeln!(
-            formatter.labeled("hunk_header"),
-            "@@ -{},{} +{},{} @@",
-            to_line_number(hunk.left_line_range.clone()),
-            hunk.left_line_range.len(),
-            to_line_number(hunk.right_line_range.clone()),
-            hunk.right_line_range.len()
-        )?;
-        for (line_type, tokens) in &hunk.lines {
+    let hunks = unified_diff_hunks(
+        contents,
+        options.context,
+        options.line_diff.compare_mode,
+        function_matcher,
+        options.function_context,
+    );
+    for hunk in hunks {
+        {
+            let mut formatter = formatter.labeled("hunk_header");
+            write!(
+                formatter,
+                "@@ -{},{} +{},{} @@",
+                to_line_number(hunk.left_line_range.clone()),
+                hunk.left_line_range.len(),
+                to_line_number(hunk.right_line_range.clone()),
+                hunk.right_line_range.len()
+            )?;
+            if let Some(header) = hunk.function_header {
+                write!(formatter, " {}", BStr::new(header))?;
+            }
+            writeln!(formatter)?;
+        }
+        let moved_flags = moved_lines
+            .map(|moved| unified_hunk_moved_flags(&hunk, moved))
+            .unwrap_or_default();
+        for (index, (line_type, tokens)) in hunk.lines.iter().enumerate() {
             let (label, sigil) = match line_type {
                 DiffLineType::Context => ("context", " "),
                 DiffLineType::Removed => ("removed", "-"),
                 DiffLineType::Added => ("added", "+"),
             };
-            write!(formatter.labeled(label), "{sigil}")?;
-            show_diff_line_tokens(*formatter.labeled(label), tokens)?;
-            let (_, content) = tokens.last().expect("hunk line must not be empty");
-            if !content.ends_with(b"\n") {
-                write!(formatter, "\n\\ No newline at end of file\n")?;
-            }
+            let is_moved = moved_flags.get(index).copied().unwrap_or(false);
+            with_moved_label(formatter, is_moved, |formatter| {
+                show_unified_diff_line(formatter, label, sigil, tokens)
+            })?;
         }
     }
     Ok(())
 }
 
+/// Returns whether each line of the `hunk` was moved.
+fn unified_hunk_moved_flags(hunk: &UnifiedDiffHunk, moved_lines: &FileMovedLines) -> Vec<bool> {
+    let mut left_line_index = hunk.left_line_range.start;
+    let mut right_line_index = hunk.right_line_range.start;
+    hunk.lines
+        .iter()
+        .map(|(line_type, _)| match line_type {
+            DiffLineType::Context => {
+                left_line_index += 1;
+                right_line_index += 1;
+                false
+            }
+            DiffLineType::Removed => {
+                left_line_index += 1;
+                moved_lines.is_removed_line_moved(left_line_index - 1)
+            }
+            DiffLineType::Added => {
+                right_line_index += 1;
+                moved_lines.is_added_line_moved(right_line_index - 1)
+            }
+        })
+        .collect()
+}
+
+fn show_unified_diff_line(
+    formatter: &mut dyn Formatter,
+    label: &str,
+    sigil: &str,
+    tokens: &[(DiffTokenType, &[u8])],
+) -> io::Result<()> {
+    write!(formatter.labeled(label), "{sigil}")?;
+    show_diff_line_tokens(*formatter.labeled(label), tokens)?;
+    let (_, content) = tokens.last().expect("hunk line must not be empty");
+    if !content.ends_with(b"\n") {
+        write!(formatter, "\n\\ No newline at end of file\n")?;
+    }
+    Ok(())
+}
+
 fn show_diff_line_tokens(
     formatter: &mut dyn Formatter,
     tokens: &[(DiffTokenType, &[u8])],
@@ -1598,6 +2107,7 @@ fn show_diff_line_tokens(
 pub async fn show_git_diff(
     formatter: &mut dyn Formatter,
     store: &Store,
+    text_converters: &TextConverters,
     tree_diff: BoxStream<'_, CopiesTreeDiffEntry>,
     options: &UnifiedDiffOptions,
     marker_style: ConflictMarkerStyle,
@@ -1607,89 +2117,231 @@ pub async fn show_git_diff(
         marker_len: None,
         merge: store.merge_options().clone(),
     };
-    let mut diff_stream = materialized_diff_stream(store, tree_diff);
-    while let Some(MaterializedTreeDiffEntry { path, values }) = diff_stream.next().await {
-        let left_path = path.source();
-        let right_path = path.target();
-        let left_path_string = left_path.as_internal_file_string();
-        let right_path_string = right_path.as_internal_file_string();
+    let mut diff_stream = materialized_text_diff_stream(store, tree_diff, text_converters);
+    let mut next_parts = async || -> Result<_, DiffRenderError> {
+        let Some(MaterializedTreeDiffEntry { path, values }) = diff_stream.next().await else {
+            return Ok(None);
+        };
         let (left_value, right_value) = values?;
-
-        let left_part = git_diff_part(left_path, left_value, &materialize_options)?;
-        let right_part = git_diff_part(right_path, right_value, &materialize_options)?;
-
-        {
-            let mut formatter = formatter.labeled("file_header");
-            writeln!(
-                formatter,
-                "diff --git a/{left_path_string} b/{right_path_string}"
-            )?;
-            let left_hash = &left_part.hash;
-            let right_hash = &right_part.hash;
-            match (left_part.mode, right_part.mode) {
-                (None, Some(right_mode)) => {
-                    writeln!(formatter, "new file mode {right_mode}")?;
-                    writeln!(formatter, "index {left_hash}..{right_hash}")?;
-                }
-                (Some(left_mode), None) => {
-                    writeln!(formatter, "deleted file mode {left_mode}")?;
-                    writeln!(formatter, "index {left_hash}..{right_hash}")?;
-                }
-                (Some(left_mode), Some(right_mode)) => {
-                    if let Some(op) = path.copy_operation() {
-                        let operation = match op {
-                            CopyOperation::Copy => "copy",
-                            CopyOperation::Rename => "rename",
-                        };
-                        // TODO: include similarity index?
-                        writeln!(formatter, "{operation} from {left_path_string}")?;
-                        writeln!(formatter, "{operation} to {right_path_string}")?;
-                    }
-                    if left_mode != right_mode {
-                        writeln!(formatter, "old mode {left_mode}")?;
-                        writeln!(formatter, "new mode {right_mode}")?;
-                        if left_hash != right_hash {
-                            writeln!(formatter, "index {left_hash}..{right_hash}")?;
-                        }
-                    } else if left_hash != right_hash {
-                        writeln!(formatter, "index {left_hash}..{right_hash} {left_mode}")?;
-                    }
-                }
-                (None, None) => panic!("either left or right part should be present"),
-            }
+        let left_part = git_diff_part(path.source(), left_value, &materialize_options)?;
+        let right_part = git_diff_part(path.target(), right_value, &materialize_options)?;
+        Ok(Some((path, left_part, right_part)))
+    };
+    if options.color_moved {
+        // Moved lines can't be detected until all files are loaded.
+        let mut files = vec![];
+        while let Some(file) = next_parts().await? {
+            files.push(file);
         }
-
-        if left_part.content.contents == right_part.content.contents {
-            continue; // no content hunks
+        let moved_lines = detect_moved_lines(
+            files
+                .iter()
+                .map(|(_, left_part, right_part)| [&left_part.content, &right_part.content]),
+            options.line_diff.compare_mode,
+        );
+        for (index, (path, left_part, right_part)) in files.iter().enumerate() {
+            let moved_lines = Some(moved_lines.file(index));
+            show_git_diff_file(formatter, path, left_part, right_part, options, moved_lines)?;
         }
-
-        let left_path = match left_part.mode {
-            Some(_) => format!("a/{left_path_string}"),
-            None => "/dev/null".to_owned(),
-        };
-        let right_path = match right_part.mode {
-            Some(_) => format!("b/{right_path_string}"),
-            None => "/dev/null".to_owned(),
-        };
-        if left_part.content.is_binary || right_part.content.is_binary {
-            // TODO: add option to emit Git binary diff
-            writeln!(
-                formatter,
-                "Binary files {left_path} and {right_path} differ"
-            )?;
-        } else {
-            writeln!(formatter.labeled("file_header"), "--- {left_path}")?;
-            writeln!(formatter.labeled("file_header"), "+++ {right_path}")?;
-            show_unified_diff_hunks(
-                formatter,
-                [&left_part.content.contents, &right_part.content.contents].map(BStr::new),
-                options,
-            )?;
+    } else {
+        while let Some((path, left_part, right_part)) = next_parts().await? {
+            show_git_diff_file(formatter, &path, &left_part, &right_part, options, None)?;
         }
     }
     Ok(())
 }
 
+fn show_git_diff_file(
+    formatter: &mut dyn Formatter,
+    path: &CopiesTreeDiffEntryPath,
+    left_part: &GitDiffPart,
+    right_part: &GitDiffPart,
+    options: &UnifiedDiffOptions,
+    moved_lines: Option<&FileMovedLines>,
+) -> Result<(), DiffRenderError> {
+    let left_path_string = path.source().as_internal_file_string();
+    let right_path_string = path.target().as_internal_file_string();
+    {
+        let mut formatter = formatter.labeled("file_header");
+        writeln!(
+            formatter,
+            "diff --git a/{left_path_string} b/{right_path_string}"
+        )?;
+        let left_hash = &left_part.hash;
+        let right_hash = &right_part.hash;
+        match (left_part.mode, right_part.mode) {
+            (None, Some(right_mode)) => {
+                writeln!(formatter, "new file mode {right_mode}")?;
+                writeln!(formatter, "index {left_hash}..{right_hash}")?;
+            }
+            (Some(left_mode), None) => {
+                writeln!(formatter, "deleted file mode {left_mode}")?;
+                writeln!(formatter, "index {left_hash}..{right_hash}")?;
+            }
+            (Some(left_mode), Some(right_mode)) => {
+                if let Some(op) = path.copy_operation() {
+                    let operation = match op {
+                        CopyOperation::Copy => "copy",
+                        CopyOperation::Rename => "rename",
+                    };
+                    // TODO: include similarity index?
+                    writeln!(formatter, "{operation} from {left_path_string}")?;
+                    writeln!(formatter, "{operation} to {right_path_string}")?;
+                }
+                if left_mode != right_mode {
+                    writeln!(formatter, "old mode {left_mode}")?;
+                    writeln!(formatter, "new mode {right_mode}")?;
+                    if left_hash != right_hash {
+                        writeln!(formatter, "index {left_hash}..{right_hash}")?;
+                    }
+                } else if left_hash != right_hash {
+                    writeln!(formatter, "index {left_hash}..{right_hash} {left_mode}")?;
+                }
+            }
+            (None, None) => panic!("either left or right part should be present"),
+        }
+    }
+
+    if left_part.content.contents == right_part.content.contents {
+        return Ok(()); // no content hunks
+    }
+
+    let left_path = match left_part.mode {
+        Some(_) => format!("a/{left_path_string}"),
+        None => "/dev/null".to_owned(),
+    };
+    let right_path = match right_part.mode {
+        Some(_) => format!("b/{right_path_string}"),
+        None => "/dev/null".to_owned(),
+    };
+    if left_part.content.is_binary || right_part.content.is_binary {
+        // TODO: add option to emit Git binary diff
+        writeln!(
+            formatter,
+            "Binary files {left_path} and {right_path} differ"
+        )?;
+    } else {
+        writeln!(formatter.labeled("file_header"), "--- {left_path}")?;
+        writeln!(formatter.labeled("file_header"), "+++ {right_path}")?;
+        show_unified_diff_hunks(
+            formatter,
+            [&left_part.content.contents, &right_part.content.contents].map(BStr::new),
+            options,
+            options.functions.matcher_for(path.target()),
+            moved_lines,
+        )?;
+    }
+    Ok(())
+}
+
+/// Materializes the tree diff, converting file contents by the
+/// `text_converters`.
+fn materialized_text_diff_stream<'a>(
+    store: &'a Store,
+    tree_diff: BoxStream<'a, CopiesTreeDiffEntry>,
+    text_converters: &'a TextConverters,
+) -> BoxStream<'a, MaterializedTreeDiffEntry> {
+    materialized_diff_stream(store, tree_diff)
+        .then(async |entry| {
+            let MaterializedTreeDiffEntry { path, values } = entry;
+            let values = match values {
+                Ok((left_value, right_value)) => try_join!(
+                    text_converters.convert_value(path.source(), left_value),
+                    text_converters.convert_value(path.target(), right_value),
+                ),
+                Err(err) => Err(err),
+            };
+            MaterializedTreeDiffEntry { path, values }
+        })
+        .boxed()
+}
+
+/// Line ranges of a unified diff hunk.
+#[derive(Clone, Debug)]
+pub struct DiffHunkSummary {
+    /// Path to the target file.
+    pub path: RepoPathBuf,
+    /// 0-based range of the hunk lines in the left file.
+    pub left_line_range: Range<usize>,
+    /// 0-based range of the hunk lines in the right file.
+    pub right_line_range: Range<usize>,
+    /// Whether all removed and added lines in the hunk were moved.
+    pub moved: bool,
+}
+
+/// Computes unified diff hunks of non-binary files. Lines moved within or
+/// across files are detected.
+pub async fn diff_hunk_summaries(
+    store: &Store,
+    text_converters: &TextConverters,
+    tree_diff: BoxStream<'_, CopiesTreeDiffEntry>,
+    options: &UnifiedDiffOptions,
+    marker_style: ConflictMarkerStyle,
+) -> Result<Vec<DiffHunkSummary>, DiffRenderError> {
+    let materialize_options = ConflictMaterializeOptions {
+        marker_style,
+        marker_len: None,
+        merge: store.merge_options().clone(),
+    };
+    let mut files = vec![];
+    let mut diff_stream = materialized_text_diff_stream(store, tree_diff, text_converters);
+    while let Some(MaterializedTreeDiffEntry { path, values }) = diff_stream.next().await {
+        let (left_value, right_value) = values?;
+        let left_part = git_diff_part(path.source(), left_value, &materialize_options)?;
+        let right_part = git_diff_part(path.target(), right_value, &materialize_options)?;
+        files.push((path.target, left_part.content, right_part.content));
+    }
+    let moved_lines = detect_moved_lines(
+        files.iter().map(|(_, left, right)| [left, right]),
+        options.line_diff.compare_mode,
+    );
+    let mut summaries = vec![];
+    for (index, (path, left, right)) in files.iter().enumerate() {
+        if left.is_binary || right.is_binary {
+            continue;
+        }
+        let hunks = unified_diff_hunks(
+            [&left.contents, &right.contents].map(BStr::new),
+            options.context,
+            options.line_diff.compare_mode,
+            None,
+            false,
+        );
+        for hunk in hunks {
+            let moved_flags = unified_hunk_moved_flags(&hunk, moved_lines.file(index));
+            let mut changed_flags = iter::zip(&hunk.lines, moved_flags)
+                .filter(|((line_type, _), _)| *line_type != DiffLineType::Context)
+                .map(|(_, is_moved)| is_moved)
+                .peekable();
+            let moved = changed_flags.peek().is_some() && changed_flags.all(|is_moved| is_moved);
+            summaries.push(DiffHunkSummary {
+                path: path.clone(),
+                left_line_range: hunk.left_line_range,
+                right_line_range: hunk.right_line_range,
+                moved,
+            });
+        }
+    }
+    Ok(summaries)
+}
+
+/// Detects lines moved within or across the `[left, right]` file contents.
+/// Binary files are ignored.
+fn detect_moved_lines<'a>(
+    contents: impl IntoIterator<Item = [&'a FileContent<BString>; 2]>,
+    compare_mode: LineCompareMode,
+) -> MovedLines {
+    let mut detector = MovedLineDetector::new(compare_mode);
+    for [left, right] in contents {
+        if left.is_binary || right.is_binary {
+            detector.add_file([BStr::new(""), BStr::new("")]);
+        } else {
+            detector.add_file([&left.contents, &right.contents].map(BStr::new));
+        }
+    }
+    detector.detect()
+}
+
 /// Generates diff of non-binary contents in Git format.
 fn show_git_diff_texts<T: AsRef<[u8]>>(
     formatter: &mut dyn Formatter,
@@ -1704,14 +2356,527 @@ fn show_git_diff_texts<T: AsRef<[u8]>>(
         writeln!(formatter, "--- {left_path}")?;
         writeln!(formatter, "+++ {right_path}")?;
     }
-    let [left, right] = contents.map(|content| match content.as_resolved() {
+    let [left, right] = contents.map(|content| materialize_text(content, materialize_options));
+    show_unified_diff_hunks(
+        formatter,
+        [left.as_ref(), right.as_ref()],
+        options,
+        None,
+        None,
+    )
+}
+
+/// Returns the text content, materializing conflicts if any.
+fn materialize_text<'a, T: AsRef<[u8]>>(
+    content: &'a Merge<T>,
+    materialize_options: &ConflictMaterializeOptions,
+) -> Cow<'a, BStr> {
+    match content.as_resolved() {
         Some(text) => Cow::Borrowed(BStr::new(text)),
         None => Cow::Owned(materialize_merge_result_to_bytes(
             content,
             materialize_options,
         )),
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SideBySideDiffOptions {
+    /// Number of context lines to show.
+    pub context: usize,
+    /// How lines are tokenized and compared.
+    pub line_diff: LineDiffOptions,
+}
+
+impl SideBySideDiffOptions {
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        Ok(Self {
+            context: settings.get("diff.side-by-side.context")?,
+            line_diff: LineDiffOptions::default(),
+        })
+    }
+
+    fn merge_args(&mut self, args: &DiffFormatArgs) {
+        if let Some(context) = args.context {
+            self.context = context;
+        }
+        self.line_diff.merge_args(args);
+    }
+}
+
+#[expect(clippy::too_many_arguments)]
+pub async fn show_side_by_side_diff(
+    formatter: &mut dyn Formatter,
+    store: &Store,
+    text_converters: &TextConverters,
+    tree_diff: BoxStream<'_, CopiesTreeDiffEntry>,
+    path_converter: &RepoPathUiConverter,
+    options: &SideBySideDiffOptions,
+    marker_style: ConflictMarkerStyle,
+    width: usize,
+) -> Result<(), DiffRenderError> {
+    let materialize_options = ConflictMaterializeOptions {
+        marker_style,
+        marker_len: None,
+        merge: store.merge_options().clone(),
+    };
+    let mut diff_stream = materialized_text_diff_stream(store, tree_diff, text_converters);
+    while let Some(MaterializedTreeDiffEntry { path, values }) = diff_stream.next().await {
+        let left_path = path.source();
+        let right_path = path.target();
+        let left_ui_path = path_converter.format_file_path(left_path);
+        let right_ui_path = path_converter.format_file_path(right_path);
+        let (left_value, right_value) = values?;
+
+        match (&left_value, &right_value) {
+            (MaterializedTreeValue::AccessDenied(source), _) => {
+                write!(
+                    formatter.labeled("access-denied"),
+                    "Access denied to {left_ui_path}:"
+                )?;
+                writeln!(formatter, " {source}")?;
+                continue;
+            }
+            (_, MaterializedTreeValue::AccessDenied(source)) => {
+                write!(
+                    formatter.labeled("access-denied"),
+                    "Access denied to {right_ui_path}:"
+                )?;
+                writeln!(formatter, " {source}")?;
+                continue;
+            }
+            _ => {}
+        }
+        let is_added_or_removed = left_value.is_absent() || right_value.is_absent();
+        if left_value.is_absent() {
+            let description = basic_diff_file_type(&right_value);
+            writeln!(
+                formatter.labeled("header"),
+                "Added {description} {right_ui_path}:"
+            )?;
+        } else if right_value.is_absent() {
+            let description = basic_diff_file_type(&left_value);
+            writeln!(
+                formatter.labeled("header"),
+                "Removed {description} {right_ui_path}:"
+            )?;
+        } else {
+            let description = modified_file_description(&left_value, &right_value);
+            if left_path == right_path {
+                writeln!(
+                    formatter.labeled("header"),
+                    "{description} {right_ui_path}:"
+                )?;
+            } else {
+                writeln!(
+                    formatter.labeled("header"),
+                    "{description} {right_ui_path} ({left_ui_path} => {right_ui_path}):"
+                )?;
+            }
+        }
+        let left_content = diff_content(left_path, left_value, &materialize_options)?;
+        let right_content = diff_content(right_path, right_value, &materialize_options)?;
+        if left_content.is_binary || right_content.is_binary {
+            writeln!(formatter.labeled("binary"), "    (binary)")?;
+        } else if left_content.contents != right_content.contents {
+            show_side_by_side_diff_hunks(
+                formatter,
+                [&left_content.contents, &right_content.contents].map(BStr::new),
+                options,
+                width,
+            )?;
+        } else if is_added_or_removed && left_content.contents.is_empty() {
+            writeln!(formatter.labeled("empty"), "    (empty)")?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints diff hunks in two columns, old content on the left and new content
+/// on the right. Lines longer than the column are wrapped.
+fn show_side_by_side_diff_hunks(
+    formatter: &mut dyn Formatter,
+    contents: [&BStr; 2],
+    options: &SideBySideDiffOptions,
+    width: usize,
+) -> io::Result<()> {
+    // Each column consists of a 4-digit line number, a space, and the content.
+    // The columns are separated by " |".
+    const MIN_CONTENT_WIDTH: usize = 10;
+    let content_width = max(width.saturating_sub(5 + 2 + 5) / 2, MIN_CONTENT_WIDTH);
+    let hunks = unified_diff_hunks(
+        contents,
+        options.context,
+        options.line_diff.compare_mode,
+        None,
+        false,
+    );
+    let mut next_line_number = DiffLineNumber { left: 1, right: 1 };
+    for hunk in &hunks {
+        let mut line_number = DiffLineNumber {
+            left: u32::try_from(hunk.left_line_range.start + 1).unwrap(),
+            right: u32::try_from(hunk.right_line_range.start + 1).unwrap(),
+        };
+        if line_number != next_line_number {
+            writeln!(formatter, "    ...")?;
+        }
+        let chunks = hunk
+            .lines
+            .iter()
+            .chunk_by(|(line_type, _)| *line_type == DiffLineType::Context);
+        for (is_context, lines) in &chunks {
+            if is_context {
+                for (_, tokens) in lines {
+                    let left = SideBySideLine {
+                        line_number: line_number.left,
+                        label: None,
+                        tokens,
+                    };
+                    let right = SideBySideLine {
+                        line_number: line_number.right,
+                        ..left
+                    };
+                    show_side_by_side_line(formatter, [Some(left), Some(right)], content_width)?;
+                    line_number.left += 1;
+                    line_number.right += 1;
+                }
+            } else {
+                let (removed, added): (Vec<_>, Vec<_>) = lines
+                    .map(|(line_type, tokens)| (*line_type, tokens))
+                    .partition(|(line_type, _)| *line_type == DiffLineType::Removed);
+                for pair in removed.iter().zip_longest(&added) {
+                    let (left, right) = pair.left_and_right();
+                    let left = left.map(|(_, tokens)| SideBySideLine {
+                        line_number: line_number.left,
+                        label: Some("removed"),
+                        tokens,
+                    });
+                    let right = right.map(|(_, tokens)| SideBySideLine {
+                        line_number: line_number.right,
+                        label: Some("added"),
+                        tokens,
+                    });
+                    line_number.left += u32::from(left.is_some());
+                    line_number.right += u32::from(right.is_some());
+                    show_side_by_side_line(formatter, [left, right], content_width)?;
+                }
+            }
+        }
+        next_line_number = line_number;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SideBySideLine<'a> {
+    line_number: u32,
+    /// Label of the changed line, or `None` if the line is unchanged.
+    label: Option<&'static str>,
+    tokens: &'a [(DiffTokenType, &'a [u8])],
+}
+
+/// Prints a pair of lines, wrapping each to `content_width`.
+fn show_side_by_side_line(
+    formatter: &mut dyn Formatter,
+    [left, right]: [Option<SideBySideLine>; 2],
+    content_width: usize,
+) -> io::Result<()> {
+    let [left_rows, right_rows] = [left, right].map(|line| {
+        line.map_or_else(Vec::new, |line| {
+            wrap_diff_line_tokens(line.tokens, content_width)
+        })
     });
-    show_unified_diff_hunks(formatter, [left.as_ref(), right.as_ref()], options)
+    let num_rows = max(left_rows.len(), right_rows.len());
+    for row_index in 0..num_rows {
+        let left_row = left.zip(left_rows.get(row_index));
+        let row_width = show_side_by_side_row(formatter, left_row, row_index == 0, "removed")?;
+        let padding = content_width.saturating_sub(row_width);
+        write!(formatter, "{:padding$} |", "")?;
+        if let Some(right_row) = right.zip(right_rows.get(row_index)) {
+            show_side_by_side_row(formatter, Some(right_row), row_index == 0, "added")?;
+        }
+        writeln!(formatter)?;
+    }
+    Ok(())
+}
+
+/// Prints the line number (if `is_first_row`) and the tokens of a wrapped row.
+/// Returns the display width of the tokens.
+fn show_side_by_side_row(
+    formatter: &mut dyn Formatter,
+    line_row: Option<(SideBySideLine, &WrappedRow)>,
+    is_first_row: bool,
+    line_number_label: &str,
+) -> io::Result<usize> {
+    let Some((line, row)) = line_row else {
+        write!(formatter, "     ")?;
+        return Ok(0);
+    };
+    if is_first_row {
+        write!(
+            formatter.labeled(line_number_label).labeled("line_number"),
+            "{:>4}",
+            line.line_number
+        )?;
+        write!(formatter, " ")?;
+    } else {
+        write!(formatter, "     ")?;
+    }
+    let tokens = row
+        .tokens
+        .iter()
+        .map(|(token_type, content)| (*token_type, content.as_slice()))
+        .collect_vec();
+    match line.label {
+        Some(label) => show_diff_line_tokens(*formatter.labeled(label), &tokens)?,
+        None => show_diff_line_tokens(formatter, &tokens)?,
+    }
+    Ok(row.width)
+}
+
+/// Part of a line fitted to the column width.
+#[derive(Clone, Debug, Default)]
+struct WrappedRow {
+    /// Display width of the tokens.
+    width: usize,
+    tokens: Vec<(DiffTokenType, BString)>,
+}
+
+/// Splits a line into rows of at most `width` columns. Tabs are expanded to
+/// spaces.
+fn wrap_diff_line_tokens(tokens: &[(DiffTokenType, &[u8])], width: usize) -> Vec<WrappedRow> {
+    const TAB_WIDTH: usize = 4;
+    let mut rows = vec![WrappedRow::default()];
+    for (token_type, content) in tokens {
+        let content = content.strip_suffix(b"\n").unwrap_or(content);
+        for (start, end, c) in content.char_indices() {
+            let (bytes, char_width) = if c == '\t' {
+                let num_spaces = TAB_WIDTH - rows.last().unwrap().width % TAB_WIDTH;
+                (&b"    "[..num_spaces], num_spaces)
+            } else {
+                (&content[start..end], c.width().unwrap_or(0))
+            };
+            let row = rows.last_mut().unwrap();
+            if row.width + char_width > width && !row.tokens.is_empty() {
+                rows.push(WrappedRow::default());
+            }
+            let row = rows.last_mut().unwrap();
+            row.width += char_width;
+            match row.tokens.last_mut() {
+                Some((last_type, last_content)) if last_type == token_type => {
+                    last_content.extend_from_slice(bytes);
+                }
+                _ => row.tokens.push((*token_type, bytes.into())),
+            }
+        }
+    }
+    rows
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JsonDiffOptions {
+    /// Number of context lines to show.
+    pub context: usize,
+    /// How lines are tokenized and compared.
+    pub line_diff: LineDiffOptions,
+}
+
+impl JsonDiffOptions {
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        Ok(Self {
+            context: settings.get("diff.json.context")?,
+            line_diff: LineDiffOptions::default(),
+        })
+    }
+
+    fn merge_args(&mut self, args: &DiffFormatArgs) {
+        if let Some(context) = args.context {
+            self.context = context;
+        }
+        self.line_diff.merge_args(args);
+    }
+}
+
+/// Version of the JSON diff schema. This should be bumped if existing fields
+/// are changed or removed.
+const JSON_DIFF_VERSION: u32 = 1;
+
+#[derive(Debug, serde::Serialize)]
+struct JsonDiff {
+    version: u32,
+    files: Vec<JsonDiffFile>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct JsonDiffFile {
+    path: String,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    old: Option<JsonDiffFileValue>,
+    new: Option<JsonDiffFileValue>,
+    binary: bool,
+    hunks: Vec<JsonDiffHunk>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct JsonDiffFileValue {
+    #[serde(rename = "type")]
+    file_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    executable: Option<bool>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct JsonDiffHunk {
+    old_start: usize,
+    old_lines: usize,
+    new_start: usize,
+    new_lines: usize,
+    lines: Vec<JsonDiffLine>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct JsonDiffLine {
+    #[serde(rename = "type")]
+    line_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_line: Option<usize>,
+    tokens: Vec<JsonDiffToken>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct JsonDiffToken {
+    text: String,
+    changed: bool,
+}
+
+/// Prints the diff as a single-line JSON document.
+pub async fn show_json_diff(
+    formatter: &mut dyn Formatter,
+    store: &Store,
+    text_converters: &TextConverters,
+    tree_diff: BoxStream<'_, CopiesTreeDiffEntry>,
+    options: &JsonDiffOptions,
+    marker_style: ConflictMarkerStyle,
+) -> Result<(), DiffRenderError> {
+    let materialize_options = ConflictMaterializeOptions {
+        marker_style,
+        marker_len: None,
+        merge: store.merge_options().clone(),
+    };
+    let mut files = vec![];
+    let mut diff_stream = materialized_text_diff_stream(store, tree_diff, text_converters);
+    while let Some(MaterializedTreeDiffEntry { path, values }) = diff_stream.next().await {
+        let left_path = path.source();
+        let right_path = path.target();
+        let (left_value, right_value) = values?;
+        let status = match path.copy_operation() {
+            Some(CopyOperation::Copy) => "copied",
+            Some(CopyOperation::Rename) => "renamed",
+            None if left_value.is_absent() => "added",
+            None if right_value.is_absent() => "removed",
+            None => "modified",
+        };
+        let [old, new] = [&left_value, &right_value].map(json_diff_file_value);
+        // Contents are unknown if access is denied.
+        let is_access_denied = [&left_value, &right_value]
+            .iter()
+            .any(|value| matches!(value, MaterializedTreeValue::AccessDenied(_)));
+        let left_content = diff_content(left_path, left_value, &materialize_options)?;
+        let right_content = diff_content(right_path, right_value, &materialize_options)?;
+        let binary = left_content.is_binary || right_content.is_binary;
+        let hunks = if binary || is_access_denied {
+            vec![]
+        } else {
+            let contents = [&left_content.contents, &right_content.contents].map(BStr::new);
+            unified_diff_hunks(
+                contents,
+                options.context,
+                options.line_diff.compare_mode,
+                None,
+                false,
+            )
+            .iter()
+            .map(json_diff_hunk)
+            .collect()
+        };
+        files.push(JsonDiffFile {
+            path: right_path.as_internal_file_string().to_owned(),
+            status,
+            source: path
+                .copy_operation()
+                .map(|_| left_path.as_internal_file_string().to_owned()),
+            old,
+            new,
+            binary,
+            hunks,
+        });
+    }
+    let diff = JsonDiff {
+        version: JSON_DIFF_VERSION,
+        files,
+    };
+    serde_json::to_writer(&mut *formatter, &diff).map_err(io::Error::from)?;
+    writeln!(formatter)?;
+    Ok(())
+}
+
+fn json_diff_file_value(value: &MaterializedTreeValue) -> Option<JsonDiffFileValue> {
+    let (file_type, executable) = match value {
+        MaterializedTreeValue::Absent => return None,
+        MaterializedTreeValue::AccessDenied(_) => ("access-denied", None),
+        MaterializedTreeValue::File(file) => ("file", Some(file.executable)),
+        MaterializedTreeValue::Symlink { .. } => ("symlink", None),
+        MaterializedTreeValue::FileConflict(file) => ("conflict", file.executable),
+        MaterializedTreeValue::OtherConflict { .. } => ("conflict", None),
+        MaterializedTreeValue::GitSubmodule(_) => ("git-submodule", None),
+        MaterializedTreeValue::Tree(_) => ("tree", None),
+    };
+    Some(JsonDiffFileValue {
+        file_type,
+        executable,
+    })
+}
+
+fn json_diff_hunk(hunk: &UnifiedDiffHunk) -> JsonDiffHunk {
+    let mut old_line = hunk.left_line_range.start + 1;
+    let mut new_line = hunk.right_line_range.start + 1;
+    let lines = hunk
+        .lines
+        .iter()
+        .map(|(line_type, tokens)| {
+            let (line_type, has_old, has_new) = match line_type {
+                DiffLineType::Context => ("context", true, true),
+                DiffLineType::Removed => ("removed", true, false),
+                DiffLineType::Added => ("added", false, true),
+            };
+            let line = JsonDiffLine {
+                line_type,
+                old_line: has_old.then_some(old_line),
+                new_line: has_new.then_some(new_line),
+                tokens: tokens
+                    .iter()
+                    .map(|(token_type, content)| JsonDiffToken {
+                        text: String::from_utf8_lossy(content).into_owned(),
+                        changed: *token_type == DiffTokenType::Different,
+                    })
+                    .collect(),
+            };
+            old_line += usize::from(has_old);
+            new_line += usize::from(has_new);
+            line
+        })
+        .collect();
+    JsonDiffHunk {
+        old_start: hunk.left_line_range.start + 1,
+        old_lines: hunk.left_line_range.len(),
+        new_start: hunk.right_line_range.start + 1,
+        new_lines: hunk.right_line_range.len(),
+        lines,
+    }
 }
 
 #[instrument(skip_all)]
@@ -1773,6 +2938,7 @@ impl DiffStats {
     /// Calculates stats of changed lines per file.
     pub async fn calculate(
         store: &Store,
+        text_converters: &TextConverters,
         tree_diff: BoxStream<'_, CopiesTreeDiffEntry>,
         options: &DiffStatOptions,
         marker_style: ConflictMarkerStyle,
@@ -1782,7 +2948,7 @@ impl DiffStats {
             marker_len: None,
             merge: store.merge_options().clone(),
         };
-        let entries = materialized_diff_stream(store, tree_diff)
+        let entries = materialized_text_diff_stream(store, tree_diff, text_converters)
             .map(|MaterializedTreeDiffEntry { path, values }| {
                 let (left, right) = values?;
                 let left_content = diff_content(path.source(), left, &materialize_options)?;
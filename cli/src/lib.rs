@@ -48,5 +48,6 @@ pub mod template_builder;
 pub mod template_parser;
 pub mod templater;
 pub mod text_util;
+pub mod textconv;
 pub mod time_util;
 pub mod ui;
@@ -9,11 +9,13 @@ use std::sync::Arc;
 use futures::StreamExt as _;
 use jj_lib::backend::MergedTreeId;
 use jj_lib::conflicts::ConflictMarkerStyle;
+use jj_lib::content_filter::ContentFilterSettings;
 use jj_lib::fsmonitor::FsmonitorSettings;
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::local_working_copy::EolConversionMode;
 use jj_lib::local_working_copy::TreeState;
 use jj_lib::local_working_copy::TreeStateError;
+use jj_lib::local_working_copy::TreeStateFormat;
 use jj_lib::local_working_copy::TreeStateSettings;
 use jj_lib::matchers::EverythingMatcher;
 use jj_lib::matchers::Matcher;
@@ -153,6 +155,9 @@ pub(crate) fn check_out_trees(
             conflict_marker_style,
             eol_conversion_mode: EolConversionMode::None,
             fsmonitor_settings: FsmonitorSettings::None,
+            content_filters: ContentFilterSettings::default(),
+            num_threads: 0,
+            tree_state_format: TreeStateFormat::SingleFile,
         };
         let mut state = TreeState::init(store.clone(), wc_path, state_dir, &tree_state_settings)?;
         state.set_sparse_patterns(changed_files.clone())?;
@@ -283,6 +288,7 @@ diff editing in mind and be a little inaccurate.
             progress: None,
             start_tracking_matcher: &EverythingMatcher,
             max_new_file_size: u64::MAX,
+            max_new_file_size_overrides: vec![],
         })?;
         Ok(output_tree_state.current_tree_id().clone())
     }
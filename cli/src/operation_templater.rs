@@ -18,6 +18,7 @@ use std::any::Any;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::io;
+use std::sync::Arc;
 
 use itertools::Itertools as _;
 use jj_lib::extensions_map::ExtensionsMap;
@@ -26,6 +27,10 @@ use jj_lib::op_store::OperationId;
 use jj_lib::operation::Operation;
 use jj_lib::repo::RepoLoader;
 use jj_lib::settings::UserSettings;
+use jj_lib::signing::SignError;
+use jj_lib::signing::SignResult;
+use jj_lib::signing::Verification;
+use jj_lib::store::Store;
 
 use crate::template_builder;
 use crate::template_builder::BuildContext;
@@ -156,6 +161,7 @@ where
     Self: WrapTemplateProperty<'a, Option<Operation>>,
     Self: WrapTemplateProperty<'a, Vec<Operation>>,
     Self: WrapTemplateProperty<'a, OperationId>,
+    Self: WrapTemplateProperty<'a, Option<OperationSignature>>,
 {
 }
 
@@ -165,6 +171,7 @@ pub enum OperationTemplatePropertyKind<'a> {
     OperationOpt(BoxedTemplateProperty<'a, Option<Operation>>),
     OperationList(BoxedTemplateProperty<'a, Vec<Operation>>),
     OperationId(BoxedTemplateProperty<'a, OperationId>),
+    OperationSignatureOpt(BoxedTemplateProperty<'a, Option<OperationSignature>>),
 }
 
 /// Implements `WrapTemplateProperty<type>` for operation property types.
@@ -178,6 +185,7 @@ macro_rules! impl_operation_property_wrappers {
             OperationOpt(Option<jj_lib::operation::Operation>),
             OperationList(Vec<jj_lib::operation::Operation>),
             OperationId(jj_lib::op_store::OperationId),
+            OperationSignatureOpt(Option<$crate::operation_templater::OperationSignature>),
         });
     };
 }
@@ -193,6 +201,7 @@ impl<'a> OperationTemplatePropertyKind<'a> {
             Self::OperationOpt(_) => "Option<Operation>",
             Self::OperationList(_) => "List<Operation>",
             Self::OperationId(_) => "OperationId",
+            Self::OperationSignatureOpt(_) => "Option<CryptographicSignature>",
         }
     }
 
@@ -202,6 +211,9 @@ impl<'a> OperationTemplatePropertyKind<'a> {
             Self::OperationOpt(property) => Some(property.map(|opt| opt.is_some()).into_dyn()),
             Self::OperationList(property) => Some(property.map(|l| !l.is_empty()).into_dyn()),
             Self::OperationId(_) => None,
+            Self::OperationSignatureOpt(property) => {
+                Some(property.map(|sig| sig.is_some()).into_dyn())
+            }
         }
     }
 
@@ -220,6 +232,7 @@ impl<'a> OperationTemplatePropertyKind<'a> {
             Self::OperationOpt(property) => Some(property.into_serialize()),
             Self::OperationList(property) => Some(property.into_serialize()),
             Self::OperationId(property) => Some(property.into_serialize()),
+            Self::OperationSignatureOpt(_) => None,
         }
     }
 
@@ -229,6 +242,7 @@ impl<'a> OperationTemplatePropertyKind<'a> {
             Self::OperationOpt(_) => None,
             Self::OperationList(_) => None,
             Self::OperationId(property) => Some(property.into_template()),
+            Self::OperationSignatureOpt(_) => None,
         }
     }
 
@@ -238,6 +252,7 @@ impl<'a> OperationTemplatePropertyKind<'a> {
             (Self::OperationOpt(_), _) => None,
             (Self::OperationList(_), _) => None,
             (Self::OperationId(_), _) => None,
+            (Self::OperationSignatureOpt(_), _) => None,
         }
     }
 
@@ -250,6 +265,7 @@ impl<'a> OperationTemplatePropertyKind<'a> {
             (Self::OperationOpt(_), _) => None,
             (Self::OperationList(_), _) => None,
             (Self::OperationId(_), _) => None,
+            (Self::OperationSignatureOpt(_), _) => None,
         }
     }
 
@@ -259,6 +275,7 @@ impl<'a> OperationTemplatePropertyKind<'a> {
             (Self::OperationOpt(_), _) => None,
             (Self::OperationList(_), _) => None,
             (Self::OperationId(_), _) => None,
+            (Self::OperationSignatureOpt(_), _) => None,
         }
     }
 
@@ -271,6 +288,7 @@ impl<'a> OperationTemplatePropertyKind<'a> {
             (Self::OperationOpt(_), _) => None,
             (Self::OperationList(_), _) => None,
             (Self::OperationId(_), _) => None,
+            (Self::OperationSignatureOpt(_), _) => None,
         }
     }
 }
@@ -363,6 +381,7 @@ pub struct OperationTemplateBuildFnTable<'a, L: ?Sized, P = <L as TemplateLangua
     pub operation_methods: TemplateBuildMethodFnMap<'a, L, Operation, P>,
     pub operation_list_methods: TemplateBuildMethodFnMap<'a, L, Vec<Operation>, P>,
     pub operation_id_methods: TemplateBuildMethodFnMap<'a, L, OperationId, P>,
+    pub operation_signature_methods: TemplateBuildMethodFnMap<'a, L, OperationSignature, P>,
 }
 
 impl<L: ?Sized, P> OperationTemplateBuildFnTable<'_, L, P> {
@@ -371,6 +390,7 @@ impl<L: ?Sized, P> OperationTemplateBuildFnTable<'_, L, P> {
             operation_methods: HashMap::new(),
             operation_list_methods: HashMap::new(),
             operation_id_methods: HashMap::new(),
+            operation_signature_methods: HashMap::new(),
         }
     }
 
@@ -379,11 +399,16 @@ impl<L: ?Sized, P> OperationTemplateBuildFnTable<'_, L, P> {
             operation_methods,
             operation_list_methods,
             operation_id_methods,
+            operation_signature_methods,
         } = other;
 
         merge_fn_map(&mut self.operation_methods, operation_methods);
         merge_fn_map(&mut self.operation_list_methods, operation_list_methods);
         merge_fn_map(&mut self.operation_id_methods, operation_id_methods);
+        merge_fn_map(
+            &mut self.operation_signature_methods,
+            operation_signature_methods,
+        );
     }
 }
 
@@ -398,6 +423,7 @@ where
             operation_methods: builtin_operation_methods(),
             operation_list_methods: template_builder::builtin_unformattable_list_methods(),
             operation_id_methods: builtin_operation_id_methods(),
+            operation_signature_methods: builtin_operation_signature_methods(),
         }
     }
 
@@ -435,6 +461,13 @@ where
                 let build = template_parser::lookup_method(type_name, table, function)?;
                 build(language, diagnostics, build_ctx, property, function)
             }
+            OperationTemplatePropertyKind::OperationSignatureOpt(property) => {
+                let type_name = "CryptographicSignature";
+                let table = &self.operation_signature_methods;
+                let build = template_parser::lookup_method(type_name, table, function)?;
+                let inner_property = property.try_unwrap(type_name).into_dyn();
+                build(language, diagnostics, build_ctx, inner_property, function)
+            }
         }
     }
 }
@@ -544,6 +577,16 @@ where
             Ok(out_property.into_dyn_wrapped())
         },
     );
+    map.insert(
+        "signature",
+        |language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let store = language.repo_loader().store().clone();
+            let out_property =
+                self_property.map(move |op| OperationSignature::new(op, store.clone()));
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
     map.insert(
         "root",
         |language, _diagnostics, _build_ctx, self_property, function| {
@@ -606,3 +649,65 @@ where
     );
     map
 }
+
+/// Cryptographic signature of an operation.
+#[derive(Debug)]
+pub struct OperationSignature {
+    op: Operation,
+    store: Arc<Store>,
+}
+
+impl OperationSignature {
+    fn new(op: Operation, store: Arc<Store>) -> Option<Self> {
+        op.is_signed().then_some(Self { op, store })
+    }
+
+    fn verify(&self) -> SignResult<Verification> {
+        self.op
+            .verification(self.store.signer())
+            .transpose()
+            .expect("must have signature")
+    }
+}
+
+fn builtin_operation_signature_methods<'a, L>()
+-> TemplateBuildMethodFnMap<'a, L, OperationSignature>
+where
+    L: TemplateLanguage<'a> + OperationTemplateEnvironment + ?Sized,
+    L::Property: OperationTemplatePropertyVar<'a>,
+{
+    // Not using maplit::hashmap!{} or custom declarative macro here because
+    // code completion inside macro is quite restricted.
+    let mut map = TemplateBuildMethodFnMap::<L, OperationSignature>::new();
+    map.insert(
+        "status",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.and_then(|sig| match sig.verify() {
+                Ok(verification) => Ok(verification.status.to_string()),
+                Err(SignError::InvalidSignatureFormat) => Ok("invalid".to_string()),
+                Err(err) => Err(err.into()),
+            });
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map.insert(
+        "key",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.and_then(|sig| Ok(sig.verify()?.key.unwrap_or_default()));
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map.insert(
+        "display",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.and_then(|sig| Ok(sig.verify()?.display.unwrap_or_default()));
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map
+}
@@ -14,6 +14,7 @@
 
 //! Utility for parsing and evaluating user-provided revset expressions.
 
+use std::error;
 use std::io;
 use std::sync::Arc;
 
@@ -24,6 +25,9 @@ use jj_lib::config::ConfigGetError;
 use jj_lib::config::ConfigNamePathBuf;
 use jj_lib::config::ConfigSource;
 use jj_lib::config::StackedConfig;
+use jj_lib::fileset;
+use jj_lib::fileset::FilesetDiagnostics;
+use jj_lib::fileset::FilesetExpression;
 use jj_lib::id_prefix::IdPrefixContext;
 use jj_lib::ref_name::RefNameBuf;
 use jj_lib::repo::Repo;
@@ -35,6 +39,7 @@ use jj_lib::revset::RevsetDiagnostics;
 use jj_lib::revset::RevsetEvaluationError;
 use jj_lib::revset::RevsetExpression;
 use jj_lib::revset::RevsetExtensions;
+use jj_lib::revset::RevsetFilterPredicate;
 use jj_lib::revset::RevsetIteratorExt as _;
 use jj_lib::revset::RevsetParseContext;
 use jj_lib::revset::RevsetParseError;
@@ -42,6 +47,8 @@ use jj_lib::revset::RevsetResolutionError;
 use jj_lib::revset::SymbolResolver;
 use jj_lib::revset::SymbolResolverExtension;
 use jj_lib::revset::UserRevsetExpression;
+use jj_lib::settings::UserSettings;
+use jj_lib::str_util::StringExpression;
 use thiserror::Error;
 
 use crate::command_error::CommandError;
@@ -203,6 +210,77 @@ pub fn load_revset_aliases(
     Ok(aliases_map)
 }
 
+/// Entry in the `signing.trust-policy` table.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TrustRule {
+    /// Signature key, matched exactly.
+    signer: String,
+    /// Revisions the signer is trusted to sign.
+    revisions: Option<String>,
+    /// Fileset the commits signed by the signer may modify.
+    paths: Option<String>,
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid trust policy rule `signing.trust-policy.{name}`")]
+pub struct TrustRuleError {
+    name: String,
+    #[source]
+    source: Box<dyn error::Error + Send + Sync>,
+}
+
+/// Builds the `trusted_signature()` expression from the rules in the
+/// `signing.trust-policy` table.
+///
+/// The revisions and paths of the rules are parsed in the given `context`,
+/// which shouldn't define `trusted_signature()` in terms of these rules.
+pub fn parse_trusted_signature_expression(
+    settings: &UserSettings,
+    context: &RevsetParseContext,
+) -> Result<Arc<UserRevsetExpression>, TrustRuleError> {
+    let expressions: Vec<_> = settings
+        .table_keys("signing.trust-policy")
+        .sorted()
+        .map(|name| {
+            parse_trust_rule(settings, name, context).map_err(|source| TrustRuleError {
+                name: name.to_owned(),
+                source,
+            })
+        })
+        .try_collect()?;
+    Ok(RevsetExpression::union_all(&expressions))
+}
+
+fn parse_trust_rule(
+    settings: &UserSettings,
+    name: &str,
+    context: &RevsetParseContext,
+) -> Result<Arc<UserRevsetExpression>, Box<dyn error::Error + Send + Sync>> {
+    let rule: TrustRule = settings.get(["signing", "trust-policy", name])?;
+    let predicate = RevsetFilterPredicate::SignedBy(StringExpression::exact(rule.signer));
+    let mut expression = RevsetExpression::filter(predicate);
+    if let Some(revisions) = &rule.revisions {
+        let mut diagnostics = RevsetDiagnostics::new();
+        let revisions = revset::parse(&mut diagnostics, revisions, context)?;
+        expression = expression.intersection(&revisions);
+    }
+    if let Some(paths) = &rule.paths {
+        let workspace = context
+            .workspace
+            .as_ref()
+            .ok_or("Cannot resolve file pattern without workspace")?;
+        let mut diagnostics = FilesetDiagnostics::new();
+        let paths = fileset::parse(&mut diagnostics, paths, workspace.path_converter)?;
+        // Commits modifying files outside of the paths aren't trusted.
+        let outside = FilesetExpression::all().difference(paths);
+        expression = expression.minus(&RevsetExpression::filter(RevsetFilterPredicate::File(
+            outside,
+        )));
+    }
+    Ok(expression)
+}
+
 /// Wraps the given `IdPrefixContext` in `SymbolResolver` to be passed in to
 /// `evaluate()`.
 pub fn default_symbol_resolver<'a>(
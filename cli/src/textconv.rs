@@ -0,0 +1,311 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion of file contents to text before diffing, configured by
+//! `diff.textconv`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use itertools::Itertools as _;
+use jj_lib::annotate::FileContentConverter;
+use jj_lib::backend::BackendError;
+use jj_lib::backend::BackendResult;
+use jj_lib::backend::FileId;
+use jj_lib::config::ConfigGetError;
+use jj_lib::conflicts::MaterializedTreeValue;
+use jj_lib::content_hash::blake2b_hash;
+use jj_lib::file_util::persist_content_addressed_temp_file;
+use jj_lib::file_util::prune_least_recently_modified;
+use jj_lib::file_util::touch_if_older_than;
+use jj_lib::fileset;
+use jj_lib::fileset::FilesetDiagnostics;
+use jj_lib::fileset::FilesetExpression;
+use jj_lib::hex_util;
+use jj_lib::matchers::Matcher;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::repo_path::RepoPathUiConverter;
+use jj_lib::settings::UserSettings;
+use thiserror::Error;
+
+use crate::config::CommandNameAndArgs;
+use crate::config::find_all_variables;
+
+/// Error from running a text converter.
+#[derive(Debug, Error)]
+pub enum TextConvError {
+    #[error("Failed to run text converter `{name}`")]
+    Spawn {
+        name: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Text converter `{name}` exited with {status}:\n{stderr}")]
+    ExitStatus {
+        name: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+}
+
+/// Maximum number of converted contents kept in the cache directory. Least
+/// recently used entries are evicted first.
+const MAX_CACHE_ENTRIES: usize = 4096;
+
+/// Cache entries used within this interval aren't marked as used again, so
+/// reading cached contents doesn't write to the file system every time.
+const CACHE_TOUCH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Commands converting file contents to text, configured by `diff.textconv`.
+#[derive(Clone, Debug, Default)]
+pub struct TextConverters {
+    /// Rules sorted by name. The first rule matching a file is used.
+    rules: Vec<TextConverterRule>,
+    /// Cache of converted contents, shared between clones.
+    cache: Option<Arc<TextConvCache>>,
+}
+
+/// Directory where converted contents are cached. The least recently used
+/// entries are evicted when the last reference is dropped, so the cache is
+/// pruned at most once per command.
+#[derive(Debug)]
+struct TextConvCache {
+    dir: PathBuf,
+    /// Whether new entries were written to the cache.
+    written: AtomicBool,
+}
+
+impl Drop for TextConvCache {
+    fn drop(&mut self) {
+        if !*self.written.get_mut() {
+            return;
+        }
+        if let Err(err) = prune_least_recently_modified(&self.dir, MAX_CACHE_ENTRIES) {
+            tracing::warn!(?err, "failed to prune text converter cache");
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TextConverterRule {
+    name: String,
+    file_matcher: Arc<dyn Matcher>,
+    command: CommandNameAndArgs,
+    /// True if the file path is passed to the command.
+    uses_path: bool,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawTextConverterRule {
+    command: CommandNameAndArgs,
+    patterns: Vec<String>,
+}
+
+impl TextConverters {
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        let rules = settings
+            .table_keys("diff.textconv")
+            .sorted()
+            .map(|name| {
+                let rule: RawTextConverterRule = settings.get(["diff", "textconv", name])?;
+                let path_converter = RepoPathUiConverter::Fs {
+                    cwd: "".into(),
+                    base: "".into(),
+                };
+                let expressions: Vec<_> = rule
+                    .patterns
+                    .iter()
+                    .map(|text| {
+                        fileset::parse(&mut FilesetDiagnostics::new(), text, &path_converter)
+                    })
+                    .try_collect()
+                    .map_err(|err| ConfigGetError::Type {
+                        name: format!("diff.textconv.{name}"),
+                        error: err.into(),
+                        source_path: None,
+                    })?;
+                let (_, args) = rule.command.split_name_and_args();
+                let uses_path = find_all_variables(&args).contains(&"path");
+                Ok(TextConverterRule {
+                    name: name.to_owned(),
+                    file_matcher: FilesetExpression::union_all(expressions)
+                        .to_matcher()
+                        .into(),
+                    command: rule.command,
+                    uses_path,
+                })
+            })
+            .try_collect()?;
+        Ok(Self { rules, cache: None })
+    }
+
+    /// Caches converted contents in the `cache_dir`.
+    pub fn with_cache_dir(mut self, cache_dir: PathBuf) -> Self {
+        self.cache = Some(Arc::new(TextConvCache {
+            dir: cache_dir,
+            written: AtomicBool::new(false),
+        }));
+        self
+    }
+
+    /// Returns true if no converters are configured.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn rule_for(&self, path: &RepoPath) -> Option<&TextConverterRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.file_matcher.matches(path))
+    }
+
+    /// Converts the `content` of the file at `path` to text. Returns the
+    /// content unchanged if no converter is configured for the file.
+    pub fn to_text(
+        &self,
+        path: &RepoPath,
+        id: &FileId,
+        content: Vec<u8>,
+    ) -> Result<Vec<u8>, TextConvError> {
+        let Some(rule) = self.rule_for(path) else {
+            return Ok(content);
+        };
+        let cache_path = self.cache.as_ref().map(|cache| {
+            // The output is assumed to depend only on the file content and the
+            // path passed to the command, but the cache shouldn't be reused if
+            // the command is changed.
+            let key = (
+                rule.command.to_string(),
+                rule.uses_path
+                    .then(|| path.as_internal_file_string().to_owned()),
+                id.hex(),
+            );
+            let key_hash = blake2b_hash(&key);
+            cache.dir.join(hex_util::encode_hex(&key_hash[..32]))
+        });
+        if let Some(cache_path) = &cache_path
+            && let Ok(text) = fs::read(cache_path)
+        {
+            // Bump the mtime so the entry is evicted last.
+            touch_if_older_than(cache_path, CACHE_TOUCH_INTERVAL).ok();
+            return Ok(text);
+        }
+        let text = run_converter(rule, path, &content)?;
+        if let Some(cache) = &self.cache
+            && let Some(cache_path) = &cache_path
+        {
+            match write_cache(cache_path, &text) {
+                Ok(()) => cache.written.store(true, Ordering::Relaxed),
+                Err(err) => {
+                    tracing::warn!(?err, ?cache_path, "failed to cache converted text");
+                }
+            }
+        }
+        Ok(text)
+    }
+
+    /// Converts the content of the materialized file `value` to text if a
+    /// converter is configured for the file.
+    pub async fn convert_value(
+        &self,
+        path: &RepoPath,
+        value: MaterializedTreeValue,
+    ) -> BackendResult<MaterializedTreeValue> {
+        match value {
+            MaterializedTreeValue::File(mut file) if self.rule_for(path).is_some() => {
+                let content = file.read_all(path).await?;
+                let text = FileContentConverter::convert(self, path, &file.id, content)?;
+                file.reader = Box::pin(io::Cursor::new(text));
+                Ok(MaterializedTreeValue::File(file))
+            }
+            _ => Ok(value),
+        }
+    }
+}
+
+impl FileContentConverter for TextConverters {
+    fn convert(&self, path: &RepoPath, id: &FileId, content: Vec<u8>) -> BackendResult<Vec<u8>> {
+        self.to_text(path, id, content)
+            .map_err(|err| BackendError::ReadFile {
+                path: path.to_owned(),
+                id: id.clone(),
+                source: err.into(),
+            })
+    }
+}
+
+/// Runs the converter command with the `content` as its input.
+fn run_converter(
+    rule: &TextConverterRule,
+    path: &RepoPath,
+    content: &[u8],
+) -> Result<Vec<u8>, TextConvError> {
+    let vars: HashMap<&str, &str> = HashMap::from([("path", path.as_internal_file_string())]);
+    let mut command = rule.command.to_command_with_variables(&vars);
+    tracing::debug!(?command, ?path, "spawning text converter");
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|source| TextConvError::Spawn {
+            name: rule.name.clone(),
+            source,
+        })?;
+    let mut stdin = child.stdin.take().unwrap();
+    let output = std::thread::scope(|s| {
+        s.spawn(move || {
+            // The converter may exit without reading the whole input.
+            stdin.write_all(content).ok();
+        });
+        child.wait_with_output()
+    })
+    .map_err(|source| TextConvError::Spawn {
+        name: rule.name.clone(),
+        source,
+    })?;
+    tracing::debug!(?command, ?output.status, "text converter exited:");
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(TextConvError::ExitStatus {
+            name: rule.name.clone(),
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr)
+                .trim_end()
+                .to_owned(),
+        })
+    }
+}
+
+fn write_cache(cache_path: &Path, text: &[u8]) -> io::Result<()> {
+    let dir = cache_path.parent().unwrap();
+    fs::create_dir_all(dir)?;
+    let mut temp_file = tempfile::NamedTempFile::new_in(dir)?;
+    temp_file.write_all(text)?;
+    persist_content_addressed_temp_file(temp_file, cache_path)?;
+    Ok(())
+}
@@ -13,9 +13,11 @@ mod test_absorb_command;
 mod test_acls;
 mod test_advance_bookmarks;
 mod test_alias;
+mod test_arrange_command;
 mod test_bisect_command;
 mod test_bookmark_command;
 mod test_builtin_aliases;
+mod test_bundle_command;
 mod test_commit_command;
 mod test_commit_template;
 mod test_completion;
@@ -24,6 +26,7 @@ mod test_config_command;
 mod test_config_schema;
 mod test_copy_detection;
 mod test_debug_command;
+mod test_debug_init_native_command;
 mod test_debug_init_simple_command;
 mod test_describe_command;
 mod test_diff_command;
@@ -48,6 +51,7 @@ mod test_git_private_commits;
 mod test_git_push;
 mod test_git_remotes;
 mod test_git_root;
+mod test_git_sync;
 mod test_gitignores;
 mod test_global_opts;
 mod test_help_command;
@@ -61,6 +65,7 @@ mod test_next_prev_commands;
 mod test_op_revert_command;
 mod test_operations;
 mod test_parallelize_command;
+mod test_patch_command;
 mod test_rebase_command;
 mod test_repo_change_report;
 mod test_resolve_command;
@@ -528,3 +528,84 @@ fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
     let template = r#"separate(" ", "[" ++ change_id.short(3) ++ "]", bookmarks)"#;
     work_dir.run_jj(["log", "-T", template])
 }
+
+#[test]
+fn test_abandon_landed() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"revset-aliases."trunk()" = "main""#);
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    let log_template = r#"separate(" ", description.first_line(), bookmarks) ++ "\n""#;
+
+    work_dir.write_file("file", "1\n2\n3\n");
+    work_dir.run_jj(["describe", "-m", "base"]).success();
+    work_dir.run_jj(["new", "-m", "local1"]).success();
+    work_dir.write_file("file", "1\ntwo\n3\n");
+    work_dir.run_jj(["new", "-m", "local2"]).success();
+    work_dir.write_file("other", "other\n");
+    // local1 is picked upstream on top of another change
+    work_dir
+        .run_jj(["new", "description(base)", "-m", "upstream1"])
+        .success();
+    work_dir.write_file("file", "one\n2\n3\n");
+    work_dir.run_jj(["new", "-m", "upstream2"]).success();
+    work_dir.write_file("file", "one\ntwo\n3\n");
+    work_dir
+        .run_jj(["bookmark", "create", "-r@", "main"])
+        .success();
+
+    let output = work_dir.run_jj([
+        "log",
+        "-r",
+        "landed(trunk(), mutable())",
+        "-T",
+        log_template,
+    ]);
+    insta::assert_snapshot!(output, @r"
+    ○  local1
+    │
+    ~
+    [EOF]
+    ");
+
+    // Revisions based on old trunk() commits aren't checked by default
+    let output = work_dir.run_jj([
+        "abandon",
+        "--landed",
+        "--config=revsets.abandon-landed='mutable() & ancestors(trunk(), 2)::'",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    No revisions to abandon.
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["abandon", "--landed"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Abandoned 1 commits:
+      kkmpptxz f733f380 local1
+    Rebased 1 descendant commits onto parents of abandoned commits
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["log", "-T", log_template]);
+    insta::assert_snapshot!(output, @r"
+    @
+    ◆  upstream2 main
+    ~  (elided revisions)
+    │ ○  local2
+    ├─╯
+    ◆  base
+    │
+    ~
+    [EOF]
+    ");
+
+    // Nothing else landed
+    let output = work_dir.run_jj(["abandon", "--landed"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    No revisions to abandon.
+    [EOF]
+    ");
+}
@@ -0,0 +1,284 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::CommandOutput;
+use crate::common::TestEnvironment;
+use crate::common::TestWorkDir;
+use crate::common::create_commit;
+
+#[test]
+fn test_arrange_no_change() {
+    let mut test_env = TestEnvironment::default();
+    let edit_script = test_env.set_up_fake_editor();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    create_commit(&work_dir, "a", &[]);
+    create_commit(&work_dir, "b", &["a"]);
+    create_commit(&work_dir, "c", &["a"]);
+    create_commit(&work_dir, "d", &["b", "c"]);
+
+    std::fs::write(&edit_script, "dump editor").unwrap();
+    let output = work_dir.run_jj(["arrange"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+    insta::assert_snapshot!(
+        std::fs::read_to_string(test_env.env_root().join("editor")).unwrap(), @r#"
+    pick 7d980be7a1d4 a
+    pick 123b4d91f6e5 b
+    edit-parents 991a7501d660 7d980be7a1d4 c
+    edit-parents 94178b38da44 123b4d91f6e5,991a7501d660 d
+
+    JJ: Reorder the lines above, and change the action at the start of each line:
+    JJ:   pick <commit>                     Keep the revision
+    JJ:   reword <commit>                   Keep the revision, and edit its description
+    JJ:   squash <commit>                   Squash into the previous revision
+    JJ:   fixup <commit>                    Squash into the previous revision, and discard
+    JJ:                                     the description
+    JJ:   drop <commit>                     Abandon the revision, and discard its changes
+    JJ:   edit-parents <commit> <parents>   Rebase onto the comma-separated parents
+    JJ:
+    JJ: Each revision is rebased onto the revision on the previous line.
+    JJ: Remove all lines to abort.
+    JJ: Lines starting with "JJ:" (like this one) will be removed.
+    "#);
+
+    // Removing all lines aborts
+    std::fs::write(&edit_script, "write\n").unwrap();
+    let output = work_dir.run_jj(["arrange"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_arrange_reorder() {
+    let mut test_env = TestEnvironment::default();
+    let edit_script = test_env.set_up_fake_editor();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    create_commit(&work_dir, "a", &[]);
+    create_commit(&work_dir, "b", &["a"]);
+    create_commit(&work_dir, "c", &["b"]);
+    create_commit(&work_dir, "d", &["c"]);
+
+    // Descendants of the arranged revisions are rebased
+    std::fs::write(&edit_script, "write\npick c\npick a\npick b\n").unwrap();
+    let output = work_dir.run_jj(["arrange", "a::c"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Rewrote 3 commits
+    Rebased 1 descendant commits
+    Working copy  (@) now at: vruxwmqv 8e8ec52a d | d
+    Parent commit (@-)      : royxmykx f380c713 c | c
+    Added 0 files, modified 0 files, removed 2 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  d d
+    │ ○  b b
+    │ ○  a a
+    ├─╯
+    ○  c c
+    ◆
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_arrange_squash_fixup_drop_reword() {
+    let mut test_env = TestEnvironment::default();
+    let edit_script = test_env.set_up_fake_editor();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    create_commit(&work_dir, "a", &[]);
+    create_commit(&work_dir, "b", &["a"]);
+    create_commit(&work_dir, "c", &["b"]);
+    create_commit(&work_dir, "d", &["c"]);
+    create_commit(&work_dir, "e", &["d"]);
+
+    std::fs::write(
+        &edit_script,
+        [
+            "write\npick a\nfixup b\ndrop c\nreword d\nsquash e\n",
+            "next invocation\n",
+            "write\nd reworded",
+            "next invocation\n",
+            "dump editor",
+            "write\ncombined",
+        ]
+        .join("\0"),
+    )
+    .unwrap();
+    let output = work_dir.run_jj(["arrange", "a::"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Rewrote 1 commits
+    Squashed 2 commits
+    Abandoned 1 commits
+    Working copy  (@) now at: vruxwmqv a8e98acf d e | combined
+    Parent commit (@-)      : rlvkpnrz 68f9a818 a b c | a
+    Added 0 files, modified 0 files, removed 1 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(
+        std::fs::read_to_string(test_env.env_root().join("editor")).unwrap(), @r#"
+    JJ: Enter a description for the combined commit.
+    JJ: Description from the destination commit:
+    d reworded
+
+    JJ: Description from source commit:
+    e
+
+    JJ: Change ID: vruxwmqv
+    JJ: This commit contains the following changes:
+    JJ:     A d
+    JJ:     A e
+    JJ:
+    JJ: Lines starting with "JJ:" (like this one) will be removed.
+    "#);
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  combined d e
+    ○  a a b
+    ◆
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_arrange_edit_parents() {
+    let mut test_env = TestEnvironment::default();
+    let edit_script = test_env.set_up_fake_editor();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    create_commit(&work_dir, "a", &[]);
+    create_commit(&work_dir, "b", &["a"]);
+    create_commit(&work_dir, "c", &["b"]);
+
+    std::fs::write(&edit_script, "write\npick a\npick b\nedit-parents c a c\n").unwrap();
+    let output = work_dir.run_jj(["arrange", "a::c"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Rewrote 1 commits
+    Working copy  (@) now at: royxmykx 8f0b3808 c | c
+    Parent commit (@-)      : rlvkpnrz 7d980be7 a | a
+    Added 0 files, modified 0 files, removed 1 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  c c
+    │ ○  b b
+    ├─╯
+    ○  a a
+    ◆
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_arrange_errors() {
+    let mut test_env = TestEnvironment::default();
+    let edit_script = test_env.set_up_fake_editor();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    create_commit(&work_dir, "a", &[]);
+    create_commit(&work_dir, "b", &["a"]);
+    create_commit(&work_dir, "c", &["b"]);
+
+    let run_with_todo = |todo: &str| {
+        std::fs::write(&edit_script, format!("write\n{todo}")).unwrap();
+        work_dir.run_jj(["arrange", "a::b"])
+    };
+    insta::assert_snapshot!(run_with_todo("pick a\n"), @r"
+    ------- stderr -------
+    Error: Revision 123b4d91f6e5 is missing from the todo list
+    Hint: Use `drop` to abandon a revision.
+    [EOF]
+    [exit status: 1]
+    ");
+    insta::assert_snapshot!(run_with_todo("pick a\nmove b\n"), @r"
+    ------- stderr -------
+    Error: Unknown action `move` in todo list line: move b
+    Hint: Valid actions are pick, reword, squash, fixup, drop, and edit-parents.
+    [EOF]
+    [exit status: 1]
+    ");
+    insta::assert_snapshot!(run_with_todo("pick a\npick b\npick c\n"), @r"
+    ------- stderr -------
+    Error: Revision c is not one of the revisions being arranged
+    [EOF]
+    [exit status: 1]
+    ");
+    insta::assert_snapshot!(run_with_todo("pick a\npick b\npick a\n"), @r"
+    ------- stderr -------
+    Error: Revision a appears more than once in the todo list
+    [EOF]
+    [exit status: 1]
+    ");
+    insta::assert_snapshot!(run_with_todo("squash a\npick b\n"), @r"
+    ------- stderr -------
+    Error: Cannot squash revision 7d980be7a1d4 because there is no previous revision
+    [EOF]
+    [exit status: 1]
+    ");
+    insta::assert_snapshot!(run_with_todo("edit-parents b a\npick a\n"), @r"
+    ------- stderr -------
+    Error: Revision 123b4d91f6e5 must be listed after its parent 7d980be7a1d4
+    [EOF]
+    [exit status: 1]
+    ");
+    insta::assert_snapshot!(run_with_todo("pick a\nedit-parents b b\n"), @r"
+    ------- stderr -------
+    Error: Revision 123b4d91f6e5 cannot be rebased onto itself
+    [EOF]
+    [exit status: 1]
+    ");
+    insta::assert_snapshot!(run_with_todo("edit-parents a b\nedit-parents b a\n"), @r"
+    ------- stderr -------
+    Error: Revision 7d980be7a1d4 must be listed after its parent 123b4d91f6e5
+    [EOF]
+    [exit status: 1]
+    ");
+    insta::assert_snapshot!(run_with_todo("drop a\nedit-parents b a\n"), @r"
+    ------- stderr -------
+    Error: Revision 123b4d91f6e5 cannot be rebased onto the dropped revision 7d980be7a1d4
+    [EOF]
+    [exit status: 1]
+    ");
+    insta::assert_snapshot!(run_with_todo("pick a\nedit-parents b c\n"), @r"
+    ------- stderr -------
+    Error: Revision 123b4d91f6e5 cannot be rebased onto dffaa0d4dacc, which is a descendant of the arranged revision 7d980be7a1d4
+    [EOF]
+    [exit status: 1]
+    ");
+
+    let output = work_dir.run_jj(["arrange", "root()"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: The root commit 000000000000 is immutable
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[must_use]
+fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
+    let template =
+        r#"separate(" ", description.first_line(), self.diff().files().map(|e| e.path()))"#;
+    work_dir.run_jj(["log", "-T", template])
+}
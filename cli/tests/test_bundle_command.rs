@@ -0,0 +1,174 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::TestEnvironment;
+use crate::common::TestWorkDir;
+
+#[must_use]
+fn get_log_output(work_dir: &TestWorkDir) -> crate::common::CommandOutput {
+    let template = r#"change_id.short() ++ " " ++ description.first_line() ++ " " ++ bookmarks"#;
+    work_dir.run_jj(["log", "-r", "all()", "-T", template])
+}
+
+#[test]
+fn test_bundle_roundtrip() {
+    let test_env = TestEnvironment::default();
+    // Change ids should be transferred even if they aren't in the commits.
+    test_env.add_config("git.write-change-id-header = false");
+    test_env.run_jj_in(".", ["git", "init", "source"]).success();
+    test_env.run_jj_in(".", ["git", "init", "dest"]).success();
+    let source_dir = test_env.work_dir("source");
+    let dest_dir = test_env.work_dir("dest");
+    source_dir.write_file("file", "a\n");
+    source_dir.run_jj(["commit", "-m", "A"]).success();
+    source_dir.write_file("file", "b\n");
+    source_dir.run_jj(["commit", "-m", "B"]).success();
+    source_dir
+        .run_jj(["bookmark", "create", "-r", "@-", "feature"])
+        .success();
+    source_dir.write_file("file", "c\n");
+    source_dir.run_jj(["describe", "-m", "C"]).success();
+    insta::assert_snapshot!(get_log_output(&source_dir), @r"
+    @  zsuskulnrvyr C
+    ○  kkmpptxzrspx B feature
+    ○  qpvuntsmwlqt A
+    ◆  zzzzzzzzzzzz
+    [EOF]
+    ");
+
+    let output = source_dir.run_jj(["bundle", "create", "-r", "::@-", "../base.bundle"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Wrote 2 commits and 1 bookmarks to ../base.bundle
+    [EOF]
+    ");
+    // The bundle can be read by Git
+    let output = std::process::Command::new("git")
+        .current_dir(test_env.env_root())
+        .args(["bundle", "list-heads", "base.bundle"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    insta::assert_snapshot!(String::from_utf8(output.stdout).unwrap(), @r"
+    bbfe7c408be2e71390833a5ef5e7587f383b2bf8 refs/heads/feature
+    479e527375253ddf13f7d64eab359aa254e565f1 refs/jj/bundle
+    ");
+
+    let output = source_dir.run_jj(["bundle", "create", "-r", "@", "../top.bundle"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Wrote 1 commits and 0 bookmarks to ../top.bundle
+    [EOF]
+    ");
+
+    // The parent of the bundled commit is missing
+    let output = dest_dir.run_jj(["bundle", "import", "../top.bundle"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Git process failed: External git program failed:
+    error: Repository lacks these prerequisite commits:
+    error: bbfe7c408be2e71390833a5ef5e7587f383b2bf8 
+
+    [EOF]
+    [exit status: 1]
+    ");
+
+    let output = dest_dir.run_jj(["bundle", "import", "../base.bundle"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    bookmark: feature@bundle [new] untracked
+    Imported 2 commits from ../base.bundle
+    [EOF]
+    ");
+    let output = dest_dir.run_jj(["bundle", "import", "../top.bundle"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Imported 1 commits from ../top.bundle
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&dest_dir), @r"
+    @  rlvkpnrzqnoo
+    │ ○  zsuskulnrvyr C
+    │ ◆  kkmpptxzrspx B feature@bundle
+    │ ◆  qpvuntsmwlqt A
+    ├─╯
+    ◆  zzzzzzzzzzzz
+    [EOF]
+    ");
+
+    // Importing again doesn't change anything
+    let output = dest_dir.run_jj(["bundle", "import", "../base.bundle"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_bundle_import_git_bundle() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "source"]).success();
+    test_env.run_jj_in(".", ["git", "init", "dest"]).success();
+    let source_dir = test_env.work_dir("source");
+    let dest_dir = test_env.work_dir("dest");
+    source_dir.write_file("file", "a\n");
+    source_dir.run_jj(["commit", "-m", "A"]).success();
+    source_dir
+        .run_jj(["bookmark", "create", "-r", "@-", "main"])
+        .success();
+    source_dir.run_jj(["git", "export"]).success();
+
+    // A bundle created by Git has no jj metadata
+    let git_dir = source_dir.root().join(".jj/repo/store/git");
+    let bundle_path = test_env.env_root().join("git.bundle");
+    let status = std::process::Command::new("git")
+        .arg("--git-dir")
+        .arg(&git_dir)
+        .args(["bundle", "create"])
+        .arg(&bundle_path)
+        .arg("refs/heads/main")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = dest_dir.run_jj(["bundle", "import", "../git.bundle", "--remote", "upstream"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    bookmark: main@upstream [new] untracked
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&dest_dir), @r"
+    @  rlvkpnrzqnoo
+    │ ◆  qpvuntsmwlqt A main@upstream
+    ├─╯
+    ◆  zzzzzzzzzzzz
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_bundle_create_no_revisions() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    let output = work_dir.run_jj(["bundle", "create", "-r", "root()", "../out.bundle"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: No revisions to bundle
+    [EOF]
+    [exit status: 1]
+    ");
+}
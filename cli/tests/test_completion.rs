@@ -1178,6 +1178,8 @@ fn test_merge_tools() {
     :name-only
     :git
     :color-words
+    :side-by-side
+    :json
     diffedit3
     diffedit3-ssh
     difft
@@ -167,6 +167,11 @@ fn test_debug_index() {
     === Changed paths ===
     Indexed commits: none
     Stats per level:
+    === Reachability bitmaps ===
+    Number of heads: 0
+    === Text index ===
+    Indexed commits: none
+    Stats per level:
     [EOF]
     ");
 
@@ -196,6 +201,96 @@ fn test_debug_index() {
         Number of commits: 1
         Number of changed paths: 0
         Number of paths: 0
+        Bloom filter size: 0 bytes
+        Name: [hash]
+    === Reachability bitmaps ===
+    Number of heads: 0
+    === Text index ===
+    Indexed commits: none
+    Stats per level:
+    [EOF]
+    ");
+
+    // Build reachability bitmaps of trunk() and bookmarks
+    work_dir
+        .run_jj(["bookmark", "create", "-r@", "foo"])
+        .success();
+    let output = work_dir.run_jj(["debug", "index-bitmaps"]);
+    assert_snapshot!(output, @r"
+    ------- stderr -------
+    Built reachability bitmaps for 2 revisions.
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["debug", "index"]);
+    assert_snapshot!(filter_index_stats(output), @r"
+    === Commits ===
+    Number of commits: 2
+    Number of merges: 0
+    Max generation number: 1
+    Number of heads: 1
+    Number of changes: 2
+    Stats per level:
+      Level 0:
+        Number of commits: 2
+        Name: [hash]
+    === Changed paths ===
+    Indexed commits: 1..2
+    Stats per level:
+      Level 0:
+        Number of commits: 1
+        Number of changed paths: 0
+        Number of paths: 0
+        Bloom filter size: 0 bytes
+        Name: [hash]
+    === Reachability bitmaps ===
+    Number of heads: 2
+    Number of reachable commits: 3
+    Name: [hash]
+    === Text index ===
+    Indexed commits: none
+    Stats per level:
+    [EOF]
+    ");
+
+    // Build text index
+    let output = work_dir.run_jj(["debug", "index-text"]);
+    assert_snapshot!(output, @r"
+    ------- stderr -------
+    Finished indexing 0..2 commits.
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["debug", "index"]);
+    assert_snapshot!(filter_index_stats(output), @r"
+    === Commits ===
+    Number of commits: 2
+    Number of merges: 0
+    Max generation number: 1
+    Number of heads: 1
+    Number of changes: 2
+    Stats per level:
+      Level 0:
+        Number of commits: 2
+        Name: [hash]
+    === Changed paths ===
+    Indexed commits: 1..2
+    Stats per level:
+      Level 0:
+        Number of commits: 1
+        Number of changed paths: 0
+        Number of paths: 0
+        Bloom filter size: 0 bytes
+        Name: [hash]
+    === Reachability bitmaps ===
+    Number of heads: 2
+    Number of reachable commits: 3
+    Name: [hash]
+    === Text index ===
+    Indexed commits: 0..2
+    Stats per level:
+      Level 0:
+        Number of commits: 2
+        Number of keys: 26
+        Number of commit keys: 26
         Name: [hash]
     [EOF]
     ");
@@ -226,6 +321,11 @@ fn test_debug_reindex() {
     === Changed paths ===
     Indexed commits: none
     Stats per level:
+    === Reachability bitmaps ===
+    Number of heads: 0
+    === Text index ===
+    Indexed commits: none
+    Stats per level:
     [EOF]
     ");
     let output = work_dir.run_jj(["debug", "reindex"]);
@@ -249,6 +349,50 @@ fn test_debug_reindex() {
     === Changed paths ===
     Indexed commits: none
     Stats per level:
+    === Reachability bitmaps ===
+    Number of heads: 0
+    === Text index ===
+    Indexed commits: none
+    Stats per level:
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_debug_snapshot() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "contents");
+    test_env.add_config("snapshot.max-new-file-size = 5");
+
+    let output = work_dir.run_jj([
+        "debug",
+        "snapshot",
+        "--stats",
+        "--config=working-copy.threads=2",
+    ]);
+    let regex = Regex::new(r"(?m): [0-9.]+[mnµ]?s$").unwrap();
+    assert_snapshot!(
+        output.normalize_stdout_with(|text| regex.replace_all(&text, ": [duration]").into_owned()),
+        @r"
+    Threads: 2
+    Untracked paths: 1
+    Query fsmonitor: [duration]
+    Traverse filesystem: [duration]
+    Write tree: [duration]
+    [EOF]
+    ------- stderr -------
+    Warning: Refused to snapshot some files:
+      file: 8.0B (8 bytes); the maximum size allowed is 5.0B (5 bytes)
+    Hint: This is to prevent large files from being added by accident. You can fix this by:
+      - Adding the file to `.gitignore`
+      - Run `jj config set --repo snapshot.max-new-file-size 8`
+        This will increase the maximum file size allowed for new files, in this repository only.
+      - Run `jj --config snapshot.max-new-file-size=8 st`
+        This will increase the maximum file size allowed for new files, for this command only.
+      - Run `jj file track --force-large file`
+        This will track the files regardless of their size.
     [EOF]
     ");
 }
@@ -330,7 +474,61 @@ fn test_debug_tree() {
     );
 }
 
+#[test]
+fn test_debug_lock() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "simple"]).success();
+    let output = test_env.work_dir("simple").run_jj(["debug", "lock"]);
+    assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: This command requires the network operation heads store
+    [EOF]
+    [exit status: 1]
+    ");
+
+    test_env.add_config(r#"operation.heads-store = "network""#);
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    let lock_path = work_dir.root().join(".jj/repo/op_heads/heads/lock");
+    let output = work_dir.run_jj(["debug", "lock"]);
+    assert_snapshot!(output, @r"
+    ------- stderr -------
+    The lock is not held
+    [EOF]
+    ");
+
+    // The lock is broken even if the owner may still be alive
+    let lock_content = "hostname = \"other-host\"\npid = 1234\ntoken = \"0123\"\n";
+    std::fs::write(&lock_path, lock_content).unwrap();
+    let regex = Regex::new(r"(?m)^Renewed: .* ago$").unwrap();
+    let output = work_dir.run_jj(["debug", "lock"]);
+    assert_snapshot!(
+        output.normalize_stdout_with(|text| regex.replace_all(&text, "Renewed: [age]").into_owned()),
+        @r"
+    Lock file: $TEST_ENV/repo/.jj/repo/op_heads/heads/lock
+    Owner host: other-host
+    Owner pid: 1234
+    Renewed: [age]
+    [EOF]
+    ");
+    assert!(lock_path.exists());
+    let output = work_dir.run_jj(["debug", "lock", "--break"]);
+    assert_snapshot!(
+        output.normalize_stdout_with(|text| regex.replace_all(&text, "Renewed: [age]").into_owned()),
+        @r"
+    Lock file: $TEST_ENV/repo/.jj/repo/op_heads/heads/lock
+    Owner host: other-host
+    Owner pid: 1234
+    Renewed: [age]
+    [EOF]
+    ------- stderr -------
+    Removed lock file
+    [EOF]
+    ");
+    assert!(!lock_path.exists());
+}
+
 fn filter_index_stats(output: CommandOutput) -> CommandOutput {
-    let regex = Regex::new(r"    Name: [0-9a-z]+").unwrap();
-    output.normalize_stdout_with(|text| regex.replace_all(&text, "    Name: [hash]").into_owned())
+    let regex = Regex::new(r"Name: [0-9a-z]+").unwrap();
+    output.normalize_stdout_with(|text| regex.replace_all(&text, "Name: [hash]").into_owned())
 }
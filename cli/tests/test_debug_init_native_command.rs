@@ -0,0 +1,77 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::TestEnvironment;
+
+#[test]
+fn test_init_native() {
+    let test_env = TestEnvironment::default();
+    let output = test_env.run_jj_in(".", ["debug", "init-native", "repo"]);
+    insta::assert_snapshot!(output, @r#"
+    ------- stderr -------
+    Initialized repo in "repo"
+    [EOF]
+    "#);
+
+    let store_path = test_env.env_root().join("repo/.jj/repo/store");
+    assert_eq!(
+        std::fs::read_to_string(store_path.join("type")).unwrap(),
+        "Native"
+    );
+    assert!(store_path.join("objects").is_dir());
+    assert!(store_path.join("packs").is_dir());
+}
+
+#[test]
+fn test_native_gc() {
+    let test_env = TestEnvironment::default();
+    test_env
+        .run_jj_in(".", ["debug", "init-native", "repo"])
+        .success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "a\n");
+    work_dir.run_jj(["commit", "-m", "first"]).success();
+    work_dir.write_file("file", "b\n");
+    work_dir.run_jj(["describe", "-m", "second"]).success();
+
+    work_dir.run_jj(["util", "gc", "--expire=now"]).success();
+    let packs_path = test_env.env_root().join("repo/.jj/repo/store/packs");
+    let num_packs = std::fs::read_dir(packs_path)
+        .unwrap()
+        .filter(|entry| entry.as_ref().unwrap().path().extension().unwrap() == "pack")
+        .count();
+    assert_eq!(num_packs, 1);
+
+    let output = work_dir.run_jj(["log", "-T", "description", "-p", "--git"]);
+    insta::assert_snapshot!(output, @r"
+    @  second
+    │  diff --git a/file b/file
+    │  index bedfbb90d8..3be587a7c7 100644
+    │  --- a/file
+    │  +++ b/file
+    │  @@ -1,1 +1,1 @@
+    │  -a
+    │  +b
+    ○  first
+    │  diff --git a/file b/file
+    │  new file mode 100644
+    │  index 0000000000..bedfbb90d8
+    │  --- /dev/null
+    │  +++ b/file
+    │  @@ -0,0 +1,1 @@
+    │  +a
+    ◆
+    [EOF]
+    ");
+}
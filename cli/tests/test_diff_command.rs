@@ -2400,6 +2400,609 @@ fn test_diff_leading_trailing_context() {
     ");
 }
 
+#[test]
+fn test_diff_function_headers() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    test_env.add_config(
+        r#"
+[diff.functions.rust]
+patterns = ["glob:'**/*.rs'"]
+regex = '^(fn \w+)'
+        "#,
+    );
+
+    let content = indoc! {"
+        fn foo() {
+            let a = 1;
+            let b = 2;
+            let c = 3;
+            let d = 4;
+        }
+
+        fn bar() {
+            let e = 5;
+            let f = 6;
+            let g = 7;
+        }
+
+        fn baz() {
+            let h = 8;
+            let i = 9;
+            let j = 10;
+            let k = 11;
+            let l = 12;
+        }
+    "};
+    work_dir.write_file("file.rs", content);
+    work_dir.write_file("file.txt", content);
+    work_dir.run_jj(["new"]).success();
+    let content = content
+        .replace("a = 1", "a = 10")
+        .replace("k = 11", "k = 110");
+    work_dir.write_file("file.rs", &content);
+    work_dir.write_file("file.txt", &content);
+
+    // Hunks are annotated with the enclosing function
+    let output = work_dir.run_jj(["diff", "--git", "file.rs", "file.txt"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/file.rs b/file.rs
+    index aa37134bdd..0d000f8f72 100644
+    --- a/file.rs
+    +++ b/file.rs
+    @@ -1,5 +1,5 @@
+     fn foo() {
+    -    let a = 1;
+    +    let a = 10;
+         let b = 2;
+         let c = 3;
+         let d = 4;
+    @@ -15,6 +15,6 @@ fn baz
+         let h = 8;
+         let i = 9;
+         let j = 10;
+    -    let k = 11;
+    +    let k = 110;
+         let l = 12;
+     }
+    diff --git a/file.txt b/file.txt
+    index aa37134bdd..0d000f8f72 100644
+    --- a/file.txt
+    +++ b/file.txt
+    @@ -1,5 +1,5 @@
+     fn foo() {
+    -    let a = 1;
+    +    let a = 10;
+         let b = 2;
+         let c = 3;
+         let d = 4;
+    @@ -15,6 +15,6 @@
+         let h = 8;
+         let i = 9;
+         let j = 10;
+    -    let k = 11;
+    +    let k = 110;
+         let l = 12;
+     }
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["diff", "--color-words", "file.rs"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file file.rs:
+       1    1: fn foo() {
+       2    2:     let a = 110;
+       3    3:     let b = 2;
+       4    4:     let c = 3;
+       5    5:     let d = 4;
+        ... fn baz
+      15   15:     let h = 8;
+      16   16:     let i = 9;
+      17   17:     let j = 10;
+      18   18:     let k = 11110;
+      19   19:     let l = 12;
+      20   20: }
+    [EOF]
+    ");
+
+    // Hunks are extended to whole functions
+    let output = work_dir.run_jj(["diff", "--git", "--function-context", "file.rs"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/file.rs b/file.rs
+    index aa37134bdd..0d000f8f72 100644
+    --- a/file.rs
+    +++ b/file.rs
+    @@ -1,7 +1,7 @@
+     fn foo() {
+    -    let a = 1;
+    +    let a = 10;
+         let b = 2;
+         let c = 3;
+         let d = 4;
+     }
+     
+    @@ -14,7 +14,7 @@ fn bar
+     fn baz() {
+         let h = 8;
+         let i = 9;
+         let j = 10;
+    -    let k = 11;
+    +    let k = 110;
+         let l = 12;
+     }
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj([
+        "diff",
+        "--color-words",
+        "--function-context",
+        "--context=0",
+        "file.rs",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file file.rs:
+       1    1: fn foo() {
+       2    2:     let a = 110;
+       3    3:     let b = 2;
+       4    4:     let c = 3;
+       5    5:     let d = 4;
+       6    6: }
+       7    7: 
+        ... fn bar
+      14   14: fn baz() {
+      15   15:     let h = 8;
+      16   16:     let i = 9;
+      17   17:     let j = 10;
+      18   18:     let k = 11110;
+      19   19:     let l = 12;
+      20   20: }
+    [EOF]
+    ");
+
+    // Function context can be enabled by config, but has no effect on files
+    // without function patterns
+    let output = work_dir.run_jj([
+        "diff",
+        "--git",
+        "--config=diff.git.function-context=true",
+        "file.txt",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/file.txt b/file.txt
+    index aa37134bdd..0d000f8f72 100644
+    --- a/file.txt
+    +++ b/file.txt
+    @@ -1,5 +1,5 @@
+     fn foo() {
+    -    let a = 1;
+    +    let a = 10;
+         let b = 2;
+         let c = 3;
+         let d = 4;
+    @@ -15,6 +15,6 @@
+         let h = 8;
+         let i = 9;
+         let j = 10;
+    -    let k = 11;
+    +    let k = 110;
+         let l = 12;
+     }
+    [EOF]
+    ");
+
+    // Invalid regex
+    let output = work_dir.run_jj(["diff", "--git", "--config=diff.functions.rust.regex='('"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Config error: Invalid type or value for diff.functions.rust
+    Caused by: regex parse error:
+        (
+        ^
+    error: unclosed group
+    For help, see https://jj-vcs.github.io/jj/latest/config/ or use `jj help -k config`.
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_diff_side_by_side() {
+    let mut test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_env_var("COLUMNS", "60");
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("file1", "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n");
+    work_dir.write_file("file2", "removed\n");
+    work_dir.write_file("binary", b"\0old");
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file(
+        "file1",
+        "a\nb\nc changed\nd\ne\nf\ng\nh\ni\nj\nthis line is long enough to be wrapped at the column \
+         width\n",
+    );
+    work_dir.remove_file("file2");
+    work_dir.write_file("file3", "\tadded\n");
+    work_dir.write_file("binary", b"\0new");
+
+    let output = work_dir.run_jj(["diff", "--tool=:side-by-side"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file binary:
+        (binary)
+    Modified regular file file1:
+       1 a                        |   1 a
+       2 b                        |   2 b
+       3 c                        |   3 c changed
+       4 d                        |   4 d
+       5 e                        |   5 e
+       6 f                        |   6 f
+        ...
+       8 h                        |   8 h
+       9 i                        |   9 i
+      10 j                        |  10 j
+                                  |  11 this line is long enough
+                                  |      to be wrapped at the co
+                                  |     lumn width
+    Removed regular file file2:
+       1 removed                  |
+    Added regular file file3:
+                                  |   1     added
+    [EOF]
+    ");
+
+    // Context is configurable
+    let output = work_dir.run_jj(["diff", "--tool=:side-by-side", "--context=0", "file1"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file file1:
+        ...
+       3 c                        |   3 c changed
+        ...
+                                  |  11 this line is long enough
+                                  |      to be wrapped at the co
+                                  |     lumn width
+    [EOF]
+    ");
+
+    // Changed words are highlighted
+    let output = work_dir.run_jj([
+        "diff",
+        "--tool=:side-by-side",
+        "--context=0",
+        "--color=always",
+        "file1",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    [38;5;3mModified regular file file1:[39m
+        ...
+    [38;5;1m   3[39m [38;5;1mc[39m                        |[38;5;2m   3[39m [38;5;2mc[4m changed[24m[39m
+        ...
+                                  |[38;5;2m  11[39m [4m[38;5;2mthis line is long enough[24m[39m
+                                  |     [4m[38;5;2m to be wrapped at the co[24m[39m
+                                  |     [4m[38;5;2mlumn width[24m[39m
+    [EOF]
+    ");
+
+    // Narrow terminal
+    let output = work_dir.run_jj_with(|cmd| {
+        cmd.args(["diff", "--tool=:side-by-side", "file1"])
+            .env("COLUMNS", "20")
+    });
+    insta::assert_snapshot!(output, @r"
+    Modified regular file file1:
+       1 a          |   1 a
+       2 b          |   2 b
+       3 c          |   3 c changed
+       4 d          |   4 d
+       5 e          |   5 e
+       6 f          |   6 f
+        ...
+       8 h          |   8 h
+       9 i          |   9 i
+      10 j          |  10 j
+                    |  11 this line 
+                    |     is long en
+                    |     ough to be
+                    |      wrapped a
+                    |     t the colu
+                    |     mn width
+    [EOF]
+    ");
+
+    // Conflicts are materialized
+    work_dir.run_jj(["describe", "-mb"]).success();
+    work_dir.run_jj(["new", "root()", "-mc"]).success();
+    work_dir.write_file("file1", "x\n");
+    work_dir
+        .run_jj(["new", "description(b)", "description(c)"])
+        .success();
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file("file1", "resolved\n");
+    let output = work_dir.run_jj(["diff", "--tool=:side-by-side", "--context=1", "file1"]);
+    insta::assert_snapshot!(output, @r"
+    Resolved conflict in file1:
+       1 <<<<<<< Conflict 1 of 1  |   1 resolved
+       2 +++++++ Contents of side |
+          #1                      |
+       3 a                        |
+       4 b                        |
+       5 c changed                |
+       6 d                        |
+       7 e                        |
+       8 f                        |
+       9 g                        |
+      10 h                        |
+      11 i                        |
+      12 j                        |
+      13 this line is long enough |
+          to be wrapped at the co |
+         lumn width               |
+      14 %%%%%%% Changes from bas |
+         e to side #2             |
+      15 +x                       |
+      16 >>>>>>> Conflict 1 of 1  |
+         ends                     |
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_diff_json() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    let pretty_json = |s: String| {
+        let value: serde_json::Value = serde_json::from_str(&s).unwrap();
+        serde_json::to_string_pretty(&value).unwrap() + "\n"
+    };
+
+    work_dir.write_file("file1", "a\nfoo bar\n");
+    work_dir.write_file("file2", "renamed\n");
+    work_dir.write_file("file3", "x\n");
+    work_dir.write_file("binary", b"\0old");
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file("file1", "a\nfoo baz\n");
+    work_dir.remove_file("file2");
+    work_dir.write_file("file2-renamed", "renamed\n");
+    work_dir.write_file("added", "new");
+    work_dir.write_file("binary", b"\0new");
+    work_dir.run_jj(["file", "chmod", "x", "file3"]).success();
+
+    // Hunks have line numbers and changed tokens
+    let output = work_dir
+        .run_jj(["diff", "--tool=:json", "--context=0", "file1", "added"])
+        .normalize_stdout_with(pretty_json);
+    insta::assert_snapshot!(output, @r#"
+    {
+      "files": [
+        {
+          "binary": false,
+          "hunks": [
+            {
+              "lines": [
+                {
+                  "new_line": 1,
+                  "tokens": [
+                    {
+                      "changed": true,
+                      "text": "new"
+                    }
+                  ],
+                  "type": "added"
+                }
+              ],
+              "new_lines": 1,
+              "new_start": 1,
+              "old_lines": 0,
+              "old_start": 1
+            }
+          ],
+          "new": {
+            "executable": false,
+            "type": "file"
+          },
+          "old": null,
+          "path": "added",
+          "status": "added"
+        },
+        {
+          "binary": false,
+          "hunks": [
+            {
+              "lines": [
+                {
+                  "old_line": 2,
+                  "tokens": [
+                    {
+                      "changed": false,
+                      "text": "foo "
+                    },
+                    {
+                      "changed": true,
+                      "text": "bar"
+                    },
+                    {
+                      "changed": false,
+                      "text": "\n"
+                    }
+                  ],
+                  "type": "removed"
+                },
+                {
+                  "new_line": 2,
+                  "tokens": [
+                    {
+                      "changed": false,
+                      "text": "foo "
+                    },
+                    {
+                      "changed": true,
+                      "text": "baz"
+                    },
+                    {
+                      "changed": false,
+                      "text": "\n"
+                    }
+                  ],
+                  "type": "added"
+                }
+              ],
+              "new_lines": 1,
+              "new_start": 2,
+              "old_lines": 1,
+              "old_start": 2
+            }
+          ],
+          "new": {
+            "executable": false,
+            "type": "file"
+          },
+          "old": {
+            "executable": false,
+            "type": "file"
+          },
+          "path": "file1",
+          "status": "modified"
+        }
+      ],
+      "version": 1
+    }
+    [EOF]
+    "#);
+
+    // Renames, mode changes, and binary files
+    let output = work_dir.run_jj([
+        "diff",
+        "--tool=:json",
+        "binary",
+        "file2",
+        "file2-renamed",
+        "file3",
+    ]);
+    insta::assert_snapshot!(output, @r#"
+    {"version":1,"files":[{"path":"binary","status":"modified","old":{"type":"file","executable":false},"new":{"type":"file","executable":false},"binary":true,"hunks":[]},{"path":"file2-renamed","status":"renamed","source":"file2","old":{"type":"file","executable":false},"new":{"type":"file","executable":false},"binary":false,"hunks":[]},{"path":"file3","status":"modified","old":{"type":"file","executable":false},"new":{"type":"file","executable":true},"binary":false,"hunks":[]}]}
+    [EOF]
+    "#);
+
+    // Available to other commands
+    let output = work_dir
+        .run_jj(["show", "--tool=:json", "-T", "''"])
+        .success();
+    let value: serde_json::Value = serde_json::from_str(output.stdout.raw()).unwrap();
+    let files = value["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|file| format!("{} {}", file["status"], file["path"]))
+        .join("\n");
+    insta::assert_snapshot!(files, @r#"
+    "added" "added"
+    "modified" "binary"
+    "modified" "file1"
+    "renamed" "file2-renamed"
+    "modified" "file3"
+    "#);
+}
+
+#[test]
+fn test_diff_color_moved() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file(
+        "file1",
+        indoc! {"
+            fn first_function() {
+                call_first_function();
+            }
+            keep
+        "},
+    );
+    work_dir.write_file("file2", "other\n");
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file("file1", "keep\n");
+    work_dir.write_file(
+        "file2",
+        indoc! {"
+            other
+            fn first_function() {
+                call_first_function();
+            }
+            added
+        "},
+    );
+
+    // Moved lines are labeled in git diff
+    let output = work_dir.run_jj(["diff", "--git", "--color=always", "--color-moved"]);
+    insta::assert_snapshot!(output, @r"
+    [1mdiff --git a/file1 b/file1[0m
+    [1mindex 89b726858e..2fa992c0b8 100644[0m
+    [1m--- a/file1[0m
+    [1m+++ b/file1[0m
+    [38;5;6m@@ -1,4 +1,1 @@[39m
+    [38;5;5m-[4mfn first_function() {[24m[39m
+    [38;5;5m-[4m    call_first_function();[24m[39m
+    [38;5;5m-[4m}[24m[39m
+     keep
+    [1mdiff --git a/file2 b/file2[0m
+    [1mindex e45c9c2666..9d1d8b5e5a 100644[0m
+    [1m--- a/file2[0m
+    [1m+++ b/file2[0m
+    [38;5;6m@@ -1,1 +1,5 @@[39m
+     other
+    [38;5;6m+[4mfn first_function() {[24m[39m
+    [38;5;6m+[4m    call_first_function();[24m[39m
+    [38;5;6m+[4m}[24m[39m
+    [38;5;2m+[4madded[24m[39m
+    [EOF]
+    ");
+
+    // Moved lines are labeled in color-words diff
+    let output = work_dir.run_jj(["diff", "--color-words", "--color=always", "--color-moved"]);
+    insta::assert_snapshot!(output, @r"
+    [38;5;3mModified regular file file1:[39m
+    [38;5;5m   1[39m     : [4m[38;5;5mfn first_function() {[24m[39m
+    [38;5;5m   2[39m     : [4m[38;5;5m    call_first_function();[24m[39m
+    [38;5;5m   3[39m     : [4m[38;5;5m}[24m[39m
+    [38;5;1m   4[39m [38;5;2m   1[39m: keep
+    [38;5;3mModified regular file file2:[39m
+    [38;5;1m   1[39m [38;5;2m   1[39m: other
+         [38;5;6m   2[39m: [4m[38;5;6mfn first_function() {[24m[39m
+         [38;5;6m   3[39m: [4m[38;5;6m    call_first_function();[24m[39m
+         [38;5;6m   4[39m: [4m[38;5;6m}[24m[39m
+         [38;5;2m   5[39m: [4m[38;5;2madded[24m[39m
+    [EOF]
+    ");
+
+    // Moved lines aren't detected by default
+    let output = work_dir.run_jj(["diff", "--git", "--color=always", "file2"]);
+    insta::assert_snapshot!(output, @r"
+    [1mdiff --git a/file2 b/file2[0m
+    [1mindex e45c9c2666..9d1d8b5e5a 100644[0m
+    [1m--- a/file2[0m
+    [1m+++ b/file2[0m
+    [38;5;6m@@ -1,1 +1,5 @@[39m
+     other
+    [38;5;2m+[4mfn first_function() {[24m[39m
+    [38;5;2m+[4m    call_first_function();[24m[39m
+    [38;5;2m+[4m}[24m[39m
+    [38;5;2m+[4madded[24m[39m
+    [EOF]
+    ");
+
+    // Moved hunks are exposed to templates
+    let template = r#"self.diff().hunks().map(|h| h.path() ++ " -" ++ h.old_start() ++ "," ++ h.old_lines() ++ " +" ++ h.new_start() ++ "," ++ h.new_lines() ++ " " ++ h.moved()).join("\n") ++ "\n""#;
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    file1 -1,4 +1,1 true
+    file2 -1,1 +1,5 false
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_diff_conflict_sides_differ() {
     let test_env = TestEnvironment::default();
@@ -3991,3 +4594,145 @@ fn test_diff_revisions() {
     [EOF]
     ");
 }
+
+#[test]
+fn test_diff_textconv() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    let converter_path = assert_cmd::cargo::cargo_bin!("fake-formatter");
+    test_env.add_config(format!(
+        r#"
+        [diff.textconv.upper]
+        command = [{converter}, "--uppercase"]
+        patterns = ["glob:*.bin"]
+        "#,
+        converter = to_toml_value(converter_path.to_str().unwrap())
+    ));
+
+    work_dir.write_file("file.bin", "a\nb\n");
+    work_dir.write_file("file.txt", "a\nb\n");
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file("file.bin", "a\nc\n");
+    work_dir.write_file("file.txt", "a\nc\n");
+
+    // Only the matching file is converted
+    let output = work_dir.run_jj(["diff"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file file.bin:
+       1    1: A
+       2    2: BC
+    Modified regular file file.txt:
+       1    1: a
+       2    2: bc
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["diff", "--git"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/file.bin b/file.bin
+    index 422c2b7ab3..0f7bc76605 100644
+    --- a/file.bin
+    +++ b/file.bin
+    @@ -1,2 +1,2 @@
+     A
+    -B
+    +C
+    diff --git a/file.txt b/file.txt
+    index 422c2b7ab3..0f7bc76605 100644
+    --- a/file.txt
+    +++ b/file.txt
+    @@ -1,2 +1,2 @@
+     a
+    -b
+    +c
+    [EOF]
+    ");
+
+    // Converted contents are cached in the repo
+    let cache_dir = work_dir.root().join(".jj/repo/textconv");
+    let cache_entries = || {
+        std::fs::read_dir(&cache_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_type().unwrap())
+            .collect_vec()
+    };
+    // One file per converted content, so the cache can be pruned by mtime
+    let entries = cache_entries();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|file_type| file_type.is_file()));
+
+    // Stats are computed from the converted contents
+    let output = work_dir.run_jj(["diff", "--stat"]);
+    insta::assert_snapshot!(output, @r"
+    file.bin | 2 +-
+    file.txt | 2 +-
+    2 files changed, 2 insertions(+), 2 deletions(-)
+    [EOF]
+    ");
+
+    // Templates share the cache
+    std::fs::remove_dir_all(&cache_dir).unwrap();
+    work_dir
+        .run_jj(["log", "--no-graph", "-r@", "-T", "diff.git()"])
+        .success();
+    assert!(cache_dir.is_dir());
+
+    // Contents converted with the path aren't shared between files
+    test_env.add_config(format!(
+        r#"
+        [diff.textconv.upper]
+        command = [{converter}, "--append=$path"]
+        "#,
+        converter = to_toml_value(converter_path.to_str().unwrap())
+    ));
+    work_dir.write_file("other.bin", "a\nc\n");
+    let output = work_dir.run_jj(["diff", "--git", "file.bin", "other.bin"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/file.bin b/file.bin
+    index 422c2b7ab3..0f7bc76605 100644
+    --- a/file.bin
+    +++ b/file.bin
+    @@ -1,3 +1,3 @@
+     a
+    -b
+    +c
+     file.bin
+    \ No newline at end of file
+    diff --git a/file.bin b/other.bin
+    copy from file.bin
+    copy to other.bin
+    index 422c2b7ab3..0f7bc76605 100644
+    --- a/file.bin
+    +++ b/other.bin
+    @@ -1,3 +1,3 @@
+     a
+    -b
+    -file.bin
+    \ No newline at end of file
+    +c
+    +other.bin
+    \ No newline at end of file
+    [EOF]
+    ");
+    work_dir.remove_file("other.bin");
+
+    // The converter failure is reported
+    test_env.add_config(format!(
+        r#"
+        [diff.textconv.upper]
+        command = [{converter}, "--fail", "--stderr=bad input"]
+        "#,
+        converter = to_toml_value(converter_path.to_str().unwrap())
+    ));
+    let output = work_dir.run_jj(["diff", "--git"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to convert file content to text
+    Caused by:
+    1: Error when reading file content for file file.bin with id 422c2b7ab3b3c668038da977e4e93a5fc623169c
+    2: Text converter `upper` exited with exit status: 1:
+    bad input
+    [EOF]
+    [exit status: 1]
+    ");
+}
@@ -263,3 +263,31 @@ fn test_annotate_with_template() {
     [EOF]
     ");
 }
+
+#[test]
+fn test_annotate_with_textconv() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    let converter_path = assert_cmd::cargo::cargo_bin!("fake-formatter");
+    test_env.add_config(format!(
+        r#"
+        [diff.textconv.upper]
+        command = [{converter}, "--uppercase"]
+        patterns = ["glob:*.bin"]
+        "#,
+        converter = toml_edit::Value::from(converter_path.to_str().unwrap())
+    ));
+
+    work_dir.write_file("file.bin", "line1\n");
+    work_dir.run_jj(["describe", "-m=initial"]).success();
+    work_dir.run_jj(["new", "-m=next"]).success();
+    work_dir.write_file("file.bin", "line1\nline2\n");
+
+    let output = work_dir.run_jj(["file", "annotate", "file.bin"]);
+    insta::assert_snapshot!(output, @r"
+    qpvuntsm test.use 2001-02-03 08:05:08    1: LINE1
+    kkmpptxz test.use 2001-02-03 08:05:10    2: LINE2
+    [EOF]
+    ");
+}
@@ -114,6 +114,37 @@ fn test_show() {
     >>>>>>> Conflict 1 of 1 ends
     [EOF]
     ");
+
+    // Can print a single term of a conflict
+    let output = work_dir.run_jj(["file", "show", "--side=1", "file1"]);
+    insta::assert_snapshot!(output, @r"
+    a
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "show", "--side=2", "file1"]);
+    insta::assert_snapshot!(output, @r"
+    c
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "show", "--base=1", "file1"]);
+    insta::assert_snapshot!(output, @r"
+    b
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "show", "--side=3", "file1"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Conflict at 'file1' has no side #3
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = work_dir.run_jj(["file", "show", "--base=2", "file1"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Conflict at 'file1' has no base #2
+    [EOF]
+    [exit status: 1]
+    ");
 }
 
 #[cfg(unix)]
@@ -165,6 +165,156 @@ fn test_git_fetch_single_remote() {
     ");
 }
 
+#[test]
+fn test_git_fetch_require_trusted_signature() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(
+        r#"
+    git.auto-local-bookmark = true
+    git.fetch-require-trusted-signature = true
+    signing.backend = "test"
+    signing.trust-policy.alice.signer = "alice"
+    "#,
+    );
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    add_git_remote(&test_env, &work_dir, "rem1");
+
+    // Commits created by Git aren't signed
+    let output = work_dir.run_jj(["git", "fetch", "--remote", "rem1"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Won't import commit 4acd0343368d since it doesn't have a trusted signature
+    Hint: Rejected commit: ppspxspk 4acd0343 rem1 | message
+    Hint: Commits must be signed by a signer in `signing.trust-policy` when `git.fetch-require-trusted-signature` is set
+    [EOF]
+    [exit status: 1]
+    ");
+    insta::assert_snapshot!(get_bookmark_output(&work_dir), @"");
+    // The rejected commit isn't imported from the Git refs later
+    let output = work_dir.run_jj(["git", "import"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_bookmark_output(&work_dir), @"");
+
+    test_env
+        .run_jj_in(".", ["git", "init", "--colocate", "signed"])
+        .success();
+    let signed_dir = test_env.work_dir("signed");
+    signed_dir
+        .run_jj([
+            "commit",
+            "--config=signing.behavior=own",
+            "--config=signing.key=alice",
+            "-m",
+            "signed",
+        ])
+        .success();
+    signed_dir
+        .run_jj(["bookmark", "create", "-r@-", "signed"])
+        .success();
+    work_dir
+        .run_jj(["git", "remote", "add", "signed", "../signed"])
+        .success();
+    let output = work_dir.run_jj(["git", "fetch", "--remote", "signed"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    bookmark: signed@signed [new] tracked
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_bookmark_output(&work_dir), @r"
+    signed: yqosqzyt fc7b537d (empty) signed
+      @signed: yqosqzyt fc7b537d (empty) signed
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_git_fetch_require_trusted_signature_conflicted_bookmark() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(
+        r#"
+    git.auto-local-bookmark = true
+    git.fetch-require-trusted-signature = true
+    signing.backend = "test"
+    signing.trust-policy.alice.signer = "alice"
+    "#,
+    );
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    test_env
+        .run_jj_in(".", ["git", "init", "--colocate", "signed"])
+        .success();
+    let signed_dir = test_env.work_dir("signed");
+    let commit_in_signed = |parent: &str, message: &str, key: Option<&str>| {
+        let mut args = vec!["new", parent, "-m", message];
+        let config;
+        if let Some(key) = key {
+            config = format!("--config=signing.key={key}");
+            args.extend(["--config=signing.behavior=own", &config]);
+        }
+        signed_dir.run_jj(args).success();
+        signed_dir
+            .run_jj(["bookmark", "set", "--allow-backwards", "-r@", "signed"])
+            .success();
+    };
+    commit_in_signed("root()", "base", Some("alice"));
+    work_dir
+        .run_jj(["git", "remote", "add", "signed", "../signed"])
+        .success();
+    work_dir.run_jj(["git", "fetch"]).success();
+    let base_op_id = work_dir.current_operation_id();
+
+    // Make the remote bookmark conflicted by concurrent fetches
+    commit_in_signed("signed", "side 1", Some("alice"));
+    work_dir.run_jj(["git", "fetch"]).success();
+    commit_in_signed("signed-", "side 2", Some("alice"));
+    work_dir
+        .run_jj(["git", "fetch", "--at-op", &base_op_id])
+        .success();
+    insta::assert_snapshot!(get_bookmark_output(&work_dir), @r"
+    signed (conflicted):
+      - kkmpptxz d26f9243 (empty) base
+      + yqosqzyt 0f368e35 (empty) side 1
+      + znkkpsqq 37de3f7a (empty) side 2
+      @signed (conflicted):
+      - kkmpptxz d26f9243 (empty) base
+      + yqosqzyt 0f368e35 (empty) side 1
+      + znkkpsqq 37de3f7a (empty) side 2
+    [EOF]
+    ");
+
+    // The rejected commit isn't imported from the Git refs later. Since the
+    // Git ref can't be reset to the conflicted target, the conflict is
+    // resolved to one of the sides.
+    commit_in_signed("signed", "untrusted", None);
+    let output = work_dir.run_jj(["git", "fetch"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Hint: Fetching from the only existing remote: signed
+    Error: Won't import commit 23bc6e2dd21c since it doesn't have a trusted signature
+    Hint: Rejected commit: lylxulpl 23bc6e2d signed | (empty) untrusted
+    Hint: Commits must be signed by a signer in `signing.trust-policy` when `git.fetch-require-trusted-signature` is set
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = work_dir.run_jj(["git", "import"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    bookmark: signed@signed [updated] tracked
+    Abandoned 1 commits that are no longer reachable.
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_bookmark_output(&work_dir), @r"
+    signed: yqosqzyt 0f368e35 (empty) side 1
+      @signed: yqosqzyt 0f368e35 (empty) side 1
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_git_fetch_single_remote_all_remotes_flag() {
     let test_env = TestEnvironment::default();
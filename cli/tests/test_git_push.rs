@@ -2191,6 +2191,55 @@ fn test_git_push_sign_on_push() {
     ");
 }
 
+#[test]
+fn test_git_push_require_trusted_signature() {
+    let test_env = TestEnvironment::default();
+    set_up(&test_env);
+    let work_dir = test_env.work_dir("local");
+    test_env.add_config(
+        r#"
+    git.push-require-trusted-signature = true
+    signing.backend = "test"
+    signing.trust-policy.alice.signer = "alice"
+    "#,
+    );
+    work_dir
+        .run_jj(["new", "bookmark2", "-m", "unsigned"])
+        .success();
+    work_dir
+        .run_jj(["bookmark", "set", "bookmark2", "-r@"])
+        .success();
+    let output = work_dir.run_jj(["git", "push"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Won't push commit f1d35530d78b since it doesn't have a trusted signature
+    Hint: Rejected commit: vruxwmqv f1d35530 bookmark2* | (empty) unsigned
+    Hint: Commits must be signed by a signer in `signing.trust-policy` when `git.push-require-trusted-signature` is set
+    [EOF]
+    [exit status: 1]
+    ");
+
+    work_dir.run_jj(["sign", "-r@", "--key", "bob"]).success();
+    let output = work_dir.run_jj(["git", "push"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Won't push commit 8b91b30e782f since it doesn't have a trusted signature
+    Hint: Rejected commit: vruxwmqv 8b91b30e bookmark2* | (empty) unsigned
+    Hint: Commits must be signed by a signer in `signing.trust-policy` when `git.push-require-trusted-signature` is set
+    [EOF]
+    [exit status: 1]
+    ");
+
+    work_dir.run_jj(["sign", "-r@", "--key", "alice"]).success();
+    let output = work_dir.run_jj(["git", "push"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Changes to push to origin:
+      Move forward bookmark bookmark2 from 38a204733702 to a23be3332e94
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_git_push_rejected_by_remote() {
     let test_env = TestEnvironment::default();
@@ -0,0 +1,347 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use testutils::git;
+
+use crate::common::CommandOutput;
+use crate::common::TestEnvironment;
+use crate::common::TestWorkDir;
+
+fn add_commit_to_branch(
+    git_repo: &gix::Repository,
+    branch: &str,
+    parents: &[gix::ObjectId],
+    filename: &str,
+    message: &str,
+) -> gix::ObjectId {
+    git::add_commit(
+        git_repo,
+        &format!("refs/heads/{branch}"),
+        filename,
+        filename.as_bytes(),
+        message,
+        parents,
+    )
+    .commit_id
+}
+
+/// Sets up a repo tracking `main@origin` and `feature@origin`, where `feature`
+/// is on top of `main`.
+fn set_up(test_env: &TestEnvironment) -> (TestWorkDir<'_>, gix::Repository) {
+    let git_repo = git::init(test_env.env_root().join("origin"));
+    let main_id = add_commit_to_branch(&git_repo, "main", &[], "base", "base");
+    add_commit_to_branch(&git_repo, "feature", &[main_id], "feature1", "feature1");
+
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir
+        .run_jj(["git", "remote", "add", "origin", "../origin"])
+        .success();
+    work_dir.run_jj(["git", "fetch"]).success();
+    work_dir
+        .run_jj(["bookmark", "track", "main@origin", "feature@origin"])
+        .success();
+    (work_dir, git_repo)
+}
+
+#[must_use]
+fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
+    let template = r#"separate(" ", description.first_line(), bookmarks) ++ "\n""#;
+    work_dir.run_jj(["log", "-T", template, "-r", "all()"])
+}
+
+#[test]
+fn test_git_sync() {
+    let test_env = TestEnvironment::default();
+    let (work_dir, git_repo) = set_up(&test_env);
+
+    // Stack on main, where the first commit will land upstream
+    work_dir.run_jj(["new", "main", "-m", "landed"]).success();
+    work_dir.write_file("landed", "landed");
+    work_dir.run_jj(["new", "-m", "local1"]).success();
+    work_dir.write_file("local1", "local1");
+    // Stack on feature
+    work_dir
+        .run_jj(["new", "feature", "-m", "local2"])
+        .success();
+    work_dir.write_file("local2", "local2");
+
+    let main_id = git_repo
+        .find_reference("refs/heads/main")
+        .unwrap()
+        .peel_to_id()
+        .unwrap()
+        .detach();
+    let main_id = add_commit_to_branch(&git_repo, "main", &[main_id], "landed", "upstream");
+    let feature_id = git_repo
+        .find_reference("refs/heads/feature")
+        .unwrap()
+        .peel_to_id()
+        .unwrap()
+        .detach();
+    add_commit_to_branch(
+        &git_repo,
+        "feature",
+        &[feature_id, main_id],
+        "feature2",
+        "feature2",
+    );
+
+    let output = work_dir.run_jj(["git", "sync"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    bookmark: feature@origin [updated] tracked
+    bookmark: main@origin    [updated] tracked
+    Rebased 1 commits onto feature@origin
+    Rebased 1 commits onto main@origin
+    Abandoned 1 commits that became empty on main@origin:
+      mzvwutvl 66cfd1c0 landed
+    Working copy  (@) now at: yqosqzyt d1c01ace local2
+    Parent commit (@-)      : pknuyxlk a5030ec4 feature | feature2
+    Added 1 files, modified 0 files, removed 0 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  local2
+    ○    feature2 feature
+    ├─╮
+    ○ │  feature1
+    │ │ ○  local1
+    │ ├─╯
+    │ ◆  upstream main
+    ├─╯
+    ◆  base
+    ◆
+    [EOF]
+    ");
+
+    // Nothing to rebase
+    let output = work_dir.run_jj(["git", "sync"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_git_fetch_auto_rebase() {
+    let test_env = TestEnvironment::default();
+    let (work_dir, git_repo) = set_up(&test_env);
+
+    // Stack on the pushed feature, which won't be moved upstream
+    work_dir
+        .run_jj(["new", "feature", "-m", "local2"])
+        .success();
+    work_dir.write_file("local2", "local2");
+    work_dir.run_jj(["new", "main", "-m", "local1"]).success();
+    work_dir.write_file("local1", "local1");
+
+    let main_id = git_repo
+        .find_reference("refs/heads/main")
+        .unwrap()
+        .peel_to_id()
+        .unwrap()
+        .detach();
+    add_commit_to_branch(&git_repo, "main", &[main_id], "main1", "main1");
+
+    // Not rebased by default
+    let output = work_dir.run_jj(["git", "fetch"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    bookmark: main@origin [updated] tracked
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  local1
+    │ ◆  main1 main
+    ├─╯
+    │ ○  local2
+    │ ○  feature1 feature
+    ├─╯
+    ◆  base
+    ◆
+    [EOF]
+    ");
+
+    work_dir.run_jj(["undo"]).success();
+    test_env.add_config("git.fetch-auto-rebase = true");
+    let output = work_dir.run_jj(["git", "fetch"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    bookmark: main@origin [updated] tracked
+    Rebased 3 commits onto main@origin
+    Working copy  (@) now at: royxmykx 56f5fef4 local1
+    Parent commit (@-)      : pttxorrn cc118637 main | main1
+    Added 1 files, modified 0 files, removed 0 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  local1
+    │ ○  local2
+    │ ○  feature1 feature*
+    ├─╯
+    ◆  main1 main
+    ◆  base
+    ◆
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_git_sync_pushed_stack() {
+    let test_env = TestEnvironment::default();
+    let (work_dir, git_repo) = set_up(&test_env);
+
+    // Stack which was pushed to the remote
+    work_dir.run_jj(["new", "main", "-m", "pushed"]).success();
+    work_dir.write_file("pushed", "pushed");
+    work_dir
+        .run_jj(["bookmark", "create", "-r@", "pushed"])
+        .success();
+    work_dir
+        .run_jj(["git", "push", "--allow-new", "--bookmark", "pushed"])
+        .success();
+
+    let main_id = git_repo
+        .find_reference("refs/heads/main")
+        .unwrap()
+        .peel_to_id()
+        .unwrap()
+        .detach();
+    add_commit_to_branch(&git_repo, "main", &[main_id], "main1", "main1");
+
+    let output = work_dir.run_jj(["git", "sync"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    bookmark: main@origin [updated] tracked
+    Rebased 2 commits onto main@origin
+    Working copy  (@) now at: mzvwutvl 475bf3a1 pushed* | pushed
+    Parent commit (@-)      : pttxorrn cc118637 main | main1
+    Added 1 files, modified 0 files, removed 0 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  pushed pushed*
+    │ ○  feature1 feature*
+    ├─╯
+    ◆  main1 main
+    ◆  base
+    ◆
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_git_sync_bookmark_moved_backwards() {
+    let test_env = TestEnvironment::default();
+    let (work_dir, git_repo) = set_up(&test_env);
+
+    let base_id = git_repo
+        .find_reference("refs/heads/main")
+        .unwrap()
+        .peel_to_id()
+        .unwrap()
+        .detach();
+    add_commit_to_branch(&git_repo, "main", &[base_id], "main1", "main1");
+    work_dir.run_jj(["git", "sync"]).success();
+    work_dir.run_jj(["new", "main", "-m", "local1"]).success();
+    work_dir.write_file("local1", "local1");
+
+    // The stack isn't rebased onto the old position
+    git_repo
+        .reference(
+            "refs/heads/main",
+            base_id,
+            gix::refs::transaction::PreviousValue::Any,
+            "reset main",
+        )
+        .unwrap();
+    let output = work_dir.run_jj(["git", "sync"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    bookmark: main@origin [updated] tracked
+    Warning: Not rebasing descendants of main@origin because the bookmark moved backwards:
+      royxmykx 6d8ff0d2 local1
+      xkvlurox 7ed2fb3f feature* | feature1
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  local1
+    │ ○  feature1 feature*
+    ├─╯
+    ○  main1
+    ◆  base main
+    ◆
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_git_sync_conflicted_bookmark() {
+    let test_env = TestEnvironment::default();
+    let (work_dir, git_repo) = set_up(&test_env);
+
+    let base_id = git_repo
+        .find_reference("refs/heads/main")
+        .unwrap()
+        .peel_to_id()
+        .unwrap()
+        .detach();
+    let base_op_id = work_dir.current_operation_id();
+
+    // Make the remote bookmark conflicted by concurrent fetches
+    add_commit_to_branch(&git_repo, "main", &[base_id], "side1", "side1");
+    work_dir.run_jj(["git", "fetch"]).success();
+    let side2_id = add_commit_to_branch(&git_repo, "side2", &[base_id], "side2", "side2");
+    git_repo
+        .reference(
+            "refs/heads/main",
+            side2_id,
+            gix::refs::transaction::PreviousValue::Any,
+            "move main",
+        )
+        .unwrap();
+    git_repo.find_reference("side2").unwrap().delete().unwrap();
+    work_dir
+        .run_jj(["git", "fetch", "--at-op", &base_op_id])
+        .success();
+    work_dir
+        .run_jj(["new", "description(side2)", "-m", "local1"])
+        .success();
+    work_dir.write_file("local1", "local1");
+
+    // The stack isn't rebased since the old position is ambiguous
+    add_commit_to_branch(&git_repo, "main", &[side2_id], "main1", "main1");
+    let output = work_dir.run_jj(["git", "sync"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    bookmark: main@origin [updated] tracked
+    Abandoned 1 commits that are no longer reachable.
+    Warning: Not rebasing descendants of main@origin because the bookmark is conflicted:
+      yqosqzyt b55548e7 local1
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  local1
+    │ ◆  main1 main
+    ├─╯
+    ◆  side2
+    │ ○  feature1 feature
+    ├─╯
+    ◆  base
+    ◆
+    [EOF]
+    ");
+}
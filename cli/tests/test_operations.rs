@@ -233,6 +233,119 @@ fn test_op_log_limit() {
     ");
 }
 
+#[test]
+fn test_op_log_opset() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m", "first"]).success();
+    let first_commit_id = work_dir
+        .run_jj(["log", "--no-graph", "-r@", "-Tcommit_id"])
+        .success()
+        .stdout
+        .into_raw();
+    work_dir.run_jj(["new", "-m", "second"]).success();
+    work_dir.run_jj(["abandon", "description(first)"]).success();
+    work_dir.run_jj(["new"]).success();
+
+    let output = work_dir.run_jj(["op", "log", "-Tdescription", "--operations=all()"]);
+    insta::assert_snapshot!(output, @r"
+    @  new empty commit
+    ○  abandon commit 68a505386f936fff6d718f55005e77ea72589bc1
+    ○  new empty commit
+    ○  describe commit e8849ae12c709f2321908879bc724fdb2ab8a781
+    ○  add workspace 'default'
+    ○
+    [EOF]
+    ");
+
+    // Operations that aren't selected are elided from the graph
+    let output = work_dir.run_jj([
+        "op",
+        "log",
+        "-Tdescription",
+        "--operations",
+        "description(glob:'new*') | root()",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    @  new empty commit
+    ○  new empty commit
+    ○
+    [EOF]
+    ");
+
+    // Find operations that changed the visibility of the commit
+    let output = work_dir.run_jj([
+        "op",
+        "log",
+        "-T",
+        r#"description ++ "\n""#,
+        "--no-graph",
+        "--operations",
+        &format!("touching({first_commit_id}) ~ description(describe)"),
+    ]);
+    insta::assert_snapshot!(output, @r"
+    abandon commit 68a505386f936fff6d718f55005e77ea72589bc1
+    new empty commit
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["op", "log", "--operations=foo("]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to parse opset: Syntax error
+    Caused by:  --> 1:5
+      |
+    1 | foo(
+      |     ^---
+      |
+      = expected <expression>
+    Hint: See https://jj-vcs.github.io/jj/latest/operation-log/ for opsets syntax.
+    [EOF]
+    [exit status: 1]
+    ");
+
+    let output = work_dir.run_jj(["op", "log", "--operations=descriptio(x)"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to parse opset: Function `descriptio` doesn't exist
+    Caused by:  --> 1:1
+      |
+    1 | descriptio(x)
+      | ^--------^
+      |
+      = Function `descriptio` doesn't exist
+    Hint: Did you mean `description`?
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // Abandon the operations selected by the expression
+    let output = work_dir.run_jj(["op", "abandon", "description(glob:'new*') ~ @"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Abandoned 1 operations and reparented 2 descendant operations.
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["op", "log", "-Tdescription"]);
+    insta::assert_snapshot!(output, @r"
+    @  new empty commit
+    ○  abandon commit 68a505386f936fff6d718f55005e77ea72589bc1
+    ○  describe commit e8849ae12c709f2321908879bc724fdb2ab8a781
+    ○  add workspace 'default'
+    ○
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["op", "abandon", "all()"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Cannot abandon the root operation
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
 #[test]
 fn test_op_log_no_graph() {
     let test_env = TestEnvironment::default();
@@ -648,6 +761,103 @@ fn test_op_log_configurable() {
     ");
 }
 
+#[test]
+fn test_op_blame() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    let template = r#"-Tdescription ++ "\n""#;
+
+    work_dir.run_jj(["describe", "-m", "first"]).success();
+    work_dir
+        .run_jj(["bookmark", "create", "-r@", "main"])
+        .success();
+    work_dir.run_jj(["new", "-m", "second"]).success();
+    work_dir
+        .run_jj(["bookmark", "move", "--to=@", "main"])
+        .success();
+    let first_commit_id = work_dir
+        .run_jj(["log", "--no-graph", "-r=description(first)", "-Tcommit_id"])
+        .success()
+        .stdout
+        .into_raw();
+    work_dir
+        .run_jj(["describe", "-r=description(first)", "-m", "first (edited)"])
+        .success();
+    work_dir.run_jj(["bookmark", "delete", "main"]).success();
+    work_dir.run_jj(["abandon", "description(first)"]).success();
+    work_dir.run_jj(["tag", "set", "-r@", "v1"]).success();
+    work_dir.run_jj(["tag", "delete", "v1"]).success();
+
+    let output = work_dir.run_jj(["op", "blame", template, "--bookmark=main"]);
+    insta::assert_snapshot!(output, @r"
+    delete bookmark main
+    + (absent)
+    - zsuskuln hidden 7a6137ef (empty) second
+    describe commit 68a505386f936fff6d718f55005e77ea72589bc1
+    + zsuskuln hidden 7a6137ef (empty) second
+    - zsuskuln hidden d60626a4 (empty) second
+    point bookmark main to commit d60626a4d53805f4634346f7539b51e62b11242c
+    + zsuskuln hidden d60626a4 (empty) second
+    - qpvuntsm hidden 68a50538 (empty) first
+    create bookmark main pointing to commit 68a505386f936fff6d718f55005e77ea72589bc1
+    + qpvuntsm hidden 68a50538 (empty) first
+    - (absent)
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["op", "blame", template, "-t", "v1"]);
+    insta::assert_snapshot!(output, @r"
+    delete tag v1
+    + (absent)
+    - zsuskuln d1bbd317 (empty) second
+    set tag v1 to commit d1bbd317814afa260f8e1fb50acdfe06499da846
+    + zsuskuln d1bbd317 (empty) second
+    - (absent)
+    [EOF]
+    ");
+
+    // Hidden commits can be specified by commit id
+    let output = work_dir.run_jj(["op", "blame", template, "-r", &first_commit_id]);
+    insta::assert_snapshot!(output, @r"
+    abandon commit b74bdb7dae14437bc3841fe70f76415452fcde4f
+    - qpvuntsm hidden b74bdb7d (empty) first (edited)
+    describe commit 68a505386f936fff6d718f55005e77ea72589bc1
+    + qpvuntsm hidden b74bdb7d (empty) first (edited)
+    - qpvuntsm hidden 68a50538 (empty) first
+    describe commit e8849ae12c709f2321908879bc724fdb2ab8a781
+    + qpvuntsm hidden 68a50538 (empty) first
+    - qpvuntsm hidden e8849ae1 (empty) (no description set)
+    add workspace 'default'
+    + qpvuntsm hidden e8849ae1 (empty) (no description set)
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["op", "blame", template, "-r", &first_commit_id, "-n1"]);
+    insta::assert_snapshot!(output, @r"
+    abandon commit b74bdb7dae14437bc3841fe70f76415452fcde4f
+    - qpvuntsm hidden b74bdb7d (empty) first (edited)
+    [EOF]
+    ");
+
+    // Unknown bookmark
+    let output = work_dir.run_jj(["op", "blame", template, "-b", "unknown"]);
+    insta::assert_snapshot!(output, @"");
+
+    let output = work_dir.run_jj(["op", "blame"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    error: the following required arguments were not provided:
+      <--bookmark <BOOKMARK>|--tag <TAG>|--revision <REVSET>>
+
+    Usage: jj operation blame <--bookmark <BOOKMARK>|--tag <TAG>|--revision <REVSET>>
+
+    For more information, try '--help'.
+    [EOF]
+    [exit status: 2]
+    ");
+}
+
 #[test]
 fn test_op_abandon_ancestors() {
     let test_env = TestEnvironment::default();
@@ -879,7 +1089,7 @@ fn test_op_abandon_multiple_heads() {
     let output = work_dir.run_jj(["op", "abandon", prev_op_id]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Abandoned 1 operations and reparented 2 descendant operations.
+    Abandoned 1 operations and reparented 1 descendant operations.
     [EOF]
     ");
 
@@ -2919,6 +3129,232 @@ fn test_op_log_anonymize() {
     ");
 }
 
+#[test]
+fn test_op_verify() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    test_env.add_config(
+        r#"
+[signing]
+backend = "test"
+sign-operations = true
+"#,
+    );
+    work_dir.run_jj(["describe", "-m", "signed 1"]).success();
+    work_dir.run_jj(["describe", "-m", "signed 2"]).success();
+
+    let output = work_dir.run_jj([
+        "op",
+        "log",
+        "--config=ui.show-cryptographic-signatures=true",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    @  07aa954bdd7d test-username@host.example.com 2001-02-03 04:05:09.000 +07:00 - 2001-02-03 04:05:09.000 +07:00 [✓︎]
+    │  describe commit 544dfd883ed828ca87e0cda7dccd9f0d2b157ec9
+    │  args: jj describe -m 'signed 2'
+    ○  699ddf0bfca1 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00 [✓︎]
+    │  describe commit e8849ae12c709f2321908879bc724fdb2ab8a781
+    │  args: jj describe -m 'signed 1'
+    ○  8f47435a3990 test-username@host.example.com 2001-02-03 04:05:07.000 +07:00 - 2001-02-03 04:05:07.000 +07:00
+    │  add workspace 'default'
+    ○  000000000000 root()
+    [EOF]
+    ");
+
+    let template =
+        r#"separate(" ", id.short(), if(signature, signature.status(), "unsigned")) ++ "\n""#;
+    let output = work_dir.run_jj(["op", "log", "--no-graph", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    07aa954bdd7d good
+    699ddf0bfca1 good
+    8f47435a3990 unsigned
+    000000000000 unsigned
+    [EOF]
+    ");
+
+    // The operation written before signing was enabled isn't signed
+    let output = work_dir.run_jj(["op", "verify"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Warning: Operation 8f47435a3990 is not signed: add workspace 'default'
+    Error: 1 operation could not be verified
+    Hint: Use `--trusted` to skip operations written before signing was enabled
+    [EOF]
+    [exit status: 1]
+    ");
+
+    let output = work_dir.run_jj(["op", "verify", "--trusted", "@--"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Verified 2 operations.
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["op", "verify", "@-", "--trusted", "@--"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Verified 1 operation.
+    [EOF]
+    ");
+
+    // Operations reparented by `op abandon` are signed again
+    let output = work_dir.run_jj(["op", "abandon", "@-"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Abandoned 1 operations and reparented 1 descendant operations.
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["op", "verify", "--trusted", "@-"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Verified 1 operation.
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_op_push_pull() {
+    let test_env = TestEnvironment::default();
+    let remote_repo = git::init_bare(test_env.env_root().join("remote.git"));
+    test_env.run_jj_in(".", ["git", "init", "laptop"]).success();
+    test_env.run_jj_in(".", ["git", "init", "vm"]).success();
+    let laptop_dir = test_env.work_dir("laptop");
+    let vm_dir = test_env.work_dir("vm");
+    for dir in [&laptop_dir, &vm_dir] {
+        dir.run_jj(["git", "remote", "add", "origin", "../remote.git"])
+            .success();
+    }
+    vm_dir.run_jj(["workspace", "rename", "vm"]).success();
+    let op_log_template = r#"id.short() ++ " " ++ description ++ "\n""#;
+
+    // Nothing has been pushed yet
+    let output = vm_dir.run_jj(["op", "pull"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    No operations to pull from origin
+    [EOF]
+    ");
+
+    laptop_dir.write_file("file", "a");
+    laptop_dir.run_jj(["commit", "-m", "first"]).success();
+    laptop_dir.write_file("file", "b");
+    laptop_dir.run_jj(["describe", "-m", "second"]).success();
+    let abandoned_commit_id = laptop_dir
+        .run_jj(["log", "--no-graph", "-r@", "-T", "commit_id"])
+        .success()
+        .stdout
+        .into_raw();
+    laptop_dir.run_jj(["abandon"]).success();
+    let output = laptop_dir.run_jj(["op", "push"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Pushed operations up to 273cc3ab986f to origin
+    [EOF]
+    ");
+    // The operation commit has a single parent besides the parent operation,
+    // which keeps the commits referenced by the view
+    let head_commit = remote_repo
+        .find_reference("refs/jj/ops/head")
+        .unwrap()
+        .peel_to_commit()
+        .unwrap();
+    assert_eq!(head_commit.parent_ids().count(), 2);
+
+    // The local operations are merged with the pulled ones
+    let output = vm_dir.run_jj(["op", "pull"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Pulled 7 operations from origin
+    Concurrent modification detected, resolving automatically.
+    [EOF]
+    ");
+    let output = vm_dir.run_jj(["op", "log", "-T", op_log_template]);
+    insta::assert_snapshot!(output, @r"
+    @    1ff20bb04858 reconcile divergent operations
+    ├─╮
+    ○ │  f896a0dc6200 Renamed workspace 'default' to 'vm'
+    ○ │  2d9a5a2b4d31 add git remote origin
+    ○ │  bb0f38a1f44d add workspace 'default'
+    │ ○  273cc3ab986f abandon commit 002c577a74368c50777c8aad397375fe8dcedafa
+    │ ○  46a80e467cfa describe commit 5bc4b54295bd255c110c6e983173bb0cea12373a
+    │ ○  50b681ca2a67 snapshot working copy
+    │ ○  51d12cfc7032 commit e5aefd1b8c04b401a96e13d30f75a54dd6d39605
+    │ ○  40ff325e53ee snapshot working copy
+    │ ○  f4530aef5d3f add git remote origin
+    │ ○  8f47435a3990 add workspace 'default'
+    ├─╯
+    ○  000000000000
+    [EOF]
+    ");
+    let output = vm_dir.run_jj(["log", "-r", "all()", "-T", "description"]);
+    insta::assert_snapshot!(output, @r"
+    @
+    │ ○
+    │ ○  first
+    ├─╯
+    ◆
+    [EOF]
+    ");
+    // Hidden commits are transferred along with the operations
+    let output = vm_dir.run_jj(["log", "--no-graph", "-r", &abandoned_commit_id]);
+    insta::assert_snapshot!(output, @r"
+    yqosqzyt hidden test.user@example.com 2001-02-03 08:05:14 002c577a
+    second
+    [EOF]
+    ");
+    let output = vm_dir.run_jj(["op", "pull"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+
+    // Pulling operations that include the local head doesn't create a merge
+    vm_dir.run_jj(["op", "push"]).success();
+    let output = laptop_dir.run_jj(["op", "pull"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Pulled 4 operations from origin
+    [EOF]
+    ");
+    let output = laptop_dir.run_jj(["op", "log", "-T", op_log_template, "--limit=2"]);
+    insta::assert_snapshot!(output, @r"
+    @    1ff20bb04858 reconcile divergent operations
+    ├─╮
+    ○ │  f896a0dc6200 Renamed workspace 'default' to 'vm'
+    [EOF]
+    ");
+
+    // Operations pushed concurrently have to be pulled first
+    vm_dir.run_jj(["new", "-m", "vm"]).success();
+    vm_dir.run_jj(["op", "push"]).success();
+    laptop_dir.run_jj(["new", "-m", "laptop"]).success();
+    let output = laptop_dir.run_jj(["op", "push"]);
+    insta::assert_snapshot!(output, @"
+    ------- stderr -------
+    Error: The remote has operations that aren't in the local operation log
+    Hint: Run `jj op pull` to integrate the remote operations first.
+    [EOF]
+    [exit status: 1]
+    ");
+    laptop_dir.run_jj(["op", "pull"]).success();
+    let output = laptop_dir.run_jj(["op", "push"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Pushed operations up to 12537760be6b to origin
+    [EOF]
+    ");
+    let output = vm_dir.run_jj(["op", "push"]);
+    insta::assert_snapshot!(output, @"
+    ------- stderr -------
+    Error: The remote has operations that aren't in the local operation log
+    Hint: Run `jj op pull` to integrate the remote operations first.
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
 fn init_bare_git_repo(git_repo_path: &Path) -> gix::Repository {
     let git_repo = git::init_bare(git_repo_path);
     let commit_result = git::add_commit(
@@ -0,0 +1,369 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::CommandOutput;
+use crate::common::TestEnvironment;
+use crate::common::TestWorkDir;
+
+#[test]
+fn test_patch_export_stdout() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "a\nb\nc\n");
+    work_dir
+        .run_jj(["commit", "-m", "add file\n\nwith a body"])
+        .success();
+    work_dir.write_file("file", "a\nB\nc\n");
+    work_dir.write_file("other", "other\n");
+    work_dir.run_jj(["describe", "-m", "modify file"]).success();
+
+    let output = work_dir.run_jj(["patch", "export", "-r", "@", "--stdout"]);
+    insta::assert_snapshot!(output, @r"
+    From 84d313b1abb849f2d912981f0392775bcc5b3dfa Mon Sep 17 00:00:00 2001
+    From: Test User <test.user@example.com>
+    Date: Sat, 3 Feb 2001 04:05:09 +0700
+    Subject: [PATCH] modify file
+    MIME-Version: 1.0
+    Content-Type: text/plain; charset=UTF-8
+    Content-Transfer-Encoding: 8bit
+
+    Change-Id: I8e4fac809cbb3b162c953458183c8dea6a6a6964
+    ---
+    file  | 2 +-
+    other | 1 +
+    2 files changed, 2 insertions(+), 1 deletion(-)
+
+    diff --git a/file b/file
+    index de980441c3..7be73ce3c1 100644
+    --- a/file
+    +++ b/file
+    @@ -1,3 +1,3 @@
+     a
+    -b
+    +B
+     c
+    diff --git a/other b/other
+    new file mode 100644
+    index 0000000000..e45c9c2666
+    --- /dev/null
+    +++ b/other
+    @@ -0,0 +1,1 @@
+    +other
+
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["patch", "export", "-r", "merges()"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    No revisions to export.
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_patch_export_files() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "a\n");
+    work_dir
+        .run_jj(["commit", "-m", "First: add a file!"])
+        .success();
+    work_dir.write_file("file", "b\n");
+    work_dir.run_jj(["describe", "-m", "second"]).success();
+
+    let output = work_dir.run_jj([
+        "patch",
+        "export",
+        "-r",
+        "::@ & ~root()",
+        "-o",
+        "out",
+        "--cover-letter",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    out/0000-cover-letter.patch
+    out/0001-First-add-a-file.patch
+    out/0002-second.patch
+    [EOF]
+    ");
+    let cover_letter = work_dir.read_file("out/0000-cover-letter.patch");
+    insta::assert_snapshot!(cover_letter, @r"
+    From 0000000000000000000000000000000000000000 Mon Sep 17 00:00:00 2001
+    From: Test User <test.user@example.com>
+    Date: Sat, 3 Feb 2001 04:05:10 +0700
+    Subject: [PATCH 0/2] *** SUBJECT HERE ***
+    MIME-Version: 1.0
+    Content-Type: text/plain; charset=UTF-8
+    Content-Transfer-Encoding: 8bit
+
+    *** BLURB HERE ***
+
+    Test User (2):
+      First: add a file!
+      second
+    ---
+    ");
+}
+
+#[test]
+fn test_patch_roundtrip() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo1"]).success();
+    test_env.run_jj_in(".", ["git", "init", "repo2"]).success();
+    let work_dir1 = test_env.work_dir("repo1");
+    let work_dir2 = test_env.work_dir("repo2");
+    work_dir1.write_file("file", "a\nb\nc\n");
+    work_dir1.write_file("removed", "removed\n");
+    work_dir1.run_jj(["commit", "-m", "first"]).success();
+    work_dir1.write_file("file", "a\nB\nc\nd\n");
+    work_dir1.remove_file("removed");
+    work_dir1.write_file("added", "added\n");
+    work_dir1
+        .run_jj([
+            "describe",
+            "-m",
+            "second\n\nSigned-off-by: Test User <test.user@example.com>",
+            "--author",
+            "Other Author <other@example.com>",
+        ])
+        .success();
+    work_dir1
+        .run_jj(["patch", "export", "-r", "::@ & ~root()", "-o", "../patches"])
+        .success();
+
+    let output = work_dir2.run_jj([
+        "patch",
+        "apply",
+        "../patches/0001-first.patch",
+        "../patches/0002-second.patch",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Applied 2 patches:
+      qpvuntsm 50a7ec70 first
+      kkmpptxz fe5357d3 second
+    [EOF]
+    ");
+    // Change ids, authors, and descriptions are preserved
+    insta::assert_snapshot!(get_log_output(&work_dir1), @r"
+    @  kkmpptxzrspx other@example.com second
+    ○  qpvuntsmwlqt test.user@example.com first
+    ◆  zzzzzzzzzzzz
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir2), @r"
+    ○  kkmpptxzrspx other@example.com second
+    ○  qpvuntsmwlqt test.user@example.com first
+    @  rlvkpnrzqnoo test.user@example.com
+    ◆  zzzzzzzzzzzz
+    [EOF]
+    ");
+    let output = work_dir2.run_jj(["log", "--no-graph", "-r", "kkmpptxz", "-T", "description"]);
+    insta::assert_snapshot!(output, @r"
+    second
+
+    Signed-off-by: Test User <test.user@example.com>
+    [EOF]
+    ");
+    let output = work_dir2.run_jj(["diff", "--git", "-r", "kkmpptxz"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/added b/added
+    new file mode 100644
+    index 0000000000..d5f7fc3f74
+    --- /dev/null
+    +++ b/added
+    @@ -0,0 +1,1 @@
+    +added
+    diff --git a/file b/file
+    index de980441c3..a7bc997ebe 100644
+    --- a/file
+    +++ b/file
+    @@ -1,3 +1,4 @@
+     a
+    -b
+    +B
+     c
+    +d
+    diff --git a/removed b/removed
+    deleted file mode 100644
+    index 2c3f0b3406..0000000000
+    --- a/removed
+    +++ /dev/null
+    @@ -1,1 +0,0 @@
+    -removed
+    [EOF]
+    ");
+
+    // The change ids are already in use
+    let output = work_dir2.run_jj(["patch", "apply", "../patches/0001-first.patch"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Applied 1 patches:
+      kpqxywon 4d46d902 first
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_patch_apply_conflict() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file("file", "1\n2\n3\n4\nfive\n6\n7\n8\nnine\n");
+    std::fs::write(
+        test_env.env_root().join("patch"),
+        indoc::indoc! {"
+            diff --git a/file b/file
+            --- a/file
+            +++ b/file
+            @@ -1,3 +1,3 @@
+             1
+            -2
+            +two
+             3
+            @@ -4,3 +4,3 @@
+             4
+            -5
+            +FIVE
+             6
+            @@ -7,3 +7,3 @@
+             7
+             8
+            -9
+            +NINE
+        "},
+    )
+    .unwrap();
+
+    // Hunks which don't match become conflicts
+    let output = work_dir.run_jj(["patch", "apply", "-d", "@", "../patch"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Applied 1 patches:
+      kkmpptxz 87db471d (conflict) (no description set)
+    New conflicts appeared in 1 commits:
+      kkmpptxz 87db471d (conflict) (no description set)
+    Hint: To resolve the conflicts, start by creating a commit on top of
+    the conflicted commit:
+      jj new kkmpptxz
+    Then use `jj resolve`, or edit the conflict markers in the file directly.
+    Once the conflicts are resolved, you can inspect the result with `jj diff`.
+    Then run `jj squash` to move the resolution into the conflicted commit.
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "show", "-r", "@+", "file"]);
+    insta::assert_snapshot!(output, @r"
+    1
+    two
+    3
+    4
+    <<<<<<< Conflict 1 of 2
+    %%%%%%% Changes from base to side #1
+    -5
+    +five
+    +++++++ Contents of side #2
+    FIVE
+    >>>>>>> Conflict 1 of 2 ends
+    6
+    7
+    8
+    <<<<<<< Conflict 2 of 2
+    %%%%%%% Changes from base to side #1
+    -9
+    +nine
+    +++++++ Contents of side #2
+    NINE
+    >>>>>>> Conflict 2 of 2 ends
+    [EOF]
+    ");
+
+    // Binary patches can't be applied
+    std::fs::write(
+        test_env.env_root().join("binary"),
+        "diff --git a/file b/file\nBinary files a/file and b/file differ\n",
+    )
+    .unwrap();
+    let output = work_dir.run_jj(["patch", "apply", "../binary"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Cannot apply binary patch to file
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // Parse errors are reported with the line number
+    std::fs::write(
+        test_env.env_root().join("invalid"),
+        "diff --git a/file b/file\n--- a/file\n+++ b/file\n@@ -1 +1 @@\n-1\n",
+    )
+    .unwrap();
+    let output = work_dir.run_jj(["patch", "apply", "../invalid"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to parse ../invalid
+    Caused by: Line 6: Unexpected end of hunk
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_patch_apply_three_way_merge() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+    work_dir.run_jj(["commit", "-m", "base"]).success();
+    work_dir.write_file("file", "1\n2\n3\n4\nfive\n6\n7\n8\n9\n");
+    work_dir.run_jj(["describe", "-m", "five"]).success();
+    work_dir
+        .run_jj(["patch", "export", "-r", "@", "-o", "../patches"])
+        .success();
+    work_dir.run_jj(["new", "@-"]).success();
+    work_dir.write_file("file", "1\n2\nthree\n4\n5\n6\n7\n8\n9\n");
+
+    // The context doesn't match, but the patch merges cleanly with the
+    // preimage found by the index line
+    let output = work_dir.run_jj(["patch", "apply", "-d", "@", "../patches/0001-five.patch"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Applied 1 patches:
+      royxmykx 6bb8f8f9 five
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "show", "-r", "@+", "file"]);
+    insta::assert_snapshot!(output, @r"
+    1
+    2
+    three
+    4
+    five
+    6
+    7
+    8
+    9
+    [EOF]
+    ");
+}
+
+#[must_use]
+fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
+    let template = r#"separate(" ", change_id.short(), author.email(), description.first_line(), if(conflict, "conflict"))"#;
+    work_dir.run_jj(["log", "-T", template])
+}
@@ -655,6 +655,84 @@ fn test_too_many_parents() {
     "#);
 }
 
+#[test]
+fn test_edit_conflict_side() {
+    let mut test_env = TestEnvironment::default();
+    let editor_script = test_env.set_up_fake_editor();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    create_commit_with_files(&work_dir, "base", &[], &[("file", "base\n")]);
+    create_commit_with_files(&work_dir, "a", &["base"], &[("file", "a\n")]);
+    create_commit_with_files(&work_dir, "b", &["base"], &[("file", "b\n")]);
+    create_commit_with_files(&work_dir, "c", &["base"], &[("file", "c\n")]);
+    create_commit_with_files(&work_dir, "conflict", &["a", "b", "c"], &[]);
+
+    let output = work_dir.run_jj(["resolve", "--edit-side=4", "file"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Conflict at 'file' has no side #4
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // Unchanged side
+    std::fs::write(&editor_script, "expect\nc\n").unwrap();
+    let output = work_dir.run_jj(["resolve", "--edit-side=3", "file"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+
+    // Editing a side to match the base removes it from the conflict
+    std::fs::write(&editor_script, "expect\nc\n\0write\nbase\n").unwrap();
+    let output = work_dir.run_jj(["resolve", "--edit-side=3", "file"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy  (@) now at: znkkpsqq c9bd619e conflict | (conflict) conflict
+    Parent commit (@-)      : zsuskuln 45537d53 a | a
+    Parent commit (@-)      : royxmykx 89d1b299 b | b
+    Parent commit (@-)      : vruxwmqv a87962b8 c | c
+    Added 0 files, modified 1 files, removed 0 files
+    Warning: There are unresolved conflicts at these paths:
+    file    2-sided conflict
+    New conflicts appeared in 1 commits:
+      znkkpsqq c9bd619e conflict | (conflict) conflict
+    Hint: To resolve the conflicts, start by creating a commit on top of
+    the conflicted commit:
+      jj new znkkpsqq
+    Then use `jj resolve`, or edit the conflict markers in the file directly.
+    Once the conflicts are resolved, you can inspect the result with `jj diff`.
+    Then run `jj squash` to move the resolution into the conflicted commit.
+    [EOF]
+    ");
+    insta::assert_snapshot!(work_dir.read_file("file"), @r"
+    <<<<<<< Conflict 1 of 1
+    %%%%%%% Changes from base to side #1
+    -base
+    +a
+    +++++++ Contents of side #2
+    b
+    >>>>>>> Conflict 1 of 1 ends
+    ");
+
+    // Resolve the remaining conflict by making both sides agree
+    std::fs::write(&editor_script, "expect\nb\n\0write\na\n").unwrap();
+    let output = work_dir.run_jj(["resolve", "--edit-side=2", "file"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy  (@) now at: znkkpsqq 29561105 conflict | conflict
+    Parent commit (@-)      : zsuskuln 45537d53 a | a
+    Parent commit (@-)      : royxmykx 89d1b299 b | b
+    Parent commit (@-)      : vruxwmqv a87962b8 c | c
+    Added 0 files, modified 1 files, removed 0 files
+    Existing conflicts were resolved or abandoned from 1 commits.
+    [EOF]
+    ");
+    insta::assert_snapshot!(work_dir.read_file("file"), @"a");
+}
+
 #[test]
 fn test_simplify_conflict_sides() {
     let mut test_env = TestEnvironment::default();
@@ -428,3 +428,138 @@ backend = "test"
     [EOF]
     ");
 }
+
+#[test]
+fn test_trusted_signature_revset() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(
+        r#"
+[signing]
+behavior = "drop"
+backend = "test"
+"#,
+    );
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "a");
+    work_dir.run_jj(["commit", "-m", "alice, file"]).success();
+    work_dir.write_file("file", "b");
+    work_dir.run_jj(["commit", "-m", "bob, file"]).success();
+    work_dir.create_dir("docs");
+    work_dir.write_file("docs/file", "a");
+    work_dir.run_jj(["commit", "-m", "bob, docs"]).success();
+    work_dir.write_file("docs/file", "b");
+    work_dir
+        .run_jj(["commit", "-m", "unsigned, docs"])
+        .success();
+    for (author, revset) in [("alice", "description(alice)"), ("bob", "description(bob)")] {
+        work_dir
+            .run_jj(["sign", "-r", revset, "--key", author])
+            .success();
+    }
+    let log_trusted = || work_dir.run_jj(["log", "-r", "trusted_signature()", "-T", "description"]);
+
+    // No commits are trusted without a policy
+    let output = log_trusted();
+    insta::assert_snapshot!(output, @"");
+
+    let output = work_dir.run_jj(["log", "-r", "signed_by(bob)", "-T", "description"]);
+    insta::assert_snapshot!(output, @r"
+    ○  bob, docs
+    ○  bob, file
+    │
+    ~
+    [EOF]
+    ");
+
+    test_env.add_config(
+        r#"
+[signing.trust-policy.alice]
+signer = "alice"
+
+[signing.trust-policy.bob]
+signer = "bob"
+paths = "root:docs"
+"#,
+    );
+    let output = log_trusted();
+    insta::assert_snapshot!(output, @r"
+    ○  bob, docs
+    ~  (elided revisions)
+    ○  alice, file
+    │
+    ~
+    [EOF]
+    ");
+
+    // Rules may restrict the revisions
+    test_env.add_config(
+        r#"
+[signing.trust-policy.bob-everywhere]
+signer = "bob"
+revisions = "description(file)"
+"#,
+    );
+    let output = log_trusted();
+    insta::assert_snapshot!(output, @r"
+    ○  bob, docs
+    ○  bob, file
+    ○  alice, file
+    │
+    ~
+    [EOF]
+    ");
+
+    // User alias takes precedence
+    let output = work_dir.run_jj([
+        "log",
+        "-r",
+        "trusted_signature()",
+        "-T",
+        "description",
+        "--config=revset-aliases.'trusted_signature()'='description(unsigned)'",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    ○  unsigned, docs
+    │
+    ~
+    [EOF]
+    ");
+
+    // Invalid rule only affects commands using the policy
+    test_env.add_config(
+        r#"
+[signing.trust-policy.broken]
+signer = "carol"
+revisions = "description("
+"#,
+    );
+    let output = work_dir.run_jj(["log", "-r", "@-", "-T", "description"]);
+    insta::assert_snapshot!(output, @r"
+    ○  unsigned, docs
+    │
+    ~
+    [EOF]
+    ");
+    let output = log_trusted();
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to parse revset: Failed to load trust policy
+    Caused by:
+    1:  --> 1:1
+      |
+    1 | trusted_signature()
+      | ^---------------^
+      |
+      = Failed to load trust policy
+    2: Invalid trust policy rule `signing.trust-policy.broken`
+    3:  --> 1:13
+      |
+    1 | description(
+      |             ^---
+      |
+      = expected <strict_identifier> or <expression>
+    [EOF]
+    [exit status: 1]
+    ");
+}
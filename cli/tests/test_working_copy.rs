@@ -41,6 +41,8 @@ fn test_snapshot_large_file() {
         This will increase the maximum file size allowed for new files, in this repository only.
       - Run `jj --config snapshot.max-new-file-size=13 st`
         This will increase the maximum file size allowed for new files, for this command only.
+      - Run `jj file track --force-large large`
+        This will track the files regardless of their size.
     [EOF]
     ");
 
@@ -61,6 +63,8 @@ fn test_snapshot_large_file() {
         This will increase the maximum file size allowed for new files, in this repository only.
       - Run `jj --config snapshot.max-new-file-size=11264 st`
         This will increase the maximum file size allowed for new files, for this command only.
+      - Run `jj file track --force-large large`
+        This will track the files regardless of their size.
     [EOF]
     ");
 
@@ -84,6 +88,8 @@ fn test_snapshot_large_file() {
         This will increase the maximum file size allowed for new files, in this repository only.
       - Run `jj --config snapshot.max-new-file-size=11264 file track large large2`
         This will increase the maximum file size allowed for new files, for this command only.
+      - Run `jj file track --force-large large large2`
+        This will track the files regardless of their size.
     [EOF]
     ");
 
@@ -140,6 +146,8 @@ fn test_snapshot_large_file_restore() {
         This will increase the maximum file size allowed for new files, in this repository only.
       - Run `jj --config snapshot.max-new-file-size=13 st`
         This will increase the maximum file size allowed for new files, for this command only.
+      - Run `jj file track --force-large file`
+        This will track the files regardless of their size.
     Working copy  (@) now at: kkmpptxz 119f5156 (no description set)
     Parent commit (@-)      : zzzzzzzz 00000000 (empty) (no description set)
     Added 1 files, modified 0 files, removed 0 files
@@ -162,6 +170,129 @@ fn test_snapshot_large_file_restore() {
     ");
 }
 
+#[test]
+fn test_snapshot_large_file_limits_and_policy() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    test_env.add_config(indoc! {r#"
+        snapshot.max-new-file-size = 10
+
+        [snapshot.file-size-limits.assets]
+        patterns = ["glob:assets/*"]
+        max-new-file-size = 20
+
+        [snapshot.file-size-limits.unlimited]
+        patterns = ["glob:**/*.bin"]
+        max-new-file-size = 0
+    "#});
+
+    // The first matching limit applies
+    work_dir.write_file("small", "small");
+    work_dir.write_file("large", "a lot of text");
+    work_dir.write_file("assets/large", "a lot of text");
+    work_dir.write_file("assets/huge", "a lot more text than that");
+    work_dir.write_file("assets/huge.bin", "a lot more text than that");
+    work_dir.write_file("huge.bin", "a lot more text than that");
+    let output = work_dir.run_jj(["file", "list"]);
+    insta::assert_snapshot!(output, @r"
+    assets/large
+    huge.bin
+    small
+    [EOF]
+    ------- stderr -------
+    Warning: Refused to snapshot some files:
+      assets/huge: 25.0B (25 bytes); the maximum size allowed is 20.0B (20 bytes)
+      assets/huge.bin: 25.0B (25 bytes); the maximum size allowed is 20.0B (20 bytes)
+      large: 13.0B (13 bytes); the maximum size allowed is 10.0B (10 bytes)
+    Hint: This is to prevent large files from being added by accident. You can fix this by:
+      - Adding the file to `.gitignore`
+      - Run `jj config set --repo snapshot.max-new-file-size 25`
+        This will increase the maximum file size allowed for new files, in this repository only.
+      - Run `jj --config snapshot.max-new-file-size=25 st`
+        This will increase the maximum file size allowed for new files, for this command only.
+      - Run `jj file track --force-large assets/huge assets/huge.bin large`
+        This will track the files regardless of their size.
+    [EOF]
+    ");
+
+    // With the "ignore" policy, large files are silently left untracked...
+    test_env.add_config(r#"snapshot.large-new-file-policy = "ignore""#);
+    work_dir.write_file("large2", "a lot of text");
+    let output = work_dir.run_jj(["file", "list"]);
+    insta::assert_snapshot!(output, @r"
+    assets/large
+    huge.bin
+    small
+    [EOF]
+    ");
+
+    // ... but still reported by `jj status`
+    let output = work_dir.run_jj(["status"]);
+    insta::assert_snapshot!(output, @r"
+    Working copy changes:
+    A assets/large
+    A huge.bin
+    A small
+    Untracked paths:
+    ? assets/huge
+    ? assets/huge.bin
+    ? large
+    ? large2
+    Working copy  (@) : qpvuntsm b1d6e7df (no description set)
+    Parent commit (@-): zzzzzzzz 00000000 (empty) (no description set)
+    [EOF]
+    ------- stderr -------
+    Warning: Refused to snapshot some files:
+      assets/huge: 25.0B (25 bytes); the maximum size allowed is 20.0B (20 bytes)
+      assets/huge.bin: 25.0B (25 bytes); the maximum size allowed is 20.0B (20 bytes)
+      large: 13.0B (13 bytes); the maximum size allowed is 10.0B (10 bytes)
+      large2: 13.0B (13 bytes); the maximum size allowed is 10.0B (10 bytes)
+    Hint: This is to prevent large files from being added by accident. You can fix this by:
+      - Adding the file to `.gitignore`
+      - Run `jj config set --repo snapshot.max-new-file-size 25`
+        This will increase the maximum file size allowed for new files, in this repository only.
+      - Run `jj --config snapshot.max-new-file-size=25 st`
+        This will increase the maximum file size allowed for new files, for this command only.
+      - Run `jj file track --force-large assets/huge assets/huge.bin large large2`
+        This will track the files regardless of their size.
+    [EOF]
+    ");
+
+    // --force-large tracks the files regardless of the limits
+    let output = work_dir.run_jj(["file", "track", "--force-large", "large", "assets/huge"]);
+    insta::assert_snapshot!(output, @"");
+    let output = work_dir.run_jj(["file", "list"]);
+    insta::assert_snapshot!(output, @r"
+    assets/huge
+    assets/large
+    huge.bin
+    large
+    small
+    [EOF]
+    ");
+
+    // Invalid limit
+    let output = work_dir.run_jj([
+        "file",
+        "list",
+        "--config=snapshot.file-size-limits.assets.patterns=['glob:(']",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to parse fileset: Syntax error
+    Caused by:  --> 1:6
+      |
+    1 | glob:(
+      |      ^---
+      |
+      = expected <identifier>, <string_literal>, or <raw_string_literal>
+    Hint: See https://jj-vcs.github.io/jj/latest/filesets/ or use `jj help -k filesets` for filesets syntax and how to match file paths.
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
 #[test]
 fn test_materialize_and_snapshot_different_conflict_markers() {
     let test_env = TestEnvironment::default();
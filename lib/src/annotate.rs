@@ -20,6 +20,7 @@
 
 use std::collections::HashMap;
 use std::collections::hash_map;
+use std::fmt::Debug;
 use std::iter;
 use std::ops::Range;
 use std::sync::Arc;
@@ -32,6 +33,7 @@ use pollster::FutureExt as _;
 use crate::backend::BackendError;
 use crate::backend::BackendResult;
 use crate::backend::CommitId;
+use crate::backend::FileId;
 use crate::commit::Commit;
 use crate::conflicts::ConflictMarkerStyle;
 use crate::conflicts::ConflictMaterializeOptions;
@@ -149,6 +151,15 @@ impl FileAnnotation {
     }
 }
 
+/// Converts file contents before lines are compared.
+///
+/// This can be used to annotate text extracted from non-text files.
+pub trait FileContentConverter: Debug + Send + Sync {
+    /// Returns the text to be annotated for the `content` of the file at
+    /// `path`.
+    fn convert(&self, path: &RepoPath, id: &FileId, content: Vec<u8>) -> BackendResult<Vec<u8>>;
+}
+
 /// Annotation process for a specific file.
 #[derive(Clone, Debug)]
 pub struct FileAnnotator {
@@ -156,6 +167,7 @@ pub struct FileAnnotator {
     file_path: RepoPathBuf,
     starting_text: BString,
     state: AnnotationState,
+    converter: Option<Arc<dyn FileContentConverter>>,
 }
 
 impl FileAnnotator {
@@ -163,8 +175,32 @@ impl FileAnnotator {
     ///
     /// If the file is not found, the result would be empty.
     pub fn from_commit(starting_commit: &Commit, file_path: &RepoPath) -> BackendResult<Self> {
-        let source = Source::load(starting_commit, file_path)?;
-        Ok(Self::with_source(starting_commit.id(), file_path, source))
+        let source = Source::load(starting_commit, file_path, None)?;
+        Ok(Self::with_source(
+            starting_commit.id(),
+            file_path,
+            source,
+            None,
+        ))
+    }
+
+    /// Initializes annotator for a specific file in the `starting_commit`.
+    /// File contents are converted by the `converter` before lines are
+    /// compared.
+    ///
+    /// If the file is not found, the result would be empty.
+    pub fn from_commit_with_converter(
+        starting_commit: &Commit,
+        file_path: &RepoPath,
+        converter: Arc<dyn FileContentConverter>,
+    ) -> BackendResult<Self> {
+        let source = Source::load(starting_commit, file_path, Some(converter.as_ref()))?;
+        Ok(Self::with_source(
+            starting_commit.id(),
+            file_path,
+            source,
+            Some(converter),
+        ))
     }
 
     /// Initializes annotator for a specific file path starting with the given
@@ -179,13 +215,14 @@ impl FileAnnotator {
         starting_text: impl Into<Vec<u8>>,
     ) -> Self {
         let source = Source::new(BString::new(starting_text.into()));
-        Self::with_source(starting_commit_id, file_path, source)
+        Self::with_source(starting_commit_id, file_path, source, None)
     }
 
     fn with_source(
         starting_commit_id: &CommitId,
         file_path: &RepoPath,
         mut source: Source,
+        converter: Option<Arc<dyn FileContentConverter>>,
     ) -> Self {
         source.fill_line_map();
         let starting_text = source.text.clone();
@@ -205,6 +242,7 @@ impl FileAnnotator {
             file_path: file_path.to_owned(),
             starting_text,
             state,
+            converter,
         }
     }
 
@@ -218,7 +256,13 @@ impl FileAnnotator {
         repo: &dyn Repo,
         domain: &Arc<ResolvedRevsetExpression>,
     ) -> Result<(), RevsetEvaluationError> {
-        process_commits(repo, &mut self.state, domain, &self.file_path)
+        process_commits(
+            repo,
+            &mut self.state,
+            domain,
+            &self.file_path,
+            self.converter.as_deref(),
+        )
     }
 
     /// Remaining commit ids to visit from.
@@ -266,9 +310,13 @@ impl Source {
         }
     }
 
-    fn load(commit: &Commit, file_path: &RepoPath) -> Result<Self, BackendError> {
+    fn load(
+        commit: &Commit,
+        file_path: &RepoPath,
+        converter: Option<&dyn FileContentConverter>,
+    ) -> Result<Self, BackendError> {
         let tree = commit.tree()?;
-        let text = get_file_contents(commit.store(), file_path, &tree).block_on()?;
+        let text = get_file_contents(commit.store(), file_path, &tree, converter).block_on()?;
         Ok(Self::new(text))
     }
 
@@ -298,6 +346,7 @@ fn process_commits(
     state: &mut AnnotationState,
     domain: &Arc<ResolvedRevsetExpression>,
     file_name: &RepoPath,
+    converter: Option<&dyn FileContentConverter>,
 ) -> Result<(), RevsetEvaluationError> {
     let predicate = RevsetFilterPredicate::File(FilesetExpression::file_path(file_name.to_owned()));
     // TODO: If the domain isn't a contiguous range, changes masked out by it
@@ -313,7 +362,7 @@ fn process_commits(
     state.num_unresolved_roots = 0;
     for node in revset.iter_graph() {
         let (commit_id, edge_list) = node?;
-        process_commit(repo, file_name, state, &commit_id, &edge_list)?;
+        process_commit(repo, file_name, converter, state, &commit_id, &edge_list)?;
         if state.commit_source_map.len() == state.num_unresolved_roots {
             // No more lines to propagate to ancestors.
             break;
@@ -328,6 +377,7 @@ fn process_commits(
 fn process_commit(
     repo: &dyn Repo,
     file_name: &RepoPath,
+    converter: Option<&dyn FileContentConverter>,
     state: &mut AnnotationState,
     current_commit_id: &CommitId,
     edges: &[GraphEdge<CommitId>],
@@ -342,7 +392,7 @@ fn process_commit(
             hash_map::Entry::Occupied(entry) => entry.into_mut(),
             hash_map::Entry::Vacant(entry) => {
                 let commit = repo.store().get_commit(entry.key())?;
-                entry.insert(Source::load(&commit, file_name)?)
+                entry.insert(Source::load(&commit, file_name, converter)?)
             }
         };
 
@@ -439,11 +489,18 @@ async fn get_file_contents(
     store: &Store,
     path: &RepoPath,
     tree: &MergedTree,
+    converter: Option<&dyn FileContentConverter>,
 ) -> Result<BString, BackendError> {
     let file_value = tree.path_value_async(path).await?;
     let effective_file_value = materialize_tree_value(store, path, file_value).await?;
     match effective_file_value {
-        MaterializedTreeValue::File(mut file) => Ok(file.read_all(path).await?.into()),
+        MaterializedTreeValue::File(mut file) => {
+            let content = file.read_all(path).await?;
+            match converter {
+                Some(converter) => Ok(converter.convert(path, &file.id, content)?.into()),
+                None => Ok(content.into()),
+            }
+        }
         MaterializedTreeValue::FileConflict(file) => {
             // TODO: track line origins without materializing
             let options = ConflictMaterializeOptions {
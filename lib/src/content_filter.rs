@@ -0,0 +1,543 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Content filters applied to file contents when snapshotting ("clean") and
+//! checking out ("smudge") the local working copy.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use bstr::ByteSlice as _;
+use digest::Digest as _;
+use itertools::Itertools as _;
+use thiserror::Error;
+
+use crate::backend::FileId;
+use crate::config::ConfigGetError;
+use crate::file_util::persist_temp_file;
+use crate::file_util::prune_least_recently_modified;
+use crate::file_util::touch_if_older_than;
+use crate::fileset;
+use crate::fileset::FilesetDiagnostics;
+use crate::fileset::FilesetExpression;
+use crate::hex_util;
+use crate::matchers::Matcher;
+use crate::object_id::ObjectId as _;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathUiConverter;
+use crate::settings::UserSettings;
+
+/// Error that may occur while running a content filter.
+#[derive(Debug, Error)]
+pub enum ContentFilterError {
+    /// The filter command couldn't be started or communicated with.
+    #[error("Failed to run {direction} filter `{name}` on {path}")]
+    Io {
+        /// Name of the filter in `working-copy.filters`.
+        name: String,
+        /// Whether the filter was run as "clean" or "smudge".
+        direction: FilterDirection,
+        /// Path of the file being filtered.
+        path: String,
+        /// Source error.
+        source: io::Error,
+    },
+    /// The filter command exited with non-zero status.
+    #[error("{direction} filter `{name}` failed on {path}: {stderr}")]
+    Failed {
+        /// Name of the filter in `working-copy.filters`.
+        name: String,
+        /// Whether the filter was run as "clean" or "smudge".
+        direction: FilterDirection,
+        /// Path of the file being filtered.
+        path: String,
+        /// Output the command wrote to its stderr.
+        stderr: String,
+    },
+}
+
+/// Whether a filter is applied when snapshotting or checking out files.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FilterDirection {
+    /// Applied to working-copy contents before they are written to the store.
+    Clean,
+    /// Applied to store contents before they are written to the working copy.
+    Smudge,
+}
+
+impl FilterDirection {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Clean => "clean",
+            Self::Smudge => "smudge",
+        }
+    }
+}
+
+impl std::fmt::Display for FilterDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Filter implemented by jj itself.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BuiltinFilter {
+    /// Removes spaces and tabs at the end of each line.
+    StripTrailingWhitespace,
+}
+
+impl BuiltinFilter {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            ":strip-trailing-whitespace" => Some(Self::StripTrailingWhitespace),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::StripTrailingWhitespace => ":strip-trailing-whitespace",
+        }
+    }
+
+    fn apply(self, contents: &[u8]) -> Vec<u8> {
+        match self {
+            Self::StripTrailingWhitespace => {
+                let mut output = Vec::with_capacity(contents.len());
+                for line in contents.lines_with_terminator() {
+                    let (line, eol) = match line.strip_suffix(b"\r\n") {
+                        Some(line) => (line, b"\r\n".as_slice()),
+                        None => match line.strip_suffix(b"\n") {
+                            Some(line) => (line, b"\n".as_slice()),
+                            None => (line, b"".as_slice()),
+                        },
+                    };
+                    output.extend_from_slice(line.trim_end_with(|c| c == ' ' || c == '\t'));
+                    output.extend_from_slice(eol);
+                }
+                output
+            }
+        }
+    }
+}
+
+/// Command to run as a clean or smudge filter.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FilterCommand {
+    /// Filter implemented by jj.
+    Builtin(BuiltinFilter),
+    /// External program which reads the contents from stdin and writes the
+    /// filtered contents to stdout. `$path` in the arguments is substituted
+    /// with the repository path of the file.
+    External(Vec<String>),
+}
+
+impl FilterCommand {
+    /// Bytes identifying this command in the cache key.
+    fn cache_key_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Builtin(builtin) => builtin.name().as_bytes().to_vec(),
+            Self::External(args) => args.join("\0").into_bytes(),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum RawFilterCommand {
+    Builtin(String),
+    External(Vec<String>),
+}
+
+impl TryFrom<RawFilterCommand> for FilterCommand {
+    type Error = String;
+
+    fn try_from(raw: RawFilterCommand) -> Result<Self, Self::Error> {
+        match raw {
+            RawFilterCommand::Builtin(name) => BuiltinFilter::from_name(&name)
+                .map(Self::Builtin)
+                .ok_or_else(|| format!("Unknown builtin filter: {name}")),
+            RawFilterCommand::External(args) if args.is_empty() => {
+                Err("Filter command must not be empty".to_owned())
+            }
+            RawFilterCommand::External(args) => Ok(Self::External(args)),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawContentFilterConfig {
+    patterns: Vec<String>,
+    #[serde(default)]
+    clean: Option<RawFilterCommand>,
+    #[serde(default)]
+    smudge: Option<RawFilterCommand>,
+    #[serde(default = "default_filter_enabled")]
+    enabled: bool,
+}
+
+fn default_filter_enabled() -> bool {
+    true
+}
+
+/// Entry of the `working-copy.filters` table.
+#[derive(Clone, Debug)]
+pub struct ContentFilterConfig {
+    /// Name of the filter.
+    pub name: String,
+    /// Files the filter applies to.
+    pub expression: FilesetExpression,
+    /// Command to run when snapshotting matching files.
+    pub clean: Option<FilterCommand>,
+    /// Command to run when checking out matching files.
+    pub smudge: Option<FilterCommand>,
+}
+
+/// Content filters configured by the `working-copy.filters` table.
+#[derive(Clone, Debug, Default)]
+pub struct ContentFilterSettings {
+    /// Filters in the order they are applied on snapshot. They are applied in
+    /// reverse order on checkout.
+    pub filters: Vec<ContentFilterConfig>,
+}
+
+impl ContentFilterSettings {
+    /// Parses the `working-copy.filters` table in the [`UserSettings`].
+    pub fn try_from_settings(user_settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: "".into(),
+            base: "".into(),
+        };
+        let mut filters = vec![];
+        // Sort keys so the filters are applied in a deterministic order.
+        for name in user_settings.table_keys("working-copy.filters").sorted() {
+            let raw: RawContentFilterConfig =
+                user_settings.get(["working-copy", "filters", name])?;
+            if !raw.enabled {
+                continue;
+            }
+            let type_error = |field: &str, error: Box<dyn std::error::Error + Send + Sync>| {
+                ConfigGetError::Type {
+                    name: format!("working-copy.filters.{name}.{field}"),
+                    error,
+                    source_path: None,
+                }
+            };
+            let mut diagnostics = FilesetDiagnostics::new();
+            let expressions: Vec<_> = raw
+                .patterns
+                .iter()
+                .map(|text| fileset::parse(&mut diagnostics, text, &path_converter))
+                .try_collect()
+                .map_err(|err| type_error("patterns", err.into()))?;
+            let clean = raw
+                .clean
+                .map(FilterCommand::try_from)
+                .transpose()
+                .map_err(|err| type_error("clean", err.into()))?;
+            let smudge = raw
+                .smudge
+                .map(FilterCommand::try_from)
+                .transpose()
+                .map_err(|err| type_error("smudge", err.into()))?;
+            filters.push(ContentFilterConfig {
+                name: name.to_owned(),
+                expression: FilesetExpression::union_all(expressions),
+                clean,
+                smudge,
+            });
+        }
+        Ok(Self { filters })
+    }
+}
+
+struct ContentFilter {
+    name: String,
+    matcher: Box<dyn Matcher>,
+    clean: Option<FilterCommand>,
+    smudge: Option<FilterCommand>,
+}
+
+/// Maximum number of filter outputs kept in the cache directory. Least
+/// recently used entries are evicted first.
+const MAX_CACHE_ENTRIES: usize = 4096;
+
+/// Cache entries used within this interval aren't marked as used again, so
+/// reading cached outputs doesn't write to the file system every time.
+const CACHE_TOUCH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Runs the configured content filters for the local working copy.
+///
+/// Filters sit between the store and the EOL conversion: snapshot applies EOL
+/// conversion and then the clean filters, and checkout applies the smudge
+/// filters and then EOL conversion. That way, the input to the smudge filters
+/// is exactly the stored file, and the results can be cached by `FileId`.
+pub(crate) struct ContentFilters {
+    filters: Vec<ContentFilter>,
+    working_copy_path: PathBuf,
+    cache_dir: PathBuf,
+    /// Whether new entries were written to the cache since it was last pruned.
+    cache_written: AtomicBool,
+}
+
+impl ContentFilters {
+    pub(crate) fn new(
+        settings: &ContentFilterSettings,
+        working_copy_path: &Path,
+        state_path: &Path,
+    ) -> Self {
+        let filters = settings
+            .filters
+            .iter()
+            .map(|config| ContentFilter {
+                name: config.name.clone(),
+                matcher: config.expression.to_matcher(),
+                clean: config.clean.clone(),
+                smudge: config.smudge.clone(),
+            })
+            .collect();
+        Self {
+            filters,
+            working_copy_path: working_copy_path.to_owned(),
+            cache_dir: state_path.join("filter_cache"),
+            cache_written: AtomicBool::new(false),
+        }
+    }
+
+    /// Evicts the least recently used cache entries if new entries were
+    /// written. This should be called once the snapshot or checkout is done.
+    pub(crate) fn prune_cache(&self) {
+        if !self.cache_written.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        if let Err(err) = prune_least_recently_modified(&self.cache_dir, MAX_CACHE_ENTRIES) {
+            tracing::warn!(?err, "failed to prune content filter cache");
+        }
+    }
+
+    /// Returns true if any clean filter applies to the `path`.
+    pub(crate) fn has_clean_filter(&self, path: &RepoPath) -> bool {
+        self.filters
+            .iter()
+            .any(|filter| filter.clean.is_some() && filter.matcher.matches(path))
+    }
+
+    /// Returns true if any smudge filter applies to the `path`.
+    pub(crate) fn has_smudge_filter(&self, path: &RepoPath) -> bool {
+        self.filters
+            .iter()
+            .any(|filter| filter.smudge.is_some() && filter.matcher.matches(path))
+    }
+
+    /// Applies the clean filters matching the `path` to the working-copy
+    /// `contents`.
+    pub(crate) fn clean(
+        &self,
+        path: &RepoPath,
+        contents: Vec<u8>,
+    ) -> Result<Vec<u8>, ContentFilterError> {
+        let commands = self
+            .filters
+            .iter()
+            .filter(|filter| filter.matcher.matches(path))
+            .filter_map(|filter| Some((&filter.name, filter.clean.as_ref()?)));
+        let mut contents = contents;
+        for (name, command) in commands {
+            let digest = blake2::Blake2b512::digest(&contents);
+            let key = self.cache_key(FilterDirection::Clean, command, &digest);
+            contents = self.run_cached(&key, || {
+                self.run(name, FilterDirection::Clean, command, path, &contents)
+            })?;
+        }
+        Ok(contents)
+    }
+
+    /// Applies the smudge filters matching the `path` to the stored `contents`
+    /// of the file `id`.
+    pub(crate) fn smudge(
+        &self,
+        path: &RepoPath,
+        id: &FileId,
+        contents: Vec<u8>,
+    ) -> Result<Vec<u8>, ContentFilterError> {
+        let commands = self
+            .filters
+            .iter()
+            .rev()
+            .filter(|filter| filter.matcher.matches(path))
+            .filter_map(|filter| Some((&filter.name, filter.smudge.as_ref()?)))
+            .collect_vec();
+        let mut contents = contents;
+        for (i, (name, command)) in commands.into_iter().enumerate() {
+            // The input to the first filter is the stored file, so use its id
+            // as the cache key. Chained filters are keyed by the previous
+            // output.
+            let key = if i == 0 {
+                self.cache_key(FilterDirection::Smudge, command, id.as_bytes())
+            } else {
+                let digest = blake2::Blake2b512::digest(&contents);
+                self.cache_key(FilterDirection::Smudge, command, &digest)
+            };
+            contents = self.run_cached(&key, || {
+                self.run(name, FilterDirection::Smudge, command, path, &contents)
+            })?;
+        }
+        Ok(contents)
+    }
+
+    fn cache_key(
+        &self,
+        direction: FilterDirection,
+        command: &FilterCommand,
+        input: &[u8],
+    ) -> String {
+        let mut hasher = blake2::Blake2b512::new();
+        hasher.update(direction.as_str());
+        hasher.update([0]);
+        hasher.update(command.cache_key_bytes());
+        hasher.update([0]);
+        hasher.update(input);
+        hex_util::encode_hex(&hasher.finalize()[..32])
+    }
+
+    /// Looks up the cached filter output, or runs the filter and caches the
+    /// output. Failure to read or write the cache isn't an error.
+    fn run_cached(
+        &self,
+        key: &str,
+        run: impl FnOnce() -> Result<Vec<u8>, ContentFilterError>,
+    ) -> Result<Vec<u8>, ContentFilterError> {
+        let cache_path = self.cache_dir.join(key);
+        if let Ok(output) = fs::read(&cache_path) {
+            // Bump the mtime so the entry is evicted last.
+            touch_if_older_than(&cache_path, CACHE_TOUCH_INTERVAL).ok();
+            return Ok(output);
+        }
+        let output = run()?;
+        let write_cache = || -> io::Result<()> {
+            fs::create_dir_all(&self.cache_dir)?;
+            let mut temp_file = tempfile::NamedTempFile::new_in(&self.cache_dir)?;
+            temp_file.write_all(&output)?;
+            persist_temp_file(temp_file, &cache_path)?;
+            self.cache_written.store(true, Ordering::Relaxed);
+            Ok(())
+        };
+        if let Err(err) = write_cache() {
+            tracing::warn!(?err, ?cache_path, "failed to write content filter cache");
+        }
+        Ok(output)
+    }
+
+    fn run(
+        &self,
+        name: &str,
+        direction: FilterDirection,
+        command: &FilterCommand,
+        path: &RepoPath,
+        contents: &[u8],
+    ) -> Result<Vec<u8>, ContentFilterError> {
+        let args = match command {
+            FilterCommand::Builtin(builtin) => return Ok(builtin.apply(contents)),
+            FilterCommand::External(args) => args,
+        };
+        let vars = HashMap::from([("path", path.as_internal_file_string())]);
+        let io_error = |source| ContentFilterError::Io {
+            name: name.to_owned(),
+            direction,
+            path: path.as_internal_file_string().to_owned(),
+            source,
+        };
+        let (program, args) = args
+            .split_first()
+            .expect("filter command shouldn't be empty");
+        tracing::debug!(?program, ?args, ?path, %direction, "spawning content filter");
+        let mut child = Command::new(program)
+            .args(args.iter().map(|arg| substitute_variables(arg, &vars)))
+            .current_dir(&self.working_copy_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(io_error)?;
+        let mut stdin = child.stdin.take().unwrap();
+        let output = std::thread::scope(|s| {
+            s.spawn(move || {
+                // The filter may exit without reading all input.
+                stdin.write_all(contents).ok();
+            });
+            child.wait_with_output()
+        })
+        .map_err(io_error)?;
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(ContentFilterError::Failed {
+                name: name.to_owned(),
+                direction,
+                path: path.as_internal_file_string().to_owned(),
+                stderr: String::from_utf8_lossy(&output.stderr)
+                    .trim_end()
+                    .to_owned(),
+            })
+        }
+    }
+}
+
+fn substitute_variables(arg: &str, vars: &HashMap<&str, &str>) -> String {
+    let mut arg = arg.to_owned();
+    for (name, value) in vars {
+        arg = arg.replace(&format!("${name}"), value);
+    }
+    arg
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(b"", b""; "empty")]
+    #[test_case(b"a \nb\t\n", b"a\nb\n"; "LF")]
+    #[test_case(b"a \r\nb\t\r\n", b"a\r\nb\r\n"; "CRLF")]
+    #[test_case(b"a\n  ", b"a\n"; "whitespace only last line")]
+    #[test_case(b" a b ", b" a b"; "no EOL")]
+    fn test_strip_trailing_whitespace(input: &[u8], expected: &[u8]) {
+        assert_eq!(
+            BuiltinFilter::StripTrailingWhitespace.apply(input),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_substitute_variables() {
+        let vars = HashMap::from([("path", "dir/file")]);
+        assert_eq!(
+            substitute_variables("--path=$path", &vars),
+            "--path=dir/file"
+        );
+        assert_eq!(substitute_variables("$other", &vars), "$other");
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bloom filter of per-commit changed paths.
+
+use std::collections::HashSet;
+
+use blake2::Blake2b512;
+use digest::Digest as _;
+
+use crate::fileset::FilePattern;
+use crate::fileset::FilesetExpression;
+use crate::repo_path::RepoPath;
+
+/// Number of filter bits allocated per key. With 7 hash functions, this gives
+/// a false-positive rate of about 1%.
+const BITS_PER_KEY: usize = 10;
+const NUM_HASHES: u64 = 7;
+
+/// Precomputed hash of a path to be looked up in Bloom filters.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) struct BloomFilterKey {
+    h1: u64,
+    h2: u64,
+}
+
+impl BloomFilterKey {
+    pub fn new(path: &RepoPath) -> Self {
+        let mut hasher = Blake2b512::new();
+        hasher.update(path.as_internal_file_string());
+        let digest = hasher.finalize();
+        let h1 = u64::from_le_bytes(digest[0..8].try_into().unwrap());
+        // Make it odd so the probes don't collapse to a single bit.
+        let h2 = u64::from_le_bytes(digest[8..16].try_into().unwrap()) | 1;
+        Self { h1, h2 }
+    }
+
+    fn bit_positions(&self, num_bits: u64) -> impl Iterator<Item = u64> {
+        (0..NUM_HASHES).map(move |i| self.h1.wrapping_add(i.wrapping_mul(self.h2)) % num_bits)
+    }
+}
+
+/// Builds Bloom filter of the given changed `paths`.
+///
+/// Ancestor directories of the paths are also inserted so that a prefix
+/// pattern can be tested against the filter. The returned filter is empty if
+/// there are no paths.
+pub(super) fn build_bloom_filter<'a>(paths: impl IntoIterator<Item = &'a RepoPath>) -> Vec<u8> {
+    let keys: HashSet<&RepoPath> = paths
+        .into_iter()
+        .flat_map(|path| path.ancestors())
+        .filter(|path| !path.is_root())
+        .collect();
+    let mut filter = vec![0; (keys.len() * BITS_PER_KEY).div_ceil(8)];
+    let num_bits = u64::try_from(filter.len() * 8).unwrap();
+    for path in keys {
+        for bit in BloomFilterKey::new(path).bit_positions(num_bits) {
+            filter[(bit / 8) as usize] |= 1 << (bit % 8);
+        }
+    }
+    filter
+}
+
+/// Returns false if the `filter` definitely doesn't contain the `key`.
+pub(super) fn bloom_filter_may_contain(filter: &[u8], key: &BloomFilterKey) -> bool {
+    if filter.is_empty() {
+        return false;
+    }
+    let num_bits = u64::try_from(filter.len() * 8).unwrap();
+    key.bit_positions(num_bits)
+        .all(|bit| filter[(bit / 8) as usize] & (1 << (bit % 8)) != 0)
+}
+
+/// Computes Bloom filter keys for the given fileset `expression`.
+///
+/// A changed path matching the `expression` must be equal to or under one of
+/// the returned paths. Returns `None` if the expression may match arbitrary
+/// paths.
+pub(super) fn bloom_filter_keys_for_fileset(
+    expression: &FilesetExpression,
+) -> Option<Vec<BloomFilterKey>> {
+    fn collect<'a>(expression: &'a FilesetExpression, paths: &mut Vec<&'a RepoPath>) -> bool {
+        match expression {
+            FilesetExpression::None => true,
+            FilesetExpression::All => false,
+            FilesetExpression::Pattern(pattern) => {
+                let path = match pattern {
+                    FilePattern::FilePath(path) => path,
+                    FilePattern::PrefixPath(path) => path,
+                    FilePattern::FileGlob { dir, .. } => dir,
+                };
+                paths.push(path);
+                !path.is_root()
+            }
+            FilesetExpression::UnionAll(expressions) => {
+                expressions.iter().all(|expr| collect(expr, paths))
+            }
+            FilesetExpression::Intersection(expression1, expression2) => {
+                let mut paths1 = vec![];
+                let mut paths2 = vec![];
+                // Either side is sufficient. Pick the narrower one.
+                match (
+                    collect(expression1, &mut paths1),
+                    collect(expression2, &mut paths2),
+                ) {
+                    (true, true) if paths2.len() < paths1.len() => paths.extend(paths2),
+                    (true, _) => paths.extend(paths1),
+                    (false, true) => paths.extend(paths2),
+                    (false, false) => return false,
+                }
+                true
+            }
+            FilesetExpression::Difference(expression1, _) => collect(expression1, paths),
+        }
+    }
+
+    let mut paths = vec![];
+    collect(expression, &mut paths).then(|| paths.into_iter().map(BloomFilterKey::new).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_path(value: &str) -> &RepoPath {
+        RepoPath::from_internal_string(value).unwrap()
+    }
+
+    fn key(value: &str) -> BloomFilterKey {
+        BloomFilterKey::new(repo_path(value))
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let filter = build_bloom_filter([]);
+        assert!(filter.is_empty());
+        assert!(!bloom_filter_may_contain(&filter, &key("a")));
+
+        let filter = build_bloom_filter([repo_path("dir/sub/file"), repo_path("top")]);
+        assert_eq!(filter.len(), 5); // 4 keys * 10 bits
+        assert!(bloom_filter_may_contain(&filter, &key("dir")));
+        assert!(bloom_filter_may_contain(&filter, &key("dir/sub")));
+        assert!(bloom_filter_may_contain(&filter, &key("dir/sub/file")));
+        assert!(bloom_filter_may_contain(&filter, &key("top")));
+
+        // No false negatives, and few false positives
+        let paths = (0..1000)
+            .map(|i| format!("dir{}/file{i}", i % 10))
+            .collect::<Vec<_>>();
+        let filter = build_bloom_filter(paths.iter().map(|path| repo_path(path)));
+        for path in &paths {
+            assert!(bloom_filter_may_contain(&filter, &key(path)));
+        }
+        let num_false_positives = (0..1000)
+            .filter(|i| bloom_filter_may_contain(&filter, &key(&format!("other/file{i}"))))
+            .count();
+        assert!(num_false_positives < 50, "{num_false_positives}");
+    }
+
+    #[test]
+    fn test_bloom_filter_keys_for_fileset() {
+        let file = |value: &str| FilesetExpression::file_path(repo_path(value).to_owned());
+        let prefix = |value: &str| FilesetExpression::prefix_path(repo_path(value).to_owned());
+
+        assert_eq!(
+            bloom_filter_keys_for_fileset(&FilesetExpression::none()),
+            Some(vec![])
+        );
+        assert_eq!(
+            bloom_filter_keys_for_fileset(&FilesetExpression::all()),
+            None
+        );
+        assert_eq!(bloom_filter_keys_for_fileset(&prefix("")), None);
+        assert_eq!(
+            bloom_filter_keys_for_fileset(&file("a/b")),
+            Some(vec![key("a/b")])
+        );
+        assert_eq!(
+            bloom_filter_keys_for_fileset(&FilesetExpression::union_all(vec![
+                file("a"),
+                prefix("b"),
+            ])),
+            Some(vec![key("a"), key("b")])
+        );
+        assert_eq!(
+            bloom_filter_keys_for_fileset(&FilesetExpression::union_all(vec![
+                file("a"),
+                FilesetExpression::all(),
+            ])),
+            None
+        );
+        assert_eq!(
+            bloom_filter_keys_for_fileset(&FilesetExpression::all().intersection(file("a"))),
+            Some(vec![key("a")])
+        );
+        assert_eq!(
+            bloom_filter_keys_for_fileset(&prefix("a").difference(file("a/b"))),
+            Some(vec![key("a")])
+        );
+        assert_eq!(
+            bloom_filter_keys_for_fileset(&FilesetExpression::all().difference(file("a"))),
+            None
+        );
+    }
+}
@@ -31,6 +31,9 @@ use futures::TryStreamExt as _;
 use itertools::Itertools as _;
 use tempfile::NamedTempFile;
 
+use super::bloom_filter::BloomFilterKey;
+use super::bloom_filter::bloom_filter_may_contain;
+use super::bloom_filter::build_bloom_filter;
 use super::entry::GlobalCommitPosition;
 use super::readonly::ReadonlyIndexLoadError;
 use crate::backend::BackendResult;
@@ -48,7 +51,11 @@ use crate::rewrite::merge_commit_trees_no_resolve_without_repo;
 use crate::tree_merge::resolve_file_values;
 
 /// Current format version of the changed-path index segment file.
-const FILE_FORMAT_VERSION: u32 = 0;
+const FILE_FORMAT_VERSION: u32 = 1;
+
+/// Format version of the segment file which has no Bloom filters. It can still
+/// be loaded, but the filters aren't available.
+const LEGACY_FILE_FORMAT_VERSION: u32 = 0;
 
 id_type!(pub(super) ChangedPathIndexSegmentId { hex() });
 
@@ -73,6 +80,7 @@ struct PathPosition(u32);
 /// u32: number of changed path entries
 /// u32: number of path entries
 /// u32: number of bytes of path entries
+/// u32: number of bytes of Bloom filter entries (since version 1)
 ///
 /// for each commit, in commit-index order
 ///   u32: position in changed-path table
@@ -86,6 +94,13 @@ struct PathPosition(u32);
 /// u32: number of bytes of path entries (as sentinel)
 /// for each path, sorted by path
 ///   <arbitrary length of bytes>: path
+///
+/// (since version 1)
+/// for each commit, in commit-index order
+///   u32: byte offset in Bloom filter table
+/// u32: number of bytes of Bloom filter entries (as sentinel)
+/// for each commit, in commit-index order
+///   <arbitrary length of bytes>: Bloom filter of changed paths
 /// ```
 ///
 /// * The parent segment id isn't stored in a segment file. This allows us to
@@ -98,6 +113,8 @@ struct PathPosition(u32);
 ///   entries by exact path or path prefix if needed.
 /// * Path components aren't split nor compressed so we can borrow `&RepoPath`
 ///   from the index data.
+/// * Bloom filters include ancestor directories of the changed paths so that
+///   commits can be skipped without scanning the changed-path table.
 ///
 /// Ideas for future improvements:
 /// * Multi-level index based on the paths? Since indexing is slow, it might
@@ -108,11 +125,14 @@ pub(super) struct ReadonlyChangedPathIndexSegment {
     num_local_commits: u32,
     num_changed_paths: u32,
     num_paths: u32,
+    num_bloom_filter_bytes: u32,
     // Base data offsets in bytes:
     commit_lookup_base: usize,
     changed_path_lookup_base: usize,
     path_lookup_base: usize,
     path_bytes_base: usize,
+    bloom_filter_lookup_base: Option<usize>,
+    bloom_filter_bytes_base: usize,
     data: Vec<u8>,
 }
 
@@ -146,7 +166,7 @@ impl ReadonlyChangedPathIndexSegment {
         };
 
         let format_version = read_u32(file)?;
-        if format_version != FILE_FORMAT_VERSION {
+        if format_version != FILE_FORMAT_VERSION && format_version != LEGACY_FILE_FORMAT_VERSION {
             return Err(ReadonlyIndexLoadError::UnexpectedVersion {
                 kind: "changed-path",
                 found_version: format_version,
@@ -158,18 +178,31 @@ impl ReadonlyChangedPathIndexSegment {
         let num_changed_paths = read_u32(file)?;
         let num_paths = read_u32(file)?;
         let num_path_bytes = read_u32(file)?;
+        let has_bloom_filters = format_version != LEGACY_FILE_FORMAT_VERSION;
+        let num_bloom_filter_bytes = if has_bloom_filters {
+            read_u32(file)?
+        } else {
+            0
+        };
         let mut data = vec![];
         file.read_to_end(&mut data).map_err(from_io_err)?;
 
         let commit_lookup_size = (num_local_commits as usize + 1) * 4;
         let changed_path_lookup_size = (num_changed_paths as usize) * 4;
         let path_lookup_size = (num_paths as usize + 1) * 4;
+        let bloom_filter_lookup_size = if has_bloom_filters {
+            (num_local_commits as usize + 1) * 4
+        } else {
+            0
+        };
 
         let commit_lookup_base = 0;
         let changed_path_lookup_base = commit_lookup_base + commit_lookup_size;
         let path_lookup_base = changed_path_lookup_base + changed_path_lookup_size;
         let path_bytes_base = path_lookup_base + path_lookup_size;
-        let expected_size = path_bytes_base + (num_path_bytes as usize);
+        let bloom_filter_lookup_base = path_bytes_base + (num_path_bytes as usize);
+        let bloom_filter_bytes_base = bloom_filter_lookup_base + bloom_filter_lookup_size;
+        let expected_size = bloom_filter_bytes_base + (num_bloom_filter_bytes as usize);
 
         if data.len() != expected_size {
             return Err(ReadonlyIndexLoadError::invalid_data(
@@ -184,10 +217,13 @@ impl ReadonlyChangedPathIndexSegment {
             num_local_commits,
             num_changed_paths,
             num_paths,
+            num_bloom_filter_bytes,
             commit_lookup_base,
             changed_path_lookup_base,
             path_lookup_base,
             path_bytes_base,
+            bloom_filter_lookup_base: has_bloom_filters.then_some(bloom_filter_lookup_base),
+            bloom_filter_bytes_base,
             data,
         }))
     }
@@ -208,6 +244,13 @@ impl ReadonlyChangedPathIndexSegment {
         self.num_paths
     }
 
+    /// Total size of the Bloom filters in bytes, or `None` if this segment
+    /// was written without Bloom filters.
+    pub(super) fn num_bloom_filter_bytes(&self) -> Option<u32> {
+        self.bloom_filter_lookup_base
+            .map(|_| self.num_bloom_filter_bytes)
+    }
+
     fn changed_paths(&self, pos: CommitPosition) -> impl ExactSizeIterator<Item = &RepoPath> {
         let table = self.changed_paths_table(pos);
         let (chunks, _remainder) = table.as_chunks();
@@ -241,10 +284,34 @@ impl ReadonlyChangedPathIndexSegment {
         let start = u32::from_le_bytes(table[offset..][0..4].try_into().unwrap());
         let end = u32::from_le_bytes(table[offset..][4..8].try_into().unwrap());
 
-        let bytes = &self.data[self.path_bytes_base..];
+        let bytes = &self.data[self.path_bytes_base..self.bloom_filter_lookup_base()];
         &bytes[start as usize..end as usize]
     }
 
+    fn bloom_filter_lookup_base(&self) -> usize {
+        self.bloom_filter_lookup_base
+            .unwrap_or(self.bloom_filter_bytes_base)
+    }
+
+    fn bloom_filter(&self, pos: CommitPosition) -> Option<&[u8]> {
+        let table = &self.data[self.bloom_filter_lookup_base?..self.bloom_filter_bytes_base];
+        let offset = pos.0 as usize * 4;
+        let start = u32::from_le_bytes(table[offset..][0..4].try_into().unwrap());
+        let end = u32::from_le_bytes(table[offset..][4..8].try_into().unwrap());
+
+        let bytes = &self.data[self.bloom_filter_bytes_base..];
+        Some(&bytes[start as usize..end as usize])
+    }
+
+    /// Returns false if none of the paths denoted by the `keys` can be changed
+    /// at the specified commit.
+    fn may_contain_any(&self, pos: CommitPosition, keys: &[BloomFilterKey]) -> bool {
+        let Some(filter) = self.bloom_filter(pos) else {
+            return true;
+        };
+        keys.iter().any(|key| bloom_filter_may_contain(filter, key))
+    }
+
     #[cfg(test)]
     fn paths(&self) -> impl ExactSizeIterator<Item = &RepoPath> {
         (0..self.num_paths).map(|pos| self.path(PathPosition(pos)))
@@ -320,6 +387,8 @@ impl MutableChangedPathIndexSegment {
         buf.extend(num_paths.to_le_bytes());
         let num_path_bytes_offset = buf.len();
         buf.extend(0_u32.to_le_bytes());
+        let num_bloom_filter_bytes_offset = buf.len();
+        buf.extend(0_u32.to_le_bytes());
 
         let mut num_changed_paths: u32 = 0;
         for paths in &self.entries {
@@ -345,6 +414,24 @@ impl MutableChangedPathIndexSegment {
         for &path in &paths {
             buf.extend(path.as_internal_file_string().as_bytes());
         }
+
+        let bloom_filters = self
+            .entries
+            .iter()
+            .map(|paths| build_bloom_filter(paths.iter().map(AsRef::as_ref)))
+            .collect_vec();
+        let mut num_bloom_filter_bytes: u32 = 0;
+        for filter in &bloom_filters {
+            buf.extend(num_bloom_filter_bytes.to_le_bytes());
+            num_bloom_filter_bytes += u32::try_from(filter.len()).unwrap();
+        }
+        buf.extend(num_bloom_filter_bytes.to_le_bytes()); // sentinel
+        buf[num_bloom_filter_bytes_offset..][..4]
+            .copy_from_slice(&num_bloom_filter_bytes.to_le_bytes());
+
+        for filter in &bloom_filters {
+            buf.extend(filter);
+        }
     }
 
     pub(super) fn save_in(
@@ -501,6 +588,20 @@ impl CompositeChangedPathIndex {
         ))
     }
 
+    /// Returns false if none of the paths denoted by the `keys` can be changed
+    /// at the specified commit. Returns true if the commit wasn't indexed or
+    /// the filter isn't available.
+    pub(super) fn may_contain_any(
+        &self,
+        global_pos: GlobalCommitPosition,
+        keys: &[BloomFilterKey],
+    ) -> bool {
+        match self.find_segment(global_pos) {
+            Some((local_pos, Either::Left(segment))) => segment.may_contain_any(local_pos, keys),
+            Some((_, Either::Right(_))) | None => true,
+        }
+    }
+
     /// Adds changed paths of the next commit.
     ///
     /// The input `paths` must be sorted.
@@ -873,4 +974,55 @@ mod tests {
             Some(vec![repo_path("5")])
         );
     }
+
+    #[test_case(false; "mutable")]
+    #[test_case(true; "readonly")]
+    fn test_composite_bloom_filter(on_disk: bool) {
+        let temp_dir = new_temp_dir();
+        let mut index = CompositeChangedPathIndex::empty(GlobalCommitPosition(0));
+        index.make_mutable();
+        index.add_changed_paths(vec![repo_path_buf("dir/sub/file")]);
+        index.add_changed_paths(vec![]);
+        if on_disk {
+            index.save_in(temp_dir.path()).unwrap();
+            assert_eq!(index.readonly_segments[0].num_bloom_filter_bytes(), Some(4));
+        }
+        let key = |value| BloomFilterKey::new(repo_path(value));
+
+        assert!(index.may_contain_any(GlobalCommitPosition(0), &[key("dir")]));
+        assert!(index.may_contain_any(GlobalCommitPosition(0), &[key("dir/sub")]));
+        assert!(index.may_contain_any(GlobalCommitPosition(0), &[key("dir/sub/file")]));
+        assert!(index.may_contain_any(GlobalCommitPosition(0), &[key("x"), key("dir")]));
+        // Commit with no changes can be skipped, but mutable segment has no
+        // filters
+        assert_eq!(
+            index.may_contain_any(GlobalCommitPosition(1), &[key("dir")]),
+            !on_disk
+        );
+        assert_eq!(
+            index.may_contain_any(GlobalCommitPosition(1), &[]),
+            !on_disk
+        );
+        // Not indexed
+        assert!(index.may_contain_any(GlobalCommitPosition(2), &[key("dir")]));
+    }
+
+    #[test]
+    fn test_load_legacy_segment() {
+        let mut buf = vec![];
+        for value in [0_u32, 1, 1, 1, 3, 0, 1, 0, 0, 3] {
+            buf.extend(value.to_le_bytes());
+        }
+        buf.extend(b"foo");
+        let id = ChangedPathIndexSegmentId::new(vec![0; 64]);
+        let segment = ReadonlyChangedPathIndexSegment::load_from(&mut &buf[..], id).unwrap();
+        assert_eq!(segment.num_bloom_filter_bytes(), None);
+        assert_eq!(
+            segment.changed_paths(CommitPosition(0)).collect_vec(),
+            [repo_path("foo")]
+        );
+        // Without filters, the commit can't be skipped
+        let key = BloomFilterKey::new(repo_path("bar"));
+        assert!(segment.may_contain_any(CommitPosition(0), &[key]));
+    }
 }
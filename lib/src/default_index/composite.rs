@@ -35,9 +35,11 @@ use super::entry::LocalCommitPosition;
 use super::entry::SmallGlobalCommitPositionsVec;
 use super::entry::SmallLocalCommitPositionsVec;
 use super::mutable::MutableCommitIndexSegment;
+use super::reachability::ReachabilityBitmapIndex;
 use super::readonly::ReadonlyCommitIndexSegment;
 use super::rev_walk::filter_slice_by_range;
 use super::revset_engine;
+use super::text_index::CompositeTextIndex;
 use crate::backend::ChangeId;
 use crate::backend::CommitId;
 use crate::hex_util;
@@ -493,41 +495,62 @@ enum CompositeCommitIndexSegment {
     Mutable(Box<MutableCommitIndexSegment>),
 }
 
+/// Mutable commit index segment and the associated indexes.
+pub(super) type MutableIndexParts = (
+    Box<MutableCommitIndexSegment>,
+    CompositeChangedPathIndex,
+    Option<Arc<ReachabilityBitmapIndex>>,
+    CompositeTextIndex,
+);
+
 #[derive(Clone, Debug)]
 pub(super) struct CompositeIndex {
     commits: CompositeCommitIndexSegment,
     changed_paths: CompositeChangedPathIndex,
+    reachability_bitmaps: Option<Arc<ReachabilityBitmapIndex>>,
+    text_index: CompositeTextIndex,
 }
 
 impl CompositeIndex {
     pub(super) fn from_readonly(
         commits: Arc<ReadonlyCommitIndexSegment>,
         changed_paths: CompositeChangedPathIndex,
+        reachability_bitmaps: Option<Arc<ReachabilityBitmapIndex>>,
+        text_index: CompositeTextIndex,
     ) -> Self {
         Self {
             commits: CompositeCommitIndexSegment::Readonly(commits),
             changed_paths,
+            reachability_bitmaps,
+            text_index,
         }
     }
 
     pub(super) fn from_mutable(
         commits: Box<MutableCommitIndexSegment>,
         changed_paths: CompositeChangedPathIndex,
+        reachability_bitmaps: Option<Arc<ReachabilityBitmapIndex>>,
+        text_index: CompositeTextIndex,
     ) -> Self {
         Self {
             commits: CompositeCommitIndexSegment::Mutable(commits),
             changed_paths,
+            reachability_bitmaps,
+            text_index,
         }
     }
 
-    pub(super) fn into_mutable(
-        self,
-    ) -> Option<(Box<MutableCommitIndexSegment>, CompositeChangedPathIndex)> {
+    pub(super) fn into_mutable(self) -> Option<MutableIndexParts> {
         let commits = match self.commits {
             CompositeCommitIndexSegment::Readonly(_) => return None,
             CompositeCommitIndexSegment::Mutable(segment) => segment,
         };
-        Some((commits, self.changed_paths))
+        Some((
+            commits,
+            self.changed_paths,
+            self.reachability_bitmaps,
+            self.text_index,
+        ))
     }
 
     pub(super) fn commits(&self) -> &CompositeCommitIndex {
@@ -558,6 +581,20 @@ impl CompositeIndex {
     pub(super) fn changed_paths_mut(&mut self) -> &mut CompositeChangedPathIndex {
         &mut self.changed_paths
     }
+
+    /// Precomputed reachability bitmaps, which may cover only a subset of the
+    /// indexed commits.
+    pub(super) fn reachability_bitmaps(&self) -> Option<&Arc<ReachabilityBitmapIndex>> {
+        self.reachability_bitmaps.as_ref()
+    }
+
+    pub(super) fn text_index(&self) -> &CompositeTextIndex {
+        &self.text_index
+    }
+
+    pub(super) fn text_index_mut(&mut self) -> &mut CompositeTextIndex {
+        &mut self.text_index
+    }
 }
 
 impl AsCompositeIndex for CompositeIndex {
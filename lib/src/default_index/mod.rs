@@ -20,16 +20,19 @@
 //! [`DefaultReadonlyIndex`] and [`DefaultMutableIndex`].
 
 mod bit_set;
+mod bloom_filter;
 mod changed_path;
 mod composite;
 mod entry;
 mod mutable;
+mod reachability;
 mod readonly;
 mod rev_walk;
 mod rev_walk_queue;
 mod revset_engine;
 mod revset_graph_iterator;
 mod store;
+mod text_index;
 
 pub use self::mutable::DefaultMutableIndex;
 pub use self::readonly::ChangedPathIndexLevelStats;
@@ -37,7 +40,9 @@ pub use self::readonly::CommitIndexLevelStats;
 pub use self::readonly::DefaultReadonlyIndex;
 pub use self::readonly::DefaultReadonlyIndexRevset;
 pub use self::readonly::IndexStats;
+pub use self::readonly::ReachabilityBitmapStats;
 pub use self::readonly::ReadonlyIndexLoadError;
+pub use self::readonly::TextIndexLevelStats;
 pub use self::store::DefaultIndexStore;
 pub use self::store::DefaultIndexStoreError;
 pub use self::store::DefaultIndexStoreInitError;
@@ -66,6 +71,7 @@ mod tests {
     use super::entry::SmallGlobalCommitPositionsVec;
     use super::mutable::MutableCommitIndexSegment;
     use super::readonly::ReadonlyCommitIndexSegment;
+    use super::text_index::CompositeTextIndex;
     use super::*;
     use crate::backend::ChangeId;
     use crate::backend::CommitId;
@@ -98,7 +104,9 @@ mod tests {
 
     fn get_commit_index_stats(commits: &Arc<ReadonlyCommitIndexSegment>) -> IndexStats {
         let changed_paths = CompositeChangedPathIndex::null();
-        let index = DefaultReadonlyIndex::from_segment(commits.clone(), changed_paths);
+        let text_index = CompositeTextIndex::null();
+        let index =
+            DefaultReadonlyIndex::from_segment(commits.clone(), changed_paths, None, text_index);
         index.stats()
     }
 
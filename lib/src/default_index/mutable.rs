@@ -40,6 +40,7 @@ use super::composite::CommitIndexSegmentId;
 use super::composite::CompositeCommitIndex;
 use super::composite::CompositeIndex;
 use super::composite::DynCommitIndexSegment;
+use super::composite::MutableIndexParts;
 use super::entry::GlobalCommitPosition;
 use super::entry::LocalCommitPosition;
 use super::entry::SmallGlobalCommitPositionsVec;
@@ -49,6 +50,8 @@ use super::readonly::DefaultReadonlyIndex;
 use super::readonly::FieldLengths;
 use super::readonly::OVERFLOW_FLAG;
 use super::readonly::ReadonlyCommitIndexSegment;
+use super::text_index::CompositeTextIndex;
+use super::text_index::collect_text_index_keys;
 use crate::backend::BackendResult;
 use crate::backend::ChangeId;
 use crate::backend::CommitId;
@@ -458,7 +461,14 @@ impl DefaultMutableIndex {
         // Changed-path index isn't enabled by default.
         let mut changed_paths = CompositeChangedPathIndex::null();
         changed_paths.make_mutable();
-        Self(CompositeIndex::from_mutable(commits, changed_paths))
+        // Nor is the text index.
+        let text_index = CompositeTextIndex::null();
+        Self(CompositeIndex::from_mutable(
+            commits,
+            changed_paths,
+            None,
+            text_index,
+        ))
     }
 
     pub(super) fn incremental(parent_index: &DefaultReadonlyIndex) -> Self {
@@ -467,12 +477,19 @@ impl DefaultMutableIndex {
         ));
         let mut changed_paths = parent_index.changed_paths().clone();
         changed_paths.make_mutable();
-        Self(CompositeIndex::from_mutable(commits, changed_paths))
-    }
-
-    pub(super) fn into_segment(
-        self,
-    ) -> (Box<MutableCommitIndexSegment>, CompositeChangedPathIndex) {
+        // Commit positions are preserved, so the bitmaps remain valid.
+        let reachability_bitmaps = parent_index.reachability_bitmaps().cloned();
+        let mut text_index = parent_index.text_index().clone();
+        text_index.make_mutable();
+        Self(CompositeIndex::from_mutable(
+            commits,
+            changed_paths,
+            reachability_bitmaps,
+            text_index,
+        ))
+    }
+
+    pub(super) fn into_segment(self) -> MutableIndexParts {
         self.0.into_mutable().expect("must have mutable")
     }
 
@@ -499,6 +516,10 @@ impl DefaultMutableIndex {
         if self.0.changed_paths().next_mutable_commit_pos() == Some(new_commit_pos) {
             self.add_commit_changed_paths(commit).await?;
         }
+        if self.0.text_index().next_mutable_commit_pos() == Some(new_commit_pos) {
+            let keys = collect_text_index_keys(commit);
+            self.0.text_index_mut().add_keys(keys);
+        }
         Ok(())
     }
 
@@ -535,6 +556,17 @@ impl DefaultMutableIndex {
                 self.0.changed_paths_mut().add_changed_paths(paths);
             }
         }
+        if self.0.text_index().next_mutable_commit_pos() == Some(start_commit_pos) {
+            let other_commits = other.as_composite().commits();
+            for self_pos in (start_commit_pos.0..self.num_commits()).map(GlobalCommitPosition) {
+                let entry = self.0.commits().entry_by_pos(self_pos);
+                let other_pos = other_commits.commit_id_to_pos(&entry.commit_id()).unwrap();
+                let Some(keys) = other.text_index().keys(other_pos) else {
+                    break; // no more indexed texts in other index
+                };
+                self.0.text_index_mut().add_keys(keys.collect());
+            }
+        }
     }
 }
 
@@ -0,0 +1,411 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Precomputed reachability bitmaps of selected heads.
+
+use std::cmp::max;
+use std::cmp::min;
+use std::collections::BTreeMap;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::Read;
+use std::io::Write as _;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use blake2::Blake2b512;
+use digest::Digest as _;
+use tempfile::NamedTempFile;
+
+use super::composite::CompositeCommitIndex;
+use super::entry::GlobalCommitPosition;
+use super::readonly::ReadonlyIndexLoadError;
+use crate::file_util::IoResultExt as _;
+use crate::file_util::PathError;
+use crate::file_util::persist_content_addressed_temp_file;
+use crate::object_id::ObjectId as _;
+use crate::object_id::id_type;
+
+/// Current format version of the reachability bitmap index file.
+const FILE_FORMAT_VERSION: u32 = 0;
+
+id_type!(pub(super) ReachabilityBitmapIndexId { hex() });
+
+/// Set of commit positions, stored as sorted runs of contiguous positions.
+///
+/// Since commits are indexed in topological order, ancestors of a long-lived
+/// head tend to be clustered, and the run-length encoding is compact.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(super) struct ReachabilityBitmap {
+    /// Non-empty, non-adjacent ranges sorted in ascending order.
+    runs: Vec<Range<u32>>,
+}
+
+impl ReachabilityBitmap {
+    /// Builds bitmap from positions sorted in descending order.
+    fn from_descending_positions(
+        positions: impl IntoIterator<Item = GlobalCommitPosition>,
+    ) -> Self {
+        let mut runs: Vec<Range<u32>> = vec![];
+        for GlobalCommitPosition(pos) in positions {
+            match runs.last_mut() {
+                Some(run) if run.start == pos + 1 => run.start = pos,
+                last => {
+                    assert!(
+                        last.is_none_or(|run| pos < run.start),
+                        "positions should be sorted"
+                    );
+                    runs.push(pos..pos + 1);
+                }
+            }
+        }
+        runs.reverse();
+        Self { runs }
+    }
+
+    /// Returns the number of positions in this set.
+    pub fn len(&self) -> u32 {
+        self.runs.iter().map(|run| run.end - run.start).sum()
+    }
+
+    /// Returns true if the given `pos` is in this set.
+    pub fn contains(&self, GlobalCommitPosition(pos): GlobalCommitPosition) -> bool {
+        let i = self.runs.partition_point(|run| run.end <= pos);
+        self.runs.get(i).is_some_and(|run| run.contains(&pos))
+    }
+
+    /// Returns positions in this set, in descending order.
+    pub fn iter_rev(self: Arc<Self>) -> impl Iterator<Item = GlobalCommitPosition> {
+        (0..self.runs.len())
+            .rev()
+            .flat_map(move |i| self.runs[i].clone().rev())
+            .map(GlobalCommitPosition)
+    }
+
+    fn push_run(&mut self, run: Range<u32>) {
+        if run.is_empty() {
+            return;
+        }
+        match self.runs.last_mut() {
+            Some(last) if last.end >= run.start => last.end = max(last.end, run.end),
+            _ => self.runs.push(run),
+        }
+    }
+
+    /// Returns positions contained in either set.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut merged = Self::default();
+        let mut runs1 = self.runs.iter().peekable();
+        let mut runs2 = other.runs.iter().peekable();
+        loop {
+            let run = match (runs1.peek(), runs2.peek()) {
+                (Some(run1), Some(run2)) if run1.start <= run2.start => runs1.next().unwrap(),
+                (Some(_), Some(_)) => runs2.next().unwrap(),
+                (Some(_), None) => runs1.next().unwrap(),
+                (None, Some(_)) => runs2.next().unwrap(),
+                (None, None) => break,
+            };
+            merged.push_run(run.clone());
+        }
+        merged
+    }
+
+    /// Returns positions contained in this set but not in the `other` set.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut diff = Self::default();
+        let mut other_runs = other.runs.iter().peekable();
+        for run in &self.runs {
+            let mut start = run.start;
+            while other_runs
+                .next_if(|other_run| other_run.end <= start)
+                .is_some()
+            {}
+            for other_run in other_runs.clone() {
+                if other_run.start >= run.end {
+                    break;
+                }
+                diff.push_run(start..min(other_run.start, run.end));
+                start = max(start, other_run.end);
+            }
+            diff.push_run(start..run.end);
+        }
+        diff
+    }
+}
+
+/// Collects ancestors of the `heads`, excluding the `excluded` set.
+///
+/// The `excluded` set must be closed under ancestry, so the walk can stop
+/// there. The walk also stops at commits of which bitmap is returned by
+/// `known_bitmap`. Returns `None` if more than `max_walked` commits would have
+/// to be walked.
+pub(super) fn collect_ancestors<'a>(
+    index: &CompositeCommitIndex,
+    heads: impl IntoIterator<Item = GlobalCommitPosition>,
+    known_bitmap: impl Fn(GlobalCommitPosition) -> Option<&'a ReachabilityBitmap>,
+    excluded: &ReachabilityBitmap,
+    max_walked: usize,
+) -> Option<ReachabilityBitmap> {
+    let mut queue: BinaryHeap<GlobalCommitPosition> = heads.into_iter().collect();
+    let mut known_ancestors = ReachabilityBitmap::default();
+    let mut walked = vec![];
+    while let Some(pos) = queue.pop() {
+        // Parents have smaller positions, so duplicates are popped in a row.
+        while queue.peek() == Some(&pos) {
+            queue.pop();
+        }
+        if excluded.contains(pos) || known_ancestors.contains(pos) {
+            continue;
+        }
+        if let Some(bitmap) = known_bitmap(pos) {
+            known_ancestors = known_ancestors.union(&bitmap.difference(excluded));
+            continue;
+        }
+        if walked.len() >= max_walked {
+            return None;
+        }
+        walked.push(pos);
+        queue.extend(index.entry_by_pos(pos).parent_positions());
+    }
+    Some(known_ancestors.union(&ReachabilityBitmap::from_descending_positions(walked)))
+}
+
+/// Builds reachability bitmaps of the `heads`.
+///
+/// Bitmaps in the `base` index are reused to speed up the walk.
+pub(super) fn build_reachability_bitmaps(
+    index: &CompositeCommitIndex,
+    heads: impl IntoIterator<Item = GlobalCommitPosition>,
+    base: Option<&ReachabilityBitmapIndex>,
+) -> BTreeMap<GlobalCommitPosition, ReachabilityBitmap> {
+    let mut bitmaps = BTreeMap::new();
+    // Visit heads in topological order so the bitmaps of ancestor heads can
+    // be reused.
+    let mut heads: Vec<_> = heads.into_iter().collect();
+    heads.sort_unstable();
+    heads.dedup();
+    for head in heads {
+        let bitmap = collect_ancestors(
+            index,
+            [head],
+            |pos| {
+                bitmaps
+                    .get(&pos)
+                    .or_else(|| base.and_then(|base| base.get(pos)))
+            },
+            &ReachabilityBitmap::default(),
+            usize::MAX,
+        )
+        .unwrap();
+        bitmaps.insert(head, bitmap);
+    }
+    bitmaps
+}
+
+/// Reachability bitmaps of selected heads backed by immutable file.
+///
+/// File format:
+/// ```text
+/// u32: file format version
+/// u32: number of bitmaps
+///
+/// for each bitmap, sorted by head position
+///   u32: head commit position
+///   u32: number of runs
+///   for each run, sorted by position
+///     u32: start position
+///     u32: end position (exclusive)
+/// ```
+///
+/// * Commit positions are global. Since descendant index segments never
+///   reorder the parent commits, bitmaps stay valid as more commits are
+///   indexed.
+pub(super) struct ReachabilityBitmapIndex {
+    id: ReachabilityBitmapIndexId,
+    bitmaps: BTreeMap<GlobalCommitPosition, ReachabilityBitmap>,
+}
+
+impl Debug for ReachabilityBitmapIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("ReachabilityBitmapIndex")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ReachabilityBitmapIndex {
+    pub(super) fn load(
+        dir: &Path,
+        id: ReachabilityBitmapIndexId,
+        num_commits: u32,
+    ) -> Result<Arc<Self>, ReadonlyIndexLoadError> {
+        let mut file = File::open(dir.join(id.hex()))
+            .map_err(|err| ReadonlyIndexLoadError::from_io_err("reachability", id.hex(), err))?;
+        Self::load_from(&mut file, id, num_commits)
+    }
+
+    pub(super) fn load_from(
+        file: &mut dyn Read,
+        id: ReachabilityBitmapIndexId,
+        num_commits: u32,
+    ) -> Result<Arc<Self>, ReadonlyIndexLoadError> {
+        let from_io_err = |err| ReadonlyIndexLoadError::from_io_err("reachability", id.hex(), err);
+        let read_u32 = |file: &mut dyn Read| {
+            let mut buf = [0; 4];
+            file.read_exact(&mut buf).map_err(from_io_err)?;
+            Ok(u32::from_le_bytes(buf))
+        };
+        let invalid_data =
+            |message: &str| ReadonlyIndexLoadError::invalid_data("reachability", id.hex(), message);
+
+        let format_version = read_u32(file)?;
+        if format_version != FILE_FORMAT_VERSION {
+            return Err(ReadonlyIndexLoadError::UnexpectedVersion {
+                kind: "reachability",
+                found_version: format_version,
+                expected_version: FILE_FORMAT_VERSION,
+            });
+        }
+
+        let num_bitmaps = read_u32(file)?;
+        let mut bitmaps = BTreeMap::new();
+        for _ in 0..num_bitmaps {
+            let head = read_u32(file)?;
+            if head >= num_commits {
+                return Err(invalid_data("head position out of range"));
+            }
+            let num_runs = read_u32(file)?;
+            let mut runs: Vec<Range<u32>> = vec![];
+            for _ in 0..num_runs {
+                let start = read_u32(file)?;
+                let end = read_u32(file)?;
+                if start >= end
+                    || end > head + 1
+                    || runs.last().is_some_and(|last| last.end >= start)
+                {
+                    return Err(invalid_data("invalid run"));
+                }
+                runs.push(start..end);
+            }
+            bitmaps.insert(GlobalCommitPosition(head), ReachabilityBitmap { runs });
+        }
+        if file.read(&mut [0]).map_err(from_io_err)? != 0 {
+            return Err(invalid_data("unexpected data length"));
+        }
+
+        Ok(Arc::new(Self { id, bitmaps }))
+    }
+
+    /// Writes the `bitmaps` to new file.
+    pub(super) fn save_in(
+        dir: &Path,
+        bitmaps: BTreeMap<GlobalCommitPosition, ReachabilityBitmap>,
+    ) -> Result<Arc<Self>, PathError> {
+        let mut buf = Vec::new();
+        buf.extend(FILE_FORMAT_VERSION.to_le_bytes());
+        buf.extend(u32::try_from(bitmaps.len()).unwrap().to_le_bytes());
+        for (GlobalCommitPosition(head), bitmap) in &bitmaps {
+            buf.extend(head.to_le_bytes());
+            buf.extend(u32::try_from(bitmap.runs.len()).unwrap().to_le_bytes());
+            for run in &bitmap.runs {
+                buf.extend(run.start.to_le_bytes());
+                buf.extend(run.end.to_le_bytes());
+            }
+        }
+        let mut hasher = Blake2b512::new();
+        hasher.update(&buf);
+
+        let id = ReachabilityBitmapIndexId::from_bytes(&hasher.finalize());
+        let file_path = dir.join(id.hex());
+        let mut file = NamedTempFile::new_in(dir).context(dir)?;
+        file.as_file_mut().write_all(&buf).context(file.path())?;
+        persist_content_addressed_temp_file(file, &file_path).context(&file_path)?;
+
+        Ok(Arc::new(Self { id, bitmaps }))
+    }
+
+    pub(super) fn id(&self) -> &ReachabilityBitmapIndexId {
+        &self.id
+    }
+
+    pub(super) fn num_bitmaps(&self) -> u32 {
+        self.bitmaps.len().try_into().unwrap()
+    }
+
+    /// Sum of the number of commits stored in the bitmaps.
+    pub(super) fn num_reachable_commits(&self) -> u64 {
+        self.bitmaps
+            .values()
+            .map(|bitmap| u64::from(bitmap.len()))
+            .sum()
+    }
+
+    /// Returns bitmap of the ancestors of the `head` if precomputed.
+    pub(super) fn get(&self, head: GlobalCommitPosition) -> Option<&ReachabilityBitmap> {
+        self.bitmaps.get(&head)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitmap(positions: &[u32]) -> ReachabilityBitmap {
+        ReachabilityBitmap::from_descending_positions(
+            positions.iter().rev().copied().map(GlobalCommitPosition),
+        )
+    }
+
+    fn to_vec(bitmap: &ReachabilityBitmap) -> Vec<u32> {
+        let bitmap = Arc::new(bitmap.clone());
+        let mut positions = bitmap.iter_rev().map(|pos| pos.0).collect::<Vec<_>>();
+        positions.reverse();
+        positions
+    }
+
+    #[test]
+    fn test_bitmap_runs() {
+        assert_eq!(bitmap(&[]).runs, []);
+        assert_eq!(bitmap(&[0, 1, 2, 5, 7, 8]).runs, [0..3, 5..6, 7..9]);
+        assert_eq!(bitmap(&[0, 1, 2, 5, 7, 8]).len(), 6);
+
+        let set = bitmap(&[1, 2, 5]);
+        assert!(!set.contains(GlobalCommitPosition(0)));
+        assert!(set.contains(GlobalCommitPosition(1)));
+        assert!(set.contains(GlobalCommitPosition(2)));
+        assert!(!set.contains(GlobalCommitPosition(3)));
+        assert!(set.contains(GlobalCommitPosition(5)));
+        assert!(!set.contains(GlobalCommitPosition(6)));
+    }
+
+    #[test]
+    fn test_bitmap_set_operations() {
+        let set1 = bitmap(&[0, 1, 2, 5, 7, 8, 9]);
+        let set2 = bitmap(&[2, 3, 4, 8, 10]);
+        assert_eq!(to_vec(&set1.union(&set2)), [0, 1, 2, 3, 4, 5, 7, 8, 9, 10]);
+        assert_eq!(set1.union(&set2).runs, [0..6, 7..11]);
+        assert_eq!(to_vec(&set1.difference(&set2)), [0, 1, 5, 7, 9]);
+        assert_eq!(to_vec(&set2.difference(&set1)), [3, 4, 10]);
+        assert_eq!(to_vec(&set1.difference(&set1)), [] as [u32; 0]);
+        assert_eq!(set1.difference(&bitmap(&[])), set1);
+        assert_eq!(bitmap(&[]).union(&set1), set1);
+        assert_eq!(
+            to_vec(&bitmap(&[0, 1, 2, 3, 4, 5]).difference(&bitmap(&[1, 3]))),
+            [0, 2, 4, 5]
+        );
+    }
+}
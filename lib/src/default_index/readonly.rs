@@ -42,8 +42,10 @@ use super::entry::LocalCommitPosition;
 use super::entry::SmallGlobalCommitPositionsVec;
 use super::entry::SmallLocalCommitPositionsVec;
 use super::mutable::DefaultMutableIndex;
+use super::reachability::ReachabilityBitmapIndex;
 use super::revset_engine;
 use super::revset_engine::RevsetImpl;
+use super::text_index::CompositeTextIndex;
 use crate::backend::ChangeId;
 use crate::backend::CommitId;
 use crate::graph::GraphNode;
@@ -616,8 +618,15 @@ impl DefaultReadonlyIndex {
     pub(super) fn from_segment(
         commits: Arc<ReadonlyCommitIndexSegment>,
         changed_paths: CompositeChangedPathIndex,
+        reachability_bitmaps: Option<Arc<ReachabilityBitmapIndex>>,
+        text_index: CompositeTextIndex,
     ) -> Self {
-        Self(CompositeIndex::from_readonly(commits, changed_paths))
+        Self(CompositeIndex::from_readonly(
+            commits,
+            changed_paths,
+            reachability_bitmaps,
+            text_index,
+        ))
     }
 
     pub(super) fn readonly_commits(&self) -> &Arc<ReadonlyCommitIndexSegment> {
@@ -628,6 +637,14 @@ impl DefaultReadonlyIndex {
         self.0.changed_paths()
     }
 
+    pub(super) fn reachability_bitmaps(&self) -> Option<&Arc<ReachabilityBitmapIndex>> {
+        self.0.reachability_bitmaps()
+    }
+
+    pub(super) fn text_index(&self) -> &CompositeTextIndex {
+        self.0.text_index()
+    }
+
     pub(super) fn has_id_impl(&self, commit_id: &CommitId) -> bool {
         self.0.commits().has_id(commit_id)
     }
@@ -672,6 +689,30 @@ impl DefaultReadonlyIndex {
                 num_commits: segment.num_local_commits(),
                 num_changed_paths: segment.num_changed_paths(),
                 num_paths: segment.num_paths(),
+                num_bloom_filter_bytes: segment.num_bloom_filter_bytes(),
+                name: segment.id().hex(),
+            })
+            .collect_vec();
+
+        let reachability_bitmaps =
+            self.reachability_bitmaps()
+                .map(|bitmaps| ReachabilityBitmapStats {
+                    num_heads: bitmaps.num_bitmaps(),
+                    num_reachable_commits: bitmaps.num_reachable_commits(),
+                    name: bitmaps.id().hex(),
+                });
+
+        let text_index = self.text_index();
+        let text_index_commits_range = text_index
+            .start_commit_pos()
+            .map(|GlobalCommitPosition(start)| start..(start + text_index.num_commits()));
+        let text_index_levels = text_index
+            .readonly_segments()
+            .iter()
+            .map(|segment| TextIndexLevelStats {
+                num_commits: segment.num_local_commits(),
+                num_keys: segment.num_keys(),
+                num_commit_keys: segment.num_commit_keys(),
                 name: segment.id().hex(),
             })
             .collect_vec();
@@ -685,6 +726,9 @@ impl DefaultReadonlyIndex {
             commit_levels,
             changed_path_commits_range,
             changed_path_levels,
+            reachability_bitmaps,
+            text_index_commits_range,
+            text_index_levels,
         }
     }
 
@@ -809,6 +853,9 @@ pub struct IndexStats {
     pub commit_levels: Vec<CommitIndexLevelStats>,
     pub changed_path_commits_range: Option<Range<u32>>,
     pub changed_path_levels: Vec<ChangedPathIndexLevelStats>,
+    pub reachability_bitmaps: Option<ReachabilityBitmapStats>,
+    pub text_index_commits_range: Option<Range<u32>>,
+    pub text_index_levels: Vec<TextIndexLevelStats>,
 }
 
 #[derive(Clone, Debug)]
@@ -825,6 +872,31 @@ pub struct ChangedPathIndexLevelStats {
     pub num_changed_paths: u32,
     /// Number of unique paths.
     pub num_paths: u32,
+    /// Total size of the Bloom filters in bytes, or `None` if the level was
+    /// written without Bloom filters.
+    pub num_bloom_filter_bytes: Option<u32>,
+    /// Index file name.
+    pub name: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct ReachabilityBitmapStats {
+    /// Number of heads which have bitmaps.
+    pub num_heads: u32,
+    /// Sum of number of commits reachable from the heads.
+    pub num_reachable_commits: u64,
+    /// Index file name.
+    pub name: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct TextIndexLevelStats {
+    /// Number of commits.
+    pub num_commits: u32,
+    /// Number of unique trigram keys.
+    pub num_keys: u32,
+    /// Sum of number of per-commit keys.
+    pub num_commit_keys: u32,
     /// Index file name.
     pub name: String,
 }
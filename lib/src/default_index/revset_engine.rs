@@ -16,6 +16,7 @@ use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::convert::Infallible;
 use std::fmt;
@@ -29,14 +30,19 @@ use futures::StreamExt as _;
 use itertools::Itertools as _;
 use pollster::FutureExt as _;
 
+use super::bloom_filter::bloom_filter_keys_for_fileset;
 use super::composite::AsCompositeIndex;
 use super::composite::CompositeIndex;
 use super::entry::GlobalCommitPosition;
+use super::reachability::ReachabilityBitmap;
+use super::reachability::collect_ancestors;
 use super::rev_walk::EagerRevWalk;
 use super::rev_walk::PeekableRevWalk;
 use super::rev_walk::RevWalk;
 use super::rev_walk::RevWalkBuilder;
 use super::revset_graph_iterator::RevsetGraphWalk;
+use super::text_index::TextField;
+use super::text_index::text_index_query_for_expression;
 use crate::backend::BackendResult;
 use crate::backend::ChangeId;
 use crate::backend::CommitId;
@@ -53,8 +59,10 @@ use crate::matchers::Matcher;
 use crate::matchers::Visit;
 use crate::merge::Merge;
 use crate::object_id::ObjectId as _;
+use crate::patch_id::PatchId;
 use crate::repo_path::RepoPath;
 use crate::revset::GENERATION_RANGE_FULL;
+use crate::revset::PARENTS_RANGE_FULL;
 use crate::revset::ResolvedExpression;
 use crate::revset::ResolvedPredicateExpression;
 use crate::revset::Revset;
@@ -62,12 +70,17 @@ use crate::revset::RevsetContainingFn;
 use crate::revset::RevsetEvaluationError;
 use crate::revset::RevsetFilterPredicate;
 use crate::rewrite;
+use crate::signing::SigStatus;
 use crate::store::Store;
 use crate::str_util::StringMatcher;
 use crate::tree_merge::MergeOptions;
 use crate::tree_merge::resolve_file_values;
 use crate::union_find;
 
+/// Maximum number of commits to walk from commits without reachability bitmaps
+/// to the nearest ancestors with bitmaps.
+const MAX_BITMAP_FALLBACK_WALK: usize = 10_000;
+
 type BoxedPredicateFn<'a> = Box<
     dyn FnMut(&CompositeIndex, GlobalCommitPosition) -> Result<bool, RevsetEvaluationError> + 'a,
 >;
@@ -312,6 +325,33 @@ impl ToPredicateFn for EagerRevset {
     }
 }
 
+/// Revset backed by a reachability bitmap. Positions are iterated lazily
+/// from the run-length encoded set.
+#[derive(Debug)]
+struct BitmapRevset {
+    bitmap: Arc<ReachabilityBitmap>,
+}
+
+impl InternalRevset for BitmapRevset {
+    fn positions<'a>(&self) -> BoxedRevWalk<'a>
+    where
+        Self: 'a,
+    {
+        let walk = EagerRevWalk::new(self.bitmap.clone().iter_rev());
+        Box::new(walk.map(|_index, pos| Ok(pos)))
+    }
+}
+
+impl ToPredicateFn for BitmapRevset {
+    fn to_predicate_fn<'a>(&self) -> BoxedPredicateFn<'a>
+    where
+        Self: 'a,
+    {
+        let bitmap = self.bitmap.clone();
+        Box::new(move |_index, pos| Ok(bitmap.contains(pos)))
+    }
+}
+
 /// Adapter for infallible `RevWalk` of `GlobalCommitPosition`s.
 struct RevWalkRevset<W> {
     walk: W,
@@ -742,6 +782,15 @@ fn to_u32_generation_range(range: &Range<u64>) -> Result<Range<u32>, RevsetEvalu
 }
 
 impl EvaluationContext<'_> {
+    fn patch_id_at(
+        &self,
+        pos: GlobalCommitPosition,
+    ) -> Result<Option<PatchId>, RevsetEvaluationError> {
+        let entry = self.index.commits().entry_by_pos(pos);
+        let commit = self.store.get_commit(&entry.commit_id())?;
+        Ok(self.store.get_patch_id(&commit).block_on()?)
+    }
+
     fn evaluate(
         &self,
         expression: &ResolvedExpression,
@@ -757,9 +806,15 @@ impl EvaluationContext<'_> {
                 parents_range,
             } => {
                 let head_set = self.evaluate(heads)?;
-                let head_positions = head_set.positions().attach(index);
+                let head_positions: Vec<_> = head_set.positions().attach(index).try_collect()?;
+                if generation == &GENERATION_RANGE_FULL
+                    && parents_range == &PARENTS_RANGE_FULL
+                    && let Some(bitmap) = self.ancestors_by_bitmaps(&head_positions, &[])
+                {
+                    return Ok(Box::new(BitmapRevset { bitmap }));
+                }
                 let builder = RevWalkBuilder::new(index)
-                    .wanted_heads(head_positions.try_collect()?)
+                    .wanted_heads(head_positions)
                     .wanted_parents_range(parents_range.clone());
                 if generation == &GENERATION_RANGE_FULL {
                     let walk = builder.ancestors().detach();
@@ -790,8 +845,16 @@ impl EvaluationContext<'_> {
                     |pos1, pos2| pos1.cmp(pos2).reverse(),
                 )
                 .attach(index);
+                let head_positions: Vec<_> = head_positions.try_collect()?;
+                if generation == &GENERATION_RANGE_FULL
+                    && parents_range == &PARENTS_RANGE_FULL
+                    && let Some(bitmap) =
+                        self.ancestors_by_bitmaps(&head_positions, &root_positions)
+                {
+                    return Ok(Box::new(BitmapRevset { bitmap }));
+                }
                 let builder = RevWalkBuilder::new(index)
-                    .wanted_heads(head_positions.try_collect()?)
+                    .wanted_heads(head_positions)
                     .wanted_parents_range(parents_range.clone())
                     .unwanted_roots(root_positions);
                 if generation == &GENERATION_RANGE_FULL {
@@ -978,6 +1041,53 @@ impl EvaluationContext<'_> {
                 }
                 Ok(Box::new(EagerRevset { positions }))
             }
+            ResolvedExpression::Landed {
+                candidates,
+                upstream,
+            } => {
+                let candidate_positions: Vec<_> = self
+                    .evaluate(candidates)?
+                    .positions()
+                    .attach(index)
+                    .try_collect()?;
+                let mut candidates_by_patch_id: HashMap<PatchId, Vec<GlobalCommitPosition>> =
+                    HashMap::new();
+                for &pos in &candidate_positions {
+                    if let Some(patch_id) = self.patch_id_at(pos)? {
+                        candidates_by_patch_id
+                            .entry(patch_id)
+                            .or_default()
+                            .push(pos);
+                    }
+                }
+                if candidates_by_patch_id.is_empty() {
+                    return Ok(Box::new(EagerRevset::empty()));
+                }
+                let mut landed_positions = HashSet::new();
+                let upstream_set = self.evaluate(upstream)?;
+                for upstream_pos in upstream_set.positions().attach(index) {
+                    let upstream_pos = upstream_pos?;
+                    let Some(patch_id) = self.patch_id_at(upstream_pos)? else {
+                        continue;
+                    };
+                    let Some(positions) = candidates_by_patch_id.get(&patch_id) else {
+                        continue;
+                    };
+                    // The upstream commit must have been applied after the
+                    // candidate forked off.
+                    landed_positions.extend(
+                        positions
+                            .iter()
+                            .copied()
+                            .filter(|&pos| !index.commits().is_ancestor_pos(upstream_pos, pos)),
+                    );
+                }
+                let positions = candidate_positions
+                    .into_iter()
+                    .filter(|pos| landed_positions.contains(pos))
+                    .collect();
+                Ok(Box::new(EagerRevset { positions }))
+            }
             ResolvedExpression::Bisect(candidates) => {
                 let set = self.evaluate(candidates)?;
                 // TODO: Make this more correct in non-linear history
@@ -1077,6 +1187,41 @@ impl EvaluationContext<'_> {
         }
     }
 
+    /// Computes `roots..heads` by using reachability bitmaps. Returns `None`
+    /// if the bitmaps aren't available for the given commits.
+    ///
+    /// Commits without bitmaps are resolved by walking to the nearest
+    /// ancestors with bitmaps. If there are no `roots`, the walk from the
+    /// `heads` is also bounded. Otherwise, we would have to walk the entire
+    /// history eagerly.
+    fn ancestors_by_bitmaps(
+        &self,
+        head_positions: &[GlobalCommitPosition],
+        root_positions: &[GlobalCommitPosition],
+    ) -> Option<Arc<ReachabilityBitmap>> {
+        let bitmaps = self.index.reachability_bitmaps()?;
+        let excluded = collect_ancestors(
+            self.index.commits(),
+            root_positions.iter().copied(),
+            |pos| bitmaps.get(pos),
+            &ReachabilityBitmap::default(),
+            MAX_BITMAP_FALLBACK_WALK,
+        )?;
+        let max_walked = if root_positions.is_empty() {
+            MAX_BITMAP_FALLBACK_WALK
+        } else {
+            usize::MAX
+        };
+        let ancestors = collect_ancestors(
+            self.index.commits(),
+            head_positions.iter().copied(),
+            |pos| bitmaps.get(pos),
+            &excluded,
+            max_walked,
+        )?;
+        Some(Arc::new(ancestors))
+    }
+
     fn revset_for_commit_ids(
         &self,
         commit_ids: &[CommitId],
@@ -1205,7 +1350,14 @@ fn build_predicate_fn(
         }
         RevsetFilterPredicate::Description(expression) => {
             let matcher = Rc::new(expression.to_matcher());
+            let text_index_query =
+                text_index_query_for_expression(TextField::Description, expression);
             box_pure_predicate_fn(move |index, pos| {
+                if let Some(alternatives) = &text_index_query
+                    && !index.text_index().may_match_any(pos, alternatives)
+                {
+                    return Ok(false);
+                }
                 let entry = index.commits().entry_by_pos(pos);
                 let commit = store.get_commit(&entry.commit_id())?;
                 Ok(matcher.is_match(commit.description()))
@@ -1213,7 +1365,14 @@ fn build_predicate_fn(
         }
         RevsetFilterPredicate::Subject(expression) => {
             let matcher = Rc::new(expression.to_matcher());
+            let text_index_query =
+                text_index_query_for_expression(TextField::Description, expression);
             box_pure_predicate_fn(move |index, pos| {
+                if let Some(alternatives) = &text_index_query
+                    && !index.text_index().may_match_any(pos, alternatives)
+                {
+                    return Ok(false);
+                }
                 let entry = index.commits().entry_by_pos(pos);
                 let commit = store.get_commit(&entry.commit_id())?;
                 Ok(matcher.is_match(commit.description().lines().next().unwrap_or_default()))
@@ -1221,7 +1380,14 @@ fn build_predicate_fn(
         }
         RevsetFilterPredicate::AuthorName(expression) => {
             let matcher = Rc::new(expression.to_matcher());
+            let text_index_query =
+                text_index_query_for_expression(TextField::AuthorName, expression);
             box_pure_predicate_fn(move |index, pos| {
+                if let Some(alternatives) = &text_index_query
+                    && !index.text_index().may_match_any(pos, alternatives)
+                {
+                    return Ok(false);
+                }
                 let entry = index.commits().entry_by_pos(pos);
                 let commit = store.get_commit(&entry.commit_id())?;
                 Ok(matcher.is_match(&commit.author().name))
@@ -1229,7 +1395,14 @@ fn build_predicate_fn(
         }
         RevsetFilterPredicate::AuthorEmail(expression) => {
             let matcher = Rc::new(expression.to_matcher());
+            let text_index_query =
+                text_index_query_for_expression(TextField::AuthorEmail, expression);
             box_pure_predicate_fn(move |index, pos| {
+                if let Some(alternatives) = &text_index_query
+                    && !index.text_index().may_match_any(pos, alternatives)
+                {
+                    return Ok(false);
+                }
                 let entry = index.commits().entry_by_pos(pos);
                 let commit = store.get_commit(&entry.commit_id())?;
                 Ok(matcher.is_match(&commit.author().email))
@@ -1271,7 +1444,13 @@ fn build_predicate_fn(
         }
         RevsetFilterPredicate::File(expr) => {
             let matcher: Rc<dyn Matcher> = expr.to_matcher().into();
+            let bloom_filter_keys = bloom_filter_keys_for_fileset(expr);
             box_pure_predicate_fn(move |index, pos| {
+                if let Some(keys) = &bloom_filter_keys
+                    && !index.changed_paths().may_contain_any(pos, keys)
+                {
+                    return Ok(false);
+                }
                 if let Some(mut paths) = index.changed_paths().changed_paths(pos) {
                     return Ok(paths.any(|path| matcher.matches(path)));
                 }
@@ -1283,7 +1462,13 @@ fn build_predicate_fn(
         RevsetFilterPredicate::DiffContains { text, files } => {
             let text_matcher = Rc::new(text.to_matcher());
             let files_matcher: Rc<dyn Matcher> = files.to_matcher().into();
+            let bloom_filter_keys = bloom_filter_keys_for_fileset(files);
             box_pure_predicate_fn(move |index, pos| {
+                if let Some(keys) = &bloom_filter_keys
+                    && !index.changed_paths().may_contain_any(pos, keys)
+                {
+                    return Ok(false);
+                }
                 let narrowed_files_matcher;
                 let files_matcher = if let Some(paths) = index.changed_paths().changed_paths(pos) {
                     let matched_paths = paths
@@ -1315,6 +1500,23 @@ fn build_predicate_fn(
             let commit = store.get_commit(&entry.commit_id())?;
             Ok(commit.is_signed())
         }),
+        RevsetFilterPredicate::SignedBy(expression) => {
+            let matcher = Rc::new(expression.to_matcher());
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.commits().entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id())?;
+                let Some(verification) = commit
+                    .verification()
+                    .map_err(|err| RevsetEvaluationError::Other(err.into()))?
+                else {
+                    return Ok(false);
+                };
+                // The display string (such as the GPG user ID) can be chosen
+                // freely by the key owner, so only the key is matched.
+                Ok(verification.status == SigStatus::Good
+                    && verification.key.is_some_and(|key| matcher.is_match(&key)))
+            })
+        }
         RevsetFilterPredicate::Extension(ext) => {
             let ext = ext.clone();
             box_pure_predicate_fn(move |index, pos| {
@@ -37,10 +37,16 @@ use super::composite::AsCompositeIndex as _;
 use super::composite::CommitIndexSegmentId;
 use super::entry::GlobalCommitPosition;
 use super::mutable::DefaultMutableIndex;
+use super::reachability::ReachabilityBitmapIndex;
+use super::reachability::ReachabilityBitmapIndexId;
+use super::reachability::build_reachability_bitmaps;
 use super::readonly::DefaultReadonlyIndex;
 use super::readonly::FieldLengths;
 use super::readonly::ReadonlyCommitIndexSegment;
 use super::readonly::ReadonlyIndexLoadError;
+use super::text_index::CompositeTextIndex;
+use super::text_index::TextIndexSegmentId;
+use super::text_index::collect_text_index_keys;
 use crate::backend::BackendError;
 use crate::backend::BackendInitError;
 use crate::backend::CommitId;
@@ -132,6 +138,8 @@ impl DefaultIndexStore {
         // will be created by the other process.
         file_util::remove_dir_contents(&self.commit_segments_dir())?;
         file_util::remove_dir_contents(&self.changed_path_segments_dir())?;
+        file_util::remove_dir_contents(&self.reachability_bitmaps_dir())?;
+        file_util::remove_dir_contents(&self.text_index_segments_dir())?;
         // jj <= 0.14 created segment files in the top directory
         for entry in self.dir.read_dir().context(&self.dir)? {
             let entry = entry.context(&self.dir)?;
@@ -151,6 +159,8 @@ impl DefaultIndexStore {
             self.legacy_operations_dir(),
             self.commit_segments_dir(),
             self.changed_path_segments_dir(),
+            self.reachability_bitmaps_dir(),
+            self.text_index_segments_dir(),
         ] {
             file_util::create_or_reuse_dir(&dir).context(&dir)?;
         }
@@ -177,6 +187,16 @@ impl DefaultIndexStore {
         self.dir.join("changed_paths")
     }
 
+    /// Directory for reachability bitmap files.
+    fn reachability_bitmaps_dir(&self) -> PathBuf {
+        self.dir.join("reachability")
+    }
+
+    /// Directory for text index segment files.
+    fn text_index_segments_dir(&self) -> PathBuf {
+        self.dir.join("text")
+    }
+
     fn load_index_at_operation(
         &self,
         op_id: &OperationId,
@@ -185,6 +205,9 @@ impl DefaultIndexStore {
         let commit_segment_id;
         let changed_path_start_commit_pos;
         let changed_path_segment_ids;
+        let reachability_bitmap_id;
+        let text_index_start_commit_pos;
+        let text_index_segment_ids;
         let op_link_file = self.op_links_dir().join(op_id.hex());
         match fs::read(&op_link_file).context(&op_link_file) {
             Ok(data) => {
@@ -201,6 +224,15 @@ impl DefaultIndexStore {
                     .into_iter()
                     .map(ChangedPathIndexSegmentId::new)
                     .collect_vec();
+                reachability_bitmap_id = (!proto.reachability_bitmap_id.is_empty())
+                    .then(|| ReachabilityBitmapIndexId::new(proto.reachability_bitmap_id));
+                text_index_start_commit_pos =
+                    proto.text_index_start_commit_pos.map(GlobalCommitPosition);
+                text_index_segment_ids = proto
+                    .text_index_segment_ids
+                    .into_iter()
+                    .map(TextIndexSegmentId::new)
+                    .collect_vec();
             }
             // TODO: drop support for legacy operation link file in jj 0.39 or so
             Err(PathError { source: error, .. }) if error.kind() == io::ErrorKind::NotFound => {
@@ -216,6 +248,9 @@ impl DefaultIndexStore {
                     .map_err(DefaultIndexStoreError::LoadAssociation)?;
                 changed_path_start_commit_pos = None;
                 changed_path_segment_ids = vec![];
+                reachability_bitmap_id = None;
+                text_index_start_commit_pos = None;
+                text_index_segment_ids = vec![];
             }
             Err(err) => return Err(DefaultIndexStoreError::LoadAssociation(err)),
         };
@@ -237,7 +272,29 @@ impl DefaultIndexStore {
         } else {
             CompositeChangedPathIndex::null()
         };
-        Ok(DefaultReadonlyIndex::from_segment(commits, changed_paths))
+        let reachability_bitmaps = reachability_bitmap_id
+            .map(|id| {
+                let num_commits = commits.as_composite().num_commits();
+                ReachabilityBitmapIndex::load(&self.reachability_bitmaps_dir(), id, num_commits)
+            })
+            .transpose()
+            .map_err(DefaultIndexStoreError::LoadIndex)?;
+        let text_index = if let Some(start_commit_pos) = text_index_start_commit_pos {
+            CompositeTextIndex::load(
+                &self.text_index_segments_dir(),
+                start_commit_pos,
+                &text_index_segment_ids,
+            )
+            .map_err(DefaultIndexStoreError::LoadIndex)?
+        } else {
+            CompositeTextIndex::null()
+        };
+        Ok(DefaultReadonlyIndex::from_segment(
+            commits,
+            changed_paths,
+            reachability_bitmaps,
+            text_index,
+        ))
     }
 
     /// Rebuilds index for the given `operation`.
@@ -478,7 +535,147 @@ impl DefaultIndexStore {
 
         // Update the operation link to point to the new segments
         let commits = index.readonly_commits().clone();
-        let index = DefaultReadonlyIndex::from_segment(commits, new_changed_paths);
+        let reachability_bitmaps = index.reachability_bitmaps().cloned();
+        let text_index = index.text_index().clone();
+        let index = DefaultReadonlyIndex::from_segment(
+            commits,
+            new_changed_paths,
+            reachability_bitmaps,
+            text_index,
+        );
+        self.associate_index_with_operation(&index, op_id)
+            .map_err(|source| DefaultIndexStoreError::AssociateIndex {
+                op_id: op_id.to_owned(),
+                source,
+            })?;
+        Ok(index)
+    }
+
+    /// Builds reachability bitmaps of the `heads` for the specified
+    /// operation.
+    ///
+    /// The existing bitmaps are replaced. Commits not in the index are
+    /// ignored.
+    #[tracing::instrument(skip(self, store))]
+    pub fn build_reachability_bitmaps_at_operation(
+        &self,
+        op_id: &OperationId,
+        store: &Arc<Store>,
+        heads: &[CommitId],
+    ) -> Result<DefaultReadonlyIndex, DefaultIndexStoreError> {
+        // Create directories in case the store was initialized by old jj.
+        self.ensure_base_dirs()
+            .map_err(DefaultIndexStoreError::SaveIndex)?;
+        let field_lengths = FieldLengths {
+            commit_id: store.commit_id_length(),
+            change_id: store.change_id_length(),
+        };
+        let index = self.load_index_at_operation(op_id, field_lengths)?;
+        let commits = index.as_composite().commits();
+        let head_positions = heads.iter().filter_map(|id| commits.commit_id_to_pos(id));
+        let bitmaps = build_reachability_bitmaps(
+            commits,
+            head_positions,
+            index.reachability_bitmaps().map(AsRef::as_ref),
+        );
+        let reachability_bitmaps =
+            ReachabilityBitmapIndex::save_in(&self.reachability_bitmaps_dir(), bitmaps)
+                .map_err(DefaultIndexStoreError::SaveIndex)?;
+
+        // Update the operation link to point to the new bitmaps
+        let index = DefaultReadonlyIndex::from_segment(
+            index.readonly_commits().clone(),
+            index.changed_paths().clone(),
+            Some(reachability_bitmaps),
+            index.text_index().clone(),
+        );
+        self.associate_index_with_operation(&index, op_id)
+            .map_err(|source| DefaultIndexStoreError::AssociateIndex {
+                op_id: op_id.to_owned(),
+                source,
+            })?;
+        Ok(index)
+    }
+
+    /// Builds text index of commit descriptions and authors for the specified
+    /// operation.
+    ///
+    /// All commits which haven't been indexed yet will be scanned. Once the
+    /// index is built, new commits will be indexed incrementally.
+    #[tracing::instrument(skip(self, store))]
+    pub async fn build_text_index_at_operation(
+        &self,
+        op_id: &OperationId,
+        store: &Arc<Store>,
+    ) -> Result<DefaultReadonlyIndex, DefaultIndexStoreError> {
+        // Create directories in case the store was initialized by old jj.
+        self.ensure_base_dirs()
+            .map_err(DefaultIndexStoreError::SaveIndex)?;
+        let field_lengths = FieldLengths {
+            commit_id: store.commit_id_length(),
+            change_id: store.change_id_length(),
+        };
+        let index = self.load_index_at_operation(op_id, field_lengths)?;
+        let old_text_index = index.text_index();
+
+        // Index the commits before and after the previously-indexed range:
+        //   |pre|old_text_index|post|
+        let pre_end = old_text_index
+            .start_commit_pos()
+            .map_or(index.num_commits(), |GlobalCommitPosition(pos)| pos);
+        let post_start = pre_end + old_text_index.num_commits();
+        let post_end = index.num_commits();
+        assert!(post_start <= post_end);
+
+        let to_index_err = |source| DefaultIndexStoreError::IndexCommits {
+            op_id: op_id.clone(),
+            source,
+        };
+        let index_commit = async |text_index: &mut CompositeTextIndex,
+                                  pos: GlobalCommitPosition| {
+            assert_eq!(text_index.next_mutable_commit_pos(), Some(pos));
+            let commit_id = index.as_composite().commits().entry_by_pos(pos).commit_id();
+            let commit = store.get_commit_async(&commit_id).await?;
+            text_index.add_keys(collect_text_index_keys(&commit));
+            Ok(())
+        };
+
+        // Index pre range
+        let mut new_text_index = CompositeTextIndex::empty(GlobalCommitPosition(0));
+        new_text_index.make_mutable();
+        tracing::info!(?pre_end, "indexing texts in commits");
+        for pos in (0..pre_end).map(GlobalCommitPosition) {
+            index_commit(&mut new_text_index, pos)
+                .await
+                .map_err(to_index_err)?;
+        }
+        new_text_index
+            .save_in(&self.text_index_segments_dir())
+            .map_err(DefaultIndexStoreError::SaveIndex)?;
+
+        // Copy previously-indexed segments
+        new_text_index.append_segments(old_text_index);
+
+        // Index post range, which is usually empty
+        new_text_index.make_mutable();
+        tracing::info!(?post_start, ?post_end, "indexing texts in commits");
+        for pos in (post_start..post_end).map(GlobalCommitPosition) {
+            index_commit(&mut new_text_index, pos)
+                .await
+                .map_err(to_index_err)?;
+        }
+        new_text_index.maybe_squash_with_ancestors();
+        new_text_index
+            .save_in(&self.text_index_segments_dir())
+            .map_err(DefaultIndexStoreError::SaveIndex)?;
+
+        // Update the operation link to point to the new segments
+        let index = DefaultReadonlyIndex::from_segment(
+            index.readonly_commits().clone(),
+            index.changed_paths().clone(),
+            index.reachability_bitmaps().cloned(),
+            new_text_index,
+        );
         self.associate_index_with_operation(&index, op_id)
             .map_err(|source| DefaultIndexStoreError::AssociateIndex {
                 op_id: op_id.to_owned(),
@@ -495,7 +692,8 @@ impl DefaultIndexStore {
         // Create directories in case the store was initialized by jj < 0.33.
         self.ensure_base_dirs()
             .map_err(DefaultIndexStoreError::SaveIndex)?;
-        let (commits, mut changed_paths) = index.into_segment();
+        let (commits, mut changed_paths, reachability_bitmaps, mut text_index) =
+            index.into_segment();
         let commits = commits
             .maybe_squash_with_ancestors()
             .save_in(&self.commit_segments_dir())
@@ -504,7 +702,16 @@ impl DefaultIndexStore {
         changed_paths
             .save_in(&self.changed_path_segments_dir())
             .map_err(DefaultIndexStoreError::SaveIndex)?;
-        let index = DefaultReadonlyIndex::from_segment(commits, changed_paths);
+        text_index.maybe_squash_with_ancestors();
+        text_index
+            .save_in(&self.text_index_segments_dir())
+            .map_err(DefaultIndexStoreError::SaveIndex)?;
+        let index = DefaultReadonlyIndex::from_segment(
+            commits,
+            changed_paths,
+            reachability_bitmaps,
+            text_index,
+        );
         self.associate_index_with_operation(&index, op_id)
             .map_err(|source| DefaultIndexStoreError::AssociateIndex {
                 op_id: op_id.to_owned(),
@@ -531,6 +738,20 @@ impl DefaultIndexStore {
                 .iter()
                 .map(|segment| segment.id().to_bytes())
                 .collect(),
+            reachability_bitmap_id: index
+                .reachability_bitmaps()
+                .map(|bitmaps| bitmaps.id().to_bytes())
+                .unwrap_or_default(),
+            text_index_start_commit_pos: index
+                .text_index()
+                .start_commit_pos()
+                .map(|GlobalCommitPosition(start)| start),
+            text_index_segment_ids: index
+                .text_index()
+                .readonly_segments()
+                .iter()
+                .map(|segment| segment.id().to_bytes())
+                .collect(),
         };
         let dir = self.op_links_dir();
         let mut temp_file = NamedTempFile::new_in(&dir).context(&dir)?;
@@ -0,0 +1,819 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Full-text index of commit descriptions and authors.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::Read;
+use std::io::Write as _;
+use std::path::Path;
+use std::sync::Arc;
+
+use blake2::Blake2b512;
+use digest::Digest as _;
+use either::Either;
+use itertools::Itertools as _;
+use tempfile::NamedTempFile;
+
+use super::entry::GlobalCommitPosition;
+use super::readonly::ReadonlyIndexLoadError;
+use crate::commit::Commit;
+use crate::file_util::IoResultExt as _;
+use crate::file_util::PathError;
+use crate::file_util::persist_content_addressed_temp_file;
+use crate::object_id::ObjectId as _;
+use crate::object_id::id_type;
+use crate::str_util::StringExpression;
+use crate::str_util::StringPattern;
+
+/// Current format version of the text index segment file.
+const FILE_FORMAT_VERSION: u32 = 0;
+
+id_type!(pub(super) TextIndexSegmentId { hex() });
+
+/// Commit position within a text index segment.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct CommitPosition(u32);
+
+/// Key position within a text index segment.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct KeyPosition(u32);
+
+/// Commit text field to be indexed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum TextField {
+    Description,
+    AuthorName,
+    AuthorEmail,
+}
+
+impl TextField {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Description => b'd',
+            Self::AuthorName => b'n',
+            Self::AuthorEmail => b'e',
+        }
+    }
+}
+
+/// Trigram of ASCII-lowercased text, prefixed with the field it appears in.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(super) struct TextIndexKey([u8; 4]);
+
+impl TextIndexKey {
+    fn new(field: TextField, trigram: &[u8; 3]) -> Self {
+        let [a, b, c] = trigram.map(|b| b.to_ascii_lowercase());
+        Self([field.to_byte(), a, b, c])
+    }
+}
+
+fn collect_trigram_keys(field: TextField, text: &str, keys: &mut Vec<TextIndexKey>) {
+    // Trigrams may split UTF-8 sequences. That's fine because the query string
+    // is split in the same way.
+    keys.extend(
+        text.as_bytes()
+            .windows(3)
+            .map(|trigram| TextIndexKey::new(field, trigram.try_into().unwrap())),
+    );
+}
+
+/// Builds sorted list of index keys of the given `commit`.
+pub(super) fn collect_text_index_keys(commit: &Commit) -> Vec<TextIndexKey> {
+    let mut keys = vec![];
+    collect_trigram_keys(TextField::Description, commit.description(), &mut keys);
+    collect_trigram_keys(TextField::AuthorName, &commit.author().name, &mut keys);
+    collect_trigram_keys(TextField::AuthorEmail, &commit.author().email, &mut keys);
+    keys.sort_unstable();
+    keys.dedup();
+    keys
+}
+
+/// Computes index keys for the string `expression` to be matched against the
+/// `field`.
+///
+/// A commit matching the `expression` must contain all keys of at least one of
+/// the returned alternatives. Returns `None` if the index can't be used to
+/// filter commits.
+pub(super) fn text_index_query_for_expression(
+    field: TextField,
+    expression: &StringExpression,
+) -> Option<Vec<Vec<TextIndexKey>>> {
+    match expression {
+        StringExpression::Pattern(pattern) => {
+            // Only ASCII case differences are folded by the string matcher, so
+            // the lowercased trigrams cover case-insensitive patterns.
+            let needle = match pattern.as_ref() {
+                StringPattern::Exact(needle)
+                | StringPattern::ExactI(needle)
+                | StringPattern::Substring(needle)
+                | StringPattern::SubstringI(needle) => needle,
+                StringPattern::Glob(_)
+                | StringPattern::GlobI(_)
+                | StringPattern::Regex(_)
+                | StringPattern::RegexI(_) => return None,
+            };
+            if needle.len() < 3 {
+                return None;
+            }
+            let mut keys = vec![];
+            collect_trigram_keys(field, needle, &mut keys);
+            keys.sort_unstable();
+            keys.dedup();
+            Some(vec![keys])
+        }
+        StringExpression::NotIn(_) => None,
+        StringExpression::Union(expression1, expression2) => {
+            let mut alternatives = text_index_query_for_expression(field, expression1)?;
+            alternatives.extend(text_index_query_for_expression(field, expression2)?);
+            Some(alternatives)
+        }
+        StringExpression::Intersection(expression1, expression2) => {
+            // Either side is sufficient.
+            text_index_query_for_expression(field, expression1)
+                .or_else(|| text_index_query_for_expression(field, expression2))
+        }
+    }
+}
+
+/// Text index segment backed by immutable file.
+///
+/// File format:
+/// ```text
+/// u32: file format version
+///
+/// u32: number of (local) commit entries
+/// u32: number of key entries
+/// u32: number of commit-key entries
+///
+/// for each commit, in commit-index order
+///   u32: position in commit-key table
+/// u32: number of commit-key entries (as sentinel)
+/// for each commit, in commit-index order
+///   for each key, sorted by key
+///     u32: lookup position of key
+///
+/// for each key, sorted by key
+///   [u8; 4]: field and trigram
+/// for each key, sorted by key
+///   u32: position in posting table
+/// u32: number of commit-key entries (as sentinel)
+/// for each key, sorted by key
+///   for each commit containing the key, in commit-index order
+///     u32: local commit position
+/// ```
+///
+/// * The parent segment id isn't stored in a segment file. This allows us to
+///   insert parents without rewriting the descendant segments.
+/// * The forward (commit-key) table is kept so that entries can be copied to
+///   another index without inverting the posting lists.
+pub(super) struct ReadonlyTextIndexSegment {
+    id: TextIndexSegmentId,
+    num_local_commits: u32,
+    num_keys: u32,
+    num_commit_keys: u32,
+    // Base data offsets in bytes:
+    commit_lookup_base: usize,
+    commit_key_lookup_base: usize,
+    key_table_base: usize,
+    posting_lookup_base: usize,
+    posting_base: usize,
+    data: Vec<u8>,
+}
+
+impl Debug for ReadonlyTextIndexSegment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("ReadonlyTextIndexSegment")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ReadonlyTextIndexSegment {
+    pub(super) fn load(
+        dir: &Path,
+        id: TextIndexSegmentId,
+    ) -> Result<Arc<Self>, ReadonlyIndexLoadError> {
+        let mut file = File::open(dir.join(id.hex()))
+            .map_err(|err| ReadonlyIndexLoadError::from_io_err("text", id.hex(), err))?;
+        Self::load_from(&mut file, id)
+    }
+
+    pub(super) fn load_from(
+        file: &mut dyn Read,
+        id: TextIndexSegmentId,
+    ) -> Result<Arc<Self>, ReadonlyIndexLoadError> {
+        let from_io_err = |err| ReadonlyIndexLoadError::from_io_err("text", id.hex(), err);
+        let read_u32 = |file: &mut dyn Read| {
+            let mut buf = [0; 4];
+            file.read_exact(&mut buf).map_err(from_io_err)?;
+            Ok(u32::from_le_bytes(buf))
+        };
+
+        let format_version = read_u32(file)?;
+        if format_version != FILE_FORMAT_VERSION {
+            return Err(ReadonlyIndexLoadError::UnexpectedVersion {
+                kind: "text",
+                found_version: format_version,
+                expected_version: FILE_FORMAT_VERSION,
+            });
+        }
+
+        let num_local_commits = read_u32(file)?;
+        let num_keys = read_u32(file)?;
+        let num_commit_keys = read_u32(file)?;
+        let mut data = vec![];
+        file.read_to_end(&mut data).map_err(from_io_err)?;
+
+        let commit_lookup_size = (num_local_commits as usize + 1) * 4;
+        let commit_key_lookup_size = (num_commit_keys as usize) * 4;
+        let key_table_size = (num_keys as usize) * 4;
+        let posting_lookup_size = (num_keys as usize + 1) * 4;
+        let posting_size = (num_commit_keys as usize) * 4;
+
+        let commit_lookup_base = 0;
+        let commit_key_lookup_base = commit_lookup_base + commit_lookup_size;
+        let key_table_base = commit_key_lookup_base + commit_key_lookup_size;
+        let posting_lookup_base = key_table_base + key_table_size;
+        let posting_base = posting_lookup_base + posting_lookup_size;
+        let expected_size = posting_base + posting_size;
+
+        if data.len() != expected_size {
+            return Err(ReadonlyIndexLoadError::invalid_data(
+                "text",
+                id.hex(),
+                "unexpected data length",
+            ));
+        }
+
+        Ok(Arc::new(Self {
+            id,
+            num_local_commits,
+            num_keys,
+            num_commit_keys,
+            commit_lookup_base,
+            commit_key_lookup_base,
+            key_table_base,
+            posting_lookup_base,
+            posting_base,
+            data,
+        }))
+    }
+
+    pub(super) fn id(&self) -> &TextIndexSegmentId {
+        &self.id
+    }
+
+    pub(super) fn num_local_commits(&self) -> u32 {
+        self.num_local_commits
+    }
+
+    pub(super) fn num_keys(&self) -> u32 {
+        self.num_keys
+    }
+
+    pub(super) fn num_commit_keys(&self) -> u32 {
+        self.num_commit_keys
+    }
+
+    fn keys(&self, pos: CommitPosition) -> impl ExactSizeIterator<Item = TextIndexKey> {
+        let table = &self.data[self.commit_lookup_base..self.commit_key_lookup_base];
+        let offset = pos.0 as usize * 4;
+        let start = u32::from_le_bytes(table[offset..][0..4].try_into().unwrap());
+        let end = u32::from_le_bytes(table[offset..][4..8].try_into().unwrap());
+
+        let table = &self.data[self.commit_key_lookup_base..self.key_table_base];
+        let (chunks, _remainder) = table[(start as usize) * 4..(end as usize) * 4].as_chunks();
+        chunks
+            .iter()
+            .map(|&chunk: &[u8; 4]| KeyPosition(u32::from_le_bytes(chunk)))
+            .map(|pos| self.key(pos))
+    }
+
+    fn key_table(&self) -> &[[u8; 4]] {
+        let (chunks, _remainder) =
+            self.data[self.key_table_base..self.posting_lookup_base].as_chunks();
+        chunks
+    }
+
+    fn key(&self, pos: KeyPosition) -> TextIndexKey {
+        TextIndexKey(self.key_table()[pos.0 as usize])
+    }
+
+    fn postings(&self, pos: KeyPosition) -> &[[u8; 4]] {
+        let table = &self.data[self.posting_lookup_base..self.posting_base];
+        let offset = pos.0 as usize * 4;
+        let start = u32::from_le_bytes(table[offset..][0..4].try_into().unwrap());
+        let end = u32::from_le_bytes(table[offset..][4..8].try_into().unwrap());
+
+        let (chunks, _remainder) =
+            self.data[self.posting_base..][(start as usize) * 4..(end as usize) * 4].as_chunks();
+        chunks
+    }
+
+    /// Returns true if the commit at `pos` contains the `key`.
+    fn contains(&self, pos: CommitPosition, key: &TextIndexKey) -> bool {
+        let Ok(key_pos) = self.key_table().binary_search(&key.0) else {
+            return false;
+        };
+        let postings = self.postings(KeyPosition(key_pos.try_into().unwrap()));
+        postings
+            .binary_search_by_key(&pos.0, |&chunk| u32::from_le_bytes(chunk))
+            .is_ok()
+    }
+}
+
+/// Text index segment which is not serialized to file.
+#[derive(Clone)]
+pub(super) struct MutableTextIndexSegment {
+    entries: Vec<Vec<TextIndexKey>>,
+}
+
+impl Debug for MutableTextIndexSegment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("MutableTextIndexSegment")
+            .finish_non_exhaustive()
+    }
+}
+
+impl MutableTextIndexSegment {
+    pub(super) fn empty() -> Self {
+        Self { entries: vec![] }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub(super) fn num_local_commits(&self) -> u32 {
+        self.entries.len().try_into().unwrap()
+    }
+
+    fn keys(&self, pos: CommitPosition) -> impl ExactSizeIterator<Item = TextIndexKey> {
+        self.entries[pos.0 as usize].iter().copied()
+    }
+
+    fn contains(&self, pos: CommitPosition, key: &TextIndexKey) -> bool {
+        self.entries[pos.0 as usize].binary_search(key).is_ok()
+    }
+
+    fn add_keys(&mut self, keys: Vec<TextIndexKey>) {
+        debug_assert!(keys.is_sorted_by(|a, b| a < b));
+        self.entries.push(keys);
+    }
+
+    fn extend_from_readonly_segment(&mut self, other_segment: &ReadonlyTextIndexSegment) {
+        self.entries
+            .reserve(usize::try_from(other_segment.num_local_commits()).unwrap());
+        for pos in (0..other_segment.num_local_commits()).map(CommitPosition) {
+            self.add_keys(other_segment.keys(pos).collect());
+        }
+    }
+
+    fn extend_from_mutable_segment(&mut self, other_segment: Self) {
+        self.entries.extend(other_segment.entries);
+    }
+
+    fn serialize_into(&self, buf: &mut Vec<u8>) {
+        let mut postings: BTreeMap<TextIndexKey, Vec<CommitPosition>> = BTreeMap::new();
+        for (pos, keys) in self.entries.iter().enumerate() {
+            let pos = CommitPosition(u32::try_from(pos).unwrap());
+            for key in keys {
+                postings.entry(*key).or_default().push(pos);
+            }
+        }
+        let key_pos_map: BTreeMap<_, _> = postings
+            .keys()
+            .enumerate()
+            .map(|(i, &key)| (key, KeyPosition(u32::try_from(i).unwrap())))
+            .collect();
+
+        buf.extend(FILE_FORMAT_VERSION.to_le_bytes());
+        let num_commits = u32::try_from(self.entries.len()).unwrap();
+        let num_keys = u32::try_from(postings.len()).unwrap();
+        let num_commit_keys =
+            u32::try_from(self.entries.iter().map(Vec::len).sum::<usize>()).unwrap();
+        buf.extend(num_commits.to_le_bytes());
+        buf.extend(num_keys.to_le_bytes());
+        buf.extend(num_commit_keys.to_le_bytes());
+
+        let mut offset: u32 = 0;
+        for keys in &self.entries {
+            buf.extend(offset.to_le_bytes());
+            offset += u32::try_from(keys.len()).unwrap();
+        }
+        buf.extend(offset.to_le_bytes()); // sentinel
+
+        for key in self.entries.iter().flatten() {
+            let KeyPosition(pos) = key_pos_map[key];
+            buf.extend(pos.to_le_bytes());
+        }
+
+        for key in postings.keys() {
+            buf.extend(key.0);
+        }
+
+        let mut offset: u32 = 0;
+        for positions in postings.values() {
+            buf.extend(offset.to_le_bytes());
+            offset += u32::try_from(positions.len()).unwrap();
+        }
+        buf.extend(offset.to_le_bytes()); // sentinel
+
+        for CommitPosition(pos) in postings.values().flatten() {
+            buf.extend(pos.to_le_bytes());
+        }
+    }
+
+    pub(super) fn save_in(&self, dir: &Path) -> Result<Arc<ReadonlyTextIndexSegment>, PathError> {
+        let mut buf = Vec::new();
+        self.serialize_into(&mut buf);
+        let mut hasher = Blake2b512::new();
+        hasher.update(&buf);
+
+        let file_id = TextIndexSegmentId::from_bytes(&hasher.finalize());
+        let file_path = dir.join(file_id.hex());
+        let mut file = NamedTempFile::new_in(dir).context(dir)?;
+        file.as_file_mut().write_all(&buf).context(file.path())?;
+        persist_content_addressed_temp_file(file, &file_path).context(&file_path)?;
+
+        let segment = ReadonlyTextIndexSegment::load_from(&mut &buf[..], file_id)
+            .expect("in-memory index data should be valid and readable");
+        Ok(segment)
+    }
+}
+
+/// Index of commit description and author texts.
+#[derive(Clone, Debug)]
+pub(super) struct CompositeTextIndex {
+    start_commit_pos: Option<GlobalCommitPosition>,
+    num_commits: u32, // cache
+    readonly_segments: Vec<Arc<ReadonlyTextIndexSegment>>,
+    mutable_segment: Option<Box<MutableTextIndexSegment>>,
+}
+
+impl CompositeTextIndex {
+    /// Creates empty text index which cannot store entries. In other words,
+    /// the text index is disabled.
+    pub(super) fn null() -> Self {
+        Self {
+            start_commit_pos: None,
+            num_commits: 0,
+            readonly_segments: vec![],
+            mutable_segment: None,
+        }
+    }
+
+    /// Creates empty text index which will store entries from
+    /// `start_commit_pos`.
+    pub(super) fn empty(start_commit_pos: GlobalCommitPosition) -> Self {
+        Self {
+            start_commit_pos: Some(start_commit_pos),
+            num_commits: 0,
+            readonly_segments: vec![],
+            mutable_segment: None,
+        }
+    }
+
+    pub(super) fn load(
+        dir: &Path,
+        start_commit_pos: GlobalCommitPosition,
+        ids: &[TextIndexSegmentId],
+    ) -> Result<Self, ReadonlyIndexLoadError> {
+        let readonly_segments: Vec<_> = ids
+            .iter()
+            .map(|id| ReadonlyTextIndexSegment::load(dir, id.clone()))
+            .try_collect()?;
+        let num_commits = readonly_segments
+            .iter()
+            .map(|segment| segment.num_local_commits())
+            .sum();
+        Ok(Self {
+            start_commit_pos: Some(start_commit_pos),
+            num_commits,
+            readonly_segments,
+            mutable_segment: None,
+        })
+    }
+
+    /// Adds mutable segment if needed.
+    pub(super) fn make_mutable(&mut self) {
+        if self.start_commit_pos.is_none() || self.mutable_segment.is_some() {
+            return;
+        }
+        self.mutable_segment = Some(Box::new(MutableTextIndexSegment::empty()));
+    }
+
+    /// Position of the first indexed (or to-be-indexed) commit.
+    pub(super) fn start_commit_pos(&self) -> Option<GlobalCommitPosition> {
+        self.start_commit_pos
+    }
+
+    /// New commit index position which can be added to this index.
+    pub(super) fn next_mutable_commit_pos(&self) -> Option<GlobalCommitPosition> {
+        if self.mutable_segment.is_some() {
+            self.start_commit_pos
+                .map(|GlobalCommitPosition(start)| GlobalCommitPosition(start + self.num_commits))
+        } else {
+            None
+        }
+    }
+
+    pub(super) fn num_commits(&self) -> u32 {
+        self.num_commits
+    }
+
+    pub(super) fn readonly_segments(&self) -> &[Arc<ReadonlyTextIndexSegment>] {
+        &self.readonly_segments
+    }
+
+    /// Appends segments from the `other` index. This and the other index should
+    /// be contiguous.
+    pub(super) fn append_segments(&mut self, other: &Self) {
+        assert!(self.mutable_segment.is_none());
+        let GlobalCommitPosition(self_start_pos) =
+            self.start_commit_pos.expect("should have start pos");
+        let Some(GlobalCommitPosition(other_start_pos)) = other.start_commit_pos else {
+            return;
+        };
+        assert_eq!(self_start_pos + self.num_commits, other_start_pos);
+        self.readonly_segments
+            .extend_from_slice(&other.readonly_segments);
+        self.mutable_segment = other.mutable_segment.clone();
+        self.num_commits += other.num_commits;
+    }
+
+    /// Maps `global_pos` to segment and segment-local position.
+    fn find_segment(
+        &self,
+        global_pos: GlobalCommitPosition,
+    ) -> Option<(
+        CommitPosition,
+        Either<&ReadonlyTextIndexSegment, &MutableTextIndexSegment>,
+    )> {
+        let mut local_pos = u32::checked_sub(global_pos.0, self.start_commit_pos?.0)?;
+        for segment in &self.readonly_segments {
+            local_pos = match u32::checked_sub(local_pos, segment.num_local_commits()) {
+                Some(next_local_pos) => next_local_pos,
+                None => return Some((CommitPosition(local_pos), Either::Left(segment))),
+            };
+        }
+        let segment = self.mutable_segment.as_deref()?;
+        (local_pos < segment.num_local_commits())
+            .then_some((CommitPosition(local_pos), Either::Right(segment)))
+    }
+
+    /// Returns sorted keys of the specified commit. Returns `None` if the
+    /// commit wasn't indexed.
+    pub(super) fn keys(
+        &self,
+        global_pos: GlobalCommitPosition,
+    ) -> Option<impl ExactSizeIterator<Item = TextIndexKey>> {
+        let (local_pos, segment) = self.find_segment(global_pos)?;
+        Some(segment.map_either(|x| x.keys(local_pos), |x| x.keys(local_pos)))
+    }
+
+    /// Returns false if the specified commit contains none of the
+    /// `alternatives`, each of which is a set of keys to be included. Returns
+    /// true if the commit wasn't indexed.
+    pub(super) fn may_match_any(
+        &self,
+        global_pos: GlobalCommitPosition,
+        alternatives: &[Vec<TextIndexKey>],
+    ) -> bool {
+        let Some((local_pos, segment)) = self.find_segment(global_pos) else {
+            return true;
+        };
+        let contains = |key: &TextIndexKey| {
+            segment.either(
+                |x| x.contains(local_pos, key),
+                |x| x.contains(local_pos, key),
+            )
+        };
+        alternatives.iter().any(|keys| keys.iter().all(contains))
+    }
+
+    /// Adds keys of the next commit.
+    ///
+    /// The input `keys` must be sorted.
+    ///
+    /// Caller must ensure that the commit matches `next_mutable_commit_pos()`.
+    /// Panics if this index isn't mutable (i.e. `next_mutable_commit_pos()` is
+    /// `None`.)
+    pub(super) fn add_keys(&mut self, keys: Vec<TextIndexKey>) {
+        let segment = self
+            .mutable_segment
+            .as_deref_mut()
+            .expect("should have mutable");
+        segment.add_keys(keys);
+        self.num_commits += 1;
+    }
+
+    /// Squashes parent segments if the mutable segment has more than half the
+    /// commits of its parent segment. This is done recursively, so the stack of
+    /// index segments has O(log n) files.
+    pub(super) fn maybe_squash_with_ancestors(&mut self) {
+        let Some(mutable_segment) = self.mutable_segment.as_deref() else {
+            return;
+        };
+        let mut num_new_commits = mutable_segment.num_local_commits();
+        let mut squash_start = self.readonly_segments.len();
+        for segment in self.readonly_segments.iter().rev() {
+            if 2 * num_new_commits < segment.num_local_commits() {
+                break;
+            }
+            num_new_commits += segment.num_local_commits();
+            squash_start -= 1;
+        }
+        if squash_start == self.readonly_segments.len() {
+            return;
+        }
+        let mut squashed_segment = Box::new(MutableTextIndexSegment::empty());
+        for segment in self.readonly_segments.drain(squash_start..) {
+            squashed_segment.extend_from_readonly_segment(&segment);
+        }
+        squashed_segment.extend_from_mutable_segment(*self.mutable_segment.take().unwrap());
+        self.mutable_segment = Some(squashed_segment);
+    }
+
+    /// Writes mutable segment if exists, turns it into readonly segment.
+    pub(super) fn save_in(&mut self, dir: &Path) -> Result<(), PathError> {
+        let Some(segment) = self.mutable_segment.take() else {
+            return Ok(());
+        };
+        if segment.is_empty() {
+            return Ok(());
+        };
+        let segment = segment.save_in(dir)?;
+        self.readonly_segments.push(segment);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::tests::new_temp_dir;
+
+    fn keys(field: TextField, text: &str) -> Vec<TextIndexKey> {
+        let mut keys = vec![];
+        collect_trigram_keys(field, text, &mut keys);
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+
+    fn query(field: TextField, pattern: StringPattern) -> Option<Vec<Vec<TextIndexKey>>> {
+        text_index_query_for_expression(field, &StringExpression::pattern(pattern))
+    }
+
+    #[test]
+    fn test_trigram_keys() {
+        assert_eq!(keys(TextField::Description, "ab"), []);
+        assert_eq!(
+            keys(TextField::Description, "Abcb"),
+            [TextIndexKey(*b"dabc"), TextIndexKey(*b"dbcb")]
+        );
+        assert_eq!(
+            keys(TextField::AuthorName, "aaaa"),
+            [TextIndexKey(*b"naaa")]
+        );
+    }
+
+    #[test]
+    fn test_query_for_expression() {
+        let field = TextField::Description;
+        assert_eq!(
+            query(field, StringPattern::substring("FOO")),
+            Some(vec![keys(field, "foo")])
+        );
+        assert_eq!(
+            query(field, StringPattern::exact_i("foo bar")),
+            Some(vec![keys(field, "foo bar")])
+        );
+        // Too short, or not a literal
+        assert_eq!(query(field, StringPattern::substring("fo")), None);
+        assert_eq!(query(field, StringPattern::all()), None);
+        assert_eq!(query(field, StringPattern::glob("foo*").unwrap()), None);
+        assert_eq!(query(field, StringPattern::regex("foo").unwrap()), None);
+
+        let foo = || StringExpression::substring("foo");
+        let bar = || StringExpression::substring("bar");
+        let all = StringExpression::all;
+        assert_eq!(
+            text_index_query_for_expression(field, &foo().union(bar())),
+            Some(vec![keys(field, "foo"), keys(field, "bar")])
+        );
+        assert_eq!(
+            text_index_query_for_expression(field, &foo().union(all())),
+            None
+        );
+        assert_eq!(
+            text_index_query_for_expression(field, &all().intersection(bar())),
+            Some(vec![keys(field, "bar")])
+        );
+        assert_eq!(
+            text_index_query_for_expression(field, &foo().negated()),
+            None
+        );
+    }
+
+    #[test_case(false, false; "mutable")]
+    #[test_case(true, false; "readonly")]
+    #[test_case(true, true; "readonly, reloaded")]
+    fn test_composite_some_commits(on_disk: bool, reload: bool) {
+        let temp_dir = new_temp_dir();
+        let start_commit_pos = GlobalCommitPosition(1);
+        let mut index = CompositeTextIndex::empty(start_commit_pos);
+        index.make_mutable();
+        index.add_keys(keys(TextField::Description, "foo bar"));
+        index.add_keys(vec![]);
+        index.add_keys(keys(TextField::Description, "bar baz"));
+        assert_eq!(
+            index.next_mutable_commit_pos(),
+            Some(GlobalCommitPosition(4))
+        );
+        if on_disk {
+            index.save_in(temp_dir.path()).unwrap();
+            assert!(index.mutable_segment.is_none());
+            assert_eq!(index.readonly_segments.len(), 1);
+            assert_eq!(index.num_commits(), 3);
+        }
+        if reload {
+            let ids = index
+                .readonly_segments()
+                .iter()
+                .map(|segment| segment.id().clone())
+                .collect_vec();
+            index = CompositeTextIndex::load(temp_dir.path(), start_commit_pos, &ids).unwrap();
+        }
+        if let [segment] = &*index.readonly_segments {
+            assert_eq!(segment.num_local_commits(), 3);
+            assert_eq!(segment.num_keys(), 8);
+            assert_eq!(segment.num_commit_keys(), 10);
+        }
+
+        let collect_keys = |pos| Some(index.keys(pos)?.collect_vec());
+        assert_eq!(collect_keys(GlobalCommitPosition(0)), None);
+        assert_eq!(
+            collect_keys(GlobalCommitPosition(1)),
+            Some(keys(TextField::Description, "foo bar"))
+        );
+        assert_eq!(collect_keys(GlobalCommitPosition(2)), Some(vec![]));
+        assert_eq!(collect_keys(GlobalCommitPosition(4)), None);
+
+        let bar = [keys(TextField::Description, "bar")];
+        let foo_baz = [keys(TextField::Description, "foo baz")];
+        let bar_name = [keys(TextField::AuthorName, "bar")];
+        // Not indexed
+        assert!(index.may_match_any(GlobalCommitPosition(0), &bar));
+        assert!(index.may_match_any(GlobalCommitPosition(1), &bar));
+        assert!(!index.may_match_any(GlobalCommitPosition(2), &bar));
+        assert!(index.may_match_any(GlobalCommitPosition(3), &bar));
+        assert!(!index.may_match_any(GlobalCommitPosition(1), &foo_baz));
+        assert!(!index.may_match_any(GlobalCommitPosition(3), &foo_baz));
+        assert!(!index.may_match_any(GlobalCommitPosition(1), &bar_name));
+        // Not indexed
+        assert!(index.may_match_any(GlobalCommitPosition(4), &bar));
+    }
+
+    #[test]
+    fn test_composite_squash_segments() {
+        let temp_dir = new_temp_dir();
+        let mut index = CompositeTextIndex::empty(GlobalCommitPosition(0));
+        for text in ["foo", "bar", "baz"] {
+            index.make_mutable();
+            index.add_keys(keys(TextField::Description, text));
+            index.maybe_squash_with_ancestors();
+            index.save_in(temp_dir.path()).unwrap();
+        }
+        assert_eq!(index.readonly_segments.len(), 1);
+        assert_eq!(index.readonly_segments[0].num_local_commits(), 3);
+
+        // Squashed segments should preserve the original entries.
+        for (pos, text) in ["foo", "bar", "baz"].iter().enumerate() {
+            let pos = GlobalCommitPosition(pos.try_into().unwrap());
+            assert_eq!(
+                index.keys(pos).unwrap().collect_vec(),
+                keys(TextField::Description, text)
+            );
+            assert!(index.may_match_any(pos, &[keys(TextField::Description, text)]));
+        }
+    }
+}
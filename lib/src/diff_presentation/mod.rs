@@ -20,8 +20,10 @@ use std::borrow::Borrow;
 use std::mem;
 
 use bstr::BString;
+use bstr::ByteSlice as _;
 use itertools::Itertools as _;
 use pollster::FutureExt as _;
+use regex::bytes::Regex;
 
 use crate::backend::BackendResult;
 use crate::conflicts::MaterializedFileValue;
@@ -35,6 +37,7 @@ use crate::diff::find_line_ranges;
 use crate::merge::Merge;
 use crate::repo_path::RepoPath;
 
+pub mod moved;
 pub mod unified;
 // TODO: colored_diffs utils should also be moved from `jj_cli::diff_utils` to
 // here.
@@ -91,6 +94,86 @@ pub enum LineCompareMode {
     IgnoreSpaceChange,
 }
 
+/// Finds lines that start functions or sections, which are used to annotate
+/// diff hunks and to extend hunks to whole functions.
+///
+/// This is similar to `diff.<driver>.xfuncname` of Git.
+#[derive(Clone, Debug)]
+pub struct FunctionMatcher {
+    regex: Regex,
+}
+
+impl FunctionMatcher {
+    /// Creates a matcher from a regular expression matching a function header
+    /// line. If the expression has a capture group, the first group is used as
+    /// the header text.
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            regex: Regex::new(pattern)?,
+        })
+    }
+
+    /// Returns the header text if the `line` starts a function.
+    pub fn match_line<'a>(&self, line: &'a [u8]) -> Option<&'a [u8]> {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let captures = self.regex.captures(line)?;
+        let header = captures.get(1).or_else(|| captures.get(0))?.as_bytes();
+        Some(header.trim_end())
+    }
+
+    /// Returns the function headers in the `content` and their line indices.
+    pub fn find_headers<'a>(&self, content: &'a [u8]) -> FunctionHeaders<'a> {
+        let headers = content
+            .split_inclusive(|b| *b == b'\n')
+            .enumerate()
+            .filter_map(|(index, line)| Some((index, self.match_line(line)?)))
+            .collect();
+        FunctionHeaders { headers }
+    }
+
+    /// Returns the numbers of leading and trailing lines of the unchanged
+    /// `content` that belong to the functions of the changes around it.
+    ///
+    /// The leading lines continue the function of the preceding change until
+    /// the next function header. The trailing lines start at the header of the
+    /// function of the following change. If the `content` has no function
+    /// header, all lines belong to both functions.
+    pub fn context_lines(&self, content: &[u8]) -> (usize, usize) {
+        let headers = self.find_headers(content).headers;
+        let num_lines = content.split_inclusive(|b| *b == b'\n').count();
+        match (headers.first(), headers.last()) {
+            (Some(&(first, _)), Some(&(last, _))) => (first, num_lines - last),
+            _ => (num_lines, num_lines),
+        }
+    }
+}
+
+impl PartialEq for FunctionMatcher {
+    fn eq(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+impl Eq for FunctionMatcher {}
+
+/// Function headers found by [`FunctionMatcher::find_headers()`].
+#[derive(Clone, Debug)]
+pub struct FunctionHeaders<'a> {
+    /// `(line_index, header)`s sorted by line index.
+    headers: Vec<(usize, &'a [u8])>,
+}
+
+impl<'a> FunctionHeaders<'a> {
+    /// Returns the header of the function enclosing the line at `line_index`,
+    /// which is the last header before the line.
+    pub fn enclosing(&self, line_index: usize) -> Option<&'a [u8]> {
+        let pos = self
+            .headers
+            .partition_point(|&(index, _)| index < line_index);
+        pos.checked_sub(1).map(|pos| self.headers[pos].1)
+    }
+}
+
 pub fn diff_by_line<'input, T: AsRef<[u8]> + ?Sized + 'input>(
     inputs: impl IntoIterator<Item = &'input T>,
     options: &LineCompareMode,
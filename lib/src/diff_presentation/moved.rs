@@ -0,0 +1,319 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Utilities to detect lines moved within or across files
+
+use std::collections::HashSet;
+
+use bstr::BStr;
+
+use super::LineCompareMode;
+use super::diff_by_line;
+use crate::diff::DiffHunkKind;
+
+/// Minimum number of alphanumeric characters in a block of lines to be
+/// considered moved. Smaller blocks such as lone braces are likely to match by
+/// accident. This is the same threshold as Git's `--color-moved`.
+const MIN_MOVED_ALNUM_CHARS: usize = 20;
+
+/// Maximum number of passes to find blocks which were moved across each
+/// other. Each pass diffs all remaining changed lines, so the number of passes
+/// is bounded to keep large diffs fast.
+const MAX_DETECTION_PASSES: usize = 4;
+
+/// Removed or added line.
+#[derive(Clone, Debug)]
+struct ChangedLine<'input> {
+    file_index: usize,
+    line_index: usize,
+    /// Position in all changed lines of the same side. Adjacent lines in the
+    /// same block have consecutive positions.
+    position: usize,
+    content: &'input [u8],
+}
+
+/// Pair of removed and added lines which match.
+type MovedBlock<'a, 'input> = (Vec<&'a ChangedLine<'input>>, Vec<&'a ChangedLine<'input>>);
+
+/// Collects removed and added lines of file diffs to find blocks of lines that
+/// were moved.
+#[derive(Clone, Debug)]
+pub struct MovedLineDetector<'input> {
+    compare_mode: LineCompareMode,
+    removed: Vec<ChangedLine<'input>>,
+    added: Vec<ChangedLine<'input>>,
+    num_files: usize,
+}
+
+impl<'input> MovedLineDetector<'input> {
+    /// Creates new detector comparing lines by `compare_mode`.
+    pub fn new(compare_mode: LineCompareMode) -> Self {
+        Self {
+            compare_mode,
+            removed: vec![],
+            added: vec![],
+            num_files: 0,
+        }
+    }
+
+    /// Adds diff of `[left, right]` file contents. Returns the index of the
+    /// file, which should be passed to [`MovedLines::file()`].
+    pub fn add_file(&mut self, contents: [&'input BStr; 2]) -> usize {
+        let file_index = self.num_files;
+        self.num_files += 1;
+        let diff = diff_by_line(contents, &self.compare_mode);
+        let mut line_indices = [0, 0];
+        for hunk in diff.hunks() {
+            let sides = [&mut self.removed, &mut self.added];
+            for ((content, line_index), changed_lines) in
+                hunk.contents.iter().zip(&mut line_indices).zip(sides)
+            {
+                let lines = content.split_inclusive(|b| *b == b'\n');
+                if hunk.kind == DiffHunkKind::Matching {
+                    *line_index += lines.count();
+                    continue;
+                }
+                // Leave a gap so blocks of different hunks aren't adjacent.
+                let start_position = changed_lines.last().map_or(0, |line| line.position + 2);
+                for (position, content) in (start_position..).zip(lines) {
+                    changed_lines.push(ChangedLine {
+                        file_index,
+                        line_index: *line_index,
+                        position,
+                        content,
+                    });
+                    *line_index += 1;
+                }
+            }
+        }
+        file_index
+    }
+
+    /// Finds blocks of removed lines that were added elsewhere.
+    pub fn detect(&self) -> MovedLines {
+        let mut moved_lines = MovedLines {
+            files: vec![FileMovedLines::default(); self.num_files],
+        };
+        let mut removed: Vec<_> = self.removed.iter().collect();
+        let mut added: Vec<_> = self.added.iter().collect();
+        // Blocks are matched in order, so blocks which were moved across each
+        // other can't be found in a single pass.
+        for _ in 0..MAX_DETECTION_PASSES {
+            let blocks = self.find_moved_blocks(&removed, &added);
+            if blocks.is_empty() {
+                break;
+            }
+            let mut moved_removed = HashSet::new();
+            let mut moved_added = HashSet::new();
+            for (removed_block, added_block) in blocks {
+                for line in removed_block {
+                    moved_lines.files[line.file_index]
+                        .removed
+                        .insert(line.line_index);
+                    moved_removed.insert(line.position);
+                }
+                for line in added_block {
+                    moved_lines.files[line.file_index]
+                        .added
+                        .insert(line.line_index);
+                    moved_added.insert(line.position);
+                }
+            }
+            removed.retain(|line| !moved_removed.contains(&line.position));
+            added.retain(|line| !moved_added.contains(&line.position));
+        }
+        moved_lines
+    }
+
+    /// Returns pairs of matching removed and added blocks.
+    fn find_moved_blocks<'a>(
+        &self,
+        removed: &[&'a ChangedLine<'input>],
+        added: &[&'a ChangedLine<'input>],
+    ) -> Vec<MovedBlock<'a, 'input>> {
+        let [removed_text, added_text] = [removed, added].map(|lines| {
+            let mut text = Vec::new();
+            for line in lines {
+                text.extend_from_slice(line.content);
+                if !line.content.ends_with(b"\n") {
+                    text.push(b'\n');
+                }
+            }
+            text
+        });
+        let diff = diff_by_line([&removed_text, &added_text], &self.compare_mode);
+        let mut blocks = vec![];
+        let mut line_indices = [0, 0];
+        for hunk in diff.hunks() {
+            let [removed_count, added_count] =
+                [0, 1].map(|side| hunk.contents[side].split_inclusive(|b| *b == b'\n').count());
+            if hunk.kind == DiffHunkKind::Matching {
+                let removed_lines = &removed[line_indices[0]..][..removed_count];
+                let added_lines = &added[line_indices[1]..][..added_count];
+                blocks.extend(split_adjacent_blocks(removed_lines, added_lines));
+            }
+            line_indices[0] += removed_count;
+            line_indices[1] += added_count;
+        }
+        blocks.retain(|(removed_block, _)| {
+            let num_alnum_chars: usize = removed_block
+                .iter()
+                .map(|line| {
+                    line.content
+                        .iter()
+                        .filter(|b| b.is_ascii_alphanumeric())
+                        .count()
+                })
+                .sum();
+            num_alnum_chars >= MIN_MOVED_ALNUM_CHARS
+        });
+        blocks
+    }
+}
+
+/// Splits matching lines into blocks which are contiguous on both sides.
+fn split_adjacent_blocks<'a, 'input>(
+    removed: &[&'a ChangedLine<'input>],
+    added: &[&'a ChangedLine<'input>],
+) -> Vec<MovedBlock<'a, 'input>> {
+    let mut blocks: Vec<MovedBlock> = vec![];
+    for (&removed_line, &added_line) in removed.iter().zip(added) {
+        match blocks.last_mut() {
+            Some((removed_block, added_block))
+                if removed_block.last().unwrap().position + 1 == removed_line.position
+                    && added_block.last().unwrap().position + 1 == added_line.position =>
+            {
+                removed_block.push(removed_line);
+                added_block.push(added_line);
+            }
+            _ => blocks.push((vec![removed_line], vec![added_line])),
+        }
+    }
+    blocks
+}
+
+/// Lines detected as moved by [`MovedLineDetector`].
+#[derive(Clone, Debug, Default)]
+pub struct MovedLines {
+    files: Vec<FileMovedLines>,
+}
+
+impl MovedLines {
+    /// Returns moved lines of the file at `file_index`.
+    pub fn file(&self, file_index: usize) -> &FileMovedLines {
+        &self.files[file_index]
+    }
+}
+
+/// Moved lines in a file diff.
+#[derive(Clone, Debug, Default)]
+pub struct FileMovedLines {
+    removed: HashSet<usize>,
+    added: HashSet<usize>,
+}
+
+impl FileMovedLines {
+    /// Returns true if the removed line at 0-based `line_index` in the left
+    /// content was added elsewhere.
+    pub fn is_removed_line_moved(&self, line_index: usize) -> bool {
+        self.removed.contains(&line_index)
+    }
+
+    /// Returns true if the added line at 0-based `line_index` in the right
+    /// content was removed elsewhere.
+    pub fn is_added_line_moved(&self, line_index: usize) -> bool {
+        self.added.contains(&line_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(files: &[[&str; 2]]) -> Vec<[Vec<usize>; 2]> {
+        let mut detector = MovedLineDetector::new(LineCompareMode::Exact);
+        for [left, right] in files {
+            detector.add_file([left, right].map(BStr::new));
+        }
+        let moved_lines = detector.detect();
+        (0..files.len())
+            .map(|index| {
+                let file = moved_lines.file(index);
+                let mut removed = file.removed.iter().copied().collect::<Vec<_>>();
+                let mut added = file.added.iter().copied().collect::<Vec<_>>();
+                removed.sort_unstable();
+                added.sort_unstable();
+                [removed, added]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_moved_within_file() {
+        let left =
+            "fn foo() {\n    call_foo_function();\n}\nfn bar() {\n    call_bar_function();\n}\n";
+        let right =
+            "fn bar() {\n    call_bar_function();\n}\nfn foo() {\n    call_foo_function();\n}\n";
+        assert_eq!(
+            detect(&[[left, right]]),
+            vec![[vec![0, 1, 2], vec![3, 4, 5]]]
+        );
+    }
+
+    #[test]
+    fn test_moved_across_files() {
+        let left = "keep\nmoved_function_call();\nanother_call();\n";
+        let right = "keep\n";
+        assert_eq!(
+            detect(&[
+                [left, right],
+                ["", "another\nmoved_function_call();\nanother_call();\n"]
+            ]),
+            vec![[vec![1, 2], vec![]], [vec![], vec![1, 2]]]
+        );
+    }
+
+    #[test]
+    fn test_small_blocks_are_not_moved() {
+        // Blocks with few alphanumeric characters are likely to match by
+        // accident.
+        let left = "a\n}\nb\n";
+        let right = "b\n}\nc\n";
+        assert_eq!(detect(&[[left, right]]), vec![[vec![], vec![]]]);
+    }
+
+    #[test]
+    fn test_blocks_moved_across_each_other() {
+        let left = "first_block_of_moved_lines();\nsecond_block_of_moved_lines();\n";
+        let right = "second_block_of_moved_lines();\nfirst_block_of_moved_lines();\n";
+        assert_eq!(
+            detect(&[[left, ""], ["", right]]),
+            vec![[vec![0, 1], vec![]], [vec![], vec![0, 1]]]
+        );
+    }
+
+    #[test]
+    fn test_blocks_split_at_hunk_boundary() {
+        // The removed lines belong to different hunks, so they aren't a
+        // contiguous block, and each of them is too small to be moved.
+        let left = "first_short_name\nkeep\nsecond_short_name\n";
+        assert_eq!(
+            detect(&[
+                [left, "keep\n"],
+                ["", "first_short_name\nsecond_short_name\n"]
+            ]),
+            vec![[vec![], vec![]], [vec![], vec![]]]
+        );
+    }
+}
@@ -23,6 +23,7 @@ use thiserror::Error;
 use super::DiffTokenType;
 use super::DiffTokenVec;
 use super::FileContent;
+use super::FunctionMatcher;
 use super::LineCompareMode;
 use super::diff_by_line;
 use super::file_content_for_diff;
@@ -145,6 +146,8 @@ pub enum DiffLineType {
 pub struct UnifiedDiffHunk<'content> {
     pub left_line_range: Range<usize>,
     pub right_line_range: Range<usize>,
+    /// Header of the function enclosing the hunk in the left content.
+    pub function_header: Option<&'content [u8]>,
     pub lines: Vec<(DiffLineType, DiffTokenVec<'content>)>,
 }
 
@@ -174,15 +177,23 @@ impl<'content> UnifiedDiffHunk<'content> {
     }
 }
 
+/// Computes unified diff hunks with `context` lines around the changes.
+///
+/// If `function_matcher` is given, the hunks are annotated with the enclosing
+/// functions. If `function_context` is also true, the hunks are extended to
+/// include the whole functions.
 pub fn unified_diff_hunks<'content>(
     contents: [&'content BStr; 2],
     context: usize,
     options: LineCompareMode,
+    function_matcher: Option<&FunctionMatcher>,
+    function_context: bool,
 ) -> Vec<UnifiedDiffHunk<'content>> {
     let mut hunks = vec![];
     let mut current_hunk = UnifiedDiffHunk {
         left_line_range: 0..0,
         right_line_range: 0..0,
+        function_header: None,
         lines: vec![],
     };
     let diff = diff_by_line(contents, &options);
@@ -194,13 +205,20 @@ pub fn unified_diff_hunks<'content>(
                 // number of skipped lines separately, but the number of the
                 // context lines should match the displayed content.
                 let [_, right] = hunk.contents[..].try_into().unwrap();
+                let (num_after, num_before) = match function_matcher {
+                    Some(matcher) if function_context => {
+                        let (num_leading, num_trailing) = matcher.context_lines(right);
+                        (context.max(num_leading), context.max(num_trailing))
+                    }
+                    _ => (context, context),
+                };
                 let mut lines = right.split_inclusive(|b| *b == b'\n').fuse();
                 if !current_hunk.lines.is_empty() {
                     // The previous hunk line should be either removed/added.
-                    current_hunk.extend_context_lines(lines.by_ref().take(context));
+                    current_hunk.extend_context_lines(lines.by_ref().take(num_after));
                 }
                 let before_lines = if diff_hunks.peek().is_some() {
-                    lines.by_ref().rev().take(context).collect()
+                    lines.by_ref().rev().take(num_before).collect()
                 } else {
                     vec![] // No more hunks
                 };
@@ -214,6 +232,7 @@ pub fn unified_diff_hunks<'content>(
                     current_hunk = UnifiedDiffHunk {
                         left_line_range: left_start..left_start,
                         right_line_range: right_start..right_start,
+                        function_header: None,
                         lines: vec![],
                     };
                 }
@@ -231,5 +250,11 @@ pub fn unified_diff_hunks<'content>(
     if !current_hunk.lines.is_empty() {
         hunks.push(current_hunk);
     }
+    if let Some(matcher) = function_matcher {
+        let headers = matcher.find_headers(contents[0]);
+        for hunk in &mut hunks {
+            hunk.function_header = headers.enclosing(hunk.left_line_range.start);
+        }
+    }
     hunks
 }
@@ -26,6 +26,8 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::task::Poll;
+use std::time::Duration;
+use std::time::SystemTime;
 
 use tempfile::NamedTempFile;
 use tempfile::PersistError;
@@ -242,6 +244,38 @@ pub fn persist_content_addressed_temp_file<P: AsRef<Path>>(
     }
 }
 
+/// Deletes the least recently modified files in the `dir` until at most
+/// `max_files` remain. Files removed concurrently are ignored.
+pub fn prune_least_recently_modified(dir: &Path, max_files: usize) -> io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let mtime = entry.metadata().ok()?.modified().ok()?;
+            Some((mtime, entry.path()))
+        })
+        .collect();
+    if entries.len() <= max_files {
+        return Ok(());
+    }
+    entries.sort_unstable();
+    for (_, path) in &entries[..entries.len() - max_files] {
+        fs::remove_file(path).ok();
+    }
+    Ok(())
+}
+
+/// Updates the mtime of the file at `path` if it was last modified more than
+/// `max_age` ago. This marks a cache entry as recently used without writing to
+/// the file system on every access.
+pub fn touch_if_older_than(path: &Path, max_age: Duration) -> io::Result<()> {
+    let mtime = fs::metadata(path)?.modified()?;
+    let now = SystemTime::now();
+    if now.duration_since(mtime).is_ok_and(|age| age > max_age) {
+        File::options().write(true).open(path)?.set_modified(now)?;
+    }
+    Ok(())
+}
+
 /// Reads from an async source and writes to a sync destination. Does not spawn
 /// a task, so writes will block.
 pub async fn copy_async_to_sync<R: AsyncRead, W: Write + ?Sized>(
@@ -383,6 +417,43 @@ mod tests {
     use super::*;
     use crate::tests::new_temp_dir;
 
+    #[test]
+    fn test_prune_least_recently_modified() {
+        let temp_dir = new_temp_dir();
+        let base_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        for (name, secs) in [("a", 3), ("b", 1), ("c", 2)] {
+            let file = File::create(temp_dir.path().join(name)).unwrap();
+            file.set_modified(base_time + Duration::from_secs(secs))
+                .unwrap();
+        }
+        prune_least_recently_modified(temp_dir.path(), 3).unwrap();
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 3);
+        prune_least_recently_modified(temp_dir.path(), 1).unwrap();
+        let names = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect_vec();
+        assert_eq!(names, ["a"]);
+    }
+
+    #[test]
+    fn test_touch_if_older_than() {
+        let temp_dir = new_temp_dir();
+        let path = temp_dir.path().join("file");
+        let recent_time = SystemTime::now() - Duration::from_secs(10);
+        File::create(&path)
+            .unwrap()
+            .set_modified(recent_time)
+            .unwrap();
+        touch_if_older_than(&path, Duration::from_secs(60)).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().modified().unwrap(),
+            recent_time
+        );
+        touch_if_older_than(&path, Duration::from_secs(1)).unwrap();
+        assert!(fs::metadata(&path).unwrap().modified().unwrap() > recent_time);
+    }
+
     #[test]
     fn test_path_bytes_roundtrip() {
         let bytes = b"ascii";
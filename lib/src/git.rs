@@ -20,20 +20,27 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::default::Default;
 use std::fs::File;
+use std::io::Write as _;
 use std::num::NonZeroU32;
+use std::path::Path;
 use std::path::PathBuf;
+use std::slice;
 use std::sync::Arc;
 
 use bstr::BStr;
 use bstr::BString;
+use bstr::ByteSlice as _;
 use futures::StreamExt as _;
 use gix::refspec::Instruction;
 use itertools::Itertools as _;
 use pollster::FutureExt as _;
+use prost::Message as _;
+use smallvec::SmallVec;
 use thiserror::Error;
 
 use crate::backend::BackendError;
 use crate::backend::BackendResult;
+use crate::backend::ChangeId;
 use crate::backend::CommitId;
 use crate::backend::TreeValue;
 use crate::commit::Commit;
@@ -42,15 +49,21 @@ use crate::file_util::PathError;
 use crate::git_backend::GitBackend;
 use crate::git_subprocess::GitSubprocessContext;
 use crate::git_subprocess::GitSubprocessError;
+use crate::index::Index;
 use crate::index::IndexError;
 use crate::matchers::EverythingMatcher;
 use crate::merged_tree::MergedTree;
 use crate::merged_tree::TreeDiffEntry;
 use crate::object_id::ObjectId as _;
+use crate::op_store;
+use crate::op_store::OpStoreError;
+use crate::op_store::OperationId;
 use crate::op_store::RefTarget;
 use crate::op_store::RefTargetOptionExt as _;
 use crate::op_store::RemoteRef;
 use crate::op_store::RemoteRefState;
+use crate::op_walk;
+use crate::operation::Operation;
 use crate::ref_name::GitRefName;
 use crate::ref_name::GitRefNameBuf;
 use crate::ref_name::RefName;
@@ -61,10 +74,12 @@ use crate::ref_name::RemoteRefSymbol;
 use crate::ref_name::RemoteRefSymbolBuf;
 use crate::refs::BookmarkPushUpdate;
 use crate::repo::MutableRepo;
+use crate::repo::ReadonlyRepo;
 use crate::repo::Repo;
 use crate::repo_path::RepoPath;
 use crate::revset::RevsetExpression;
 use crate::settings::GitSettings;
+use crate::simple_op_store;
 use crate::store::Store;
 use crate::str_util::StringPattern;
 use crate::view::View;
@@ -2571,6 +2586,50 @@ impl<'a> GitFetch<'a> {
     }
 }
 
+/// Resets the Git refs of the remote bookmarks and tags changed by the import
+/// to their old targets. A conflicted old target is reset to one of its added
+/// sides, which will resolve the conflict on the next import.
+///
+/// This can be used to reject the refs fetched by [`GitFetch`] after
+/// inspecting the imported commits. Otherwise, the fetched refs would be
+/// imported again by the next [`import_refs()`]. The changes made to the repo
+/// view aren't reverted.
+pub fn reset_imported_git_refs(
+    store: &Store,
+    import_stats: &GitImportStats,
+) -> Result<(), GitImportError> {
+    let git_repo = get_git_backend(store)?.git_repo();
+    let changed_bookmarks = import_stats
+        .changed_remote_bookmarks
+        .iter()
+        .map(|(symbol, (old_remote_ref, _))| (GitRefKind::Bookmark, symbol, old_remote_ref));
+    let changed_tags = import_stats
+        .changed_remote_tags
+        .iter()
+        .map(|(symbol, (old_remote_ref, _))| (GitRefKind::Tag, symbol, old_remote_ref));
+    for (kind, symbol, old_remote_ref) in changed_bookmarks.chain(changed_tags) {
+        let Some(git_ref_name) = to_git_ref_name(kind, symbol.as_ref()) else {
+            continue;
+        };
+        // A conflicted target can't be represented as a Git ref. Reset it to
+        // one of the previously-imported sides so that the fetched commits
+        // won't be imported.
+        if let Some(id) = old_remote_ref.target.added_ids().next() {
+            git_repo
+                .reference(
+                    git_ref_name.as_str(),
+                    gix::ObjectId::from_bytes_or_panic(id.as_bytes()),
+                    gix::refs::transaction::PreviousValue::Any,
+                    "reset by jj",
+                )
+                .map_err(GitImportError::from_git)?;
+        } else if let Ok(git_ref) = git_repo.find_reference(git_ref_name.as_str()) {
+            git_ref.delete().map_err(GitImportError::from_git)?;
+        }
+    }
+    Ok(())
+}
+
 #[derive(Error, Debug)]
 pub enum GitPushError {
     #[error("No git remote named '{}'", .0.as_symbol())]
@@ -2694,6 +2753,768 @@ pub fn push_updates(
     Ok(push_stats)
 }
 
+/// Git ref under which the operation log is published, both in the local Git
+/// repo and on remotes.
+pub const OPERATIONS_REF: &str = "refs/jj/ops/head";
+
+/// Trailer in the message of an operation commit recording the operation id.
+const OPERATION_ID_TRAILER: &str = "jj-operation-id: ";
+
+/// Returns the local Git ref recording the last known position of the remote's
+/// [`OPERATIONS_REF`].
+pub fn remote_operations_ref(remote: &RemoteName) -> GitRefNameBuf {
+    format!("refs/jj/ops/remotes/{remote}", remote = remote.as_str()).into()
+}
+
+#[derive(Debug, Error)]
+pub enum GitOperationSyncError {
+    #[error("No git remote named '{}'", .0.as_symbol())]
+    NoSuchRemote(RemoteNameBuf),
+    #[error(transparent)]
+    RemoteName(#[from] GitRemoteNameError),
+    #[error(transparent)]
+    Subprocess(#[from] GitSubprocessError),
+    #[error(transparent)]
+    UnexpectedBackend(#[from] UnexpectedGitBackendError),
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    #[error(transparent)]
+    OpStore(#[from] OpStoreError),
+    #[error(transparent)]
+    Index(#[from] IndexError),
+    #[error(transparent)]
+    Push(#[from] GitPushError),
+    #[error("The remote has operations that aren't in the local operation log")]
+    RemoteAhead,
+    #[error("The remote rejected the update of the operation log")]
+    Rejected(Option<String>),
+    #[error("The remote operation log is unrelated to the local one")]
+    UnrelatedHistory,
+    #[error("Invalid operation commit {0}")]
+    InvalidObject(
+        gix::ObjectId,
+        #[source] Box<dyn std::error::Error + Send + Sync>,
+    ),
+    #[error("Failed to access the Git repository")]
+    Git(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Publishes the operation log up to the repo's operation to the remote's
+/// [`OPERATIONS_REF`].
+///
+/// Each operation is stored as a Git commit containing the encoded operation
+/// and view. The heads of the commits referenced by the view are recorded as
+/// parents of a separate commit, which is an additional parent of the
+/// operation commit, so that they are transferred along with the operation.
+///
+/// Only the operations which aren't on the remote yet are exported. The push
+/// is refused if the remote has operations that haven't been pulled by
+/// [`fetch_operations()`] yet. Returns the id of the pushed commit.
+pub fn push_operations(
+    repo: &ReadonlyRepo,
+    git_settings: &GitSettings,
+    remote_name: &RemoteName,
+    callbacks: RemoteCallbacks,
+) -> Result<CommitId, GitOperationSyncError> {
+    validate_remote_name(remote_name)?;
+    let git_backend = get_git_backend(repo.store())?;
+    let git_repo = git_backend.git_repo();
+    if git_repo.try_find_remote(remote_name.as_str()).is_none() {
+        return Err(GitOperationSyncError::NoSuchRemote(remote_name.to_owned()));
+    }
+
+    let remote_ref = remote_operations_ref(remote_name);
+    let old_git_id = find_ref_target(&git_repo, &remote_ref);
+    let mut exported_ops = vec![];
+    let mut exported_git_ids = HashMap::new();
+    if let Some(old_git_id) = old_git_id {
+        let old_op_id = read_operation_commit(&git_repo, old_git_id)?.0;
+        let old_op = match repo.loader().load_operation(&old_op_id) {
+            Ok(op) => op,
+            Err(OpStoreError::ObjectNotFound { .. }) => {
+                return Err(GitOperationSyncError::RemoteAhead);
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let mut remote_only_ops = op_walk::walk_ancestors_range(
+            slice::from_ref(&old_op),
+            slice::from_ref(repo.operation()),
+        );
+        if remote_only_ops.next().transpose()?.is_some() {
+            return Err(GitOperationSyncError::RemoteAhead);
+        }
+        exported_ops.push(old_op);
+        exported_git_ids.insert(old_op_id, old_git_id);
+    }
+    let head_git_id = export_operations(
+        &git_repo,
+        repo.index(),
+        repo.operation(),
+        &exported_ops,
+        exported_git_ids,
+        repo.store().root_commit_id(),
+    )?;
+
+    let head_commit_id = CommitId::from_bytes(head_git_id.as_bytes());
+    let update = GitRefUpdate {
+        qualified_name: OPERATIONS_REF.into(),
+        expected_current_target: old_git_id.map(|id| CommitId::from_bytes(id.as_bytes())),
+        new_target: Some(head_commit_id.clone()),
+    };
+    let push_stats = push_updates(repo, git_settings, remote_name, &[update], callbacks)?;
+    if !push_stats.rejected.is_empty() {
+        // The remote ref moved since the last fetch.
+        return Err(GitOperationSyncError::RemoteAhead);
+    }
+    if let Some((_, reason)) = push_stats.remote_rejected.into_iter().next() {
+        return Err(GitOperationSyncError::Rejected(reason));
+    }
+    set_ref_target(&git_repo, &remote_ref, head_git_id)?;
+    Ok(head_commit_id)
+}
+
+/// Stats from [`fetch_operations()`].
+#[derive(Clone, Debug)]
+pub struct GitOperationFetchStats {
+    /// Head of the remote operation log, or `None` if the remote doesn't have
+    /// one.
+    pub remote_head_id: Option<OperationId>,
+    /// Number of operations that were added to the local operation store.
+    pub num_imported: usize,
+}
+
+/// Fetches the remote's [`OPERATIONS_REF`] and imports the operations into the
+/// local operation store.
+///
+/// The operation heads aren't updated. It's up to the caller to add the remote
+/// head so that it's reconciled with the local operations.
+pub fn fetch_operations(
+    repo: &ReadonlyRepo,
+    git_settings: &GitSettings,
+    remote_name: &RemoteName,
+    mut callbacks: RemoteCallbacks,
+) -> Result<GitOperationFetchStats, GitOperationSyncError> {
+    validate_remote_name(remote_name)?;
+    let git_backend = get_git_backend(repo.store())?;
+    let git_repo = git_backend.git_repo();
+    if git_repo.try_find_remote(remote_name.as_str()).is_none() {
+        return Err(GitOperationSyncError::NoSuchRemote(remote_name.to_owned()));
+    }
+
+    let remote_ref = remote_operations_ref(remote_name);
+    let git_ctx =
+        GitSubprocessContext::from_git_backend(git_backend, &git_settings.executable_path);
+    let refspec = RefSpec::forced(OPERATIONS_REF, remote_ref.as_str());
+    let failing_refspec = git_ctx.spawn_fetch(
+        remote_name,
+        &[refspec],
+        &[],
+        &mut callbacks,
+        None,
+        Some(FetchTagsOverride::NoTags),
+    )?;
+    // The remote ref doesn't exist if nothing has been pushed yet.
+    let Some(head_git_id) = failing_refspec
+        .is_none()
+        .then(|| find_ref_target(&git_repo, &remote_ref))
+        .flatten()
+    else {
+        return Ok(GitOperationFetchStats {
+            remote_head_id: None,
+            num_imported: 0,
+        });
+    };
+
+    let op_store = repo.op_store();
+    let root_op_id = op_store.root_operation_id();
+    let mut new_ops = vec![];
+    let mut remote_head_id = None;
+    let mut visited = HashSet::new();
+    let mut to_visit = vec![head_git_id];
+    while let Some(git_id) = to_visit.pop() {
+        if !visited.insert(git_id) {
+            continue;
+        }
+        let (op_id, operation, view) = read_operation_commit(&git_repo, git_id)?;
+        remote_head_id.get_or_insert_with(|| op_id.clone());
+        match op_store.read_operation(&op_id).block_on() {
+            // The operation and its ancestors have been imported before.
+            Ok(_) => continue,
+            Err(OpStoreError::ObjectNotFound { .. }) => {}
+            Err(err) => return Err(err.into()),
+        }
+        to_visit.extend(operation_parent_commit_ids(
+            &git_repo, git_id, &operation, root_op_id,
+        )?);
+        new_ops.push((git_id, op_id, operation, view));
+    }
+
+    let new_op_ids: HashSet<_> = new_ops.iter().map(|(_, op_id, ..)| op_id).collect();
+    for (_, _, operation, _) in &new_ops {
+        for parent_id in &operation.parents {
+            if parent_id != root_op_id && !new_op_ids.contains(parent_id) {
+                match op_store.read_operation(parent_id).block_on() {
+                    Ok(_) => {}
+                    Err(OpStoreError::ObjectNotFound { .. }) => {
+                        return Err(GitOperationSyncError::UnrelatedHistory);
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+    }
+
+    let mut referenced_commit_ids = HashSet::new();
+    for (git_id, op_id, operation, view) in new_ops.iter().rev() {
+        let view_id = op_store.write_view(view).block_on()?;
+        let written_op_id = op_store.write_operation(operation).block_on()?;
+        if view_id != operation.view_id || written_op_id != *op_id {
+            return Err(GitOperationSyncError::InvalidObject(
+                *git_id,
+                "Operation id doesn't match the content".into(),
+            ));
+        }
+        let view = View::new(view.clone());
+        referenced_commit_ids.extend(view.all_referenced_commit_ids().cloned());
+    }
+    // Record the change ids of commits that only exist in the fetched views.
+    git_backend.import_head_commits(&referenced_commit_ids)?;
+
+    Ok(GitOperationFetchStats {
+        remote_head_id,
+        num_imported: new_ops.len(),
+    })
+}
+
+/// Writes Git commits for the given operation and its ancestors, excluding
+/// the ancestors of the `exported_ops`, and returns the Git commit id of the
+/// `head_op`.
+///
+/// `exported_git_ids` should contain the Git commits of the `exported_ops`.
+/// The Git commits of their ancestors are looked up from the history of these
+/// commits as needed.
+fn export_operations(
+    git_repo: &gix::Repository,
+    index: &dyn Index,
+    head_op: &Operation,
+    exported_ops: &[Operation],
+    mut git_ids: HashMap<OperationId, gix::ObjectId>,
+    root_commit_id: &CommitId,
+) -> Result<gix::ObjectId, GitOperationSyncError> {
+    let to_git_err =
+        |err: Box<dyn std::error::Error + Send + Sync>| GitOperationSyncError::Git(err);
+    let ops: Vec<Operation> =
+        op_walk::walk_ancestors_range(slice::from_ref(head_op), exported_ops).try_collect()?;
+    let root_op_id = head_op.op_store().root_operation_id().clone();
+    // A merge operation may have parents which are ancestors of the exported
+    // operations.
+    let new_op_ids: HashSet<&OperationId> = ops.iter().map(|op| op.id()).collect();
+    let missing_op_ids: HashSet<OperationId> = ops
+        .iter()
+        .flat_map(|op| op.parent_ids())
+        .filter(|id| **id != root_op_id && !new_op_ids.contains(id) && !git_ids.contains_key(id))
+        .cloned()
+        .collect();
+    find_operation_commits(git_repo, &root_op_id, &mut git_ids, missing_op_ids)?;
+
+    // Parents are emitted after their children.
+    for op in ops.iter().rev() {
+        if *op.id() == root_op_id {
+            continue;
+        }
+        let view = op.view()?;
+        let operation_blob = git_repo
+            .write_blob(simple_op_store::encode_operation(op.store_operation()))
+            .map_err(|err| to_git_err(err.into()))?;
+        let view_blob = git_repo
+            .write_blob(simple_op_store::encode_view(view.store_view()))
+            .map_err(|err| to_git_err(err.into()))?;
+        let mut entries = vec![
+            gix::objs::tree::Entry {
+                mode: gix::object::tree::EntryKind::Blob.into(),
+                filename: "operation".into(),
+                oid: operation_blob.detach(),
+            },
+            gix::objs::tree::Entry {
+                mode: gix::object::tree::EntryKind::Blob.into(),
+                filename: "view".into(),
+                oid: view_blob.detach(),
+            },
+        ];
+        entries.sort_unstable();
+        let tree_id = git_repo
+            .write_object(gix::objs::Tree { entries })
+            .map_err(|err| to_git_err(err.into()))?;
+
+        let op_parents = op
+            .parent_ids()
+            .iter()
+            .filter(|id| **id != root_op_id)
+            .map(|id| git_ids[id]);
+        let view_heads = index.heads(
+            &mut view
+                .all_referenced_commit_ids()
+                .filter(|id| *id != root_commit_id),
+        )?;
+        let keep_alive_parent = write_keep_alive_commit(git_repo, &view_heads)?;
+        let metadata = op.metadata();
+        let signature = gix::actor::Signature {
+            name: "jj".into(),
+            email: "jj@localhost".into(),
+            time: gix::date::Time::new(
+                metadata.time.end.timestamp.0.div_euclid(1000),
+                metadata.time.end.tz_offset * 60,
+            ),
+        };
+        let message = format!(
+            "{}\n\n{OPERATION_ID_TRAILER}{}\n",
+            metadata.description.lines().next().unwrap_or_default(),
+            op.id().hex()
+        );
+        let commit = gix::objs::Commit {
+            message: message.into(),
+            tree: tree_id.detach(),
+            author: signature.clone(),
+            committer: signature,
+            encoding: None,
+            parents: op_parents.chain(keep_alive_parent).collect(),
+            extra_headers: vec![],
+        };
+        let git_id = git_repo
+            .write_object(&commit)
+            .map_err(|err| to_git_err(err.into()))?;
+        git_ids.insert(op.id().clone(), git_id.detach());
+    }
+    Ok(git_ids[head_op.id()])
+}
+
+/// Writes a commit whose parents are the given commits, so they are kept
+/// reachable from the operation commit. Returns the commit itself if there's
+/// only one.
+fn write_keep_alive_commit(
+    git_repo: &gix::Repository,
+    commit_ids: &[CommitId],
+) -> Result<Option<gix::ObjectId>, GitOperationSyncError> {
+    let to_git_err =
+        |err: Box<dyn std::error::Error + Send + Sync>| GitOperationSyncError::Git(err);
+    let parents: SmallVec<[gix::ObjectId; 1]> = commit_ids
+        .iter()
+        .map(|id| gix::ObjectId::from_bytes_or_panic(id.as_bytes()))
+        .collect();
+    if parents.len() <= 1 {
+        return Ok(parents.first().copied());
+    }
+    let tree_id = git_repo
+        .write_object(gix::objs::Tree::empty())
+        .map_err(|err| to_git_err(err.into()))?;
+    // Use a fixed signature so the same commit is reused as long as the heads
+    // don't change.
+    let signature = gix::actor::Signature {
+        name: "jj".into(),
+        email: "jj@localhost".into(),
+        time: gix::date::Time::new(0, 0),
+    };
+    let commit = gix::objs::Commit {
+        message: "Keep commits referenced by jj operation\n".into(),
+        tree: tree_id.detach(),
+        author: signature.clone(),
+        committer: signature,
+        encoding: None,
+        parents,
+        extra_headers: vec![],
+    };
+    let git_id = git_repo
+        .write_object(&commit)
+        .map_err(|err| to_git_err(err.into()))?;
+    Ok(Some(git_id.detach()))
+}
+
+/// Looks up the Git commits of the `wanted` operations by walking the history
+/// of the operation commits in `git_ids`, and adds them to `git_ids`.
+fn find_operation_commits(
+    git_repo: &gix::Repository,
+    root_op_id: &OperationId,
+    git_ids: &mut HashMap<OperationId, gix::ObjectId>,
+    mut wanted: HashSet<OperationId>,
+) -> Result<(), GitOperationSyncError> {
+    let mut visited = HashSet::new();
+    let mut to_visit = git_ids.values().copied().collect_vec();
+    while !wanted.is_empty() {
+        let Some(git_id) = to_visit.pop() else {
+            return Err(GitOperationSyncError::UnrelatedHistory);
+        };
+        if !visited.insert(git_id) {
+            continue;
+        }
+        let (op_id, operation, _) = read_operation_commit(git_repo, git_id)?;
+        to_visit.extend(operation_parent_commit_ids(
+            git_repo, git_id, &operation, root_op_id,
+        )?);
+        wanted.remove(&op_id);
+        git_ids.insert(op_id, git_id);
+    }
+    Ok(())
+}
+
+/// Returns the Git commits of the parent operations of the operation commit.
+/// The parents of the commit which aren't operation commits are skipped.
+fn operation_parent_commit_ids(
+    git_repo: &gix::Repository,
+    git_id: gix::ObjectId,
+    operation: &op_store::Operation,
+    root_op_id: &OperationId,
+) -> Result<Vec<gix::ObjectId>, GitOperationSyncError> {
+    let num_op_parents = operation
+        .parents
+        .iter()
+        .filter(|id| *id != root_op_id)
+        .count();
+    let commit = git_repo
+        .find_commit(git_id)
+        .map_err(|err| GitOperationSyncError::Git(err.into()))?;
+    Ok(commit
+        .parent_ids()
+        .take(num_op_parents)
+        .map(|id| id.detach())
+        .collect())
+}
+
+/// Reads the operation and view stored in an operation commit.
+fn read_operation_commit(
+    git_repo: &gix::Repository,
+    git_id: gix::ObjectId,
+) -> Result<(OperationId, op_store::Operation, op_store::View), GitOperationSyncError> {
+    let to_invalid = |err: Box<dyn std::error::Error + Send + Sync>| {
+        GitOperationSyncError::InvalidObject(git_id, err)
+    };
+    let commit = git_repo
+        .find_commit(git_id)
+        .map_err(|err| to_invalid(err.into()))?;
+    let op_id = commit
+        .message_raw_sloppy()
+        .lines()
+        .find_map(|line| line.strip_prefix(OPERATION_ID_TRAILER.as_bytes()))
+        .and_then(|hex| OperationId::try_from_hex(hex.trim()))
+        .ok_or_else(|| to_invalid("Missing operation id".into()))?;
+    let tree = commit.tree().map_err(|err| to_invalid(err.into()))?;
+    let read_blob = |name: &str| {
+        let entry = tree
+            .find_entry(name)
+            .ok_or_else(|| to_invalid(format!("Missing {name}").into()))?;
+        let object = entry.object().map_err(|err| to_invalid(err.into()))?;
+        Ok::<_, GitOperationSyncError>(object.detach().data)
+    };
+    let operation =
+        simple_op_store::decode_operation(&read_blob("operation")?).map_err(to_invalid)?;
+    let view = simple_op_store::decode_view(&read_blob("view")?).map_err(to_invalid)?;
+    Ok((op_id, operation, view))
+}
+
+fn find_ref_target(git_repo: &gix::Repository, name: &GitRefName) -> Option<gix::ObjectId> {
+    let git_ref = git_repo.find_reference(name.as_str()).ok()?;
+    git_ref.inner.target.try_id().map(|id| id.to_owned())
+}
+
+fn set_ref_target(
+    git_repo: &gix::Repository,
+    name: &GitRefName,
+    target: gix::ObjectId,
+) -> Result<(), GitOperationSyncError> {
+    git_repo
+        .reference(
+            name.as_str(),
+            target,
+            gix::refs::transaction::PreviousValue::Any,
+            "push operations",
+        )
+        .map_err(|err| GitOperationSyncError::Git(err.into()))?;
+    Ok(())
+}
+
+/// Git ref in a bundle pointing to the commit that holds the jj metadata of the
+/// bundled commits.
+pub const BUNDLE_METADATA_REF: &str = "refs/jj/bundle";
+
+/// Local Git ref to which the metadata commit is fetched while importing a
+/// bundle.
+const BUNDLE_IMPORT_REF: &str = "refs/jj/bundle-import";
+
+const BUNDLE_SIGNATURE: &[u8] = b"# v2 git bundle\n";
+
+#[derive(Debug, Error)]
+pub enum GitBundleError {
+    #[error(transparent)]
+    RemoteName(#[from] GitRemoteNameError),
+    #[error(transparent)]
+    Subprocess(#[from] GitSubprocessError),
+    #[error(transparent)]
+    UnexpectedBackend(#[from] UnexpectedGitBackendError),
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    #[error(transparent)]
+    Import(#[from] GitImportError),
+    #[error(transparent)]
+    Io(#[from] PathError),
+    #[error("Invalid bundle metadata commit {0}")]
+    InvalidMetadata(
+        gix::ObjectId,
+        #[source] Box<dyn std::error::Error + Send + Sync>,
+    ),
+    #[error("Failed to access the Git repository")]
+    Git(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Stats from [`create_bundle()`].
+#[derive(Clone, Debug)]
+pub struct GitBundleCreateStats {
+    /// Number of commits in the bundle.
+    pub num_commits: usize,
+    /// Local bookmarks included in the bundle, sorted by name.
+    pub bookmarks: Vec<RefNameBuf>,
+}
+
+/// Writes the given commits to a Git bundle file at `path`.
+///
+/// Local bookmarks pointing to the commits are included as `refs/heads/*`.
+/// The change ids of the commits are recorded in a commit at
+/// [`BUNDLE_METADATA_REF`], whose parents are the heads of the bundled commits.
+/// Parents of the commits that aren't bundled become prerequisites, which have
+/// to exist in the repo importing the bundle.
+pub fn create_bundle(
+    repo: &ReadonlyRepo,
+    git_settings: &GitSettings,
+    commits: &[Commit],
+    path: &Path,
+) -> Result<GitBundleCreateStats, GitBundleError> {
+    let git_backend = get_git_backend(repo.store())?;
+    let git_repo = git_backend.git_repo();
+    let to_git_err = |err: Box<dyn std::error::Error + Send + Sync>| GitBundleError::Git(err);
+    let to_git_id = |id: &CommitId| gix::ObjectId::from_bytes_or_panic(id.as_bytes());
+
+    let root_commit_id = repo.store().root_commit_id();
+    let commits = commits
+        .iter()
+        .filter(|commit| commit.id() != root_commit_id)
+        .collect_vec();
+    let commit_ids: HashSet<&CommitId> = commits.iter().map(|commit| commit.id()).collect();
+    let parent_ids: HashSet<&CommitId> = commits
+        .iter()
+        .flat_map(|commit| commit.parent_ids())
+        .collect();
+    let head_ids = commits
+        .iter()
+        .map(|commit| commit.id())
+        .filter(|id| !parent_ids.contains(id))
+        .sorted()
+        .collect_vec();
+    let prerequisites = parent_ids
+        .iter()
+        .copied()
+        .filter(|id| !commit_ids.contains(id) && *id != root_commit_id)
+        .sorted()
+        .cloned()
+        .collect_vec();
+    let bookmarks = repo
+        .view()
+        .local_bookmarks()
+        .filter_map(|(name, target)| {
+            let id = target.as_normal()?;
+            commit_ids.contains(id).then_some((name, id))
+        })
+        .collect_vec();
+
+    let mut entries = vec![];
+    for commit in &commits {
+        let proto = crate::protos::git_store::Commit {
+            change_id: commit.change_id().to_bytes(),
+            ..Default::default()
+        };
+        let blob_id = git_repo
+            .write_blob(proto.encode_to_vec())
+            .map_err(|err| to_git_err(err.into()))?;
+        entries.push(gix::objs::tree::Entry {
+            mode: gix::object::tree::EntryKind::Blob.into(),
+            filename: commit.id().hex().into(),
+            oid: blob_id.detach(),
+        });
+    }
+    entries.sort_unstable();
+    let tree_id = git_repo
+        .write_object(gix::objs::Tree { entries })
+        .map_err(|err| to_git_err(err.into()))?;
+    // Use a fixed signature so the bundle only depends on the bundled commits.
+    let signature = gix::actor::Signature {
+        name: "jj".into(),
+        email: "jj@localhost".into(),
+        time: gix::date::Time::new(0, 0),
+    };
+    let metadata_commit = gix::objs::Commit {
+        message: "jj bundle metadata\n".into(),
+        tree: tree_id.detach(),
+        author: signature.clone(),
+        committer: signature,
+        encoding: None,
+        parents: head_ids.iter().map(|id| to_git_id(id)).collect(),
+        extra_headers: vec![],
+    };
+    let metadata_id = git_repo
+        .write_object(&metadata_commit)
+        .map_err(|err| to_git_err(err.into()))?
+        .detach();
+
+    let mut header = BUNDLE_SIGNATURE.to_vec();
+    for id in &prerequisites {
+        let commit = repo.store().get_commit(id)?;
+        let subject = commit.description().lines().next().unwrap_or_default();
+        header.extend(format!("-{} {subject}\n", id.hex()).into_bytes());
+    }
+    for (name, id) in &bookmarks {
+        header.extend(format!("{} refs/heads/{}\n", id.hex(), name.as_str()).into_bytes());
+    }
+    header.extend(format!("{metadata_id} {BUNDLE_METADATA_REF}\n\n").into_bytes());
+    let mut file = File::create(path).context(path)?;
+    file.write_all(&header).context(path)?;
+
+    let revs = itertools::chain(
+        [metadata_id.to_string()],
+        prerequisites.iter().map(|id| format!("^{}", id.hex())),
+    )
+    .collect_vec();
+    let git_ctx =
+        GitSubprocessContext::from_git_backend(git_backend, &git_settings.executable_path);
+    git_ctx.spawn_pack_objects(&revs, file)?;
+
+    Ok(GitBundleCreateStats {
+        num_commits: commits.len(),
+        bookmarks: bookmarks
+            .into_iter()
+            .map(|(name, _)| name.to_owned())
+            .collect(),
+    })
+}
+
+/// Stats from [`import_bundle()`].
+#[derive(Clone, Debug)]
+pub struct GitBundleImportStats {
+    /// Number of commits recorded in the bundle metadata that were new to the
+    /// repo. This is zero if the bundle wasn't created by [`create_bundle()`].
+    pub num_new_commits: usize,
+    /// Stats from importing the bookmarks of the bundle.
+    pub import_stats: GitImportStats,
+}
+
+/// Fetches the bookmarks in the Git bundle file at `path` as remote bookmarks
+/// of `remote_name`, and imports them into the repo.
+///
+/// The change ids recorded in the bundle metadata are assigned to the commits
+/// that are new to the repo. The heads of the bundled commits are made visible
+/// even if they aren't pointed to by bookmarks.
+pub fn import_bundle(
+    mut_repo: &mut MutableRepo,
+    git_settings: &GitSettings,
+    remote_name: &RemoteName,
+    path: &Path,
+) -> Result<GitBundleImportStats, GitBundleError> {
+    validate_remote_name(remote_name)?;
+    let store = mut_repo.store().clone();
+    let git_backend = get_git_backend(&store)?;
+    let git_repo = git_backend.git_repo();
+    let git_ctx =
+        GitSubprocessContext::from_git_backend(git_backend, &git_settings.executable_path);
+
+    let mut refspecs = vec![
+        RefSpec::forced(
+            "refs/heads/*",
+            format!("refs/remotes/{}/*", remote_name.as_str()),
+        ),
+        RefSpec::forced(BUNDLE_METADATA_REF, BUNDLE_IMPORT_REF),
+    ];
+    // Bundles created by Git don't have the metadata ref.
+    while let Some(failing_refspec) = git_ctx.spawn_fetch_bundle(path, &refspecs)? {
+        tracing::debug!(failing_refspec, "failed to fetch ref from bundle");
+        let num_refspecs = refspecs.len();
+        refspecs.retain(|r| r.source.as_ref() != Some(&failing_refspec));
+        if refspecs.len() == num_refspecs {
+            return Err(GitSubprocessError::External(format!(
+                "Failed to fetch {failing_refspec} from the bundle"
+            ))
+            .into());
+        }
+    }
+
+    let mut head_ids = vec![];
+    let mut change_ids = HashMap::new();
+    if let Some(metadata_id) = find_ref_target(&git_repo, BUNDLE_IMPORT_REF.as_ref()) {
+        (head_ids, change_ids) = read_bundle_metadata(&git_repo, metadata_id)?;
+        let git_ref = git_repo
+            .find_reference(BUNDLE_IMPORT_REF)
+            .map_err(|err| GitBundleError::Git(err.into()))?;
+        git_ref
+            .delete()
+            .map_err(|err| GitBundleError::Git(err.into()))?;
+    }
+    let index = mut_repo.index();
+    let new_ids: Vec<&CommitId> = change_ids
+        .keys()
+        .filter_map(|id| match index.has_id(id) {
+            Ok(false) => Some(Ok(id)),
+            Ok(true) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .try_collect()
+        .map_err(GitImportError::Index)?;
+    let num_new_commits = new_ids.len();
+    git_backend.import_head_commits_with_change_ids(new_ids, &change_ids)?;
+
+    let import_stats = import_some_refs(mut_repo, git_settings, |kind, symbol| {
+        kind == GitRefKind::Bookmark && symbol.remote == remote_name
+    })?;
+    let head_commits: Vec<_> = head_ids
+        .iter()
+        .map(|id| store.get_commit(id))
+        .try_collect()?;
+    mut_repo.add_heads(&head_commits)?;
+
+    Ok(GitBundleImportStats {
+        num_new_commits,
+        import_stats,
+    })
+}
+
+/// Reads the heads and the change ids of the bundled commits from the bundle
+/// metadata commit.
+fn read_bundle_metadata(
+    git_repo: &gix::Repository,
+    git_id: gix::ObjectId,
+) -> Result<(Vec<CommitId>, HashMap<CommitId, ChangeId>), GitBundleError> {
+    let to_invalid = |err: Box<dyn std::error::Error + Send + Sync>| {
+        GitBundleError::InvalidMetadata(git_id, err)
+    };
+    let commit = git_repo
+        .find_commit(git_id)
+        .map_err(|err| to_invalid(err.into()))?;
+    let head_ids = commit
+        .parent_ids()
+        .map(|id| CommitId::from_bytes(id.as_bytes()))
+        .collect();
+    let tree = commit.tree().map_err(|err| to_invalid(err.into()))?;
+    let mut change_ids = HashMap::new();
+    for entry in tree.iter() {
+        let entry = entry.map_err(|err| to_invalid(err.into()))?;
+        let commit_id = entry
+            .filename()
+            .to_str()
+            .ok()
+            .and_then(CommitId::try_from_hex)
+            .ok_or_else(|| to_invalid(format!("Invalid entry {}", entry.filename()).into()))?;
+        let object = entry.object().map_err(|err| to_invalid(err.into()))?;
+        let proto = crate::protos::git_store::Commit::decode(&*object.data)
+            .map_err(|err| to_invalid(err.into()))?;
+        change_ids.insert(commit_id, ChangeId::new(proto.change_id));
+    }
+    Ok((head_ids, change_ids))
+}
+
 #[non_exhaustive]
 #[derive(Default)]
 #[expect(clippy::type_complexity)]
@@ -14,6 +14,7 @@
 
 #![expect(missing_docs)]
 
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::ffi::OsStr;
 use std::fmt::Debug;
@@ -417,6 +418,21 @@ impl GitBackend {
     pub fn import_head_commits<'a>(
         &self,
         head_ids: impl IntoIterator<Item = &'a CommitId>,
+    ) -> BackendResult<()> {
+        self.import_head_commits_with_change_ids(head_ids, &HashMap::new())
+    }
+
+    /// Imports the given commits and ancestors like
+    /// [`import_head_commits()`](Self::import_head_commits), but assigns the
+    /// given `change_ids` instead of the ones derived from the Git commits.
+    ///
+    /// The change ids of commits that have already been imported are
+    /// preserved.
+    #[tracing::instrument(skip_all)]
+    pub fn import_head_commits_with_change_ids<'a>(
+        &self,
+        head_ids: impl IntoIterator<Item = &'a CommitId>,
+        change_ids: &HashMap<CommitId, ChangeId>,
     ) -> BackendResult<()> {
         let head_ids: HashSet<&CommitId> = head_ids
             .into_iter()
@@ -446,6 +462,7 @@ impl GitBackend {
             &mut mut_table,
             &table_lock,
             &head_ids,
+            change_ids,
             self.shallow_root_ids(&locked_repo)?,
         )?;
         self.save_extra_metadata_table(mut_table, &table_lock)
@@ -926,6 +943,7 @@ fn import_extra_metadata_entries_from_heads(
     mut_table: &mut MutableTable,
     _table_lock: &FileLock,
     head_ids: &HashSet<&CommitId>,
+    change_ids: &HashMap<CommitId, ChangeId>,
     shallow_roots: &[CommitId],
 ) -> BackendResult<()> {
     let mut work_ids = head_ids
@@ -941,7 +959,10 @@ fn import_extra_metadata_entries_from_heads(
         // TODO(#1624): Should we read the root tree here and check if it has a
         // `.jjconflict-...` entries? That could happen if the user used `git` to e.g.
         // change the description of a commit with tree-level conflicts.
-        let commit = commit_from_git_without_root_parent(&id, &git_object, is_shallow)?;
+        let mut commit = commit_from_git_without_root_parent(&id, &git_object, is_shallow)?;
+        if let Some(change_id) = change_ids.get(&id) {
+            commit.change_id = change_id.clone();
+        }
         mut_table.add_entry(id.to_bytes(), serialize_extras(&commit));
         work_ids.extend(
             commit
@@ -12,9 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fs::File;
 use std::io;
 use std::io::BufReader;
 use std::io::Read;
+use std::io::Write as _;
 use std::num::NonZeroU32;
 use std::path::Path;
 use std::path::PathBuf;
@@ -206,6 +208,62 @@ impl<'a> GitSubprocessContext<'a> {
         parse_git_fetch_output(output)
     }
 
+    /// Fetch refs from a bundle file
+    ///
+    /// Unlike `spawn_fetch()`, stale refs aren't pruned because the bundle
+    /// may contain only some of the refs.
+    ///
+    /// This returns a fully qualified ref that wasn't fetched successfully
+    pub(crate) fn spawn_fetch_bundle(
+        &self,
+        bundle_path: &Path,
+        refspecs: &[RefSpec],
+    ) -> Result<Option<String>, GitSubprocessError> {
+        if refspecs.is_empty() {
+            return Ok(None);
+        }
+        let mut command = self.create_command();
+        command.stdout(Stdio::piped());
+        command.args(["fetch", "--no-write-fetch-head", "--no-tags", "--"]);
+        command.arg(bundle_path);
+        command.args(refspecs.iter().map(|x| x.to_git_format()));
+
+        let output = wait_with_output(self.spawn_cmd(command)?)?;
+
+        parse_git_fetch_output(output)
+    }
+
+    /// Write a pack of the objects reachable from `revs` to `output`
+    ///
+    /// `revs` are passed to `git pack-objects --revs`, so objects reachable
+    /// from revisions prefixed with `^` are excluded.
+    pub(crate) fn spawn_pack_objects(
+        &self,
+        revs: &[String],
+        output: File,
+    ) -> Result<(), GitSubprocessError> {
+        let mut command = self.create_command();
+        command.stdin(Stdio::piped());
+        command.stdout(output);
+        command.args([
+            "pack-objects",
+            "--stdout",
+            "--thin",
+            "--delta-base-offset",
+            "--revs",
+        ]);
+
+        let mut child = self.spawn_cmd(command)?;
+        let mut stdin = child.stdin.take().expect("stdin should be piped");
+        for rev in revs {
+            writeln!(stdin, "{rev}").map_err(GitSubprocessError::Wait)?;
+        }
+        drop(stdin);
+        let output = wait_with_output(child)?;
+
+        parse_git_pack_objects_output(output)
+    }
+
     /// Prune particular branches
     pub(crate) fn spawn_branch_prune(
         &self,
@@ -427,6 +485,18 @@ fn parse_git_branch_prune_output(output: Output) -> Result<(), GitSubprocessErro
     Err(external_git_error(&output.stderr))
 }
 
+fn parse_git_pack_objects_output(output: Output) -> Result<(), GitSubprocessError> {
+    if output.status.success() {
+        return Ok(());
+    }
+
+    if let Some(option) = parse_unknown_option(&output.stderr) {
+        return Err(GitSubprocessError::UnsupportedGitOption(option));
+    }
+
+    Err(external_git_error(&output.stderr))
+}
+
 fn parse_git_remote_show_output(output: Output) -> Result<Output, GitSubprocessError> {
     if output.status.success() {
         return Ok(output);
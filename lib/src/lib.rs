@@ -37,6 +37,7 @@ pub mod commit_builder;
 pub mod config;
 mod config_resolver;
 pub mod conflicts;
+pub mod content_filter;
 pub mod copies;
 pub mod dag_walk;
 pub mod default_index;
@@ -72,11 +73,17 @@ pub mod lock;
 pub mod matchers;
 pub mod merge;
 pub mod merged_tree;
+pub mod native_backend;
+pub mod network_op_heads_store;
 pub mod object_id;
 pub mod op_heads_store;
 pub mod op_store;
 pub mod op_walk;
 pub mod operation;
+pub mod opset;
+mod opset_parser;
+pub mod patch;
+pub mod patch_id;
 #[expect(missing_docs)]
 pub mod protos;
 pub mod ref_name;
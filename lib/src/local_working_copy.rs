@@ -15,6 +15,7 @@
 #![expect(missing_docs)]
 
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::collections::HashSet;
 use std::error::Error;
 use std::fs;
@@ -37,9 +38,12 @@ use std::sync::Arc;
 use std::sync::OnceLock;
 use std::sync::mpsc::Sender;
 use std::sync::mpsc::channel;
+use std::time::Instant;
 use std::time::UNIX_EPOCH;
 
 use async_trait::async_trait;
+use blake2::Blake2b512;
+use blake2::Digest as _;
 use either::Either;
 use futures::StreamExt as _;
 use itertools::EitherOrBoth;
@@ -48,6 +52,7 @@ use once_cell::unsync::OnceCell;
 use pollster::FutureExt as _;
 use prost::Message as _;
 use rayon::iter::IntoParallelIterator as _;
+use rayon::iter::ParallelDrainRange as _;
 use rayon::prelude::IndexedParallelIterator as _;
 use rayon::prelude::ParallelIterator as _;
 use tempfile::NamedTempFile;
@@ -76,11 +81,14 @@ use crate::conflicts::MaterializedTreeValue;
 use crate::conflicts::choose_materialized_conflict_marker_len;
 use crate::conflicts::materialize_merge_result_to_bytes;
 use crate::conflicts::materialize_tree_value;
+use crate::content_filter::ContentFilterSettings;
+use crate::content_filter::ContentFilters;
 pub use crate::eol::EolConversionMode;
 use crate::eol::TargetEolStrategy;
 use crate::file_util::BlockingAsyncReader;
 use crate::file_util::check_symlink_support;
 use crate::file_util::copy_async_to_sync;
+use crate::file_util::persist_content_addressed_temp_file;
 use crate::file_util::persist_temp_file;
 use crate::file_util::try_symlink;
 use crate::fsmonitor::FsmonitorSettings;
@@ -89,6 +97,7 @@ use crate::fsmonitor::WatchmanConfig;
 #[cfg(feature = "watchman")]
 use crate::fsmonitor::watchman;
 use crate::gitignore::GitIgnoreFile;
+use crate::hex_util;
 use crate::lock::FileLock;
 use crate::matchers::DifferenceMatcher;
 use crate::matchers::EverythingMatcher;
@@ -121,6 +130,7 @@ use crate::working_copy::SnapshotError;
 use crate::working_copy::SnapshotOptions;
 use crate::working_copy::SnapshotProgress;
 use crate::working_copy::SnapshotStats;
+use crate::working_copy::SnapshotTimings;
 use crate::working_copy::UntrackedReason;
 use crate::working_copy::WorkingCopy;
 use crate::working_copy::WorkingCopyFactory;
@@ -235,11 +245,17 @@ impl FileState {
 #[derive(Clone, Debug)]
 struct FileStatesMap {
     data: Vec<crate::protos::local_working_copy::FileStateEntry>,
+    /// Paths changed since the map was last loaded or saved. `None` if all
+    /// entries have to be rewritten.
+    changed_paths: Option<BTreeSet<RepoPathBuf>>,
 }
 
 impl FileStatesMap {
     fn new() -> Self {
-        Self { data: Vec::new() }
+        Self {
+            data: Vec::new(),
+            changed_paths: None,
+        }
     }
 
     fn from_proto(
@@ -254,7 +270,10 @@ impl FileStatesMap {
             });
         }
         debug_assert!(is_file_state_entries_proto_unique_and_sorted(&data));
-        Self { data }
+        Self {
+            data,
+            changed_paths: Some(BTreeSet::new()),
+        }
     }
 
     /// Merges changed and deleted entries into this map. The changed entries
@@ -271,6 +290,14 @@ impl FileStatesMap {
             changed_file_states.is_sorted_by(|(path1, _), (path2, _)| path1 < path2),
             "changed_file_states must be sorted and have no duplicates"
         );
+        if let Some(changed_paths) = &mut self.changed_paths {
+            changed_paths.extend(changed_file_states.iter().map(|(path, _)| path.clone()));
+            changed_paths.extend(deleted_files.iter().cloned());
+            // Everything will be rewritten anyway.
+            if changed_paths.len() > self.data.len() {
+                self.changed_paths = None;
+            }
+        }
         self.data = itertools::merge_join_by(
             mem::take(&mut self.data),
             changed_file_states,
@@ -294,8 +321,52 @@ impl FileStatesMap {
         .collect();
     }
 
+    /// Applies the entries of a tree state segment on top of this map.
+    fn apply_segment(&mut self, segment: crate::protos::local_working_copy::FileStateSegment) {
+        let crate::protos::local_working_copy::FileStateSegment {
+            file_states,
+            removed_paths,
+        } = segment;
+        debug_assert!(is_file_state_entries_proto_unique_and_sorted(&file_states));
+        let removed_paths: HashSet<String> = removed_paths.into_iter().collect();
+        self.data = itertools::merge_join_by(
+            mem::take(&mut self.data),
+            file_states,
+            |old_entry, new_entry| {
+                let old_path = RepoPath::from_internal_string(&old_entry.path).unwrap();
+                let new_path = RepoPath::from_internal_string(&new_entry.path).unwrap();
+                old_path.cmp(new_path)
+            },
+        )
+        .filter_map(|diff| match diff {
+            EitherOrBoth::Both(_, entry) | EitherOrBoth::Right(entry) => Some(entry),
+            EitherOrBoth::Left(entry) => (!removed_paths.contains(&entry.path)).then_some(entry),
+        })
+        .collect();
+    }
+
+    /// Builds a segment containing the current states of the given paths.
+    /// Paths not in this map are recorded as removed.
+    fn to_segment<'a>(
+        &self,
+        paths: impl IntoIterator<Item = &'a RepoPath>,
+    ) -> crate::protos::local_working_copy::FileStateSegment {
+        let file_states = self.all();
+        let mut segment = crate::protos::local_working_copy::FileStateSegment::default();
+        for path in paths {
+            match file_states.exact_position(path) {
+                Some(pos) => segment.file_states.push(self.data[pos].clone()),
+                None => segment
+                    .removed_paths
+                    .push(path.as_internal_file_string().to_owned()),
+            }
+        }
+        segment
+    }
+
     fn clear(&mut self) {
         self.data.clear();
+        self.changed_paths = None;
     }
 
     /// Returns read-only map containing all file states.
@@ -662,6 +733,10 @@ fn can_create_new_file(disk_path: &Path) -> Result<bool, CheckoutError> {
 
 const RESERVED_DIR_NAMES: &[&str] = &[".git", ".jj"];
 
+/// Maximum number of materialized files to buffer before writing them to disk
+/// in parallel.
+const CHECKOUT_BATCH_SIZE: usize = 1000;
+
 fn same_file_handle_from_path(disk_path: &Path) -> io::Result<Option<same_file::Handle>> {
     match same_file::Handle::from_path(disk_path) {
         Ok(handle) => Ok(Some(handle)),
@@ -799,6 +874,37 @@ struct FsmonitorMatcher {
     watchman_clock: Option<crate::protos::local_working_copy::WatchmanClock>,
 }
 
+/// On-disk format of the tree state.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TreeStateFormat {
+    /// All file states are rewritten to a single file on every update.
+    #[default]
+    SingleFile,
+    /// File states are stored in segment files. Only the changed file states
+    /// are written on update, and segments are squashed as they accumulate.
+    Segmented,
+}
+
+/// Summary of a segment file of the tree state.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileStateSegmentInfo {
+    /// Name of the segment file.
+    pub name: String,
+    /// Number of added or updated entries.
+    pub num_entries: usize,
+    /// Number of removed paths.
+    pub num_removed_paths: usize,
+}
+
+/// Segment of the tree state loaded or written by this process.
+#[derive(Clone, Debug)]
+struct FileStateSegmentRef {
+    info: FileStateSegmentInfo,
+    /// Paths updated by this segment. Empty for the base segment.
+    paths: Vec<RepoPathBuf>,
+}
+
 /// Settings specific to the tree state of the [`LocalWorkingCopy`] backend.
 #[derive(Clone, Debug)]
 pub struct TreeStateSettings {
@@ -811,6 +917,14 @@ pub struct TreeStateSettings {
     pub eol_conversion_mode: EolConversionMode,
     /// The fsmonitor (e.g. Watchman) to use, if any.
     pub fsmonitor_settings: FsmonitorSettings,
+    /// Clean and smudge filters to apply to matching files.
+    pub content_filters: ContentFilterSettings,
+    /// Maximum number of threads used to scan and update the working copy. If
+    /// 0, the number of available CPUs is used.
+    pub num_threads: usize,
+    /// Format to write the tree state in. The tree state is migrated to this
+    /// format the next time it's saved.
+    pub tree_state_format: TreeStateFormat,
 }
 
 impl TreeStateSettings {
@@ -820,6 +934,9 @@ impl TreeStateSettings {
             conflict_marker_style: user_settings.get("ui.conflict-marker-style")?,
             eol_conversion_mode: EolConversionMode::try_from_settings(user_settings)?,
             fsmonitor_settings: FsmonitorSettings::from_settings(user_settings)?,
+            content_filters: ContentFilterSettings::try_from_settings(user_settings)?,
+            num_threads: user_settings.get("working-copy.threads")?,
+            tree_state_format: user_settings.get("working-copy.tree-state-format")?,
         })
     }
 }
@@ -843,6 +960,13 @@ pub struct TreeState {
     conflict_marker_style: ConflictMarkerStyle,
     fsmonitor_settings: FsmonitorSettings,
     target_eol_strategy: TargetEolStrategy,
+    content_filters: ContentFilters,
+    num_threads: usize,
+    tree_state_format: TreeStateFormat,
+    /// Segments the file states were read from or last written to, from the
+    /// base segment to the most recent one. Empty if the file states are
+    /// stored in the tree state file.
+    file_state_segments: Vec<FileStateSegmentRef>,
 }
 
 #[derive(Debug, Error)]
@@ -902,9 +1026,13 @@ impl TreeState {
             conflict_marker_style,
             eol_conversion_mode,
             ref fsmonitor_settings,
+            ref content_filters,
+            num_threads,
+            tree_state_format,
         }: &TreeStateSettings,
     ) -> Self {
         let tree_id = store.empty_merged_tree_id();
+        let content_filters = ContentFilters::new(content_filters, &working_copy_path, &state_path);
         Self {
             store,
             working_copy_path,
@@ -918,6 +1046,10 @@ impl TreeState {
             conflict_marker_style,
             fsmonitor_settings: fsmonitor_settings.clone(),
             target_eol_strategy: TargetEolStrategy::new(eol_conversion_mode),
+            content_filters,
+            num_threads,
+            tree_state_format,
+            file_state_segments: vec![],
         }
     }
 
@@ -946,6 +1078,24 @@ impl TreeState {
         Ok(wc)
     }
 
+    /// Runs `op` in a thread pool bounded by the configured number of threads.
+    /// Parallel iterators and scopes within `op` will use that pool.
+    fn install_thread_pool<R: Send>(
+        &self,
+        op: impl FnOnce() -> R + Send,
+    ) -> Result<R, rayon::ThreadPoolBuildError> {
+        if self.num_threads == 0 {
+            return Ok(op());
+        }
+        Ok(self.build_thread_pool()?.install(op))
+    }
+
+    fn build_thread_pool(&self) -> Result<rayon::ThreadPool, rayon::ThreadPoolBuildError> {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.num_threads)
+            .build()
+    }
+
     fn update_own_mtime(&mut self) {
         if let Ok(metadata) = self.state_path.join("tree_state").symlink_metadata() {
             self.own_mtime = mtime_from_metadata(&metadata);
@@ -979,13 +1129,175 @@ impl TreeState {
                 .collect();
             self.tree_id = MergedTreeId::new(tree_ids_builder.build());
         }
-        self.file_states =
-            FileStatesMap::from_proto(proto.file_states, proto.is_file_states_sorted);
+        if proto.file_state_segments.is_empty() {
+            self.file_states =
+                FileStatesMap::from_proto(proto.file_states, proto.is_file_states_sorted);
+        } else {
+            self.read_file_state_segments(&proto.file_state_segments)?;
+        }
         self.sparse_patterns = sparse_patterns_from_proto(proto.sparse_patterns.as_ref());
         self.watchman_clock = proto.watchman_clock;
         Ok(())
     }
 
+    fn file_state_segments_dir(&self) -> PathBuf {
+        self.state_path.join("tree_state_segments")
+    }
+
+    fn read_file_state_segments(&mut self, names: &[String]) -> Result<(), TreeStateError> {
+        let segments_dir = self.file_state_segments_dir();
+        let mut file_states = FileStatesMap::new();
+        let mut segments = Vec::with_capacity(names.len());
+        for name in names {
+            let segment_path = segments_dir.join(name);
+            let buf = fs::read(&segment_path).map_err(|source| TreeStateError::ReadTreeState {
+                path: segment_path.clone(),
+                source,
+            })?;
+            let segment = crate::protos::local_working_copy::FileStateSegment::decode(&*buf)
+                .map_err(|source| TreeStateError::DecodeTreeState {
+                    path: segment_path.clone(),
+                    source,
+                })?;
+            let info = FileStateSegmentInfo {
+                name: name.clone(),
+                num_entries: segment.file_states.len(),
+                num_removed_paths: segment.removed_paths.len(),
+            };
+            let paths = if segments.is_empty() {
+                vec![]
+            } else {
+                segment
+                    .file_states
+                    .iter()
+                    .map(|entry| &entry.path)
+                    .chain(&segment.removed_paths)
+                    .map(|path| RepoPathBuf::from_internal_string(path).unwrap())
+                    .collect()
+            };
+            file_states.apply_segment(segment);
+            segments.push(FileStateSegmentRef { info, paths });
+        }
+        file_states.changed_paths = Some(BTreeSet::new());
+        self.file_states = file_states;
+        self.file_state_segments = segments;
+        Ok(())
+    }
+
+    /// Writes the changed file states to a new segment file, and returns the
+    /// names of the segments to be recorded in the tree state file.
+    fn write_file_state_segments(&mut self) -> Result<Vec<String>, TreeStateError> {
+        let mut segments = self.file_state_segments.clone();
+        let mut changed_paths = match &self.file_states.changed_paths {
+            Some(paths) if !segments.is_empty() => Some(paths.clone()),
+            _ => None,
+        };
+        // Squash the recent segments if they aren't much larger than the new
+        // one, so there are O(log n) segments.
+        if let Some(paths) = &mut changed_paths
+            && !paths.is_empty()
+        {
+            while segments.len() > 1 && 2 * paths.len() >= segments.last().unwrap().paths.len() {
+                paths.extend(segments.pop().unwrap().paths);
+            }
+            if segments.len() == 1 && 2 * paths.len() >= segments[0].info.num_entries {
+                changed_paths = None;
+            }
+        }
+        let new_segment = match changed_paths {
+            Some(paths) if paths.is_empty() => None,
+            Some(paths) => {
+                let segment = self.file_states.to_segment(paths.iter().map(AsRef::as_ref));
+                Some((segment, paths.into_iter().collect()))
+            }
+            None => {
+                segments.clear();
+                let segment = crate::protos::local_working_copy::FileStateSegment {
+                    file_states: self.file_states.data.clone(),
+                    removed_paths: vec![],
+                };
+                Some((segment, vec![]))
+            }
+        };
+        if let Some((segment, paths)) = new_segment {
+            let info = self.write_file_state_segment(&segment)?;
+            segments.push(FileStateSegmentRef { info, paths });
+        }
+        let names = segments
+            .iter()
+            .map(|segment| segment.info.name.clone())
+            .collect();
+        self.file_state_segments = segments;
+        Ok(names)
+    }
+
+    fn write_file_state_segment(
+        &self,
+        segment: &crate::protos::local_working_copy::FileStateSegment,
+    ) -> Result<FileStateSegmentInfo, TreeStateError> {
+        let segments_dir = self.file_state_segments_dir();
+        let wrap_write_err = |source| TreeStateError::WriteTreeState {
+            path: segments_dir.clone(),
+            source,
+        };
+        fs::create_dir_all(&segments_dir).map_err(wrap_write_err)?;
+        let buf = segment.encode_to_vec();
+        let name = hex_util::encode_hex(&Blake2b512::digest(&buf));
+        let mut temp_file = NamedTempFile::new_in(&segments_dir).map_err(wrap_write_err)?;
+        temp_file.write_all(&buf).map_err(wrap_write_err)?;
+        let target_path = segments_dir.join(&name);
+        persist_content_addressed_temp_file(temp_file, &target_path).map_err(|source| {
+            TreeStateError::PersistTreeState {
+                path: target_path.clone(),
+                source,
+            }
+        })?;
+        Ok(FileStateSegmentInfo {
+            name,
+            num_entries: segment.file_states.len(),
+            num_removed_paths: segment.removed_paths.len(),
+        })
+    }
+
+    /// Removes segment files that are referenced by neither the current nor
+    /// the previous tree state. Segments of the previous tree state are kept
+    /// so that concurrent readers of it can still load them.
+    fn remove_unused_file_state_segments(&self, previous_names: &[String]) {
+        let Ok(dir_entries) = self.file_state_segments_dir().read_dir() else {
+            return;
+        };
+        let used_names: HashSet<&str> = self
+            .file_state_segments
+            .iter()
+            .map(|segment| segment.info.name.as_str())
+            .chain(previous_names.iter().map(String::as_str))
+            .collect();
+        for entry in dir_entries.flatten() {
+            let file_name = entry.file_name();
+            if file_name
+                .to_str()
+                .is_some_and(|name| !used_names.contains(name))
+            {
+                // It's fine if the file can't be removed. It will be retried
+                // next time.
+                fs::remove_file(entry.path()).ok();
+            }
+        }
+    }
+
+    /// Returns true if the tree state was read in a different format than the
+    /// configured one.
+    fn is_format_outdated(&self) -> bool {
+        let is_segmented = !self.file_state_segments.is_empty();
+        is_segmented != (self.tree_state_format == TreeStateFormat::Segmented)
+    }
+
+    /// Returns the segments the file states are stored in. Empty if the file
+    /// states are stored in the tree state file.
+    pub fn file_state_segments(&self) -> impl Iterator<Item = &FileStateSegmentInfo> {
+        self.file_state_segments.iter().map(|segment| &segment.info)
+    }
+
     #[expect(clippy::assigning_clones, clippy::field_reassign_with_default)]
     pub fn save(&mut self) -> Result<(), TreeStateError> {
         let mut proto: crate::protos::local_working_copy::TreeState = Default::default();
@@ -995,9 +1307,22 @@ impl TreeState {
             .iter()
             .map(|id| id.to_bytes())
             .collect();
-        proto.file_states = self.file_states.data.clone();
-        // `FileStatesMap` is guaranteed to be sorted.
-        proto.is_file_states_sorted = true;
+        let previous_segment_names = self
+            .file_state_segments
+            .iter()
+            .map(|segment| segment.info.name.clone())
+            .collect_vec();
+        match self.tree_state_format {
+            TreeStateFormat::SingleFile => {
+                proto.file_states = self.file_states.data.clone();
+                // `FileStatesMap` is guaranteed to be sorted.
+                proto.is_file_states_sorted = true;
+                self.file_state_segments.clear();
+            }
+            TreeStateFormat::Segmented => {
+                proto.file_state_segments = self.write_file_state_segments()?;
+            }
+        }
         let mut sparse_patterns = crate::protos::local_working_copy::SparsePatterns::default();
         for path in &self.sparse_patterns {
             sparse_patterns
@@ -1028,6 +1353,8 @@ impl TreeState {
                 source,
             }
         })?;
+        self.file_states.changed_paths = Some(BTreeSet::new());
+        self.remove_unused_file_state_segments(&previous_segment_names);
         Ok(())
     }
 
@@ -1087,17 +1414,21 @@ impl TreeState {
             ref base_ignores,
             progress,
             start_tracking_matcher,
-            max_new_file_size,
+            max_new_file_size: _,
+            max_new_file_size_overrides: _,
         } = options;
 
         let sparse_matcher = self.sparse_matcher();
 
         let fsmonitor_clock_needs_save = self.fsmonitor_settings != FsmonitorSettings::None;
         let mut is_dirty = fsmonitor_clock_needs_save;
+        let mut timings = SnapshotTimings::default();
+        let start_time = Instant::now();
         let FsmonitorMatcher {
             matcher: fsmonitor_matcher,
             watchman_clock,
         } = self.make_fsmonitor_matcher(&self.fsmonitor_settings)?;
+        timings.fsmonitor = start_time.elapsed();
         let fsmonitor_matcher = match fsmonitor_matcher.as_ref() {
             None => &EverythingMatcher,
             Some(fsmonitor_matcher) => fsmonitor_matcher.as_ref(),
@@ -1115,7 +1446,8 @@ impl TreeState {
         let (untracked_paths_tx, untracked_paths_rx) = channel();
         let (deleted_files_tx, deleted_files_rx) = channel();
 
-        trace_span!("traverse filesystem").in_scope(|| -> Result<(), SnapshotError> {
+        let start_time = Instant::now();
+        let num_threads = trace_span!("traverse filesystem").in_scope(|| {
             let snapshotter = FileSnapshotter {
                 tree_state: self,
                 current_tree: &self.current_tree()?,
@@ -1128,7 +1460,7 @@ impl TreeState {
                 deleted_files_tx,
                 error: OnceLock::new(),
                 progress,
-                max_new_file_size,
+                options,
             };
             let directory_to_visit = DirectoryToVisit {
                 dir: RepoPathBuf::root(),
@@ -1136,18 +1468,28 @@ impl TreeState {
                 git_ignore: base_ignores.clone(),
                 file_states: self.file_states.all(),
             };
-            // Here we use scope as a queue of per-directory jobs.
-            rayon::scope(|scope| {
-                snapshotter.spawn_ok(scope, |scope| {
-                    snapshotter.visit_directory(directory_to_visit, scope)
-                });
-            });
-            snapshotter.into_result()
+            let num_threads = self
+                .install_thread_pool(|| {
+                    // Here we use scope as a queue of per-directory jobs.
+                    rayon::scope(|scope| {
+                        snapshotter.spawn_ok(scope, |scope| {
+                            snapshotter.visit_directory(directory_to_visit, scope)
+                        });
+                    });
+                    rayon::current_num_threads()
+                })
+                .map_err(|err| SnapshotError::Other {
+                    message: "Failed to create thread pool".to_string(),
+                    err: err.into(),
+                })?;
+            snapshotter.into_result()?;
+            Ok::<_, SnapshotError>(num_threads)
         })?;
+        timings.traverse_filesystem = start_time.elapsed();
+        self.content_filters.prune_cache();
 
-        let stats = SnapshotStats {
-            untracked_paths: untracked_paths_rx.into_iter().collect(),
-        };
+        let start_time = Instant::now();
+        let untracked_paths = untracked_paths_rx.into_iter().collect();
         let mut tree_builder = MergedTreeBuilder::new(self.tree_id.clone());
         trace_span!("process tree entries").in_scope(|| {
             for (path, tree_values) in &tree_entries_rx {
@@ -1177,6 +1519,12 @@ impl TreeState {
             self.tree_id = new_tree_id;
             Ok(())
         })?;
+        timings.write_tree = start_time.elapsed();
+        let stats = SnapshotStats {
+            untracked_paths,
+            num_threads,
+            timings,
+        };
         if cfg!(debug_assertions) {
             let tree = self.current_tree().unwrap();
             let tree_paths: HashSet<_> = tree
@@ -1275,7 +1623,7 @@ struct FileSnapshotter<'a> {
     deleted_files_tx: Sender<RepoPathBuf>,
     error: OnceLock<SnapshotError>,
     progress: Option<&'a SnapshotProgress<'a>>,
-    max_new_file_size: u64,
+    options: &'a SnapshotOptions<'a>,
 }
 
 impl FileSnapshotter<'_> {
@@ -1417,11 +1765,12 @@ impl FileSnapshotter<'_> {
                     message: format!("Failed to stat file {}", entry.path().display()),
                     err: err.into(),
                 })?;
-                if maybe_current_file_state.is_none() && metadata.len() > self.max_new_file_size {
+                let max_new_file_size = self.options.max_new_file_size_for(&path);
+                if maybe_current_file_state.is_none() && metadata.len() > max_new_file_size {
                     // Leave the large file untracked
                     let reason = UntrackedReason::FileTooLarge {
                         size: metadata.len(),
-                        max_size: self.max_new_file_size,
+                        max_size: max_new_file_size,
                     };
                     self.untracked_paths_tx.send((path, reason)).ok();
                     Ok(None)
@@ -1731,7 +2080,26 @@ impl FileSnapshotter<'_> {
                 message: "Failed to convert the EOL".to_string(),
                 err: err.into(),
             })?;
-        Ok(self.store().write_file(path, &mut contents).await?)
+        if !self.tree_state.content_filters.has_clean_filter(path) {
+            return Ok(self.store().write_file(path, &mut contents).await?);
+        }
+        let mut buf = vec![];
+        contents
+            .read_to_end(&mut buf)
+            .await
+            .map_err(|err| SnapshotError::Other {
+                message: format!("Failed to read file {}", disk_path.display()),
+                err: err.into(),
+            })?;
+        let buf = self
+            .tree_state
+            .content_filters
+            .clean(path, buf)
+            .map_err(|err| SnapshotError::Other {
+                message: format!("Failed to filter file {}", disk_path.display()),
+                err: err.into(),
+            })?;
+        Ok(self.store().write_file(path, &mut buf.as_slice()).await?)
     }
 
     async fn write_symlink_to_store(
@@ -1938,6 +2306,9 @@ impl TreeState {
         };
         let mut changed_file_states = Vec::new();
         let mut deleted_files = HashSet::new();
+        let mut pending_writes = Vec::new();
+        // Built on first use, and shared by all batches of pending writes.
+        let mut thread_pool = None;
         let mut diff_stream = old_tree
             .diff_stream_for_file_system(new_tree, matcher)
             .map(async |TreeDiffEntry { path, values }| match values {
@@ -1991,8 +2362,12 @@ impl TreeState {
             }
 
             // TODO: Check that the file has not changed before overwriting/removing it.
-            let file_state = match after {
+            match after {
                 MaterializedTreeValue::Absent | MaterializedTreeValue::AccessDenied(_) => {
+                    // Pending files have to be written before removing empty
+                    // parent directories.
+                    changed_file_states
+                        .extend(self.write_pending_files(&mut pending_writes, &mut thread_pool)?);
                     let mut parent_dir = disk_path.parent().unwrap();
                     loop {
                         if fs::remove_dir(parent_dir).is_err() {
@@ -2001,60 +2376,128 @@ impl TreeState {
                         parent_dir = parent_dir.parent().unwrap();
                     }
                     deleted_files.insert(path);
-                    continue;
-                }
-                MaterializedTreeValue::File(file) => {
-                    self.write_file(&disk_path, file.reader, file.executable, true)
-                        .await?
                 }
-                MaterializedTreeValue::Symlink { id: _, target } => {
-                    if self.symlink_support {
-                        self.write_symlink(&disk_path, target)?
-                    } else {
-                        self.write_file(&disk_path, target.as_bytes(), false, false)
-                            .await?
+                after => {
+                    pending_writes.push((path, disk_path, after));
+                    if pending_writes.len() >= CHECKOUT_BATCH_SIZE {
+                        changed_file_states.extend(
+                            self.write_pending_files(&mut pending_writes, &mut thread_pool)?,
+                        );
                     }
                 }
-                MaterializedTreeValue::GitSubmodule(_) => {
-                    eprintln!("ignoring git submodule at {path:?}");
-                    FileState::for_gitsubmodule()
-                }
-                MaterializedTreeValue::Tree(_) => {
-                    panic!("unexpected tree entry in diff at {path:?}");
-                }
-                MaterializedTreeValue::FileConflict(file) => {
-                    let conflict_marker_len =
-                        choose_materialized_conflict_marker_len(&file.contents);
-                    let options = ConflictMaterializeOptions {
-                        marker_style: self.conflict_marker_style,
-                        marker_len: Some(conflict_marker_len),
-                        merge: self.store.merge_options().clone(),
-                    };
-                    let contents = materialize_merge_result_to_bytes(&file.contents, &options);
-                    let mut file_state = self
-                        .write_conflict(&disk_path, &contents, file.executable.unwrap_or(false))
-                        .await?;
-                    file_state.materialized_conflict_data = Some(MaterializedConflictData {
-                        conflict_marker_len: conflict_marker_len.try_into().unwrap_or(u32::MAX),
-                    });
-                    file_state
-                }
-                MaterializedTreeValue::OtherConflict { id } => {
-                    // Unless all terms are regular files, we can't do much
-                    // better than trying to describe the merge.
-                    let contents = id.describe();
-                    let executable = false;
-                    self.write_conflict(&disk_path, contents.as_bytes(), executable)
-                        .await?
-                }
-            };
-            changed_file_states.push((path, file_state));
+            }
         }
+        changed_file_states
+            .extend(self.write_pending_files(&mut pending_writes, &mut thread_pool)?);
+        changed_file_states.sort_unstable_by(|(path1, _), (path2, _)| path1.cmp(path2));
         self.file_states
             .merge_in(changed_file_states, &deleted_files);
+        self.content_filters.prune_cache();
         Ok(stats)
     }
 
+    /// Writes the pending files in parallel, and returns their file states in
+    /// the same order.
+    fn write_pending_files(
+        &self,
+        pending_writes: &mut Vec<(RepoPathBuf, PathBuf, MaterializedTreeValue)>,
+        thread_pool: &mut Option<rayon::ThreadPool>,
+    ) -> Result<Vec<(RepoPathBuf, FileState)>, CheckoutError> {
+        let write = |(path, disk_path, value): (RepoPathBuf, PathBuf, MaterializedTreeValue)| {
+            let file_state = self
+                .write_materialized_value(&path, &disk_path, value)
+                .block_on()?;
+            Ok((path, file_state))
+        };
+        if pending_writes.len() <= 1 {
+            return pending_writes.drain(..).map(write).collect();
+        }
+        if self.num_threads == 0 {
+            return pending_writes.par_drain(..).map(write).collect();
+        }
+        let thread_pool = match thread_pool {
+            Some(thread_pool) => thread_pool,
+            None => thread_pool.insert(self.build_thread_pool().map_err(|err| {
+                CheckoutError::Other {
+                    message: "Failed to create thread pool".to_string(),
+                    err: err.into(),
+                }
+            })?),
+        };
+        thread_pool.install(|| pending_writes.par_drain(..).map(write).collect())
+    }
+
+    async fn write_materialized_value(
+        &self,
+        path: &RepoPath,
+        disk_path: &Path,
+        value: MaterializedTreeValue,
+    ) -> Result<FileState, CheckoutError> {
+        let file_state = match value {
+            MaterializedTreeValue::Absent | MaterializedTreeValue::AccessDenied(_) => {
+                panic!("absent entry should have been removed at {path:?}");
+            }
+            MaterializedTreeValue::File(mut file)
+                if self.content_filters.has_smudge_filter(path) =>
+            {
+                let contents = file.read_all(path).await?;
+                let contents = self
+                    .content_filters
+                    .smudge(path, &file.id, contents)
+                    .map_err(|err| CheckoutError::Other {
+                        message: format!("Failed to filter file {}", disk_path.display()),
+                        err: err.into(),
+                    })?;
+                self.write_file(disk_path, contents.as_slice(), file.executable, true)
+                    .await?
+            }
+            MaterializedTreeValue::File(file) => {
+                self.write_file(disk_path, file.reader, file.executable, true)
+                    .await?
+            }
+            MaterializedTreeValue::Symlink { id: _, target } => {
+                if self.symlink_support {
+                    self.write_symlink(disk_path, target)?
+                } else {
+                    self.write_file(disk_path, target.as_bytes(), false, false)
+                        .await?
+                }
+            }
+            MaterializedTreeValue::GitSubmodule(_) => {
+                eprintln!("ignoring git submodule at {path:?}");
+                FileState::for_gitsubmodule()
+            }
+            MaterializedTreeValue::Tree(_) => {
+                panic!("unexpected tree entry in diff at {path:?}");
+            }
+            MaterializedTreeValue::FileConflict(file) => {
+                let conflict_marker_len = choose_materialized_conflict_marker_len(&file.contents);
+                let options = ConflictMaterializeOptions {
+                    marker_style: self.conflict_marker_style,
+                    marker_len: Some(conflict_marker_len),
+                    merge: self.store.merge_options().clone(),
+                };
+                let contents = materialize_merge_result_to_bytes(&file.contents, &options);
+                let mut file_state = self
+                    .write_conflict(disk_path, &contents, file.executable.unwrap_or(false))
+                    .await?;
+                file_state.materialized_conflict_data = Some(MaterializedConflictData {
+                    conflict_marker_len: conflict_marker_len.try_into().unwrap_or(u32::MAX),
+                });
+                file_state
+            }
+            MaterializedTreeValue::OtherConflict { id } => {
+                // Unless all terms are regular files, we can't do much
+                // better than trying to describe the merge.
+                let contents = id.describe();
+                let executable = false;
+                self.write_conflict(disk_path, contents.as_bytes(), executable)
+                    .await?
+            }
+        };
+        Ok(file_state)
+    }
+
     pub async fn reset(&mut self, new_tree: &MergedTree) -> Result<(), ResetError> {
         let old_tree = self.current_tree().map_err(|err| match err {
             err @ BackendError::ObjectNotFound { .. } => ResetError::SourceNotFound {
@@ -2337,6 +2780,12 @@ impl LocalWorkingCopy {
         Ok(self.tree_state()?.file_states())
     }
 
+    /// Returns the segments the file states are stored in. Empty if the file
+    /// states are stored in a single file.
+    pub fn file_state_segments(&self) -> Result<Vec<FileStateSegmentInfo>, WorkingCopyStateError> {
+        Ok(self.tree_state()?.file_state_segments().cloned().collect())
+    }
+
     #[cfg(feature = "watchman")]
     pub fn query_watchman(
         &self,
@@ -2489,7 +2938,7 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
         operation_id: OperationId,
     ) -> Result<Box<dyn WorkingCopy>, WorkingCopyStateError> {
         assert!(self.tree_state_dirty || &self.old_tree_id == self.wc.tree_id()?);
-        if self.tree_state_dirty {
+        if self.tree_state_dirty || self.wc.tree_state()?.is_format_outdated() {
             self.wc
                 .tree_state_mut()?
                 .save()
@@ -0,0 +1,312 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lock based on lease files, which works on network file systems.
+//!
+//! `flock()` isn't reliable on network file systems, and a lock file left by a
+//! crashed process would never be released. A lease lock is a file created
+//! exclusively, which records the owner. The owner renews the lease
+//! periodically while the lock is held by writing to the file, which updates
+//! its modification time. A process waiting for the lock breaks it if the file
+//! hasn't changed for the lease duration.
+//!
+//! The waiting process measures the duration with its own monotonic clock, and
+//! only compares the modification time with the previously observed one. The
+//! clocks of the owner and the file server therefore don't have to agree with
+//! the local clock.
+
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::instrument;
+
+use super::FileLockError;
+
+/// Default duration of the lease.
+pub const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(30);
+
+/// Owner of a lease lock, which is recorded in the lock file.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct LockOwner {
+    /// Host name of the owner process.
+    pub hostname: String,
+    /// Process id of the owner.
+    pub pid: u32,
+    /// Random token which identifies the lock acquisition.
+    pub token: String,
+}
+
+impl LockOwner {
+    fn new(hostname: &str) -> Self {
+        Self {
+            hostname: hostname.to_owned(),
+            pid: std::process::id(),
+            token: format!("{:016x}", rand::random::<u64>()),
+        }
+    }
+}
+
+/// State of a lease lock file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LeaseLockInfo {
+    /// Owner recorded in the lock file, or `None` if the file couldn't be
+    /// parsed. The file may be incomplete if the owner crashed while writing
+    /// it.
+    pub owner: Option<LockOwner>,
+    /// Modification time of the lock file, which is the time the lease was
+    /// acquired or last renewed according to the file server's clock.
+    pub renewed_at: SystemTime,
+}
+
+/// Watches a lease lock file to tell whether the lease is still being renewed.
+#[derive(Debug)]
+pub struct LeaseWatcher {
+    lease_duration: Duration,
+    last_change: Option<(LeaseLockInfo, Instant)>,
+}
+
+impl LeaseWatcher {
+    /// Creates a watcher that considers a lease expired if the lock file
+    /// doesn't change within `lease_duration`.
+    pub fn new(lease_duration: Duration) -> Self {
+        Self {
+            lease_duration,
+            last_change: None,
+        }
+    }
+
+    /// Records the current state of the lock file. Returns true if the state
+    /// hasn't changed for longer than the lease duration since it was first
+    /// observed.
+    pub fn is_expired(&mut self, info: &LeaseLockInfo) -> bool {
+        let now = Instant::now();
+        match &self.last_change {
+            Some((last_info, changed_at)) if last_info == info => {
+                now.duration_since(*changed_at) > self.lease_duration
+            }
+            _ => {
+                self.last_change = Some((info.clone(), now));
+                false
+            }
+        }
+    }
+}
+
+/// Options for [`LeaseLock`].
+#[derive(Clone, Debug)]
+pub struct LeaseLockOptions {
+    /// Host name to be recorded in the lock file.
+    pub hostname: String,
+    /// Duration after which the lock can be broken unless renewed.
+    pub lease_duration: Duration,
+    /// Maximum time to wait for the lock, or `None` to wait as long as the
+    /// owner keeps renewing the lease.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for LeaseLockOptions {
+    fn default() -> Self {
+        Self {
+            hostname: String::new(),
+            lease_duration: DEFAULT_LEASE_DURATION,
+            timeout: None,
+        }
+    }
+}
+
+/// Lock held by creating a lease file. The lease is renewed by a background
+/// thread until the lock is dropped.
+pub struct LeaseLock {
+    path: PathBuf,
+    owner: LockOwner,
+    renewer: Option<(mpsc::Sender<()>, JoinHandle<()>)>,
+}
+
+impl LeaseLock {
+    /// Creates the lock file at `path`, waiting for the current owner to
+    /// release it. Lock files which aren't renewed within the lease duration
+    /// are removed.
+    pub fn lock(path: PathBuf, options: &LeaseLockOptions) -> Result<Self, FileLockError> {
+        let owner = LockOwner::new(&options.hostname);
+        let mut sleep_duration = Duration::from_millis(1);
+        let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+        let mut watcher = LeaseWatcher::new(options.lease_duration);
+        loop {
+            let err = match create_lock_file(&path, &owner) {
+                Ok(()) => break,
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => err,
+                Err(err) => {
+                    return Err(FileLockError {
+                        message: "Failed to create lock file",
+                        path,
+                        err,
+                    });
+                }
+            };
+            match inspect_lock(&path) {
+                Ok(Some(info)) if watcher.is_expired(&info) => {
+                    tracing::info!(?path, ?info, "Breaking expired lease lock");
+                    break_lock(&path, &info)?;
+                    continue;
+                }
+                // Removed by the owner in the meantime
+                Ok(None) => continue,
+                Ok(Some(_)) => {}
+                Err(err) => return Err(err),
+            }
+            if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                return Err(FileLockError {
+                    message: "Timed out while trying to create lock file",
+                    path,
+                    err,
+                });
+            }
+            thread::sleep(sleep_duration);
+            sleep_duration = (sleep_duration * 2).min(Duration::from_secs(1));
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let renewer = thread::spawn({
+            let path = path.clone();
+            let owner = owner.clone();
+            let interval = options.lease_duration / 3;
+            move || renew_lease(&path, owner, interval, &receiver)
+        });
+        Ok(Self {
+            path,
+            owner,
+            renewer: Some((sender, renewer)),
+        })
+    }
+
+    /// Owner recorded in the lock file when the lock was acquired.
+    pub fn owner(&self) -> &LockOwner {
+        &self.owner
+    }
+}
+
+impl Drop for LeaseLock {
+    #[instrument(skip_all)]
+    fn drop(&mut self) {
+        if let Some((sender, renewer)) = self.renewer.take() {
+            drop(sender);
+            renewer.join().ok();
+        }
+        // Don't delete the lock file if it's been taken over by another process
+        // because our lease expired.
+        match inspect_lock(&self.path) {
+            Ok(Some(LeaseLockInfo {
+                owner: Some(owner), ..
+            })) if owner.token == self.owner.token => {
+                fs::remove_file(&self.path)
+                    .inspect_err(|err| {
+                        tracing::warn!(?err, ?self.path, "Failed to delete lock file");
+                    })
+                    .ok();
+            }
+            _ => tracing::warn!(?self.path, "Lease lock was taken over by another process"),
+        }
+    }
+}
+
+/// Reads the lock file at `path`. Returns `None` if the lock isn't held.
+pub fn inspect_lock(path: &Path) -> Result<Option<LeaseLockInfo>, FileLockError> {
+    let to_lock_error = |err| FileLockError {
+        message: "Failed to read lock file",
+        path: path.to_owned(),
+        err,
+    };
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(to_lock_error(err)),
+    };
+    // The file may be incomplete if the owner crashed while writing it.
+    let owner = toml_edit::de::from_str::<LockOwner>(&content).ok();
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(to_lock_error(err)),
+    };
+    let renewed_at = metadata.modified().map_err(to_lock_error)?;
+    Ok(Some(LeaseLockInfo { owner, renewed_at }))
+}
+
+/// Removes the lock file at `path` if it is still in the given state. Returns
+/// true if the lock file was removed.
+///
+/// There's a short window between reading and removing the lock file, so this
+/// should only be used to break locks which are known to be stale, or whose
+/// owner is known to have terminated.
+pub fn break_lock(path: &Path, info: &LeaseLockInfo) -> Result<bool, FileLockError> {
+    if inspect_lock(path)?.as_ref() != Some(info) {
+        return Ok(false);
+    }
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(FileLockError {
+            message: "Failed to delete lock file",
+            path: path.to_owned(),
+            err,
+        }),
+    }
+}
+
+fn create_lock_file(path: &Path, owner: &LockOwner) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(serialize_owner(owner).as_bytes())?;
+    Ok(())
+}
+
+fn renew_lease(path: &Path, owner: LockOwner, interval: Duration, stop: &mpsc::Receiver<()>) {
+    let content = serialize_owner(&owner);
+    while let Err(mpsc::RecvTimeoutError::Timeout) = stop.recv_timeout(interval) {
+        match inspect_lock(path) {
+            Ok(Some(LeaseLockInfo {
+                owner: Some(current),
+                ..
+            })) if current.token == owner.token => {}
+            _ => {
+                tracing::warn!(?path, "Lost lease lock");
+                return;
+            }
+        }
+        // Rewrite the same content in place so the file system updates the
+        // modification time. The file isn't truncated, so readers never see
+        // an incomplete file.
+        OpenOptions::new()
+            .write(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .inspect_err(|err| tracing::warn!(?err, ?path, "Failed to renew lease lock"))
+            .ok();
+    }
+}
+
+fn serialize_owner(owner: &LockOwner) -> String {
+    toml_edit::ser::to_string(owner).expect("lock owner should be serializable")
+}
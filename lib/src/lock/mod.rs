@@ -15,6 +15,7 @@
 #![expect(missing_docs)]
 
 mod fallback;
+pub mod lease;
 #[cfg(unix)]
 mod unix;
 
@@ -25,6 +26,8 @@ use thiserror::Error;
 
 #[cfg(not(unix))]
 pub use self::fallback::FileLock;
+pub use self::lease::LeaseLock;
+pub use self::lease::LeaseLockOptions;
 #[cfg(unix)]
 pub use self::unix::FileLock;
 
@@ -43,14 +46,20 @@ mod tests {
     use std::fs;
     use std::thread;
     use std::time::Duration;
+    use std::time::SystemTime;
 
     use test_case::test_case;
 
     use super::*;
     use crate::tests::new_temp_dir;
 
+    fn lease_lock(path: PathBuf) -> Result<LeaseLock, FileLockError> {
+        LeaseLock::lock(path, &LeaseLockOptions::default())
+    }
+
     #[test_case(FileLock::lock)]
     #[cfg_attr(unix, test_case(fallback::FileLock::lock))]
+    #[test_case(lease_lock)]
     fn lock_basic<T>(lock_fn: fn(PathBuf) -> Result<T, FileLockError>) {
         let temp_dir = new_temp_dir();
         let lock_path = temp_dir.path().join("test.lock");
@@ -64,6 +73,7 @@ mod tests {
 
     #[test_case(FileLock::lock)]
     #[cfg_attr(unix, test_case(fallback::FileLock::lock))]
+    #[test_case(lease_lock)]
     fn lock_concurrent<T>(lock_fn: fn(PathBuf) -> Result<T, FileLockError>) {
         let temp_dir = new_temp_dir();
         let data_path = temp_dir.path().join("test");
@@ -85,4 +95,95 @@ mod tests {
         let value = u32::from_le_bytes(data.try_into().unwrap());
         assert_eq!(value, num_threads as u32);
     }
+
+    #[test]
+    fn lease_lock_owner() {
+        let temp_dir = new_temp_dir();
+        let lock_path = temp_dir.path().join("test.lock");
+        let options = LeaseLockOptions {
+            hostname: "host.example.com".to_owned(),
+            ..LeaseLockOptions::default()
+        };
+        let lock = LeaseLock::lock(lock_path.clone(), &options).unwrap();
+        let info = lease::inspect_lock(&lock_path).unwrap().unwrap();
+        let owner = info.owner.as_ref().unwrap();
+        assert_eq!(owner, lock.owner());
+        assert_eq!(owner.hostname, "host.example.com");
+        assert_eq!(owner.pid, std::process::id());
+        drop(lock);
+        assert_eq!(lease::inspect_lock(&lock_path).unwrap(), None);
+    }
+
+    #[test]
+    fn lease_lock_break_expired() {
+        let temp_dir = new_temp_dir();
+        let lock_path = temp_dir.path().join("test.lock");
+        let options = LeaseLockOptions {
+            lease_duration: Duration::from_millis(100),
+            ..LeaseLockOptions::default()
+        };
+
+        // Lock file left by a crashed process. The expiry doesn't depend on
+        // the modification time relative to the local clock.
+        let file = fs::File::create(&lock_path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(3600))
+            .unwrap();
+        drop(file);
+        let info = lease::inspect_lock(&lock_path).unwrap().unwrap();
+        assert_eq!(info.owner, None);
+        let mut watcher = lease::LeaseWatcher::new(options.lease_duration);
+        assert!(!watcher.is_expired(&info));
+        thread::sleep(Duration::from_millis(200));
+        assert!(watcher.is_expired(&info));
+
+        // The expired lock is broken
+        let lock = LeaseLock::lock(lock_path.clone(), &options).unwrap();
+        let info = lease::inspect_lock(&lock_path).unwrap().unwrap();
+        assert_eq!(info.owner.as_ref(), Some(lock.owner()));
+
+        // The lock held by a live process is renewed and isn't broken
+        let mut watcher = lease::LeaseWatcher::new(options.lease_duration);
+        assert!(!watcher.is_expired(&info));
+        thread::sleep(Duration::from_millis(200));
+        let renewed_info = lease::inspect_lock(&lock_path).unwrap().unwrap();
+        assert_ne!(renewed_info, info);
+        assert!(!watcher.is_expired(&renewed_info));
+        assert!(!lease::break_lock(&lock_path, &info).unwrap());
+        drop(lock);
+        assert!(!lock_path.exists());
+    }
+
+    #[test]
+    fn lease_lock_wait_while_renewed() {
+        let temp_dir = new_temp_dir();
+        let lock_path = temp_dir.path().join("test.lock");
+        let options = LeaseLockOptions {
+            lease_duration: Duration::from_millis(100),
+            ..LeaseLockOptions::default()
+        };
+        let lock = LeaseLock::lock(lock_path.clone(), &options).unwrap();
+
+        // Waits for the owner beyond the lease duration as long as the lease
+        // is renewed
+        let waiter = thread::spawn({
+            let lock_path = lock_path.clone();
+            let options = options.clone();
+            move || LeaseLock::lock(lock_path, &options).unwrap()
+        });
+        thread::sleep(Duration::from_millis(500));
+        let info = lease::inspect_lock(&lock_path).unwrap().unwrap();
+        assert_eq!(info.owner.as_ref(), Some(lock.owner()));
+        drop(lock);
+        let lock = waiter.join().unwrap();
+        let info = lease::inspect_lock(&lock_path).unwrap().unwrap();
+        assert_eq!(info.owner.as_ref(), Some(lock.owner()));
+
+        // Gives up after the timeout
+        let options = LeaseLockOptions {
+            timeout: Some(Duration::from_millis(200)),
+            ..options
+        };
+        let err = LeaseLock::lock(lock_path.clone(), &options).err().unwrap();
+        assert_eq!(err.message, "Timed out while trying to create lock file");
+    }
 }
@@ -246,6 +246,11 @@ impl<T> Merge<T> {
         self.values.get(index * 2)
     }
 
+    /// Returns a mutable reference to the `index`-th added value.
+    pub fn get_add_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.values.get_mut(index * 2)
+    }
+
     /// Removes the specified "removed"/"added" values. The removed slots are
     /// replaced by the last "removed"/"added" values.
     pub fn swap_remove(&mut self, remove_index: usize, add_index: usize) -> (T, T) {
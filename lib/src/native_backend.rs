@@ -0,0 +1,1327 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Native commit backend which stores objects in compressed pack files.
+//!
+//! New objects are written as zstd-compressed loose files under
+//! `objects/<kind>/<id>`. Garbage collection moves the reachable objects into
+//! a single pack file under `packs/`, which is the concatenation of the
+//! compressed objects. Each pack file has an index file listing the location
+//! of the objects sorted by kind and id:
+//!
+//! ```text
+//! u32: format version
+//! u32: number of entries
+//! for each entry:
+//!   u8: object kind
+//!   [u8; 64]: object id
+//!   u64: offset in the pack file
+//!   u64: length in the pack file
+//! ```
+//!
+//! All integers are little-endian. Unlike the Git backend, change ids, copy
+//! histories, and conflicted trees are stored natively. Since copy histories
+//! only point to their parents, the children of each copy history are recorded
+//! as empty files under `copy_children/<parent>/<child>`.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::hash_map;
+use std::fmt::Debug;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Cursor;
+use std::io::Read as _;
+use std::io::Seek as _;
+use std::io::SeekFrom;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use blake2::Blake2b512;
+use blake2::Digest as _;
+use futures::stream;
+use futures::stream::BoxStream;
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use prost::Message as _;
+use tempfile::NamedTempFile;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt as _;
+
+use crate::backend::Backend;
+use crate::backend::BackendError;
+use crate::backend::BackendInitError;
+use crate::backend::BackendLoadError;
+use crate::backend::BackendResult;
+use crate::backend::ChangeId;
+use crate::backend::Commit;
+use crate::backend::CommitId;
+use crate::backend::CopyHistory;
+use crate::backend::CopyId;
+use crate::backend::CopyRecord;
+use crate::backend::FileId;
+use crate::backend::SecureSig;
+use crate::backend::SigningFn;
+use crate::backend::SymlinkId;
+use crate::backend::Tree;
+use crate::backend::TreeId;
+use crate::backend::TreeValue;
+use crate::backend::make_root_commit;
+use crate::content_hash::blake2b_hash;
+use crate::dag_walk::topo_order_reverse;
+use crate::file_util::IoResultExt as _;
+use crate::file_util::PathError;
+use crate::file_util::persist_content_addressed_temp_file;
+use crate::hex_util;
+use crate::index::Index;
+use crate::object_id::ObjectId;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::simple_backend::commit_from_proto;
+use crate::simple_backend::commit_to_proto;
+use crate::simple_backend::tree_from_proto;
+use crate::simple_backend::tree_to_proto;
+
+const COMMIT_ID_LENGTH: usize = 64;
+const CHANGE_ID_LENGTH: usize = 16;
+const OBJECT_ID_LENGTH: usize = 64;
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+const PACK_INDEX_VERSION: u32 = 1;
+const PACK_INDEX_HEADER_SIZE: usize = 8;
+const PACK_INDEX_ENTRY_SIZE: usize = 1 + OBJECT_ID_LENGTH + 8 + 8;
+/// Coarsest timestamp granularity of the filesystems we expect to run on.
+const PACKS_DIR_MTIME_GRANULARITY: Duration = Duration::from_secs(2);
+
+/// Kind of objects stored by the native backend.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum ObjectKind {
+    Commit,
+    Tree,
+    File,
+    Symlink,
+    Copy,
+}
+
+impl ObjectKind {
+    const ALL: [Self; 5] = [
+        Self::Commit,
+        Self::Tree,
+        Self::File,
+        Self::Symlink,
+        Self::Copy,
+    ];
+
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::Commit => "commits",
+            Self::Tree => "trees",
+            Self::File => "files",
+            Self::Symlink => "symlinks",
+            Self::Copy => "copies",
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Commit => 0,
+            Self::Tree => 1,
+            Self::File => 2,
+            Self::Symlink => 3,
+            Self::Copy => 4,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.to_byte() == byte)
+    }
+}
+
+/// Object key used for lookup and reachability analysis.
+type ObjectKey = (ObjectKind, Vec<u8>);
+
+#[derive(Clone, Debug)]
+struct PackEntry {
+    kind: ObjectKind,
+    id: Vec<u8>,
+    offset: u64,
+    len: u64,
+}
+
+/// Index of objects in a pack file.
+#[derive(Debug)]
+struct PackIndex {
+    name: String,
+    /// Entries sorted by `(kind, id)`.
+    entries: Vec<PackEntry>,
+    /// Pack file opened when the index was loaded, so a pack removed by
+    /// concurrent gc can still be read.
+    file: Mutex<File>,
+}
+
+impl PackIndex {
+    fn load(dir: &Path, name: &str) -> Result<Self, PackIndexLoadError> {
+        let path = dir.join(format!("{name}.idx"));
+        let data = fs::read(&path).context(&path)?;
+        let entries = decode_pack_index(&data).ok_or_else(|| PackIndexLoadError::Corrupt {
+            name: name.to_owned(),
+        })?;
+        let pack_path = dir.join(format!("{name}.pack"));
+        let file = File::open(&pack_path).context(&pack_path)?;
+        Ok(Self {
+            name: name.to_owned(),
+            entries,
+            file: Mutex::new(file),
+        })
+    }
+
+    fn read_entry(&self, entry: &PackEntry) -> io::Result<Vec<u8>> {
+        let mut file = self.file.lock().unwrap();
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut buf = vec![0; usize::try_from(entry.len).unwrap()];
+        file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn find(&self, kind: ObjectKind, id: &[u8]) -> Option<&PackEntry> {
+        let pos = self
+            .entries
+            .binary_search_by(|entry| (entry.kind, entry.id.as_slice()).cmp(&(kind, id)))
+            .ok()?;
+        Some(&self.entries[pos])
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum PackIndexLoadError {
+    #[error(transparent)]
+    Io(#[from] PathError),
+    #[error("Corrupt pack index {name}")]
+    Corrupt { name: String },
+}
+
+fn encode_pack_index(entries: &[PackEntry]) -> Vec<u8> {
+    let mut buf =
+        Vec::with_capacity(PACK_INDEX_HEADER_SIZE + entries.len() * PACK_INDEX_ENTRY_SIZE);
+    buf.extend(PACK_INDEX_VERSION.to_le_bytes());
+    buf.extend(u32::try_from(entries.len()).unwrap().to_le_bytes());
+    for entry in entries {
+        buf.push(entry.kind.to_byte());
+        buf.extend(&entry.id);
+        buf.extend(entry.offset.to_le_bytes());
+        buf.extend(entry.len.to_le_bytes());
+    }
+    buf
+}
+
+fn decode_pack_index(data: &[u8]) -> Option<Vec<PackEntry>> {
+    let (header, body) = data.split_at_checked(PACK_INDEX_HEADER_SIZE)?;
+    let version = u32::from_le_bytes(header[0..4].try_into().unwrap());
+    let num_entries = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    if version != PACK_INDEX_VERSION
+        || body.len() != usize::try_from(num_entries).ok()? * PACK_INDEX_ENTRY_SIZE
+    {
+        return None;
+    }
+    body.chunks_exact(PACK_INDEX_ENTRY_SIZE)
+        .map(|chunk| {
+            let (kind, rest) = chunk.split_first().unwrap();
+            let (id, rest) = rest.split_at(OBJECT_ID_LENGTH);
+            let (offset, len) = rest.split_at(8);
+            Some(PackEntry {
+                kind: ObjectKind::from_byte(*kind)?,
+                id: id.to_vec(),
+                offset: u64::from_le_bytes(offset.try_into().unwrap()),
+                len: u64::from_le_bytes(len.try_into().unwrap()),
+            })
+        })
+        .collect()
+}
+
+/// Statistics about objects stored by [`NativeBackend`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct NativeBackendStats {
+    /// Number of pack files.
+    pub num_packs: usize,
+    /// Number of objects in pack files.
+    pub num_packed_objects: usize,
+    /// Number of objects stored as loose files.
+    pub num_loose_objects: usize,
+}
+
+fn map_not_found_err(err: io::Error, id: &impl ObjectId) -> BackendError {
+    if err.kind() == io::ErrorKind::NotFound {
+        BackendError::ObjectNotFound {
+            object_type: id.object_type(),
+            hash: id.hex(),
+            source: Box::new(err),
+        }
+    } else {
+        BackendError::ReadObject {
+            object_type: id.object_type(),
+            hash: id.hex(),
+            source: Box::new(err),
+        }
+    }
+}
+
+fn to_other_err(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> BackendError {
+    BackendError::Other(err.into())
+}
+
+/// Pack indexes loaded from the packs directory.
+#[derive(Debug)]
+struct LoadedPacks {
+    packs: Arc<Vec<PackIndex>>,
+    /// Modification time of the packs directory when it was listed, or `None`
+    /// if it was too recent to tell whether a later change would update it.
+    dir_mtime: Option<SystemTime>,
+}
+
+/// Commit backend which stores objects natively in zstd-compressed loose files
+/// and pack files.
+#[derive(Debug)]
+pub struct NativeBackend {
+    path: PathBuf,
+    root_commit_id: CommitId,
+    root_change_id: ChangeId,
+    empty_tree_id: TreeId,
+    /// Loaded pack indexes, reloaded when an object isn't found and the
+    /// packs directory has changed.
+    packs: Mutex<Option<LoadedPacks>>,
+}
+
+impl NativeBackend {
+    /// Name of the backend recorded in the store type file.
+    pub fn name() -> &'static str {
+        "Native"
+    }
+
+    /// Initializes a new store at `store_path`.
+    pub fn init(store_path: &Path) -> Result<Self, BackendInitError> {
+        let objects_dir = store_path.join("objects");
+        for kind in ObjectKind::ALL {
+            let dir = objects_dir.join(kind.dir_name());
+            fs::create_dir_all(&dir)
+                .context(&dir)
+                .map_err(|err| BackendInitError(err.into()))?;
+        }
+        let packs_dir = store_path.join("packs");
+        fs::create_dir(&packs_dir)
+            .context(&packs_dir)
+            .map_err(|err| BackendInitError(err.into()))?;
+        let backend = Self::load(store_path).map_err(|err| BackendInitError(err.0))?;
+        let empty_tree_id = backend
+            .write_tree(RepoPath::root(), &Tree::default())
+            .block_on()
+            .map_err(|err| BackendInitError(err.into()))?;
+        assert_eq!(empty_tree_id, backend.empty_tree_id);
+        Ok(backend)
+    }
+
+    /// Loads an existing store at `store_path`.
+    pub fn load(store_path: &Path) -> Result<Self, BackendLoadError> {
+        let root_commit_id = CommitId::from_bytes(&[0; COMMIT_ID_LENGTH]);
+        let root_change_id = ChangeId::from_bytes(&[0; CHANGE_ID_LENGTH]);
+        let empty_tree_id = TreeId::new(blake2b_hash(&Tree::default()).to_vec());
+        Ok(Self {
+            path: store_path.to_path_buf(),
+            root_commit_id,
+            root_change_id,
+            empty_tree_id,
+            packs: Mutex::new(None),
+        })
+    }
+
+    fn packs_dir(&self) -> PathBuf {
+        self.path.join("packs")
+    }
+
+    fn loose_object_dir(&self, kind: ObjectKind) -> PathBuf {
+        self.path.join("objects").join(kind.dir_name())
+    }
+
+    fn loose_object_path(&self, kind: ObjectKind, id: &[u8]) -> PathBuf {
+        self.loose_object_dir(kind).join(hex_util::encode_hex(id))
+    }
+
+    fn copy_children_dir(&self) -> PathBuf {
+        self.path.join("copy_children")
+    }
+
+    /// Returns the loaded pack indexes, loading them if needed.
+    fn packs(&self) -> BackendResult<Arc<Vec<PackIndex>>> {
+        let mut packs = self.packs.lock().unwrap();
+        if let Some(loaded) = &*packs {
+            return Ok(loaded.packs.clone());
+        }
+        let loaded = self.load_packs()?;
+        let result = loaded.packs.clone();
+        *packs = Some(loaded);
+        Ok(result)
+    }
+
+    /// Reloads the pack indexes, which may have been updated by another
+    /// process.
+    fn reload_packs(&self) -> BackendResult<Arc<Vec<PackIndex>>> {
+        let loaded = self.load_packs()?;
+        let result = loaded.packs.clone();
+        *self.packs.lock().unwrap() = Some(loaded);
+        Ok(result)
+    }
+
+    /// Reloads the pack indexes if the packs directory has been modified since
+    /// they were loaded. Returns `None` if they are up to date.
+    fn reload_packs_if_changed(&self) -> BackendResult<Option<Arc<Vec<PackIndex>>>> {
+        let dir = self.packs_dir();
+        let dir_mtime = file_mtime(&dir)?;
+        let mut packs = self.packs.lock().unwrap();
+        if packs
+            .as_ref()
+            .is_some_and(|loaded| loaded.dir_mtime == Some(dir_mtime))
+        {
+            return Ok(None);
+        }
+        let loaded = self.load_packs()?;
+        let result = loaded.packs.clone();
+        *packs = Some(loaded);
+        Ok(Some(result))
+    }
+
+    fn load_packs(&self) -> BackendResult<LoadedPacks> {
+        let dir = self.packs_dir();
+        // The mtime is read before listing so that a concurrent change makes
+        // it differ next time. If the mtime is recent, a change within the
+        // filesystem's timestamp granularity could go unnoticed.
+        let dir_mtime = Some(file_mtime(&dir)?).filter(|mtime| {
+            SystemTime::now()
+                .duration_since(*mtime)
+                .is_ok_and(|age| age > PACKS_DIR_MTIME_GRANULARITY)
+        });
+        let mut packs = vec![];
+        for entry in fs::read_dir(&dir).context(&dir).map_err(to_other_err)? {
+            let entry = entry.context(&dir).map_err(to_other_err)?;
+            let file_name = entry.file_name();
+            let Some(name) = file_name
+                .to_str()
+                .and_then(|name| name.strip_suffix(".idx"))
+            else {
+                continue;
+            };
+            match PackIndex::load(&dir, name) {
+                Ok(pack) => packs.push(pack),
+                // The pack may have been removed by concurrent gc
+                Err(PackIndexLoadError::Io(err))
+                    if err.source.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(to_other_err(err)),
+            }
+        }
+        packs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(LoadedPacks {
+            packs: Arc::new(packs),
+            dir_mtime,
+        })
+    }
+
+    fn read_from_packs(
+        &self,
+        packs: &[PackIndex],
+        kind: ObjectKind,
+        id: &impl ObjectId,
+    ) -> BackendResult<Option<Vec<u8>>> {
+        for pack in packs {
+            let Some(entry) = pack.find(kind, id.as_bytes()) else {
+                continue;
+            };
+            let compressed = pack
+                .read_entry(entry)
+                .map_err(|err| map_not_found_err(err, id))?;
+            return decompress(&compressed, id).map(Some);
+        }
+        Ok(None)
+    }
+
+    fn read_object(&self, kind: ObjectKind, id: &impl ObjectId) -> BackendResult<Vec<u8>> {
+        if let Some(data) = self.read_from_packs(&self.packs()?, kind, id)? {
+            return Ok(data);
+        }
+        let not_found_err = match fs::read(self.loose_object_path(kind, id.as_bytes())) {
+            Ok(compressed) => return decompress(&compressed, id),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => err,
+            Err(err) => return Err(map_not_found_err(err, id)),
+        };
+        // The object may have been moved to a new pack by concurrent gc
+        if let Some(packs) = self.reload_packs_if_changed()?
+            && let Some(data) = self.read_from_packs(&packs, kind, id)?
+        {
+            return Ok(data);
+        }
+        Err(map_not_found_err(not_found_err, id))
+    }
+
+    fn write_object(&self, kind: ObjectKind, id: &[u8], data: &[u8]) -> BackendResult<()> {
+        // An existing unreachable object could be removed by concurrent gc
+        // before the new object referring to it becomes reachable. Refresh
+        // the mtime of the loose object so it's kept by `keep_newer`.
+        let path = self.loose_object_path(kind, id);
+        match File::options().write(true).open(&path) {
+            Ok(file) => {
+                return file
+                    .set_modified(SystemTime::now())
+                    .context(&path)
+                    .map_err(to_other_err);
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(to_other_err(PathError { path, source: err })),
+        }
+        // For the same reason, packed objects are written again as loose
+        // objects. The duplicates are removed by the next gc.
+        let compressed = zstd::encode_all(data, COMPRESSION_LEVEL).map_err(to_other_err)?;
+        let dir = self.loose_object_dir(kind);
+        let mut temp_file = NamedTempFile::new_in(&dir).map_err(to_other_err)?;
+        temp_file.write_all(&compressed).map_err(to_other_err)?;
+        persist_content_addressed_temp_file(temp_file, path).map_err(to_other_err)?;
+        Ok(())
+    }
+
+    fn write_copy_child(&self, parent: &CopyId, child: &CopyId) -> BackendResult<()> {
+        let dir = self.copy_children_dir().join(parent.hex());
+        fs::create_dir_all(&dir)
+            .context(&dir)
+            .map_err(to_other_err)?;
+        let path = dir.join(child.hex());
+        File::create(&path).context(&path).map_err(to_other_err)?;
+        Ok(())
+    }
+
+    fn list_copy_children(&self, parent: &CopyId) -> BackendResult<Vec<CopyId>> {
+        let dir = self.copy_children_dir().join(parent.hex());
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => {
+                return Err(to_other_err(PathError {
+                    path: dir,
+                    source: err,
+                }));
+            }
+        };
+        let mut children = vec![];
+        for entry in entries {
+            let entry = entry.context(&dir).map_err(to_other_err)?;
+            if let Some(id) = entry.file_name().to_str().and_then(CopyId::try_from_hex) {
+                children.push(id);
+            }
+        }
+        Ok(children)
+    }
+
+    /// Reads the copy history, or returns `None` if it doesn't exist.
+    fn try_read_copy(&self, id: &CopyId) -> BackendResult<Option<CopyHistory>> {
+        match self.read_copy_sync(id) {
+            Ok(copy) => Ok(Some(copy)),
+            // Copy ids may be placeholders, or removed by gc
+            Err(BackendError::ObjectNotFound { .. }) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the root tree id of the commit, or `None` if the tree is
+    /// conflicted.
+    fn resolved_root_tree_id(&self, commit_id: &CommitId) -> BackendResult<Option<TreeId>> {
+        let commit = self.read_commit(commit_id).block_on()?;
+        Ok(commit.root_tree.into_merge().into_resolved().ok())
+    }
+
+    /// Looks up the file at `path` in the tree, and returns its file and copy
+    /// ids. Only the trees along the path are read.
+    fn find_file(
+        &self,
+        tree_id: &TreeId,
+        path: &RepoPath,
+    ) -> BackendResult<Option<(FileId, CopyId)>> {
+        let Some((dir, name)) = path.split() else {
+            return Ok(None);
+        };
+        let mut tree_id = tree_id.clone();
+        let mut tree_path = RepoPathBuf::root();
+        for component in dir.components() {
+            let tree = self.read_tree(&tree_path, &tree_id).block_on()?;
+            let Some(TreeValue::Tree(id)) = tree.value(component) else {
+                return Ok(None);
+            };
+            tree_id = id.clone();
+            tree_path = tree_path.join(component);
+        }
+        let tree = self.read_tree(&tree_path, &tree_id).block_on()?;
+        match tree.value(name) {
+            Some(TreeValue::File { id, copy_id, .. }) => Ok(Some((id.clone(), copy_id.clone()))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Lists files in the head tree whose copy id differs from the file at the
+    /// same path in the root tree. Subtrees which are unchanged aren't read.
+    fn list_changed_files(
+        &self,
+        root_tree_id: &TreeId,
+        head_tree_id: &TreeId,
+    ) -> BackendResult<Vec<(RepoPathBuf, CopyId)>> {
+        let mut files = vec![];
+        let mut tree_ids = vec![(
+            RepoPathBuf::root(),
+            Some(root_tree_id.clone()),
+            head_tree_id.clone(),
+        )];
+        while let Some((dir, root_id, head_id)) = tree_ids.pop() {
+            if root_id.as_ref() == Some(&head_id) {
+                continue;
+            }
+            let root_tree = match &root_id {
+                Some(id) => self.read_tree(&dir, id).block_on()?,
+                None => Tree::default(),
+            };
+            let head_tree = self.read_tree(&dir, &head_id).block_on()?;
+            for entry in head_tree.entries() {
+                let path = dir.join(entry.name());
+                let root_value = root_tree.value(entry.name());
+                match entry.value() {
+                    TreeValue::File { copy_id, .. } => {
+                        let unchanged = matches!(
+                            root_value,
+                            Some(TreeValue::File { copy_id: root_copy_id, .. })
+                                if root_copy_id == copy_id
+                        );
+                        if !unchanged {
+                            files.push((path, copy_id.clone()));
+                        }
+                    }
+                    TreeValue::Tree(id) => {
+                        let root_id = match root_value {
+                            Some(TreeValue::Tree(root_id)) => Some(root_id.clone()),
+                            _ => None,
+                        };
+                        tree_ids.push((path, root_id, id.clone()));
+                    }
+                    TreeValue::Symlink(_) | TreeValue::GitSubmodule(_) => {}
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Lists loose objects with their modification time.
+    fn list_loose_objects(&self) -> BackendResult<Vec<(ObjectKey, SystemTime)>> {
+        let mut objects = vec![];
+        for kind in ObjectKind::ALL {
+            let dir = self.loose_object_dir(kind);
+            for entry in fs::read_dir(&dir).context(&dir).map_err(to_other_err)? {
+                let entry = entry.context(&dir).map_err(to_other_err)?;
+                // Skip temporary files
+                let Some(id) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(hex_util::decode_hex)
+                    .filter(|id| id.len() == OBJECT_ID_LENGTH)
+                else {
+                    continue;
+                };
+                let path = entry.path();
+                let mtime = match entry.metadata().and_then(|metadata| metadata.modified()) {
+                    Ok(mtime) => mtime,
+                    // The object may have been removed by concurrent gc
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                    Err(err) => return Err(to_other_err(PathError { path, source: err })),
+                };
+                objects.push(((kind, id), mtime));
+            }
+        }
+        Ok(objects)
+    }
+
+    /// Returns statistics about the stored objects.
+    pub fn stats(&self) -> BackendResult<NativeBackendStats> {
+        let packs = self.reload_packs()?;
+        Ok(NativeBackendStats {
+            num_packs: packs.len(),
+            num_packed_objects: packs.iter().map(|pack| pack.entries.len()).sum(),
+            num_loose_objects: self.list_loose_objects()?.len(),
+        })
+    }
+
+    fn read_copy_sync(&self, id: &CopyId) -> BackendResult<CopyHistory> {
+        let buf = self.read_object(ObjectKind::Copy, id)?;
+        let proto =
+            crate::protos::simple_store::CopyHistory::decode(&*buf).map_err(to_other_err)?;
+        copy_history_from_proto(proto)
+    }
+
+    /// Collects objects reachable from the given commits.
+    fn collect_reachable_objects(
+        &self,
+        heads: impl IntoIterator<Item = CommitId>,
+    ) -> BackendResult<HashSet<ObjectKey>> {
+        let mut reachable = HashSet::new();
+        reachable.insert((ObjectKind::Tree, self.empty_tree_id.to_bytes()));
+        let mut commit_ids = heads.into_iter().collect_vec();
+        let mut tree_ids = vec![];
+        let mut copy_ids = vec![];
+        while let Some(id) = commit_ids.pop() {
+            if id == self.root_commit_id || !reachable.insert((ObjectKind::Commit, id.to_bytes())) {
+                continue;
+            }
+            let commit = self.read_commit(&id).block_on()?;
+            commit_ids.extend(commit.parents);
+            // Predecessors may have been pruned by older versions
+            for id in commit.predecessors {
+                match self.read_commit(&id).block_on() {
+                    Ok(_) => commit_ids.push(id),
+                    Err(BackendError::ObjectNotFound { .. }) => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            tree_ids.extend(commit.root_tree.into_merge());
+        }
+        while let Some(id) = tree_ids.pop() {
+            if !reachable.insert((ObjectKind::Tree, id.to_bytes())) {
+                continue;
+            }
+            let tree = self.read_tree(RepoPath::root(), &id).block_on()?;
+            for entry in tree.entries() {
+                match entry.value() {
+                    TreeValue::File { id, copy_id, .. } => {
+                        reachable.insert((ObjectKind::File, id.to_bytes()));
+                        if !copy_id.as_bytes().is_empty() {
+                            copy_ids.push(copy_id.clone());
+                        }
+                    }
+                    TreeValue::Symlink(id) => {
+                        reachable.insert((ObjectKind::Symlink, id.to_bytes()));
+                    }
+                    TreeValue::Tree(id) => tree_ids.push(id.clone()),
+                    TreeValue::GitSubmodule(_) => {}
+                }
+            }
+        }
+        while let Some(id) = copy_ids.pop() {
+            if !reachable.insert((ObjectKind::Copy, id.to_bytes())) {
+                continue;
+            }
+            if let Some(copy) = self.try_read_copy(&id)? {
+                copy_ids.extend(copy.parents);
+            }
+        }
+        Ok(reachable)
+    }
+
+    /// Moves the `reachable` objects into a new pack file, and removes
+    /// unreachable objects older than `keep_newer`.
+    fn repack(&self, reachable: &HashSet<ObjectKey>, keep_newer: SystemTime) -> BackendResult<()> {
+        let packs_dir = self.packs_dir();
+        let old_packs = self.reload_packs()?;
+        let loose_objects = self.list_loose_objects()?;
+
+        let mut pack_file = NamedTempFile::new_in(&packs_dir).map_err(to_other_err)?;
+        let mut entries: Vec<PackEntry> = vec![];
+        let mut packed: HashSet<ObjectKey> = HashSet::new();
+        let mut offset = 0;
+        let mut add_object = |key: &ObjectKey, compressed: &[u8]| -> BackendResult<()> {
+            if !packed.insert(key.clone()) {
+                return Ok(());
+            }
+            pack_file.write_all(compressed).map_err(to_other_err)?;
+            let len = u64::try_from(compressed.len()).unwrap();
+            entries.push(PackEntry {
+                kind: key.0,
+                id: key.1.clone(),
+                offset,
+                len,
+            });
+            offset += len;
+            Ok(())
+        };
+        for pack in old_packs.iter() {
+            let pack_path = packs_dir.join(format!("{}.pack", pack.name));
+            let mtime = fs::metadata(&pack_path)
+                .and_then(|metadata| metadata.modified())
+                .context(&pack_path)
+                .map_err(to_other_err)?;
+            // Objects in a new pack might not be reachable from the index yet
+            let keep_all = mtime > keep_newer;
+            for entry in &pack.entries {
+                let key = (entry.kind, entry.id.clone());
+                if keep_all || reachable.contains(&key) {
+                    let compressed = pack
+                        .read_entry(entry)
+                        .context(&pack_path)
+                        .map_err(to_other_err)?;
+                    add_object(&key, &compressed)?;
+                }
+            }
+        }
+        for (key, _mtime) in &loose_objects {
+            if reachable.contains(key) {
+                let path = self.loose_object_path(key.0, &key.1);
+                let compressed = fs::read(&path).context(&path).map_err(to_other_err)?;
+                add_object(key, &compressed)?;
+            }
+        }
+
+        let mut new_pack_name = None;
+        if !entries.is_empty() {
+            entries.sort_unstable_by(|a, b| (a.kind, &a.id).cmp(&(b.kind, &b.id)));
+            let index_data = encode_pack_index(&entries);
+            let name = hex_util::encode_hex(&Blake2b512::digest(&index_data));
+            pack_file.as_file().sync_data().map_err(to_other_err)?;
+            persist_content_addressed_temp_file(pack_file, packs_dir.join(format!("{name}.pack")))
+                .map_err(to_other_err)?;
+            // The index is written last so that readers never see an
+            // incomplete pack.
+            let mut index_file = NamedTempFile::new_in(&packs_dir).map_err(to_other_err)?;
+            index_file.write_all(&index_data).map_err(to_other_err)?;
+            index_file.as_file().sync_data().map_err(to_other_err)?;
+            persist_content_addressed_temp_file(index_file, packs_dir.join(format!("{name}.idx")))
+                .map_err(to_other_err)?;
+            new_pack_name = Some(name);
+        }
+
+        // Close our handles of the old pack files before removing them
+        let old_pack_names = old_packs.iter().map(|pack| pack.name.clone()).collect_vec();
+        drop(old_packs);
+        *self.packs.lock().unwrap() = None;
+        for name in &old_pack_names {
+            if new_pack_name.as_ref() == Some(name) {
+                continue;
+            }
+            for ext in ["idx", "pack"] {
+                let path = packs_dir.join(format!("{name}.{ext}"));
+                remove_file_if_exists(&path)?;
+            }
+        }
+        for (key, mtime) in &loose_objects {
+            let path = self.loose_object_path(key.0, &key.1);
+            // The object may have been written again since it was listed
+            if !packed.contains(key) && (*mtime > keep_newer || file_mtime(&path)? > keep_newer) {
+                continue;
+            }
+            remove_file_if_exists(&path)?;
+        }
+        self.reload_packs()?;
+        self.prune_copy_children(keep_newer)
+    }
+
+    /// Removes the child records of copy histories which no longer exist.
+    fn prune_copy_children(&self, keep_newer: SystemTime) -> BackendResult<()> {
+        let dir = self.copy_children_dir();
+        let parent_entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => {
+                return Err(to_other_err(PathError {
+                    path: dir,
+                    source: err,
+                }));
+            }
+        };
+        for parent_entry in parent_entries {
+            let parent_dir = parent_entry.context(&dir).map_err(to_other_err)?.path();
+            for child_entry in fs::read_dir(&parent_dir)
+                .context(&parent_dir)
+                .map_err(to_other_err)?
+            {
+                let path = child_entry
+                    .context(&parent_dir)
+                    .map_err(to_other_err)?
+                    .path();
+                // A new copy history might not be visible to this gc yet
+                if file_mtime(&path)? > keep_newer {
+                    continue;
+                }
+                let Some(child) = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(CopyId::try_from_hex)
+                else {
+                    continue;
+                };
+                if self.try_read_copy(&child)?.is_none() {
+                    remove_file_if_exists(&path)?;
+                }
+            }
+            // Fails if the directory still has children
+            fs::remove_dir(&parent_dir).ok();
+        }
+        Ok(())
+    }
+}
+
+/// Returns the modification time of the file, or `UNIX_EPOCH` if it doesn't
+/// exist.
+fn file_mtime(path: &Path) -> BackendResult<SystemTime> {
+    match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(mtime) => Ok(mtime),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(SystemTime::UNIX_EPOCH),
+        Err(err) => Err(to_other_err(PathError {
+            path: path.to_owned(),
+            source: err,
+        })),
+    }
+}
+
+fn remove_file_if_exists(path: &Path) -> BackendResult<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(to_other_err(PathError {
+            path: path.to_owned(),
+            source: err,
+        })),
+    }
+}
+
+fn decompress(compressed: &[u8], id: &impl ObjectId) -> BackendResult<Vec<u8>> {
+    zstd::decode_all(compressed).map_err(|err| BackendError::ReadObject {
+        object_type: id.object_type(),
+        hash: id.hex(),
+        source: Box::new(err),
+    })
+}
+
+#[async_trait]
+impl Backend for NativeBackend {
+    fn name(&self) -> &str {
+        Self::name()
+    }
+
+    fn commit_id_length(&self) -> usize {
+        COMMIT_ID_LENGTH
+    }
+
+    fn change_id_length(&self) -> usize {
+        CHANGE_ID_LENGTH
+    }
+
+    fn root_commit_id(&self) -> &CommitId {
+        &self.root_commit_id
+    }
+
+    fn root_change_id(&self) -> &ChangeId {
+        &self.root_change_id
+    }
+
+    fn empty_tree_id(&self) -> &TreeId {
+        &self.empty_tree_id
+    }
+
+    fn concurrency(&self) -> usize {
+        1
+    }
+
+    async fn read_file(
+        &self,
+        _path: &RepoPath,
+        id: &FileId,
+    ) -> BackendResult<Pin<Box<dyn AsyncRead + Send>>> {
+        let buf = self.read_object(ObjectKind::File, id)?;
+        Ok(Box::pin(Cursor::new(buf)))
+    }
+
+    async fn write_file(
+        &self,
+        _path: &RepoPath,
+        contents: &mut (dyn AsyncRead + Send + Unpin),
+    ) -> BackendResult<FileId> {
+        let mut buf = vec![];
+        contents.read_to_end(&mut buf).await.map_err(to_other_err)?;
+        let id = FileId::new(Blake2b512::digest(&buf).to_vec());
+        self.write_object(ObjectKind::File, id.as_bytes(), &buf)?;
+        Ok(id)
+    }
+
+    async fn read_symlink(&self, _path: &RepoPath, id: &SymlinkId) -> BackendResult<String> {
+        let buf = self.read_object(ObjectKind::Symlink, id)?;
+        String::from_utf8(buf).map_err(|err| BackendError::ReadObject {
+            object_type: id.object_type(),
+            hash: id.hex(),
+            source: Box::new(err),
+        })
+    }
+
+    async fn write_symlink(&self, _path: &RepoPath, target: &str) -> BackendResult<SymlinkId> {
+        let id = SymlinkId::new(Blake2b512::digest(target.as_bytes()).to_vec());
+        self.write_object(ObjectKind::Symlink, id.as_bytes(), target.as_bytes())?;
+        Ok(id)
+    }
+
+    async fn read_copy(&self, id: &CopyId) -> BackendResult<CopyHistory> {
+        self.read_copy_sync(id)
+    }
+
+    async fn write_copy(&self, copy: &CopyHistory) -> BackendResult<CopyId> {
+        let id = CopyId::new(blake2b_hash(copy).to_vec());
+        let proto = copy_history_to_proto(copy);
+        self.write_object(ObjectKind::Copy, id.as_bytes(), &proto.encode_to_vec())?;
+        for parent in &copy.parents {
+            self.write_copy_child(parent, &id)?;
+        }
+        Ok(id)
+    }
+
+    async fn get_related_copies(&self, copy_id: &CopyId) -> BackendResult<Vec<CopyHistory>> {
+        let mut copies = HashMap::new();
+        copies.insert(copy_id.clone(), self.read_copy_sync(copy_id)?);
+
+        // Ancestors of the specified copy history, plus their descendants
+        let mut ancestors = vec![copy_id.clone()];
+        let mut to_visit = copies[copy_id].parents.clone();
+        while let Some(id) = to_visit.pop() {
+            if let hash_map::Entry::Vacant(entry) = copies.entry(id)
+                && let Some(copy) = self.try_read_copy(entry.key())?
+            {
+                to_visit.extend(copy.parents.iter().cloned());
+                ancestors.push(entry.key().clone());
+                entry.insert(copy);
+            }
+        }
+        let mut related = HashSet::new();
+        let mut to_visit = ancestors;
+        while let Some(id) = to_visit.pop() {
+            if !related.insert(id.clone()) {
+                continue;
+            }
+            for child in self.list_copy_children(&id)? {
+                if let hash_map::Entry::Vacant(entry) = copies.entry(child.clone()) {
+                    let Some(copy) = self.try_read_copy(&child)? else {
+                        continue;
+                    };
+                    entry.insert(copy);
+                }
+                to_visit.push(child);
+            }
+        }
+        let related_ids = topo_order_reverse(
+            related.iter(),
+            |id| *id,
+            |id| {
+                copies[*id]
+                    .parents
+                    .iter()
+                    .filter(|parent| related.contains(*parent))
+            },
+            |_| panic!("graph has cycle"),
+        )
+        .unwrap();
+        Ok(related_ids
+            .into_iter()
+            .map(|id| copies[id].clone())
+            .collect())
+    }
+
+    async fn read_tree(&self, _path: &RepoPath, id: &TreeId) -> BackendResult<Tree> {
+        let buf = self.read_object(ObjectKind::Tree, id)?;
+        let proto = crate::protos::simple_store::Tree::decode(&*buf).map_err(to_other_err)?;
+        Ok(tree_from_proto(proto))
+    }
+
+    async fn write_tree(&self, _path: &RepoPath, tree: &Tree) -> BackendResult<TreeId> {
+        let id = TreeId::new(blake2b_hash(tree).to_vec());
+        let proto = tree_to_proto(tree);
+        self.write_object(ObjectKind::Tree, id.as_bytes(), &proto.encode_to_vec())?;
+        Ok(id)
+    }
+
+    async fn read_commit(&self, id: &CommitId) -> BackendResult<Commit> {
+        if *id == self.root_commit_id {
+            return Ok(make_root_commit(
+                self.root_change_id().clone(),
+                self.empty_tree_id.clone(),
+            ));
+        }
+        let buf = self.read_object(ObjectKind::Commit, id)?;
+        let proto = crate::protos::simple_store::Commit::decode(&*buf).map_err(to_other_err)?;
+        Ok(commit_from_proto(proto))
+    }
+
+    async fn write_commit(
+        &self,
+        mut commit: Commit,
+        sign_with: Option<&mut SigningFn>,
+    ) -> BackendResult<(CommitId, Commit)> {
+        assert!(commit.secure_sig.is_none(), "commit.secure_sig was set");
+
+        if commit.parents.is_empty() {
+            return Err(BackendError::Other(
+                "Cannot write a commit with no parents".into(),
+            ));
+        }
+        let mut proto = commit_to_proto(&commit);
+        if let Some(sign) = sign_with {
+            let data = proto.encode_to_vec();
+            let sig = sign(&data).map_err(to_other_err)?;
+            proto.secure_sig = Some(sig.clone());
+            commit.secure_sig = Some(SecureSig { data, sig });
+        }
+        let id = CommitId::new(blake2b_hash(&commit).to_vec());
+        self.write_object(ObjectKind::Commit, id.as_bytes(), &proto.encode_to_vec())?;
+        Ok((id, commit))
+    }
+
+    fn get_copy_records(
+        &self,
+        paths: Option<&[RepoPathBuf]>,
+        root: &CommitId,
+        head: &CommitId,
+    ) -> BackendResult<BoxStream<'_, BackendResult<CopyRecord>>> {
+        let (Some(root_tree_id), Some(head_tree_id)) = (
+            self.resolved_root_tree_id(root)?,
+            self.resolved_root_tree_id(head)?,
+        ) else {
+            return Ok(Box::pin(stream::empty()));
+        };
+        let targets = if let Some(paths) = paths {
+            let mut targets = vec![];
+            for path in paths {
+                let Some((_, copy_id)) = self.find_file(&head_tree_id, path)? else {
+                    continue;
+                };
+                let root_copy_id = self.find_file(&root_tree_id, path)?.map(|(_, id)| id);
+                if root_copy_id.as_ref() != Some(&copy_id) {
+                    targets.push((path.clone(), copy_id));
+                }
+            }
+            targets
+        } else {
+            self.list_changed_files(&root_tree_id, &head_tree_id)?
+        };
+        let mut records = vec![];
+        for (target, copy_id) in targets {
+            if copy_id.as_bytes().is_empty() {
+                continue;
+            }
+            // Find the nearest copy history which exists in the root tree at
+            // its current path.
+            let mut visited = HashSet::new();
+            let mut to_visit = vec![copy_id];
+            while let Some(id) = to_visit.pop() {
+                if !visited.insert(id.clone()) {
+                    continue;
+                }
+                let Some(copy) = self.try_read_copy(&id)? else {
+                    continue;
+                };
+                let source_file = self
+                    .find_file(&root_tree_id, &copy.current_path)?
+                    .filter(|(_, source_copy_id)| *source_copy_id == id)
+                    .map(|(file_id, _)| file_id);
+                if let Some(source_file) = source_file {
+                    if copy.current_path != target {
+                        records.push(Ok(CopyRecord {
+                            target: target.clone(),
+                            target_commit: head.clone(),
+                            source: copy.current_path,
+                            source_file,
+                            source_commit: root.clone(),
+                        }));
+                    }
+                    break;
+                }
+                to_visit.extend(copy.parents);
+            }
+        }
+        Ok(Box::pin(stream::iter(records)))
+    }
+
+    fn gc(&self, index: &dyn Index, keep_newer: SystemTime) -> BackendResult<()> {
+        let heads = index
+            .all_heads_for_gc()
+            .map_err(to_other_err)?
+            .collect_vec();
+        let reachable = self.collect_reachable_objects(heads)?;
+        self.repack(&reachable, keep_newer)
+    }
+}
+
+fn copy_history_to_proto(copy: &CopyHistory) -> crate::protos::simple_store::CopyHistory {
+    crate::protos::simple_store::CopyHistory {
+        current_path: copy.current_path.as_internal_file_string().to_owned(),
+        parents: copy.parents.iter().map(|id| id.to_bytes()).collect(),
+        salt: copy.salt.clone(),
+    }
+}
+
+fn copy_history_from_proto(
+    proto: crate::protos::simple_store::CopyHistory,
+) -> BackendResult<CopyHistory> {
+    let current_path =
+        RepoPathBuf::from_internal_string(proto.current_path).map_err(to_other_err)?;
+    Ok(CopyHistory {
+        current_path,
+        parents: proto.parents.into_iter().map(CopyId::new).collect(),
+        salt: proto.salt,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt as _;
+    use pollster::FutureExt as _;
+
+    use super::*;
+    use crate::backend::MergedTreeId;
+    use crate::repo_path::RepoPathComponentBuf;
+    use crate::tests::new_temp_dir;
+
+    fn copy_history(path: &str, parents: &[CopyId]) -> CopyHistory {
+        CopyHistory {
+            current_path: RepoPathBuf::from_internal_string(path).unwrap(),
+            parents: parents.to_vec(),
+            salt: vec![],
+        }
+    }
+
+    #[test]
+    fn test_pack_index_roundtrip() {
+        let entries = vec![
+            PackEntry {
+                kind: ObjectKind::Commit,
+                id: vec![1; OBJECT_ID_LENGTH],
+                offset: 0,
+                len: 10,
+            },
+            PackEntry {
+                kind: ObjectKind::Copy,
+                id: vec![0; OBJECT_ID_LENGTH],
+                offset: 10,
+                len: u64::MAX,
+            },
+        ];
+        let data = encode_pack_index(&entries);
+        assert_eq!(
+            data.len(),
+            PACK_INDEX_HEADER_SIZE + 2 * PACK_INDEX_ENTRY_SIZE
+        );
+        let decoded = decode_pack_index(&data).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[1].kind, ObjectKind::Copy);
+        assert_eq!(decoded[1].len, u64::MAX);
+
+        // Truncated or unknown data is rejected
+        assert!(decode_pack_index(&data[..data.len() - 1]).is_none());
+        assert!(decode_pack_index(&data[..4]).is_none());
+        let mut bad_kind = data.clone();
+        bad_kind[PACK_INDEX_HEADER_SIZE] = 0xff;
+        assert!(decode_pack_index(&bad_kind).is_none());
+    }
+
+    #[test]
+    fn test_copy_records() {
+        let temp_dir = new_temp_dir();
+        let backend = NativeBackend::init(temp_dir.path()).unwrap();
+        let file_id = backend
+            .write_file(RepoPath::root(), &mut &b"content\n"[..])
+            .block_on()
+            .unwrap();
+        let write_commit = |name: &str, copy_id: &CopyId| {
+            let value = TreeValue::File {
+                id: file_id.clone(),
+                executable: false,
+                copy_id: copy_id.clone(),
+            };
+            let name = RepoPathComponentBuf::new(name).unwrap();
+            let tree = Tree::from_sorted_entries(vec![(name, value)]);
+            let tree_id = backend
+                .write_tree(RepoPath::root(), &tree)
+                .block_on()
+                .unwrap();
+            let mut commit =
+                make_root_commit(ChangeId::new(vec![1; CHANGE_ID_LENGTH]), tree_id.clone());
+            commit.parents = vec![backend.root_commit_id().clone()];
+            commit.root_tree = MergedTreeId::resolved(tree_id);
+            let (id, _) = backend.write_commit(commit, None).block_on().unwrap();
+            id
+        };
+
+        let copy1_id = backend
+            .write_copy(&copy_history("foo", &[]))
+            .block_on()
+            .unwrap();
+        let copy2_id = backend
+            .write_copy(&copy_history("bar", std::slice::from_ref(&copy1_id)))
+            .block_on()
+            .unwrap();
+        let commit1_id = write_commit("foo", &copy1_id);
+        let commit2_id = write_commit("bar", &copy2_id);
+        let get_records = |paths: Option<&[RepoPathBuf]>, root, head| {
+            backend
+                .get_copy_records(paths, root, head)
+                .unwrap()
+                .collect::<Vec<_>>()
+                .block_on()
+                .into_iter()
+                .map(|record| {
+                    let record = record.unwrap();
+                    (record.source, record.target)
+                })
+                .collect_vec()
+        };
+        let foo = RepoPathBuf::from_internal_string("foo").unwrap();
+        let bar = RepoPathBuf::from_internal_string("bar").unwrap();
+        assert_eq!(
+            get_records(None, &commit1_id, &commit2_id),
+            [(foo.clone(), bar.clone())]
+        );
+        assert_eq!(
+            get_records(Some(std::slice::from_ref(&foo)), &commit1_id, &commit2_id),
+            []
+        );
+        // Not a copy in the reverse direction
+        assert_eq!(get_records(None, &commit2_id, &commit1_id), []);
+    }
+
+    #[test]
+    fn test_related_copies() {
+        let temp_dir = new_temp_dir();
+        let backend = NativeBackend::init(temp_dir.path()).unwrap();
+
+        let copy1 = copy_history("foo1", &[]);
+        let copy1_id = backend.write_copy(&copy1).block_on().unwrap();
+        let copy2 = copy_history("foo2", std::slice::from_ref(&copy1_id));
+        let copy2_id = backend.write_copy(&copy2).block_on().unwrap();
+        let copy3 = copy_history("foo3", std::slice::from_ref(&copy2_id));
+        let copy3_id = backend.write_copy(&copy3).block_on().unwrap();
+        let unrelated = copy_history("bar", &[]);
+        backend.write_copy(&unrelated).block_on().unwrap();
+
+        assert_eq!(backend.read_copy(&copy2_id).block_on().unwrap(), copy2);
+        assert!(
+            backend
+                .get_related_copies(&CopyId::from_hex("abcd"))
+                .block_on()
+                .is_err()
+        );
+        let related = backend.get_related_copies(&copy1_id).block_on().unwrap();
+        assert_eq!(related, vec![copy3.clone(), copy2.clone(), copy1.clone()]);
+        let related = backend.get_related_copies(&copy3_id).block_on().unwrap();
+        assert_eq!(related, vec![copy3, copy2, copy1]);
+    }
+}
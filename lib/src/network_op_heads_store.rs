@@ -0,0 +1,194 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Operation heads store for repositories on network file systems.
+//!
+//! The on-disk layout is the same as [`SimpleOpHeadsStore`]: each head is an
+//! empty file named after the operation id. Since removal of the old head files
+//! may not be visible to other clients immediately (or may fail halfway), each
+//! new head file also lists the ids of the heads it replaced. Heads listed by
+//! another head are ignored. The heads directory is locked by a
+//! [`LeaseLock`] instead of `flock()`, which is unreliable over NFS and SMB.
+//!
+//! [`SimpleOpHeadsStore`]: crate::simple_op_heads_store::SimpleOpHeadsStore
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use itertools::Itertools as _;
+
+use crate::file_util::IoResultExt as _;
+use crate::hex_util;
+use crate::lock::LeaseLock;
+use crate::lock::LeaseLockOptions;
+use crate::object_id::ObjectId as _;
+use crate::op_heads_store::OpHeadsStore;
+use crate::op_heads_store::OpHeadsStoreError;
+use crate::op_heads_store::OpHeadsStoreLock;
+use crate::op_store::OperationId;
+use crate::settings::UserSettings;
+use crate::simple_op_heads_store::SimpleOpHeadsStoreInitError;
+
+/// Operation heads store which tolerates network file systems with weak
+/// consistency and unreliable locking.
+pub struct NetworkOpHeadsStore {
+    dir: PathBuf,
+    lock_options: LeaseLockOptions,
+}
+
+impl Debug for NetworkOpHeadsStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NetworkOpHeadsStore")
+            .field("dir", &self.dir)
+            .finish_non_exhaustive()
+    }
+}
+
+impl NetworkOpHeadsStore {
+    /// Returns the name of the store type.
+    pub fn name() -> &'static str {
+        "network_op_heads_store"
+    }
+
+    /// Creates an empty store in the given directory.
+    pub fn init(dir: &Path, settings: &UserSettings) -> Result<Self, SimpleOpHeadsStoreInitError> {
+        let op_heads_dir = dir.join("heads");
+        fs::create_dir(&op_heads_dir).context(&op_heads_dir)?;
+        Ok(Self::load_heads_dir(op_heads_dir, settings))
+    }
+
+    /// Loads an existing store from the given directory.
+    pub fn load(dir: &Path, settings: &UserSettings) -> Self {
+        Self::load_heads_dir(dir.join("heads"), settings)
+    }
+
+    fn load_heads_dir(dir: PathBuf, settings: &UserSettings) -> Self {
+        let lock_options = LeaseLockOptions {
+            hostname: settings.operation_hostname().to_owned(),
+            ..LeaseLockOptions::default()
+        };
+        Self { dir, lock_options }
+    }
+
+    /// Path to the lease lock file.
+    pub fn lock_path(&self) -> PathBuf {
+        self.dir.join("lock")
+    }
+
+    /// Options used to acquire the lease lock.
+    pub fn lock_options(&self) -> &LeaseLockOptions {
+        &self.lock_options
+    }
+
+    fn add_op_head(&self, id: &OperationId, replaced_ids: &[OperationId]) -> io::Result<()> {
+        let content: String = replaced_ids
+            .iter()
+            .map(|id| format!("{}\n", id.hex()))
+            .collect();
+        // The file isn't renamed into place because rename isn't atomic on
+        // some network file systems. If the content is truncated, the
+        // replaced heads will reappear, but they are ancestors of the new head
+        // and will be resolved as such.
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(self.dir.join(id.hex()))?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    }
+
+    fn remove_op_head(&self, id: &OperationId) -> io::Result<()> {
+        match fs::remove_file(self.dir.join(id.hex())) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+struct NetworkOpHeadsStoreLock {
+    _lock: LeaseLock,
+}
+
+impl OpHeadsStoreLock for NetworkOpHeadsStoreLock {}
+
+#[async_trait]
+impl OpHeadsStore for NetworkOpHeadsStore {
+    fn name(&self) -> &str {
+        Self::name()
+    }
+
+    async fn update_op_heads(
+        &self,
+        old_ids: &[OperationId],
+        new_id: &OperationId,
+    ) -> Result<(), OpHeadsStoreError> {
+        assert!(!old_ids.contains(new_id));
+        let to_write_error = |err: io::Error| OpHeadsStoreError::Write {
+            new_op_id: new_id.clone(),
+            source: err.into(),
+        };
+        self.add_op_head(new_id, old_ids).map_err(to_write_error)?;
+        for old_id in old_ids {
+            // The old head is ignored even if the removal isn't visible to
+            // other clients yet.
+            self.remove_op_head(old_id).map_err(to_write_error)?;
+        }
+        Ok(())
+    }
+
+    async fn get_op_heads(&self) -> Result<Vec<OperationId>, OpHeadsStoreError> {
+        let to_read_error = |err: io::Error| OpHeadsStoreError::Read(err.into());
+        let mut op_heads = vec![];
+        let mut replaced_ids = HashSet::new();
+        for op_head_entry in fs::read_dir(&self.dir).map_err(to_read_error)? {
+            let op_head_path = op_head_entry.map_err(to_read_error)?.path();
+            let Some(op_head) = op_head_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(hex_util::decode_hex)
+            else {
+                continue;
+            };
+            let content = match fs::read_to_string(&op_head_path) {
+                Ok(content) => content,
+                // Replaced by another process in the meantime
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(to_read_error(err)),
+            };
+            replaced_ids.extend(content.lines().filter_map(hex_util::decode_hex));
+            op_heads.push(OperationId::new(op_head));
+        }
+        let op_heads = op_heads
+            .into_iter()
+            .filter(|id| !replaced_ids.contains(id.as_bytes()))
+            .collect_vec();
+        Ok(op_heads)
+    }
+
+    async fn lock(&self) -> Result<Box<dyn OpHeadsStoreLock + '_>, OpHeadsStoreError> {
+        let lock = LeaseLock::lock(self.lock_path(), &self.lock_options)
+            .map_err(|err| OpHeadsStoreError::Lock(err.into()))?;
+        Ok(Box::new(NetworkOpHeadsStoreLock { _lock: lock }))
+    }
+}
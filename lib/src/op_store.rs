@@ -24,6 +24,8 @@ use std::sync::LazyLock;
 use std::time::SystemTime;
 
 use async_trait::async_trait;
+use blake2::Blake2b512;
+use blake2::Digest as _;
 use itertools::Itertools as _;
 use thiserror::Error;
 
@@ -31,6 +33,7 @@ use crate::backend::CommitId;
 use crate::backend::MillisSinceEpoch;
 use crate::backend::Timestamp;
 use crate::content_hash::ContentHash;
+use crate::content_hash::DigestUpdate;
 use crate::merge::Merge;
 use crate::object_id::HexPrefix;
 use crate::object_id::ObjectId as _;
@@ -357,7 +360,7 @@ pub struct TimestampRange {
 /// taken and it will be checked that the current head of the operation
 /// graph is unchanged. If the current head has changed, there has been
 /// concurrent operation.
-#[derive(ContentHash, PartialEq, Eq, Clone, Debug, serde::Serialize)]
+#[derive(PartialEq, Eq, Clone, Debug, serde::Serialize)]
 pub struct Operation {
     #[serde(skip)] // TODO: should be exposed?
     pub view_id: ViewId,
@@ -381,6 +384,20 @@ pub struct Operation {
     // cost matters, maybe this can be changed to sorted Vec.
     #[serde(skip)] // TODO: should be exposed?
     pub commit_predecessors: Option<BTreeMap<CommitId, Vec<CommitId>>>,
+    /// Cryptographic signature of [`Operation::signed_data()`], or `None` if
+    /// the operation wasn't signed.
+    #[serde(skip)]
+    pub signature: Option<Vec<u8>>,
+}
+
+impl ContentHash for Operation {
+    fn hash(&self, state: &mut impl DigestUpdate) {
+        self.hash_unsigned(state);
+        // Unsigned operations hash the same as before signing was supported.
+        if let Some(signature) = &self.signature {
+            signature.hash(state);
+        }
+    }
 }
 
 impl Operation {
@@ -409,8 +426,34 @@ impl Operation {
             // may be other commits created within the abandoned operations.
             // They don't have any predecessors records as well.
             commit_predecessors: Some(BTreeMap::new()),
+            signature: None,
         }
     }
+
+    /// Returns the data to be signed, which is the content hash of the
+    /// operation excluding the signature.
+    ///
+    /// Since the operation refers to its parents by content-addressed ids, the
+    /// signature also covers the whole chain of ancestor operations.
+    pub fn signed_data(&self) -> Vec<u8> {
+        let mut hasher = Blake2b512::new();
+        self.hash_unsigned(&mut hasher);
+        hasher.finalize().to_vec()
+    }
+
+    fn hash_unsigned(&self, state: &mut impl DigestUpdate) {
+        let Self {
+            view_id,
+            parents,
+            metadata,
+            commit_predecessors,
+            signature: _,
+        } = self;
+        view_id.hash(state);
+        parents.hash(state);
+        metadata.hash(state);
+        commit_predecessors.hash(state);
+    }
 }
 
 #[derive(ContentHash, PartialEq, Eq, Clone, Debug, serde::Serialize)]
@@ -24,21 +24,33 @@ use itertools::Itertools as _;
 use pollster::FutureExt as _;
 use thiserror::Error;
 
+use crate::backend::CommitId;
+use crate::backend::SigningFn;
 use crate::dag_walk;
+use crate::index::IndexError;
 use crate::object_id::HexPrefix;
 use crate::object_id::PrefixResolution;
 use crate::op_heads_store;
 use crate::op_heads_store::OpHeadResolutionError;
 use crate::op_heads_store::OpHeadsStore;
 use crate::op_heads_store::OpHeadsStoreError;
+use crate::op_store;
 use crate::op_store::OpStore;
 use crate::op_store::OpStoreError;
 use crate::op_store::OpStoreResult;
 use crate::op_store::OperationId;
 use crate::operation::Operation;
+use crate::opset;
+use crate::opset::OpsetEvaluator;
+use crate::opset::OpsetParseContext;
+use crate::opset::OpsetParseError;
 use crate::repo::ReadonlyRepo;
-use crate::repo::Repo as _;
+use crate::repo::Repo;
 use crate::repo::RepoLoader;
+use crate::revset::RevsetEvaluationError;
+use crate::revset::RevsetExpression;
+use crate::signing::SignError;
+use crate::view::View;
 
 /// Error that may occur during evaluation of operation set expression.
 #[derive(Debug, Error)]
@@ -55,6 +67,26 @@ pub enum OpsetEvaluationError {
     /// Failed to access operation object.
     #[error(transparent)]
     OpStore(#[from] OpStoreError),
+    /// Failed to parse operation set expression.
+    #[error(transparent)]
+    OpsetParse(#[from] OpsetParseError),
+    /// Failed to look up commits referenced by operation set expression.
+    #[error(transparent)]
+    Index(#[from] IndexError),
+    /// Failed to evaluate commits referenced by operation set expression.
+    #[error(transparent)]
+    RevsetEvaluation(#[from] RevsetEvaluationError),
+}
+
+/// Error that may occur while rewriting operations.
+#[derive(Debug, Error)]
+pub enum OpRewriteError {
+    /// Failed to access operation object.
+    #[error(transparent)]
+    OpStore(#[from] OpStoreError),
+    /// Failed to sign rewritten operation.
+    #[error("Failed to sign operation")]
+    Sign(#[from] SignError),
 }
 
 /// Error that may occur during parsing and resolution of operation set
@@ -102,7 +134,8 @@ pub fn resolve_op_for_load(
         })
     };
     let get_head_ops = || get_current_head_ops(op_store, op_heads_store);
-    resolve_single_op(op_store, get_current_op, get_head_ops, op_str)
+    let evaluator = OpsetEvaluator::new(op_store, &get_current_op, &get_head_ops, None);
+    resolve_single_op(&evaluator, op_str, &OpsetParseContext::new())
 }
 
 /// Resolves operation set expression against the loaded repo.
@@ -121,6 +154,42 @@ pub fn resolve_op_at(
     head_ops: &[Operation],
     op_str: &str,
 ) -> Result<Operation, OpsetEvaluationError> {
+    resolve_op_at_with_context(op_store, head_ops, op_str, &OpsetParseContext::new())
+}
+
+/// Resolves operation set expression at the given head operations. Functions
+/// like `touching()` are resolved by the `context`.
+pub fn resolve_op_at_with_context(
+    op_store: &Arc<dyn OpStore>,
+    head_ops: &[Operation],
+    op_str: &str,
+    context: &OpsetParseContext,
+) -> Result<Operation, OpsetEvaluationError> {
+    with_evaluator_at(op_store, head_ops, context, |evaluator| {
+        resolve_single_op(evaluator, op_str, context)
+    })
+}
+
+/// Resolves operation set expression at the given head operations to any
+/// number of operations. The order of the returned operations is unspecified.
+pub fn resolve_ops_at(
+    op_store: &Arc<dyn OpStore>,
+    head_ops: &[Operation],
+    op_str: &str,
+    context: &OpsetParseContext,
+) -> Result<Vec<Operation>, OpsetEvaluationError> {
+    with_evaluator_at(op_store, head_ops, context, |evaluator| {
+        let expression = opset::parse(op_str, context)?;
+        evaluator.evaluate(&expression)
+    })
+}
+
+fn with_evaluator_at<T>(
+    op_store: &Arc<dyn OpStore>,
+    head_ops: &[Operation],
+    context: &OpsetParseContext,
+    f: impl FnOnce(&OpsetEvaluator) -> Result<T, OpsetEvaluationError>,
+) -> Result<T, OpsetEvaluationError> {
     let get_current_op = || match head_ops {
         [head_op] => Ok(head_op.clone()),
         [] => Err(OpsetResolutionError::EmptyOperations("@".to_owned()).into()),
@@ -131,22 +200,55 @@ pub fn resolve_op_at(
         .into()),
     };
     let get_head_ops = || Ok(head_ops.to_vec());
-    resolve_single_op(op_store, get_current_op, get_head_ops, op_str)
+    let evaluator = OpsetEvaluator::new(
+        op_store,
+        &get_current_op,
+        &get_head_ops,
+        context.commit_resolver,
+    );
+    f(&evaluator)
 }
 
-/// Resolves operation set expression with the given "@" symbol resolution
-/// callbacks.
+/// Resolves operation set expression to exactly one operation.
 fn resolve_single_op(
-    op_store: &Arc<dyn OpStore>,
-    get_current_op: impl FnOnce() -> Result<Operation, OpsetEvaluationError>,
-    get_head_ops: impl FnOnce() -> Result<Vec<Operation>, OpsetEvaluationError>,
+    evaluator: &OpsetEvaluator,
+    op_str: &str,
+    context: &OpsetParseContext,
+) -> Result<Operation, OpsetEvaluationError> {
+    let op_symbol = op_str.trim_end_matches(['-', '+']);
+    // Operation id or "@" followed by neighbor operators can be resolved
+    // without walking the whole operation history. This also reports the
+    // exact subexpression that resolved to multiple operations.
+    if op_symbol == "@" || op_symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return resolve_single_op_symbol(evaluator, op_str);
+    }
+    let expression = opset::parse(op_str, context)?;
+    let mut ops = evaluator.evaluate(&expression)?;
+    match ops.len() {
+        0 => Err(OpsetResolutionError::EmptyOperations(op_str.to_owned()).into()),
+        1 => Ok(ops.pop().unwrap()),
+        _ => Err(OpsetResolutionError::MultipleOperations {
+            expr: op_str.to_owned(),
+            candidates: ops.iter().map(|op| op.id().clone()).collect(),
+        }
+        .into()),
+    }
+}
+
+/// Resolves operation id or "@" symbol followed by `-`/`+` operators.
+fn resolve_single_op_symbol(
+    evaluator: &OpsetEvaluator,
     op_str: &str,
 ) -> Result<Operation, OpsetEvaluationError> {
+    let op_store = evaluator.op_store;
     let op_symbol = op_str.trim_end_matches(['-', '+']);
     let op_postfix = &op_str[op_symbol.len()..];
-    let head_ops = op_postfix.contains('+').then(get_head_ops).transpose()?;
+    let head_ops = op_postfix
+        .contains('+')
+        .then(evaluator.get_head_ops)
+        .transpose()?;
     let mut operation = match op_symbol {
-        "@" => get_current_op(),
+        "@" => (evaluator.get_current_op)(),
         s => resolve_single_op_from_store(op_store, s),
     }?;
     for (i, c) in op_postfix.chars().enumerate() {
@@ -177,7 +279,7 @@ fn resolve_single_op(
     Ok(operation)
 }
 
-fn resolve_single_op_from_store(
+pub(crate) fn resolve_single_op_from_store(
     op_store: &Arc<dyn OpStore>,
     op_str: &str,
 ) -> Result<Operation, OpsetEvaluationError> {
@@ -330,6 +432,120 @@ fn collect_ancestors_until_roots(
     items
 }
 
+/// Operation at which a value in the view changed.
+#[derive(Clone, Debug)]
+pub struct OpValueChange<T> {
+    /// The operation that changed the value.
+    pub operation: Operation,
+    /// Value at the parent operations.
+    pub old_value: T,
+    /// Value at the operation.
+    pub new_value: T,
+}
+
+/// Walks `head_ops` and their ancestors in reverse topological order, yielding
+/// operations at which `value_of(view)` changed.
+///
+/// If the parent operations disagree on the value (e.g. at a merge operation),
+/// the change is attributed to the operations that introduced it instead. The
+/// root operation is never reported.
+pub fn walk_value_changes<T, E>(
+    head_ops: &[Operation],
+    mut value_of: impl FnMut(&View) -> Result<T, E>,
+) -> impl Iterator<Item = Result<OpValueChange<T>, E>>
+where
+    T: Clone + Eq,
+    E: From<OpStoreError>,
+{
+    // Values of the operations to be visited. Since children are visited
+    // before their parents, each value can be dropped once visited.
+    let mut values: HashMap<OperationId, T> = HashMap::new();
+    let mut value_at = move |op: &Operation, remove: bool| -> Result<T, E> {
+        let cached = if remove {
+            values.remove(op.id())
+        } else {
+            values.get(op.id()).cloned()
+        };
+        if let Some(value) = cached {
+            return Ok(value);
+        }
+        let value = value_of(&op.view()?)?;
+        if !remove {
+            values.insert(op.id().clone(), value.clone());
+        }
+        Ok(value)
+    };
+    walk_ancestors(head_ops).filter_map(move |op| {
+        let visit = || -> Result<Option<OpValueChange<T>>, E> {
+            let op = op?;
+            let new_value = value_at(&op, true)?;
+            let parent_values: Vec<T> = op
+                .parents()
+                .map(|parent| value_at(&parent?, false))
+                .try_collect()?;
+            let Some(old_value) = changed_from_parents(&new_value, &parent_values) else {
+                return Ok(None);
+            };
+            Ok(Some(OpValueChange {
+                operation: op,
+                old_value: old_value.clone(),
+                new_value,
+            }))
+        };
+        visit().transpose()
+    })
+}
+
+/// Returns the value all `parent_values` agree on if the `new_value` differs
+/// from it.
+///
+/// If the parents disagree (e.g. at a merge operation), the change is
+/// attributed to the operation that introduced it instead, and `None` is
+/// returned.
+pub(crate) fn changed_from_parents<'a, T: Eq>(
+    new_value: &T,
+    parent_values: &'a [T],
+) -> Option<&'a T> {
+    let (old_value, rest) = parent_values.split_first()?;
+    if rest.iter().any(|value| value != old_value) || old_value == new_value {
+        return None;
+    }
+    Some(old_value)
+}
+
+/// Returns the `commit_ids` that are ancestors of the heads of the `view`.
+///
+/// The commits and heads are looked up in the index of the `repo`. The ones
+/// that aren't indexed are ignored.
+pub fn visible_commit_ids(
+    repo: &dyn Repo,
+    view: &View,
+    commit_ids: &[CommitId],
+) -> Result<Vec<CommitId>, RevsetEvaluationError> {
+    let index = repo.index();
+    let indexed_ids = |ids: &mut dyn Iterator<Item = &CommitId>| -> Result<Vec<_>, _> {
+        ids.filter_map(|id| {
+            index
+                .has_id(id)
+                .map(|found| found.then(|| id.clone()))
+                .transpose()
+        })
+        .try_collect()
+        .map_err(|err: IndexError| RevsetEvaluationError::Other(err.into()))
+    };
+    let candidate_ids = indexed_ids(&mut commit_ids.iter())?;
+    if candidate_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let head_ids = indexed_ids(&mut view.heads().iter())?;
+    // A single walk from the heads is cheaper than testing each pair of
+    // candidate and head.
+    let expression = RevsetExpression::commits(candidate_ids)
+        .intersection(&RevsetExpression::commits(head_ids).ancestors());
+    let ids = expression.evaluate(repo)?.iter().try_collect()?;
+    Ok(ids)
+}
+
 /// Stats about `reparent_range()`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ReparentStats {
@@ -350,13 +566,16 @@ pub struct ReparentStats {
 ///
 /// If the source operation range `root_ops..head_ops` was empty, the
 /// `new_head_ids` will be `[dest_op.id()]`, meaning the `dest_op` is the head.
+///
+/// The rewritten operations are signed by `sign_with`, or left unsigned.
 // TODO: Find better place to host this function. It might be an OpStore method.
 pub fn reparent_range(
     op_store: &dyn OpStore,
     root_ops: &[Operation],
     head_ops: &[Operation],
     dest_op: &Operation,
-) -> OpStoreResult<ReparentStats> {
+    mut sign_with: Option<&mut SigningFn>,
+) -> Result<ReparentStats, OpRewriteError> {
     let ops_to_reparent: Vec<_> = walk_ancestors_range(head_ops, root_ops).try_collect()?;
     let unreachable_count = walk_ancestors_range(root_ops, slice::from_ref(dest_op))
         .process_results(|iter| iter.count())?;
@@ -377,7 +596,7 @@ pub fn reparent_range(
             .filter_map(|id| rewritten_ids.get(id).or_else(|| dest_once.take()))
             .cloned()
             .collect();
-        let new_id = op_store.write_operation(&data).block_on()?;
+        let new_id = write_rewritten_operation(op_store, data, sign_with.as_deref_mut())?;
         rewritten_ids.insert(old_op.id().clone(), new_id);
     }
 
@@ -393,3 +612,95 @@ pub fn reparent_range(
         unreachable_count,
     })
 }
+
+/// Writes the rewritten operation `data`. The old signature doesn't cover the
+/// new content, so it's replaced with a signature made by `sign_with`.
+fn write_rewritten_operation(
+    op_store: &dyn OpStore,
+    mut data: op_store::Operation,
+    sign_with: Option<&mut SigningFn>,
+) -> Result<OperationId, OpRewriteError> {
+    data.signature = None;
+    if let Some(sign) = sign_with {
+        data.signature = Some(sign(&data.signed_data())?);
+    }
+    Ok(op_store.write_operation(&data).block_on()?)
+}
+
+/// Removes the `abandon_ops` from the history of the `head_ops`.
+///
+/// Descendants of the abandoned operations are reparented onto the nearest
+/// ancestors that aren't abandoned. Returns the new head operation ids in
+/// order of the old `head_ops` as well as some stats. The `head_ops` and the
+/// root operation must not be abandoned.
+///
+/// The rewritten operations are signed by `sign_with`, or left unsigned.
+pub fn abandon_ops(
+    op_store: &dyn OpStore,
+    head_ops: &[Operation],
+    abandon_ops: &[Operation],
+    mut sign_with: Option<&mut SigningFn>,
+) -> Result<ReparentStats, OpRewriteError> {
+    let abandon_ids: HashSet<&OperationId> = abandon_ops.iter().map(|op| op.id()).collect();
+    assert!(
+        !abandon_ids.contains(op_store.root_operation_id()),
+        "root operation cannot be abandoned"
+    );
+
+    // Descendants of the abandoned operations are visited first.
+    let mut pending_ids = abandon_ids.clone();
+    let mut ops_to_visit = Vec::new();
+    for op in walk_ancestors(head_ops) {
+        if pending_ids.is_empty() {
+            break;
+        }
+        let op = op?;
+        pending_ids.remove(op.id());
+        ops_to_visit.push(op);
+    }
+
+    let mut new_parent_ids: HashMap<OperationId, Vec<OperationId>> = HashMap::new();
+    let map_parents = |new_parent_ids: &HashMap<_, Vec<_>>, op: &Operation| {
+        op.parent_ids()
+            .iter()
+            .flat_map(|id| {
+                new_parent_ids
+                    .get(id)
+                    .map_or(slice::from_ref(id), |ids| ids.as_slice())
+            })
+            .unique()
+            .cloned()
+            .collect_vec()
+    };
+    let mut rewritten_count = 0;
+    let mut unreachable_count = 0;
+    for old_op in ops_to_visit.iter().rev() {
+        let parent_ids = map_parents(&new_parent_ids, old_op);
+        if abandon_ids.contains(old_op.id()) {
+            new_parent_ids.insert(old_op.id().clone(), parent_ids);
+            unreachable_count += 1;
+        } else if parent_ids != old_op.parent_ids() {
+            let mut data = old_op.store_operation().clone();
+            data.parents = parent_ids;
+            let new_id = write_rewritten_operation(op_store, data, sign_with.as_deref_mut())?;
+            new_parent_ids.insert(old_op.id().clone(), vec![new_id]);
+            rewritten_count += 1;
+        }
+    }
+
+    let new_head_ids = head_ops
+        .iter()
+        .map(|op| match new_parent_ids.get(op.id()) {
+            Some(ids) => {
+                assert!(!abandon_ids.contains(op.id()), "head cannot be abandoned");
+                ids[0].clone()
+            }
+            None => op.id().clone(),
+        })
+        .collect();
+    Ok(ReparentStats {
+        new_head_ids,
+        rewritten_count,
+        unreachable_count,
+    })
+}
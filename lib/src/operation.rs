@@ -32,6 +32,9 @@ use crate::op_store::OpStoreResult;
 use crate::op_store::OperationId;
 use crate::op_store::OperationMetadata;
 use crate::op_store::ViewId;
+use crate::signing::SignResult;
+use crate::signing::Signer;
+use crate::signing::Verification;
 use crate::view::View;
 
 /// A wrapper around [`op_store::Operation`] that defines additional methods and
@@ -123,6 +126,21 @@ impl Operation {
         &self.data.metadata
     }
 
+    /// Returns true if the operation has a cryptographic signature.
+    pub fn is_signed(&self) -> bool {
+        self.data.signature.is_some()
+    }
+
+    /// Verifies the operation signature. Returns `None` if the operation isn't
+    /// signed.
+    pub fn verification(&self, signer: &Signer) -> SignResult<Option<Verification>> {
+        self.data
+            .signature
+            .as_ref()
+            .map(|sig| signer.verify_operation(&self.id, &self.data.signed_data(), sig))
+            .transpose()
+    }
+
     /// Returns true if predecessors are recorded in this operation.
     ///
     /// This returns false only if the operation was written by jj < 0.30.
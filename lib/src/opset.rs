@@ -0,0 +1,489 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Functional language for selecting a set of operations.
+
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error;
+use std::sync::Arc;
+use std::sync::LazyLock;
+
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+
+use crate::backend::CommitId;
+use crate::dsl_util::collect_similar;
+use crate::op_store::OpStore;
+use crate::op_store::OperationId;
+use crate::op_walk;
+use crate::op_walk::OpsetEvaluationError;
+use crate::operation::Operation;
+use crate::opset_parser;
+use crate::opset_parser::BinaryOp;
+use crate::opset_parser::ExpressionKind;
+use crate::opset_parser::ExpressionNode;
+use crate::opset_parser::FunctionCallNode;
+pub use crate::opset_parser::OpsetParseError;
+pub use crate::opset_parser::OpsetParseErrorKind;
+pub use crate::opset_parser::OpsetParseResult;
+use crate::opset_parser::UnaryOp;
+use crate::repo::Repo;
+use crate::str_util::StringPattern;
+use crate::time_util::DatePattern;
+use crate::time_util::DatePatternContext;
+use crate::view::View;
+
+/// Resolves commits referenced by opset expression.
+pub trait OpsetCommitResolver {
+    /// Evaluates the revset `text` to commit ids.
+    fn resolve_commits(
+        &self,
+        text: &str,
+    ) -> Result<Vec<CommitId>, Box<dyn error::Error + Send + Sync>>;
+
+    /// Repo to look up the resolved commits in. This is called only after
+    /// [`Self::resolve_commits()`] succeeded.
+    fn repo(&self) -> &dyn Repo;
+}
+
+/// Context needed to resolve opset expression.
+#[derive(Clone, Copy)]
+pub struct OpsetParseContext<'a> {
+    /// Context to interpret date patterns.
+    pub date_pattern_context: DatePatternContext,
+    /// Resolves revsets passed to `touching()`. The function isn't available
+    /// if unset.
+    pub commit_resolver: Option<&'a dyn OpsetCommitResolver>,
+}
+
+impl OpsetParseContext<'_> {
+    /// Creates context with no commit resolver and the current local time.
+    pub fn new() -> Self {
+        OpsetParseContext {
+            date_pattern_context: chrono::Local::now().into(),
+            commit_resolver: None,
+        }
+    }
+}
+
+impl Default for OpsetParseContext<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Predicate to filter operations by their metadata or content.
+#[derive(Clone, Debug)]
+pub enum OpsetFilterPredicate {
+    /// Operations with matching description.
+    Description(StringPattern),
+    /// Operations run by matching `username@hostname` or user name.
+    User(StringPattern),
+    /// Operations finished in the time range.
+    Time(DatePattern),
+    /// Operations that created, rewrote, hid, revealed, or pointed refs to or
+    /// away from any of the commits.
+    Touching(Vec<CommitId>),
+    /// Operations that changed the working-copy commit of matching workspaces.
+    Workspace(StringPattern),
+}
+
+/// Tree of opset expressions.
+#[derive(Clone, Debug)]
+pub enum OpsetExpression {
+    /// Set of no operations.
+    None,
+    /// All operations reachable from the current heads.
+    All,
+    /// The current operation. (`@`)
+    CurrentOperation,
+    /// The current head operations.
+    Heads,
+    /// The root operation.
+    Root,
+    /// Operation id or its unique prefix.
+    IdPrefix(String),
+    /// Parents of the operations. (`x-`)
+    Parents(Box<Self>),
+    /// Children of the operations. (`x+`)
+    Children(Box<Self>),
+    /// Ancestors of `heads` excluding ancestors of `roots`. (`roots..heads`)
+    Range {
+        /// Operations whose ancestors are excluded.
+        roots: Box<Self>,
+        /// Operations whose ancestors are included.
+        heads: Box<Self>,
+    },
+    /// Operations matching the predicate.
+    Filter(OpsetFilterPredicate),
+    /// Operations not in the set. (`~x`)
+    NotIn(Box<Self>),
+    /// Union of the sets. (`x | y | ..`)
+    UnionAll(Vec<Self>),
+    /// Intersection of the sets. (`x & y`)
+    Intersection(Box<Self>, Box<Self>),
+    /// Difference of the sets. (`x ~ y`)
+    Difference(Box<Self>, Box<Self>),
+}
+
+type OpsetFunction =
+    for<'a> fn(&FunctionCallNode<'a>, &OpsetParseContext) -> OpsetParseResult<OpsetExpression>;
+
+static BUILTIN_FUNCTION_MAP: LazyLock<HashMap<&str, OpsetFunction>> = LazyLock::new(|| {
+    // Not using maplit::hashmap!{} or custom declarative macro here because
+    // code completion inside macro is quite restricted.
+    let mut map: HashMap<&str, OpsetFunction> = HashMap::new();
+    map.insert("none", |function, _context| {
+        function.expect_no_arguments()?;
+        Ok(OpsetExpression::None)
+    });
+    map.insert("all", |function, _context| {
+        function.expect_no_arguments()?;
+        Ok(OpsetExpression::All)
+    });
+    map.insert("root", |function, _context| {
+        function.expect_no_arguments()?;
+        Ok(OpsetExpression::Root)
+    });
+    map.insert("description", |function, _context| {
+        let [arg] = function.expect_exact_arguments()?;
+        let pattern = expect_string_pattern(arg)?;
+        Ok(OpsetExpression::Filter(OpsetFilterPredicate::Description(
+            pattern,
+        )))
+    });
+    map.insert("user", |function, _context| {
+        let [arg] = function.expect_exact_arguments()?;
+        let pattern = expect_string_pattern(arg)?;
+        Ok(OpsetExpression::Filter(OpsetFilterPredicate::User(pattern)))
+    });
+    map.insert("time", |function, context| {
+        let [arg] = function.expect_exact_arguments()?;
+        let (value, kind) = opset_parser::expect_string_pattern("date pattern", arg)?;
+        let kind = kind.ok_or_else(|| {
+            OpsetParseError::expression("Date pattern must specify 'after' or 'before'", arg.span)
+        })?;
+        let pattern = context
+            .date_pattern_context
+            .parse_relative(value, kind)
+            .map_err(|err| {
+                OpsetParseError::expression("Invalid date pattern", arg.span).with_source(err)
+            })?;
+        Ok(OpsetExpression::Filter(OpsetFilterPredicate::Time(pattern)))
+    });
+    map.insert("touching", |function, context| {
+        let [arg] = function.expect_exact_arguments()?;
+        let resolver = context.commit_resolver.ok_or_else(|| {
+            OpsetParseError::expression("Revsets cannot be resolved in this context", arg.span)
+        })?;
+        // Revset operators conflict with the opset ones, so the revset has to
+        // be quoted unless it's a simple symbol.
+        let text = opset_parser::expect_string_literal("revset string", arg)?;
+        let commit_ids = resolver.resolve_commits(text).map_err(|err| {
+            OpsetParseError::expression("In revset expression", arg.span).with_source(err)
+        })?;
+        Ok(OpsetExpression::Filter(OpsetFilterPredicate::Touching(
+            commit_ids,
+        )))
+    });
+    map.insert("workspace", |function, _context| {
+        let [arg] = function.expect_exact_arguments()?;
+        let pattern = expect_string_pattern(arg)?;
+        Ok(OpsetExpression::Filter(OpsetFilterPredicate::Workspace(
+            pattern,
+        )))
+    });
+    map
+});
+
+fn expect_string_pattern(node: &ExpressionNode) -> OpsetParseResult<StringPattern> {
+    let (value, kind) = opset_parser::expect_string_pattern("string pattern", node)?;
+    let pattern = match kind {
+        Some(kind) => StringPattern::from_str_kind(value, kind),
+        None => Ok(StringPattern::substring(value)),
+    };
+    pattern.map_err(|err| {
+        OpsetParseError::expression("Invalid string pattern", node.span).with_source(err)
+    })
+}
+
+fn resolve_function(
+    function: &FunctionCallNode,
+    context: &OpsetParseContext,
+) -> OpsetParseResult<OpsetExpression> {
+    if let Some(func) = BUILTIN_FUNCTION_MAP.get(function.name) {
+        func(function, context)
+    } else {
+        Err(OpsetParseError::new(
+            OpsetParseErrorKind::NoSuchFunction {
+                name: function.name.to_owned(),
+                candidates: collect_similar(function.name, BUILTIN_FUNCTION_MAP.keys()),
+            },
+            function.name_span,
+        ))
+    }
+}
+
+fn resolve_expression(
+    node: &ExpressionNode,
+    context: &OpsetParseContext,
+) -> OpsetParseResult<OpsetExpression> {
+    match &node.kind {
+        ExpressionKind::Identifier(name) => Ok(OpsetExpression::IdPrefix((*name).to_owned())),
+        ExpressionKind::String(name) => Ok(OpsetExpression::IdPrefix(name.clone())),
+        ExpressionKind::StringPattern { .. } => Err(OpsetParseError::expression(
+            "String patterns may not be used as operation ids",
+            node.span,
+        )),
+        ExpressionKind::AtOperation => Ok(OpsetExpression::CurrentOperation),
+        ExpressionKind::RangeAll => Ok(OpsetExpression::Range {
+            roots: Box::new(OpsetExpression::Root),
+            heads: Box::new(OpsetExpression::Heads),
+        }),
+        ExpressionKind::Unary(op, arg_node) => {
+            let arg = Box::new(resolve_expression(arg_node, context)?);
+            match op {
+                UnaryOp::Negate => Ok(OpsetExpression::NotIn(arg)),
+                UnaryOp::RangePre => Ok(OpsetExpression::Range {
+                    roots: Box::new(OpsetExpression::Root),
+                    heads: arg,
+                }),
+                UnaryOp::RangePost => Ok(OpsetExpression::Range {
+                    roots: arg,
+                    heads: Box::new(OpsetExpression::Heads),
+                }),
+                UnaryOp::Parents => Ok(OpsetExpression::Parents(arg)),
+                UnaryOp::Children => Ok(OpsetExpression::Children(arg)),
+            }
+        }
+        ExpressionKind::Binary(op, lhs_node, rhs_node) => {
+            let lhs = Box::new(resolve_expression(lhs_node, context)?);
+            let rhs = Box::new(resolve_expression(rhs_node, context)?);
+            match op {
+                BinaryOp::Intersection => Ok(OpsetExpression::Intersection(lhs, rhs)),
+                BinaryOp::Difference => Ok(OpsetExpression::Difference(lhs, rhs)),
+                BinaryOp::Range => Ok(OpsetExpression::Range {
+                    roots: lhs,
+                    heads: rhs,
+                }),
+            }
+        }
+        ExpressionKind::UnionAll(nodes) => {
+            let expressions = nodes
+                .iter()
+                .map(|node| resolve_expression(node, context))
+                .try_collect()?;
+            Ok(OpsetExpression::UnionAll(expressions))
+        }
+        ExpressionKind::FunctionCall(function) => resolve_function(function, context),
+    }
+}
+
+/// Parses text into operation set expression.
+pub fn parse(text: &str, context: &OpsetParseContext) -> OpsetParseResult<OpsetExpression> {
+    let node = opset_parser::parse_program(text)?;
+    resolve_expression(&node, context)
+}
+
+/// Evaluates operation set expression against the operation history.
+pub(crate) struct OpsetEvaluator<'a> {
+    pub op_store: &'a Arc<dyn OpStore>,
+    pub get_current_op: &'a dyn Fn() -> Result<Operation, OpsetEvaluationError>,
+    pub get_head_ops: &'a dyn Fn() -> Result<Vec<Operation>, OpsetEvaluationError>,
+    pub commit_resolver: Option<&'a dyn OpsetCommitResolver>,
+    /// Operations reachable from the heads, walked once on first use.
+    all_ops: OnceCell<Vec<Operation>>,
+}
+
+impl<'a> OpsetEvaluator<'a> {
+    pub fn new(
+        op_store: &'a Arc<dyn OpStore>,
+        get_current_op: &'a dyn Fn() -> Result<Operation, OpsetEvaluationError>,
+        get_head_ops: &'a dyn Fn() -> Result<Vec<Operation>, OpsetEvaluationError>,
+        commit_resolver: Option<&'a dyn OpsetCommitResolver>,
+    ) -> Self {
+        Self {
+            op_store,
+            get_current_op,
+            get_head_ops,
+            commit_resolver,
+            all_ops: OnceCell::new(),
+        }
+    }
+
+    /// Evaluates the expression to operations. The order of the returned
+    /// operations is unspecified.
+    pub fn evaluate(
+        &self,
+        expression: &OpsetExpression,
+    ) -> Result<Vec<Operation>, OpsetEvaluationError> {
+        match expression {
+            OpsetExpression::None => Ok(vec![]),
+            OpsetExpression::All => Ok(self.all_ops()?.to_vec()),
+            OpsetExpression::CurrentOperation => Ok(vec![(self.get_current_op)()?]),
+            OpsetExpression::Heads => (self.get_head_ops)(),
+            OpsetExpression::Root => {
+                let id = self.op_store.root_operation_id().clone();
+                let data = self.op_store.read_operation(&id).block_on()?;
+                Ok(vec![Operation::new(self.op_store.clone(), id, data)])
+            }
+            OpsetExpression::IdPrefix(prefix) => Ok(vec![op_walk::resolve_single_op_from_store(
+                self.op_store,
+                prefix,
+            )?]),
+            OpsetExpression::Parents(inner) => {
+                let ops = self.evaluate(inner)?;
+                let parents: Vec<_> = ops.iter().flat_map(|op| op.parents()).try_collect()?;
+                Ok(parents.into_iter().unique().collect())
+            }
+            OpsetExpression::Children(inner) => {
+                let ids: HashSet<_> = self
+                    .evaluate(inner)?
+                    .into_iter()
+                    .map(|op| op.id().clone())
+                    .collect();
+                Ok(self
+                    .all_ops()?
+                    .iter()
+                    .filter(|op| op.parent_ids().iter().any(|id| ids.contains(id)))
+                    .cloned()
+                    .collect())
+            }
+            OpsetExpression::Range { roots, heads } => {
+                let roots = self.evaluate(roots)?;
+                let heads = self.evaluate(heads)?;
+                Ok(op_walk::walk_ancestors_range(&heads, &roots).try_collect()?)
+            }
+            OpsetExpression::Filter(predicate) => {
+                let root_op_id = self.op_store.root_operation_id();
+                let mut matched = vec![];
+                for op in self.all_ops()? {
+                    if op.id() != root_op_id && self.matches(predicate, op)? {
+                        matched.push(op.clone());
+                    }
+                }
+                Ok(matched)
+            }
+            OpsetExpression::NotIn(inner) => {
+                let excluded = self.evaluate(inner)?;
+                Ok(difference(self.all_ops()?.iter().cloned(), &excluded))
+            }
+            OpsetExpression::UnionAll(expressions) => {
+                let ops: Vec<_> = expressions
+                    .iter()
+                    .map(|expression| self.evaluate(expression))
+                    .flatten_ok()
+                    .try_collect()?;
+                Ok(ops.into_iter().unique().collect())
+            }
+            OpsetExpression::Intersection(lhs, rhs) => {
+                let lhs = self.evaluate(lhs)?;
+                let rhs_ids: HashSet<_> = self
+                    .evaluate(rhs)?
+                    .into_iter()
+                    .map(|op| op.id().clone())
+                    .collect();
+                Ok(lhs
+                    .into_iter()
+                    .filter(|op| rhs_ids.contains(op.id()))
+                    .collect())
+            }
+            OpsetExpression::Difference(lhs, rhs) => {
+                let lhs = self.evaluate(lhs)?;
+                let rhs = self.evaluate(rhs)?;
+                Ok(difference(lhs, &rhs))
+            }
+        }
+    }
+
+    fn all_ops(&self) -> Result<&[Operation], OpsetEvaluationError> {
+        if let Some(ops) = self.all_ops.get() {
+            return Ok(ops);
+        }
+        let head_ops = (self.get_head_ops)()?;
+        let ops: Vec<_> = op_walk::walk_ancestors(&head_ops).try_collect()?;
+        Ok(self.all_ops.get_or_init(|| ops))
+    }
+
+    fn matches(
+        &self,
+        predicate: &OpsetFilterPredicate,
+        op: &Operation,
+    ) -> Result<bool, OpsetEvaluationError> {
+        let metadata = op.metadata();
+        match predicate {
+            OpsetFilterPredicate::Description(pattern) => {
+                Ok(pattern.is_match(&metadata.description))
+            }
+            OpsetFilterPredicate::User(pattern) => Ok(pattern.is_match(&metadata.username)
+                || pattern.is_match(&format!("{}@{}", metadata.username, metadata.hostname))),
+            OpsetFilterPredicate::Time(pattern) => Ok(pattern.matches(&metadata.time.end)),
+            OpsetFilterPredicate::Touching(commit_ids) => {
+                let repo = self
+                    .commit_resolver
+                    .expect("touching() should be rejected without commit resolver")
+                    .repo();
+                if op
+                    .all_referenced_commit_ids()
+                    .any(|id| commit_ids.contains(id))
+                {
+                    return Ok(true);
+                }
+                // (referenced, visible) state of each commit
+                let states_at = |view: &View| -> Result<Vec<(bool, bool)>, OpsetEvaluationError> {
+                    let referenced: HashSet<_> = view.all_referenced_commit_ids().collect();
+                    let visible = op_walk::visible_commit_ids(repo, view, commit_ids)?;
+                    Ok(commit_ids
+                        .iter()
+                        .map(|id| (referenced.contains(id), visible.contains(id)))
+                        .collect())
+                };
+                let new_states = states_at(&op.view()?)?;
+                let parent_states: Vec<_> = op
+                    .parents()
+                    .map(|parent| states_at(&parent?.view()?))
+                    .try_collect()?;
+                Ok((0..commit_ids.len()).any(|i| {
+                    let parent_values = parent_states.iter().map(|states| states[i]).collect_vec();
+                    op_walk::changed_from_parents(&new_states[i], &parent_values).is_some()
+                }))
+            }
+            OpsetFilterPredicate::Workspace(pattern) => {
+                let view = op.view()?;
+                let parent_views: Vec<_> = op.parents().map(|op| op?.view()).try_collect()?;
+                let names: HashSet<_> = itertools::chain(&parent_views, [&view])
+                    .flat_map(|view| view.wc_commit_ids().keys())
+                    .filter(|name| pattern.is_match(name.as_str()))
+                    .collect();
+                Ok(names.into_iter().any(|name| {
+                    let parent_values = parent_views
+                        .iter()
+                        .map(|view| view.get_wc_commit_id(name))
+                        .collect_vec();
+                    let new_value = view.get_wc_commit_id(name);
+                    op_walk::changed_from_parents(&new_value, &parent_values).is_some()
+                }))
+            }
+        }
+    }
+}
+
+fn difference(ops: impl IntoIterator<Item = Operation>, excluded: &[Operation]) -> Vec<Operation> {
+    let excluded_ids: HashSet<&OperationId> = excluded.iter().map(|op| op.id()).collect();
+    ops.into_iter()
+        .filter(|op| !excluded_ids.contains(op.id()))
+        .collect()
+}
@@ -0,0 +1,1192 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Patch series in the mbox format written by `git format-patch`.
+//!
+//! Each message carries the author, the description, and the Git-style diff of
+//! a single commit. If the file content the diff was made against is found by
+//! its `index` line, the patched content is merged with the current content.
+//! Otherwise, hunks are located by their context lines, using the line numbers
+//! as a hint. Hunks whose context doesn't match the file content are recorded
+//! as conflicts between the file content and the patch.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+
+use bstr::BString;
+use bstr::ByteSlice as _;
+use itertools::Itertools as _;
+use thiserror::Error;
+use tokio::io::AsyncReadExt as _;
+
+use crate::backend::BackendError;
+use crate::backend::ChangeId;
+use crate::backend::CommitId;
+use crate::backend::CopyId;
+use crate::backend::FileId;
+use crate::backend::MergedTreeId;
+use crate::backend::Signature;
+use crate::backend::Timestamp;
+use crate::backend::TreeValue;
+use crate::conflicts::MaterializedTreeValue;
+use crate::conflicts::materialize_tree_value;
+use crate::files;
+use crate::merge::Merge;
+use crate::merged_tree::MergedTree;
+use crate::merged_tree::MergedTreeBuilder;
+use crate::object_id::ObjectId as _;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::store::Store;
+use crate::trailer::parse_description_trailers;
+
+/// Suffix which pads the change id to the length of a Gerrit `Change-Id`. This
+/// is the hex of "jjid".
+const CHANGE_ID_TRAILER_SUFFIX: &str = "6a6a6964";
+
+/// Commit message parsed from a patch file.
+#[derive(Clone, Debug)]
+pub struct PatchMessage {
+    /// Author from the `From` and `Date` headers, or `None` if the patch has
+    /// no mail headers.
+    pub author: Option<Signature>,
+    /// Description built from the `Subject` header and the message body.
+    pub description: String,
+    /// Change id recorded in the `Change-Id` trailer. The trailer is removed
+    /// from the description.
+    pub change_id: Option<ChangeId>,
+    /// Changes to files.
+    pub files: Vec<FilePatch>,
+}
+
+/// Changes to a single file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FilePatch {
+    /// Path before the change, or `None` if the file is added.
+    pub old_path: Option<RepoPathBuf>,
+    /// Path after the change, or `None` if the file is deleted.
+    pub new_path: Option<RepoPathBuf>,
+    /// True if the file is copied from the `old_path` instead of renamed.
+    pub is_copy: bool,
+    /// New executable bit, or `None` if unchanged.
+    pub executable: Option<bool>,
+    /// True if the content change can't be represented by text hunks.
+    pub is_binary: bool,
+    /// Hex id of the file content before the change from the `index` line.
+    /// The id may be abbreviated.
+    pub old_file_id: Option<String>,
+    /// Text hunks in order.
+    pub hunks: Vec<PatchHunk>,
+}
+
+/// Lines replaced by a patch.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PatchHunk {
+    /// 0-based line number where the hunk starts in the old file.
+    pub old_start: usize,
+    /// Context and removed lines, including the newline characters.
+    pub old_lines: Vec<BString>,
+    /// Context and added lines, including the newline characters.
+    pub new_lines: Vec<BString>,
+}
+
+/// Error that may occur while parsing patch files.
+#[derive(Debug, Error)]
+#[error("Line {line_number}: {message}")]
+pub struct PatchParseError {
+    /// 1-based line number in the input.
+    pub line_number: usize,
+    /// Description of the error.
+    pub message: String,
+}
+
+/// Error that may occur while applying a patch to a tree.
+#[derive(Debug, Error)]
+pub enum PatchApplyError {
+    /// Failed to read or write file contents.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// The patch changes a binary file.
+    #[error("Cannot apply binary patch to {}", .0.as_internal_file_string())]
+    BinaryPatch(RepoPathBuf),
+    /// The file to be patched isn't a regular file.
+    #[error("Cannot apply patch to {}: {reason}", path.as_internal_file_string())]
+    UnsupportedFile {
+        /// Path to the file.
+        path: RepoPathBuf,
+        /// Why the file can't be patched.
+        reason: &'static str,
+    },
+}
+
+/// Formats the mail headers and the description of a patch message. The
+/// diffstat and the diff are supposed to follow the returned text.
+///
+/// If the `change_id` is given, a `Change-Id` trailer is added unless the
+/// description already has one.
+pub fn format_patch_header(
+    commit_id: &CommitId,
+    change_id: Option<&ChangeId>,
+    author: &Signature,
+    subject_prefix: &str,
+    description: &str,
+) -> String {
+    let (subject, body) = split_description(description);
+    let date = author
+        .timestamp
+        .to_datetime()
+        .map(|datetime| datetime.to_rfc2822())
+        .unwrap_or_default();
+    let subject = if subject_prefix.is_empty() {
+        subject
+    } else {
+        format!("[{subject_prefix}] {subject}")
+    };
+    let mut text = String::new();
+    // The date is a magic value which identifies the output of `git
+    // format-patch`.
+    writeln!(text, "From {} Mon Sep 17 00:00:00 2001", commit_id.hex()).unwrap();
+    writeln!(
+        text,
+        "From: {}",
+        format_address(&author.name, &author.email)
+    )
+    .unwrap();
+    writeln!(text, "Date: {date}").unwrap();
+    writeln!(text, "Subject: {}", encode_header(&subject)).unwrap();
+    writeln!(text, "MIME-Version: 1.0").unwrap();
+    writeln!(text, "Content-Type: text/plain; charset=UTF-8").unwrap();
+    writeln!(text, "Content-Transfer-Encoding: 8bit").unwrap();
+    text.push('\n');
+    text.push_str(&body);
+    let trailers = parse_description_trailers(description);
+    if let Some(change_id) = change_id
+        && !trailers.iter().any(|trailer| trailer.key == "Change-Id")
+    {
+        if !body.is_empty() && trailers.is_empty() {
+            text.push('\n');
+        }
+        let change_id_hex = change_id.hex();
+        writeln!(
+            text,
+            "Change-Id: I{change_id_hex}{CHANGE_ID_TRAILER_SUFFIX}"
+        )
+        .unwrap();
+    }
+    text.push_str("---\n");
+    text
+}
+
+/// Splits the description into the subject line and the body. Lines in the
+/// first paragraph are joined as in `git format-patch`.
+fn split_description(description: &str) -> (String, String) {
+    let description = description.trim_start_matches('\n');
+    let (first_paragraph, rest) = description.split_once("\n\n").unwrap_or((description, ""));
+    let subject = first_paragraph.lines().map(str::trim).collect::<Vec<_>>();
+    let body = rest.trim_start_matches('\n').trim_end();
+    let body = if body.is_empty() {
+        String::new()
+    } else {
+        format!("{body}\n")
+    };
+    (subject.join(" "), body)
+}
+
+fn format_address(name: &str, email: &str) -> String {
+    if name.is_empty() {
+        format!("<{email}>")
+    } else if !name.is_ascii() {
+        format!("{} <{email}>", encode_header(name))
+    } else if name.contains(|c| "()<>[]:;@\\,.\"".contains(c)) {
+        let escaped = name.replace('\\', "\\\\").replace('"', "\\\"");
+        format!("\"{escaped}\" <{email}>")
+    } else {
+        format!("{name} <{email}>")
+    }
+}
+
+fn parse_address(value: &str) -> (String, String) {
+    let value = decode_header(value);
+    let Some((name, rest)) = value.rsplit_once('<') else {
+        return (String::new(), value.trim().to_owned());
+    };
+    let email = rest.split_once('>').map_or(rest, |(email, _)| email);
+    let name = name.trim();
+    let name = match name
+        .strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+    {
+        Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => name.to_owned(),
+    };
+    (name, email.trim().to_owned())
+}
+
+/// Encodes a header value as an RFC 2047 encoded word if it contains non-ASCII
+/// characters.
+fn encode_header(value: &str) -> Cow<'_, str> {
+    if value.is_ascii() {
+        return Cow::Borrowed(value);
+    }
+    let mut encoded = "=?UTF-8?q?".to_owned();
+    for &b in value.as_bytes() {
+        match b {
+            b' ' => encoded.push('_'),
+            b'0'..=b'9' | b'A'..=b'Z' | b'a'..=b'z' | b'!' | b'*' | b'+' | b'-' | b'/' => {
+                encoded.push(char::from(b));
+            }
+            _ => write!(encoded, "={b:02X}").unwrap(),
+        }
+    }
+    encoded.push_str("?=");
+    Cow::Owned(encoded)
+}
+
+/// Decodes RFC 2047 encoded words in a header value. The value is returned
+/// as is if it can't be decoded.
+fn decode_header(value: &str) -> String {
+    rfc2047_decoder::decode(value).unwrap_or_else(|_| value.to_owned())
+}
+
+/// Parses patch messages. The input may be an mbox file containing multiple
+/// messages, a single message, or a plain Git-style diff.
+pub fn parse_patches(data: &[u8]) -> Result<Vec<PatchMessage>, PatchParseError> {
+    let mut parser = PatchParser {
+        lines: data.split_inclusive(|b| *b == b'\n').collect(),
+        pos: 0,
+    };
+    let mut messages = vec![];
+    while parser.skip_blank_lines() {
+        messages.push(parser.parse_message()?);
+    }
+    Ok(messages)
+}
+
+fn is_mbox_separator(line: &[u8]) -> bool {
+    // Only lines which look like "From <commit id> <date>" are recognized, so
+    // the description can contain lines starting with "From ".
+    line.strip_prefix(b"From ")
+        .and_then(|rest| rest.split_once_str(" "))
+        .is_some_and(|(id, _)| !id.is_empty() && id.iter().all(u8::is_ascii_hexdigit))
+}
+
+fn is_header_line(line: &[u8]) -> bool {
+    line.split_once_str(": ").is_some_and(|(name, _)| {
+        !name.is_empty() && name.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'-')
+    })
+}
+
+fn parse_file_mode(mode: &str) -> Option<bool> {
+    match mode {
+        "100644" => Some(false),
+        "100755" => Some(true),
+        _ => None,
+    }
+}
+
+struct PatchParser<'a> {
+    lines: Vec<&'a [u8]>,
+    pos: usize,
+}
+
+impl<'a> PatchParser<'a> {
+    fn peek(&self) -> Option<&'a [u8]> {
+        self.lines.get(self.pos).copied()
+    }
+
+    fn error(&self, message: impl Into<String>) -> PatchParseError {
+        PatchParseError {
+            line_number: self.pos + 1,
+            message: message.into(),
+        }
+    }
+
+    /// Skips blank lines. Returns false if there are no more lines.
+    fn skip_blank_lines(&mut self) -> bool {
+        while let Some(line) = self.peek() {
+            if !line.trim().is_empty() {
+                return true;
+            }
+            self.pos += 1;
+        }
+        false
+    }
+
+    fn text_line(&self, line: &'a [u8]) -> Result<&'a str, PatchParseError> {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        std::str::from_utf8(line).map_err(|_| self.error("Patch message is not valid UTF-8"))
+    }
+
+    fn parse_message(&mut self) -> Result<PatchMessage, PatchParseError> {
+        if self.peek().is_some_and(is_mbox_separator) {
+            self.pos += 1;
+        }
+        let mut author_name_email = None;
+        let mut date = None;
+        let mut subject = String::new();
+        let mut has_headers = false;
+        if self.peek().is_some_and(is_header_line) {
+            has_headers = true;
+            let mut headers: Vec<(&str, String)> = vec![];
+            while let Some(line) = self.peek() {
+                let line = self.text_line(line)?;
+                if line.is_empty() {
+                    self.pos += 1;
+                    break;
+                }
+                if line.starts_with([' ', '\t']) {
+                    if let Some((_, value)) = headers.last_mut() {
+                        value.push_str(line);
+                    }
+                } else if let Some((name, value)) = line.split_once(':') {
+                    headers.push((name, value.trim().to_owned()));
+                } else {
+                    return Err(self.error(format!("Invalid header line: {line}")));
+                }
+                self.pos += 1;
+            }
+            for (name, value) in headers {
+                if name.eq_ignore_ascii_case("from") {
+                    author_name_email = Some(parse_address(&value));
+                } else if name.eq_ignore_ascii_case("date") {
+                    let datetime = chrono::DateTime::parse_from_rfc2822(&value)
+                        .map_err(|err| self.error(format!("Invalid date {value:?}: {err}")))?;
+                    date = Some(Timestamp::from_datetime(datetime));
+                } else if name.eq_ignore_ascii_case("subject") {
+                    subject = strip_subject_prefix(&decode_header(&value)).to_owned();
+                }
+            }
+        }
+        let author = match (author_name_email, date) {
+            (Some((name, email)), Some(timestamp)) => Some(Signature {
+                name,
+                email,
+                timestamp,
+            }),
+            (None, _) if !has_headers => None,
+            (None, _) => return Err(self.error("Patch message has no From header")),
+            (Some(_), None) => return Err(self.error("Patch message has no Date header")),
+        };
+
+        let mut body = String::new();
+        while let Some(line) = self.peek() {
+            if is_mbox_separator(line) || line.starts_with(b"diff --git ") {
+                break;
+            }
+            let line = self.text_line(line)?;
+            self.pos += 1;
+            if line == "---" {
+                break;
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+        let body = body.trim_start_matches('\n').trim_end();
+        let mut description = match (subject.is_empty(), body.is_empty()) {
+            (true, true) => String::new(),
+            (false, true) => format!("{subject}\n"),
+            (true, false) => format!("{body}\n"),
+            (false, false) => format!("{subject}\n\n{body}\n"),
+        };
+        let mut change_id = None;
+        let trailers = parse_description_trailers(&description);
+        if let Some(trailer) = trailers.iter().find(|trailer| trailer.key == "Change-Id")
+            && let Some(id) = trailer
+                .value
+                .strip_prefix('I')
+                .and_then(|hex| hex.strip_suffix(CHANGE_ID_TRAILER_SUFFIX))
+                .and_then(ChangeId::try_from_hex)
+        {
+            let trailer_line = format!("Change-Id: {}", trailer.value);
+            let remaining = description
+                .lines()
+                .filter(|line| line.trim() != trailer_line)
+                .join("\n");
+            description = match remaining.trim_end() {
+                "" => String::new(),
+                remaining => format!("{remaining}\n"),
+            };
+            change_id = Some(id);
+        }
+
+        let files = self.parse_file_patches()?;
+        Ok(PatchMessage {
+            author,
+            description,
+            change_id,
+            files,
+        })
+    }
+
+    fn parse_file_patches(&mut self) -> Result<Vec<FilePatch>, PatchParseError> {
+        let mut files = vec![];
+        while let Some(line) = self.peek() {
+            if is_mbox_separator(line) {
+                break;
+            }
+            self.pos += 1;
+            // Other lines such as the diffstat and the signature are ignored.
+            if let Some(paths) = line.strip_prefix(b"diff --git ") {
+                let paths = self.text_line(paths)?;
+                files.push(self.parse_file_patch(paths)?);
+            }
+        }
+        Ok(files)
+    }
+
+    fn parse_file_patch(&mut self, paths: &str) -> Result<FilePatch, PatchParseError> {
+        let (old_path, new_path) = self.parse_git_diff_paths(paths)?;
+        let mut file = FilePatch {
+            old_path: Some(old_path),
+            new_path: Some(new_path),
+            is_copy: false,
+            executable: None,
+            is_binary: false,
+            old_file_id: None,
+            hunks: vec![],
+        };
+        while let Some(line) = self.peek() {
+            if is_mbox_separator(line) || line.starts_with(b"diff --git ") {
+                break;
+            }
+            if line.starts_with(b"@@ ") {
+                let hunk = self.parse_hunk()?;
+                file.hunks.push(hunk);
+                continue;
+            }
+            if !file.hunks.is_empty() {
+                break;
+            }
+            let line = self.text_line(line)?;
+            if let Some(mode) = line.strip_prefix("new file mode ") {
+                file.old_path = None;
+                file.executable = Some(self.parse_mode(mode)?);
+            } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+                self.parse_mode(mode)?;
+                file.new_path = None;
+            } else if let Some(mode) = line.strip_prefix("new mode ") {
+                file.executable = Some(self.parse_mode(mode)?);
+            } else if let Some(ids) = line.strip_prefix("index ") {
+                let (ids, _mode) = ids.split_once(' ').unwrap_or((ids, ""));
+                file.old_file_id = ids
+                    .split_once("..")
+                    .map(|(old, _new)| old)
+                    .filter(|old| old.bytes().any(|b| b != b'0'))
+                    .map(str::to_owned);
+            } else if let Some(path) = line.strip_prefix("rename from ") {
+                file.old_path = Some(self.parse_path(path)?);
+            } else if let Some(path) = line.strip_prefix("rename to ") {
+                file.new_path = Some(self.parse_path(path)?);
+            } else if let Some(path) = line.strip_prefix("copy from ") {
+                file.old_path = Some(self.parse_path(path)?);
+                file.is_copy = true;
+            } else if let Some(path) = line.strip_prefix("copy to ") {
+                file.new_path = Some(self.parse_path(path)?);
+                file.is_copy = true;
+            } else if let Some(path) = line.strip_prefix("--- ") {
+                file.old_path = self.parse_hunk_path(path, "a/")?;
+            } else if let Some(path) = line.strip_prefix("+++ ") {
+                file.new_path = self.parse_hunk_path(path, "b/")?;
+            } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+                file.is_binary = true;
+            }
+            self.pos += 1;
+        }
+        Ok(file)
+    }
+
+    fn parse_git_diff_paths(
+        &self,
+        paths: &str,
+    ) -> Result<(RepoPathBuf, RepoPathBuf), PatchParseError> {
+        // Paths are ambiguous if they contain " b/", but the old and new paths
+        // are the same unless the file is renamed or copied, in which case the
+        // paths are given by the extended headers.
+        let (old, new) = if let Some(path) = paths
+            .strip_prefix("a/")
+            .filter(|rest| rest.len() % 2 == 1)
+            .map(|rest| rest.split_at(rest.len() / 2))
+            .and_then(|(old, new)| (new.strip_prefix(" b/") == Some(old)).then_some(old))
+        {
+            (path, path)
+        } else {
+            paths
+                .strip_prefix("a/")
+                .and_then(|rest| rest.split_once(" b/"))
+                .ok_or_else(|| self.error(format!("Invalid diff header: diff --git {paths}")))?
+        };
+        Ok((self.parse_path(old)?, self.parse_path(new)?))
+    }
+
+    fn parse_hunk_path(
+        &self,
+        path: &str,
+        prefix: &str,
+    ) -> Result<Option<RepoPathBuf>, PatchParseError> {
+        // Non-Git diffs may have a timestamp after the path.
+        let path = path.split_once('\t').map_or(path, |(path, _)| path);
+        if path == "/dev/null" {
+            return Ok(None);
+        }
+        let path = path.strip_prefix(prefix).unwrap_or(path);
+        self.parse_path(path).map(Some)
+    }
+
+    fn parse_path(&self, path: &str) -> Result<RepoPathBuf, PatchParseError> {
+        RepoPathBuf::from_internal_string(path)
+            .map_err(|_| self.error(format!("Invalid path {path:?}")))
+    }
+
+    fn parse_mode(&self, mode: &str) -> Result<bool, PatchParseError> {
+        parse_file_mode(mode).ok_or_else(|| self.error(format!("Unsupported file mode {mode}")))
+    }
+
+    fn parse_hunk(&mut self) -> Result<PatchHunk, PatchParseError> {
+        let header = self.text_line(self.peek().unwrap())?;
+        let parse_range = |range: &str| -> Option<(usize, usize)> {
+            match range.split_once(',') {
+                Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+                None => Some((range.parse().ok()?, 1)),
+            }
+        };
+        let ((old_start, mut old_len), (_, mut new_len)) = header
+            .strip_prefix("@@ -")
+            .and_then(|rest| rest.split_once(" @@"))
+            .and_then(|(ranges, _)| ranges.split_once(" +"))
+            .and_then(|(old, new)| Some((parse_range(old)?, parse_range(new)?)))
+            .ok_or_else(|| self.error(format!("Invalid hunk header: {header}")))?;
+        self.pos += 1;
+
+        let mut hunk = PatchHunk {
+            // The start is the preceding line if the range is empty.
+            old_start: if old_len == 0 {
+                old_start
+            } else {
+                old_start.saturating_sub(1)
+            },
+            old_lines: vec![],
+            new_lines: vec![],
+        };
+        // Which sides the last line was added to
+        let mut last_sides = (false, false);
+        loop {
+            let Some(line) = self.peek() else {
+                if old_len == 0 && new_len == 0 {
+                    break;
+                }
+                return Err(self.error("Unexpected end of hunk"));
+            };
+            if let Some(marker) = line.strip_prefix(b"\\") {
+                // "\ No newline at end of file" applies to the previous line
+                if marker.trim_ascii_start().starts_with(b"No newline") {
+                    let strip = |lines: &mut Vec<BString>| {
+                        if let Some(last) = lines.last_mut()
+                            && last.ends_with(b"\n")
+                        {
+                            last.pop();
+                        }
+                    };
+                    if last_sides.0 {
+                        strip(&mut hunk.old_lines);
+                    }
+                    if last_sides.1 {
+                        strip(&mut hunk.new_lines);
+                    }
+                }
+                self.pos += 1;
+                continue;
+            }
+            if old_len == 0 && new_len == 0 {
+                break;
+            }
+            let (kind, content) = match line.split_first() {
+                // Some mail clients strip the trailing space of empty context lines.
+                Some((b'\n', _)) => (b' ', &b"\n"[..]),
+                Some((kind, content)) => (*kind, content),
+                None => unreachable!("split lines should not be empty"),
+            };
+            match kind {
+                b' ' if old_len > 0 && new_len > 0 => {
+                    hunk.old_lines.push(content.into());
+                    hunk.new_lines.push(content.into());
+                    old_len -= 1;
+                    new_len -= 1;
+                    last_sides = (true, true);
+                }
+                b'-' if old_len > 0 => {
+                    hunk.old_lines.push(content.into());
+                    old_len -= 1;
+                    last_sides = (true, false);
+                }
+                b'+' if new_len > 0 => {
+                    hunk.new_lines.push(content.into());
+                    new_len -= 1;
+                    last_sides = (false, true);
+                }
+                _ => {
+                    let line = String::from_utf8_lossy(line);
+                    return Err(self.error(format!("Invalid hunk line: {}", line.trim_end())));
+                }
+            }
+            self.pos += 1;
+        }
+        Ok(hunk)
+    }
+}
+
+/// Removes prefixes like `[PATCH 1/2]` and `Re:` from the subject.
+fn strip_subject_prefix(mut subject: &str) -> &str {
+    loop {
+        subject = subject.trim_start();
+        if let Some(rest) = subject.strip_prefix('[')
+            && let Some((_, rest)) = rest.split_once(']')
+        {
+            subject = rest;
+        } else if subject.len() >= 3 && subject[..3].eq_ignore_ascii_case("re:") {
+            subject = &subject[3..];
+        } else {
+            return subject.trim_end();
+        }
+    }
+}
+
+/// Applies the `hunks` to the `content`.
+///
+/// Each hunk is located by its context and removed lines, searching outward
+/// from its line number. If a hunk can't be located, the lines at its line
+/// number are recorded as a conflict. The returned merge is the `content`, the
+/// old lines, and the new lines in that case.
+pub fn apply_hunks(content: &[u8], hunks: &[PatchHunk]) -> Merge<BString> {
+    let lines: Vec<&[u8]> = content.split_inclusive(|b| *b == b'\n').collect();
+    let mut left = BString::default();
+    let mut base = BString::default();
+    let mut right = BString::default();
+    let mut push_all = |sides: [&[&[u8]]; 3]| {
+        for (text, lines) in [&mut left, &mut base, &mut right].into_iter().zip(sides) {
+            for line in lines {
+                text.extend_from_slice(line);
+            }
+        }
+    };
+    let mut cursor = 0;
+    let mut offset: isize = 0;
+    let mut has_conflict = false;
+    for hunk in hunks {
+        let old_lines = hunk
+            .old_lines
+            .iter()
+            .map(|line| line.as_slice())
+            .collect::<Vec<_>>();
+        let new_lines = hunk
+            .new_lines
+            .iter()
+            .map(|line| line.as_slice())
+            .collect::<Vec<_>>();
+        let expected = hunk
+            .old_start
+            .saturating_add_signed(offset)
+            .clamp(cursor, lines.len());
+        let matches_at = |pos: usize| {
+            lines
+                .get(pos..pos + old_lines.len())
+                .is_some_and(|candidate| candidate == old_lines)
+        };
+        let found = (0..=lines.len()).find_map(|distance| {
+            let before = expected.checked_sub(distance).filter(|&pos| pos >= cursor);
+            let after = Some(expected + distance).filter(|&pos| distance > 0 && pos <= lines.len());
+            [before, after]
+                .into_iter()
+                .flatten()
+                .find(|&pos| matches_at(pos))
+        });
+        if let Some(pos) = found {
+            push_all([&lines[cursor..pos]; 3]);
+            push_all([&new_lines; 3]);
+            cursor = pos + old_lines.len();
+            offset = pos as isize - hunk.old_start as isize;
+        } else {
+            let end = (expected + old_lines.len()).min(lines.len());
+            push_all([&lines[cursor..expected]; 3]);
+            push_all([&lines[expected..end], &old_lines, &new_lines]);
+            cursor = end;
+            has_conflict = true;
+        }
+    }
+    push_all([&lines[cursor..]; 3]);
+    if has_conflict {
+        Merge::from_vec(vec![left, base, right])
+    } else {
+        Merge::resolved(left)
+    }
+}
+
+/// Applies the file patches to the `tree`, and returns the id of the new tree.
+/// Files which can't be patched cleanly are written as conflicts.
+pub async fn apply_file_patches(
+    tree: &MergedTree,
+    files: &[FilePatch],
+) -> Result<MergedTreeId, PatchApplyError> {
+    let store = tree.store();
+    let mut tree_builder = MergedTreeBuilder::new(tree.id());
+    for file in files {
+        let Some(target_path) = file.new_path.as_ref().or(file.old_path.as_ref()) else {
+            continue;
+        };
+        let source_path = file.old_path.as_ref().unwrap_or(target_path);
+        if file.is_binary {
+            return Err(PatchApplyError::BinaryPatch(target_path.clone()));
+        }
+        let current = read_file(tree, source_path).await?;
+        let preimage = match (&file.old_file_id, &file.new_path, &current) {
+            (Some(hex), Some(_), Some(_)) => read_preimage(tree, source_path, hex).await?,
+            _ => None,
+        };
+        let old_content = || -> BString {
+            file.hunks
+                .iter()
+                .flat_map(|hunk| &hunk.old_lines)
+                .flat_map(|line| line.iter().copied())
+                .collect()
+        };
+        let new_content = || -> BString {
+            file.hunks
+                .iter()
+                .flat_map(|hunk| &hunk.new_lines)
+                .flat_map(|line| line.iter().copied())
+                .collect()
+        };
+        let contents: Merge<Option<BString>> = match (&file.old_path, &file.new_path, &current) {
+            // Added file
+            (None, _, None) => Merge::normal(new_content()),
+            (None, _, Some((content, _, _))) => {
+                Merge::from_vec(vec![Some(content.clone()), None, Some(new_content())])
+            }
+            // Deleted file
+            (_, None, Some((content, _, _))) if *content == old_content() => Merge::absent(),
+            (_, None, current) => Merge::from_vec(vec![
+                current.as_ref().map(|(content, _, _)| content.clone()),
+                Some(old_content()),
+                None,
+            ]),
+            // Modified file
+            (Some(_), Some(_), None) => {
+                Merge::from_vec(vec![None, Some(old_content()), Some(new_content())])
+            }
+            (Some(_), Some(_), Some((content, _, _))) => {
+                // Hunks which don't apply to their own preimage suggest that
+                // the index line is unreliable, so they are located in the
+                // current content instead.
+                let merged = preimage.and_then(|base| {
+                    let new = apply_hunks(&base, &file.hunks).into_resolved().ok()?;
+                    let inputs = Merge::from_vec(vec![content.clone(), base, new]);
+                    Some(files::merge(&inputs, store.merge_options()))
+                });
+                merged
+                    .unwrap_or_else(|| apply_hunks(content, &file.hunks))
+                    .map(|text| Some(text.clone()))
+            }
+        };
+        let (executable, copy_id) = match &current {
+            Some((_, executable, copy_id)) => (*executable, copy_id.clone()),
+            None => (false, CopyId::placeholder()),
+        };
+        let executable = file.executable.unwrap_or(executable);
+        let mut values = Vec::with_capacity(contents.num_sides() * 2 - 1);
+        for content in contents.iter() {
+            let value = match content {
+                Some(content) => {
+                    let id = store
+                        .write_file(target_path, &mut content.as_slice())
+                        .await?;
+                    Some(TreeValue::File {
+                        id,
+                        executable,
+                        copy_id: copy_id.clone(),
+                    })
+                }
+                None => None,
+            };
+            values.push(value);
+        }
+        if let Some(old_path) = &file.old_path
+            && old_path != target_path
+            && !file.is_copy
+        {
+            tree_builder.set_or_remove(old_path.clone(), Merge::absent());
+        }
+        tree_builder.set_or_remove(target_path.clone(), Merge::from_vec(values).simplify());
+    }
+    Ok(tree_builder.write_tree(store)?)
+}
+
+/// Reads the content, executable bit, and copy id of a file. Returns `None`
+/// if the file doesn't exist.
+async fn read_file(
+    tree: &MergedTree,
+    path: &RepoPath,
+) -> Result<Option<(BString, bool, CopyId)>, PatchApplyError> {
+    let unsupported = |reason| PatchApplyError::UnsupportedFile {
+        path: path.to_owned(),
+        reason,
+    };
+    let value = tree.path_value_async(path).await?;
+    match materialize_tree_value(tree.store(), path, value).await? {
+        MaterializedTreeValue::Absent => Ok(None),
+        MaterializedTreeValue::File(mut file) => {
+            let content = file.read_all(path).await?;
+            Ok(Some((content.into(), file.executable, file.copy_id)))
+        }
+        MaterializedTreeValue::AccessDenied(_) => Err(unsupported("Access is denied")),
+        MaterializedTreeValue::Symlink { .. } => Err(unsupported("Is a symlink")),
+        MaterializedTreeValue::FileConflict(_) | MaterializedTreeValue::OtherConflict { .. } => {
+            Err(unsupported("Is a conflict"))
+        }
+        MaterializedTreeValue::GitSubmodule(_) => Err(unsupported("Is a Git submodule")),
+        MaterializedTreeValue::Tree(_) => Err(unsupported("Is a directory")),
+    }
+}
+
+/// Reads the content the patch was made against, identified by the possibly
+/// abbreviated id from the `index` line. Returns `None` if the content isn't
+/// found.
+async fn read_preimage(
+    tree: &MergedTree,
+    path: &RepoPath,
+    hex: &str,
+) -> Result<Option<BString>, PatchApplyError> {
+    let store = tree.store();
+    let id = match tree.path_value_async(path).await?.into_resolved() {
+        Ok(Some(TreeValue::File { id, .. })) if id.hex().starts_with(hex) => id,
+        _ => match resolve_file_id_prefix(store, hex) {
+            Some(id) => id,
+            None => return Ok(None),
+        },
+    };
+    let mut reader = match store.read_file(path, &id).await {
+        Ok(reader) => reader,
+        Err(BackendError::ObjectNotFound { .. }) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let mut content = vec![];
+    reader
+        .read_to_end(&mut content)
+        .await
+        .map_err(|err| BackendError::ReadFile {
+            path: path.to_owned(),
+            id: id.clone(),
+            source: err.into(),
+        })?;
+    Ok(Some(content.into()))
+}
+
+#[cfg_attr(not(feature = "git"), expect(unused_variables))]
+fn resolve_file_id_prefix(store: &Store, hex: &str) -> Option<FileId> {
+    #[cfg(feature = "git")]
+    if let Ok(git_repo) = crate::git::get_git_repo(store) {
+        let prefix = gix::hash::Prefix::from_hex(hex).ok()?;
+        let id = git_repo.objects.lookup_prefix(prefix, None).ok()??.ok()?;
+        let object = git_repo.find_header(id).ok()?;
+        return (object.kind() == gix::object::Kind::Blob)
+            .then(|| FileId::from_bytes(id.as_bytes()));
+    }
+    FileId::try_from_hex(hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::backend::MillisSinceEpoch;
+
+    fn hunk(old_start: usize, old_lines: &[&str], new_lines: &[&str]) -> PatchHunk {
+        PatchHunk {
+            old_start,
+            old_lines: old_lines.iter().map(|line| (*line).into()).collect(),
+            new_lines: new_lines.iter().map(|line| (*line).into()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_header() {
+        assert_eq!(encode_header("plain text"), "plain text");
+        assert_eq!(
+            encode_header("Søren Kœn"),
+            "=?UTF-8?q?S=C3=B8ren_K=C5=93n?="
+        );
+        assert_eq!(
+            decode_header("=?UTF-8?q?S=C3=B8ren_K=C5=93n?="),
+            "Søren Kœn"
+        );
+        assert_eq!(
+            decode_header("=?utf-8?b?U8O4cmVu?= =?utf-8?q?_K?="),
+            "Søren K"
+        );
+        assert_eq!(decode_header("=?ISO-8859-1?q?S=F8ren?="), "Søren");
+        assert_eq!(decode_header("a =?utf-8?x?b?= c"), "a =?utf-8?x?b?= c");
+        assert_eq!(
+            parse_address("\"Doe, John \\\"JD\\\"\" <jd@example.com>"),
+            ("Doe, John \"JD\"".to_owned(), "jd@example.com".to_owned())
+        );
+        assert_eq!(
+            format_address("Doe, John \"JD\"", "jd@example.com"),
+            "\"Doe, John \\\"JD\\\"\" <jd@example.com>"
+        );
+        assert_eq!(
+            parse_address("jd@example.com"),
+            (String::new(), "jd@example.com".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_strip_subject_prefix() {
+        assert_eq!(strip_subject_prefix("[PATCH 1/2] foo"), "foo");
+        assert_eq!(
+            strip_subject_prefix("Re: [RFC] [PATCH v2] foo bar "),
+            "foo bar"
+        );
+        assert_eq!(strip_subject_prefix("foo [bar]"), "foo [bar]");
+    }
+
+    #[test]
+    fn test_format_and_parse_header() {
+        let author = Signature {
+            name: "Test User".to_owned(),
+            email: "test.user@example.com".to_owned(),
+            timestamp: Timestamp {
+                timestamp: MillisSinceEpoch(1_000_000_000_000),
+                tz_offset: 60,
+            },
+        };
+        let commit_id = CommitId::from_hex("0123456789abcdef");
+        let change_id = ChangeId::from_hex("00112233445566778899aabbccddeeff");
+        let text = format_patch_header(
+            &commit_id,
+            Some(&change_id),
+            &author,
+            "PATCH 1/2",
+            "first\nline\n\nbody\n",
+        );
+        insta::assert_snapshot!(text, @r"
+        From 0123456789abcdef Mon Sep 17 00:00:00 2001
+        From: Test User <test.user@example.com>
+        Date: Sun, 9 Sep 2001 02:46:40 +0100
+        Subject: [PATCH 1/2] first line
+        MIME-Version: 1.0
+        Content-Type: text/plain; charset=UTF-8
+        Content-Transfer-Encoding: 8bit
+
+        body
+
+        Change-Id: I00112233445566778899aabbccddeeff6a6a6964
+        ---
+        ");
+        let messages = parse_patches(text.as_bytes()).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].author.as_ref(), Some(&author));
+        assert_eq!(messages[0].description, "first line\n\nbody\n");
+        assert_eq!(messages[0].change_id.as_ref(), Some(&change_id));
+        assert!(messages[0].files.is_empty());
+
+        // Existing trailers are kept, and the Change-Id is added to them
+        let text = format_patch_header(
+            &commit_id,
+            Some(&change_id),
+            &author,
+            "",
+            "subject\n\nSigned-off-by: Test User <test.user@example.com>\n",
+        );
+        let messages = parse_patches(text.as_bytes()).unwrap();
+        assert_eq!(
+            messages[0].description,
+            "subject\n\nSigned-off-by: Test User <test.user@example.com>\n"
+        );
+        assert_eq!(messages[0].change_id.as_ref(), Some(&change_id));
+    }
+
+    #[test]
+    fn test_parse_patches() {
+        let data = indoc! {b"
+            From 1111111111111111111111111111111111111111 Mon Sep 17 00:00:00 2001
+            From: =?UTF-8?q?S=C3=B8ren?= <soren@example.com>
+            Date: Mon, 1 Jan 2024 00:00:00 +0000
+            Subject: [PATCH 1/2] Add files and
+             fold the subject
+
+            From the body
+            ---
+             a | 2 ++
+             1 file changed, 2 insertions(+)
+
+            diff --git a/a b/a
+            new file mode 100755
+            index 0000000000..1111111111
+            --- /dev/null
+            +++ b/a
+            @@ -0,0 +1,2 @@
+            +first
+            +second
+            \\ No newline at end of file
+            diff --git a/b b/c
+            similarity index 100%
+            rename from b
+            rename to c
+            diff --git a/d b/d
+            deleted file mode 100644
+            index 1111111111..0000000000
+            --- a/d
+            +++ /dev/null
+            @@ -1 +0,0 @@
+            -d
+            -- 
+            2.43.0
+
+            From 2222222222222222222222222222222222222222 Mon Sep 17 00:00:00 2001
+            From: Test User <test.user@example.com>
+            Date: Tue, 2 Jan 2024 00:00:00 +0100
+            Subject: [PATCH 2/2] Modify
+
+            ---
+            diff --git a/x y b/x y
+            index 1111111111..2222222222 100644
+            --- a/x y
+            +++ b/x y
+            @@ -2,3 +2,3 @@ context
+             b
+
+            -c
+            +C
+            -- 
+            2.43.0
+        "};
+        let messages = parse_patches(data).unwrap();
+        assert_eq!(messages.len(), 2);
+        let author = messages[0].author.as_ref().unwrap();
+        assert_eq!(author.name, "Søren");
+        assert_eq!(author.email, "soren@example.com");
+        assert_eq!(
+            messages[0].description,
+            "Add files and fold the subject\n\nFrom the body\n"
+        );
+        assert_eq!(messages[0].change_id, None);
+        let path = |name: &str| Some(RepoPathBuf::from_internal_string(name).unwrap());
+        assert_eq!(
+            messages[0].files,
+            vec![
+                FilePatch {
+                    old_path: None,
+                    new_path: path("a"),
+                    is_copy: false,
+                    executable: Some(true),
+                    is_binary: false,
+                    old_file_id: None,
+                    hunks: vec![hunk(0, &[], &["first\n", "second"])],
+                },
+                FilePatch {
+                    old_path: path("b"),
+                    new_path: path("c"),
+                    is_copy: false,
+                    executable: None,
+                    is_binary: false,
+                    old_file_id: None,
+                    hunks: vec![],
+                },
+                FilePatch {
+                    old_path: path("d"),
+                    new_path: None,
+                    is_copy: false,
+                    executable: None,
+                    is_binary: false,
+                    old_file_id: Some("1111111111".to_owned()),
+                    hunks: vec![hunk(0, &["d\n"], &[])],
+                },
+            ]
+        );
+        assert_eq!(messages[1].author.as_ref().unwrap().timestamp.tz_offset, 60);
+        assert_eq!(messages[1].description, "Modify\n");
+        assert_eq!(
+            messages[1].files,
+            vec![FilePatch {
+                old_path: path("x y"),
+                new_path: path("x y"),
+                is_copy: false,
+                executable: None,
+                is_binary: false,
+                old_file_id: Some("1111111111".to_owned()),
+                hunks: vec![hunk(1, &["b\n", "\n", "c\n"], &["b\n", "\n", "C\n"])],
+            }]
+        );
+
+        // Plain diff without mail headers
+        let messages =
+            parse_patches(b"diff --git a/a b/a\n--- a/a\n+++ b/a\n@@ -1 +1 @@\n-a\n+b\n").unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].author, None);
+        assert_eq!(messages[0].description, "");
+        assert_eq!(
+            messages[0].files[0].hunks,
+            vec![hunk(0, &["a\n"], &["b\n"])]
+        );
+    }
+
+    #[test]
+    fn test_parse_patches_error() {
+        let parse_err = |data: &[u8]| parse_patches(data).unwrap_err().to_string();
+        insta::assert_snapshot!(
+            parse_err(b"From: a <a@example.com>\nSubject: foo\n\n"),
+            @"Line 4: Patch message has no Date header");
+        insta::assert_snapshot!(
+            parse_err(b"diff --git a/a b/a\n--- a/a\n+++ b/a\n@@ -1,2 +1 @@\n-a\n"),
+            @"Line 6: Unexpected end of hunk");
+        insta::assert_snapshot!(
+            parse_err(b"diff --git a/a b/a\n--- a/a\n+++ b/a\n@@ -1 +1 @@\n*a\n+b\n"),
+            @"Line 5: Invalid hunk line: *a");
+        insta::assert_snapshot!(
+            parse_err(b"diff --git a/a b/a\nnew file mode 120000\n"),
+            @"Line 2: Unsupported file mode 120000");
+    }
+
+    #[test]
+    fn test_apply_hunks() {
+        let content = b"a\nb\nc\nd\ne\n";
+        // Hunks are located by their context even if the line numbers differ
+        let merge = apply_hunks(
+            content,
+            &[
+                hunk(0, &["b\n"], &["B\n"]),
+                hunk(3, &["d\n", "e\n"], &["D\n", "e\n", "f\n"]),
+            ],
+        );
+        assert_eq!(merge, Merge::resolved("a\nB\nc\nD\ne\nf\n".into()));
+
+        // Insertion at the end
+        let merge = apply_hunks(content, &[hunk(5, &[], &["f\n"])]);
+        assert_eq!(merge, Merge::resolved("a\nb\nc\nd\ne\nf\n".into()));
+
+        // Unmatched hunks are recorded as conflicts
+        let merge = apply_hunks(
+            content,
+            &[hunk(1, &["x\n"], &["X\n"]), hunk(3, &["d\n"], &["D\n"])],
+        );
+        assert_eq!(
+            merge,
+            Merge::from_vec(vec![
+                "a\nb\nc\nD\ne\n".into(),
+                "a\nx\nc\nD\ne\n".into(),
+                "a\nX\nc\nD\ne\n".into(),
+            ])
+        );
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Content fingerprints of changes, similar to `git patch-id`.
+//!
+//! Two commits have the same patch id if they make the same line changes to
+//! the same files, regardless of their parents, the context around the changes,
+//! and whitespace. This can be used to find commits that were cherry-picked or
+//! squashed into another history.
+
+#![expect(missing_docs)]
+
+use blake2::Blake2b512;
+use blake2::Digest as _;
+use futures::StreamExt as _;
+
+use crate::backend::BackendResult;
+use crate::commit::Commit;
+use crate::conflicts::MaterializedTreeValue;
+use crate::conflicts::materialize_tree_value;
+use crate::diff::CompareBytesIgnoreAllWhitespace;
+use crate::diff::ContentDiff;
+use crate::diff::DiffHunkKind;
+use crate::diff::find_line_ranges;
+use crate::matchers::EverythingMatcher;
+use crate::merged_tree::MergedTree;
+use crate::object_id::ObjectId as _;
+use crate::object_id::id_type;
+use crate::repo_path::RepoPath;
+use crate::store::Store;
+
+id_type!(
+    /// Fingerprint of the changes made by a commit. Unlike the `CommitId`, the
+    /// `PatchId` doesn't change when the commit is rebased.
+    pub PatchId { hex() }
+);
+
+/// Computes the patch id of the changes from `from_tree` to `to_tree`.
+///
+/// Returns `None` if there are no changes other than whitespace, or if any of
+/// the changed files is conflicted or isn't a regular file or symlink.
+pub async fn compute_patch_id(
+    store: &Store,
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+) -> BackendResult<Option<PatchId>> {
+    let mut hasher = Blake2b512::new();
+    let mut has_changes = false;
+    let mut tree_diff = from_tree.diff_stream(to_tree, &EverythingMatcher);
+    while let Some(entry) = tree_diff.next().await {
+        let values = entry.values?;
+        let (left_value, right_value) = futures::try_join!(
+            materialize_tree_value(store, &entry.path, values.before),
+            materialize_tree_value(store, &entry.path, values.after),
+        )?;
+        let Some(left) = to_text(&entry.path, left_value).await? else {
+            return Ok(None);
+        };
+        let Some(right) = to_text(&entry.path, right_value).await? else {
+            return Ok(None);
+        };
+        // Paths of which only whitespace changed aren't included.
+        let lines = changed_lines(&left, &right);
+        if lines.is_empty() {
+            continue;
+        }
+        hasher.update(entry.path.as_internal_file_string());
+        hasher.update([0]);
+        for (sigil, line) in lines {
+            hasher.update([sigil]);
+            hasher.update(line);
+            hasher.update([b'\n']);
+        }
+        has_changes = true;
+    }
+    Ok(has_changes.then(|| PatchId::new(hasher.finalize().to_vec())))
+}
+
+/// Computes the patch id of the changes made by `commit` to its parent.
+///
+/// The root commit and merge commits have no patch id.
+pub async fn commit_patch_id(commit: &Commit) -> BackendResult<Option<PatchId>> {
+    let parents = commit.parents_async().await?;
+    let [parent] = parents.as_slice() else {
+        return Ok(None);
+    };
+    let from_tree = parent.tree_async().await?;
+    let to_tree = commit.tree_async().await?;
+    compute_patch_id(commit.store(), &from_tree, &to_tree).await
+}
+
+/// Returns the file or symlink content, or `None` if the value can't be
+/// represented as text.
+async fn to_text(path: &RepoPath, value: MaterializedTreeValue) -> BackendResult<Option<Vec<u8>>> {
+    match value {
+        MaterializedTreeValue::Absent => Ok(Some(vec![])),
+        MaterializedTreeValue::File(mut file) => Ok(Some(file.read_all(path).await?)),
+        MaterializedTreeValue::Symlink { id: _, target } => Ok(Some(target.into_bytes())),
+        MaterializedTreeValue::AccessDenied(_)
+        | MaterializedTreeValue::GitSubmodule(_)
+        | MaterializedTreeValue::FileConflict(_)
+        | MaterializedTreeValue::OtherConflict { .. }
+        | MaterializedTreeValue::Tree(_) => Ok(None),
+    }
+}
+
+/// Returns the removed (`-`) and added (`+`) lines with whitespace stripped.
+fn changed_lines(left: &[u8], right: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    let diff = ContentDiff::for_tokenizer(
+        [left, right],
+        find_line_ranges,
+        CompareBytesIgnoreAllWhitespace,
+    );
+    diff.hunks()
+        .filter(|hunk| hunk.kind == DiffHunkKind::Different)
+        .flat_map(|hunk| {
+            let removed = hunk.contents[0].split_inclusive(|b| *b == b'\n');
+            let added = hunk.contents[1].split_inclusive(|b| *b == b'\n');
+            let removed = removed.map(|line| (b'-', line));
+            let added = added.map(|line| (b'+', line));
+            removed.chain(added)
+        })
+        .map(|(sigil, line)| {
+            let line: Vec<u8> = line
+                .iter()
+                .filter(|b| !b.is_ascii_whitespace())
+                .copied()
+                .collect();
+            (sigil, line)
+        })
+        .filter(|(_, line)| !line.is_empty())
+        .collect()
+}
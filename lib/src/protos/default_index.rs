@@ -12,4 +12,15 @@ pub struct SegmentControl {
     /// Hashes (or file names) of the changed-path index segments.
     #[prost(bytes = "vec", repeated, tag = "3")]
     pub changed_path_segment_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// Hash (or file name) of the reachability bitmap index. Empty if bitmaps
+    /// aren't built.
+    #[prost(bytes = "vec", tag = "4")]
+    pub reachability_bitmap_id: ::prost::alloc::vec::Vec<u8>,
+    /// First commit stored in the text index segments. Unset if text index is
+    /// disabled.
+    #[prost(uint32, optional, tag = "5")]
+    pub text_index_start_commit_pos: ::core::option::Option<u32>,
+    /// Hashes (or file names) of the text index segments.
+    #[prost(bytes = "vec", repeated, tag = "6")]
+    pub text_index_segment_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
@@ -45,6 +45,23 @@ pub struct TreeState {
     pub sparse_patterns: ::core::option::Option<SparsePatterns>,
     #[prost(message, optional, tag = "4")]
     pub watchman_clock: ::core::option::Option<WatchmanClock>,
+    /// Names of the segment files storing the file states, from the base segment
+    /// to the most recent one. If empty, the file states are stored inline in
+    /// `file_states`.
+    #[prost(string, repeated, tag = "7")]
+    pub file_state_segments: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// File states stored in a separate file. The base segment contains all
+/// entries, and each following segment updates the entries of the previous
+/// segments.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FileStateSegment {
+    /// Added or updated entries, sorted by path.
+    #[prost(message, repeated, tag = "1")]
+    pub file_states: ::prost::alloc::vec::Vec<FileStateEntry>,
+    /// Removed paths, sorted.
+    #[prost(string, repeated, tag = "2")]
+    pub removed_paths: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct WatchmanClock {
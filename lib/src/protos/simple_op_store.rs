@@ -161,6 +161,9 @@ pub struct Operation {
     /// Whether or not `commit_predecessors` is recorded.
     #[prost(bool, tag = "5")]
     pub stores_commit_predecessors: bool,
+    /// Signature of the operation content, if signed.
+    #[prost(bytes = "vec", optional, tag = "6")]
+    pub signature: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
 /// TODO: Share with store.proto? Do we even need the timezone here?
 #[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
@@ -61,6 +61,8 @@ use crate::index::ReadonlyIndex;
 use crate::merge::MergeBuilder;
 use crate::merge::SameChange;
 use crate::merge::trivial_merge;
+use crate::native_backend::NativeBackend;
+use crate::network_op_heads_store::NetworkOpHeadsStore;
 use crate::object_id::HexPrefix;
 use crate::object_id::PrefixResolution;
 use crate::op_heads_store;
@@ -183,7 +185,18 @@ impl ReadonlyRepo {
     }
 
     pub fn default_op_heads_store_initializer() -> &'static OpHeadsStoreInitializer<'static> {
-        &|_settings, store_path| Ok(Box::new(SimpleOpHeadsStore::init(store_path)?))
+        &|settings, store_path| {
+            let store_type = settings
+                .get_string("operation.heads-store")
+                .map_err(|err| BackendInitError(err.into()))?;
+            match store_type.as_str() {
+                "simple" => Ok(Box::new(SimpleOpHeadsStore::init(store_path)?)),
+                "network" => Ok(Box::new(NetworkOpHeadsStore::init(store_path, settings)?)),
+                _ => Err(BackendInitError(
+                    format!("Invalid operation heads store type: {store_type}").into(),
+                )),
+            }
+        }
     }
 
     pub fn default_index_store_initializer() -> &'static IndexStoreInitializer<'static> {
@@ -426,6 +439,10 @@ impl Default for StoreFactories {
             SimpleBackend::name(),
             Box::new(|_settings, store_path| Ok(Box::new(SimpleBackend::load(store_path)))),
         );
+        factories.add_backend(
+            NativeBackend::name(),
+            Box::new(|_settings, store_path| Ok(Box::new(NativeBackend::load(store_path)?))),
+        );
         #[cfg(feature = "git")]
         factories.add_backend(
             crate::git_backend::GitBackend::name(),
@@ -458,6 +475,12 @@ impl Default for StoreFactories {
             SimpleOpHeadsStore::name(),
             Box::new(|_settings, store_path| Ok(Box::new(SimpleOpHeadsStore::load(store_path)))),
         );
+        factories.add_op_heads_store(
+            NetworkOpHeadsStore::name(),
+            Box::new(|settings, store_path| {
+                Ok(Box::new(NetworkOpHeadsStore::load(store_path, settings)))
+            }),
+        );
 
         // Index
         factories.add_index_store(
@@ -206,6 +206,8 @@ pub enum RevsetFilterPredicate {
     HasConflict,
     /// Commits that are cryptographically signed.
     Signed,
+    /// Commits with a good signature whose key or signer matches the pattern.
+    SignedBy(StringExpression),
     /// Custom predicates provided by extensions
     Extension(Arc<dyn RevsetFilterExtension>),
 }
@@ -298,6 +300,11 @@ pub enum RevsetExpression<St: ExpressionState> {
     Roots(Arc<Self>),
     ForkPoint(Arc<Self>),
     Bisect(Arc<Self>),
+    /// Commits in `candidates` whose changes were applied to `upstream`.
+    Landed {
+        candidates: Arc<Self>,
+        upstream: Arc<Self>,
+    },
     HasSize {
         candidates: Arc<Self>,
         count: usize,
@@ -529,6 +536,22 @@ impl<St: ExpressionState> RevsetExpression<St> {
         Arc::new(Self::ForkPoint(self.clone()))
     }
 
+    /// Commits in this set whose changes were applied to `upstream`, as
+    /// determined by their patch ids. Commits that are ancestors of `upstream`
+    /// aren't included.
+    pub fn landed(self: &Arc<Self>, upstream: &Arc<Self>) -> Arc<Self> {
+        let candidates = self.minus(&upstream.ancestors());
+        // Only upstream commits that aren't ancestors of all candidates need
+        // to be compared.
+        let upstream = upstream
+            .ancestors()
+            .minus(&candidates.fork_point().ancestors());
+        Arc::new(Self::Landed {
+            candidates,
+            upstream,
+        })
+    }
+
     /// Commits with ~half of the descendants in `self`.
     pub fn bisect(self: &Arc<Self>) -> Arc<Self> {
         Arc::new(Self::Bisect(self.clone()))
@@ -736,6 +759,12 @@ pub enum ResolvedExpression {
     Roots(Box<Self>),
     ForkPoint(Box<Self>),
     Bisect(Box<Self>),
+    /// Commits in `candidates` whose patch ids match any commit in `upstream`
+    /// that isn't an ancestor of them.
+    Landed {
+        candidates: Box<Self>,
+        upstream: Box<Self>,
+    },
     HasSize {
         candidates: Box<Self>,
         count: usize,
@@ -959,6 +988,12 @@ static BUILTIN_FUNCTION_MAP: LazyLock<HashMap<&str, RevsetFunction>> = LazyLock:
         let expression = lower_expression(diagnostics, expression_arg, context)?;
         Ok(RevsetExpression::fork_point(&expression))
     });
+    map.insert("landed", |diagnostics, function, context| {
+        let [upstream_arg, candidates_arg] = function.expect_exact_arguments()?;
+        let upstream = lower_expression(diagnostics, upstream_arg, context)?;
+        let candidates = lower_expression(diagnostics, candidates_arg, context)?;
+        Ok(candidates.landed(&upstream))
+    });
     map.insert("bisect", |diagnostics, function, context| {
         let [expression_arg] = function.expect_exact_arguments()?;
         let expression = lower_expression(diagnostics, expression_arg, context)?;
@@ -1020,6 +1055,22 @@ static BUILTIN_FUNCTION_MAP: LazyLock<HashMap<&str, RevsetFunction>> = LazyLock:
         let predicate = RevsetFilterPredicate::Signed;
         Ok(RevsetExpression::filter(predicate))
     });
+    map.insert("signed_by", |diagnostics, function, _context| {
+        let [arg] = function.expect_exact_arguments()?;
+        let expr = expect_string_expression(diagnostics, arg)?;
+        let predicate = RevsetFilterPredicate::SignedBy(expr);
+        Ok(RevsetExpression::filter(predicate))
+    });
+    map.insert("trusted_signature", |_diagnostics, function, context| {
+        function.expect_no_arguments()?;
+        let Some(trust_policy) = context.trust_policy else {
+            return Ok(RevsetExpression::none());
+        };
+        trust_policy.trusted_signature().map_err(|err| {
+            RevsetParseError::expression("Failed to load trust policy", function.name_span)
+                .with_source(err)
+        })
+    });
     map.insert("mine", |_diagnostics, function, context| {
         function.expect_no_arguments()?;
         // Email address domains are inherently case‐insensitive, and the local‐parts
@@ -1530,6 +1581,15 @@ fn try_transform_expression<St: ExpressionState, E>(
             RevsetExpression::Bisect(expression) => {
                 transform_rec(expression, pre, post)?.map(RevsetExpression::Bisect)
             }
+            RevsetExpression::Landed {
+                candidates,
+                upstream,
+            } => transform_rec_pair((candidates, upstream), pre, post)?.map(
+                |(candidates, upstream)| RevsetExpression::Landed {
+                    candidates,
+                    upstream,
+                },
+            ),
             RevsetExpression::HasSize { candidates, count } => {
                 transform_rec(candidates, pre, post)?.map(|candidates| RevsetExpression::HasSize {
                     candidates,
@@ -1777,6 +1837,18 @@ where
             let expression = folder.fold_expression(expression)?;
             RevsetExpression::Bisect(expression).into()
         }
+        RevsetExpression::Landed {
+            candidates,
+            upstream,
+        } => {
+            let candidates = folder.fold_expression(candidates)?;
+            let upstream = folder.fold_expression(upstream)?;
+            RevsetExpression::Landed {
+                candidates,
+                upstream,
+            }
+            .into()
+        }
         RevsetExpression::HasSize { candidates, count } => {
             let candidates = folder.fold_expression(candidates)?;
             RevsetExpression::HasSize {
@@ -3108,6 +3180,13 @@ impl VisibilityResolutionContext<'_> {
             RevsetExpression::Bisect(expression) => {
                 ResolvedExpression::Bisect(self.resolve(expression).into())
             }
+            RevsetExpression::Landed {
+                candidates,
+                upstream,
+            } => ResolvedExpression::Landed {
+                candidates: self.resolve(candidates).into(),
+                upstream: self.resolve(upstream).into(),
+            },
             RevsetExpression::Latest { candidates, count } => ResolvedExpression::Latest {
                 candidates: self.resolve(candidates).into(),
                 count: *count,
@@ -3237,6 +3316,7 @@ impl VisibilityResolutionContext<'_> {
             | RevsetExpression::Roots(_)
             | RevsetExpression::ForkPoint(_)
             | RevsetExpression::Bisect(_)
+            | RevsetExpression::Landed { .. }
             | RevsetExpression::HasSize { .. }
             | RevsetExpression::Latest { .. } => {
                 ResolvedPredicateExpression::Set(self.resolve(expression).into())
@@ -3402,6 +3482,8 @@ pub struct RevsetParseContext<'a> {
     pub default_ignored_remote: Option<&'a RemoteName>,
     pub extensions: &'a RevsetExtensions,
     pub workspace: Option<RevsetWorkspaceContext<'a>>,
+    /// Definition of `trusted_signature()`. Matches no commits if unset.
+    pub trust_policy: Option<&'a dyn RevsetTrustPolicy>,
 }
 
 impl<'a> RevsetParseContext<'a> {
@@ -3414,6 +3496,7 @@ impl<'a> RevsetParseContext<'a> {
             default_ignored_remote,
             extensions,
             workspace,
+            trust_policy,
         } = *self;
         LoweringContext {
             user_email,
@@ -3421,6 +3504,7 @@ impl<'a> RevsetParseContext<'a> {
             default_ignored_remote,
             extensions,
             workspace,
+            trust_policy,
         }
     }
 }
@@ -3433,6 +3517,7 @@ pub struct LoweringContext<'a> {
     default_ignored_remote: Option<&'a RemoteName>,
     extensions: &'a RevsetExtensions,
     workspace: Option<RevsetWorkspaceContext<'a>>,
+    trust_policy: Option<&'a dyn RevsetTrustPolicy>,
 }
 
 impl<'a> LoweringContext<'a> {
@@ -3449,6 +3534,17 @@ impl<'a> LoweringContext<'a> {
     }
 }
 
+/// Source of the commits accepted by the `trusted_signature()` function.
+///
+/// The expression is requested only when the function is used, so the
+/// implementation can load and parse the policy lazily.
+pub trait RevsetTrustPolicy {
+    /// Returns the expression matching commits with trusted signatures.
+    fn trusted_signature(
+        &self,
+    ) -> Result<Arc<UserRevsetExpression>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
 /// Workspace information needed to parse revset expression.
 #[derive(Clone, Copy, Debug)]
 pub struct RevsetWorkspaceContext<'a> {
@@ -3520,6 +3616,7 @@ mod tests {
             default_ignored_remote: Some("ignored".as_ref()),
             extensions: &RevsetExtensions::default(),
             workspace: None,
+            trust_policy: None,
         };
         super::parse(&mut RevsetDiagnostics::new(), revset_str, &context)
     }
@@ -3550,6 +3647,7 @@ mod tests {
             default_ignored_remote: Some("ignored".as_ref()),
             extensions: &RevsetExtensions::default(),
             workspace: Some(workspace_ctx),
+            trust_policy: None,
         };
         super::parse(&mut RevsetDiagnostics::new(), revset_str, &context)
     }
@@ -3576,6 +3674,7 @@ mod tests {
             default_ignored_remote: Some("ignored".as_ref()),
             extensions: &RevsetExtensions::default(),
             workspace: None,
+            trust_policy: None,
         };
         super::parse_with_modifier(&mut RevsetDiagnostics::new(), revset_str, &context)
     }
@@ -4173,6 +4272,10 @@ mod tests {
         )
         "#);
         insta::assert_debug_snapshot!(parse("signed()").unwrap(), @"Filter(Signed)");
+        insta::assert_debug_snapshot!(
+            parse("signed_by(exact:foo)").unwrap(),
+            @r#"Filter(SignedBy(Pattern(Exact("foo"))))"#);
+        insta::assert_debug_snapshot!(parse("trusted_signature()").unwrap(), @"None");
     }
 
     #[test]
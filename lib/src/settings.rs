@@ -54,6 +54,7 @@ struct UserSettingsData {
     operation_username: String,
     signing_behavior: SignBehavior,
     signing_key: Option<String>,
+    sign_operations: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -138,6 +139,7 @@ impl UserSettings {
         let operation_username = config.get("operation.username")?;
         let signing_behavior = config.get("signing.behavior")?;
         let signing_key = config.get("signing.key").optional()?;
+        let sign_operations = config.get("signing.sign-operations")?;
         let data = UserSettingsData {
             user_name,
             user_email,
@@ -147,6 +149,7 @@ impl UserSettings {
             operation_username,
             signing_behavior,
             signing_key,
+            sign_operations,
         };
         Ok(Self {
             config: Arc::new(config),
@@ -225,6 +228,12 @@ impl UserSettings {
         Ok((backend != "none").then_some(backend))
     }
 
+    /// Whether new operations should be signed with the configured signing
+    /// backend.
+    pub fn sign_operations(&self) -> bool {
+        self.data.sign_operations
+    }
+
     pub fn sign_settings(&self) -> SignSettings {
         SignSettings {
             behavior: self.data.signing_behavior,
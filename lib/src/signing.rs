@@ -17,6 +17,7 @@
 
 use std::fmt::Debug;
 use std::fmt::Display;
+use std::hash::Hash;
 use std::sync::Mutex;
 
 use clru::CLruCache;
@@ -26,6 +27,7 @@ use crate::backend::CommitId;
 use crate::config::ConfigGetError;
 use crate::gpg_signing::GpgBackend;
 use crate::gpg_signing::GpgsmBackend;
+use crate::op_store::OperationId;
 use crate::settings::UserSettings;
 use crate::ssh_signing::SshBackend;
 use crate::store::COMMIT_CACHE_CAPACITY;
@@ -172,6 +174,7 @@ pub struct Signer {
     /// for ownership reasons.
     backends: Vec<Box<dyn SigningBackend>>,
     cache: Mutex<CLruCache<CommitId, Verification>>,
+    op_cache: Mutex<CLruCache<OperationId, Verification>>,
 }
 
 impl Signer {
@@ -210,6 +213,7 @@ impl Signer {
             main_backend,
             backends: other_backends,
             cache: Mutex::new(CLruCache::new(COMMIT_CACHE_CAPACITY.try_into().unwrap())),
+            op_cache: Mutex::new(CLruCache::new(COMMIT_CACHE_CAPACITY.try_into().unwrap())),
         }
     }
 
@@ -235,13 +239,26 @@ impl Signer {
         data: &[u8],
         signature: &[u8],
     ) -> SignResult<Verification> {
-        let cached = self.cache.lock().unwrap().get(commit_id).cloned();
-        if let Some(check) = cached {
-            return Ok(check);
-        }
+        verify_cached(&self.cache, commit_id, || {
+            self.verify_uncached(data, signature)
+        })
+    }
+
+    /// Like [`Signer::verify()`], but for operation signatures.
+    pub fn verify_operation(
+        &self,
+        op_id: &OperationId,
+        data: &[u8],
+        signature: &[u8],
+    ) -> SignResult<Verification> {
+        verify_cached(&self.op_cache, op_id, || {
+            self.verify_uncached(data, signature)
+        })
+    }
 
-        let verification = self
-            .main_backend
+    /// Returns `None` if no backend knows how to handle the signature.
+    fn verify_uncached(&self, data: &[u8], signature: &[u8]) -> SignResult<Option<Verification>> {
+        self.main_backend
             .iter()
             .chain(self.backends.iter())
             .filter(|b| b.can_read(signature))
@@ -252,29 +269,37 @@ impl Signer {
                 Err(SignError::InvalidSignatureFormat) => None,
                 e => Some(e),
             })
-            .transpose()?;
+            .transpose()
+    }
+}
+
+fn verify_cached<K: Clone + Eq + Hash>(
+    cache: &Mutex<CLruCache<K, Verification>>,
+    key: &K,
+    verify: impl FnOnce() -> SignResult<Option<Verification>>,
+) -> SignResult<Verification> {
+    let cached = cache.lock().unwrap().get(key).cloned();
+    if let Some(check) = cached {
+        return Ok(check);
+    }
 
-        if let Some(verification) = verification {
-            // a key might get imported before next call?.
-            // realistically this is unlikely, but technically
-            // it's correct to not cache unknowns here
-            if verification.status != SigStatus::Unknown {
-                self.cache
-                    .lock()
-                    .unwrap()
-                    .put(commit_id.clone(), verification.clone());
-            }
-            Ok(verification)
-        } else {
-            // now here it's correct to cache unknowns, as we don't
-            // have a backend that knows how to handle this signature
-            //
-            // not sure about how much of an optimization this is
-            self.cache
-                .lock()
-                .unwrap()
-                .put(commit_id.clone(), Verification::unknown());
-            Ok(Verification::unknown())
+    if let Some(verification) = verify()? {
+        // a key might get imported before next call?.
+        // realistically this is unlikely, but technically
+        // it's correct to not cache unknowns here
+        if verification.status != SigStatus::Unknown {
+            cache.lock().unwrap().put(key.clone(), verification.clone());
         }
+        Ok(verification)
+    } else {
+        // now here it's correct to cache unknowns, as we don't
+        // have a backend that knows how to handle this signature
+        //
+        // not sure about how much of an optimization this is
+        cache
+            .lock()
+            .unwrap()
+            .put(key.clone(), Verification::unknown());
+        Ok(Verification::unknown())
     }
 }
@@ -401,6 +401,35 @@ enum PostDecodeError {
     EvenNumberOfRefTargetTerms(usize),
 }
 
+/// Encodes the operation in the format of the files in the operations
+/// directory.
+#[cfg(feature = "git")]
+pub(crate) fn encode_operation(operation: &Operation) -> Vec<u8> {
+    operation_to_proto(operation).encode_to_vec()
+}
+
+/// Decodes an operation encoded by [`encode_operation()`].
+#[cfg(feature = "git")]
+pub(crate) fn decode_operation(
+    buf: &[u8],
+) -> Result<Operation, Box<dyn std::error::Error + Send + Sync>> {
+    let proto = crate::protos::simple_op_store::Operation::decode(buf)?;
+    Ok(operation_from_proto(proto)?)
+}
+
+/// Encodes the view in the format of the files in the views directory.
+#[cfg(feature = "git")]
+pub(crate) fn encode_view(view: &View) -> Vec<u8> {
+    view_to_proto(view).encode_to_vec()
+}
+
+/// Decodes a view encoded by [`encode_view()`].
+#[cfg(feature = "git")]
+pub(crate) fn decode_view(buf: &[u8]) -> Result<View, Box<dyn std::error::Error + Send + Sync>> {
+    let proto = crate::protos::simple_op_store::View::decode(buf)?;
+    Ok(view_from_proto(proto)?)
+}
+
 fn operation_id_from_proto(bytes: Vec<u8>) -> Result<OperationId, PostDecodeError> {
     if bytes.len() != OPERATION_ID_LENGTH {
         Err(PostDecodeError::InvalidHashLength {
@@ -510,6 +539,7 @@ fn operation_to_proto(operation: &Operation) -> crate::protos::simple_op_store::
         metadata: Some(operation_metadata_to_proto(&operation.metadata)),
         commit_predecessors,
         stores_commit_predecessors,
+        signature: operation.signature.clone(),
     }
 }
 
@@ -531,6 +561,7 @@ fn operation_from_proto(
         parents,
         metadata,
         commit_predecessors,
+        signature: proto.signature,
     })
 }
 
@@ -1041,6 +1072,7 @@ mod tests {
                     CommitId::from_hex("444444"),
                 ],
             }),
+            signature: None,
         }
     }
 
@@ -1088,6 +1120,30 @@ mod tests {
         assert_eq!(read_operation, operation);
     }
 
+    #[test]
+    fn test_read_write_signed_operation() {
+        let temp_dir = new_temp_dir();
+        let root_data = RootOperationData {
+            root_commit_id: CommitId::from_hex("000000"),
+        };
+        let store = SimpleOpStore::init(temp_dir.path(), root_data).unwrap();
+        let unsigned_operation = create_operation();
+        let unsigned_op_id = store
+            .write_operation(&unsigned_operation)
+            .block_on()
+            .unwrap();
+        let operation = Operation {
+            signature: Some(b"signature".to_vec()),
+            ..unsigned_operation.clone()
+        };
+        let op_id = store.write_operation(&operation).block_on().unwrap();
+        assert_ne!(op_id, unsigned_op_id);
+        let read_operation = store.read_operation(&op_id).block_on().unwrap();
+        assert_eq!(read_operation, operation);
+        // The signed data doesn't depend on the signature.
+        assert_eq!(operation.signed_data(), unsigned_operation.signed_data());
+    }
+
     #[test]
     fn test_remote_views_legacy_roundtrip() {
         let mut view = create_view();
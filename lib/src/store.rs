@@ -40,6 +40,8 @@ use crate::backend::TreeId;
 use crate::commit::Commit;
 use crate::index::Index;
 use crate::merged_tree::MergedTree;
+use crate::patch_id::PatchId;
+use crate::patch_id::commit_patch_id;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 use crate::signing::Signer;
@@ -50,6 +52,9 @@ use crate::tree_merge::MergeOptions;
 // commits.
 pub(crate) const COMMIT_CACHE_CAPACITY: usize = 100;
 const TREE_CACHE_CAPACITY: usize = 1000;
+// Patch ids are compared across long ranges of history, and are expensive to
+// compute.
+const PATCH_ID_CACHE_CAPACITY: usize = 10000;
 
 /// Wraps the low-level backend and makes it return more convenient types. Also
 /// adds caching.
@@ -58,6 +63,7 @@ pub struct Store {
     signer: Signer,
     commit_cache: Mutex<CLruCache<CommitId, Arc<backend::Commit>>>,
     tree_cache: Mutex<CLruCache<(RepoPathBuf, TreeId), Arc<backend::Tree>>>,
+    patch_id_cache: Mutex<CLruCache<CommitId, Option<PatchId>>>,
     merge_options: MergeOptions,
 }
 
@@ -80,6 +86,7 @@ impl Store {
             signer,
             commit_cache: Mutex::new(CLruCache::new(COMMIT_CACHE_CAPACITY.try_into().unwrap())),
             tree_cache: Mutex::new(CLruCache::new(TREE_CACHE_CAPACITY.try_into().unwrap())),
+            patch_id_cache: Mutex::new(CLruCache::new(PATCH_ID_CACHE_CAPACITY.try_into().unwrap())),
             merge_options,
         })
     }
@@ -166,6 +173,21 @@ impl Store {
         Ok(data)
     }
 
+    /// Returns the patch id of the changes made by the `commit`. See
+    /// [`commit_patch_id()`] for details.
+    pub async fn get_patch_id(&self, commit: &Commit) -> BackendResult<Option<PatchId>> {
+        {
+            let mut locked_cache = self.patch_id_cache.lock().unwrap();
+            if let Some(patch_id) = locked_cache.get(commit.id()).cloned() {
+                return Ok(patch_id);
+            }
+        }
+        let patch_id = commit_patch_id(commit).await?;
+        let mut locked_cache = self.patch_id_cache.lock().unwrap();
+        locked_cache.put(commit.id().clone(), patch_id.clone());
+        Ok(patch_id)
+    }
+
     pub async fn write_commit(
         self: &Arc<Self>,
         commit: backend::Commit,
@@ -20,6 +20,7 @@ use itertools::Itertools as _;
 use pollster::FutureExt as _;
 use thiserror::Error;
 
+use crate::backend::SigningFn;
 use crate::backend::Timestamp;
 use crate::dag_walk;
 use crate::index::IndexStoreError;
@@ -36,7 +37,10 @@ use crate::repo::ReadonlyRepo;
 use crate::repo::Repo as _;
 use crate::repo::RepoLoader;
 use crate::repo::RepoLoaderError;
+use crate::settings::SignSettings;
 use crate::settings::UserSettings;
+use crate::signing::SignError;
+use crate::signing::Signer;
 use crate::view::View;
 
 /// Error from attempts to write and publish transaction.
@@ -46,6 +50,7 @@ pub enum TransactionCommitError {
     IndexStore(#[from] IndexStoreError),
     OpHeadsStore(#[from] OpHeadsStoreError),
     OpStore(#[from] OpStoreError),
+    Sign(#[from] SignError),
 }
 
 /// An in-memory representation of a repo and any changes being made to it.
@@ -64,6 +69,7 @@ pub struct Transaction {
     parent_ops: Vec<Operation>,
     op_metadata: OperationMetadata,
     end_time: Option<Timestamp>,
+    sign_settings: Option<SignSettings>,
 }
 
 impl Transaction {
@@ -71,11 +77,15 @@ impl Transaction {
         let parent_ops = vec![mut_repo.base_repo().operation().clone()];
         let op_metadata = create_op_metadata(user_settings, "".to_string(), false);
         let end_time = user_settings.operation_timestamp();
+        let sign_settings = user_settings
+            .sign_operations()
+            .then(|| user_settings.sign_settings());
         Self {
             mut_repo,
             parent_ops,
             op_metadata,
             end_time,
+            sign_settings,
         }
     }
 
@@ -148,12 +158,17 @@ impl Transaction {
             self.op_metadata.description = description.into();
             self.op_metadata.time.end = self.end_time.unwrap_or_else(Timestamp::now);
             let parents = self.parent_ops.iter().map(|op| op.id().clone()).collect();
-            let store_operation = op_store::Operation {
+            let mut store_operation = op_store::Operation {
                 view_id,
                 parents,
                 metadata: self.op_metadata,
                 commit_predecessors: Some(predecessors),
+                signature: None,
             };
+            let signer = base_repo.store().signer();
+            if let Some(mut sign) = new_operation_signing_fn(signer, self.sign_settings)? {
+                store_operation.signature = Some(sign(&store_operation.signed_data())?);
+            }
             let new_op_id = base_repo
                 .op_store()
                 .write_operation(&store_operation)
@@ -167,6 +182,38 @@ impl Transaction {
     }
 }
 
+/// Returns a function to sign operations with if `signing.sign-operations` is
+/// enabled.
+///
+/// Returns an error if operations should be signed, but no signing backend is
+/// configured.
+pub fn operation_signing_fn<'a>(
+    signer: &'a Signer,
+    user_settings: &UserSettings,
+) -> Result<Option<Box<SigningFn<'a>>>, SignError> {
+    let sign_settings = user_settings
+        .sign_operations()
+        .then(|| user_settings.sign_settings());
+    new_operation_signing_fn(signer, sign_settings)
+}
+
+fn new_operation_signing_fn(
+    signer: &Signer,
+    sign_settings: Option<SignSettings>,
+) -> Result<Option<Box<SigningFn<'_>>>, SignError> {
+    let Some(sign_settings) = sign_settings else {
+        return Ok(None);
+    };
+    if !signer.can_sign() {
+        return Err(SignError::Backend(
+            "`signing.sign-operations` is enabled, but no signing backend is configured".into(),
+        ));
+    }
+    Ok(Some(Box::new(move |data| {
+        signer.sign(data, sign_settings.key.as_deref())
+    })))
+}
+
 pub fn create_op_metadata(
     user_settings: &UserSettings,
     description: String,
@@ -20,6 +20,7 @@ use std::collections::BTreeMap;
 use std::ffi::OsString;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use itertools::Itertools as _;
@@ -222,11 +223,34 @@ pub struct SnapshotOptions<'a> {
     /// match this.
     pub start_tracking_matcher: &'a dyn Matcher,
     /// The size of the largest file that should be allowed to become tracked
-    /// (already tracked files are always snapshotted). If there are larger
-    /// files in the working copy, then `LockedWorkingCopy::snapshot()` may
-    /// (depending on implementation)
-    /// return `SnapshotError::NewFileTooLarge`.
+    /// (already tracked files are always snapshotted). Larger files in the
+    /// working copy are left untracked and reported as
+    /// [`UntrackedReason::FileTooLarge`].
     pub max_new_file_size: u64,
+    /// Limits overriding `max_new_file_size` for matching paths. The first
+    /// matching entry applies.
+    pub max_new_file_size_overrides: Vec<NewFileSizeLimit>,
+}
+
+impl SnapshotOptions<'_> {
+    /// Returns the size of the largest file at `path` that should be allowed
+    /// to become tracked.
+    pub fn max_new_file_size_for(&self, path: &RepoPath) -> u64 {
+        self.max_new_file_size_overrides
+            .iter()
+            .find(|limit| limit.matcher.matches(path))
+            .map_or(self.max_new_file_size, |limit| limit.max_size)
+    }
+}
+
+/// Size limit for new files matching a fileset.
+#[derive(Clone, Debug)]
+pub struct NewFileSizeLimit {
+    /// Paths this limit applies to.
+    pub matcher: Arc<dyn Matcher>,
+    /// The size of the largest matching file that should be allowed to become
+    /// tracked.
+    pub max_size: u64,
 }
 
 /// A callback for getting progress updates.
@@ -237,6 +261,21 @@ pub type SnapshotProgress<'a> = dyn Fn(&RepoPath) + 'a + Sync;
 pub struct SnapshotStats {
     /// List of new (previously untracked) files which are still untracked.
     pub untracked_paths: BTreeMap<RepoPathBuf, UntrackedReason>,
+    /// Number of threads used to scan the working copy.
+    pub num_threads: usize,
+    /// Time spent in each phase of the snapshot.
+    pub timings: SnapshotTimings,
+}
+
+/// Time spent in each phase of a snapshot operation.
+#[derive(Clone, Debug, Default)]
+pub struct SnapshotTimings {
+    /// Time spent querying the filesystem monitor, if any.
+    pub fsmonitor: Duration,
+    /// Time spent walking the working copy and hashing changed files.
+    pub traverse_filesystem: Duration,
+    /// Time spent building and writing the new tree.
+    pub write_tree: Duration,
 }
 
 /// Reason why the new path isn't tracked.
@@ -33,6 +33,7 @@ use crate::file_util::IoResultExt as _;
 use crate::file_util::PathError;
 use crate::local_working_copy::LocalWorkingCopy;
 use crate::local_working_copy::LocalWorkingCopyFactory;
+use crate::native_backend::NativeBackend;
 use crate::op_heads_store::OpHeadsStoreError;
 use crate::op_store::OperationId;
 use crate::ref_name::WorkspaceName;
@@ -194,6 +195,18 @@ impl Workspace {
         Self::init_with_backend(user_settings, workspace_root, backend_initializer, signer)
     }
 
+    /// Initializes a workspace with a new native backend, which stores objects
+    /// in compressed pack files.
+    pub fn init_native(
+        user_settings: &UserSettings,
+        workspace_root: &Path,
+    ) -> Result<(Self, Arc<ReadonlyRepo>), WorkspaceInitError> {
+        let backend_initializer: &BackendInitializer =
+            &|_settings, store_path| Ok(Box::new(NativeBackend::init(store_path)?));
+        let signer = Signer::from_settings(user_settings)?;
+        Self::init_with_backend(user_settings, workspace_root, backend_initializer, signer)
+    }
+
     /// Initializes a workspace with a new Git backend and bare Git repo in
     /// `.jj/repo/store/git`.
     #[cfg(feature = "git")]
@@ -12,6 +12,7 @@ mod test_bisect;
 mod test_commit_builder;
 mod test_commit_concurrent;
 mod test_conflicts;
+mod test_content_filter;
 mod test_default_revset_graph_iterator;
 mod test_eol;
 mod test_evolution_predecessors;
@@ -29,6 +30,7 @@ mod test_local_working_copy_sparse;
 mod test_merge_trees;
 mod test_merged_tree;
 mod test_mut_repo;
+mod test_native_backend;
 mod test_operations;
 mod test_refs;
 mod test_revset;
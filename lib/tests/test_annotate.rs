@@ -18,7 +18,10 @@ use std::sync::Arc;
 use itertools::Itertools as _;
 use jj_lib::annotate::FileAnnotation;
 use jj_lib::annotate::FileAnnotator;
+use jj_lib::annotate::FileContentConverter;
+use jj_lib::backend::BackendResult;
 use jj_lib::backend::CommitId;
+use jj_lib::backend::FileId;
 use jj_lib::backend::MergedTreeId;
 use jj_lib::backend::MillisSinceEpoch;
 use jj_lib::backend::Signature;
@@ -410,3 +413,50 @@ fn test_annotate_file_directory_transition() {
 
     insta::assert_snapshot!(annotate(tx.repo(), &commit2, file_path2), @"commit2:1 : 2");
 }
+
+#[test]
+fn test_annotate_with_converter() {
+    // Splits comma-separated values into lines
+    #[derive(Debug)]
+    struct CsvConverter;
+
+    impl FileContentConverter for CsvConverter {
+        fn convert(
+            &self,
+            _path: &RepoPath,
+            _id: &FileId,
+            content: Vec<u8>,
+        ) -> BackendResult<Vec<u8>> {
+            Ok(content
+                .into_iter()
+                .map(|b| if b == b',' { b'\n' } else { b })
+                .collect())
+        }
+    }
+
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let root_commit_id = repo.store().root_commit_id();
+    let file_path = repo_path("file");
+
+    let mut tx = repo.start_transaction();
+    let mut create_commit = create_commit_fn(tx.repo_mut());
+    let tree1 = create_tree(repo, &[(file_path, "1a,1b,")]);
+    let tree2 = create_tree(repo, &[(file_path, "1a,2,1b,")]);
+    let commit1 = create_commit("commit1", &[root_commit_id], tree1.id());
+    let commit2 = create_commit("commit2", &[commit1.id()], tree2.id());
+    drop(create_commit);
+
+    let mut annotator =
+        FileAnnotator::from_commit_with_converter(&commit2, file_path, Arc::new(CsvConverter))
+            .unwrap();
+    annotator
+        .compute(tx.repo(), &RevsetExpression::all())
+        .unwrap();
+    insta::assert_snapshot!(format_annotation(tx.repo(), &annotator.to_annotation()), @r"
+    commit1:1 : 1a
+    commit2:2 : 2
+    commit1:2 : 1b
+    ");
+}
@@ -15,12 +15,18 @@
 use std::path::Path;
 
 use itertools::Itertools as _;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigSource;
+use jj_lib::network_op_heads_store::NetworkOpHeadsStore;
+use jj_lib::object_id::ObjectId as _;
 use jj_lib::repo::Repo as _;
 use jj_lib::repo::StoreFactories;
+use jj_lib::settings::UserSettings;
 use jj_lib::workspace::Workspace;
 use jj_lib::workspace::default_working_copy_factories;
 use pollster::FutureExt as _;
 use test_case::test_case;
+use testutils::TestRepo;
 use testutils::TestRepoBackend;
 use testutils::TestWorkspace;
 use testutils::write_random_commit;
@@ -203,3 +209,44 @@ fn test_bad_locking_interrupted(backend: TestRepoBackend) {
     let reloaded_repo = test_env.load_repo_at_head(&settings, test_workspace.repo_path());
     assert_eq!(reloaded_repo.op_id(), &op_id);
 }
+
+#[test]
+fn test_network_op_heads_store_stale_head() {
+    // Test that an old op-head which is still visible (e.g. because the removal
+    // hasn't propagated to this client yet) is ignored by the network op-heads
+    // store.
+    let mut config = testutils::base_user_config();
+    config.add_layer(
+        ConfigLayer::parse(ConfigSource::User, r#"operation.heads-store = "network""#).unwrap(),
+    );
+    let settings = UserSettings::from_config(config).unwrap();
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let test_env = &test_repo.env;
+    let repo = &test_repo.repo;
+    assert_eq!(repo.op_heads_store().name(), NetworkOpHeadsStore::name());
+
+    let mut tx = repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+    let old_op_id = repo.op_id().clone();
+
+    let op_heads_dir = test_repo.repo_path().join("op_heads");
+    let backup_path = test_env.root().join("backup");
+    copy_directory(&op_heads_dir, &backup_path);
+    let mut tx = repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let new_op_id = tx.commit("test").unwrap().op_id().clone();
+    copy_directory(&backup_path, &op_heads_dir);
+    assert!(op_heads_dir.join("heads").join(old_op_id.hex()).exists());
+
+    let reloaded_repo = test_env.load_repo_at_head(&settings, test_repo.repo_path());
+    assert_eq!(reloaded_repo.op_id(), &new_op_id);
+    assert_eq!(
+        reloaded_repo
+            .op_heads_store()
+            .get_op_heads()
+            .block_on()
+            .unwrap(),
+        vec![new_op_id]
+    );
+}
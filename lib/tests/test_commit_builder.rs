@@ -68,6 +68,7 @@ fn to_owned_path_vec(paths: &[&RepoPath]) -> Vec<RepoPathBuf> {
 }
 
 #[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Native ; "native backend")]
 #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_initial(backend: TestRepoBackend) {
     let test_repo = TestRepo::init_with_backend(backend);
@@ -138,6 +139,7 @@ fn test_initial(backend: TestRepoBackend) {
 }
 
 #[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Native ; "native backend")]
 #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_rewrite(backend: TestRepoBackend) {
     let settings = testutils::user_settings();
@@ -239,6 +241,7 @@ fn test_rewrite(backend: TestRepoBackend) {
 
 // An author field with an empty name/email should get filled in on rewrite
 #[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Native ; "native backend")]
 #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_rewrite_update_missing_user(backend: TestRepoBackend) {
     let missing_user_settings = UserSettings::from_config(StackedConfig::with_defaults()).unwrap();
@@ -295,6 +298,7 @@ fn test_rewrite_update_missing_user(backend: TestRepoBackend) {
 }
 
 #[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Native ; "native backend")]
 #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_rewrite_resets_author_timestamp(backend: TestRepoBackend) {
     let test_repo = TestRepo::init_with_backend(backend);
@@ -370,6 +374,7 @@ fn test_rewrite_resets_author_timestamp(backend: TestRepoBackend) {
 }
 
 #[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Native ; "native backend")]
 #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_rewrite_to_identical_commit(backend: TestRepoBackend) {
     let timestamp = "2001-02-03T04:05:06+07:00";
@@ -421,6 +426,7 @@ fn test_rewrite_to_identical_commit(backend: TestRepoBackend) {
 }
 
 #[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Native ; "native backend")]
 // #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_commit_builder_descendants(backend: TestRepoBackend) {
     let test_repo = TestRepo::init_with_backend(backend);
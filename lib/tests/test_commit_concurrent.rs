@@ -44,6 +44,7 @@ fn count_non_merge_operations(repo: &Arc<ReadonlyRepo>) -> usize {
 }
 
 #[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Native ; "native backend")]
 #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_commit_parallel(backend: TestRepoBackend) {
     // This loads a Repo instance and creates and commits many concurrent
@@ -74,6 +75,7 @@ fn test_commit_parallel(backend: TestRepoBackend) {
 }
 
 #[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Native ; "native backend")]
 #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_commit_parallel_instances(backend: TestRepoBackend) {
     // Like the test above but creates a new repo instance for every thread, which
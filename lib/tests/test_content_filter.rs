@@ -0,0 +1,204 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use assert_matches::assert_matches;
+use jj_lib::backend::TreeValue;
+use jj_lib::config::ConfigGetError;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigSource;
+use jj_lib::content_filter::ContentFilterSettings;
+use jj_lib::local_working_copy::LocalWorkingCopy;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::settings::UserSettings;
+use jj_lib::store::Store;
+use testutils::TestRepoBackend;
+use testutils::TestWorkspace;
+use testutils::base_user_config;
+use testutils::commit_with_tree;
+use testutils::create_tree;
+use testutils::repo_path;
+
+fn user_settings_with_extra_config(extra_config: &str) -> UserSettings {
+    let mut config = base_user_config();
+    config.add_layer(ConfigLayer::parse(ConfigSource::User, extra_config).unwrap());
+    UserSettings::from_config(config).unwrap()
+}
+
+fn read_stored_file(store: &Arc<Store>, tree: &MergedTree, path: &RepoPath) -> Vec<u8> {
+    let Some(TreeValue::File { id, .. }) = tree.path_value(path).unwrap().into_resolved().unwrap()
+    else {
+        panic!("{path:?} should be a file");
+    };
+    testutils::read_file(store, path, &id)
+}
+
+#[test]
+fn test_clean_filter_snapshot() {
+    let settings = user_settings_with_extra_config(
+        r#"
+        [working-copy.filters.strip]
+        patterns = ["glob:*.txt"]
+        clean = ":strip-trailing-whitespace"
+        "#,
+    );
+    let mut test_workspace =
+        TestWorkspace::init_with_backend_and_settings(TestRepoBackend::Test, &settings);
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    testutils::write_working_copy_file(&workspace_root, repo_path("a.txt"), "a  \nb\t\n");
+    testutils::write_working_copy_file(&workspace_root, repo_path("b.md"), "a  \nb\t\n");
+    let tree = test_workspace.snapshot().unwrap();
+    let store = test_workspace.repo.store();
+
+    // Only the matching file is filtered in the store
+    assert_eq!(
+        read_stored_file(store, &tree, repo_path("a.txt")),
+        b"a\nb\n"
+    );
+    assert_eq!(
+        read_stored_file(store, &tree, repo_path("b.md")),
+        b"a  \nb\t\n"
+    );
+    // The working copy is left as is
+    assert_eq!(
+        std::fs::read(workspace_root.join("a.txt")).unwrap(),
+        b"a  \nb\t\n"
+    );
+    // ... and considered clean
+    let new_tree = test_workspace.snapshot().unwrap();
+    assert_eq!(new_tree.id(), tree.id());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_smudge_filter_checkout() {
+    let settings = user_settings_with_extra_config(
+        r#"
+        [working-copy.filters.secret]
+        patterns = ["secret.conf"]
+        clean = ["sed", "s/hunter2/REDACTED/"]
+        smudge = ["sed", "s/REDACTED/hunter2/"]
+        "#,
+    );
+    if !testutils::is_external_tool_installed("sed") {
+        testutils::ensure_running_outside_ci("`sed` must be in the PATH");
+        eprintln!("Skipping test because sed is not installed on the system");
+        return;
+    }
+    let mut test_workspace =
+        TestWorkspace::init_with_backend_and_settings(TestRepoBackend::Test, &settings);
+    let repo = test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let path = repo_path("secret.conf");
+
+    let tree = create_tree(&repo, &[(path, "password=REDACTED\n")]);
+    let commit = commit_with_tree(repo.store(), tree.id());
+    test_workspace
+        .workspace
+        .check_out(repo.op_id().clone(), None, &commit)
+        .unwrap();
+    assert_eq!(
+        std::fs::read(workspace_root.join("secret.conf")).unwrap(),
+        b"password=hunter2\n"
+    );
+    // The smudged output is cached
+    let wc: &LocalWorkingCopy = test_workspace
+        .workspace
+        .working_copy()
+        .downcast_ref()
+        .unwrap();
+    let cache_dir = wc.state_path().join("filter_cache");
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 1);
+
+    // Unchanged file is cleaned back to the stored content
+    std::fs::write(workspace_root.join("secret.conf"), "password=hunter2\n").unwrap();
+    let new_tree = test_workspace.snapshot().unwrap();
+    assert_eq!(new_tree.id(), tree.id());
+    assert_eq!(
+        read_stored_file(repo.store(), &new_tree, path),
+        b"password=REDACTED\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_clean_filter_failure() {
+    let settings = user_settings_with_extra_config(
+        r#"
+        [working-copy.filters.broken]
+        patterns = ["all()"]
+        clean = ["false"]
+        "#,
+    );
+    let mut test_workspace =
+        TestWorkspace::init_with_backend_and_settings(TestRepoBackend::Test, &settings);
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    testutils::write_working_copy_file(&workspace_root, repo_path("file"), "contents\n");
+    assert!(test_workspace.snapshot().is_err());
+}
+
+#[test]
+fn test_filter_settings() {
+    let settings = user_settings_with_extra_config(
+        r#"
+        [working-copy.filters.b]
+        patterns = ["glob:*.ipynb"]
+        clean = ["nbstripout", "--textconv", "$path"]
+
+        [working-copy.filters.a]
+        patterns = ["secrets"]
+        smudge = ":strip-trailing-whitespace"
+
+        [working-copy.filters.disabled]
+        patterns = ["all()"]
+        clean = ["false"]
+        enabled = false
+        "#,
+    );
+    let filter_settings = ContentFilterSettings::try_from_settings(&settings).unwrap();
+    let names: Vec<_> = filter_settings
+        .filters
+        .iter()
+        .map(|filter| filter.name.as_str())
+        .collect();
+    assert_eq!(names, ["a", "b"]);
+    assert!(filter_settings.filters[0].clean.is_none());
+    assert!(filter_settings.filters[1].smudge.is_none());
+
+    let settings = user_settings_with_extra_config(
+        r#"
+        [working-copy.filters.bad]
+        patterns = ["all()"]
+        clean = ":no-such-filter"
+        "#,
+    );
+    assert_matches!(
+        ContentFilterSettings::try_from_settings(&settings),
+        Err(ConfigGetError::Type { name, .. }) if name == "working-copy.filters.bad.clean"
+    );
+
+    let settings = user_settings_with_extra_config(
+        r#"
+        [working-copy.filters.bad]
+        patterns = ["glob:("]
+        "#,
+    );
+    assert_matches!(
+        ContentFilterSettings::try_from_settings(&settings),
+        Err(ConfigGetError::Type { name, .. }) if name == "working-copy.filters.bad.patterns"
+    );
+}
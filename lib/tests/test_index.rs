@@ -46,6 +46,7 @@ use pollster::FutureExt as _;
 use test_case::test_case;
 use testutils::TestRepo;
 use testutils::commit_transactions;
+use testutils::create_random_commit;
 use testutils::create_tree;
 use testutils::repo_path;
 use testutils::repo_path_buf;
@@ -1099,6 +1100,213 @@ fn test_merge_changed_path_segments_enabled_and_disabled() {
     assert_eq!(stats.changed_path_commits_range, Some(2..3));
 }
 
+#[test]
+fn test_reachability_bitmaps() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let root_commit_id = repo.store().root_commit_id().clone();
+    let default_index_store: &DefaultIndexStore = repo.index_store().downcast_ref().unwrap();
+
+    // 5 F
+    // | |
+    // 4 E
+    // |/|
+    // | D
+    // C |
+    // |/
+    // B
+    // |
+    // A
+    let mut tx = repo.start_transaction();
+    let commit_a = write_random_commit(tx.repo_mut());
+    let commit_b = write_random_commit_with_parents(tx.repo_mut(), &[&commit_a]);
+    let commit_c = write_random_commit_with_parents(tx.repo_mut(), &[&commit_b]);
+    let commit_d = write_random_commit_with_parents(tx.repo_mut(), &[&commit_b]);
+    let commit_e = write_random_commit_with_parents(tx.repo_mut(), &[&commit_c, &commit_d]);
+    let commit_f = write_random_commit_with_parents(tx.repo_mut(), &[&commit_e]);
+    let repo = tx.commit("test").unwrap();
+
+    let evaluate_range = |repo: &Arc<ReadonlyRepo>, roots: &[&Commit], heads: &[&Commit]| {
+        let expression = ResolvedExpression::Range {
+            roots: ResolvedExpression::Commits(roots.iter().map(|c| c.id().clone()).collect())
+                .into(),
+            heads: ResolvedExpression::Commits(heads.iter().map(|c| c.id().clone()).collect())
+                .into(),
+            generation: GENERATION_RANGE_FULL,
+            parents_range: PARENTS_RANGE_FULL,
+        };
+        let index = as_readonly_index(repo);
+        let revset = index.evaluate_revset(&expression, repo.store()).unwrap();
+        revset.iter().map(Result::unwrap).collect_vec()
+    };
+    let test_cases = [
+        (vec![], vec![&commit_e]),
+        (vec![], vec![&commit_c, &commit_d]),
+        (vec![], vec![&commit_f]),
+        (vec![&commit_c], vec![&commit_e]),
+        (vec![&commit_d], vec![&commit_f]),
+        (vec![&commit_c], vec![&commit_d]),
+        (vec![&commit_c, &commit_d], vec![&commit_f]),
+        (vec![&commit_e], vec![&commit_c]),
+    ];
+    let expected = test_cases
+        .iter()
+        .map(|(roots, heads)| evaluate_range(&repo, roots, heads))
+        .collect_vec();
+    assert!(
+        as_readonly_index(&repo)
+            .stats()
+            .reachability_bitmaps
+            .is_none()
+    );
+    assert_eq!(
+        expected[0],
+        [&commit_e, &commit_d, &commit_c, &commit_b, &commit_a]
+            .map(|c| c.id().clone())
+            .into_iter()
+            .chain([root_commit_id.clone()])
+            .collect_vec()
+    );
+
+    // Build bitmaps for C and E. The unknown commit should be ignored.
+    default_index_store
+        .build_reachability_bitmaps_at_operation(
+            repo.op_id(),
+            repo.store(),
+            &[
+                commit_c.id().clone(),
+                commit_e.id().clone(),
+                CommitId::from_hex("ffffff"),
+            ],
+        )
+        .unwrap();
+    let repo = repo.reload_at(repo.operation()).unwrap();
+    let stats = as_readonly_index(&repo).stats();
+    let bitmap_stats = stats.reachability_bitmaps.unwrap();
+    assert_eq!(bitmap_stats.num_heads, 2);
+    assert_eq!(bitmap_stats.num_reachable_commits, 4 + 6);
+    for ((roots, heads), expected) in test_cases.iter().zip(&expected) {
+        assert_eq!(&evaluate_range(&repo, roots, heads), expected);
+    }
+
+    // Bitmaps should be carried over to descendant operations
+    let mut tx = repo.start_transaction();
+    let commit_g = write_random_commit_with_parents(tx.repo_mut(), &[&commit_f]);
+    let repo = tx.commit("test").unwrap();
+    let stats = as_readonly_index(&repo).stats();
+    assert_eq!(stats.reachability_bitmaps.unwrap().name, bitmap_stats.name);
+    assert_eq!(
+        evaluate_range(&repo, &[&commit_d], &[&commit_g]),
+        [&commit_g, &commit_f, &commit_e, &commit_c].map(|c| c.id().clone())
+    );
+
+    // Commits without bitmaps are resolved from the nearest ancestors with
+    // bitmaps
+    assert_eq!(
+        evaluate_range(&repo, &[], &[&commit_g]),
+        [&commit_g, &commit_f]
+            .map(|c| c.id().clone())
+            .into_iter()
+            .chain(expected[0].iter().cloned())
+            .collect_vec()
+    );
+    assert_eq!(
+        evaluate_range(&repo, &[&commit_f], &[&commit_g]),
+        [commit_g.id().clone()]
+    );
+    assert_eq!(
+        evaluate_range(&repo, &[&commit_g], &[&commit_d]),
+        Vec::<CommitId>::new()
+    );
+
+    // Rebuilding bitmaps replaces the old ones
+    default_index_store
+        .build_reachability_bitmaps_at_operation(
+            repo.op_id(),
+            repo.store(),
+            &[commit_g.id().clone()],
+        )
+        .unwrap();
+    let repo = repo.reload_at(repo.operation()).unwrap();
+    let stats = as_readonly_index(&repo).stats();
+    let bitmap_stats = stats.reachability_bitmaps.unwrap();
+    assert_eq!(bitmap_stats.num_heads, 1);
+    assert_eq!(bitmap_stats.num_reachable_commits, 8);
+    assert_eq!(
+        evaluate_range(&repo, &[&commit_g], &[&commit_f]),
+        Vec::<CommitId>::new()
+    );
+    assert_eq!(
+        evaluate_range(&repo, &[&commit_c], &[&commit_g]),
+        [&commit_g, &commit_f, &commit_e, &commit_d].map(|c| c.id().clone())
+    );
+}
+
+#[test]
+fn test_build_text_index() {
+    let test_repo = TestRepo::init();
+    let repo = test_repo.repo;
+    let default_index_store: &DefaultIndexStore = repo.index_store().downcast_ref().unwrap();
+
+    let write_commit = |mut_repo: &mut MutableRepo, description: &str| {
+        create_random_commit(mut_repo)
+            .set_description(description)
+            .write()
+            .unwrap()
+    };
+    let mut tx = repo.start_transaction();
+    write_commit(tx.repo_mut(), "abcd");
+    write_commit(tx.repo_mut(), "abc");
+    let repo = tx.commit("test").unwrap();
+    let stats = as_readonly_index(&repo).stats();
+    assert_eq!(stats.text_index_commits_range, None);
+    assert!(stats.text_index_levels.is_empty());
+
+    // Index all commits including the root
+    default_index_store
+        .build_text_index_at_operation(repo.op_id(), repo.store())
+        .block_on()
+        .unwrap();
+    let repo = repo.reload_at(repo.operation()).unwrap();
+    let stats = as_readonly_index(&repo).stats();
+    assert_eq!(stats.text_index_commits_range, Some(0..3));
+    assert_eq!(stats.text_index_levels.len(), 1);
+    assert_eq!(stats.text_index_levels[0].num_commits, 3);
+
+    // New commits should be indexed incrementally, including the ones merged
+    // from concurrent operations
+    let tx1 = {
+        let mut tx = repo.start_transaction();
+        write_commit(tx.repo_mut(), "foo");
+        tx
+    };
+    let tx2 = {
+        let mut tx = repo.start_transaction();
+        write_commit(tx.repo_mut(), "bar");
+        write_commit(tx.repo_mut(), "baz");
+        tx
+    };
+    let repo = commit_transactions(vec![tx1, tx2]);
+    let stats = as_readonly_index(&repo).stats();
+    assert_eq!(stats.num_commits, 6);
+    assert_eq!(stats.text_index_commits_range, Some(0..6));
+    let num_indexed_commits: u32 = stats
+        .text_index_levels
+        .iter()
+        .map(|level| level.num_commits)
+        .sum();
+    assert_eq!(num_indexed_commits, 6);
+
+    // Already indexed
+    default_index_store
+        .build_text_index_at_operation(repo.op_id(), repo.store())
+        .block_on()
+        .unwrap();
+    let repo = repo.reload_at(repo.operation()).unwrap();
+    let stats = as_readonly_index(&repo).stats();
+    assert_eq!(stats.text_index_commits_range, Some(0..6));
+}
+
 #[test_case(false; "without changed-path index")]
 #[test_case(true; "with changed-path index")]
 fn test_commit_is_empty(indexed: bool) {
@@ -124,6 +124,7 @@ fn test_init_external_git() {
 }
 
 #[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Native ; "native backend")]
 #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_init_with_default_config(backend: TestRepoBackend) {
     // Test that we can create a repo without setting any non-default config
@@ -142,6 +143,7 @@ fn test_init_with_default_config(backend: TestRepoBackend) {
 }
 
 #[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Native ; "native backend")]
 #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_init_checkout(backend: TestRepoBackend) {
     // Test the contents of the working-copy commit after init
@@ -28,13 +28,17 @@ use jj_lib::backend::CopyId;
 use jj_lib::backend::MergedTreeId;
 use jj_lib::backend::TreeId;
 use jj_lib::backend::TreeValue;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigSource;
 use jj_lib::file_util::check_symlink_support;
 use jj_lib::file_util::try_symlink;
 use jj_lib::fsmonitor::FsmonitorSettings;
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::local_working_copy::LocalWorkingCopy;
 use jj_lib::local_working_copy::TreeState;
+use jj_lib::local_working_copy::TreeStateFormat;
 use jj_lib::local_working_copy::TreeStateSettings;
+use jj_lib::matchers::PrefixMatcher;
 use jj_lib::merge::Merge;
 use jj_lib::merge::MergedTreeValue;
 use jj_lib::merged_tree::MergedTree;
@@ -46,9 +50,11 @@ use jj_lib::repo::Repo as _;
 use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::secret_backend::SecretBackend;
+use jj_lib::settings::UserSettings;
 use jj_lib::tree_builder::TreeBuilder;
 use jj_lib::working_copy::CheckoutError;
 use jj_lib::working_copy::CheckoutStats;
+use jj_lib::working_copy::NewFileSizeLimit;
 use jj_lib::working_copy::SnapshotOptions;
 use jj_lib::working_copy::UntrackedReason;
 use jj_lib::working_copy::WorkingCopy as _;
@@ -411,6 +417,59 @@ fn test_checkout_no_op() {
     assert_eq!(*wc.operation_id(), new_op_id);
 }
 
+#[test]
+fn test_checkout_parallel() {
+    // Check out enough files to be written in several parallel batches, with
+    // deletions interleaved so that empty directories get removed in between.
+    let mut config = testutils::base_user_config();
+    config.add_layer(ConfigLayer::parse(ConfigSource::User, "working-copy.threads = 3").unwrap());
+    let settings = UserSettings::from_config(config).unwrap();
+    let mut test_workspace =
+        TestWorkspace::init_with_backend_and_settings(TestRepoBackend::Test, &settings);
+    let repo = test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+
+    let paths1 = (0..2500)
+        .map(|i| repo_path_buf(format!("dir{}/file{i}", i % 7)))
+        .collect_vec();
+    let paths2 = (0..2500)
+        .filter(|i| i % 3 != 0)
+        .map(|i| repo_path_buf(format!("dir{}/file{i}", i % 5)))
+        .collect_vec();
+    let tree1 = create_tree_with(&repo, |builder| {
+        for path in &paths1 {
+            builder.file(path, format!("contents of {path:?}"));
+        }
+    });
+    let tree2 = create_tree_with(&repo, |builder| {
+        for path in &paths2 {
+            builder.file(path, format!("new contents of {path:?}"));
+        }
+    });
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let commit2 = commit_with_tree(repo.store(), tree2.id());
+
+    let ws = &mut test_workspace.workspace;
+    let stats = ws.check_out(repo.op_id().clone(), None, &commit1).unwrap();
+    assert_eq!(stats.added_files, 2500);
+    let stats = ws.check_out(repo.op_id().clone(), None, &commit2).unwrap();
+    assert_eq!(stats.skipped_files, 0);
+    for path in &paths2 {
+        let disk_path = path.to_fs_path_unchecked(&workspace_root);
+        assert_eq!(
+            std::fs::read_to_string(disk_path).unwrap(),
+            format!("new contents of {path:?}")
+        );
+    }
+    let wc: &LocalWorkingCopy = ws.working_copy().downcast_ref().unwrap();
+    let file_states = wc.file_states().unwrap();
+    assert_eq!(file_states.paths().count(), paths2.len());
+
+    // The file states are recorded, so snapshotting finds nothing to do.
+    let new_tree = test_workspace.snapshot().unwrap();
+    assert_eq!(new_tree.id(), tree2.id());
+}
+
 // Test case for issue #2165
 #[test]
 fn test_conflict_subdirectory() {
@@ -2039,6 +2098,130 @@ fn test_fsmonitor() {
     tree_state.save().unwrap();
 }
 
+#[test]
+fn test_tree_state_segmented_format() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let workspace_root = test_repo.env.root().join("workspace");
+    let state_path = test_repo.env.root().join("state");
+    let segments_dir = state_path.join("tree_state_segments");
+    std::fs::create_dir(&workspace_root).unwrap();
+    std::fs::create_dir(&state_path).unwrap();
+    let single_file_settings = TreeStateSettings::try_from_user_settings(repo.settings()).unwrap();
+    let segmented_settings = TreeStateSettings {
+        tree_state_format: TreeStateFormat::Segmented,
+        ..single_file_settings.clone()
+    };
+    let load = |settings: &TreeStateSettings| {
+        TreeState::load(
+            repo.store().clone(),
+            workspace_root.clone(),
+            state_path.clone(),
+            settings,
+        )
+        .unwrap()
+    };
+    let segment_sizes = |tree_state: &TreeState| {
+        tree_state
+            .file_state_segments()
+            .map(|segment| (segment.num_entries, segment.num_removed_paths))
+            .collect_vec()
+    };
+    let file_states = |tree_state: &TreeState| {
+        tree_state
+            .file_states()
+            .iter()
+            .map(|(path, state)| (path.to_owned(), state))
+            .collect_vec()
+    };
+    let create_tree_with_files = |files: &[(&str, &str)]| {
+        create_tree_with(repo, |builder| {
+            for &(path, contents) in files {
+                builder.file(repo_path(path), contents);
+            }
+        })
+    };
+
+    let files1 = (0..10)
+        .map(|i| (format!("file{i}"), "a".to_owned()))
+        .collect_vec();
+    let files1 = files1
+        .iter()
+        .map(|(path, contents)| (path.as_str(), contents.as_str()))
+        .collect_vec();
+    let tree1 = create_tree_with_files(&files1);
+    let mut tree_state = TreeState::init(
+        repo.store().clone(),
+        workspace_root.clone(),
+        state_path.clone(),
+        &single_file_settings,
+    )
+    .unwrap();
+    tree_state.check_out(&tree1).unwrap();
+    tree_state.save().unwrap();
+    assert_eq!(segment_sizes(&tree_state), []);
+    assert!(!segments_dir.exists());
+
+    // The single-file state is migrated when saved in the segmented format
+    let mut tree_state = load(&segmented_settings);
+    let expected_file_states = file_states(&tree_state);
+    assert_eq!(segment_sizes(&tree_state), []);
+    tree_state.save().unwrap();
+    assert_eq!(segment_sizes(&tree_state), [(10, 0)]);
+    let mut tree_state = load(&segmented_settings);
+    assert_eq!(segment_sizes(&tree_state), [(10, 0)]);
+    assert_eq!(file_states(&tree_state), expected_file_states);
+
+    // Only the changed entries are written to a new segment
+    let mut files2 = files1.clone();
+    files2[0].1 = "b";
+    files2.remove(1);
+    files2.push(("file_new", "c"));
+    let tree2 = create_tree_with_files(&files2);
+    tree_state.check_out(&tree2).unwrap();
+    tree_state.save().unwrap();
+    assert_eq!(segment_sizes(&tree_state), [(10, 0), (2, 1)]);
+    let expected_file_states = file_states(&tree_state);
+    let mut tree_state = load(&segmented_settings);
+    assert_eq!(file_states(&tree_state), expected_file_states);
+
+    let mut files3 = files2.clone();
+    files3[1].1 = "b";
+    let tree3 = create_tree_with_files(&files3);
+    tree_state.check_out(&tree3).unwrap();
+    tree_state.save().unwrap();
+    assert_eq!(segment_sizes(&tree_state), [(10, 0), (2, 1), (1, 0)]);
+    let expected_file_states = file_states(&tree_state);
+    let mut tree_state = load(&segmented_settings);
+    assert_eq!(file_states(&tree_state), expected_file_states);
+
+    // Segments of similar sizes are squashed, eventually into a new base
+    let mut files4 = files3.clone();
+    files4[2].1 = "b";
+    files4[3].1 = "b";
+    let tree4 = create_tree_with_files(&files4);
+    tree_state.check_out(&tree4).unwrap();
+    tree_state.save().unwrap();
+    assert_eq!(segment_sizes(&tree_state), [(10, 0)]);
+    let expected_file_states = file_states(&tree_state);
+    let mut tree_state = load(&segmented_settings);
+    assert_eq!(file_states(&tree_state), expected_file_states);
+
+    // Segments of the previous state are kept until the next save
+    assert_eq!(std::fs::read_dir(&segments_dir).unwrap().count(), 4);
+    tree_state.save().unwrap();
+    assert_eq!(std::fs::read_dir(&segments_dir).unwrap().count(), 1);
+
+    // The segmented state is migrated back when saved in the single-file format
+    let mut tree_state = load(&single_file_settings);
+    assert_eq!(segment_sizes(&tree_state), [(10, 0)]);
+    tree_state.save().unwrap();
+    assert_eq!(segment_sizes(&tree_state), []);
+    let tree_state = load(&single_file_settings);
+    assert_eq!(segment_sizes(&tree_state), []);
+    assert_eq!(file_states(&tree_state), expected_file_states);
+}
+
 #[test]
 fn test_snapshot_max_new_file_size() {
     let mut test_workspace = TestWorkspace::init();
@@ -2121,3 +2304,40 @@ fn test_snapshot_max_new_file_size() {
         UntrackedReason::FileTooLarge { .. }
     );
 }
+
+#[test]
+fn test_snapshot_max_new_file_size_overrides() {
+    let mut test_workspace = TestWorkspace::init();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let limit: usize = 1024;
+    for path in ["large", "assets/large", "assets/vendor/large"] {
+        testutils::write_working_copy_file(&workspace_root, repo_path(path), vec![0; limit + 1]);
+    }
+    let options = SnapshotOptions {
+        max_new_file_size: limit as u64,
+        max_new_file_size_overrides: vec![
+            NewFileSizeLimit {
+                matcher: Arc::new(PrefixMatcher::new([repo_path("assets/vendor")])),
+                max_size: limit as u64,
+            },
+            NewFileSizeLimit {
+                matcher: Arc::new(PrefixMatcher::new([repo_path("assets")])),
+                max_size: u64::MAX,
+            },
+        ],
+        ..empty_snapshot_options()
+    };
+    let (tree, stats) = test_workspace.snapshot_with_options(&options).unwrap();
+    assert_eq!(
+        tree.entries().map(|(path, _)| path).collect_vec(),
+        [repo_path_buf("assets/large")]
+    );
+    assert_eq!(
+        stats
+            .untracked_paths
+            .keys()
+            .map(AsRef::as_ref)
+            .collect_vec(),
+        [repo_path("assets/vendor/large"), repo_path("large")]
+    );
+}
@@ -0,0 +1,260 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use assert_matches::assert_matches;
+use itertools::Itertools as _;
+use jj_lib::backend::Backend as _;
+use jj_lib::backend::BackendError;
+use jj_lib::backend::MergedTreeId;
+use jj_lib::merge::Merge;
+use jj_lib::native_backend::NativeBackend;
+use jj_lib::native_backend::NativeBackendStats;
+use jj_lib::repo::ReadonlyRepo;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use pollster::FutureExt as _;
+use testutils::TestRepo;
+use testutils::TestRepoBackend;
+use testutils::create_single_tree;
+use testutils::create_tree;
+use testutils::repo_path;
+
+fn get_native_backend(repo: &Arc<ReadonlyRepo>) -> &NativeBackend {
+    repo.store().backend_impl().unwrap()
+}
+
+#[test]
+fn test_native_backend_conflicted_tree() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Native);
+    let repo = &test_repo.repo;
+
+    let base = create_single_tree(repo, &[(repo_path("file"), "base\n")]);
+    let side1 = create_single_tree(repo, &[(repo_path("file"), "1\n")]);
+    let side2 = create_single_tree(repo, &[(repo_path("file"), "2\n")]);
+    let tree_id = MergedTreeId::new(Merge::from_vec(vec![
+        side1.id().clone(),
+        base.id().clone(),
+        side2.id().clone(),
+    ]));
+    let mut tx = repo.start_transaction();
+    let commit = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree_id.clone())
+        .write()
+        .unwrap();
+    tx.commit("test").unwrap();
+
+    // Reload the commit from a fresh backend
+    let backend = NativeBackend::load(&test_repo.repo_path().join("store")).unwrap();
+    let stored = backend.read_commit(commit.id()).block_on().unwrap();
+    assert_eq!(stored.root_tree, tree_id);
+    assert_eq!(stored.change_id, *commit.change_id());
+}
+
+#[test]
+fn test_native_backend_gc() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Native);
+    let store_path = test_repo.repo_path().join("store");
+    let repo = test_repo.repo;
+    let backend = get_native_backend(&repo);
+    let base_index = repo.readonly_index();
+
+    // Set up commits:
+    //
+    // C B
+    // |/
+    // A
+    let mut tx = repo.start_transaction();
+    let tree_a = create_tree(&repo, &[(repo_path("a"), "a\n")]);
+    let tree_b = create_tree(&repo, &[(repo_path("a"), "a\n"), (repo_path("b"), "b\n")]);
+    let tree_c = create_tree(&repo, &[(repo_path("a"), "a\n"), (repo_path("c"), "c\n")]);
+    let commit_a = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree_a.id())
+        .write()
+        .unwrap();
+    let commit_b = tx
+        .repo_mut()
+        .new_commit(vec![commit_a.id().clone()], tree_b.id())
+        .write()
+        .unwrap();
+    let commit_c = tx
+        .repo_mut()
+        .new_commit(vec![commit_a.id().clone()], tree_c.id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    let stats = backend.stats().unwrap();
+    assert_eq!(stats.num_packs, 0);
+    assert_eq!(stats.num_packed_objects, 0);
+    assert!(stats.num_loose_objects > 0);
+
+    // Don't rely on the exact system time because file modification time might
+    // have lower precision for example.
+    let now = || SystemTime::now() + Duration::from_secs(1);
+
+    // All reachable objects are packed. The empty tree from the root commit is
+    // also kept.
+    repo.store().gc(repo.index(), now()).unwrap();
+    assert_eq!(
+        backend.stats().unwrap(),
+        NativeBackendStats {
+            num_packs: 1,
+            // 3 commits, 4 trees, 3 files
+            num_packed_objects: 10,
+            num_loose_objects: 0,
+        }
+    );
+    let fresh_backend = NativeBackend::load(&store_path).unwrap();
+    for commit in [&commit_a, &commit_b, &commit_c] {
+        let stored = fresh_backend.read_commit(commit.id()).block_on().unwrap();
+        assert_eq!(stored, **commit.store_commit());
+    }
+
+    // Repacking is idempotent
+    repo.store().gc(repo.index(), now()).unwrap();
+    assert_eq!(backend.stats().unwrap().num_packs, 1);
+    assert_eq!(backend.stats().unwrap().num_packed_objects, 10);
+
+    // Empty index, but all kept by file modification time
+    repo.store()
+        .gc(base_index.as_index(), SystemTime::UNIX_EPOCH)
+        .unwrap();
+    assert_eq!(backend.stats().unwrap().num_packed_objects, 10);
+
+    // C is no longer reachable
+    let mut mut_index = base_index.start_modification();
+    mut_index.add_commit(&commit_a).unwrap();
+    mut_index.add_commit(&commit_b).unwrap();
+    repo.store().gc(mut_index.as_index(), now()).unwrap();
+    assert_eq!(
+        backend.stats().unwrap(),
+        NativeBackendStats {
+            num_packs: 1,
+            num_packed_objects: 7,
+            num_loose_objects: 0,
+        }
+    );
+    assert_matches!(
+        backend.read_commit(commit_c.id()).block_on(),
+        Err(BackendError::ObjectNotFound { .. })
+    );
+    let stored = backend.read_commit(commit_b.id()).block_on().unwrap();
+    assert_eq!(stored, **commit_b.store_commit());
+    let fresh_backend = NativeBackend::load(&store_path).unwrap();
+    let tree_id = tree_b.id().into_merge().into_resolved().unwrap();
+    let tree = fresh_backend
+        .read_tree(RepoPath::root(), &tree_id)
+        .block_on()
+        .unwrap();
+    assert_eq!(
+        tree.names()
+            .map(|name| name.as_internal_str())
+            .collect_vec(),
+        ["a", "b"]
+    );
+
+    // All unreachable
+    repo.store().gc(base_index.as_index(), now()).unwrap();
+    assert_eq!(
+        backend.stats().unwrap(),
+        NativeBackendStats {
+            num_packs: 1,
+            num_packed_objects: 1,
+            num_loose_objects: 0,
+        }
+    );
+}
+
+#[test]
+fn test_native_backend_write_packed_object() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Native);
+    let repo = test_repo.repo;
+    let backend = get_native_backend(&repo);
+
+    let mut tx = repo.start_transaction();
+    let tree = create_tree(&repo, &[(repo_path("a"), "a\n")]);
+    tx.repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree.id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    let now = || SystemTime::now() + Duration::from_secs(1);
+    repo.store().gc(repo.index(), now()).unwrap();
+    assert_eq!(backend.stats().unwrap().num_loose_objects, 0);
+    let num_packed_objects = backend.stats().unwrap().num_packed_objects;
+
+    // Writing a packed object creates a loose object, so it won't be removed
+    // by concurrent gc before it becomes reachable again.
+    backend
+        .write_file(repo_path("a"), &mut &b"a\n"[..])
+        .block_on()
+        .unwrap();
+    assert_eq!(backend.stats().unwrap().num_loose_objects, 1);
+
+    // The duplicate is removed by gc
+    repo.store().gc(repo.index(), now()).unwrap();
+    assert_eq!(
+        backend.stats().unwrap(),
+        NativeBackendStats {
+            num_packs: 1,
+            num_packed_objects,
+            num_loose_objects: 0,
+        }
+    );
+}
+
+#[test]
+fn test_native_backend_read_after_concurrent_gc() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Native);
+    let store_path = test_repo.repo_path().join("store");
+    let repo = test_repo.repo;
+    let base_index = repo.readonly_index();
+
+    let mut tx = repo.start_transaction();
+    let tree_a = create_tree(&repo, &[(repo_path("a"), "a\n")]);
+    let tree_b = create_tree(&repo, &[(repo_path("b"), "b\n")]);
+    let commit_a = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree_a.id())
+        .write()
+        .unwrap();
+    let commit_b = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree_b.id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    let now = || SystemTime::now() + Duration::from_secs(1);
+
+    // Another process has loaded the pack indexes before gc
+    let other_backend = NativeBackend::load(&store_path).unwrap();
+    other_backend.read_commit(commit_a.id()).block_on().unwrap();
+
+    // Loose objects moved to a new pack are found by reloading the indexes
+    repo.store().gc(repo.index(), now()).unwrap();
+    let stored = other_backend.read_commit(commit_b.id()).block_on().unwrap();
+    assert_eq!(stored, **commit_b.store_commit());
+
+    // The pack loaded by the other process is replaced, but can still be read
+    let mut mut_index = base_index.start_modification();
+    mut_index.add_commit(&commit_a).unwrap();
+    repo.store().gc(mut_index.as_index(), now()).unwrap();
+    let stored = other_backend.read_commit(commit_a.id()).block_on().unwrap();
+    assert_eq!(stored, **commit_a.store_commit());
+}
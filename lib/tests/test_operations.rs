@@ -25,14 +25,19 @@ use jj_lib::config::ConfigSource;
 use jj_lib::evolution::walk_predecessors;
 use jj_lib::index::Index;
 use jj_lib::object_id::ObjectId as _;
+use jj_lib::op_store::OpStoreResult;
 use jj_lib::op_store::OperationId;
+use jj_lib::op_store::RefTarget;
 use jj_lib::op_walk;
 use jj_lib::op_walk::OpsetEvaluationError;
 use jj_lib::op_walk::OpsetResolutionError;
 use jj_lib::operation::Operation;
+use jj_lib::opset::OpsetCommitResolver;
+use jj_lib::opset::OpsetParseContext;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo;
 use jj_lib::settings::UserSettings;
+use jj_lib::view::View;
 use pollster::FutureExt as _;
 use test_case::test_case;
 use testutils::TestRepo;
@@ -299,6 +304,7 @@ fn test_reparent_range_linear() {
         slice::from_ref(repo_b.operation()),
         slice::from_ref(repo_d.operation()),
         repo_a.operation(),
+        None,
     )
     .unwrap();
     assert_eq!(stats.new_head_ids.len(), 1);
@@ -318,6 +324,7 @@ fn test_reparent_range_linear() {
         slice::from_ref(repo_d.operation()),
         slice::from_ref(repo_d.operation()),
         repo_a.operation(),
+        None,
     )
     .unwrap();
     assert_eq!(stats.new_head_ids, vec![repo_a.op_id().clone()]);
@@ -378,6 +385,7 @@ fn test_reparent_range_branchy() {
         slice::from_ref(repo_d.operation()),
         slice::from_ref(repo_g.operation()),
         repo_b.operation(),
+        None,
     )
     .unwrap();
     assert_eq!(stats.new_head_ids.len(), 1);
@@ -405,6 +413,7 @@ fn test_reparent_range_branchy() {
         slice::from_ref(repo_b.operation()),
         slice::from_ref(repo_g.operation()),
         repo_a.operation(),
+        None,
     )
     .unwrap();
     assert_eq!(stats.new_head_ids.len(), 1);
@@ -431,6 +440,7 @@ fn test_reparent_range_branchy() {
         &[op_e.clone(), op_f.clone()],
         slice::from_ref(repo_g.operation()),
         repo_d.operation(),
+        None,
     )
     .unwrap();
     assert_eq!(stats.new_head_ids.len(), 1);
@@ -453,6 +463,7 @@ fn test_reparent_range_branchy() {
         slice::from_ref(repo_c.operation()),
         slice::from_ref(&op_f),
         repo_d.operation(),
+        None,
     )
     .unwrap();
     assert_eq!(stats.new_head_ids.len(), 1);
@@ -464,6 +475,99 @@ fn test_reparent_range_branchy() {
     assert_eq!(new_op_f.parent_ids(), slice::from_ref(repo_d.op_id()));
 }
 
+#[test]
+fn test_abandon_ops() {
+    let test_repo = TestRepo::init();
+    let repo_0 = test_repo.repo;
+    let loader = repo_0.loader();
+    let op_store = repo_0.op_store();
+
+    let read_op = |id| loader.load_operation(id).unwrap();
+
+    fn op_parents<const N: usize>(op: &Operation) -> [Operation; N] {
+        let parents: Vec<_> = op.parents().try_collect().unwrap();
+        parents.try_into().unwrap()
+    }
+
+    // Set up branchy operation graph:
+    // E
+    // |\
+    // | D
+    // C |
+    // |/
+    // B
+    // A
+    // 0 (initial)
+    let random_tx = |repo: &Arc<ReadonlyRepo>| {
+        let mut tx = repo.start_transaction();
+        write_random_commit(tx.repo_mut());
+        tx
+    };
+    let repo_a = random_tx(&repo_0).commit("op A").unwrap();
+    let repo_b = random_tx(&repo_a).commit("op B").unwrap();
+    let tx_c = random_tx(&repo_b);
+    let tx_d = random_tx(&repo_b);
+    let repo_e = testutils::commit_transactions(vec![tx_c, tx_d]);
+    let [op_c, op_d] = op_parents(repo_e.operation());
+
+    // Abandon A|C (non-contiguous):
+    // E'
+    // |\
+    // | D'
+    // | |
+    // |/
+    // B'
+    // 0 (initial)
+    let stats = op_walk::abandon_ops(
+        op_store.as_ref(),
+        slice::from_ref(repo_e.operation()),
+        &[repo_a.operation().clone(), op_c.clone()],
+        None,
+    )
+    .unwrap();
+    assert_eq!(stats.new_head_ids.len(), 1);
+    assert_eq!(stats.rewritten_count, 3);
+    assert_eq!(stats.unreachable_count, 2);
+    let new_op_e = read_op(&stats.new_head_ids[0]);
+    assert_eq!(new_op_e.metadata(), repo_e.operation().metadata());
+    assert_eq!(new_op_e.view_id(), repo_e.operation().view_id());
+    let [new_op_b, new_op_d] = op_parents(&new_op_e);
+    assert_eq!(new_op_b.metadata(), repo_b.operation().metadata());
+    assert_eq!(new_op_b.parent_ids(), slice::from_ref(repo_0.op_id()));
+    assert_eq!(new_op_d.metadata(), op_d.metadata());
+    assert_eq!(new_op_d.parent_ids(), slice::from_ref(new_op_b.id()));
+
+    // Abandon C|D, the merge parents collapse into one:
+    // E'
+    // B
+    // A
+    // 0 (initial)
+    let stats = op_walk::abandon_ops(
+        op_store.as_ref(),
+        slice::from_ref(repo_e.operation()),
+        &[op_c.clone(), op_d.clone()],
+        None,
+    )
+    .unwrap();
+    assert_eq!(stats.new_head_ids.len(), 1);
+    assert_eq!(stats.rewritten_count, 1);
+    assert_eq!(stats.unreachable_count, 2);
+    let new_op_e = read_op(&stats.new_head_ids[0]);
+    assert_eq!(new_op_e.parent_ids(), slice::from_ref(repo_b.op_id()));
+
+    // Abandon nothing
+    let stats = op_walk::abandon_ops(
+        op_store.as_ref(),
+        slice::from_ref(repo_e.operation()),
+        &[],
+        None,
+    )
+    .unwrap();
+    assert_eq!(stats.new_head_ids, vec![repo_e.op_id().clone()]);
+    assert_eq!(stats.rewritten_count, 0);
+    assert_eq!(stats.unreachable_count, 0);
+}
+
 #[test_case(false; "legacy commit.predecessors")]
 #[test_case(true; "op.commit_predecessors")]
 fn test_reparent_discarding_predecessors(op_stores_commit_predecessors: bool) {
@@ -565,6 +669,7 @@ fn test_reparent_discarding_predecessors(op_stores_commit_predecessors: bool) {
         slice::from_ref(repo_1.operation()),
         slice::from_ref(repo_4.operation()),
         repo_0.operation(),
+        None,
     )
     .unwrap();
     assert_eq!(stats.new_head_ids.len(), 1);
@@ -591,6 +696,7 @@ fn test_reparent_discarding_predecessors(op_stores_commit_predecessors: bool) {
         slice::from_ref(repo_2.operation()),
         slice::from_ref(repo_4.operation()),
         repo_0.operation(),
+        None,
     )
     .unwrap();
     assert_eq!(stats.new_head_ids.len(), 1);
@@ -625,6 +731,7 @@ fn test_reparent_discarding_predecessors(op_stores_commit_predecessors: bool) {
         slice::from_ref(repo_3.operation()),
         slice::from_ref(repo_4.operation()),
         repo_0.operation(),
+        None,
     )
     .unwrap();
     assert_eq!(stats.new_head_ids.len(), 1);
@@ -847,6 +954,178 @@ fn test_resolve_op_parents_children() {
     );
 }
 
+#[test]
+fn test_resolve_ops_expression() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let repo_0 = test_repo.repo;
+    let op_store = repo_0.op_store();
+
+    let mut tx = repo_0.start_transaction();
+    let commit = write_random_commit(tx.repo_mut());
+    let repo_1 = tx.commit("create commit").unwrap();
+    let mut tx = repo_1.start_transaction();
+    tx.repo_mut()
+        .set_wc_commit("ws".into(), commit.id().clone())
+        .unwrap();
+    let repo_2 = tx.commit("check out commit").unwrap();
+    let mut tx = repo_2.start_transaction();
+    tx.repo_mut().record_abandoned_commit(&commit);
+    tx.repo_mut().rebase_descendants().unwrap();
+    let repo_3 = tx.commit("abandon commit").unwrap();
+    let ops = [&repo_0, &repo_1, &repo_2, &repo_3].map(|repo| repo.operation().clone());
+
+    let resolve = |op_str: &str| -> Result<Vec<Operation>, OpsetEvaluationError> {
+        let context = OpsetParseContext::new();
+        let mut resolved = op_walk::resolve_ops_at(
+            op_store,
+            slice::from_ref(repo_3.operation()),
+            op_str,
+            &context,
+        )?;
+        resolved.sort_by_key(|op| ops.iter().position(|other| other == op));
+        Ok(resolved)
+    };
+
+    assert_eq!(resolve("@").unwrap(), [ops[3].clone()]);
+    assert_eq!(resolve("@--..").unwrap(), [ops[2].clone(), ops[3].clone()]);
+    assert_eq!(
+        resolve(r#"description("commit") ~ @"#).unwrap(),
+        [ops[1].clone(), ops[2].clone()]
+    );
+    assert_eq!(
+        resolve(r#"description(glob:"*out*") | description(exact:"create commit")"#).unwrap(),
+        [ops[1].clone(), ops[2].clone()]
+    );
+    assert_eq!(resolve("user(test-username)").unwrap().len(), 3);
+    assert_eq!(resolve("user(nobody)").unwrap(), []);
+    assert_eq!(resolve(r#"time(after:"2000-01-01")"#).unwrap().len(), 3);
+    assert_eq!(resolve(r#"time(before:"2000-01-01")"#).unwrap(), []);
+    // The removal of the working-copy commit also counts
+    assert_eq!(
+        resolve("workspace(ws)").unwrap(),
+        [ops[2].clone(), ops[3].clone()]
+    );
+    assert_eq!(resolve("workspace(exact:w)").unwrap(), []);
+
+    // touching() isn't available without commit resolver
+    assert_matches!(
+        resolve(&format!("touching({})", commit.id())),
+        Err(OpsetEvaluationError::OpsetParse(_))
+    );
+    assert_matches!(
+        resolve("description(foo"),
+        Err(OpsetEvaluationError::OpsetParse(_))
+    );
+    assert_matches!(
+        resolve("unknown()"),
+        Err(OpsetEvaluationError::OpsetParse(_))
+    );
+
+    // Filters can also be used where a single operation is expected
+    assert_eq!(
+        op_walk::resolve_op_with_repo(&repo_3, r#"description("check out")"#).unwrap(),
+        ops[2]
+    );
+    assert_matches!(
+        op_walk::resolve_op_with_repo(&repo_3, r#"description("commit")"#),
+        Err(OpsetEvaluationError::OpsetResolution(
+            OpsetResolutionError::MultipleOperations { .. }
+        ))
+    );
+}
+
+#[test]
+fn test_resolve_ops_touching() {
+    struct Resolver<'a>(&'a ReadonlyRepo);
+
+    impl OpsetCommitResolver for Resolver<'_> {
+        fn resolve_commits(
+            &self,
+            text: &str,
+        ) -> Result<Vec<CommitId>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(vec![
+                CommitId::try_from_hex(text).ok_or("invalid commit id")?,
+            ])
+        }
+
+        fn repo(&self) -> &dyn Repo {
+            self.0
+        }
+    }
+
+    let test_repo = TestRepo::init();
+    let repo_0 = test_repo.repo;
+
+    // Create A and B on top
+    let mut tx = repo_0.start_transaction();
+    let commit_a = write_random_commit(tx.repo_mut());
+    let commit_b = write_random_commit_with_parents(tx.repo_mut(), &[&commit_a]);
+    let repo_1 = tx.commit("create commits").unwrap();
+    // Create unrelated C
+    let mut tx = repo_1.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let repo_2 = tx.commit("create unrelated commit").unwrap();
+    // Abandon A, which isn't a head
+    let mut tx = repo_2.start_transaction();
+    tx.repo_mut().record_abandoned_commit(&commit_a);
+    tx.repo_mut().rebase_descendants().unwrap();
+    let repo_3 = tx.commit("abandon commit").unwrap();
+
+    let resolver = Resolver(&repo_3);
+    let context = OpsetParseContext {
+        commit_resolver: Some(&resolver),
+        ..OpsetParseContext::new()
+    };
+    let resolve = |op_str: &str| {
+        let ops = op_walk::resolve_ops_at(
+            repo_3.op_store(),
+            slice::from_ref(repo_3.operation()),
+            op_str,
+            &context,
+        )
+        .unwrap();
+        ops.iter()
+            .map(|op| op.metadata().description.clone())
+            .sorted()
+            .collect_vec()
+    };
+    assert_eq!(
+        resolve(&format!("touching({})", commit_a.id())),
+        ["abandon commit", "create commits"]
+    );
+    // B was rebased
+    assert_eq!(
+        resolve(&format!("touching({})", commit_b.id())),
+        ["abandon commit", "create commits"]
+    );
+    assert_eq!(
+        resolve(&format!("touching({}) & @", commit_a.id())),
+        ["abandon commit"]
+    );
+    assert_eq!(
+        op_walk::resolve_op_at_with_context(
+            repo_3.op_store(),
+            slice::from_ref(repo_3.operation()),
+            &format!(r#"touching("{}") ~ @"#, commit_a.id()),
+            &context,
+        )
+        .unwrap(),
+        *repo_1.operation()
+    );
+
+    // Revset expressions have to be quoted
+    assert_matches!(
+        op_walk::resolve_ops_at(
+            repo_3.op_store(),
+            slice::from_ref(repo_3.operation()),
+            &format!("touching({}-)", commit_b.id()),
+            &context,
+        ),
+        Err(OpsetEvaluationError::OpsetParse(_))
+    );
+}
+
 #[test]
 fn test_walk_ancestors() {
     let test_repo = TestRepo::init();
@@ -977,6 +1256,74 @@ fn test_walk_ancestors() {
     );
 }
 
+#[test]
+fn test_walk_value_changes() {
+    let test_repo = TestRepo::init();
+    let repo_0 = test_repo.repo;
+
+    let bookmark_target = |view: &View| -> OpStoreResult<RefTarget> {
+        Ok(view.get_local_bookmark("main".as_ref()).clone())
+    };
+    let collect_changes = |head_op: &Operation| {
+        op_walk::walk_value_changes(slice::from_ref(head_op), bookmark_target)
+            .map_ok(|change| {
+                (
+                    change.operation.metadata().description.clone(),
+                    change.old_value,
+                    change.new_value,
+                )
+            })
+            .try_collect::<_, Vec<_>, _>()
+            .unwrap()
+    };
+
+    // Set up operation graph:
+    // E (merge)
+    // |\
+    // | D (moves other bookmark)
+    // C | (moves main to commit 3)
+    // |/
+    // B (moves main to commit 2)
+    // A (creates main at commit 1)
+    // 0 (initial)
+    let mut tx = repo_0.start_transaction();
+    let commit1 = write_random_commit(tx.repo_mut());
+    let commit2 = write_random_commit(tx.repo_mut());
+    let commit3 = write_random_commit(tx.repo_mut());
+    let target1 = RefTarget::normal(commit1.id().clone());
+    let target2 = RefTarget::normal(commit2.id().clone());
+    let target3 = RefTarget::normal(commit3.id().clone());
+    tx.repo_mut()
+        .set_local_bookmark_target("main".as_ref(), target1.clone());
+    let repo_a = tx.commit("op A").unwrap();
+    let mut tx = repo_a.start_transaction();
+    tx.repo_mut()
+        .set_local_bookmark_target("main".as_ref(), target2.clone());
+    let repo_b = tx.commit("op B").unwrap();
+    let mut tx_c = repo_b.start_transaction();
+    tx_c.repo_mut()
+        .set_local_bookmark_target("main".as_ref(), target3.clone());
+    let mut tx_d = repo_b.start_transaction();
+    tx_d.repo_mut()
+        .set_local_bookmark_target("other".as_ref(), target1.clone());
+    let repo_e = testutils::commit_transactions(vec![tx_c, tx_d]);
+
+    // The merge operation isn't reported since the value was changed by one
+    // of the parents.
+    assert_eq!(
+        collect_changes(repo_e.operation()),
+        [
+            ("test".to_owned(), target2.clone(), target3.clone()),
+            ("op B".to_owned(), target1.clone(), target2.clone()),
+            ("op A".to_owned(), RefTarget::absent(), target1.clone()),
+        ]
+    );
+    assert_eq!(
+        collect_changes(repo_a.operation()),
+        [("op A".to_owned(), RefTarget::absent(), target1.clone())]
+    );
+}
+
 #[test]
 fn test_gc() {
     let settings = stable_op_id_settings();
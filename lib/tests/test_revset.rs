@@ -32,6 +32,7 @@ use jj_lib::git;
 use jj_lib::graph::GraphEdge;
 use jj_lib::graph::reverse_graph;
 use jj_lib::id_prefix::IdPrefixContext;
+use jj_lib::merged_tree::MergedTree;
 use jj_lib::object_id::ObjectId as _;
 use jj_lib::op_store::RefTarget;
 use jj_lib::op_store::RemoteRef;
@@ -99,6 +100,7 @@ fn resolve_symbol(repo: &dyn Repo, symbol: &str) -> Result<Vec<CommitId>, Revset
         default_ignored_remote: Some(git::REMOTE_NAME_FOR_LOCAL_GIT_REPO),
         extensions: &RevsetExtensions::default(),
         workspace: None,
+        trust_policy: None,
     };
     let expression = parse(&mut RevsetDiagnostics::new(), symbol, &context).unwrap();
     assert_matches!(*expression, RevsetExpression::CommitRef(_));
@@ -133,6 +135,15 @@ fn build_changed_path_index(repo: &ReadonlyRepo) -> Arc<ReadonlyRepo> {
     repo.reload_at(repo.operation()).unwrap()
 }
 
+fn build_text_index(repo: &ReadonlyRepo) -> Arc<ReadonlyRepo> {
+    let default_index_store: &DefaultIndexStore = repo.index_store().downcast_ref().unwrap();
+    default_index_store
+        .build_text_index_at_operation(repo.op_id(), repo.store())
+        .block_on()
+        .unwrap();
+    repo.reload_at(repo.operation()).unwrap()
+}
+
 #[test]
 fn test_resolve_symbol_empty_string() {
     let test_repo = TestRepo::init();
@@ -232,6 +243,7 @@ fn test_resolve_symbol_commit_id() {
         default_ignored_remote: Some(git::REMOTE_NAME_FOR_LOCAL_GIT_REPO),
         extensions: &RevsetExtensions::default(),
         workspace: None,
+        trust_policy: None,
     };
     assert_matches!(
         parse(&mut RevsetDiagnostics::new(), "present(01)", &context).unwrap()
@@ -1022,6 +1034,7 @@ fn try_resolve_expression(
         default_ignored_remote: Some(git::REMOTE_NAME_FOR_LOCAL_GIT_REPO),
         extensions: &RevsetExtensions::default(),
         workspace: None,
+        trust_policy: None,
     };
     let expression = parse(&mut RevsetDiagnostics::new(), revset_str, &context).unwrap();
     let symbol_resolver = default_symbol_resolver(repo);
@@ -1072,6 +1085,7 @@ fn resolve_commit_ids_in_workspace(
         default_ignored_remote: Some(git::REMOTE_NAME_FOR_LOCAL_GIT_REPO),
         extensions: &RevsetExtensions::default(),
         workspace: Some(workspace_ctx),
+        trust_policy: None,
     };
     let expression = parse(&mut RevsetDiagnostics::new(), revset_str, &context).unwrap();
     let symbol_resolver = default_symbol_resolver(repo);
@@ -3208,6 +3222,80 @@ fn test_evaluate_expression_bisect_nonlinear() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_landed() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+
+    let file_path = repo_path("file");
+    let other_path = repo_path("other");
+    let (upstream1, upstream2, local1, local2, local3) = {
+        let mut write_commit = |parent: &Commit, tree: MergedTree| {
+            mut_repo
+                .new_commit(vec![parent.id().clone()], tree.id())
+                .write()
+                .unwrap()
+        };
+        let root_commit = repo.store().root_commit();
+        let base = write_commit(&root_commit, create_tree(repo, &[(file_path, "1\n2\n3\n")]));
+        // Upstream picks the local change with different context and whitespace
+        let upstream1 = write_commit(&base, create_tree(repo, &[(file_path, "one\n2\n3\n")]));
+        let upstream2 = write_commit(
+            &upstream1,
+            create_tree(repo, &[(file_path, "one\n two \n3\n")]),
+        );
+        let local1 = write_commit(&base, create_tree(repo, &[(file_path, "1\ntwo\n3\n")]));
+        let local2 = write_commit(
+            &local1,
+            create_tree(repo, &[(file_path, "1\ntwo\n3\n"), (other_path, "2\n")]),
+        );
+        // Empty commits have no patch id
+        let local3 = write_commit(&local2, local2.tree().unwrap());
+        (upstream1, upstream2, local1, local2, local3)
+    };
+
+    let resolve_ids = |input: &str| resolve_commit_ids(mut_repo, input);
+
+    assert_eq!(
+        resolve_ids(&format!("landed({}, all())", upstream2.id())),
+        vec![local1.id().clone()]
+    );
+    assert_eq!(
+        resolve_ids(&format!("landed({}, {})", upstream2.id(), local1.id())),
+        vec![local1.id().clone()]
+    );
+    assert_eq!(
+        resolve_ids(&format!(
+            "landed({}, {}|{})",
+            upstream2.id(),
+            local2.id(),
+            local3.id()
+        )),
+        vec![]
+    );
+
+    // Not landed before the upstream commit
+    assert_eq!(
+        resolve_ids(&format!("landed({}, all())", upstream1.id())),
+        vec![]
+    );
+
+    // Ancestors of upstream aren't landed
+    assert_eq!(
+        resolve_ids(&format!("landed({}, {})", upstream2.id(), upstream2.id())),
+        vec![]
+    );
+
+    // Works in either direction
+    assert_eq!(
+        resolve_ids(&format!("landed({}, all())", local2.id())),
+        vec![upstream2.id().clone()]
+    );
+}
+
 #[test]
 fn test_evaluate_expression_merges() {
     let test_repo = TestRepo::init();
@@ -4366,6 +4454,164 @@ fn test_evaluate_expression_file(indexed: bool) {
     );
 }
 
+#[test_case(false; "without changed-path index")]
+#[test_case(true; "with changed-path index")]
+fn test_evaluate_expression_file_indexed_commits(indexed: bool) {
+    let test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let tree1 = create_tree(repo, &[(repo_path("dir/sub/file"), "1")]);
+    let tree2 = create_tree(
+        repo,
+        &[
+            (repo_path("dir/sub/file"), "1"),
+            (repo_path("dir/other"), "2"),
+        ],
+    );
+    let tree3 = create_tree(
+        repo,
+        &[
+            (repo_path("dir/sub/file"), "1"),
+            (repo_path("dir/other"), "2"),
+            (repo_path("top"), "3"),
+        ],
+    );
+    let commit1 = mut_repo
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree1.id())
+        .write()
+        .unwrap();
+    let commit2 = mut_repo
+        .new_commit(vec![commit1.id().clone()], tree2.id())
+        .write()
+        .unwrap();
+    let commit3 = mut_repo
+        .new_commit(vec![commit2.id().clone()], tree3.id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    // Commits are indexed in readonly segment
+    let repo = if indexed {
+        build_changed_path_index(&repo)
+    } else {
+        repo
+    };
+    let resolve = |revset_str: &str| {
+        resolve_commit_ids_in_workspace(repo.as_ref(), revset_str, &test_workspace.workspace, None)
+    };
+
+    assert_eq!(
+        resolve("files(dir)"),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(resolve("files(dir/sub)"), vec![commit1.id().clone()]);
+    assert_eq!(
+        resolve("files(file:dir/sub/file)"),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(resolve("files(dir/sub/file/nested)"), vec![]);
+    assert_eq!(resolve("files(glob:'dir/*')"), vec![commit2.id().clone()]);
+    assert_eq!(
+        resolve("files(top | dir/other)"),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
+    assert_eq!(resolve("files(dir ~ dir/sub)"), vec![commit2.id().clone()]);
+    assert_eq!(resolve("files(~dir)"), vec![commit3.id().clone()]);
+    assert_eq!(
+        resolve("diff_contains('3', top | dir)"),
+        vec![commit3.id().clone()]
+    );
+}
+
+#[test_case(false; "without text index")]
+#[test_case(true; "with text index")]
+fn test_evaluate_expression_text_indexed_commits(indexed: bool) {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let signature = |name: &str, email: &str| Signature {
+        name: name.to_owned(),
+        email: email.to_owned(),
+        timestamp: Timestamp {
+            timestamp: MillisSinceEpoch(0),
+            tz_offset: 0,
+        },
+    };
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit1 = create_random_commit(mut_repo)
+        .set_description("Fix the frobnicator\n")
+        .set_author(signature("Alice", "alice@example.com"))
+        .write()
+        .unwrap();
+    let commit2 = create_random_commit(mut_repo)
+        .set_parents(vec![commit1.id().clone()])
+        .set_description("Add tests\n\nfrobnicate more\n")
+        .set_author(signature("Bob", "bob@example.org"))
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    // Commits are indexed in readonly segment
+    let repo = if indexed {
+        build_text_index(&repo)
+    } else {
+        repo
+    };
+    // Commits are indexed in mutable segment
+    let mut tx = repo.start_transaction();
+    let mut_repo = tx.repo_mut();
+    let commit3 = create_random_commit(mut_repo)
+        .set_parents(vec![commit2.id().clone()])
+        .set_description("Frobnicate\n")
+        .set_author(signature("Alice", "alice@example.org"))
+        .write()
+        .unwrap();
+    let mut_repo = &*mut_repo;
+
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(frobnicat)"),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(substring-i:frobnicat)"),
+        vec![
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone()
+        ]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(exact:'Frobnicate\n')"),
+        vec![commit3.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "subject(frobnicat)"),
+        vec![commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(tests | frobnicator)"),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(glob:'*frob*') & description(tests)"),
+        vec![commit2.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "description(~frobnicat)"),
+        vec![commit3.id().clone(), repo.store().root_commit_id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author(alice)"),
+        vec![commit3.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author_email(example.org)"),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
+    assert_eq!(resolve_commit_ids(mut_repo, "author_name(example)"), vec![]);
+}
+
 #[test_case(false; "without changed-path index")]
 #[test_case(true; "with changed-path index")]
 fn test_evaluate_expression_diff_contains(indexed: bool) {
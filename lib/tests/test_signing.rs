@@ -1,16 +1,32 @@
+use std::slice;
+
+use assert_matches::assert_matches;
+use jj_lib::backend::CommitId;
 use jj_lib::backend::MillisSinceEpoch;
 use jj_lib::backend::Signature;
+use jj_lib::backend::SigningFn;
 use jj_lib::backend::Timestamp;
 use jj_lib::config::ConfigLayer;
 use jj_lib::config::ConfigSource;
+use jj_lib::op_walk;
+use jj_lib::operation::Operation;
+use jj_lib::repo::MutableRepo;
 use jj_lib::repo::Repo as _;
+use jj_lib::revset::ResolvedRevsetExpression;
+use jj_lib::revset::RevsetFilterPredicate;
 use jj_lib::settings::UserSettings;
 use jj_lib::signing::SigStatus;
 use jj_lib::signing::SignBehavior;
 use jj_lib::signing::Signer;
 use jj_lib::signing::Verification;
+use jj_lib::str_util::StringExpression;
 use jj_lib::test_signing_backend::TestSigningBackend;
+use jj_lib::transaction::Transaction;
+use jj_lib::transaction::TransactionCommitError;
+use jj_lib::transaction::operation_signing_fn;
+use pollster::FutureExt as _;
 use test_case::test_case;
+use testutils::TestRepo;
 use testutils::TestRepoBackend;
 use testutils::TestWorkspace;
 use testutils::create_random_commit;
@@ -208,3 +224,142 @@ fn drop_behavior(backend: TestRepoBackend) {
     let rewritten_commit = repo.store().get_commit(rewritten.id()).unwrap();
     assert_eq!(rewritten_commit.verification().unwrap(), None);
 }
+
+#[test]
+fn sign_operations() {
+    let mut config = testutils::base_user_config();
+    config.add_layer(
+        ConfigLayer::parse(
+            ConfigSource::User,
+            r#"
+            signing.key = "impeccable"
+            signing.sign-operations = true
+            "#,
+        )
+        .unwrap(),
+    );
+    let settings = UserSettings::from_config(config).unwrap();
+
+    let signer = Signer::new(Some(Box::new(TestSigningBackend)), vec![]);
+    let test_workspace =
+        TestWorkspace::init_with_backend_and_signer(TestRepoBackend::Simple, signer, &settings);
+    let repo = &test_workspace.repo;
+    let signer = repo.store().signer();
+
+    let mut tx = repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+    let op = repo.operation();
+    assert!(op.is_signed());
+    assert_eq!(op.verification(signer).unwrap(), good_verification());
+
+    // Tampering with the operation invalidates the signature
+    let mut data = op.store_operation().clone();
+    data.metadata.description = "tampered".to_owned();
+    let op_store = repo.op_store();
+    let tampered_id = op_store.write_operation(&data).block_on().unwrap();
+    let tampered_op = Operation::new(op_store.clone(), tampered_id, data);
+    assert_eq!(
+        tampered_op.verification(signer).unwrap().unwrap().status,
+        SigStatus::Bad
+    );
+
+    // Rewritten operations are signed again, or left unsigned
+    let mut tx = repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let child_repo = tx.commit("child").unwrap();
+    let parent_op = op.parents().next().unwrap().unwrap();
+    let reparent = |sign_with: Option<&mut SigningFn>| {
+        let stats = op_walk::reparent_range(
+            op_store.as_ref(),
+            slice::from_ref(op),
+            slice::from_ref(child_repo.operation()),
+            &parent_op,
+            sign_with,
+        )
+        .unwrap();
+        repo.loader()
+            .load_operation(&stats.new_head_ids[0])
+            .unwrap()
+    };
+    let mut sign = operation_signing_fn(signer, &settings).unwrap().unwrap();
+    let resigned_op = reparent(Some(&mut sign));
+    assert_eq!(
+        resigned_op.verification(signer).unwrap(),
+        good_verification()
+    );
+    let unsigned_op = reparent(None);
+    assert_eq!(unsigned_op.verification(signer).unwrap(), None);
+    let stats = op_walk::abandon_ops(
+        op_store.as_ref(),
+        slice::from_ref(child_repo.operation()),
+        slice::from_ref(op),
+        Some(&mut sign),
+    )
+    .unwrap();
+    let resigned_op = repo
+        .loader()
+        .load_operation(&stats.new_head_ids[0])
+        .unwrap();
+    assert_eq!(
+        resigned_op.verification(signer).unwrap(),
+        good_verification()
+    );
+
+    // Operations can't be signed without a signing backend
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let mut_repo = MutableRepo::new(repo.clone(), repo.readonly_index(), repo.view());
+    let mut tx = Transaction::new(mut_repo, &settings);
+    write_random_commit(tx.repo_mut());
+    assert_matches!(tx.commit("test"), Err(TransactionCommitError::Sign(_)));
+
+    // Operations aren't signed by default
+    let test_repo = TestRepo::init();
+    let mut tx = test_repo.repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+    assert!(!repo.operation().is_signed());
+    assert_eq!(repo.operation().verification(signer).unwrap(), None);
+}
+
+#[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Git ; "git backend")]
+fn revset_signed_by(backend: TestRepoBackend) {
+    let settings = user_settings(SignBehavior::Own);
+
+    let signer = Signer::new(Some(Box::new(TestSigningBackend)), vec![]);
+    let test_workspace = TestWorkspace::init_with_backend_and_signer(backend, signer, &settings);
+
+    let repo = &test_workspace.repo;
+    let mut tx = repo.start_transaction();
+    let commit1 = create_random_commit(tx.repo_mut())
+        .set_sign_behavior(SignBehavior::Own)
+        .write()
+        .unwrap();
+    let commit2 = create_random_commit(tx.repo_mut())
+        .set_sign_behavior(SignBehavior::Own)
+        .set_author(someone_else())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+
+    let candidates =
+        ResolvedRevsetExpression::commits(vec![commit1.id().clone(), commit2.id().clone()]);
+    let signed_by = |pattern: StringExpression| -> Vec<CommitId> {
+        ResolvedRevsetExpression::filter(RevsetFilterPredicate::SignedBy(pattern))
+            .intersection(&candidates)
+            .evaluate(repo.as_ref())
+            .unwrap()
+            .iter()
+            .map(Result::unwrap)
+            .collect()
+    };
+    assert_eq!(
+        signed_by(StringExpression::exact("impeccable")),
+        vec![commit1.id().clone()]
+    );
+    // The signer identity isn't verified by the key, so it doesn't match
+    assert_eq!(signed_by(StringExpression::exact("test-display")), vec![]);
+    assert_eq!(signed_by(StringExpression::exact("other")), vec![]);
+}
@@ -47,6 +47,7 @@ use jj_lib::git_backend::GitBackend;
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::matchers::EverythingMatcher;
 use jj_lib::merged_tree::MergedTree;
+use jj_lib::native_backend::NativeBackend;
 use jj_lib::object_id::ObjectId as _;
 use jj_lib::repo::MutableRepo;
 use jj_lib::repo::ReadonlyRepo;
@@ -134,6 +135,7 @@ pub fn empty_snapshot_options() -> SnapshotOptions<'static> {
         progress: None,
         start_tracking_matcher: &EverythingMatcher,
         max_new_file_size: u64::MAX,
+        max_new_file_size_overrides: vec![],
     }
 }
 
@@ -211,6 +213,7 @@ pub struct TestRepo {
 pub enum TestRepoBackend {
     Git,
     Simple,
+    Native,
     Test,
 }
 
@@ -224,6 +227,7 @@ impl TestRepoBackend {
         match self {
             Self::Git => Ok(Box::new(GitBackend::init_internal(settings, store_path)?)),
             Self::Simple => Ok(Box::new(SimpleBackend::init(store_path))),
+            Self::Native => Ok(Box::new(NativeBackend::init(store_path)?)),
             Self::Test => Ok(Box::new(env.test_backend_factory.init(store_path))),
         }
     }